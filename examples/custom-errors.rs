@@ -42,6 +42,6 @@ fn main() {
     let err = parse(input).unwrap_err();
     eprintln!("{}", err.message);
     // Output:
-    //  line 4, column 14: Expected valid value.
+    //  line 4, column 14: Invalid number.
     //    help: Floating point numbers must be base 10, and have numbers after the decimal point.
 }
@@ -0,0 +1,78 @@
+/*
+ * C API for kdl-rs, available with its `capi` feature. See src/capi.rs for
+ * the full documentation of each function.
+ *
+ * Documents returned by kdl_parse() are owned by the caller and freed with
+ * kdl_document_free(). Nodes, entries, child documents, and kdl_str values
+ * borrow from the document they came from, and stay valid until it's freed.
+ * Strings are UTF-8 and aren't NUL-terminated, except for error messages and
+ * kdl_document_to_string()'s output, which are freed with kdl_string_free().
+ */
+
+#ifndef KDL_H
+#define KDL_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct kdl_document kdl_document;
+typedef struct kdl_node kdl_node;
+typedef struct kdl_entry kdl_entry;
+
+/* A borrowed UTF-8 string. `ptr` is NULL when there's no string at all. */
+typedef struct kdl_str {
+    const char *ptr;
+    size_t len;
+} kdl_str;
+
+/* Versions for kdl_parse(). */
+#define KDL_VERSION_AUTO 0
+#define KDL_VERSION_1 1
+#define KDL_VERSION_2 2
+
+typedef enum kdl_value_kind {
+    KDL_VALUE_NULL = 0,
+    KDL_VALUE_BOOL = 1,
+    KDL_VALUE_INTEGER = 2,
+    KDL_VALUE_FLOAT = 3,
+    KDL_VALUE_STRING = 4,
+} kdl_value_kind;
+
+/* Parsing and writing documents. */
+kdl_document *kdl_parse(const char *text, size_t len, int version, char **error);
+void kdl_document_free(kdl_document *doc);
+void kdl_string_free(char *s);
+char *kdl_document_to_string(const kdl_document *doc, size_t *len);
+void kdl_document_fmt(kdl_document *doc);
+
+/* Documents. */
+size_t kdl_document_node_count(const kdl_document *doc);
+const kdl_node *kdl_document_node(const kdl_document *doc, size_t index);
+const kdl_node *kdl_document_get(const kdl_document *doc, const char *name, size_t len);
+
+/* Nodes. */
+kdl_str kdl_node_name(const kdl_node *node);
+kdl_str kdl_node_type(const kdl_node *node);
+size_t kdl_node_entry_count(const kdl_node *node);
+const kdl_entry *kdl_node_entry(const kdl_node *node, size_t index);
+const kdl_document *kdl_node_children(const kdl_node *node);
+
+/* Entries. */
+kdl_str kdl_entry_name(const kdl_entry *entry);
+kdl_str kdl_entry_type(const kdl_entry *entry);
+kdl_value_kind kdl_entry_kind(const kdl_entry *entry);
+bool kdl_entry_as_bool(const kdl_entry *entry, bool *out);
+bool kdl_entry_as_i64(const kdl_entry *entry, int64_t *out);
+bool kdl_entry_as_f64(const kdl_entry *entry, double *out);
+kdl_str kdl_entry_as_string(const kdl_entry *entry);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* KDL_H */
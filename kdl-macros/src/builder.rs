@@ -0,0 +1,192 @@
+//! `KdlBuilder`: typed builders for writing nodes.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::derive::{generic_arg, Body, Container, Field, Kind};
+
+pub(crate) fn builder(input: TokenStream) -> TokenStream {
+    match syn::parse2(input)
+        .and_then(Container::from_input)
+        .and_then(|container| expand(&container))
+    {
+        Ok(tokens) => tokens,
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+fn expand(container: &Container) -> syn::Result<TokenStream> {
+    let input = &container.input;
+    let ident = &input.ident;
+    let vis = &input.vis;
+    if let Some(param) = input.generics.params.first() {
+        return Err(syn::Error::new_spanned(
+            param,
+            "builders can't be derived for generic structs",
+        ));
+    }
+    let fields = match &container.body {
+        Body::Struct(fields) => fields,
+        Body::Enum(_) => {
+            return Err(syn::Error::new(
+                ident.span(),
+                "builders can only be derived for structs",
+            ))
+        }
+    };
+
+    let builder = format_ident!("{}Builder", ident);
+    let name = &container.name;
+    let slots: Vec<_> = (0..fields.len())
+        .map(|i| format_ident!("field_{}", i))
+        .collect();
+    let slot_types = fields.iter().map(|field| match field.kind {
+        Kind::Argument | Kind::Property => quote!(::std::option::Option<::kdl::KdlValue>),
+        Kind::Child => quote!(::std::option::Option<::kdl::KdlNode>),
+        Kind::Children => quote!(::std::vec::Vec<::kdl::KdlNode>),
+    });
+    let setters = fields
+        .iter()
+        .zip(&slots)
+        .map(|(field, slot)| setter(vis, field, slot))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let slots_of = |kinds: &[Kind]| -> Vec<_> {
+        fields
+            .iter()
+            .zip(&slots)
+            .filter(|(field, _)| kinds.contains(&field.kind))
+            .collect()
+    };
+    let args = slots_of(&[Kind::Argument])
+        .into_iter()
+        .map(|(_, slot)| slot);
+    let props = slots_of(&[Kind::Property])
+        .into_iter()
+        .map(|(field, slot)| {
+            let name = &field.name;
+            quote! {
+                if let ::std::option::Option::Some(value) = self.#slot {
+                    node.push(::kdl::KdlEntry::new_prop(#name, value));
+                }
+            }
+        });
+    let children = slots_of(&[Kind::Child, Kind::Children])
+        .into_iter()
+        .map(|(_, slot)| quote!(children.extend(self.#slot);));
+
+    let doc = format!(
+        "Builds a `{}` node with a method for each of [`{}`]'s fields. \
+         Created by [`{}::builder`].",
+        name, ident, ident
+    );
+    let new_doc = format!(
+        "Starts building a `{}` node, without having to create a `{}`.",
+        name, ident
+    );
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Default)]
+        #vis struct #builder {
+            #(#slots: #slot_types,)*
+        }
+
+        impl #ident {
+            #[doc = #new_doc]
+            #vis fn builder() -> #builder {
+                ::std::default::Default::default()
+            }
+        }
+
+        impl #builder {
+            #(#setters)*
+
+            /// Builds the node, leaving out anything that wasn't set.
+            /// Arguments that weren't set but come before ones that were are
+            /// written as `null`.
+            #vis fn build(self) -> ::kdl::KdlNode {
+                let mut node = ::kdl::KdlNode::new(#name);
+                let mut args: ::std::vec::Vec<::std::option::Option<::kdl::KdlValue>> =
+                    ::std::vec![#(self.#args),*];
+                while let ::std::option::Option::Some(::std::option::Option::None) = args.last() {
+                    args.pop();
+                }
+                for arg in args {
+                    node.push(::kdl::KdlEntry::new(arg.unwrap_or(::kdl::KdlValue::Null)));
+                }
+                #(#props)*
+                #[allow(unused_mut)]
+                let mut children: ::std::vec::Vec<::kdl::KdlNode> = ::std::vec::Vec::new();
+                #(#children)*
+                if !children.is_empty() {
+                    node.ensure_children().nodes_mut().extend(children);
+                }
+                node
+            }
+        }
+    })
+}
+
+fn setter(vis: &syn::Visibility, field: &Field, slot: &syn::Ident) -> syn::Result<TokenStream> {
+    let ident = &field.ident;
+    let ty = generic_arg(&field.ty, "Option").unwrap_or(&field.ty);
+    let name = field.name.as_deref().unwrap_or_default();
+    Ok(match field.kind {
+        Kind::Argument => {
+            let doc = format!("Sets the `{}` argument.", ident);
+            quote! {
+                #[doc = #doc]
+                #vis fn #ident(mut self, value: #ty) -> Self {
+                    self.#slot = ::std::option::Option::Some(::kdl::KdlValue::from(value));
+                    self
+                }
+            }
+        }
+        Kind::Property => {
+            let doc = format!("Sets the `{}` property.", name);
+            quote! {
+                #[doc = #doc]
+                #vis fn #ident(mut self, value: #ty) -> Self {
+                    self.#slot = ::std::option::Option::Some(::kdl::KdlValue::from(value));
+                    self
+                }
+            }
+        }
+        Kind::Child => {
+            let doc = format!("Sets the `{}` child.", name);
+            quote! {
+                #[doc = #doc]
+                #vis fn #ident(mut self, value: #ty) -> Self {
+                    let mut child = ::kdl::EncodeKdl::encode_kdl(&value);
+                    child.set_name(#name);
+                    self.#slot = ::std::option::Option::Some(child);
+                    self
+                }
+            }
+        }
+        Kind::Children => {
+            let item = generic_arg(&field.ty, "Vec").ok_or_else(|| {
+                syn::Error::new_spanned(&field.ty, "builders need `children` fields to be a `Vec`")
+            })?;
+            let (doc, rename) = match &field.name {
+                Some(name) => (
+                    format!("Adds `{}` children.", name),
+                    Some(quote!(child.set_name(#name);)),
+                ),
+                None => ("Adds children.".to_string(), None),
+            };
+            quote! {
+                #[doc = #doc]
+                #vis fn #ident(mut self, values: impl ::std::iter::IntoIterator<Item = #item>) -> Self {
+                    for value in values {
+                        #[allow(unused_mut)]
+                        let mut child = ::kdl::EncodeKdl::encode_kdl(&value);
+                        #rename
+                        self.#slot.push(child);
+                    }
+                    self
+                }
+            }
+        }
+    })
+}
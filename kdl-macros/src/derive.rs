@@ -0,0 +1,623 @@
+//! `DecodeKdl` and `EncodeKdl`: mapping structs and enums onto nodes.
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    ext::IdentExt, spanned::Spanned, Attribute, Data, DeriveInput, Fields, Ident, LitStr, Type,
+};
+
+pub(crate) fn decode(input: TokenStream) -> TokenStream {
+    match syn::parse2(input).and_then(Container::from_input) {
+        Ok(container) => container.decode(),
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+pub(crate) fn encode(input: TokenStream) -> TokenStream {
+    match syn::parse2(input).and_then(Container::from_input) {
+        Ok(container) => container.encode(),
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+pub(crate) struct Container {
+    pub(crate) input: DeriveInput,
+    pub(crate) name: String,
+    pub(crate) body: Body,
+}
+
+pub(crate) enum Body {
+    Struct(Vec<Field>),
+    Enum(Vec<Variant>),
+}
+
+pub(crate) struct Variant {
+    ident: Ident,
+    name: String,
+    shape: Shape,
+}
+
+enum Shape {
+    Unit,
+    /// A single unnamed field, decoded from the whole node.
+    Newtype,
+    Struct(Vec<Field>),
+}
+
+pub(crate) struct Field {
+    pub(crate) ident: Ident,
+    pub(crate) ty: Type,
+    pub(crate) kind: Kind,
+    /// The property or child name, if any.
+    pub(crate) name: Option<String>,
+    /// Whether the field's type is an `Option`.
+    pub(crate) optional: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Kind {
+    Argument,
+    Property,
+    Child,
+    Children,
+}
+
+/// The `#[kdl(...)]` attributes allowed on a type or enum variant.
+#[derive(Default)]
+struct ItemAttrs {
+    name: Option<String>,
+    rename_all: Option<Case>,
+}
+
+impl ItemAttrs {
+    fn parse(attrs: &[Attribute], allow_rename_all: bool) -> syn::Result<Self> {
+        let mut parsed = ItemAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("kdl")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    parsed.name = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else if allow_rename_all && meta.path.is_ident("rename_all") {
+                    let case = meta.value()?.parse::<LitStr>()?;
+                    parsed.rename_all = Some(Case::from_lit(&case)?);
+                    Ok(())
+                } else if allow_rename_all {
+                    Err(meta.error("unknown attribute; expected `name` or `rename_all`"))
+                } else {
+                    Err(meta.error("unknown attribute; expected `name`"))
+                }
+            })?;
+        }
+        Ok(parsed)
+    }
+}
+
+impl Container {
+    pub(crate) fn from_input(input: DeriveInput) -> syn::Result<Self> {
+        let attrs = ItemAttrs::parse(&input.attrs, true)?;
+        let case = attrs.rename_all.unwrap_or(Case::Kebab);
+        let name = attrs
+            .name
+            .unwrap_or_else(|| Case::Kebab.apply(&input.ident.to_string()));
+
+        let body = match &input.data {
+            Data::Struct(data) => match &data.fields {
+                Fields::Unnamed(fields) => {
+                    return Err(syn::Error::new(
+                        fields.span(),
+                        "tuple structs aren't supported; give the fields names",
+                    ))
+                }
+                fields => Body::Struct(Field::from_fields(fields, case)?),
+            },
+            Data::Enum(data) => Body::Enum(
+                data.variants
+                    .iter()
+                    .map(|variant| Variant::from_variant(variant, case))
+                    .collect::<syn::Result<_>>()?,
+            ),
+            Data::Union(_) => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "this can only be derived for structs and enums",
+                ))
+            }
+        };
+        Ok(Container { input, name, body })
+    }
+
+    fn decode(&self) -> TokenStream {
+        let ident = &self.input.ident;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        let body = match &self.body {
+            Body::Struct(fields) => decode_fields(quote!(Self), fields),
+            Body::Enum(variants) => {
+                let names = variants.iter().map(|variant| &variant.name);
+                let arms = variants.iter().map(|variant| {
+                    let name = &variant.name;
+                    let ident = &variant.ident;
+                    let body = match &variant.shape {
+                        Shape::Unit => decode_fields(quote!(Self::#ident), &[]),
+                        Shape::Newtype => quote! {
+                            ::std::result::Result::Ok(Self::#ident(
+                                ::kdl::DecodeKdl::decode_kdl(node)?,
+                            ))
+                        },
+                        Shape::Struct(fields) => decode_fields(quote!(Self::#ident), fields),
+                    };
+                    quote!(#name => { #body })
+                });
+                quote! {
+                    match node.name().value() {
+                        #(#arms)*
+                        _ => ::std::result::Result::Err(
+                            ::kdl::KdlDecoder::new(node).unknown_node(&[#(#names),*]),
+                        ),
+                    }
+                }
+            }
+        };
+        let value_impl = self.unit_variants().map(|variants| {
+            let (idents, names): (Vec<_>, Vec<_>) = variants.into_iter().unzip();
+            let quoted: Vec<_> = names.iter().map(|name| format!("{:?}", name)).collect();
+            let expected = match &quoted[..] {
+                [name] => name.clone(),
+                _ => format!("one of {}", quoted.join(", ")),
+            };
+            quote! {
+                impl #impl_generics ::kdl::FromKdlValue for #ident #ty_generics #where_clause {
+                    const EXPECTED: &'static str = #expected;
+
+                    fn from_kdl_value(
+                        value: &::kdl::KdlValue,
+                    ) -> ::std::option::Option<Self> {
+                        match value.as_string()? {
+                            #(#names => ::std::option::Option::Some(Self::#idents),)*
+                            _ => ::std::option::Option::None,
+                        }
+                    }
+                }
+            }
+        });
+        quote! {
+            impl #impl_generics ::kdl::DecodeKdl for #ident #ty_generics #where_clause {
+                fn decode_kdl(
+                    node: &::kdl::KdlNode,
+                ) -> ::std::result::Result<Self, ::kdl::KdlDecodeError> {
+                    #body
+                }
+            }
+            #value_impl
+        }
+    }
+
+    fn encode(&self) -> TokenStream {
+        let ident = &self.input.ident;
+        let (impl_generics, ty_generics, where_clause) = self.input.generics.split_for_impl();
+        let body = match &self.body {
+            Body::Struct(fields) => {
+                let accesses = fields.iter().map(|field| {
+                    let ident = &field.ident;
+                    quote!(&self.#ident)
+                });
+                encode_fields(&self.name, fields, accesses)
+            }
+            Body::Enum(variants) => {
+                let arms = variants.iter().map(|variant| {
+                    let name = &variant.name;
+                    let ident = &variant.ident;
+                    match &variant.shape {
+                        Shape::Unit => quote!(Self::#ident => ::kdl::KdlNode::new(#name),),
+                        Shape::Newtype => quote! {
+                            Self::#ident(value) => {
+                                let mut node = ::kdl::EncodeKdl::encode_kdl(value);
+                                node.set_name(#name);
+                                node
+                            }
+                        },
+                        Shape::Struct(fields) => {
+                            let idents = fields.iter().map(|field| &field.ident);
+                            let bindings: Vec<_> = (0..fields.len())
+                                .map(|i| format_ident!("field_{}", i))
+                                .collect();
+                            let body = encode_fields(
+                                name,
+                                fields,
+                                bindings.iter().map(|binding| quote!(#binding)),
+                            );
+                            quote!(Self::#ident { #(#idents: #bindings),* } => { #body })
+                        }
+                    }
+                });
+                quote! {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        };
+        let value_impl = self.unit_variants().map(|variants| {
+            let (idents, names): (Vec<_>, Vec<_>) = variants.into_iter().unzip();
+            quote! {
+                impl #impl_generics ::std::convert::From<#ident #ty_generics> for ::kdl::KdlValue
+                #where_clause
+                {
+                    fn from(value: #ident #ty_generics) -> Self {
+                        match value {
+                            #(#ident::#idents => ::kdl::KdlValue::from(#names),)*
+                        }
+                    }
+                }
+            }
+        });
+        quote! {
+            impl #impl_generics ::kdl::EncodeKdl for #ident #ty_generics #where_clause {
+                fn encode_kdl(&self) -> ::kdl::KdlNode {
+                    #body
+                }
+            }
+            #value_impl
+        }
+    }
+
+    /// The idents and names of this enum's variants, if it's an enum with
+    /// only unit variants. These also get converted to and from strings, so
+    /// they can be used as values.
+    fn unit_variants(&self) -> Option<Vec<(&Ident, &String)>> {
+        match &self.body {
+            Body::Enum(variants)
+                if !variants.is_empty()
+                    && variants
+                        .iter()
+                        .all(|variant| matches!(variant.shape, Shape::Unit)) =>
+            {
+                Some(
+                    variants
+                        .iter()
+                        .map(|variant| (&variant.ident, &variant.name))
+                        .collect(),
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Decodes `node` into the struct or variant at `path`, returning a
+/// `Result`.
+fn decode_fields(path: TokenStream, fields: &[Field]) -> TokenStream {
+    // Children without a name take whatever the other fields didn't, so
+    // they have to go last.
+    let (rest, others): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .partition(|field| field.kind == Kind::Children && field.name.is_none());
+    let fields = others.into_iter().chain(rest).map(|field| {
+        let ident = &field.ident;
+        let name = field.name.iter();
+        let method = match (field.kind, field.optional, &field.name) {
+            (Kind::Argument, ..) => quote!(argument),
+            (Kind::Property, ..) => quote!(property),
+            (Kind::Child, false, _) => quote!(child),
+            (Kind::Child, true, _) => quote!(optional_child),
+            (Kind::Children, _, Some(_)) => quote!(children_named),
+            (Kind::Children, _, None) => quote!(children),
+        };
+        quote!(#ident: decoder.#method(#(#name)*)?,)
+    });
+    quote! {
+        #[allow(unused_mut)]
+        let mut decoder = ::kdl::KdlDecoder::new(node);
+        let value = #path { #(#fields)* };
+        decoder.finish()?;
+        ::std::result::Result::Ok(value)
+    }
+}
+
+/// Builds a node called `name` out of `fields`, where `accesses` are
+/// expressions for references to each field's value.
+fn encode_fields(
+    name: &str,
+    fields: &[Field],
+    accesses: impl Iterator<Item = TokenStream>,
+) -> TokenStream {
+    let fields = fields
+        .iter()
+        .zip(accesses)
+        .map(|(field, access)| field.encode(access));
+    quote! {
+        #[allow(unused_mut)]
+        let mut node = ::kdl::KdlNode::new(#name);
+        #(#fields)*
+        node
+    }
+}
+
+impl Variant {
+    fn from_variant(variant: &syn::Variant, case: Case) -> syn::Result<Self> {
+        let attrs = ItemAttrs::parse(&variant.attrs, false)?;
+        let name = attrs
+            .name
+            .unwrap_or_else(|| case.apply(&variant.ident.to_string()));
+        let shape =
+            match &variant.fields {
+                Fields::Unit => Shape::Unit,
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => Shape::Newtype,
+                Fields::Unnamed(fields) => return Err(syn::Error::new(
+                    fields.span(),
+                    "tuple variants can only have one field, which is decoded from the whole node",
+                )),
+                // Variants' fields get their names in kebab-case, like a
+                // struct's, since `rename_all` is about the variant names here.
+                fields => Shape::Struct(Field::from_fields(fields, Case::Kebab)?),
+            };
+        Ok(Variant {
+            ident: variant.ident.clone(),
+            name,
+            shape,
+        })
+    }
+}
+
+impl Field {
+    fn from_fields(fields: &Fields, case: Case) -> syn::Result<Vec<Self>> {
+        fields
+            .iter()
+            .map(|field| Field::from_field(field, case))
+            .collect()
+    }
+
+    fn from_field(field: &syn::Field, case: Case) -> syn::Result<Self> {
+        let ident = field.ident.clone().expect("named fields have names");
+        let mut kind = None;
+        let mut name = None;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("kdl"))
+        {
+            attr.parse_nested_meta(|meta| {
+                let new_kind = if meta.path.is_ident("argument") {
+                    Kind::Argument
+                } else if meta.path.is_ident("property") {
+                    Kind::Property
+                } else if meta.path.is_ident("child") {
+                    Kind::Child
+                } else if meta.path.is_ident("children") {
+                    Kind::Children
+                } else if meta.path.is_ident("name") {
+                    name = Some(meta.value()?.parse::<LitStr>()?);
+                    return Ok(());
+                } else {
+                    return Err(meta.error(
+                        "unknown attribute; expected `argument`, `property`, `child`, `children`, or `name`",
+                    ));
+                };
+                if kind.replace(new_kind).is_some() {
+                    return Err(meta.error("a field can only come from one place"));
+                }
+                Ok(())
+            })?;
+        }
+        let kind = kind.ok_or_else(|| {
+            syn::Error::new(
+                ident.span(),
+                "expected `#[kdl(argument)]`, `#[kdl(property)]`, `#[kdl(child)]`, or `#[kdl(children)]`",
+            )
+        })?;
+        let name = match (kind, name) {
+            (Kind::Argument, Some(name)) => {
+                return Err(syn::Error::new(name.span(), "arguments don't have names"))
+            }
+            (Kind::Argument, None) | (Kind::Children, None) => None,
+            (_, Some(name)) => Some(name.value()),
+            (_, None) => Some(case.apply(&ident.unraw().to_string())),
+        };
+        Ok(Field {
+            ident,
+            ty: field.ty.clone(),
+            kind,
+            name,
+            optional: generic_arg(&field.ty, "Option").is_some(),
+        })
+    }
+
+    /// Adds this field to `node`, given an expression for a reference to its
+    /// value.
+    fn encode(&self, access: TokenStream) -> TokenStream {
+        let rename = self.name.iter().map(|name| quote!(child.set_name(#name);));
+        match (self.kind, self.optional, &self.name) {
+            (Kind::Argument, ..) => quote! {
+                node.push(::kdl::KdlEntry::new(::kdl::KdlValue::from(
+                    ::std::clone::Clone::clone(#access),
+                )));
+            },
+            (Kind::Property, false, Some(name)) => quote! {
+                node.push(::kdl::KdlEntry::new_prop(
+                    #name,
+                    ::kdl::KdlValue::from(::std::clone::Clone::clone(#access)),
+                ));
+            },
+            (Kind::Property, true, Some(name)) => quote! {
+                if let ::std::option::Option::Some(value) = #access {
+                    node.push(::kdl::KdlEntry::new_prop(
+                        #name,
+                        ::kdl::KdlValue::from(::std::clone::Clone::clone(value)),
+                    ));
+                }
+            },
+            (Kind::Child, false, _) => quote! {
+                let mut child = ::kdl::EncodeKdl::encode_kdl(#access);
+                #(#rename)*
+                node.ensure_children().nodes_mut().push(child);
+            },
+            (Kind::Child, true, _) => quote! {
+                if let ::std::option::Option::Some(value) = #access {
+                    let mut child = ::kdl::EncodeKdl::encode_kdl(value);
+                    #(#rename)*
+                    node.ensure_children().nodes_mut().push(child);
+                }
+            },
+            (Kind::Children, ..) => quote! {
+                for value in #access {
+                    #[allow(unused_mut)]
+                    let mut child = ::kdl::EncodeKdl::encode_kdl(value);
+                    #(#rename)*
+                    node.ensure_children().nodes_mut().push(child);
+                }
+            },
+            (Kind::Property, _, None) => unreachable!("properties always have names"),
+        }
+    }
+}
+
+/// If `ty` is spelled `wrapper<T>`, like `Option<T>`, returns `T`. The
+/// derive can't see through type aliases, but this is what decides whether
+/// a child may be left out.
+pub(crate) fn generic_arg<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let segment = match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != wrapper {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => {
+            match args.args.first()? {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// A naming convention for `rename_all`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Case {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl Case {
+    const NAMES: &'static [(&'static str, Case)] = &[
+        ("lowercase", Case::Lower),
+        ("UPPERCASE", Case::Upper),
+        ("PascalCase", Case::Pascal),
+        ("camelCase", Case::Camel),
+        ("snake_case", Case::Snake),
+        ("SCREAMING_SNAKE_CASE", Case::ScreamingSnake),
+        ("kebab-case", Case::Kebab),
+        ("SCREAMING-KEBAB-CASE", Case::ScreamingKebab),
+    ];
+
+    fn from_lit(lit: &LitStr) -> syn::Result<Self> {
+        let value = lit.value();
+        Case::NAMES
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, case)| *case)
+            .ok_or_else(|| {
+                let names: Vec<_> = Case::NAMES
+                    .iter()
+                    .map(|(name, _)| format!("`{}`", name))
+                    .collect();
+                syn::Error::new(
+                    lit.span(),
+                    format!("unknown case; expected one of {}", names.join(", ")),
+                )
+            })
+    }
+
+    /// Converts a Rust name, in either `snake_case` or `PascalCase`, to this
+    /// case.
+    fn apply(self, name: &str) -> String {
+        let words = words(name);
+        let capitalize = |word: &str| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        };
+        match self {
+            Case::Lower => words.concat(),
+            Case::Upper => words.concat().to_uppercase(),
+            Case::Pascal => words.iter().map(|word| capitalize(word)).collect(),
+            Case::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    if i == 0 {
+                        word.clone()
+                    } else {
+                        capitalize(word)
+                    }
+                })
+                .collect(),
+            Case::Snake => words.join("_"),
+            Case::ScreamingSnake => words.join("_").to_uppercase(),
+            Case::Kebab => words.join("-"),
+            Case::ScreamingKebab => words.join("-").to_uppercase(),
+        }
+    }
+}
+
+/// Splits a `snake_case` or `PascalCase` name into lowercase words.
+fn words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if c.is_uppercase() && i > 0 && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).map_or(false, |next| next.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.extend(c.to_lowercase());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cases() {
+        assert_eq!(Case::Kebab.apply("ServerConfig"), "server-config");
+        assert_eq!(Case::Kebab.apply("max_connections"), "max-connections");
+        assert_eq!(Case::Kebab.apply("HTTPServer"), "http-server");
+        assert_eq!(Case::Kebab.apply("ipv6_addr"), "ipv6-addr");
+        assert_eq!(Case::Kebab.apply("Ipv6Addr"), "ipv6-addr");
+        assert_eq!(Case::Kebab.apply("x"), "x");
+
+        assert_eq!(Case::Lower.apply("MaxSize"), "maxsize");
+        assert_eq!(Case::Upper.apply("max_size"), "MAXSIZE");
+        assert_eq!(Case::Pascal.apply("max_size"), "MaxSize");
+        assert_eq!(Case::Camel.apply("MaxSize"), "maxSize");
+        assert_eq!(Case::Snake.apply("MaxSize"), "max_size");
+        assert_eq!(Case::ScreamingSnake.apply("MaxSize"), "MAX_SIZE");
+        assert_eq!(Case::ScreamingKebab.apply("max_size"), "MAX-SIZE");
+    }
+}
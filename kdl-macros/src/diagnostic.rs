@@ -0,0 +1,15 @@
+//! Turning the parser's diagnostics into compile errors.
+
+use miette::{Diagnostic, GraphicalReportHandler, GraphicalTheme};
+use proc_macro2::Span;
+
+/// Renders `diagnostic` the way miette would at runtime, minus the colors,
+/// as a compile error at `span`.
+pub(crate) fn error(span: Span, diagnostic: &dyn Diagnostic) -> syn::Error {
+    let mut rendered = String::new();
+    let handler = GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor());
+    if handler.render_report(&mut rendered, diagnostic).is_err() {
+        rendered = diagnostic.to_string();
+    }
+    syn::Error::new(span, rendered)
+}
@@ -0,0 +1,77 @@
+//! `include_kdl!`: embedding documents that were checked at compile time.
+
+use std::path::PathBuf;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::LitStr;
+
+use crate::diagnostic;
+use crate::syntax::check_document;
+
+pub(crate) fn include(input: TokenStream) -> TokenStream {
+    let lit: LitStr = match syn::parse2(input) {
+        Ok(lit) => lit,
+        Err(err) => return err.to_compile_error(),
+    };
+    let path = lit.value();
+    // Proc macros can't find out which file they were called from, so paths
+    // are relative to the crate root instead, like `env!("CARGO_MANIFEST_DIR")`.
+    let full_path = match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(dir) => PathBuf::from(dir).join(&path),
+        None => PathBuf::from(&path),
+    };
+    let text = match std::fs::read_to_string(&full_path) {
+        Ok(text) => text,
+        Err(err) => {
+            let message = format!("couldn't read `{}`: {}", full_path.display(), err);
+            return syn::Error::new(lit.span(), message).to_compile_error();
+        }
+    };
+    if is_v2(&text) {
+        let message = format!(
+            "`{}` is KDL 2.0, but include_kdl! only checks KDL 1.0. Parse it at runtime with `KdlDocument::parse_auto(include_str!(...))` instead.",
+            path
+        );
+        return syn::Error::new(lit.span(), message).to_compile_error();
+    }
+    if let Err(err) = check_document(&text) {
+        let err = err.with_source_name(path);
+        return diagnostic::error(lit.span(), &err).to_compile_error();
+    }
+    // Going through `include_str!` rather than pasting the text in here is
+    // what tells cargo to rebuild when the file changes.
+    let full_path = full_path.to_string_lossy();
+    quote! {
+        <::kdl::KdlDocument as ::std::str::FromStr>::from_str(::std::include_str!(#full_path))
+            .expect("include_kdl! already checked this document")
+    }
+}
+
+/// Whether `text` starts with a `/- kdl-version 2` marker.
+fn is_v2(text: &str) -> bool {
+    let marker = text
+        .trim_start_matches(|c: char| c.is_whitespace() || c == '\u{FEFF}')
+        .strip_prefix("/-")
+        .map(str::trim_start)
+        .and_then(|rest| rest.strip_prefix("kdl-version"))
+        .map(str::trim_start);
+    match marker.and_then(|rest| rest.strip_prefix('2')) {
+        Some(rest) => !rest.starts_with(|c: char| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn v2_marker() {
+        assert!(is_v2("/- kdl-version 2\nnode #true"));
+        assert!(is_v2("\u{FEFF}\n  /-kdl-version 2; node"));
+        assert!(!is_v2("/- kdl-version 1\nnode true"));
+        assert!(!is_v2("/- kdl-version 20"));
+        assert!(!is_v2("node\n/- kdl-version 2"));
+    }
+}
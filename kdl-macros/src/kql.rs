@@ -0,0 +1,471 @@
+//! A copy of kdl's KQL grammar that only checks syntax, for the same reasons
+//! as [`crate::syntax`]. It mirrors `src/query_parser.rs`.
+
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{cut, map, opt, recognize};
+use nom::error::context;
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, preceded, terminated};
+
+use crate::nom_compat::many0;
+use crate::syntax::{
+    identifier, newline, parse_all, set_details, unicode_space, value, Kind, PError, PResult,
+    SyntaxError, Value,
+};
+
+/// Checks `input` as a KQL query, the way `KdlQuery::from_str` does.
+pub(crate) fn check_query(input: &str) -> Result<(), SyntaxError> {
+    parse_all(input, query, |e| {
+        let help = if e.kind.is_none() && e.context.is_none() {
+            Some("The general syntax for queries is '(type)nodename[prop=value], anothernode, etc'. For more details, please see https://github.com/kdl-org/kdl/blob/main/QUERY-SPEC.md")
+        } else {
+            None
+        };
+        (Kind::Context("a valid KQL query"), help)
+    })
+}
+
+fn error<'a>(
+    input: &'a str,
+    len: usize,
+    label: &'static str,
+    help: &'static str,
+    context: &'static str,
+) -> PError<&'a str> {
+    PError {
+        input,
+        len,
+        kind: None,
+        label: Some(label),
+        help: Some(help),
+        related: None,
+        touched: false,
+        context: Some(context),
+    }
+}
+
+fn query(input: &str) -> PResult<'_, ()> {
+    map(
+        separated_list1(delimited(whitespace, tag(","), whitespace), query_selector),
+        |_| (),
+    )(input)
+}
+
+fn query_selector(input: &str) -> PResult<'_, ()> {
+    let mut is_scope = true;
+    let mut input = input;
+    loop {
+        let (inp, _) = whitespace(input)?;
+        let (inp, _) = node_matchers(is_scope)(inp)?;
+        let (inp, _) = whitespace(inp)?;
+        let (inp, op) = opt(segment_combinator)(inp)?;
+        input = inp;
+        if op.is_none() {
+            break;
+        }
+        is_scope = false;
+    }
+    let (input, _) = whitespace(input)?;
+    Ok((input, ()))
+}
+
+fn segment_combinator(input: &str) -> PResult<'_, &str> {
+    alt((tag(">>"), tag(">"), tag("++"), tag("+")))(input)
+}
+
+fn node_matchers(is_scope: bool) -> impl Fn(&str) -> PResult<'_, ()> {
+    move |input| {
+        let mut matched = false;
+
+        let (input, _) = whitespace(input)?;
+
+        let start = input;
+        let (input, scope) = opt(scope_accessor)(input)?;
+        if scope.is_some() {
+            if is_scope {
+                return Ok((input, ()));
+            } else {
+                return Err(nom::Err::Error(error(
+                    start,
+                    start.len() - input.len(),
+                    "scope()",
+                    "Make sure scope() precedes any other items within a (comma-separated) selector.",
+                    "scope() to be the first item in this selector",
+                )));
+            }
+        }
+
+        let (input, details) = opt(annotation_matcher)(input)?;
+        if details.is_some() {
+            matched = true;
+            let start = input;
+            let (input, typed) = opt(annotation_matcher)(input)?;
+            if typed.is_some() {
+                return Err(nom::Err::Error(error(
+                    start,
+                    start.len() - input.len(),
+                    "type annotation",
+                    "The syntax for node selectors is (type)node[attribute=value].",
+                    "only one type annotation per selector",
+                )));
+            }
+        }
+
+        let (input, node) = opt(identifier)(input)?;
+        matched |= node.is_some();
+
+        let start = input;
+        let (input, typed) = opt(annotation_matcher)(input)?;
+        if typed.is_some() {
+            return Err(nom::Err::Error(error(
+                start,
+                start.len() - input.len(),
+                "type annotation",
+                "The syntax for node selectors is (type)node[attribute=value].",
+                "type annotation to not be used after a node name",
+            )));
+        }
+
+        let start = input;
+        let (input, attribute_matchers) = many0(attribute_matcher)(input)?;
+        matched |= !attribute_matchers.is_empty();
+
+        if !matched {
+            Err(nom::Err::Error(error(
+                start,
+                0,
+                "node matcher",
+                "node matcher must not be empty",
+                "a valid node matcher",
+            )))
+        } else {
+            // Check for trailing type annotations.
+            let start = input;
+            let (end, typed) = opt(annotation_matcher)(input)?;
+            if typed.is_some() {
+                return Err(nom::Err::Error(error(
+                    start,
+                    start.len() - end.len(),
+                    "type annotation",
+                    "The syntax for node selectors is (type)node[attribute=value].",
+                    "type annotation to come before attribute matcher(s)",
+                )));
+            }
+
+            // Check for trailing node name matcher.
+            let (end, ident) = opt(identifier)(input)?;
+            if ident.is_some() {
+                return Err(nom::Err::Error(error(
+                    start,
+                    start.len() - end.len(),
+                    "node name",
+                    "The syntax for node selectors is (type)node[attribute=value].",
+                    "node name to come before attribute matcher(s)",
+                )));
+            }
+
+            Ok((input, ()))
+        }
+    }
+}
+
+fn attribute_matcher(input: &str) -> PResult<'_, ()> {
+    let start = input;
+    let (input, _) = tag("[")(input)?;
+    let (input, _) = whitespace(input)?;
+    let (input, _) = attribute_matcher_inner(input)?;
+    let (input, _) = whitespace(input)?;
+    let (input, _) = context("a closing ']' for this attribute matcher", cut(tag("]")))(input)
+        .map_err(|e| set_details(e, start, Some("partial attribute matcher"), None))?;
+    Ok((input, ()))
+}
+
+fn attribute_matcher_inner(input: &str) -> PResult<'_, ()> {
+    let (input, xsr) = opt(accessor)(input)?;
+    if xsr.is_none() {
+        return Ok((input, ()));
+    }
+    let (input, _) = whitespace(input)?;
+    let (input, op) = opt(attribute_op)(input)?;
+    let (input, _) = whitespace(input)?;
+    let op = match op {
+        Some(op) => op,
+        None => return Ok((input, ())),
+    };
+    let prev = input;
+    let (input, val) = opt(value)(input)?;
+    match val {
+        // Make sure it's a syntax error to try and use string operators
+        // with non-string arguments.
+        Some(val) if matches!(op, "^=" | "$=" | "*=") && val != Value::String => {
+            Err(nom::Err::Failure(error(
+                prev,
+                prev.len() - input.len(),
+                "non-string operator value",
+                "Only strings can be used as arguments for string-related operators (*=, ^=, $=).",
+                "a string as an operator value",
+            )))
+        }
+        Some(_) => Ok((input, ())),
+        None => Err(nom::Err::Failure(error(
+            prev,
+            0,
+            "operator value",
+            "Only valid KDL values can be used on the right hand side of attribute matcher operators.",
+            "a valid operator argument",
+        ))),
+    }
+}
+
+fn attribute_op(input: &str) -> PResult<'_, &str> {
+    alt((
+        tag("="),
+        tag("!="),
+        tag(">"),
+        tag(">="),
+        tag("<"),
+        tag("<="),
+        tag("^="),
+        tag("$="),
+        tag("*="),
+    ))(input)
+}
+
+fn annotation_matcher(input: &str) -> PResult<'_, ()> {
+    let start = input;
+    let (input, _) = tag("(")(input)?;
+    let (input, _) = whitespace(input)?;
+    let (input, _) = opt(identifier)(input)?;
+    let (input, _) = context("closing ')' for type annotation", cut(tag(")")))(input)
+        .map_err(|e| set_details(e, start, Some("annotation"), Some("annotations can only be KDL identifiers (including string identifiers), and can't have any space inside the parentheses.")))?;
+    Ok((input, ()))
+}
+
+fn scope_accessor(input: &str) -> PResult<'_, ()> {
+    let start = input;
+    let (input, _) = tag("scope(")(input)?;
+    let (input, _) = context(
+        "a valid scope accessor",
+        cut(preceded(whitespace, tag(")"))),
+    )(input)
+    .map_err(|e| set_details(e, start, Some("partial scope accessor"), None))?;
+    Ok((input, ()))
+}
+
+fn accessor(input: &str) -> PResult<'_, ()> {
+    alt((
+        type_accessor,
+        arg_accessor,
+        prop_accessor,
+        prop_name_accessor,
+        bad_accessor,
+    ))(input)
+}
+
+fn type_accessor(input: &str) -> PResult<'_, ()> {
+    let start = input;
+    let (input, _) = tag("type")(input)?;
+    let (input, _) = context(
+        "an opening '(' for a 'type()' accessor",
+        preceded(whitespace, tag("(")),
+    )(input)
+    .map_err(|e| set_details(e, start, Some("partial type accessor"), None))?;
+    let (input, _) = context(
+        "a closing ')' for this 'type()' accessor",
+        cut(preceded(whitespace, tag(")"))),
+    )(input)
+    .map_err(|e| {
+        set_details(
+            e,
+            start,
+            Some("partial type accessor"),
+            Some("type() accessors don't take any arguments. Use e.g. [type() = \"foo\"] instead."),
+        )
+    })?;
+    Ok((input, ()))
+}
+
+fn arg_accessor(input: &str) -> PResult<'_, ()> {
+    let (input, _) = tag("arg")(input)?;
+    let (input, arg) = parenthesized_arg(input)?;
+    match arg {
+        // Like `KdlValue::as_i64`, then into a `usize` index.
+        Some(Value::Int(int))
+            if i64::try_from(int).map_or(false, |i| usize::try_from(i).is_ok()) =>
+        {
+            Ok((input, ()))
+        }
+        Some(_) => Err(nom::Err::Error(error(
+            input,
+            0,
+            "arg accessor",
+            "arg accessor must be an integer",
+            "a valid arg accessor",
+        ))),
+        None => Ok((input, ())),
+    }
+}
+
+fn prop_name_accessor(input: &str) -> PResult<'_, ()> {
+    let start = input;
+    let (input, _) = identifier(input)?;
+    let (_, paren) = opt(preceded(whitespace, tag("(")))(input)?;
+    if paren.is_some() {
+        Err(nom::Err::Error(error(
+            start,
+            0,
+            "accessor",
+            "accessor must be one of: type(), arg(), prop(), propname",
+            "a valid accessor",
+        )))
+    } else {
+        Ok((input, ()))
+    }
+}
+
+fn prop_accessor(input: &str) -> PResult<'_, ()> {
+    let (input, _) = tag("prop")(input)?;
+    let (input, _) = tag("(")(input)?;
+    let (input, _) = identifier(input)?;
+    let (input, _) = tag(")")(input)?;
+    Ok((input, ()))
+}
+
+fn parenthesized_arg(input: &str) -> PResult<'_, Option<Value>> {
+    let (input, _) = tag("(")(input)?;
+    let (input, maybe_value) = opt(value)(input)?;
+    let (input, _) = tag(")")(input)?;
+    Ok((input, maybe_value))
+}
+
+fn bad_accessor(input: &str) -> PResult<'_, ()> {
+    let start = input;
+
+    let (input, scope) = opt(preceded(
+        tag("scope"),
+        preceded(
+            whitespace,
+            opt(terminated(tag("("), opt(preceded(whitespace, tag(")"))))),
+        ),
+    ))(input)?;
+
+    if scope.is_some() {
+        return Err(nom::Err::Failure(error(
+            start,
+            start.len() - input.len(),
+            "incorrect scope() accessor",
+            "Accessors must be one of: type(), arg(), prop(), propname",
+            "'scope()' to be the first item only at the top level of the query selector",
+        )));
+    }
+
+    let (input, ident) = opt(terminated(
+        identifier,
+        preceded(
+            whitespace,
+            terminated(tag("("), opt(preceded(whitespace, tag(")")))),
+        ),
+    ))(input)?;
+
+    if let Some(ident) = ident {
+        if !matches!(&ident[..], "type" | "arg" | "prop" | "val") {
+            return Err(nom::Err::Failure(error(
+                start,
+                start.len() - input.len(),
+                "invalid attribute accessor",
+                "Accessors must be one of: type(), arg(), prop(), propname",
+                "a valid attribute accessor",
+            )));
+        }
+    }
+
+    Err(nom::Err::Error(error(
+        start,
+        0,
+        "accessor",
+        "accessor must be one of: type(), arg(), prop(), propname",
+        "a valid accessor",
+    )))
+}
+
+fn whitespace(input: &str) -> PResult<'_, &str> {
+    recognize(many0(alt((unicode_space, newline))))(input)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn same_as_kdl(input: &str) {
+        let ours = check_query(input);
+        let theirs = input.parse::<kdl::KdlQuery>();
+        match (ours, theirs) {
+            (Ok(()), Ok(_)) => {}
+            (Err(ours), Err(theirs)) => {
+                let span = (theirs.span.offset(), theirs.span.len());
+                assert_eq!(ours.kind.code(), theirs.kind.code_str(), "{:?}", input);
+                assert_eq!(ours.to_string(), theirs.to_string(), "{:?}", input);
+                assert_eq!(ours.span(), span, "{:?}", input);
+            }
+            (ours, theirs) => panic!(
+                "{:?}: kdl-macros says {:?}, kdl says {:?}",
+                input,
+                ours.map_err(|e| e.to_string()),
+                theirs.map(|_| ()).map_err(|e| e.to_string())
+            ),
+        }
+    }
+
+    #[test]
+    fn matches_kdl() {
+        for input in [
+            "a",
+            "a, b",
+            "scope() > a",
+            "a > b >> c + d ++ e",
+            "(ty)a[prop=1]",
+            "()a",
+            "(\"quoted\")a",
+            "[val()]",
+            "[arg()]",
+            "[arg(1)]",
+            "[arg(-1)]",
+            "[arg(1.5)]",
+            "[arg(\"x\")]",
+            "[prop(name) >= 2]",
+            "[name != null]",
+            "[type() = \"t\"]",
+            "[type ( ) = \"t\"]",
+            "[x ^= \"a\"]",
+            "[x $= r\"a\"]",
+            "[x *= 1]",
+            "[x = ]",
+            "[\"type\"()]",
+            "[foo()]",
+            "[scope()]",
+            "a scope()",
+            "a, scope()",
+            "(a)(b)c",
+            "a(b)",
+            "[x](b)",
+            "[x]a",
+            "a[x][y = 0x10] > b",
+            "",
+            ",",
+            "a >",
+            "a[",
+            "a[x",
+        ] {
+            same_as_kdl(input);
+            for (idx, c) in input.char_indices() {
+                same_as_kdl(&input[..idx]);
+                same_as_kdl(&format!(
+                    "{}{}",
+                    &input[..idx],
+                    &input[idx + c.len_utf8()..]
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Procedural macros for [`kdl`](https://crates.io/crates/kdl).
+//!
+//! Don't depend on this crate directly: enable `kdl`'s `macros` feature and
+//! use the macros from there, since the generated code refers to `::kdl`.
+#![deny(missing_debug_implementations, nonstandard_style)]
+#![warn(missing_docs, unreachable_pub, rust_2018_idioms)]
+
+use proc_macro::TokenStream;
+
+mod builder;
+mod derive;
+mod diagnostic;
+mod include;
+mod kql;
+mod literal;
+mod nom_compat;
+mod query;
+mod syntax;
+
+/// Builds a [`KdlDocument`](https://docs.rs/kdl/latest/kdl/struct.KdlDocument.html)
+/// from KDL written inline, checking its syntax at compile time.
+///
+/// Since this goes through Rust's tokenizer, the syntax differs from KDL
+/// text in a few ways:
+///
+/// * Nodes are separated by `;` (or by their children block), since line
+///   breaks aren't visible to macros.
+/// * Names and property keys are Rust identifiers, `-`, or string literals
+///   for anything else, like `"max-size"=10`.
+/// * Values are Rust literals (numbers, strings, and raw strings), `true`,
+///   `false`, or `null`. Integer literals keep their radix, so `0xff` is a
+///   `Base16` value.
+/// * `#name` and `#(expr)` insert the value of a Rust expression, converted
+///   with `KdlValue::from`.
+///
+/// Type annotations work as usual, like `(u8)255`.
+///
+/// # Examples
+///
+/// ```rust
+/// use kdl::kdl;
+///
+/// let port = 8080;
+/// let doc = kdl! {
+///     server "main" port=#port {
+///         "max-connections" (u16)1000;
+///         - "alice"; - "bob"
+///     }
+///     debug enabled=#(port != 80) mask=0xff
+/// };
+/// assert_eq!(
+///     doc.to_string(),
+///     r#"server "main" port=8080 {
+///     max-connections (u16)1000
+///     - "alice"
+///     - "bob"
+/// }
+/// debug enabled=true mask=0xff
+/// "#
+/// );
+/// ```
+#[proc_macro]
+pub fn kdl(input: TokenStream) -> TokenStream {
+    literal::document(input.into()).into()
+}
+
+/// Like [`kdl!`], but builds a single
+/// [`KdlNode`](https://docs.rs/kdl/latest/kdl/struct.KdlNode.html).
+///
+/// # Examples
+///
+/// ```rust
+/// use kdl::kdl_node;
+///
+/// let node = kdl_node!(package name="kdl" version="5.0.0");
+/// assert_eq!(node.to_string(), r#"package name="kdl" version="5.0.0""#);
+/// ```
+#[proc_macro]
+pub fn kdl_node(input: TokenStream) -> TokenStream {
+    literal::node(input.into()).into()
+}
+
+/// Parses a [KQL](https://github.com/kdl-org/kdl/blob/main/QUERY-SPEC.md)
+/// query, failing the build if it's invalid.
+///
+/// This evaluates to a
+/// [`KdlQuery`](https://docs.rs/kdl/latest/kdl/struct.KdlQuery.html) that
+/// can be passed to `query()` and friends. Queries can't be built in a
+/// `const`, so the query is parsed again each time the macro's expression
+/// runs, but that parse can't fail. Keep the query around when using it in
+/// a loop.
+///
+/// # Examples
+///
+/// ```rust
+/// use kdl::{kdl_query, KdlDocument};
+///
+/// let doc: KdlDocument = "package { name \"kdl\"; }\nname \"other\"".parse().unwrap();
+/// let query = kdl_query!("package > name");
+/// let node = doc.query(query).unwrap().unwrap();
+/// assert_eq!(node[0], "kdl".into());
+/// ```
+///
+/// Typos are caught when compiling:
+///
+/// ```compile_fail
+/// let query = kdl::kdl_query!("scope() > package[name = ]");
+/// ```
+#[proc_macro]
+pub fn kdl_query(input: TokenStream) -> TokenStream {
+    query::query(input.into()).into()
+}
+
+/// Includes a KDL file as a
+/// [`KdlDocument`](https://docs.rs/kdl/latest/kdl/struct.KdlDocument.html),
+/// failing the build if it doesn't parse.
+///
+/// The path is relative to the crate's root directory (where its
+/// `Cargo.toml` is), since macros can't tell which file they're used in.
+/// Parse errors are reported the same way as at runtime, with a snippet of
+/// the file.
+///
+/// The file's text is embedded in the binary with `include_str!` and parsed
+/// again, keeping all of its formatting, each time the macro's expression
+/// runs. That parse can't fail.
+///
+/// Only KDL 1.0 files can be included for now, since the compile-time check
+/// is a copy of kdl's KDL 1.0 grammar. Files starting with a
+/// `/- kdl-version 2` marker get an error saying so. Parse KDL 2.0 files at
+/// runtime instead, with `KdlDocument::parse_auto(include_str!(...))`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use kdl::{include_kdl, KdlDocument};
+///
+/// let defaults: KdlDocument = include_kdl!("config/defaults.kdl");
+/// ```
+#[proc_macro]
+pub fn include_kdl(input: TokenStream) -> TokenStream {
+    include::include(input.into()).into()
+}
+
+/// Derives `kdl::DecodeKdl` for a struct, using `#[kdl(...)]` attributes on
+/// each field to say where in the node it comes from. See the
+/// [trait's documentation](https://docs.rs/kdl/latest/kdl/trait.DecodeKdl.html)
+/// for the attributes.
+#[proc_macro_derive(DecodeKdl, attributes(kdl))]
+pub fn derive_decode_kdl(input: TokenStream) -> TokenStream {
+    derive::decode(input.into()).into()
+}
+
+/// Derives `kdl::EncodeKdl` for a struct, using the same attributes as
+/// [`DecodeKdl`](derive@DecodeKdl).
+#[proc_macro_derive(EncodeKdl, attributes(kdl))]
+pub fn derive_encode_kdl(input: TokenStream) -> TokenStream {
+    derive::encode(input.into()).into()
+}
+
+/// Derives a builder for writing a struct's node without creating the struct
+/// first, using the same attributes as [`DecodeKdl`](derive@DecodeKdl).
+///
+/// For a struct `Foo`, this adds `Foo::builder()`, returning a `FooBuilder`
+/// with a method named after each field. Arguments and properties take the
+/// field's type (without the `Option`), `child` fields take the child's
+/// type, and `children` fields take any number of items. Children are
+/// encoded with `kdl::EncodeKdl`, so their types need to derive that.
+/// `build()` returns the finished `KdlNode`.
+///
+/// Anything that isn't set is left out, so only the names of the fields are
+/// checked at compile time, not whether they're all there. Derive
+/// `EncodeKdl` and build the struct itself for that.
+///
+/// # Examples
+///
+/// ```rust
+/// use kdl::{EncodeKdl, KdlBuilder};
+///
+/// #[derive(KdlBuilder)]
+/// struct Server {
+///     #[kdl(argument)]
+///     host: String,
+///     #[kdl(property)]
+///     port: u16,
+///     #[kdl(property)]
+///     max_connections: Option<u32>,
+///     #[kdl(children, name = "route")]
+///     routes: Vec<Route>,
+/// }
+///
+/// #[derive(EncodeKdl)]
+/// struct Route {
+///     #[kdl(argument)]
+///     path: String,
+/// }
+///
+/// let node = Server::builder()
+///     .port(8080)
+///     .host("localhost".into())
+///     .routes(["/", "/api"].iter().map(|path| Route { path: path.to_string() }))
+///     .build();
+/// assert_eq!(
+///     node.to_string(),
+///     r#"server "localhost" port=8080 {
+///     route "/"
+///     route "/api"
+/// }"#
+/// );
+/// ```
+#[proc_macro_derive(KdlBuilder, attributes(kdl))]
+pub fn derive_kdl_builder(input: TokenStream) -> TokenStream {
+    builder::builder(input.into()).into()
+}
@@ -0,0 +1,261 @@
+//! `kdl!` and `kdl_node!`: parsing KDL-ish Rust tokens into code that builds
+//! the equivalent document at runtime.
+
+use proc_macro2::{Literal, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{
+    braced,
+    ext::IdentExt,
+    parenthesized,
+    parse::{Parse, ParseStream},
+    Ident, Lit, LitFloat, LitInt, LitStr, Token,
+};
+
+pub(crate) fn document(input: TokenStream) -> TokenStream {
+    match syn::parse2::<Document>(input) {
+        Ok(doc) => doc.into_token_stream(),
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+pub(crate) fn node(input: TokenStream) -> TokenStream {
+    let doc = match syn::parse2::<Document>(input) {
+        Ok(doc) => doc,
+        Err(err) => return err.to_compile_error(),
+    };
+    match <[Node; 1]>::try_from(doc.nodes) {
+        Ok([node]) => node.into_token_stream(),
+        Err(_) => syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "kdl_node! takes exactly one node; use kdl! for more",
+        )
+        .to_compile_error(),
+    }
+}
+
+struct Document {
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    ty: Option<Name>,
+    name: Name,
+    entries: Vec<Entry>,
+    children: Option<Document>,
+}
+
+struct Entry {
+    name: Option<Name>,
+    ty: Option<Name>,
+    value: TokenStream,
+}
+
+/// A node name, property key, or type annotation.
+struct Name(LitStr);
+
+impl Parse for Document {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let mut nodes = Vec::new();
+        while !input.is_empty() {
+            if input.parse::<Option<Token![;]>>()?.is_none() {
+                nodes.push(input.parse()?);
+            }
+        }
+        Ok(Document { nodes })
+    }
+}
+
+impl Parse for Node {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let ty = annotation(input)?;
+        let name = input.parse()?;
+        let mut entries = Vec::new();
+        while !(input.is_empty() || input.peek(Token![;]) || input.peek(syn::token::Brace)) {
+            entries.push(input.parse()?);
+        }
+        let children = if input.peek(syn::token::Brace) {
+            let content;
+            braced!(content in input);
+            Some(content.parse()?)
+        } else {
+            None
+        };
+        Ok(Node {
+            ty,
+            name,
+            entries,
+            children,
+        })
+    }
+}
+
+impl Parse for Entry {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let is_prop = (input.peek(Ident::peek_any) || input.peek(LitStr)) && input.peek2(Token![=]);
+        let name = if is_prop {
+            let name = input.parse()?;
+            input.parse::<Token![=]>()?;
+            Some(name)
+        } else {
+            None
+        };
+        let ty = annotation(input)?;
+        let value = value(input)?;
+        Ok(Entry { name, ty, value })
+    }
+}
+
+impl Parse for Name {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            Ok(Name(input.parse()?))
+        } else if let Some(dash) = input.parse::<Option<Token![-]>>()? {
+            Ok(Name(LitStr::new("-", dash.span)))
+        } else {
+            let ident = input.call(Ident::parse_any)?;
+            Ok(Name(LitStr::new(&ident.unraw().to_string(), ident.span())))
+        }
+    }
+}
+
+/// An optional `(type)` annotation.
+fn annotation(input: ParseStream<'_>) -> syn::Result<Option<Name>> {
+    if !input.peek(syn::token::Paren) {
+        return Ok(None);
+    }
+    let content;
+    parenthesized!(content in input);
+    let name = content.parse()?;
+    if !content.is_empty() {
+        return Err(content.error("expected `)` after the type annotation"));
+    }
+    Ok(Some(name))
+}
+
+/// Parses a value, returning an expression that evaluates to a `KdlValue`.
+fn value(input: ParseStream<'_>) -> syn::Result<TokenStream> {
+    if input.parse::<Option<Token![#]>>()?.is_some() {
+        let expr = if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            content.parse::<syn::Expr>()?.into_token_stream()
+        } else {
+            input.parse::<Ident>()?.into_token_stream()
+        };
+        return Ok(quote!(::kdl::KdlValue::from(#expr)));
+    }
+    if input.peek(Ident) {
+        let ident: Ident = input.parse()?;
+        return match ident.to_string().as_str() {
+            "null" => Ok(quote!(::kdl::KdlValue::Null)),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                "expected a value; bare identifiers aren't values, and nodes need to be separated by `;`",
+            )),
+        };
+    }
+    let negative = input.parse::<Option<Token![-]>>()?.is_some();
+    match input.parse::<Lit>()? {
+        Lit::Int(lit) if lit.suffix().is_empty() => int(&lit, negative),
+        Lit::Float(lit) if lit.suffix().is_empty() => float(&lit, negative),
+        Lit::Int(lit) => Err(suffix_error(lit.span())),
+        Lit::Float(lit) => Err(suffix_error(lit.span())),
+        Lit::Str(lit) if !negative => {
+            let raw = lit.token().to_string().starts_with('r');
+            Ok(if raw {
+                quote!(::kdl::KdlValue::RawString(::std::string::String::from(#lit)))
+            } else {
+                quote!(::kdl::KdlValue::String(::std::string::String::from(#lit)))
+            })
+        }
+        Lit::Bool(lit) if !negative => {
+            let value = lit.value;
+            Ok(quote!(::kdl::KdlValue::Bool(#value)))
+        }
+        lit => Err(syn::Error::new(lit.span(), "expected a KDL value")),
+    }
+}
+
+fn int(lit: &LitInt, negative: bool) -> syn::Result<TokenStream> {
+    let text = lit.to_string();
+    let variant = match text.get(..2) {
+        Some("0x") => quote!(Base16),
+        Some("0o") => quote!(Base8),
+        Some("0b") => quote!(Base2),
+        _ => quote!(Base10),
+    };
+    let magnitude: u128 = lit.base10_parse()?;
+    let value = match (negative, i128::try_from(magnitude)) {
+        (false, Ok(value)) => Some(value),
+        (true, Ok(value)) => Some(-value),
+        (true, Err(_)) if magnitude == i128::MIN.unsigned_abs() => Some(i128::MIN),
+        _ => None,
+    };
+    let value =
+        value.ok_or_else(|| syn::Error::new(lit.span(), "integer doesn't fit in an i128"))?;
+    let value = Literal::i128_unsuffixed(value);
+    Ok(quote!(::kdl::KdlValue::#variant(#value)))
+}
+
+fn float(lit: &LitFloat, negative: bool) -> syn::Result<TokenStream> {
+    let value: f64 = lit.base10_parse()?;
+    if !value.is_finite() {
+        return Err(syn::Error::new(lit.span(), "float is too large"));
+    }
+    let value = Literal::f64_unsuffixed(if negative { -value } else { value });
+    Ok(quote!(::kdl::KdlValue::Base10Float(#value)))
+}
+
+fn suffix_error(span: proc_macro2::Span) -> syn::Error {
+    syn::Error::new(
+        span,
+        "number suffixes aren't supported; use a type annotation like `(u8)1` instead",
+    )
+}
+
+impl ToTokens for Document {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let nodes = &self.nodes;
+        tokens.extend(quote! {{
+            let mut doc = ::kdl::KdlDocument::new();
+            #(doc.nodes_mut().push(#nodes);)*
+            doc
+        }});
+    }
+}
+
+impl ToTokens for Node {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.name.0;
+        let ty = self.ty.iter().map(|ty| &ty.0);
+        let entries = &self.entries;
+        let children = self.children.iter();
+        tokens.extend(quote! {{
+            let mut node = ::kdl::KdlNode::new(#name);
+            #(node.set_ty(#ty);)*
+            #(node.push(#entries);)*
+            #(node.set_children(#children);)*
+            node
+        }});
+    }
+}
+
+impl ToTokens for Entry {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = &self.value;
+        let entry = match &self.name {
+            Some(name) => {
+                let name = &name.0;
+                quote!(::kdl::KdlEntry::new_prop(#name, #value))
+            }
+            None => quote!(::kdl::KdlEntry::new(#value)),
+        };
+        let ty = self.ty.iter().map(|ty| &ty.0);
+        tokens.extend(quote! {{
+            #[allow(unused_mut)]
+            let mut entry = #entry;
+            #(entry.set_ty(#ty);)*
+            entry
+        }});
+    }
+}
@@ -0,0 +1,22 @@
+//! `kdl_query!`: checking KQL queries at compile time.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::LitStr;
+
+use crate::diagnostic;
+use crate::kql::check_query;
+
+pub(crate) fn query(input: TokenStream) -> TokenStream {
+    let lit: LitStr = match syn::parse2(input) {
+        Ok(lit) => lit,
+        Err(err) => return err.to_compile_error(),
+    };
+    if let Err(err) = check_query(&lit.value()) {
+        return diagnostic::error(lit.span(), &err).to_compile_error();
+    }
+    quote! {
+        <::kdl::KdlQuery as ::std::str::FromStr>::from_str(#lit)
+            .expect("kdl_query! already checked this query")
+    }
+}
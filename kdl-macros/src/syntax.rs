@@ -0,0 +1,1048 @@
+//! A copy of kdl's KDL 1.0 grammar that only checks syntax.
+//!
+//! kdl-macros can't depend on kdl, since kdl depends on it for its `macros`
+//! feature, so the macros that check KDL at compile time bring their own
+//! parser. Anything this accepts gets parsed again at runtime with
+//! `str::parse`, which `.expect`s, so it has to accept exactly what kdl's
+//! parser accepts with the default [`ParseOptions`]. It mirrors
+//! `src/parser.rs` function for function, including where it cuts, and the
+//! tests check the two against each other.
+//!
+//! Only the KDL 1.0 grammar is copied: `include_kdl!` turns KDL 2.0 files
+//! away rather than checking them. Changes to the KDL 1.0 parts of
+//! `src/parser.rs` need making here too, or `same_as_kdl` fails.
+//!
+//! [`ParseOptions`]: https://docs.rs/kdl/latest/kdl/struct.ParseOptions.html
+
+use std::fmt;
+use std::ops::RangeTo;
+
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode};
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_until, take_while, take_while_m_n};
+use nom::character::complete::{anychar, char, none_of, one_of};
+use nom::combinator::{all_consuming, cut, eof, map, map_opt, map_res, opt, peek, recognize};
+use nom::error::{context, ContextError, ErrorKind, FromExternalError, ParseError};
+use nom::sequence::{delimited, preceded, terminated, tuple};
+use nom::{Finish, IResult, Offset, Parser, Slice};
+
+use crate::nom_compat::{many0, many1, many_till};
+
+/// The kinds of errors the parser reports, with the same messages and codes
+/// as kdl's `KdlErrorKind`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Kind {
+    ParseInt(std::num::ParseIntError),
+    UnterminatedString,
+    InvalidEscape,
+    UnterminatedComment,
+    InvalidIdentifierChar,
+    ReservedKeyword,
+    BareIdentifier,
+    InvalidNumber,
+    InvalidValue,
+    InvalidAnnotation,
+    InvalidLineEscape,
+    InvalidNodeTerminator,
+    UnbalancedChildren,
+    Context(&'static str),
+    Other,
+}
+
+impl Kind {
+    pub(crate) fn code(&self) -> &'static str {
+        use Kind::*;
+        match self {
+            ParseInt(_) => "kdl::parse_int",
+            UnterminatedString => "kdl::unterminated_string",
+            InvalidEscape => "kdl::invalid_escape",
+            UnterminatedComment => "kdl::unterminated_comment",
+            InvalidIdentifierChar => "kdl::invalid_identifier",
+            ReservedKeyword => "kdl::reserved_keyword",
+            BareIdentifier => "kdl::bare_identifier",
+            InvalidNumber => "kdl::invalid_number",
+            InvalidValue => "kdl::invalid_value",
+            InvalidAnnotation => "kdl::invalid_annotation",
+            InvalidLineEscape => "kdl::invalid_line_escape",
+            InvalidNodeTerminator => "kdl::invalid_node_terminator",
+            UnbalancedChildren => "kdl::unbalanced_children",
+            Context(_) => "kdl::parse_component",
+            Other => "kdl::other",
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Kind::*;
+        match self {
+            ParseInt(err) => write!(f, "{}", err),
+            UnterminatedString => write!(f, "Unterminated string."),
+            InvalidEscape => write!(f, "Invalid escape sequence."),
+            UnterminatedComment => write!(f, "Unterminated multi-line comment."),
+            InvalidIdentifierChar => write!(f, "Invalid identifier character."),
+            ReservedKeyword => write!(f, "Reserved keyword used as an identifier."),
+            BareIdentifier => write!(f, "Bare identifier used as a value."),
+            InvalidNumber => write!(f, "Invalid number."),
+            InvalidValue => write!(f, "Invalid property value."),
+            InvalidAnnotation => write!(f, "Invalid type annotation."),
+            InvalidLineEscape => write!(f, "Invalid line escape."),
+            InvalidNodeTerminator => write!(f, "Invalid node terminator."),
+            UnbalancedChildren => write!(f, "Unbalanced children block."),
+            Context(ctx) => write!(f, "Expected {}.", ctx),
+            Other => write!(f, "An unspecified error occurred."),
+        }
+    }
+}
+
+/// A syntax error, rendered the way kdl renders its `KdlError`.
+#[derive(Debug)]
+pub(crate) struct SyntaxError {
+    source: Box<Source>,
+    span: (usize, usize),
+    label: Option<&'static str>,
+    related: Option<((usize, usize), &'static str)>,
+    help: Option<&'static str>,
+    pub(crate) kind: Kind,
+}
+
+#[derive(Debug)]
+enum Source {
+    Unnamed(String),
+    Named(NamedSource),
+}
+
+impl SyntaxError {
+    /// Attaches a name (usually a file path) to the source of this error,
+    /// like `KdlError::with_source_name`.
+    pub(crate) fn with_source_name(mut self, name: impl AsRef<str>) -> Self {
+        if let Source::Unnamed(text) = *self.source {
+            self.source = Box::new(Source::Named(NamedSource::new(name, text)));
+        }
+        self
+    }
+
+    /// The byte offset and length of the span the error points at.
+    #[cfg(test)]
+    pub(crate) fn span(&self) -> (usize, usize) {
+        self.span
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+impl Diagnostic for SyntaxError {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(self.kind.code()))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.help
+            .map(|help| Box::new(help) as Box<dyn fmt::Display + 'a>)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        match &*self.source {
+            Source::Unnamed(text) => Some(text),
+            Source::Named(named) => Some(named),
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let main = LabeledSpan::new(
+            Some(self.label.unwrap_or("here").to_string()),
+            self.span.0,
+            self.span.1,
+        );
+        let related = self
+            .related
+            .map(|(span, label)| LabeledSpan::new(Some(label.to_string()), span.0, span.1));
+        Some(Box::new(std::iter::once(main).chain(related)))
+    }
+}
+
+/// kdl's `KdlParseError`, which carries the details for a [`SyntaxError`]
+/// through nom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PError<I> {
+    pub(crate) input: I,
+    pub(crate) context: Option<&'static str>,
+    pub(crate) len: usize,
+    pub(crate) label: Option<&'static str>,
+    pub(crate) help: Option<&'static str>,
+    pub(crate) kind: Option<Kind>,
+    pub(crate) related: Option<Box<(I, &'static str)>>,
+    pub(crate) touched: bool,
+}
+
+impl<I> ParseError<I> for PError<I> {
+    fn from_error_kind(input: I, _kind: ErrorKind) -> Self {
+        Self {
+            input,
+            len: 0,
+            label: None,
+            help: None,
+            context: None,
+            kind: None,
+            related: None,
+            touched: false,
+        }
+    }
+
+    fn append(_input: I, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl<I> ContextError<I> for PError<I> {
+    fn add_context(_input: I, ctx: &'static str, mut other: Self) -> Self {
+        other.context = other.context.or(Some(ctx));
+        other
+    }
+}
+
+impl<'a> FromExternalError<&'a str, std::num::ParseIntError> for PError<&'a str> {
+    fn from_external_error(input: &'a str, _kind: ErrorKind, e: std::num::ParseIntError) -> Self {
+        PError {
+            input,
+            len: 0,
+            label: None,
+            help: None,
+            context: None,
+            kind: Some(Kind::ParseInt(e)),
+            related: None,
+            touched: false,
+        }
+    }
+}
+
+impl<'a> FromExternalError<&'a str, std::num::ParseFloatError> for PError<&'a str> {
+    fn from_external_error(
+        input: &'a str,
+        _kind: ErrorKind,
+        _e: std::num::ParseFloatError,
+    ) -> Self {
+        // kdl reports these as `ParseFloatError`s, but the float grammar only
+        // hands `f64::from_str` text it accepts.
+        PError::from_error_kind(input, ErrorKind::MapRes)
+    }
+}
+
+pub(crate) type PResult<'a, T> = IResult<&'a str, T, PError<&'a str>>;
+
+/// Runs `parser` over all of `full_input`, like `KdlParser::parse`.
+/// `fallback` is the kind for errors that didn't get a more specific one.
+pub(crate) fn parse_all<'a, T, P>(
+    full_input: &'a str,
+    parser: P,
+    fallback: impl FnOnce(&PError<&'a str>) -> (Kind, Option<&'static str>),
+) -> Result<T, SyntaxError>
+where
+    P: Parser<&'a str, T, PError<&'a str>>,
+{
+    all_consuming(parser)(full_input)
+        .finish()
+        .map(|(_, out)| out)
+        .map_err(|e| {
+            let span = |substr: &str| (full_input.offset(substr), substr.len());
+            let (fallback_kind, fallback_help) = fallback(&e);
+            SyntaxError {
+                source: Box::new(Source::Unnamed(full_input.to_owned())),
+                span: span(&e.input[..e.len]),
+                label: e.label,
+                related: e.related.as_ref().map(|r| (span(r.0), r.1)),
+                help: e.help.or(fallback_help),
+                kind: if let Some(kind) = e.kind {
+                    kind
+                } else if let Some(ctx) = e.context {
+                    Kind::Context(ctx)
+                } else {
+                    fallback_kind
+                },
+            }
+        })
+}
+
+/// Checks `input` as a KDL document, the way `KdlDocument::from_str` does.
+pub(crate) fn check_document(input: &str) -> Result<(), SyntaxError> {
+    parse_all(input, document(true), |_| (Kind::Other, None))
+}
+
+pub(crate) fn set_details<'a>(
+    mut err: nom::Err<PError<&'a str>>,
+    start: &'a str,
+    label: Option<&'static str>,
+    help: Option<&'static str>,
+) -> nom::Err<PError<&'a str>> {
+    match &mut err {
+        nom::Err::Error(e) | nom::Err::Failure(e) if !e.touched => {
+            e.len = start.offset(e.input);
+            e.input = start;
+            e.label = label;
+            e.help = help;
+            e.touched = true;
+        }
+        _ => {}
+    }
+    err
+}
+
+fn set_kind(mut err: nom::Err<PError<&str>>, kind: Kind) -> nom::Err<PError<&str>> {
+    match &mut err {
+        nom::Err::Error(e) | nom::Err::Failure(e) if e.kind.is_none() => {
+            e.kind = Some(kind);
+        }
+        _ => {}
+    }
+    err
+}
+
+/// What the query grammar needs to know about a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Value {
+    Int(i128),
+    String,
+    Other,
+}
+
+/// `top_level` stands in for kdl's check of whether the document starts at
+/// the start of the input, rather than inside a children block.
+fn document(top_level: bool) -> impl Fn(&str) -> PResult<'_, ()> {
+    move |input| {
+        let (input, _) = many0(node)(input)?;
+        let (input, _) = all_whitespace(input)?;
+        stray_terminator(top_level, input)?;
+        Ok((input, ()))
+    }
+}
+
+fn stray_terminator(top_level: bool, input: &str) -> Result<(), nom::Err<PError<&str>>> {
+    let (label, help, kind) = if input.starts_with(';') {
+        (
+            "extra `;`",
+            "Each node is terminated by a single `;`. Remove the extra one.",
+            Kind::InvalidNodeTerminator,
+        )
+    } else if top_level && input.starts_with('}') {
+        (
+            "unmatched `}`",
+            "There's no children block for this `}` to close.",
+            Kind::UnbalancedChildren,
+        )
+    } else {
+        return Ok(());
+    };
+    Err(nom::Err::Failure(PError {
+        input,
+        context: None,
+        len: 1,
+        label: Some(label),
+        help: Some(help),
+        kind: Some(kind),
+        related: None,
+        touched: true,
+    }))
+}
+
+fn node(input: &str) -> PResult<'_, ()> {
+    let (input, _) = all_whitespace(input)?;
+    let start = input;
+    let (input, _) = opt(context("valid node type annotation", annotation))(input)?;
+    let (input, _) = context("valid node name", identifier)(input)?;
+    let (input, _) = many0(context("valid node entry", entry))(input)?;
+    let (input, _) = opt(context("valid node children block", children))(input)?;
+    let (input, _) = context(
+        "valid node terminator",
+        cut(recognize(preceded(
+            many0(node_space),
+            alt((
+                terminated(recognize(tag(";")), opt(alt((linespace, newline, eof)))),
+                alt((newline, single_line_comment, eof)),
+            )),
+        ))),
+    )(input)
+    .map_err(|e| {
+        set_kind(
+            set_details(
+                e,
+                start,
+                Some("parsed node"),
+                Some("Nodes can only be terminated by `;` or a valid line ending."),
+            ),
+            Kind::InvalidNodeTerminator,
+        )
+    })?;
+    Ok((input, ()))
+}
+
+/// Returns the identifier's value, which the query grammar looks at.
+pub(crate) fn identifier(input: &str) -> PResult<'_, String> {
+    alt((quoted_identifier, plain_identifier))(input)
+}
+
+fn errant_plain_identifier(input: &str) -> PResult<'_, ()> {
+    let start = input;
+    let (_input, name) = recognize(plain_identifier)(input)?;
+    Err(nom::Err::Failure(PError {
+        input: start,
+        context: Some("a valid node entry"),
+        len: name.len(),
+        label: Some("plain identifiers can't be used here"),
+        help: Some("If this was supposed to be a string, wrap it in quotes.\nIf this was supposed to be a new node, terminate the previous node with `;` or a newline."),
+        kind: Some(Kind::BareIdentifier),
+        related: None,
+        touched: false,
+    }))
+}
+
+fn plain_identifier(input: &str) -> PResult<'_, String> {
+    let start = input;
+    let (input, name) = recognize(preceded(
+        take_while_m_n(1, 1, is_initial_char),
+        cut(take_while(is_identifier_char)),
+    ))(input).map_err(|e| set_kind(set_details(e, start, Some("invalid identifier character"), Some("See https://github.com/kdl-org/kdl/blob/main/SPEC.md#identifier for an explanation of valid KDL identifiers.")), Kind::InvalidIdentifierChar))?;
+    if !is_bare(name) && !matches!(name, "false" | "true" | "null") {
+        // A sign followed by a digit, which starts a number instead.
+        return Err(nom::Err::Error(PError {
+            input: start,
+            context: Some("non-numeric identifier"),
+            len: name.len(),
+            label: Some("invalid identifier"),
+            help: Some("Identifiers can't start with `+` or `-` followed by a digit. If this was supposed to be a string, wrap it in quotes."),
+            kind: Some(Kind::InvalidIdentifierChar),
+            related: None,
+            touched: false,
+        }));
+    }
+    if let "false" | "true" | "null" = name {
+        return Err(nom::Err::Error(PError {
+            input: start,
+            context: Some("non-keyword identifier"),
+            len: name.len(),
+            label: Some("reserved keyword"),
+            help: Some("Reserved keywords cannot be used as identifiers."),
+            kind: Some(Kind::ReservedKeyword),
+            related: None,
+            touched: false,
+        }));
+    }
+    Ok((input, name.to_owned()))
+}
+
+fn quoted_identifier(input: &str) -> PResult<'_, String> {
+    alt((string, raw_string))(input)
+}
+
+fn is_identifier_char(c: char) -> bool {
+    !((c as u32) < 0x20
+        || matches!(
+            c,
+            '\\' | '/'
+                | '('
+                | ')'
+                | '{'
+                | '}'
+                | '<'
+                | '>'
+                | ';'
+                | '['
+                | ']'
+                | '='
+                | ','
+                | '"'
+                // Newlines
+                | '\r'
+                | '\n'
+                | '\u{0085}'
+                | '\u{000C}'
+                | '\u{2028}'
+                | '\u{2029}'
+                // Whitespace
+                | ' '
+                | '\t'
+                | '\u{FEFF}'
+                | '\u{00A0}'
+                | '\u{1680}'
+                | '\u{2000}'
+                | '\u{2001}'
+                | '\u{2002}'
+                | '\u{2003}'
+                | '\u{2004}'
+                | '\u{2005}'
+                | '\u{2006}'
+                | '\u{2007}'
+                | '\u{2008}'
+                | '\u{2009}'
+                | '\u{200A}'
+                | '\u{202F}'
+                | '\u{205F}'
+                | '\u{3000}'
+        ))
+}
+
+fn is_initial_char(c: char) -> bool {
+    !c.is_ascii_digit() && is_identifier_char(c)
+}
+
+fn is_bare(value: &str) -> bool {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(c) if is_initial_char(c) => {}
+        _ => return false,
+    }
+    if value.starts_with(['+', '-']) && chars.next().map_or(false, |c| c.is_ascii_digit()) {
+        return false;
+    }
+    value.chars().all(is_identifier_char) && !matches!(value, "true" | "false" | "null")
+}
+
+fn entry(input: &str) -> PResult<'_, ()> {
+    let (input, _) = many1(node_space)(input)?;
+    alt((property, map(argument, |_| ()), errant_plain_identifier))(input)
+}
+
+fn entry_maybe_space(input: &str) -> PResult<'_, ()> {
+    let (input, _) = many0(node_space)(input)?;
+    alt((property, map(argument, |_| ()), errant_plain_identifier))(input)
+}
+
+fn property(input: &str) -> PResult<'_, ()> {
+    let (input, _) = identifier(input)?;
+    let (input, _) = context("'=' after property name", tag("="))(input)?;
+    let (input, _) = opt(annotation)(input)?;
+    let (input, _) = context("property value", cut(value))(input).map_err(|e| set_kind(set_details(e, input, Some("invalid value"), Some("Please refer to https://github.com/kdl-org/kdl/blob/main/SPEC.md#value for valid KDL value syntaxes.")), Kind::InvalidValue))?;
+    Ok((input, ()))
+}
+
+fn argument(input: &str) -> PResult<'_, Value> {
+    let (input, ty) = opt(annotation)(input)?;
+    if ty.is_some() {
+        context("valid value", cut(value))(input)
+    } else {
+        context("valid value", value)(input)
+    }
+}
+
+pub(crate) fn value(input: &str) -> PResult<'_, Value> {
+    alt((
+        map(null, |_| Value::Other),
+        map(boolean, |_| Value::Other),
+        map(string, |_| Value::String),
+        map(raw_string, |_| Value::Other),
+        map(hexadecimal, Value::Int),
+        map(octal, Value::Int),
+        map(binary, Value::Int),
+        map(float, |_| Value::Other),
+        map(integer, Value::Int),
+    ))(input)
+}
+
+fn children(input: &str) -> PResult<'_, ()> {
+    let (input, _) = many0(node_space)(input)?;
+    let start = input;
+    let (input, _) = tag("{")(input)?;
+    let (input, _) = document(false)(input)?;
+    let (input, _) =
+        cut(context("closing '}' in node children block", tag("}")))(input).map_err(|e| {
+            set_kind(
+                set_details(e, start, Some("children block body"), None),
+                Kind::UnbalancedChildren,
+            )
+        })?;
+    Ok((input, ()))
+}
+
+fn annotation(input: &str) -> PResult<'_, ()> {
+    let start = input;
+    let (input, _) = tag("(")(input)?;
+    let (input, _) = cut(identifier)(input)?;
+    let (input, _) = context("closing ')' for type annotation", cut(tag(")")))(input)
+        .map_err(|e| set_kind(set_details(e, start, Some("annotation"), Some("annotations can only be KDL identifiers (including string identifiers), and can't have any space inside the parentheses.")), Kind::InvalidAnnotation))?;
+    Ok((input, ()))
+}
+
+fn all_whitespace(input: &str) -> PResult<'_, &str> {
+    recognize(many0(alt((comment, unicode_space, newline))))(input)
+}
+
+fn whitespace(input: &str) -> PResult<'_, &str> {
+    recognize(alt((unicode_space, multi_line_comment)))(input)
+}
+
+fn linespace(input: &str) -> PResult<'_, &str> {
+    recognize(alt((unicode_space, newline, single_line_comment)))(input)
+}
+
+fn node_space(input: &str) -> PResult<'_, &str> {
+    context(
+        "node space",
+        recognize(alt((
+            delimited(many0(whitespace), escline, many0(whitespace)),
+            recognize(many1(whitespace)),
+            node_slashdash,
+        ))),
+    )(input)
+}
+
+fn escline(input: &str) -> PResult<'_, &str> {
+    recognize(preceded(
+        tag("\\"),
+        context(
+            "newline after line escape",
+            cut(preceded(
+                many0(whitespace),
+                alt((single_line_comment, newline)),
+            )),
+        ),
+    ))(input).map_err(|e| set_kind(set_details(e, input, Some("line escape starts here"), Some("line escapes can only be followed by whitespace plus a newline (or single-line comment).")), Kind::InvalidLineEscape))
+}
+
+pub(crate) fn unicode_space(input: &str) -> PResult<'_, &str> {
+    alt((
+        tag(" "),
+        tag("\t"),
+        tag("\u{FEFF}"), // BOM
+        tag("\u{00A0}"),
+        tag("\u{1680}"),
+        tag("\u{2000}"),
+        tag("\u{2001}"),
+        tag("\u{2002}"),
+        tag("\u{2003}"),
+        tag("\u{2004}"),
+        tag("\u{2005}"),
+        tag("\u{2006}"),
+        tag("\u{2007}"),
+        tag("\u{2008}"),
+        tag("\u{2009}"),
+        tag("\u{200A}"),
+        tag("\u{202F}"),
+        tag("\u{205F}"),
+        tag("\u{3000}"),
+    ))(input)
+}
+
+pub(crate) fn newline(input: &str) -> PResult<'_, &str> {
+    alt((
+        tag("\r\n"),
+        tag("\r"),
+        tag("\n"),
+        tag("\u{0085}"),
+        tag("\u{000C}"),
+        tag("\u{2028}"),
+        tag("\u{2029}"),
+    ))(input)
+}
+
+fn comment(input: &str) -> PResult<'_, &str> {
+    alt((single_line_comment, multi_line_comment, slashdash_comment))(input)
+}
+
+fn single_line_comment(input: &str) -> PResult<'_, &str> {
+    recognize(preceded(
+        tag("//"),
+        cut(many_till(
+            anychar,
+            context("newline or eof after //", alt((newline, eof))),
+        )),
+    ))(input)
+    .map_err(|e| set_details(e, input, Some("comment"), None))
+}
+
+fn multi_line_comment(input: &str) -> PResult<'_, &str> {
+    recognize(preceded(
+        tag("/*"),
+        context("comment block body", cut(commented_block)),
+    ))(input)
+    .map_err(|e| set_kind(set_details(e, input, Some("comment"), Some("multi-line comments must start with /* and be terminated with a matching */. They may be nested, but their */ must match.")), Kind::UnterminatedComment))
+}
+
+fn commented_block(input: &str) -> PResult<'_, &str> {
+    alt((
+        tag("*/"),
+        terminated(
+            alt((
+                multi_line_comment,
+                tag("*"),
+                tag("/"),
+                recognize(many_till(anychar, peek(alt((tag("*"), tag("/")))))),
+            )),
+            commented_block,
+        ),
+    ))(input)
+}
+
+fn node_slashdash(input: &str) -> PResult<'_, &str> {
+    recognize(preceded(
+        tag("/-"),
+        context(
+            "node following a slashdash",
+            cut(alt((recognize(entry_maybe_space), recognize(children)))),
+        ),
+    ))(input)
+    .map_err(|e| set_details(e, input, Some("slashdash"), None))
+}
+
+fn slashdash_comment(input: &str) -> PResult<'_, &str> {
+    recognize(preceded(tag("/-"), cut(node)))(input)
+        .map_err(|e| set_details(e, input, Some("slashdash"), None))
+}
+
+fn boolean(input: &str) -> PResult<'_, &str> {
+    alt((tag("true"), tag("false")))(input)
+}
+
+fn null(input: &str) -> PResult<'_, &str> {
+    tag("null")(input)
+}
+
+fn string(input: &str) -> PResult<'_, String> {
+    let start = input;
+    let (input, _) = tag("\"")(input)?;
+    let (input, chars) = many0(character)(input)?;
+    let (input, _) =
+        cut(tag("\""))(input).map_err(|e| unterminated_string(e, start, &start[1..], 1))?;
+    Ok((input, chars.into_iter().map(|(_, c)| c).collect()))
+}
+
+fn unterminated_string<'a>(
+    err: nom::Err<PError<&'a str>>,
+    start: &'a str,
+    body: &'a str,
+    open_len: usize,
+) -> nom::Err<PError<&'a str>> {
+    let related = match (body.find('\n'), body.char_indices().last()) {
+        (Some(idx), _) => Some((&body[idx..idx + 1], "string continues past this line")),
+        (None, Some((idx, _))) => Some((&body[idx..], "input ends here")),
+        (None, None) => None,
+    };
+    let mut err = set_kind(
+        set_details(
+            err,
+            start,
+            Some("string starts here"),
+            Some("Add a closing quote to end the string."),
+        ),
+        Kind::UnterminatedString,
+    );
+    if let nom::Err::Error(e) | nom::Err::Failure(e) = &mut err {
+        if e.kind == Some(Kind::UnterminatedString) && e.related.is_none() {
+            e.len = open_len;
+            e.related = related.map(Box::new);
+        }
+    }
+    err
+}
+
+fn character(input: &str) -> PResult<'_, (&str, char)> {
+    with_raw(alt((preceded(char('\\'), cut(escape)), none_of("\\\""))))(input).map_err(
+        |e| match e {
+            nom::Err::Failure(_) => {
+                let mut e = set_details(
+                    e,
+                    input,
+                    Some("invalid escape"),
+                    Some("Valid escapes are \\n, \\r, \\t, \\\\, \\/, \\\", \\b, \\f, and \\u{XXXX}."),
+                );
+                if let nom::Err::Failure(e) = &mut e {
+                    e.len = 1 + bad_escape_len(&input[1..]);
+                }
+                e
+            }
+            e => e,
+        },
+    )
+}
+
+fn bad_escape_len(input: &str) -> usize {
+    if let Some(rest) = input.strip_prefix("u{") {
+        let end = rest
+            .find(|c: char| c == '}' || c == '"' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let close = usize::from(rest[end..].starts_with('}'));
+        "u{".len() + end + close
+    } else {
+        input.chars().next().map(char::len_utf8).unwrap_or(0)
+    }
+}
+
+fn with_raw<I: Clone + Offset + Slice<RangeTo<usize>>, O, E: ParseError<I>, F>(
+    mut parser: F,
+) -> impl FnMut(I) -> IResult<I, (I, O), E>
+where
+    F: Parser<I, O, E>,
+{
+    move |input: I| {
+        let i = input.clone();
+        match parser.parse(i) {
+            Ok((i, x)) => {
+                let index = input.offset(&i);
+                Ok((i, (input.slice(..index), x)))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn escape(input: &str) -> PResult<'_, char> {
+    alt((
+        delimited(tag("u{"), cut(unicode), char('}')),
+        map_opt(anychar, |c| match c {
+            '"' => Some('"'),
+            '\\' => Some('\\'),
+            '/' => Some('/'),
+            'b' => Some('\u{08}'),
+            'f' => Some('\u{0C}'),
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            _ => None,
+        }),
+    ))(input)
+    .map_err(|e| set_kind(e, Kind::InvalidEscape))
+}
+
+fn unicode(input: &str) -> PResult<'_, char> {
+    map_opt(
+        map_res(
+            take_while_m_n(1, 6, |c: char| c.is_ascii_hexdigit()),
+            |hex| u32::from_str_radix(hex, 16),
+        ),
+        std::char::from_u32,
+    )(input)
+}
+
+fn raw_string(input: &str) -> PResult<'_, String> {
+    let start = input;
+    let (input, _) = char('r')(input)?;
+    let (input, hashes) = recognize(many0(char('#')))(input)?;
+    let (input, _) = char('"')(input)?;
+    let close = format!("\"{}", hashes);
+    let (input, value) = cut(take_until(&close[..]))(input)
+        .map_err(|e| unterminated_string(e, start, input, hashes.len() + 2))?;
+    let (input, _) = cut(tag(&close[..]))(input)?;
+    Ok((input, value.to_owned()))
+}
+
+fn float(input: &str) -> PResult<'_, f64> {
+    map_res(
+        alt((
+            recognize(tuple((
+                integer_digits,
+                opt(preceded(char('.'), cut(integer_digits))),
+                one_of("eE"),
+                opt(one_of("+-")),
+                cut(integer_digits),
+            ))),
+            recognize(tuple((integer_digits, char('.'), cut(integer_digits)))),
+        )),
+        |x: &str| str::replace(x, "_", "").parse::<f64>(),
+    )(input)
+    .map_err(|e| {
+        set_kind(
+            set_details(
+                e,
+                input,
+                Some("invalid float"),
+                Some(
+                    "Floating point numbers must be base 10, and have numbers after the decimal point.",
+                ),
+            ),
+            Kind::InvalidNumber,
+        )
+    })
+}
+
+fn integer(input: &str) -> PResult<'_, i128> {
+    let (input, sign) = sign(input)?;
+    map_res(
+        recognize(many1(terminated(one_of("0123456789"), many0(char('_'))))),
+        move |out| int_value(sign, out, 10),
+    )(input)
+}
+
+fn integer_digits(input: &str) -> PResult<'_, &str> {
+    recognize(tuple((
+        sign,
+        many1(terminated(one_of("0123456789"), many0(char('_')))),
+    )))(input)
+}
+
+fn hexadecimal(input: &str) -> PResult<'_, i128> {
+    let (input, sign) = sign(input)?;
+    map_res(
+        preceded(
+            alt((tag("0x"), tag("0X"))),
+            context(
+                "hexadecimal value",
+                cut(recognize(many1(terminated(
+                    one_of("0123456789abcdefABCDEF"),
+                    many0(char('_')),
+                )))),
+            ),
+        ),
+        move |hex| int_value(sign, hex, 16),
+    )(input)
+    .map_err(|e| set_kind(set_details(e, input, Some("invalid hexadecimal"), Some("Hexadecimal values can only include the characters 0-9 and a-f (case-insensitive), with optional `_` separators.")), Kind::InvalidNumber))
+}
+
+fn octal(input: &str) -> PResult<'_, i128> {
+    let (input, sign) = sign(input)?;
+    map_res(
+        preceded(
+            alt((tag("0o"), tag("0O"))),
+            context(
+                "octal value",
+                cut(recognize(many1(terminated(
+                    one_of("01234567"),
+                    many0(char('_')),
+                )))),
+            ),
+        ),
+        move |oct| int_value(sign, oct, 8),
+    )(input)
+    .map_err(|e| {
+        set_kind(
+            set_details(
+                e,
+                input,
+                Some("invalid octal"),
+                Some("octal values can only include the characters 0-7, with optional `_` separators."),
+            ),
+            Kind::InvalidNumber,
+        )
+    })
+}
+
+fn binary(input: &str) -> PResult<'_, i128> {
+    let (input, sign) = sign(input)?;
+    map_res(
+        preceded(
+            alt((tag("0b"), tag("0B"))),
+            context(
+                "binary value",
+                cut(recognize(many1(terminated(one_of("01"), many0(char('_')))))),
+            ),
+        ),
+        move |binary| int_value(sign, binary, 2),
+    )(input)
+    .map_err(|e| set_kind(set_details(e, input, Some("invalid binary"), Some("Hexadecimal values can only include the characters 0 and 1, with optional `_` separators.")), Kind::InvalidNumber))
+}
+
+/// Like kdl's `int_value` with `IntegerOverflow::Error`, the default.
+fn int_value(sign: i128, digits: &str, radix: u32) -> Result<i128, std::num::ParseIntError> {
+    let mut digits = str::replace(digits, "_", "");
+    if sign < 0 {
+        digits.insert(0, '-');
+    }
+    i128::from_str_radix(&digits, radix)
+}
+
+fn sign(input: &str) -> PResult<'_, i128> {
+    let (input, sign) = opt(alt((char('+'), char('-'))))(input)?;
+    Ok((input, if sign == Some('-') { -1 } else { 1 }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Checks `input` against kdl's own parser: both have to either accept
+    /// it, or reject it with the same error in the same place.
+    fn same_as_kdl(input: &str) {
+        let ours = check_document(input);
+        let theirs = input.parse::<kdl::KdlDocument>();
+        match (ours, theirs) {
+            (Ok(()), Ok(_)) => {}
+            (Err(ours), Err(theirs)) => {
+                let span = (theirs.span.offset(), theirs.span.len());
+                assert_eq!(ours.kind.code(), theirs.kind.code_str(), "{:?}", input);
+                assert_eq!(ours.to_string(), theirs.to_string(), "{:?}", input);
+                assert_eq!(ours.span(), span, "{:?}", input);
+                assert_eq!(ours.help, theirs.help, "{:?}", input);
+            }
+            (ours, theirs) => panic!(
+                "{:?}: kdl-macros says {:?}, kdl says {:?}",
+                input,
+                ours.map_err(|e| e.to_string()),
+                theirs.map(|_| ()).map_err(|e| e.to_string())
+            ),
+        }
+    }
+
+    /// The compliance suite, plus every prefix of each test and each test
+    /// with one character left out, which covers most ways of getting
+    /// things wrong.
+    #[test]
+    fn matches_kdl() {
+        let dir =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../tests/test_cases/input");
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let input = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            same_as_kdl(&input);
+            for (idx, c) in input.char_indices() {
+                same_as_kdl(&input[..idx]);
+                same_as_kdl(&format!(
+                    "{}{}",
+                    &input[..idx],
+                    &input[idx + c.len_utf8()..]
+                ));
+            }
+            count += 1;
+        }
+        assert!(count > 100);
+    }
+
+    #[test]
+    fn edge_cases() {
+        for input in [
+            "node 170141183460469231731687303715884105727",
+            "node 170141183460469231731687303715884105728",
+            "node -170141183460469231731687303715884105728",
+            "node 0x_1",
+            "node 1e400",
+            "node \"\\u{110000}\"",
+            "node \"\\u{}\"",
+            "node \"\\q\"",
+            "node r##\"a\"#\"##",
+            "node r#\"a\"",
+            "node /* unclosed",
+            "node \\ x",
+            "+1 2",
+            "-a 2",
+            "true 1",
+            "a b",
+            "a (b)c=1",
+            "a c=(b)",
+            "a (b",
+            "a {",
+            "a }",
+            "a; ;",
+            "a;;",
+            "a { b;; }",
+            "a\n}",
+            "a\u{2028}b\u{0085}c",
+            "\u{FEFF}a",
+        ] {
+            same_as_kdl(input);
+        }
+    }
+
+    #[test]
+    fn source_name() {
+        let err = check_document("a b")
+            .unwrap_err()
+            .with_source_name("app.kdl");
+        let mut rendered = String::new();
+        miette::GraphicalReportHandler::new_themed(miette::GraphicalTheme::unicode_nocolor())
+            .render_report(&mut rendered, &err)
+            .unwrap();
+        assert!(rendered.contains("[app.kdl:1:1]"), "{}", rendered);
+    }
+}
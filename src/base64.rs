@@ -0,0 +1,124 @@
+use crate::KdlEntry;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl KdlEntry {
+    /// Decodes this entry's value as base64 (the standard alphabet from [RFC
+    /// 4648](https://www.rfc-editor.org/rfc/rfc4648#section-4)), if it's a
+    /// string annotated with `(base64)`. Trailing `=` padding is optional.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let node: KdlNode = r#"key secret=(base64)"aGVsbG8=""#.parse().unwrap();
+    /// let secret = node.entry("secret").unwrap().as_bytes();
+    /// assert_eq!(secret.as_deref(), Some(&b"hello"[..]));
+    /// ```
+    pub fn as_bytes(&self) -> Option<Vec<u8>> {
+        decode(self.annotated_string("base64")?)
+    }
+
+    /// Creates a new argument holding `bytes` encoded as padded base64 and
+    /// annotated with `(base64)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlEntry;
+    /// let entry = KdlEntry::from_bytes(b"hello");
+    /// assert_eq!(entry.to_string(), r#"(base64)"aGVsbG8=""#);
+    /// ```
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Self {
+        let mut entry = KdlEntry::new(encode(bytes.as_ref()));
+        entry.set_ty("base64");
+        entry
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, b)| acc | u32::from(*b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn decode(input: &str) -> Option<Vec<u8>> {
+    let data = input.trim_end_matches('=');
+    if input.len() - data.len() > 2 || (input.len() != data.len() && input.len() % 4 != 0) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut group = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let sextet = ALPHABET.iter().position(|a| a == c)? as u32;
+            group |= sextet << (18 - 6 * i);
+        }
+        let len = chunk.len() - 1;
+        // Leftover bits in the last character must be zero, so that every
+        // byte string has exactly one encoding.
+        if group & (0xff_ffff >> (8 * len)) != 0 {
+            return None;
+        }
+        out.extend((0..len).map(|i| (group >> (16 - 8 * i)) as u8));
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        for (bytes, encoded) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (b"foobar", "Zm9vYmFy"),
+            (&[0xfb, 0xff, 0xbf], "+/+/"),
+        ] {
+            assert_eq!(encode(bytes), encoded);
+            assert_eq!(decode(encoded).as_deref(), Some(bytes), "{}", encoded);
+        }
+    }
+
+    #[test]
+    fn decoding() {
+        assert_eq!(decode("Zm8").as_deref(), Some(&b"fo"[..]));
+        assert_eq!(decode("Zg").as_deref(), Some(&b"f"[..]));
+        for bad in [
+            "Z", "Zg=", "Zg===", "Zm9v=", "Zh==", "Zm9=", "Zm 9v", "Zm9v\n", "Zm-v", "=Zm9",
+        ] {
+            assert_eq!(decode(bad), None, "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn entries() {
+        let entry = KdlEntry::from_bytes([0u8, 1, 2, 254, 255]);
+        let reparsed: KdlEntry = entry.to_string().parse().unwrap();
+        assert_eq!(reparsed.as_bytes(), Some(vec![0, 1, 2, 254, 255]));
+
+        let entry: KdlEntry = r#""aGVsbG8=""#.parse().unwrap();
+        assert_eq!(entry.as_bytes(), None);
+    }
+}
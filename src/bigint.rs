@@ -0,0 +1,125 @@
+use num_bigint::BigInt;
+
+use crate::KdlValue;
+
+/// Integration with [`num_bigint`], for integers of any size. Requires the
+/// `bigint` feature.
+impl KdlValue {
+    /// Returns `Some(BigInt)` if the `KdlValue` is any kind of integer,
+    /// including a [`KdlValue::BigInt`], otherwise returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{IntegerOverflow, KdlDocument, ParseOptions};
+    /// let input = "key 0x1_0000_0000_0000_0000_0000_0000_0000_0000";
+    /// assert!(KdlDocument::parse_with_options(input, &ParseOptions::default()).is_err());
+    ///
+    /// let options = ParseOptions {
+    ///     integer_overflow: IntegerOverflow::BigInt,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let doc = KdlDocument::parse_with_options(input, &options).unwrap();
+    /// let key = doc.get_arg("key").unwrap().as_bigint().unwrap();
+    /// assert_eq!(key, num_bigint::BigInt::from(1) << 128);
+    /// ```
+    pub fn as_bigint(&self) -> Option<BigInt> {
+        match self {
+            KdlValue::BigInt(int) => Some(int.clone()),
+            _ => self.as_i128().map(BigInt::from),
+        }
+    }
+
+    /// Returns `true` if the value is a [`KdlValue::BigInt`].
+    pub fn is_bigint(&self) -> bool {
+        matches!(self, KdlValue::BigInt(..))
+    }
+}
+
+/// Creates a [`KdlValue::Base10`] if `int` fits in an `i128`, and a
+/// [`KdlValue::BigInt`] otherwise.
+impl From<BigInt> for KdlValue {
+    fn from(int: BigInt) -> Self {
+        match i128::try_from(&int) {
+            Ok(int) => KdlValue::Base10(int),
+            Err(_) => KdlValue::BigInt(int),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{IntegerOverflow, KdlDocument, ParseOptions};
+
+    fn parse(input: &str) -> Result<KdlDocument, crate::KdlError> {
+        let options = ParseOptions {
+            integer_overflow: IntegerOverflow::BigInt,
+            ..ParseOptions::default()
+        };
+        KdlDocument::parse_with_options(input, &options)
+    }
+
+    #[test]
+    fn parsing() {
+        let big: BigInt = "-340282366920938463463374607431768211457".parse().unwrap();
+        let input = format!("n {} 0b{} 0o7 12", big, "1".repeat(200));
+        let doc = parse(&input).unwrap();
+        let args: Vec<_> = doc.nodes()[0].entries().iter().map(|e| e.value()).collect();
+        assert_eq!(args[0], &KdlValue::BigInt(big));
+        assert_eq!(args[1].as_bigint(), Some((BigInt::from(1) << 200) - 1));
+        assert_eq!(args[2], &KdlValue::Base8(7));
+        assert_eq!(args[3].as_bigint(), Some(BigInt::from(12)));
+        assert_eq!(doc.to_string(), input);
+
+        // Only overflow is let through; other errors are still errors.
+        assert!(parse("n 0x").is_err());
+        assert!("n 340282366920938463463374607431768211456"
+            .parse::<KdlDocument>()
+            .is_err());
+    }
+
+    #[test]
+    fn conversions() {
+        let big = BigInt::from(u128::MAX);
+        let value = KdlValue::from(big.clone());
+        assert!(value.is_bigint());
+        assert_eq!(value.to_string(), big.to_string());
+        assert_eq!(value.as_i128(), None);
+        assert_eq!(KdlValue::from(BigInt::from(-5)), KdlValue::Base10(-5));
+        assert_eq!(KdlValue::Bool(true).as_bigint(), None);
+    }
+
+    #[test]
+    fn ordering() {
+        let huge: BigInt = BigInt::from(1) << 200;
+        let values = [
+            KdlValue::Base10Float(-1e300),
+            KdlValue::BigInt(-huge.clone()),
+            KdlValue::Base10(-7),
+            KdlValue::Base10(5),
+            KdlValue::BigInt(BigInt::from(5)),
+            KdlValue::Base10(6),
+            KdlValue::Base10Float(10.0),
+            KdlValue::BigInt(BigInt::from(10)),
+            KdlValue::Base10(i128::MAX),
+            KdlValue::BigInt(huge.clone()),
+            KdlValue::Base10Float(1e300),
+        ];
+        for a in &values {
+            for b in &values {
+                assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{:?} vs {:?}", a, b);
+                for c in &values {
+                    if a <= b && b <= c {
+                        assert!(a <= c, "{:?} <= {:?} <= {:?}", a, b, c);
+                    }
+                }
+            }
+        }
+        let mut sorted = values.to_vec();
+        sorted.reverse();
+        sorted.sort();
+        assert_eq!(sorted, values);
+        assert!(KdlValue::BigInt(BigInt::from(5)).same_value(&KdlValue::Base10(5)));
+    }
+}
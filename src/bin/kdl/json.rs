@@ -0,0 +1,101 @@
+//! Writes documents as JSON.
+//!
+//! Each node becomes an object with its `name`, type annotation as `type`
+//! (or `null`), `arguments`, `properties`, and `children`, so a document is
+//! an array of those. When a property appears more than once, the last one
+//! wins, as it does in KDL. Type annotations on values aren't kept, and NaN
+//! and infinite floats are written as `null`.
+
+use std::fmt::Write;
+
+use kdl::{KdlDocument, KdlNode, KdlValue};
+
+pub(crate) fn document(doc: &KdlDocument) -> String {
+    let mut out = String::new();
+    nodes(&mut out, doc);
+    out.push('\n');
+    out
+}
+
+fn nodes(out: &mut String, doc: &KdlDocument) {
+    out.push('[');
+    for (i, node) in doc.nodes().iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        self::node(out, node);
+    }
+    out.push(']');
+}
+
+fn node(out: &mut String, node: &KdlNode) {
+    out.push_str("{\"name\":");
+    string(out, node.name().value());
+    out.push_str(",\"type\":");
+    match node.ty() {
+        Some(ty) => string(out, ty.value()),
+        None => out.push_str("null"),
+    }
+
+    out.push_str(",\"arguments\":[");
+    for (i, arg) in node.args().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        value(out, arg);
+    }
+
+    let mut props: Vec<(&str, &KdlValue)> = Vec::new();
+    for (key, val) in node.props() {
+        match props.iter_mut().find(|(k, _)| *k == key) {
+            Some(prop) => prop.1 = val,
+            None => props.push((key, val)),
+        }
+    }
+    out.push_str("],\"properties\":{");
+    for (i, (key, val)) in props.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        string(out, key);
+        out.push(':');
+        value(out, val);
+    }
+
+    out.push_str("},\"children\":");
+    match node.children() {
+        Some(children) => nodes(out, children),
+        None => out.push_str("[]"),
+    }
+    out.push('}');
+}
+
+fn value(out: &mut String, value: &KdlValue) {
+    match value {
+        KdlValue::String(s) | KdlValue::RawString(s) => string(out, s),
+        KdlValue::Base2(i) | KdlValue::Base8(i) | KdlValue::Base10(i) | KdlValue::Base16(i) => {
+            write!(out, "{}", i).unwrap()
+        }
+        #[cfg(feature = "bigint")]
+        KdlValue::BigInt(i) => write!(out, "{}", i).unwrap(),
+        KdlValue::Base10Float(f) if f.is_finite() => write!(out, "{:?}", f).unwrap(),
+        KdlValue::Bool(b) => write!(out, "{}", b).unwrap(),
+        _ => out.push_str("null"),
+    }
+}
+
+fn string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
@@ -0,0 +1,213 @@
+//! The `kdl` command-line tool. Requires the `cli` feature.
+
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+use kdl::{EmitOptions, KdlDocument, KdlSource, KdlVersion};
+
+mod json;
+mod schema;
+
+const USAGE: &str = "\
+Usage: kdl <COMMAND> [OPTIONS] [FILE]
+
+Commands:
+  fmt [--check]                 Print FILE, formatted. With --check, print
+                                nothing and fail if it isn't formatted.
+  query <QUERY>                 Print the nodes in FILE that match a KQL query.
+  validate [--schema <SCHEMA>]  Check that FILE is valid KDL, and that it
+                                matches a KDL Schema.
+  convert --to <FORMAT>         Print FILE as `json`, `v1` (KDL 1.0), or `v2`
+                                (KDL 2.0).
+
+FILE is read from standard input if it's left out or is `-`. Either version
+of KDL is accepted, going by a `/- kdl-version` marker when there is one.
+";
+
+/// A failure to report, with the code to exit with.
+struct Failure(i32, miette::Report);
+
+type CliResult = Result<(), Failure>;
+
+fn fail(code: i32, message: impl std::fmt::Display) -> Failure {
+    Failure(code, miette::miette!("{}", message))
+}
+
+fn usage(message: impl std::fmt::Display) -> Failure {
+    fail(2, format!("{}\n\n{}", message, USAGE))
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(Failure(code, report)) = run(args) {
+        eprintln!("{:?}", report);
+        std::process::exit(code);
+    }
+}
+
+fn run(args: Vec<String>) -> CliResult {
+    let mut args = args.into_iter();
+    let command = match args.next() {
+        Some(command) => command,
+        None => return Err(usage("missing command")),
+    };
+    let mut positional = Vec::new();
+    let mut check = false;
+    let mut schema = None;
+    let mut to = None;
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--check" if command == "fmt" => check = true,
+            "--schema" if command == "validate" => schema = Some(value(&arg, args.next())?),
+            "--to" if command == "convert" => to = Some(value(&arg, args.next())?),
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                return Ok(());
+            }
+            _ if arg.starts_with("--") => return Err(usage(format!("unknown option `{}`", arg))),
+            _ => positional.push(arg),
+        }
+    }
+    match &command[..] {
+        "fmt" => fmt(file(positional)?, check),
+        "query" => {
+            if positional.is_empty() {
+                return Err(usage("missing query"));
+            }
+            let query = positional.remove(0);
+            query_cmd(&query, file(positional)?)
+        }
+        "validate" => validate(file(positional)?, schema),
+        "convert" => match to {
+            Some(to) => convert(file(positional)?, &to),
+            None => Err(usage("missing `--to <FORMAT>`")),
+        },
+        "-h" | "--help" | "help" => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(usage(format!("unknown command `{}`", command))),
+    }
+}
+
+fn value(option: &str, value: Option<String>) -> Result<String, Failure> {
+    value.ok_or_else(|| usage(format!("`{}` needs a value", option)))
+}
+
+/// The file named by the only `positional` argument, or `-`.
+fn file(positional: Vec<String>) -> Result<String, Failure> {
+    let mut files = positional.into_iter();
+    let file = files.next().unwrap_or_else(|| "-".into());
+    match files.next() {
+        Some(extra) => Err(usage(format!("unexpected argument `{}`", extra))),
+        None => Ok(file),
+    }
+}
+
+/// Reads `path`, or standard input for `-`.
+fn read(path: &str) -> Result<KdlSource, Failure> {
+    let mut bytes = Vec::new();
+    let result = if path == "-" {
+        std::io::stdin().read_to_end(&mut bytes).map(|_| ())
+    } else {
+        std::fs::File::open(path).and_then(|mut f| f.read_to_end(&mut bytes).map(|_| ()))
+    };
+    result.map_err(|e| fail(1, format!("couldn't read {}: {}", path, e)))?;
+    let text = String::from_utf8(bytes)
+        .map_err(|_| fail(1, format!("couldn't read {}: it isn't valid UTF-8", path)))?;
+    let name = if path == "-" { "<stdin>" } else { path };
+    Ok(KdlSource::named(name, text))
+}
+
+fn parse(source: &KdlSource) -> Result<KdlDocument, Failure> {
+    KdlDocument::parse_auto(&source.text).map_err(|err| {
+        let err = match &source.name {
+            Some(name) => err.with_source_name(name.clone()),
+            None => err,
+        };
+        Failure(1, err.into())
+    })
+}
+
+/// Writes `doc` in `version`.
+fn emit(doc: &KdlDocument, version: KdlVersion) -> Result<String, Failure> {
+    let options = EmitOptions {
+        version: Some(version),
+        ..EmitOptions::default()
+    };
+    doc.to_string_with_options(&options)
+        .map_err(|err| Failure(1, err.into()))
+}
+
+fn print(text: &str) -> CliResult {
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    stdout
+        .write_all(text.as_bytes())
+        .and_then(|_| stdout.flush())
+        .map_err(|e| fail(1, format!("couldn't write output: {}", e)))
+}
+
+fn fmt(path: String, check: bool) -> CliResult {
+    let source = read(&path)?;
+    let mut doc = parse(&source)?;
+    doc.fmt();
+    let formatted = emit(&doc, doc.version())?;
+    if !check {
+        return print(&formatted);
+    }
+    if formatted != source.text {
+        return Err(fail(1, format!("{} isn't formatted", path)));
+    }
+    Ok(())
+}
+
+fn query_cmd(query: &str, path: String) -> CliResult {
+    let source = read(&path)?;
+    let doc = parse(&source)?;
+    let found = doc.query_all(query).map_err(|err| Failure(2, err.into()))?;
+    let mut results = KdlDocument::new();
+    results.nodes_mut().extend(found.cloned());
+    results.fmt();
+    print(&emit(&results, doc.version())?)
+}
+
+fn validate(path: String, schema: Option<String>) -> CliResult {
+    let source = read(&path)?;
+    let doc = parse(&source)?;
+    let schema_source = match schema {
+        Some(schema) => read(&schema)?,
+        None => return Ok(()),
+    };
+    let schema_doc = parse(&schema_source)?;
+    let schema = schema::Schema::new(&schema_doc).map_err(|e| {
+        fail(
+            2,
+            format!("{}: {}", schema_source.name.unwrap_or_default(), e),
+        )
+    })?;
+    let errors = schema.validate(&doc, Arc::new(source));
+    let count = errors.len();
+    for error in errors {
+        eprintln!("{:?}", miette::Report::new(error));
+    }
+    match count {
+        0 => Ok(()),
+        1 => Err(fail(1, format!("{} doesn't match the schema", path))),
+        n => Err(fail(
+            1,
+            format!("{} doesn't match the schema in {} places", path, n),
+        )),
+    }
+}
+
+fn convert(path: String, to: &str) -> CliResult {
+    let source = read(&path)?;
+    let doc = parse(&source)?;
+    match to {
+        "json" => print(&json::document(&doc)),
+        "v1" | "1" => print(&emit(&doc, KdlVersion::V1)?),
+        "v2" | "2" => print(&emit(&doc, KdlVersion::V2)?),
+        _ => Err(usage(format!("unknown format `{}`", to))),
+    }
+}
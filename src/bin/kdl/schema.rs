@@ -0,0 +1,254 @@
+//! Checks documents against a [KDL Schema](https://github.com/kdl-org/kdl/blob/main/SCHEMA-SPEC.md).
+//!
+//! Only part of the schema language is understood: which nodes may appear
+//! where and how many times (`node`, `min`, `max`, `other-nodes-allowed`),
+//! their values and properties (`value`, `prop`, `required`,
+//! `other-props-allowed`), and the `type` and `enum` of those. `ref`s are
+//! looked up as KQL queries against the schema. Anything else in the schema
+//! is ignored.
+
+use std::sync::Arc;
+
+use kdl::{KdlDocument, KdlNode, KdlSource, KdlValue};
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+/// A way the document doesn't match the schema.
+#[derive(Debug, Diagnostic, Error)]
+#[error("{message}")]
+#[diagnostic(code(kdl::schema))]
+pub(crate) struct SchemaError {
+    #[source_code]
+    input: Arc<KdlSource>,
+
+    #[label("here")]
+    span: SourceSpan,
+
+    message: String,
+}
+
+pub(crate) struct Schema<'a> {
+    doc: &'a KdlDocument,
+}
+
+impl<'a> Schema<'a> {
+    /// Reads `doc` as a schema, which must have a top-level `document` node.
+    pub(crate) fn new(doc: &'a KdlDocument) -> Result<Self, String> {
+        if doc.get("document").is_none() {
+            return Err("schema has no top-level `document` node".into());
+        }
+        Ok(Schema { doc })
+    }
+
+    /// Returns every way `doc` doesn't match the schema. `input` is the
+    /// source `doc` was parsed from.
+    pub(crate) fn validate(&self, doc: &KdlDocument, input: Arc<KdlSource>) -> Vec<SchemaError> {
+        let mut checker = Checker {
+            schema: self.doc,
+            input,
+            errors: Vec::new(),
+        };
+        let rules = self.doc.get("document").unwrap();
+        checker.children(doc, &[rules], doc.span());
+        checker.errors
+    }
+}
+
+struct Checker<'a> {
+    schema: &'a KdlDocument,
+    input: Arc<KdlSource>,
+    errors: Vec<SchemaError>,
+}
+
+impl<'a> Checker<'a> {
+    fn error(&mut self, span: SourceSpan, message: String) {
+        self.errors.push(SchemaError {
+            input: self.input.clone(),
+            span,
+            message,
+        });
+    }
+
+    /// Follows `rule`'s `ref`, if it has one.
+    fn resolve(&self, rule: &'a KdlNode) -> &'a KdlNode {
+        let mut rule = rule;
+        // A limit, in case refs point at each other.
+        for _ in 0..32 {
+            let target = match rule.get("ref").and_then(|r| r.as_string()) {
+                Some(query) => self.schema.query(query).ok().flatten(),
+                None => None,
+            };
+            match target {
+                Some(target) if !std::ptr::eq(target, rule) => rule = target,
+                _ => break,
+            }
+        }
+        rule
+    }
+
+    /// Rules of `name` inside `rules`, with their refs followed.
+    fn rules(&self, rules: Option<&'a KdlDocument>, name: &'a str) -> Vec<&'a KdlNode> {
+        rules
+            .into_iter()
+            .flat_map(|rules| rules.nodes())
+            .filter(|rule| rule.name().value() == name)
+            .map(|rule| self.resolve(rule))
+            .collect()
+    }
+
+    /// Checks `nodes` against every `node` rule in `blocks`, the `children`
+    /// rules that apply to them.
+    fn children(&mut self, nodes: &KdlDocument, blocks: &[&'a KdlNode], span: SourceSpan) {
+        let node_rules: Vec<_> = blocks
+            .iter()
+            .flat_map(|block| self.rules(block.children(), "node"))
+            .collect();
+        for rule in &node_rules {
+            let name = rule.get(0).and_then(|n| n.as_string());
+            let rules = rule.children();
+            let matching: Vec<_> = nodes
+                .nodes()
+                .iter()
+                .filter(|node| name.map_or(true, |name| node.name().value() == name))
+                .collect();
+            if let Some(name) = name {
+                let what = format!("`{}` nodes", name);
+                self.count(span, &what, matching.len(), rules);
+            }
+            for node in matching {
+                self.node(node, rules);
+            }
+        }
+        if !blocks
+            .iter()
+            .any(|block| flag(block.children(), "other-nodes-allowed"))
+        {
+            let known: Vec<_> = node_rules
+                .iter()
+                .map(|rule| rule.get(0).and_then(|n| n.as_string()))
+                .collect();
+            if known.contains(&None) {
+                return;
+            }
+            for node in nodes.nodes() {
+                if !known.contains(&Some(node.name().value())) {
+                    let message = format!("unexpected node `{}`", node.name().value());
+                    self.error(node.name().span(), message);
+                }
+            }
+        }
+    }
+
+    fn node(&mut self, node: &KdlNode, rules: Option<&'a KdlDocument>) {
+        let args: Vec<_> = node.args().collect();
+        for rule in self.rules(rules, "value") {
+            let rules = rule.children();
+            self.count(node.name().span(), "values", args.len(), rules);
+            for entry in node.entries().iter().filter(|e| e.name().is_none()) {
+                self.value(entry.value(), entry.span(), rules);
+            }
+        }
+
+        let prop_rules = self.rules(rules, "prop");
+        for rule in &prop_rules {
+            let key = match rule.get(0).and_then(|k| k.as_string()) {
+                Some(key) => key,
+                None => continue,
+            };
+            match node.entry(key) {
+                Some(entry) => self.value(entry.value(), entry.span(), rule.children()),
+                None if flag(rule.children(), "required") => {
+                    let message = format!("missing required property `{}`", key);
+                    self.error(node.name().span(), message);
+                }
+                None => {}
+            }
+        }
+        if !flag(rules, "other-props-allowed") {
+            let known: Vec<_> = prop_rules
+                .iter()
+                .filter_map(|rule| rule.get(0).and_then(|k| k.as_string()))
+                .collect();
+            for entry in node.entries() {
+                if let Some(key) = entry.name() {
+                    if !known.contains(&key.value()) {
+                        let message = format!("unexpected property `{}`", key.value());
+                        self.error(entry.span(), message);
+                    }
+                }
+            }
+        }
+
+        let empty = KdlDocument::new();
+        let children = node.children().unwrap_or(&empty);
+        let blocks = self.rules(rules, "children");
+        if !blocks.is_empty() {
+            self.children(children, &blocks, node.name().span());
+        }
+    }
+
+    fn value(&mut self, value: &KdlValue, span: SourceSpan, rules: Option<&'a KdlDocument>) {
+        for rule in self.rules(rules, "type") {
+            let types: Vec<_> = rule.args().filter_map(|t| t.as_string()).collect();
+            if !types.is_empty() && !types.iter().any(|ty| has_type(value, ty)) {
+                let message = format!("expected a value of type {}", types.join(" or "));
+                self.error(span, message);
+            }
+        }
+        for rule in self.rules(rules, "enum") {
+            if !rule.args().any(|allowed| allowed == value) {
+                let allowed: Vec<_> = rule.args().map(|a| a.to_string()).collect();
+                let message = format!("expected one of {}", allowed.join(", "));
+                self.error(span, message);
+            }
+        }
+    }
+
+    /// Checks `count` against the `min` and `max` in `rules`.
+    fn count(
+        &mut self,
+        span: SourceSpan,
+        what: &str,
+        count: usize,
+        rules: Option<&'a KdlDocument>,
+    ) {
+        let limit = |name| {
+            rules
+                .and_then(|rules| rules.get_arg(name))
+                .and_then(|n| n.as_i64())
+                .map(|n| n.max(0) as usize)
+        };
+        if let Some(min) = limit("min").filter(|&min| count < min) {
+            self.error(
+                span,
+                format!("expected at least {} {}, found {}", min, what, count),
+            );
+        }
+        if let Some(max) = limit("max").filter(|&max| count > max) {
+            self.error(
+                span,
+                format!("expected at most {} {}, found {}", max, what, count),
+            );
+        }
+    }
+}
+
+/// Whether `rules` has a `name` node whose value is true.
+fn flag(rules: Option<&KdlDocument>, name: &str) -> bool {
+    rules
+        .and_then(|rules| rules.get_arg(name))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+fn has_type(value: &KdlValue, ty: &str) -> bool {
+    match ty {
+        "string" => value.is_string_value(),
+        "boolean" => value.is_bool(),
+        "null" => value.is_null(),
+        "number" => value.is_i64_value() || value.is_float_value(),
+        "integer" => value.is_i64_value(),
+        "float" => value.is_float_value(),
+        _ => true,
+    }
+}
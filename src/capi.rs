@@ -0,0 +1,507 @@
+//! A C API, enabled by the `capi` feature, declared in `include/kdl.h`.
+//!
+//! Documents, nodes, and entries are opaque pointers. Only documents
+//! returned by [`kdl_parse`] are owned by the caller, and must be freed with
+//! [`kdl_document_free`]. Nodes, entries, child documents, and [`KdlStr`]s
+//! borrow from the document they came from, and stay valid until it's
+//! freed. Strings are UTF-8 and aren't NUL-terminated, since KDL strings can
+//! contain NULs, except for error messages and [`kdl_document_to_string`]'s
+//! output, which are, and must be freed with [`kdl_string_free`].
+//!
+//! Every function that takes a pointer requires it to be valid (or `NULL`
+//! where that's allowed) and properly aligned, and to have come from this
+//! API.
+//!
+//! To build the library for linking from C, use
+//! `cargo rustc --release --features capi --crate-type cdylib` (or
+//! `staticlib`).
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use crate::{
+    EmitOptions, KdlDocument, KdlEntry, KdlError, KdlNode, KdlValue, KdlVersion, ParseOptions,
+};
+
+/// A borrowed UTF-8 string, `kdl_str` in C. `ptr` is `NULL` when there's
+/// no string at all, such as the name of an argument.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct KdlStr {
+    /// The first byte of the string.
+    pub ptr: *const c_char,
+    /// Length of the string in bytes.
+    pub len: usize,
+}
+
+impl KdlStr {
+    const NONE: KdlStr = KdlStr {
+        ptr: ptr::null(),
+        len: 0,
+    };
+
+    fn new(s: &str) -> Self {
+        KdlStr {
+            ptr: s.as_ptr() as *const c_char,
+            len: s.len(),
+        }
+    }
+}
+
+impl From<Option<&str>> for KdlStr {
+    fn from(s: Option<&str>) -> Self {
+        s.map_or(KdlStr::NONE, KdlStr::new)
+    }
+}
+
+/// The kind of an entry's value, `kdl_value_kind` in C.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum KdlValueKind {
+    /// `null`.
+    Null = 0,
+    /// `true` or `false`.
+    Bool = 1,
+    /// An integer in any base. It may not fit in an `int64_t`.
+    Integer = 2,
+    /// A float, possibly NaN or infinite.
+    Float = 3,
+    /// A string, raw or not.
+    String = 4,
+}
+
+fn c_string(s: String) -> CString {
+    CString::new(s.replace('\0', "\u{FFFD}")).unwrap()
+}
+
+fn describe(input: &str, e: KdlError) -> String {
+    let offset = e.span.offset().min(input.len());
+    let line = input[..offset].matches('\n').count() + 1;
+    match e.label {
+        Some(label) => format!("{} ({}, at line {})", e, label, line),
+        None => format!("{} (at line {})", e, line),
+    }
+}
+
+unsafe fn bytes<'a>(ptr: *const c_char, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(ptr as *const u8, len)
+    }
+}
+
+/// Parses `len` bytes of UTF-8 text at `text` as a document. `version` is
+/// `1` for KDL 1.0, `2` for KDL 2.0, or `0` for either, as with
+/// [`KdlDocument::parse_auto`]. Returns `NULL` on failure, and if `error`
+/// isn't `NULL`, points it at a description of what went wrong, which the
+/// caller frees with [`kdl_string_free`].
+///
+/// # Safety
+///
+/// `text` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_parse(
+    text: *const c_char,
+    len: usize,
+    version: c_int,
+    error: *mut *mut c_char,
+) -> *mut KdlDocument {
+    let result = std::str::from_utf8(bytes(text, len))
+        .map_err(|e| format!("input isn't valid UTF-8: {}", e))
+        .and_then(|input| {
+            let version = match version {
+                1 => KdlVersion::V1,
+                2 => KdlVersion::V2,
+                _ => return KdlDocument::parse_auto(input).map_err(|e| describe(input, e)),
+            };
+            let options = ParseOptions {
+                version,
+                ..ParseOptions::default()
+            };
+            KdlDocument::parse_with_options(input, &options).map_err(|e| describe(input, e))
+        });
+    match result {
+        Ok(doc) => Box::into_raw(Box::new(doc)),
+        Err(message) => {
+            if !error.is_null() {
+                *error = c_string(message).into_raw();
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Frees a document returned by [`kdl_parse`]. Does nothing with `NULL`.
+///
+/// # Safety
+///
+/// `doc` must have come from [`kdl_parse`], and not been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_document_free(doc: *mut KdlDocument) {
+    if !doc.is_null() {
+        drop(Box::from_raw(doc));
+    }
+}
+
+/// Frees a string returned by this API. Does nothing with `NULL`.
+///
+/// # Safety
+///
+/// `s` must have come from this API, and not been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Writes `doc` out as text, in the KDL version it was parsed as,
+/// NUL-terminated, and stores its length (without the NUL) in `len` if it
+/// isn't `NULL`. Free it with [`kdl_string_free`].
+///
+/// # Safety
+///
+/// `doc` must be a valid document.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_document_to_string(
+    doc: *const KdlDocument,
+    len: *mut usize,
+) -> *mut c_char {
+    let doc = &*doc;
+    let options = EmitOptions {
+        version: Some(doc.version()),
+        ..EmitOptions::default()
+    };
+    let text = c_string(
+        doc.to_string_with_options(&options)
+            .unwrap_or_else(|_| doc.to_string()),
+    );
+    if !len.is_null() {
+        *len = text.as_bytes().len();
+    }
+    text.into_raw()
+}
+
+/// Formats `doc` in place, as [`KdlDocument::fmt`] does.
+///
+/// # Safety
+///
+/// `doc` must be a document returned by [`kdl_parse`].
+#[no_mangle]
+pub unsafe extern "C" fn kdl_document_fmt(doc: *mut KdlDocument) {
+    (*doc).fmt();
+}
+
+/// The number of nodes in `doc`, not counting their children.
+///
+/// # Safety
+///
+/// `doc` must be a valid document.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_document_node_count(doc: *const KdlDocument) -> usize {
+    (*doc).nodes().len()
+}
+
+/// The node at `index` in `doc`, or `NULL` if there isn't one.
+///
+/// # Safety
+///
+/// `doc` must be a valid document.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_document_node(
+    doc: *const KdlDocument,
+    index: usize,
+) -> *const KdlNode {
+    (*doc)
+        .nodes()
+        .get(index)
+        .map_or(ptr::null(), |n| n as *const _)
+}
+
+/// The first node named `name` (`len` bytes of UTF-8) in `doc`, or `NULL`.
+///
+/// # Safety
+///
+/// `doc` must be a valid document, and `name` must point to `len` readable
+/// bytes.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_document_get(
+    doc: *const KdlDocument,
+    name: *const c_char,
+    len: usize,
+) -> *const KdlNode {
+    match std::str::from_utf8(bytes(name, len)) {
+        Ok(name) => (*doc).get(name).map_or(ptr::null(), |n| n as *const _),
+        Err(_) => ptr::null(),
+    }
+}
+
+/// The node's name.
+///
+/// # Safety
+///
+/// `node` must be a valid node.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_node_name(node: *const KdlNode) -> KdlStr {
+    KdlStr::new((*node).name().value())
+}
+
+/// The node's type annotation, if it has one.
+///
+/// # Safety
+///
+/// `node` must be a valid node.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_node_type(node: *const KdlNode) -> KdlStr {
+    (*node).ty().map(|ty| ty.value()).into()
+}
+
+/// The number of arguments and properties on `node`.
+///
+/// # Safety
+///
+/// `node` must be a valid node.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_node_entry_count(node: *const KdlNode) -> usize {
+    (*node).entries().len()
+}
+
+/// The entry at `index` on `node`, in the order they're written, or `NULL`
+/// if there isn't one.
+///
+/// # Safety
+///
+/// `node` must be a valid node.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_node_entry(node: *const KdlNode, index: usize) -> *const KdlEntry {
+    (*node)
+        .entries()
+        .get(index)
+        .map_or(ptr::null(), |e| e as *const _)
+}
+
+/// The node's children, or `NULL` if it has no children block.
+///
+/// # Safety
+///
+/// `node` must be a valid node.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_node_children(node: *const KdlNode) -> *const KdlDocument {
+    (*node).children().map_or(ptr::null(), |c| c as *const _)
+}
+
+/// The entry's property name, or no string for an argument.
+///
+/// # Safety
+///
+/// `entry` must be a valid entry.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_entry_name(entry: *const KdlEntry) -> KdlStr {
+    (*entry).name().map(|name| name.value()).into()
+}
+
+/// The entry's type annotation, if it has one.
+///
+/// # Safety
+///
+/// `entry` must be a valid entry.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_entry_type(entry: *const KdlEntry) -> KdlStr {
+    (*entry).ty().map(|ty| ty.value()).into()
+}
+
+/// The kind of the entry's value.
+///
+/// # Safety
+///
+/// `entry` must be a valid entry.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_entry_kind(entry: *const KdlEntry) -> KdlValueKind {
+    match (*entry).value() {
+        KdlValue::Null => KdlValueKind::Null,
+        KdlValue::Bool(_) => KdlValueKind::Bool,
+        KdlValue::Base10Float(_) => KdlValueKind::Float,
+        KdlValue::String(_) | KdlValue::RawString(_) => KdlValueKind::String,
+        _ => KdlValueKind::Integer,
+    }
+}
+
+/// Stores the entry's value in `out` and returns `true` if it's a boolean.
+///
+/// # Safety
+///
+/// `entry` must be a valid entry, and `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_entry_as_bool(entry: *const KdlEntry, out: *mut bool) -> bool {
+    match (*entry).value().as_bool() {
+        Some(b) => {
+            *out = b;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Stores the entry's value in `out` and returns `true` if it's an integer
+/// that fits in an `int64_t`.
+///
+/// # Safety
+///
+/// `entry` must be a valid entry, and `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_entry_as_i64(entry: *const KdlEntry, out: *mut i64) -> bool {
+    match (*entry).value().as_i64() {
+        Some(i) => {
+            *out = i;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Stores the entry's value in `out` and returns `true` if it's a float.
+///
+/// # Safety
+///
+/// `entry` must be a valid entry, and `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_entry_as_f64(entry: *const KdlEntry, out: *mut f64) -> bool {
+    match (*entry).value().as_f64() {
+        Some(f) => {
+            *out = f;
+            true
+        }
+        None => false,
+    }
+}
+
+/// The entry's value if it's a string, or no string otherwise.
+///
+/// # Safety
+///
+/// `entry` must be a valid entry.
+#[no_mangle]
+pub unsafe extern "C" fn kdl_entry_as_string(entry: *const KdlEntry) -> KdlStr {
+    (*entry).value().as_string().into()
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::CStr;
+
+    use super::*;
+
+    unsafe fn as_str<'a>(s: KdlStr) -> Option<&'a str> {
+        if s.ptr.is_null() {
+            return None;
+        }
+        let bytes = std::slice::from_raw_parts(s.ptr as *const u8, s.len);
+        Some(std::str::from_utf8(bytes).unwrap())
+    }
+
+    unsafe fn parse(text: &str, version: c_int) -> Result<*mut KdlDocument, String> {
+        let mut error = ptr::null_mut();
+        let doc = kdl_parse(
+            text.as_ptr() as *const c_char,
+            text.len(),
+            version,
+            &mut error,
+        );
+        if doc.is_null() {
+            let message = CStr::from_ptr(error).to_str().unwrap().to_string();
+            kdl_string_free(error);
+            Err(message)
+        } else {
+            Ok(doc)
+        }
+    }
+
+    #[test]
+    fn walk() {
+        unsafe {
+            let doc = parse("(t)a 1 x=(u8)2.5 { b \"s\\u{0}\" null true; }", 0).unwrap();
+            assert_eq!(kdl_document_node_count(doc), 1);
+            assert!(kdl_document_node(doc, 1).is_null());
+            let name = "a";
+            let a = kdl_document_get(doc, name.as_ptr() as *const c_char, name.len());
+            assert_eq!(a, kdl_document_node(doc, 0));
+            assert_eq!(as_str(kdl_node_name(a)), Some("a"));
+            assert_eq!(as_str(kdl_node_type(a)), Some("t"));
+            assert_eq!(kdl_node_entry_count(a), 2);
+            assert!(kdl_node_entry(a, 2).is_null());
+
+            let one = kdl_node_entry(a, 0);
+            assert_eq!(as_str(kdl_entry_name(one)), None);
+            assert_eq!(kdl_entry_kind(one), KdlValueKind::Integer);
+            let mut i = 0;
+            assert!(kdl_entry_as_i64(one, &mut i));
+            assert_eq!(i, 1);
+            let mut f = 0.0;
+            assert!(!kdl_entry_as_f64(one, &mut f));
+
+            let x = kdl_node_entry(a, 1);
+            assert_eq!(as_str(kdl_entry_name(x)), Some("x"));
+            assert_eq!(as_str(kdl_entry_type(x)), Some("u8"));
+            assert_eq!(kdl_entry_kind(x), KdlValueKind::Float);
+            assert!(kdl_entry_as_f64(x, &mut f));
+            assert_eq!(f, 2.5);
+
+            let children = kdl_node_children(a);
+            let b = kdl_document_node(children, 0);
+            assert!(kdl_node_children(b).is_null());
+            let s = kdl_node_entry(b, 0);
+            assert_eq!(kdl_entry_kind(s), KdlValueKind::String);
+            assert_eq!(as_str(kdl_entry_as_string(s)), Some("s\0"));
+            assert_eq!(kdl_entry_kind(kdl_node_entry(b, 1)), KdlValueKind::Null);
+            let mut bool = false;
+            assert!(kdl_entry_as_bool(kdl_node_entry(b, 2), &mut bool));
+            assert!(bool);
+
+            kdl_document_free(doc);
+        }
+    }
+
+    #[test]
+    fn parse_and_write() {
+        unsafe {
+            let input = "a   #true\n";
+            assert!(parse(input, 1).unwrap_err().contains("at line 1"));
+            let doc = parse(input, 2).unwrap();
+            let mut len = 0;
+            let text = kdl_document_to_string(doc, &mut len);
+            assert_eq!(CStr::from_ptr(text).to_str(), Ok("a   #true\n"));
+            assert_eq!(len, input.len());
+            kdl_string_free(text);
+
+            kdl_document_fmt(doc);
+            let text = kdl_document_to_string(doc, ptr::null_mut());
+            assert_eq!(CStr::from_ptr(text).to_str(), Ok("a #true\n"));
+            kdl_string_free(text);
+            kdl_document_free(doc);
+
+            let doc = parse("", 0).unwrap();
+            assert_eq!(kdl_document_node_count(doc), 0);
+            kdl_document_free(doc);
+            let bytes = b"a \"\xff\"";
+            let doc = kdl_parse(bytes.as_ptr() as *const c_char, 5, 0, ptr::null_mut());
+            assert!(doc.is_null());
+        }
+    }
+
+    #[test]
+    fn header() {
+        let header = include_str!("../include/kdl.h");
+        for line in include_str!("capi.rs").lines() {
+            if let Some(rest) = line.strip_prefix("pub unsafe extern \"C\" fn ") {
+                let name = &rest[..rest.find('(').unwrap()];
+                assert!(
+                    header.contains(&format!("{}(", name)),
+                    "{} isn't declared in include/kdl.h",
+                    name
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use std::ops::Range;
+
+use crate::KdlNode;
+
+/// Comments above a node, as structured text.
+///
+/// A node's comment block is the run of comment lines directly above it:
+/// `//` comments, and `/* */` comments that take up whole lines, with no
+/// blank lines between them or between the last one and the node. Comments
+/// further up, or on the same line as the node, aren't part of it.
+///
+/// The block is kept in the node's [leading text](KdlNode::leading), so it
+/// stays with the node when it's edited, moved around in
+/// [`KdlDocument::nodes_mut`](crate::KdlDocument::nodes_mut), cloned into
+/// another document, or removed. Setting the leading text with
+/// [`KdlNode::set_leading`] replaces it, and [`KdlNode::clear_fmt`] and
+/// [`KdlNode::fmt_no_comments`] remove it. [`KdlNode::fmt`] keeps it, but
+/// since formatting also removes blank lines, comments that were separated
+/// from the node by one become part of its block.
+impl KdlNode {
+    /// Gets the lines of this node's comment block, without their comment
+    /// markers, or an empty `Vec` if it doesn't have one.
+    ///
+    /// For `//` comments, the `//` and a single space after it are removed.
+    /// For `/* */` comments, so are the delimiters, a single space after the
+    /// opening one, and the `*` that each of the following lines may start
+    /// with, as well as any lines left empty at the start or end.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = r#"
+    /// // Not this one.
+    ///
+    /// // The address to listen on.
+    /// /*
+    ///  * Defaults to localhost.
+    ///  */
+    /// host "::1" // Nor this one.
+    /// "#.parse().unwrap();
+    /// assert_eq!(
+    ///     doc.get("host").unwrap().comments(),
+    ///     vec!["The address to listen on.", "Defaults to localhost."]
+    /// );
+    /// ```
+    pub fn comments(&self) -> Vec<String> {
+        let leading = self.leading.as_deref().unwrap_or_default();
+        let block = CommentBlock::find(leading);
+        let mut lines = Vec::new();
+        for comment in &block.comments {
+            lines.extend(comment_lines(comment));
+        }
+        lines
+    }
+
+    /// Gets this node's documentation, written in a comment block right
+    /// above it, or `None` if it doesn't have any. This lets a document
+    /// describe itself, for tools that show what each node is for, like
+    /// schema generators or a `--help` for a configuration file.
+    ///
+    /// If the comment block ends with doc comments, `///` lines or a
+    /// `/** */` comment in the style of Rust and Java, only those are the
+    /// documentation, and other comments above them aren't. Otherwise, the
+    /// whole block is, except for banner lines that start with four or more
+    /// slashes. Either way, the lines are stripped the way
+    /// [`KdlNode::comments`] strips them (along with the extra `/` of `///`),
+    /// blank lines at the start and end are dropped, and the rest are joined
+    /// with `\n`. Comments with no text in them, like a lone `//`, aren't
+    /// documentation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = r#"
+    /// // TODO: raise this.
+    /// /// How many requests to handle at once.
+    /// ///
+    /// /// Zero means no limit.
+    /// workers 8
+    ///
+    /// // How long to wait, in seconds.
+    /// timeout 30
+    ///
+    /// retries 3
+    /// "#.parse().unwrap();
+    /// assert_eq!(
+    ///     doc.get("workers").unwrap().doc().as_deref(),
+    ///     Some("How many requests to handle at once.\n\nZero means no limit.")
+    /// );
+    /// assert_eq!(
+    ///     doc.get("timeout").unwrap().doc().as_deref(),
+    ///     Some("How long to wait, in seconds.")
+    /// );
+    /// assert_eq!(doc.get("retries").unwrap().doc(), None);
+    /// ```
+    pub fn doc(&self) -> Option<String> {
+        let leading = self.leading.as_deref().unwrap_or_default();
+        let block = CommentBlock::find(leading);
+        let docs = block
+            .comments
+            .iter()
+            .rev()
+            .take_while(|comment| is_doc_comment(comment))
+            .count();
+        let comments = if docs > 0 {
+            &block.comments[block.comments.len() - docs..]
+        } else {
+            &block.comments[..]
+        };
+        let mut lines = Vec::new();
+        for comment in comments
+            .iter()
+            .filter(|comment| !comment.starts_with("////"))
+        {
+            match comment.strip_prefix("///").filter(|_| docs > 0) {
+                Some(line) => lines.push(line.strip_prefix(' ').unwrap_or(line).trim_end().into()),
+                None => lines.extend(comment_lines(comment)),
+            }
+        }
+        while lines.last().map_or(false, String::is_empty) {
+            lines.pop();
+        }
+        let blank = lines.iter().take_while(|line| line.is_empty()).count();
+        lines.drain(..blank);
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
+        }
+    }
+
+    /// Replaces this node's comment block with `text`, written as one `//`
+    /// comment per line, indented to line up with the node. Setting it to
+    /// an empty string removes the comment block altogether. Anything in the
+    /// leading text above the block, like other comments and blank lines, is
+    /// left alone.
+    ///
+    /// Nodes without any leading text, like ones made with [`KdlNode::new`],
+    /// don't know how far they'll be indented, so their comments aren't
+    /// indented until the document is formatted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let mut doc: KdlDocument = "server {\n    // Old.\n    port 80\n}\n".parse().unwrap();
+    /// let server = doc.get_mut("server").unwrap().children_mut().as_mut().unwrap();
+    /// server.get_mut("port").unwrap().set_comment("The port to listen on.\nDefaults to 80.");
+    /// assert_eq!(
+    ///     doc.to_string(),
+    ///     "server {\n    // The port to listen on.\n    // Defaults to 80.\n    port 80\n}\n"
+    /// );
+    /// ```
+    pub fn set_comment(&mut self, text: &str) {
+        let leading = self.leading.take().unwrap_or_default();
+        let block = CommentBlock::find(&leading);
+        let indent = &leading[block.indent.clone()];
+        let mut comment = String::new();
+        for line in text.lines() {
+            comment.push_str(indent);
+            comment.push_str("//");
+            if !line.is_empty() {
+                comment.push(' ');
+                comment.push_str(line.trim_end());
+            }
+            comment.push('\n');
+        }
+        let mut result = leading[..block.range.start].to_string();
+        result.push_str(&comment);
+        result.push_str(&leading[block.range.end..]);
+        self.leading = Some(result);
+    }
+}
+
+/// Where a node's comment block is in its leading text.
+#[derive(Debug)]
+pub(crate) struct CommentBlock<'a> {
+    /// The whole lines the block takes up, which is empty (at the start of
+    /// the node's line) when there's no block.
+    pub(crate) range: Range<usize>,
+    /// The whitespace the node's own line starts with.
+    pub(crate) indent: Range<usize>,
+    /// Each comment, in order, with the whitespace around it trimmed.
+    pub(crate) comments: Vec<&'a str>,
+}
+
+impl<'a> CommentBlock<'a> {
+    pub(crate) fn find(leading: &'a str) -> Self {
+        let line_start = leading.rfind('\n').map_or(0, |i| i + 1);
+        let rest = &leading[line_start..];
+        let indent = line_start..line_start + rest.len() - rest.trim_start().len();
+        let lines = leading[..line_start]
+            .split_inclusive('\n')
+            .scan(0, |offset, line| {
+                let start = *offset;
+                *offset += line.len();
+                Some((start, line))
+            })
+            .collect::<Vec<_>>();
+
+        let mut start = line_start;
+        let mut comments = Vec::new();
+        let mut end = lines.len();
+        while let Some(&(offset, line)) = end.checked_sub(1).and_then(|last| lines.get(last)) {
+            let line = line.trim();
+            let first = if line.starts_with("//") {
+                end - 1
+            } else if line.ends_with("*/") {
+                // A block comment, which may start some lines up.
+                let found = lines[..end]
+                    .iter()
+                    .rposition(|(_, line)| line.trim_start().starts_with("/*"));
+                match found {
+                    Some(first) if lines[first..end - 1].iter().all(|(_, l)| !l.contains("*/")) => {
+                        first
+                    }
+                    _ => break,
+                }
+            } else {
+                break;
+            };
+            start = lines[first].0;
+            comments.push(leading[start..offset + lines[end - 1].1.len()].trim());
+            end = first;
+        }
+        comments.reverse();
+        CommentBlock {
+            range: start..line_start,
+            indent,
+            comments,
+        }
+    }
+}
+
+/// Whether `comment` is a `///` or `/** */` doc comment, and not just a
+/// line of slashes or asterisks.
+fn is_doc_comment(comment: &str) -> bool {
+    (comment.starts_with("///") && !comment.starts_with("////"))
+        || (comment.starts_with("/**") && !comment.starts_with("/***") && comment != "/**/")
+}
+
+/// The lines of text in a single comment.
+fn comment_lines(comment: &str) -> Vec<String> {
+    if let Some(line) = comment.strip_prefix("//") {
+        let line = line.strip_prefix(' ').unwrap_or(line);
+        return vec![line.trim_end().into()];
+    }
+    let inner = comment
+        .trim_start_matches("/*")
+        .strip_suffix("*/")
+        .unwrap_or_default()
+        .trim_end_matches('*');
+    let mut lines = inner
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = if i == 0 {
+                line.trim_start_matches('*')
+            } else {
+                let line = line.trim_start();
+                line.strip_prefix('*').unwrap_or(line)
+            };
+            line.strip_prefix(' ')
+                .unwrap_or(line)
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    while lines.last().map_or(false, |line| line.is_empty()) {
+        lines.pop();
+    }
+    let blank = lines.iter().take_while(|line| line.is_empty()).count();
+    lines.drain(..blank);
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use crate::KdlDocument;
+
+    fn comments(input: &str) -> Vec<Vec<String>> {
+        let doc: KdlDocument = input.parse().unwrap();
+        doc.nodes().iter().map(|node| node.comments()).collect()
+    }
+
+    #[test]
+    fn blocks() {
+        assert_eq!(
+            comments("// a\n//\n//b \na\n\n// c\n\nb\n/* d */\n/** e\n * f */\nc"),
+            vec![vec!["a", "", "b"], vec![], vec!["d", "e", "f"]] as Vec<Vec<&str>>
+        );
+        assert_eq!(
+            comments("a /* no */\n/* no */ b\n/- c\nd\nparent {\n    // kid\n    kid\n}"),
+            vec![Vec::<&str>::new(); 4]
+        );
+        let doc: KdlDocument = "parent {\n    // kid\n    kid\n}".parse().unwrap();
+        assert_eq!(
+            doc.nodes()[0].children().unwrap().nodes()[0].comments(),
+            vec!["kid"]
+        );
+    }
+
+    #[test]
+    fn set_comment() {
+        let mut doc: KdlDocument = "// top\n\n// old\na\n  b".parse().unwrap();
+        doc.nodes_mut()[0].set_comment("new\n\nlines");
+        doc.nodes_mut()[1].set_comment("b's");
+        assert_eq!(
+            doc.to_string(),
+            "// top\n\n// new\n//\n// lines\na\n  // b's\n  b"
+        );
+        assert_eq!(doc.nodes()[0].comments(), vec!["new", "", "lines"]);
+
+        doc.nodes_mut()[0].set_comment("");
+        assert_eq!(doc.to_string(), "// top\n\na\n  // b's\n  b");
+        assert!(doc.nodes()[0].comments().is_empty());
+
+        doc.nodes_mut().swap(0, 1);
+        doc.fmt();
+        assert_eq!(doc.to_string(), "// b's\nb\n// top\na\n");
+
+        let mut node = crate::KdlNode::new("new");
+        node.set_comment("fresh");
+        assert_eq!(node.to_string(), "// fresh\nnew");
+    }
+
+    #[test]
+    fn doc() {
+        let docs = |input: &str| {
+            let doc: KdlDocument = input.parse().unwrap();
+            doc.nodes()
+                .iter()
+                .map(|node| node.doc())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            docs("/** Block\n * doc. */\na\n/**/\n// plain\nb\n//// banner\nc\n/* x */\n///\n/// y\nd"),
+            vec![
+                Some("Block\ndoc.".into()),
+                Some("plain".into()),
+                None,
+                Some("y".into()),
+            ]
+        );
+        assert_eq!(docs("a\n//\nb"), vec![None, None]);
+        assert_eq!(
+            docs("//////////\n// Section.\n//////////\n//\n// Text.\n//\na"),
+            vec![Some("Section.\n\nText.".into())]
+        );
+    }
+}
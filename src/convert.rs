@@ -0,0 +1,335 @@
+use std::fmt::Display;
+
+use miette::{Diagnostic, SourceSpan};
+
+use crate::{KdlNode, KdlValue, NodeKey};
+
+/// Conversion from a [`KdlValue`] into a Rust type, used by
+/// [`KdlNode::get_as`] and [`KdlNode::arg_as`].
+///
+/// Integers convert only from integer values that fit in the target type,
+/// and never from floats. Floats convert from any number that can be
+/// represented exactly (see [`KdlValue::as_f64_lossless`]). `Option<T>`
+/// converts from `null`, and also from a missing entry.
+pub trait FromKdlValue: Sized {
+    /// Name of the type, used in error messages (e.g. `"u16"`).
+    const EXPECTED: &'static str;
+
+    /// Converts `value`, or returns `None` if it's not the right kind of
+    /// value.
+    fn from_kdl_value(value: &KdlValue) -> Option<Self>;
+
+    /// What to use when the entry doesn't exist at all. Defaults to `None`,
+    /// which makes a missing entry an error.
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+macro_rules! from_kdl_ints {
+    ($($ty:ident: $as:ident),* $(,)?) => {
+        $(
+            impl FromKdlValue for $ty {
+                const EXPECTED: &'static str = stringify!($ty);
+
+                fn from_kdl_value(value: &KdlValue) -> Option<Self> {
+                    value.$as()
+                }
+            }
+        )*
+    };
+}
+
+from_kdl_ints! {
+    i8: as_i8,
+    i16: as_i16,
+    i32: as_i32,
+    i64: as_i64,
+    i128: as_i128,
+    isize: as_isize,
+    u8: as_u8,
+    u16: as_u16,
+    u32: as_u32,
+    u64: as_u64,
+    u128: as_u128,
+    usize: as_usize,
+}
+
+impl FromKdlValue for f64 {
+    const EXPECTED: &'static str = "f64";
+
+    fn from_kdl_value(value: &KdlValue) -> Option<Self> {
+        value.as_f64_lossless()
+    }
+}
+
+impl FromKdlValue for f32 {
+    const EXPECTED: &'static str = "f32";
+
+    fn from_kdl_value(value: &KdlValue) -> Option<Self> {
+        let value = value.as_f64_lossless()?;
+        let narrowed = value as f32;
+        if f64::from(narrowed) == value || !value.is_finite() {
+            Some(narrowed)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromKdlValue for bool {
+    const EXPECTED: &'static str = "bool";
+
+    fn from_kdl_value(value: &KdlValue) -> Option<Self> {
+        value.as_bool()
+    }
+}
+
+impl FromKdlValue for String {
+    const EXPECTED: &'static str = "string";
+
+    fn from_kdl_value(value: &KdlValue) -> Option<Self> {
+        value.as_string().map(String::from)
+    }
+}
+
+impl FromKdlValue for KdlValue {
+    const EXPECTED: &'static str = "value";
+
+    fn from_kdl_value(value: &KdlValue) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl<T: FromKdlValue> FromKdlValue for Option<T> {
+    const EXPECTED: &'static str = T::EXPECTED;
+
+    fn from_kdl_value(value: &KdlValue) -> Option<Self> {
+        if value.is_null() {
+            Some(None)
+        } else {
+            T::from_kdl_value(value).map(Some)
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+/// Error returned by [`KdlNode::get_as`] and [`KdlNode::arg_as`] when an
+/// entry is missing or can't be converted to the requested type.
+///
+/// Like [`KdlError`](crate::KdlError), this implements
+/// [`miette::Diagnostic`], but needs the document's source text attached to
+/// render a snippet: see [Error Reporting](crate#error-reporting).
+#[derive(Debug, Diagnostic, Clone, Eq, PartialEq)]
+#[diagnostic(code(kdl::conversion))]
+pub struct KdlConversionError {
+    /// Name of the node the entry was looked up on.
+    pub node: String,
+
+    /// The property name or argument index that was looked up.
+    pub key: NodeKey,
+
+    /// Name of the type the value was supposed to convert to, from
+    /// [`FromKdlValue::EXPECTED`].
+    pub expected: &'static str,
+
+    /// The value that was found, or `None` if the entry was missing.
+    pub found: Option<KdlValue>,
+
+    /// Span of the offending entry, or of the whole node if the entry was
+    /// missing. This is `0..0` without the `span` feature.
+    #[label("expected {}", expected)]
+    pub span: SourceSpan,
+}
+
+impl Display for KdlConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let key = match &self.key {
+            NodeKey::Key(key) => format!("property `{}`", key.value()),
+            NodeKey::Index(idx) => format!("argument {}", idx),
+        };
+        match &self.found {
+            Some(found) => write!(
+                f,
+                "Expected {} of node `{}` to be {}, but found `{}`.",
+                key, self.node, self.expected, found
+            ),
+            None => write!(
+                f,
+                "Node `{}` is missing {}, expected {}.",
+                self.node, key, self.expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KdlConversionError {}
+
+// Errors are the uncommon path here, and a flat struct is much easier to
+// inspect than a boxed one.
+#[allow(clippy::result_large_err)]
+impl KdlNode {
+    /// Fetches an entry by key, like [`KdlNode::get`], and converts its value
+    /// to `T`. Fails if the entry is missing (unless `T` is an `Option`) or
+    /// holds the wrong kind of value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let node: KdlNode = r#"server "localhost" port=8080 tls=true"#.parse().unwrap();
+    /// assert_eq!(node.get_as::<u16>("port"), Ok(8080));
+    /// assert_eq!(node.get_as::<String>(0).as_deref(), Ok("localhost"));
+    /// assert_eq!(node.get_as::<Option<u32>>("timeout"), Ok(None));
+    ///
+    /// let err = node.get_as::<u8>("port").unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Expected property `port` of node `server` to be u8, but found `8080`."
+    /// );
+    /// ```
+    pub fn get_as<T: FromKdlValue>(
+        &self,
+        key: impl Into<NodeKey>,
+    ) -> Result<T, KdlConversionError> {
+        let key = key.into();
+        let entry = self.entry(key.clone());
+        let converted = match entry {
+            Some(entry) => T::from_kdl_value(entry.value()),
+            None => T::from_missing(),
+        };
+        converted.ok_or_else(|| KdlConversionError {
+            node: self.name().value().into(),
+            key,
+            expected: T::EXPECTED,
+            found: entry.map(|entry| entry.value().clone()),
+            #[cfg(feature = "span")]
+            span: entry.map_or(self.span(), |entry| entry.span()),
+            #[cfg(not(feature = "span"))]
+            span: SourceSpan::from(0..0),
+        })
+    }
+
+    /// Fetches the argument at `idx` and converts it to `T`. Shorthand for
+    /// [`KdlNode::get_as`] with an index.
+    pub fn arg_as<T: FromKdlValue>(&self, idx: usize) -> Result<T, KdlConversionError> {
+        self.get_as(idx)
+    }
+
+    /// Fetches an entry by key, like [`KdlNode::get_as`], falling back to
+    /// `default` if it's missing. An entry that's there but can't be
+    /// converted to `T` is still an error, so a typo'd or out-of-range value
+    /// isn't quietly replaced by the default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let node: KdlNode = r#"server port=8080 host=null"#.parse().unwrap();
+    /// assert_eq!(node.get_or("port", 80u16), Ok(8080));
+    /// assert_eq!(node.get_or("timeout", 30u32), Ok(30));
+    /// assert_eq!(node.get_or("host", Some(String::from("localhost"))), Ok(None));
+    /// assert!(node.get_or("port", 80u8).is_err());
+    /// ```
+    pub fn get_or<T: FromKdlValue>(
+        &self,
+        key: impl Into<NodeKey>,
+        default: T,
+    ) -> Result<T, KdlConversionError> {
+        let key = key.into();
+        if self.entry(key.clone()).is_none() {
+            Ok(default)
+        } else {
+            self.get_as(key)
+        }
+    }
+
+    /// Fetches an entry by key, like [`KdlNode::get_or`], falling back to
+    /// `T::default()` if it's missing.
+    pub fn get_or_default<T: FromKdlValue + Default>(
+        &self,
+        key: impl Into<NodeKey>,
+    ) -> Result<T, KdlConversionError> {
+        self.get_or(key, T::default())
+    }
+
+    /// Fetches the argument at `idx`, like [`KdlNode::arg_as`], falling back
+    /// to `default` if it's missing. Shorthand for [`KdlNode::get_or`] with
+    /// an index.
+    pub fn arg_or<T: FromKdlValue>(&self, idx: usize, default: T) -> Result<T, KdlConversionError> {
+        self.get_or(idx, default)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn node() -> KdlNode {
+        r#"job 3 2.5 null "x" retries=300 ratio=1 on=true"#.parse().unwrap()
+    }
+
+    #[test]
+    fn conversions() {
+        let node = node();
+        assert_eq!(node.arg_as::<u8>(0), Ok(3));
+        assert_eq!(node.arg_as::<f64>(0), Ok(3.0));
+        assert_eq!(node.arg_as::<f32>(1), Ok(2.5));
+        assert_eq!(node.arg_as::<Option<i32>>(2), Ok(None));
+        assert_eq!(node.arg_as::<Option<String>>(3), Ok(Some("x".into())));
+        assert_eq!(node.get_as::<u16>("retries"), Ok(300));
+        assert_eq!(node.get_as::<f64>("ratio"), Ok(1.0));
+        assert_eq!(node.get_as::<bool>("on"), Ok(true));
+        assert_eq!(node.get_as::<KdlValue>("on"), Ok(KdlValue::Bool(true)));
+        assert_eq!(node.get_as::<Option<bool>>("off"), Ok(None));
+
+        assert!(node.arg_as::<i64>(1).is_err());
+        assert!(node.arg_as::<String>(0).is_err());
+        assert!(node.arg_as::<i32>(2).is_err());
+        assert!(node.get_as::<u8>("retries").is_err());
+        assert!(node.get_as::<bool>("off").is_err());
+    }
+
+    #[test]
+    fn fallbacks() {
+        let node = node();
+        assert_eq!(node.arg_or(0, 9u8), Ok(3));
+        assert_eq!(node.arg_or(9, 9u8), Ok(9));
+        assert_eq!(node.arg_or(2, Some(1)), Ok(None));
+        assert_eq!(node.get_or("retries", 0u16), Ok(300));
+        assert_eq!(node.get_or("on", false), Ok(true));
+        assert_eq!(node.get_or("off", true), Ok(true));
+        assert_eq!(node.get_or_default::<u32>("missing"), Ok(0));
+        assert_eq!(node.get_or_default::<String>(3).as_deref(), Ok("x"));
+
+        let err = node.get_or("retries", 7u8).unwrap_err();
+        assert_eq!(err.found, Some(KdlValue::Base10(300)));
+        assert_eq!(err.expected, "u8");
+        assert!(node.arg_or(3, 9u8).is_err());
+        assert!(node.get_or_default::<String>(0).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let node = node();
+        let err = node.arg_as::<u8>(9).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Node `job` is missing argument 9, expected u8."
+        );
+        assert_eq!(err.found, None);
+
+        let err = node.get_as::<String>("retries").unwrap_err();
+        assert_eq!(err.key, NodeKey::from("retries"));
+        assert_eq!(err.expected, "string");
+        assert_eq!(err.found, Some(KdlValue::Base10(300)));
+        #[cfg(feature = "span")]
+        {
+            assert_eq!(err.span, node.entry("retries").unwrap().span());
+            assert_eq!(node.arg_as::<u8>(9).unwrap_err().span, node.span());
+        }
+    }
+}
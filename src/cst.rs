@@ -0,0 +1,569 @@
+use std::{fmt::Display, str::FromStr};
+
+use miette::SourceSpan;
+use nom::{bytes::complete::tag, combinator::recognize, multi::many1, Offset};
+
+use crate::{parser, KdlDocument, KdlError, KdlValue};
+
+/// A node in a lossless concrete syntax tree of a KDL document.
+///
+/// Where [`KdlDocument`] and friends keep whitespace and comments as opaque
+/// leading/trailing strings, a CST breaks everything down into
+/// [`KdlCstToken`]s (including whitespace, newlines, comments, semicolons,
+/// and line continuations), grouped into nested syntax nodes. Concatenating
+/// the text of every token gives back the exact input.
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::{KdlCstNode, KdlSyntaxKind, KdlTokenKind};
+/// let input = "foo 1 // comment\n";
+/// let cst: KdlCstNode = input.parse().unwrap();
+/// assert_eq!(cst.kind, KdlSyntaxKind::Document);
+/// assert_eq!(cst.to_string(), input);
+///
+/// let kinds = cst.tokens().iter().map(|t| t.kind).collect::<Vec<_>>();
+/// assert_eq!(
+///     kinds,
+///     vec![
+///         KdlTokenKind::Identifier,
+///         KdlTokenKind::Whitespace,
+///         KdlTokenKind::Number,
+///         KdlTokenKind::Whitespace,
+///         KdlTokenKind::SingleLineComment,
+///         KdlTokenKind::Newline,
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KdlCstNode {
+    /// What kind of syntax this node is.
+    pub kind: KdlSyntaxKind,
+
+    /// The span of all of this node's tokens.
+    pub span: SourceSpan,
+
+    /// This node's tokens and child nodes, in source order.
+    pub children: Vec<KdlCstElement>,
+}
+
+/// A child of a [`KdlCstNode`]: either a nested node or a single token.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KdlCstElement {
+    /// A nested syntax node.
+    Node(KdlCstNode),
+    /// A single token.
+    Token(KdlCstToken),
+}
+
+/// A single token in a [`KdlCstNode`] tree.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KdlCstToken {
+    /// What kind of token this is.
+    pub kind: KdlTokenKind,
+
+    /// Where the token is in the source text.
+    pub span: SourceSpan,
+
+    /// The token's exact source text.
+    pub text: String,
+}
+
+/// The kind of a [`KdlCstNode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KdlSyntaxKind {
+    /// A whole document, or the contents of a children block.
+    Document,
+    /// A node, from its type annotation or name through its terminator.
+    Node,
+    /// An argument or property.
+    Entry,
+    /// A type annotation, including its parentheses.
+    TypeAnnotation,
+    /// A children block, including its braces.
+    Children,
+    /// A `/-` along with the node, entry, or children block it comments out.
+    SlashDash,
+}
+
+/// The kind of a [`KdlCstToken`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KdlTokenKind {
+    /// A run of non-newline whitespace.
+    Whitespace,
+    /// A single line ending.
+    Newline,
+    /// A `//` comment, not including the line ending after it.
+    SingleLineComment,
+    /// A `/* */` comment, including any nested comments.
+    MultiLineComment,
+    /// The `\` starting a line continuation.
+    LineContinuation,
+    /// The `/-` starting a slashdash comment.
+    SlashDash,
+    /// A `;` node terminator.
+    Semicolon,
+    /// The `=` between a property's key and value.
+    Equals,
+    /// The `(` starting a type annotation.
+    OpenParen,
+    /// The `)` ending a type annotation.
+    CloseParen,
+    /// The `{` starting a children block.
+    OpenBrace,
+    /// The `}` ending a children block.
+    CloseBrace,
+    /// A node name, property key, or type annotation, quoted or not.
+    Identifier,
+    /// A string value.
+    String,
+    /// A raw string value.
+    RawString,
+    /// A numeric value, in any base.
+    Number,
+    /// A `true` or `false` value.
+    Bool,
+    /// A `null` value.
+    Null,
+}
+
+impl KdlCstNode {
+    /// Gets this node's child nodes, skipping over its tokens.
+    pub fn nodes(&self) -> impl Iterator<Item = &KdlCstNode> {
+        self.children.iter().filter_map(|child| match child {
+            KdlCstElement::Node(node) => Some(node),
+            KdlCstElement::Token(_) => None,
+        })
+    }
+
+    /// Gets every token in this node and its descendants, in source order.
+    pub fn tokens(&self) -> Vec<&KdlCstToken> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a KdlCstToken>) {
+        for child in &self.children {
+            match child {
+                KdlCstElement::Node(node) => node.collect_tokens(tokens),
+                KdlCstElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    /// Converts this tree (which should be a [`KdlSyntaxKind::Document`])
+    /// back into a [`KdlDocument`], by parsing its text.
+    pub fn to_document(&self) -> Result<KdlDocument, KdlError> {
+        self.to_string().parse()
+    }
+}
+
+impl KdlDocument {
+    /// Builds a [`KdlCstNode`] tree for this document's string
+    /// representation.
+    ///
+    /// This only fails if the document was modified in a way that makes its
+    /// string representation invalid KDL (for example, with a custom
+    /// [`KdlEntry::set_value_repr`](crate::KdlEntry::set_value_repr)).
+    pub fn to_cst(&self) -> Result<KdlCstNode, KdlError> {
+        self.to_string().parse()
+    }
+}
+
+impl From<KdlCstToken> for KdlCstElement {
+    fn from(token: KdlCstToken) -> Self {
+        KdlCstElement::Token(token)
+    }
+}
+
+impl From<KdlCstNode> for KdlCstElement {
+    fn from(node: KdlCstNode) -> Self {
+        KdlCstElement::Node(node)
+    }
+}
+
+impl Display for KdlCstNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for child in &self.children {
+            write!(f, "{}", child)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for KdlCstElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KdlCstElement::Node(node) => write!(f, "{}", node),
+            KdlCstElement::Token(token) => write!(f, "{}", token),
+        }
+    }
+}
+
+impl Display for KdlCstToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl FromStr for KdlCstNode {
+    type Err = KdlError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        // Let the real parser validate the input (and report any errors), so
+        // that building the tree only has to deal with valid KDL.
+        input.parse::<KdlDocument>()?;
+        let builder = Builder {
+            text: input,
+            parser: parser::KdlParser::new(input),
+        };
+        let mut rest = input;
+        Ok(builder.document(&mut rest, false))
+    }
+}
+
+/// Builds a CST out of input that's already known to be valid KDL, reusing
+/// the parser's own rules for individual tokens.
+struct Builder<'a> {
+    text: &'a str,
+    parser: parser::KdlParser<'a>,
+}
+
+type Res<'a> = nom::IResult<&'a str, &'a str, crate::KdlParseError<&'a str>>;
+
+impl<'a> Builder<'a> {
+    fn span(&self, s: &'a str) -> SourceSpan {
+        let start = self.text.offset(s);
+        SourceSpan::from(start..start + s.len())
+    }
+
+    /// Consumes the text matched by `rule`, if any, as a token.
+    fn eat(
+        &self,
+        input: &mut &'a str,
+        out: &mut Vec<KdlCstElement>,
+        kind: KdlTokenKind,
+        mut rule: impl FnMut(&'a str) -> Res<'a>,
+    ) -> bool {
+        match rule(input) {
+            Ok((rest, matched)) if !matched.is_empty() => {
+                out.push(self.token(kind, matched));
+                *input = rest;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn token(&self, kind: KdlTokenKind, text: &'a str) -> KdlCstElement {
+        KdlCstElement::Token(KdlCstToken {
+            kind,
+            span: self.span(text),
+            text: text.into(),
+        })
+    }
+
+    fn node(
+        &self,
+        kind: KdlSyntaxKind,
+        start: &'a str,
+        children: Vec<KdlCstElement>,
+    ) -> KdlCstNode {
+        let offset = self.text.offset(start);
+        let end = children
+            .last()
+            .map(|child| match child {
+                KdlCstElement::Node(node) => node.span,
+                KdlCstElement::Token(token) => token.span,
+            })
+            .map(|span| span.offset() + span.len())
+            .unwrap_or(offset);
+        KdlCstNode {
+            kind,
+            span: SourceSpan::from(offset..end),
+            children,
+        }
+    }
+
+    fn document(&self, input: &mut &'a str, nested: bool) -> KdlCstNode {
+        let start = *input;
+        let mut children = Vec::new();
+        while !(input.is_empty() || nested && input.starts_with('}')) {
+            if !self.line_trivia(input, &mut children) {
+                let node = self.node_body(input);
+                children.push(node.into());
+            }
+        }
+        self.node(KdlSyntaxKind::Document, start, children)
+    }
+
+    /// Consumes one piece of whitespace, a newline, or a comment, as found
+    /// between nodes.
+    fn line_trivia(&self, input: &mut &'a str, out: &mut Vec<KdlCstElement>) -> bool {
+        use KdlTokenKind::*;
+        self.eat(
+            input,
+            out,
+            Whitespace,
+            recognize(many1(parser::unicode_space)),
+        ) || self.eat(input, out, Newline, parser::newline)
+            || self.single_line_comment(input, out)
+            || self.eat(input, out, MultiLineComment, parser::multi_line_comment)
+            || self.slashdash(input, out, false)
+    }
+
+    /// Consumes one piece of whitespace, a line continuation, or a comment,
+    /// as found between a node's entries.
+    fn node_trivia(&self, input: &mut &'a str, out: &mut Vec<KdlCstElement>) -> bool {
+        use KdlTokenKind::*;
+        if self.eat(input, out, LineContinuation, tag("\\")) {
+            while self.eat(
+                input,
+                out,
+                Whitespace,
+                recognize(many1(parser::unicode_space)),
+            ) || self.eat(input, out, MultiLineComment, parser::multi_line_comment)
+            {}
+            return self.single_line_comment(input, out)
+                || self.eat(input, out, Newline, parser::newline);
+        }
+        self.eat(
+            input,
+            out,
+            Whitespace,
+            recognize(many1(parser::unicode_space)),
+        ) || self.eat(input, out, MultiLineComment, parser::multi_line_comment)
+            || self.slashdash(input, out, true)
+    }
+
+    /// Consumes a single-line comment and the line ending after it.
+    fn single_line_comment(&self, input: &mut &'a str, out: &mut Vec<KdlCstElement>) -> bool {
+        let comment = match parser::single_line_comment(input) {
+            Ok((_, comment)) => comment,
+            Err(_) => return false,
+        };
+        let newline = [
+            "\r\n", "\r", "\n", "\u{0085}", "\u{000C}", "\u{2028}", "\u{2029}",
+        ]
+        .iter()
+        .find(|newline| comment.ends_with(*newline))
+        .map(|newline| newline.len())
+        .unwrap_or(0);
+        let len = comment.len() - newline;
+        out.push(self.token(KdlTokenKind::SingleLineComment, &input[..len]));
+        *input = &input[len..];
+        self.eat(input, out, KdlTokenKind::Newline, parser::newline);
+        true
+    }
+
+    fn slashdash(&self, input: &mut &'a str, out: &mut Vec<KdlCstElement>, in_node: bool) -> bool {
+        let start = *input;
+        let mut children = Vec::new();
+        if !self.eat(input, &mut children, KdlTokenKind::SlashDash, tag("/-")) {
+            return false;
+        }
+        if in_node {
+            while self.node_trivia(input, &mut children) {}
+            if input.starts_with('{') {
+                children.push(self.children(input).into());
+            } else {
+                children.push(self.entry(input).into());
+            }
+        } else {
+            while self.line_trivia(input, &mut children) {}
+            children.push(self.node_body(input).into());
+        }
+        out.push(self.node(KdlSyntaxKind::SlashDash, start, children).into());
+        true
+    }
+
+    fn annotation(&self, input: &mut &'a str, out: &mut Vec<KdlCstElement>) {
+        let start = *input;
+        let mut children = Vec::new();
+        if self.eat(input, &mut children, KdlTokenKind::OpenParen, tag("(")) {
+            self.identifier(input, &mut children);
+            self.eat(input, &mut children, KdlTokenKind::CloseParen, tag(")"));
+            out.push(
+                self.node(KdlSyntaxKind::TypeAnnotation, start, children)
+                    .into(),
+            );
+        }
+    }
+
+    fn identifier(&self, input: &mut &'a str, out: &mut Vec<KdlCstElement>) -> bool {
+        let res = parser::identifier(&self.parser)(input);
+        match res {
+            Ok((rest, _)) => {
+                out.push(self.token(KdlTokenKind::Identifier, &input[..input.offset(rest)]));
+                *input = rest;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Consumes a node, from its type annotation through its terminator.
+    fn node_body(&self, input: &mut &'a str) -> KdlCstNode {
+        use KdlTokenKind::*;
+        let start = *input;
+        let mut children = Vec::new();
+        self.annotation(input, &mut children);
+        self.identifier(input, &mut children);
+        loop {
+            if input.is_empty()
+                || self.eat(input, &mut children, Semicolon, tag(";"))
+                || self.eat(input, &mut children, Newline, parser::newline)
+                || self.single_line_comment(input, &mut children)
+            {
+                break;
+            }
+            if self.node_trivia(input, &mut children) {
+                continue;
+            }
+            if input.starts_with('{') {
+                children.push(self.children(input).into());
+            } else if input.starts_with('}') {
+                // Only possible for a node with no terminator, at the end of
+                // a children block.
+                break;
+            } else {
+                children.push(self.entry(input).into());
+            }
+        }
+        self.node(KdlSyntaxKind::Node, start, children)
+    }
+
+    fn children(&self, input: &mut &'a str) -> KdlCstNode {
+        let start = *input;
+        let mut children = Vec::new();
+        self.eat(input, &mut children, KdlTokenKind::OpenBrace, tag("{"));
+        children.push(self.document(input, true).into());
+        self.eat(input, &mut children, KdlTokenKind::CloseBrace, tag("}"));
+        self.node(KdlSyntaxKind::Children, start, children)
+    }
+
+    fn entry(&self, input: &mut &'a str) -> KdlCstNode {
+        let start = *input;
+        let mut children = Vec::new();
+        if let Ok((rest, _)) = parser::identifier(&self.parser)(input) {
+            if rest.starts_with('=') {
+                self.identifier(input, &mut children);
+                self.eat(input, &mut children, KdlTokenKind::Equals, tag("="));
+            }
+        }
+        self.annotation(input, &mut children);
+        if let Ok((rest, (_, value))) = parser::value(input) {
+            let kind = match value {
+                KdlValue::String(_) => KdlTokenKind::String,
+                KdlValue::RawString(_) => KdlTokenKind::RawString,
+                KdlValue::Bool(_) => KdlTokenKind::Bool,
+                KdlValue::Null => KdlTokenKind::Null,
+                _ => KdlTokenKind::Number,
+            };
+            children.push(self.token(kind, &input[..input.offset(rest)]));
+            *input = rest;
+        }
+        if children.is_empty() {
+            // Can't happen for valid input, but make sure we always make
+            // progress regardless.
+            let len = input.chars().next().map(char::len_utf8).unwrap_or(0);
+            children.push(self.token(KdlTokenKind::Identifier, &input[..len]));
+            *input = &input[len..];
+        }
+        self.node(KdlSyntaxKind::Entry, start, children)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use KdlSyntaxKind as S;
+    use KdlTokenKind as T;
+
+    fn shape(node: &KdlCstNode) -> String {
+        let mut out = format!("{:?}(", node.kind);
+        for child in &node.children {
+            match child {
+                KdlCstElement::Node(node) => out.push_str(&shape(node)),
+                KdlCstElement::Token(token) => out.push_str(&format!("{:?} ", token.kind)),
+            }
+        }
+        out.push(')');
+        out
+    }
+
+    #[test]
+    fn structure() {
+        let cst: KdlCstNode = r#"(t)foo key=(u8)1 /-"x" \
+    { bar; } // c
+"#
+        .parse()
+        .unwrap();
+        assert_eq!(cst.kind, S::Document);
+        let node = cst.nodes().next().unwrap();
+        assert_eq!(node.kind, S::Node);
+        assert_eq!(
+            shape(node),
+            "Node(TypeAnnotation(OpenParen Identifier CloseParen )Identifier Whitespace \
+             Entry(Identifier Equals TypeAnnotation(OpenParen Identifier CloseParen )Number )\
+             Whitespace SlashDash(SlashDash Entry(String ))Whitespace LineContinuation Newline \
+             Whitespace Children(OpenBrace Document(Whitespace Node(Identifier Semicolon )\
+             Whitespace )CloseBrace )Whitespace SingleLineComment Newline )"
+        );
+    }
+
+    #[test]
+    fn slashdashes() {
+        let input = "/- foo {\n    bar\n}\nbaz /-{ a; } /- 1\n";
+        let cst: KdlCstNode = input.parse().unwrap();
+        assert_eq!(cst.to_string(), input);
+        let kinds = cst.nodes().map(|n| n.kind).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![S::SlashDash, S::Node]);
+        let baz = cst.nodes().nth(1).unwrap();
+        let kinds = baz
+            .nodes()
+            .map(|n| (n.kind, n.nodes().next().map(|n| n.kind)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (S::SlashDash, Some(S::Children)),
+                (S::SlashDash, Some(S::Entry)),
+            ]
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let input = "a 1; b \"two\" {\n  c null\n}";
+        let cst: KdlCstNode = input.parse().unwrap();
+        assert_eq!(cst.span, (0, input.len()).into());
+        let mut end = 0;
+        for token in cst.tokens() {
+            assert_eq!(token.span.offset(), end);
+            assert_eq!(
+                &input[token.span.offset()..][..token.span.len()],
+                token.text
+            );
+            end += token.span.len();
+        }
+        assert_eq!(end, input.len());
+        let b = cst.nodes().nth(1).unwrap();
+        assert_eq!(
+            &input[b.span.offset()..][..b.span.len()],
+            "b \"two\" {\n  c null\n}"
+        );
+        assert_eq!(cst.tokens().iter().filter(|t| t.kind == T::Null).count(), 1);
+    }
+
+    #[test]
+    fn conversions() {
+        let mut doc: KdlDocument = "foo 1".parse().unwrap();
+        doc.get_mut("foo").unwrap().push(2);
+        let cst = doc.to_cst().unwrap();
+        assert_eq!(cst.to_string(), "foo 1 2");
+        assert_eq!(cst.to_document().unwrap().to_string(), doc.to_string());
+        assert!("foo {".parse::<KdlCstNode>().is_err());
+    }
+}
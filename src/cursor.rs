@@ -0,0 +1,362 @@
+use crate::{KdlDocument, KdlNode};
+
+/// A position in a [`KdlDocument`] that can move up, down, and sideways
+/// through the tree, for algorithms that need to look at a node's parent or
+/// neighbors. Created by [`KdlDocument::cursor`].
+///
+/// A cursor is always on a node. It remembers the path it took from the
+/// document root, so moving back to the parent is as cheap as moving to a
+/// child. Each move returns `true` if it succeeded; otherwise the cursor
+/// stays where it was.
+///
+/// # Examples
+///
+/// Finding the `profile` a node belongs to:
+///
+/// ```rust
+/// # use kdl::KdlDocument;
+/// let doc: KdlDocument = r#"
+/// profile "dev" {
+///     server {
+///         port 8080
+///     }
+/// }
+/// "#
+/// .parse()
+/// .unwrap();
+/// let mut cursor = doc.cursor_at(&[0, 0, 0]).unwrap();
+/// assert_eq!(cursor.node().name().value(), "port");
+/// while cursor.node().name().value() != "profile" && cursor.parent() {}
+/// assert_eq!(cursor.node()[0], "dev".into());
+/// ```
+#[derive(Debug, Clone)]
+pub struct KdlCursor<'a> {
+    doc: &'a KdlDocument,
+    path: Vec<usize>,
+}
+
+impl<'a> KdlCursor<'a> {
+    /// The node the cursor is on.
+    pub fn node(&self) -> &'a KdlNode {
+        &siblings(self.doc, &self.path)[self.index()]
+    }
+
+    /// The indices of the cursor's node and each of its ancestors within
+    /// their documents, starting from the document root. Can be passed to
+    /// [`KdlDocument::cursor_at`] to get back here later.
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// How deep the cursor's node is: `0` for top-level nodes, `1` for their
+    /// children, and so on.
+    pub fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+
+    /// The cursor's node and all of its siblings, in document order.
+    pub fn siblings(&self) -> &'a [KdlNode] {
+        siblings(self.doc, &self.path)
+    }
+
+    /// Moves to the node whose children block the cursor's node is in.
+    pub fn parent(&mut self) -> bool {
+        move_to_parent(&mut self.path)
+    }
+
+    /// Moves to the node's first child.
+    pub fn first_child(&mut self) -> bool {
+        if !has_children(self.node()) {
+            return false;
+        }
+        self.path.push(0);
+        true
+    }
+
+    /// Moves to the node after this one in the same document.
+    pub fn next_sibling(&mut self) -> bool {
+        move_to_next_sibling(self.doc, &mut self.path)
+    }
+
+    /// Moves to the node before this one in the same document.
+    pub fn prev_sibling(&mut self) -> bool {
+        move_to_prev_sibling(&mut self.path)
+    }
+}
+
+/// Like [`KdlCursor`], but with mutable access to the node it's on.
+/// Created by [`KdlDocument::cursor_mut`].
+///
+/// Only the current node (and so its entries and descendants) can be
+/// changed, since the cursor relies on the path to it staying put.
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::KdlDocument;
+/// let mut doc: KdlDocument = "a { b; c; }".parse().unwrap();
+/// let mut cursor = doc.cursor_mut().unwrap();
+/// cursor.first_child();
+/// while cursor.next_sibling() {}
+/// cursor.node_mut().push("last");
+/// assert_eq!(doc.to_string(), r#"a { b; c "last"; }"#);
+/// ```
+#[derive(Debug)]
+pub struct KdlCursorMut<'a> {
+    doc: &'a mut KdlDocument,
+    path: Vec<usize>,
+}
+
+impl<'a> KdlCursorMut<'a> {
+    /// The node the cursor is on.
+    pub fn node(&self) -> &KdlNode {
+        &siblings(self.doc, &self.path)[self.index()]
+    }
+
+    /// A mutable reference to the node the cursor is on.
+    pub fn node_mut(&mut self) -> &mut KdlNode {
+        let (index, parents) = self.path.split_last().expect("cursor path is never empty");
+        let mut doc = &mut *self.doc;
+        for &i in parents {
+            doc = doc.nodes_mut()[i]
+                .children_mut()
+                .as_mut()
+                .expect("cursor path only goes through nodes with children");
+        }
+        &mut doc.nodes_mut()[*index]
+    }
+
+    /// See [`KdlCursor::path`].
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// See [`KdlCursor::depth`].
+    pub fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+
+    /// See [`KdlCursor::siblings`].
+    pub fn siblings(&self) -> &[KdlNode] {
+        siblings(self.doc, &self.path)
+    }
+
+    /// See [`KdlCursor::parent`].
+    pub fn parent(&mut self) -> bool {
+        move_to_parent(&mut self.path)
+    }
+
+    /// See [`KdlCursor::first_child`].
+    pub fn first_child(&mut self) -> bool {
+        if !has_children(self.node()) {
+            return false;
+        }
+        self.path.push(0);
+        true
+    }
+
+    /// See [`KdlCursor::next_sibling`].
+    pub fn next_sibling(&mut self) -> bool {
+        move_to_next_sibling(self.doc, &mut self.path)
+    }
+
+    /// See [`KdlCursor::prev_sibling`].
+    pub fn prev_sibling(&mut self) -> bool {
+        move_to_prev_sibling(&mut self.path)
+    }
+}
+
+impl KdlCursor<'_> {
+    fn index(&self) -> usize {
+        *self.path.last().expect("cursor path is never empty")
+    }
+}
+
+impl KdlCursorMut<'_> {
+    fn index(&self) -> usize {
+        *self.path.last().expect("cursor path is never empty")
+    }
+}
+
+impl KdlDocument {
+    /// Returns a cursor on this document's first node, or `None` if it has
+    /// no nodes.
+    pub fn cursor(&self) -> Option<KdlCursor<'_>> {
+        self.cursor_at(&[0])
+    }
+
+    /// Returns a cursor on the node at `path`, as given by
+    /// [`KdlCursor::path`], or `None` if there's no node there.
+    pub fn cursor_at(&self, path: &[usize]) -> Option<KdlCursor<'_>> {
+        valid_path(self, path).then(|| KdlCursor {
+            doc: self,
+            path: path.to_vec(),
+        })
+    }
+
+    /// Returns a cursor on the innermost node whose span covers the byte
+    /// `offset`, the same node [`KdlDocument::node_at`] finds.
+    #[cfg(feature = "span")]
+    pub fn cursor_at_offset(&self, offset: usize) -> Option<KdlCursor<'_>> {
+        let mut path = Vec::new();
+        let mut doc = self;
+        while let Some(i) = doc
+            .nodes()
+            .iter()
+            .position(|node| crate::document::span_contains(node.span(), offset))
+        {
+            path.push(i);
+            match doc.nodes()[i].children() {
+                Some(children) => doc = children,
+                None => break,
+            }
+        }
+        self.cursor_at(&path)
+    }
+
+    /// Returns a mutable cursor on this document's first node, or `None` if
+    /// it has no nodes.
+    pub fn cursor_mut(&mut self) -> Option<KdlCursorMut<'_>> {
+        self.cursor_mut_at(&[0])
+    }
+
+    /// Returns a mutable cursor on the node at `path`, or `None` if there's
+    /// no node there.
+    pub fn cursor_mut_at(&mut self, path: &[usize]) -> Option<KdlCursorMut<'_>> {
+        if !valid_path(self, path) {
+            return None;
+        }
+        Some(KdlCursorMut {
+            doc: self,
+            path: path.to_vec(),
+        })
+    }
+}
+
+fn valid_path(doc: &KdlDocument, path: &[usize]) -> bool {
+    let (index, parents) = match path.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+    let mut doc = doc;
+    for &i in parents {
+        match doc.nodes().get(i).and_then(|node| node.children()) {
+            Some(children) => doc = children,
+            None => return false,
+        }
+    }
+    *index < doc.nodes().len()
+}
+
+/// The nodes in the document containing the node at `path`, which must be
+/// valid.
+fn siblings<'a>(doc: &'a KdlDocument, path: &[usize]) -> &'a [KdlNode] {
+    let mut doc = doc;
+    for &i in &path[..path.len() - 1] {
+        doc = doc.nodes()[i]
+            .children()
+            .expect("cursor path only goes through nodes with children");
+    }
+    doc.nodes()
+}
+
+fn move_to_parent(path: &mut Vec<usize>) -> bool {
+    if path.len() < 2 {
+        return false;
+    }
+    path.pop();
+    true
+}
+
+fn has_children(node: &KdlNode) -> bool {
+    node.children()
+        .map_or(false, |children| !children.nodes().is_empty())
+}
+
+fn move_to_next_sibling(doc: &KdlDocument, path: &mut [usize]) -> bool {
+    let len = siblings(doc, path).len();
+    let index = path.last_mut().expect("cursor path is never empty");
+    if *index + 1 >= len {
+        return false;
+    }
+    *index += 1;
+    true
+}
+
+fn move_to_prev_sibling(path: &mut [usize]) -> bool {
+    let index = path.last_mut().expect("cursor path is never empty");
+    if *index == 0 {
+        return false;
+    }
+    *index -= 1;
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const DOC: &str = "a {\n    b 1\n    c {\n        d\n    }\n}\ne";
+
+    #[test]
+    fn navigation() -> miette::Result<()> {
+        let doc: KdlDocument = DOC.parse()?;
+        let mut cursor = doc.cursor().unwrap();
+        assert_eq!(cursor.node().name().value(), "a");
+        assert!(!cursor.parent());
+        assert!(!cursor.prev_sibling());
+        assert!(cursor.first_child());
+        assert_eq!(cursor.node().name().value(), "b");
+        assert!(!cursor.first_child());
+        assert!(cursor.next_sibling());
+        assert!(!cursor.next_sibling());
+        assert!(cursor.first_child());
+        assert_eq!(cursor.node().name().value(), "d");
+        assert_eq!(cursor.path(), &[0, 1, 0]);
+        assert_eq!(cursor.depth(), 2);
+        assert_eq!(cursor.siblings().len(), 1);
+        assert!(cursor.parent());
+        assert!(cursor.prev_sibling());
+        assert_eq!(cursor.node().name().value(), "b");
+        assert!(cursor.parent());
+        assert!(cursor.next_sibling());
+        assert_eq!(cursor.node().name().value(), "e");
+        assert_eq!(cursor.path(), &[1]);
+
+        assert!(KdlDocument::new().cursor().is_none());
+        assert!(doc.cursor_at(&[]).is_none());
+        assert!(doc.cursor_at(&[0, 2]).is_none());
+        assert!(doc.cursor_at(&[1, 0]).is_none());
+        Ok(())
+    }
+
+    #[cfg(feature = "span")]
+    #[test]
+    fn at_offset() -> miette::Result<()> {
+        let doc: KdlDocument = DOC.parse()?;
+        let offset = DOC.find('d').unwrap();
+        let cursor = doc.cursor_at_offset(offset).unwrap();
+        assert_eq!(cursor.path(), &[0, 1, 0]);
+        assert_eq!(Some(cursor.node()), doc.node_at(offset), "matches node_at");
+        let cursor = doc.cursor_at_offset(DOC.find('b').unwrap()).unwrap();
+        assert_eq!(cursor.path(), &[0, 0]);
+        assert!(doc.cursor_at_offset(DOC.len() + 10).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn editing() -> miette::Result<()> {
+        let mut doc: KdlDocument = DOC.parse()?;
+        let mut cursor = doc.cursor_mut_at(&[0, 1]).unwrap();
+        cursor.node_mut().clear_children();
+        assert!(!cursor.first_child());
+        assert!(cursor.prev_sibling());
+        cursor.node_mut().set_name("bee");
+        assert!(cursor.parent());
+        assert!(cursor.next_sibling());
+        cursor.node_mut().push(2);
+        assert_eq!(doc.to_string(), "a {\n    bee 1\n    c\n}\ne 2");
+        assert!(doc.cursor_mut_at(&[5]).is_none());
+        Ok(())
+    }
+}
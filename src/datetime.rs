@@ -0,0 +1,145 @@
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, SecondsFormat, TimeZone};
+
+use crate::KdlEntry;
+
+/// Integration with [`chrono`], for values using KDL's reserved `(date-time)`,
+/// `(date)`, and `(time)` type annotations. Requires the `chrono` feature.
+impl KdlEntry {
+    /// Parses this entry's value as an [RFC
+    /// 3339](https://www.rfc-editor.org/rfc/rfc3339) date and time, if it's a
+    /// string annotated with `(date-time)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlEntry;
+    /// let entry: KdlEntry = r#"(date-time)"2023-04-01T12:30:00+02:00""#.parse().unwrap();
+    /// let dt = entry.as_datetime().unwrap();
+    /// assert_eq!(dt.timestamp(), 1680345000);
+    ///
+    /// let entry: KdlEntry = r#""2023-04-01T12:30:00+02:00""#.parse().unwrap();
+    /// assert_eq!(entry.as_datetime(), None);
+    /// ```
+    pub fn as_datetime(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(self.annotated_string("date-time")?).ok()
+    }
+
+    /// Parses this entry's value as a `YYYY-MM-DD` date, if it's a string
+    /// annotated with `(date)`.
+    pub fn as_date(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(self.annotated_string("date")?, DATE_FORMAT).ok()
+    }
+
+    /// Parses this entry's value as an `HH:MM:SS` time, with optional
+    /// fractional seconds, if it's a string annotated with `(time)`.
+    pub fn as_time(&self) -> Option<NaiveTime> {
+        NaiveTime::parse_from_str(self.annotated_string("time")?, TIME_FORMAT).ok()
+    }
+
+    /// Creates a new argument holding `datetime`, formatted as RFC 3339 and
+    /// annotated with `(date-time)`. UTC is written as `Z`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlEntry;
+    /// use chrono::{TimeZone, Utc};
+    ///
+    /// let dt = Utc.with_ymd_and_hms(2023, 4, 1, 12, 30, 0).unwrap();
+    /// let entry = KdlEntry::new_datetime(&dt);
+    /// assert_eq!(entry.to_string(), r#"(date-time)"2023-04-01T12:30:00Z""#);
+    /// assert_eq!(entry.as_datetime(), Some(dt.into()));
+    /// ```
+    pub fn new_datetime<Tz: TimeZone>(datetime: &DateTime<Tz>) -> Self
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        let mut entry = KdlEntry::new(datetime.to_rfc3339_opts(SecondsFormat::AutoSi, true));
+        entry.set_ty("date-time");
+        entry
+    }
+
+    /// Creates a new argument holding `date`, formatted as `YYYY-MM-DD` and
+    /// annotated with `(date)`.
+    pub fn new_date(date: NaiveDate) -> Self {
+        let mut entry = KdlEntry::new(date.format(DATE_FORMAT).to_string());
+        entry.set_ty("date");
+        entry
+    }
+
+    /// Creates a new argument holding `time`, formatted as `HH:MM:SS` (plus
+    /// fractional seconds, if there are any) and annotated with `(time)`.
+    pub fn new_time(time: NaiveTime) -> Self {
+        let mut entry = KdlEntry::new(time.format(TIME_FORMAT).to_string());
+        entry.set_ty("time");
+        entry
+    }
+}
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M:%S%.f";
+
+#[cfg(test)]
+mod test {
+    use chrono::Utc;
+
+    use super::*;
+
+    #[test]
+    fn datetimes() {
+        let entry: KdlEntry = r#"(date-time)"2023-04-01T12:30:00.5-07:00""#.parse().unwrap();
+        let dt = entry.as_datetime().unwrap();
+        assert_eq!(dt.offset().local_minus_utc(), -7 * 3600);
+        assert_eq!(dt.timestamp_subsec_millis(), 500);
+        assert_eq!(
+            KdlEntry::new_datetime(&dt).value().as_string(),
+            Some("2023-04-01T12:30:00.500-07:00")
+        );
+
+        let utc = Utc.with_ymd_and_hms(1999, 12, 31, 23, 59, 59).unwrap();
+        let entry = KdlEntry::new_datetime(&utc);
+        assert_eq!(entry.value().as_string(), Some("1999-12-31T23:59:59Z"));
+        let reparsed: KdlEntry = entry.to_string().parse().unwrap();
+        assert_eq!(reparsed.as_datetime(), Some(utc.into()));
+
+        for bad in [
+            r#"(date-time)"2023-04-01""#,
+            r#"(date-time)"not a date""#,
+            r#"(date-time)1"#,
+            r#"(date)"2023-04-01T12:30:00Z""#,
+        ] {
+            let entry: KdlEntry = bad.parse().unwrap();
+            assert_eq!(entry.as_datetime(), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn dates_and_times() {
+        let entry: KdlEntry = r#"(date)"2024-02-29""#.parse().unwrap();
+        let date = entry.as_date().unwrap();
+        assert_eq!(date, NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+        assert_eq!(
+            KdlEntry::new_date(date).to_string(),
+            r#"(date)"2024-02-29""#
+        );
+        let entry: KdlEntry = r#"(date)"2023-02-29""#.parse().unwrap();
+        assert_eq!(entry.as_date(), None);
+
+        let entry: KdlEntry = r#"(time)"08:15:30""#.parse().unwrap();
+        let time = entry.as_time().unwrap();
+        assert_eq!(time, NaiveTime::from_hms_opt(8, 15, 30).unwrap());
+        assert_eq!(KdlEntry::new_time(time).to_string(), r#"(time)"08:15:30""#);
+        let entry: KdlEntry = r#"(time)"08:15:30.25""#.parse().unwrap();
+        let time = entry.as_time().unwrap();
+        assert_eq!(time, NaiveTime::from_hms_milli_opt(8, 15, 30, 250).unwrap());
+        assert_eq!(
+            KdlEntry::new_time(time).value().as_string(),
+            Some("08:15:30.250")
+        );
+
+        let entry: KdlEntry = r#"(date)"2024-02-29""#.parse().unwrap();
+        assert_eq!(entry.as_time(), None);
+        let entry = KdlEntry::new_prop("at", "2024-02-29 08:15:30");
+        assert_eq!(entry.as_date(), None);
+    }
+}
@@ -0,0 +1,171 @@
+use rust_decimal::{
+    prelude::{FromPrimitive, ToPrimitive},
+    Decimal,
+};
+
+use crate::{KdlEntry, KdlValue};
+
+/// Integration with [`rust_decimal`], for exact decimal numbers. Requires the
+/// `rust_decimal` feature.
+///
+/// Numbers are always parsed into an `f64` for [`KdlEntry::value`], but
+/// entries remember the text they were parsed from, so the exact decimal
+/// value can still be recovered from it.
+impl KdlEntry {
+    /// Gets this entry's number as an exact [`Decimal`], if it's annotated
+    /// with `(decimal)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// use rust_decimal::Decimal;
+    ///
+    /// let node: KdlNode = "invoice total=(decimal)1234.10".parse().unwrap();
+    /// let total = node.entry("total").unwrap().as_decimal();
+    /// assert_eq!(total, Some(Decimal::new(123410, 2)));
+    /// ```
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        if self.ty()?.value() == "decimal" {
+            self.to_decimal()
+        } else {
+            None
+        }
+    }
+
+    /// Gets this entry's number as an exact [`Decimal`], regardless of its
+    /// annotation. Non-integer numbers are converted from the text they were
+    /// parsed from rather than from their `f64` value, so `0.1` is exactly
+    /// `0.1`. Returns `None` for non-numbers and for numbers `Decimal` can't
+    /// represent.
+    pub fn to_decimal(&self) -> Option<Decimal> {
+        match self.value() {
+            KdlValue::Base10Float(float) => match self.value_repr() {
+                Some(repr) => parse_decimal(repr),
+                None => Decimal::try_from(*float).ok(),
+            },
+            KdlValue::Base2(int)
+            | KdlValue::Base8(int)
+            | KdlValue::Base10(int)
+            | KdlValue::Base16(int) => Decimal::from_i128(*int),
+            _ => None,
+        }
+    }
+}
+
+/// Creates a new argument holding the decimal, annotated with `(decimal)`.
+/// Its exact digits are kept as the entry's representation, and survive
+/// [`KdlEntry::fmt`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::KdlEntry;
+/// use rust_decimal::Decimal;
+///
+/// let entry = KdlEntry::from(Decimal::new(1999, 2));
+/// assert_eq!(entry.to_string(), "(decimal)19.99");
+/// ```
+impl From<Decimal> for KdlEntry {
+    fn from(decimal: Decimal) -> Self {
+        let mut entry = if decimal.scale() == 0 {
+            match decimal.to_i64() {
+                Some(int) => KdlEntry::new(int),
+                None => KdlEntry::new(decimal.to_f64().unwrap_or(f64::NAN)),
+            }
+        } else {
+            KdlEntry::new(decimal.to_f64().unwrap_or(f64::NAN))
+        };
+        if matches!(entry.value(), KdlValue::Base10Float(_)) {
+            let mut repr = decimal.to_string();
+            if !repr.contains('.') {
+                repr.push_str(".0");
+            }
+            entry.set_value_repr(repr);
+        }
+        entry.set_ty("decimal");
+        entry
+    }
+}
+
+fn parse_decimal(repr: &str) -> Option<Decimal> {
+    let digits = repr.replace('_', "");
+    let digits = digits.strip_prefix('+').unwrap_or(&digits);
+    if digits.contains(['e', 'E']) {
+        Decimal::from_scientific(digits).ok()
+    } else {
+        digits.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::KdlDocument;
+
+    fn entry(input: &str) -> KdlEntry {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(entry("(decimal)0.1").as_decimal(), Some(Decimal::new(1, 1)));
+        assert_eq!(
+            entry("(decimal)-1_000.25").as_decimal(),
+            Some(Decimal::new(-100025, 2))
+        );
+        assert_eq!(
+            entry("(decimal)+2.5").as_decimal(),
+            Some(Decimal::new(25, 1))
+        );
+        assert_eq!(
+            entry("(decimal)1.5e3").as_decimal(),
+            Some(Decimal::from(1500))
+        );
+        assert_eq!(
+            entry("(decimal)2.5E-2").as_decimal(),
+            Some(Decimal::new(25, 3))
+        );
+        assert_eq!(entry("(decimal)0x10").as_decimal(), Some(Decimal::from(16)));
+        assert_eq!(entry("(decimal)1e400").as_decimal(), None);
+        assert_eq!(entry("(decimal)\"1.5\"").as_decimal(), None);
+        assert_eq!(entry("0.1").as_decimal(), None);
+        assert_eq!(entry("0.1").to_decimal(), Some(Decimal::new(1, 1)));
+        assert_eq!(KdlEntry::new(0.5).to_decimal(), Some(Decimal::new(5, 1)));
+    }
+
+    #[test]
+    fn round_trips() {
+        for decimal in [
+            Decimal::new(1999, 2),
+            Decimal::new(-5, 1),
+            Decimal::new(100, 2),
+            Decimal::from(42),
+            Decimal::new(123_456_789_012_345_678, 10),
+            Decimal::new(1, 28),
+        ] {
+            let entry = KdlEntry::from(decimal);
+            let reparsed = entry.to_string().parse::<KdlEntry>().unwrap();
+            assert_eq!(reparsed.as_decimal(), Some(decimal), "{}", entry);
+        }
+        assert_eq!(KdlEntry::from(Decimal::from(42)).to_string(), "(decimal)42");
+        assert_eq!(
+            KdlEntry::from(Decimal::new(100, 2)).to_string(),
+            "(decimal)1.00"
+        );
+    }
+
+    #[test]
+    fn survives_fmt() {
+        let mut doc: KdlDocument = "price    (decimal)0.30000000000000001 0.10"
+            .parse()
+            .unwrap();
+        doc.fmt();
+        assert_eq!(doc.to_string(), "price (decimal)0.30000000000000001 0.1\n");
+        let entry = &doc.nodes()[0].entries()[0];
+        assert_eq!(
+            entry.as_decimal(),
+            Some(Decimal::new(30000000000000001, 17))
+        );
+    }
+}
@@ -0,0 +1,559 @@
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::util::ErrorSpan;
+use crate::{FromKdlValue, KdlConversionError, KdlNode, NodeKey};
+
+/// Conversion from a [`KdlNode`] into a Rust type.
+///
+/// With the `macros` feature, this can be derived for structs with named
+/// fields. Each field needs an attribute saying where in the node it comes
+/// from:
+///
+/// * `#[kdl(argument)]`: the next argument, in field order, converted with
+///   [`FromKdlValue`].
+/// * `#[kdl(property)]`: the property with the field's name, converted with
+///   [`FromKdlValue`].
+/// * `#[kdl(child)]`: the one child node with the field's name, decoded with
+///   [`DecodeKdl`]. An `Option` field allows the child to be left out.
+/// * `#[kdl(children)]`: a `Vec` of every child node, or with
+///   `#[kdl(children, name = "...")]`, of every child with that name.
+///
+/// Names are the field's name in kebab-case unless given with
+/// `name = "..."`. Anything in the node that no field asks for is an error,
+/// which catches typos in hand-written configuration.
+///
+/// The node's own name isn't checked, since whoever is decoding it has
+/// usually picked it out by name already.
+///
+/// Enums can be derived too, and pick a variant based on the node's name,
+/// so a `#[kdl(children)]` field of an enum type can hold a mix of
+/// differently-named children. Unit variants take nodes with nothing in
+/// them, struct variants take their fields from the node like a struct
+/// does, and variants with a single unnamed field decode the whole node into
+/// it. Enums with only unit variants also implement [`FromKdlValue`] (and
+/// `From<T> for KdlValue`, with [`EncodeKdl`]), so they can be used as
+/// arguments and properties holding keywords like `level="warn"`.
+///
+/// Variants are named in kebab-case unless given `#[kdl(name = "...")]`.
+/// `#[kdl(rename_all = "...")]` on the type picks another convention for
+/// its variants, or for a struct's fields. It takes the same names as
+/// serde's: `lowercase`, `UPPERCASE`, `PascalCase`, `camelCase`,
+/// `snake_case`, `SCREAMING_SNAKE_CASE`, `kebab-case`, and
+/// `SCREAMING-KEBAB-CASE`.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "macros")]
+/// # {
+/// use kdl::{DecodeKdl, KdlNode};
+///
+/// #[derive(DecodeKdl, Debug, PartialEq)]
+/// struct Server {
+///     #[kdl(argument)]
+///     host: String,
+///     #[kdl(property)]
+///     port: u16,
+///     #[kdl(property, name = "max-conn")]
+///     connections: Option<u32>,
+///     #[kdl(children, name = "route")]
+///     routes: Vec<Route>,
+/// }
+///
+/// #[derive(DecodeKdl, Debug, PartialEq)]
+/// struct Route {
+///     #[kdl(argument)]
+///     path: String,
+/// }
+///
+/// let node: KdlNode = r#"server "localhost" port=8080 {
+///     route "/"
+///     route "/api"
+/// }"#
+/// .parse()
+/// .unwrap();
+/// let server = Server::decode_kdl(&node).unwrap();
+/// assert_eq!(server.port, 8080);
+/// assert_eq!(server.connections, None);
+/// assert_eq!(server.routes[1].path, "/api");
+///
+/// let node: KdlNode = r#"server "localhost" prot=8080"#.parse().unwrap();
+/// assert_eq!(
+///     Server::decode_kdl(&node).unwrap_err().to_string(),
+///     "Node `server` is missing property `port`, expected u16."
+/// );
+/// # }
+/// ```
+///
+/// With enums:
+///
+/// ```rust
+/// # #[cfg(feature = "macros")]
+/// # {
+/// use kdl::{DecodeKdl, KdlNode};
+///
+/// #[derive(DecodeKdl, Debug, PartialEq)]
+/// struct Logger {
+///     #[kdl(property)]
+///     level: Level,
+///     #[kdl(children)]
+///     outputs: Vec<Output>,
+/// }
+///
+/// #[derive(DecodeKdl, Debug, PartialEq)]
+/// #[kdl(rename_all = "lowercase")]
+/// enum Level {
+///     Debug,
+///     Warn,
+/// }
+///
+/// #[derive(DecodeKdl, Debug, PartialEq)]
+/// enum Output {
+///     Stderr,
+///     File {
+///         #[kdl(argument)]
+///         path: String,
+///     },
+/// }
+///
+/// let node: KdlNode = r#"logger level="warn" {
+///     stderr
+///     file "app.log"
+/// }"#
+/// .parse()
+/// .unwrap();
+/// let logger = Logger::decode_kdl(&node).unwrap();
+/// assert_eq!(logger.level, Level::Warn);
+/// assert_eq!(
+///     logger.outputs,
+///     vec![Output::Stderr, Output::File { path: "app.log".into() }]
+/// );
+/// # }
+/// ```
+///
+/// Implementing it by hand is mostly a matter of asking a [`KdlDecoder`] for
+/// each piece, which also takes care of rejecting anything left over:
+///
+/// ```rust
+/// use kdl::{DecodeKdl, KdlDecodeError, KdlDecoder, KdlNode};
+///
+/// struct Dependency {
+///     name: String,
+///     optional: bool,
+/// }
+///
+/// impl DecodeKdl for Dependency {
+///     fn decode_kdl(node: &KdlNode) -> Result<Self, KdlDecodeError> {
+///         let mut decoder = KdlDecoder::new(node);
+///         let dep = Dependency {
+///             name: decoder.argument()?,
+///             optional: decoder.property::<Option<bool>>("optional")?.unwrap_or(false),
+///         };
+///         decoder.finish()?;
+///         Ok(dep)
+///     }
+/// }
+///
+/// let node: KdlNode = r#"dep "nom" optional=true"#.parse().unwrap();
+/// assert!(Dependency::decode_kdl(&node).unwrap().optional);
+/// ```
+#[allow(clippy::result_large_err)]
+pub trait DecodeKdl: Sized {
+    /// Decodes `node`.
+    fn decode_kdl(node: &KdlNode) -> Result<Self, KdlDecodeError>;
+}
+
+/// Conversion from a Rust type into a [`KdlNode`].
+///
+/// With the `macros` feature, this can be derived using the same field
+/// attributes as [`DecodeKdl`]. The node is named after the type in
+/// kebab-case, or `#[kdl(name = "...")]` on the type, although nodes
+/// encoded as `child` fields, or as `children` fields with a `name`, are
+/// renamed to match. Fields that are `None` are left out, except for
+/// arguments, which are written as `null` so that the ones after them keep
+/// their positions.
+///
+/// Values are converted with [`KdlValue::from`](crate::KdlValue), so field
+/// types need to be `Clone`.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "macros")]
+/// # {
+/// use kdl::EncodeKdl;
+///
+/// #[derive(EncodeKdl)]
+/// #[kdl(name = "dep")]
+/// struct Dependency {
+///     #[kdl(argument)]
+///     name: String,
+///     #[kdl(property)]
+///     version: Option<String>,
+/// }
+///
+/// let dep = Dependency {
+///     name: "miette".into(),
+///     version: None,
+/// };
+/// assert_eq!(dep.encode_kdl().to_string(), r#"dep "miette""#);
+/// # }
+/// ```
+pub trait EncodeKdl {
+    /// Encodes `self` as a new node.
+    fn encode_kdl(&self) -> KdlNode;
+}
+
+/// Error returned by [`DecodeKdl::decode_kdl`].
+///
+/// Every variant carries the span of the part of the document it's about,
+/// so it can be rendered as a [`miette::Diagnostic`] once the source is
+/// attached with [`miette::Report::with_source_code`]. Spans are `0..0`
+/// without the `span` feature.
+#[derive(Debug, Diagnostic, Clone, Eq, PartialEq, Error)]
+#[non_exhaustive]
+pub enum KdlDecodeError {
+    /// An argument or property was missing or had the wrong kind of value.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Conversion(#[from] KdlConversionError),
+
+    /// A required child node was missing.
+    #[error("Node `{node}` is missing child `{child}`.")]
+    #[diagnostic(code(kdl::decode::missing_child))]
+    MissingChild {
+        /// Name of the node being decoded.
+        node: String,
+        /// Name of the child that was expected.
+        child: String,
+        /// Span of the node being decoded.
+        #[label("expected a `{}` child", child)]
+        span: SourceSpan,
+    },
+
+    /// More than one child node had a name that only one was expected for.
+    #[error("Node `{node}` has more than one `{child}` child.")]
+    #[diagnostic(code(kdl::decode::duplicate_child))]
+    DuplicateChild {
+        /// Name of the node being decoded.
+        node: String,
+        /// Name of the duplicated child.
+        child: String,
+        /// Span of the second child with that name.
+        #[label("duplicate child")]
+        span: SourceSpan,
+    },
+
+    /// The node had an argument or property that nothing asked for.
+    #[error("Unexpected {} on node `{node}`.", describe_key(key))]
+    #[diagnostic(code(kdl::decode::unexpected_entry))]
+    UnexpectedEntry {
+        /// Name of the node being decoded.
+        node: String,
+        /// The leftover property name or argument index.
+        key: NodeKey,
+        /// Span of the leftover entry.
+        #[label("unexpected")]
+        span: SourceSpan,
+    },
+
+    /// The node's name wasn't one of the ones that could be decoded, such
+    /// as the names of an enum's variants.
+    #[error("Unknown node `{node}`, expected {}.", describe_names(expected))]
+    #[diagnostic(code(kdl::decode::unknown_node))]
+    UnknownNode {
+        /// Name of the node being decoded.
+        node: String,
+        /// The names that would have been accepted.
+        expected: &'static [&'static str],
+        /// Span of the node.
+        #[label("unknown node")]
+        span: SourceSpan,
+    },
+
+    /// The node had a child that nothing asked for.
+    #[error("Unexpected child `{child}` in node `{node}`.")]
+    #[diagnostic(code(kdl::decode::unexpected_child))]
+    UnexpectedChild {
+        /// Name of the node being decoded.
+        node: String,
+        /// Name of the leftover child.
+        child: String,
+        /// Span of the leftover child.
+        #[label("unexpected")]
+        span: SourceSpan,
+    },
+}
+
+fn describe_names(names: &[&str]) -> String {
+    match names {
+        [] => "nothing".into(),
+        [name] => format!("`{}`", name),
+        _ => {
+            let names: Vec<_> = names.iter().map(|name| format!("`{}`", name)).collect();
+            format!("one of {}", names.join(", "))
+        }
+    }
+}
+
+fn describe_key(key: &NodeKey) -> String {
+    match key {
+        NodeKey::Key(key) => format!("property `{}`", key.value()),
+        NodeKey::Index(idx) => format!("argument {}", idx),
+    }
+}
+
+/// Helper for implementing [`DecodeKdl`], and what the derive uses under
+/// the hood. Takes arguments, properties, and children from a node one at a
+/// time, keeping track of which ones have been used so that
+/// [`KdlDecoder::finish`] can reject the rest.
+#[derive(Debug, Clone)]
+pub struct KdlDecoder<'a> {
+    node: &'a KdlNode,
+    args: usize,
+    props: Vec<&'a str>,
+    children: Vec<&'a str>,
+    all_children: bool,
+}
+
+// Errors are the uncommon path here, and a flat struct is much easier to
+// inspect than a boxed one.
+#[allow(clippy::result_large_err)]
+impl<'a> KdlDecoder<'a> {
+    /// Starts decoding `node`.
+    pub fn new(node: &'a KdlNode) -> Self {
+        Self {
+            node,
+            args: 0,
+            props: Vec::new(),
+            children: Vec::new(),
+            all_children: false,
+        }
+    }
+
+    /// The node being decoded.
+    pub fn node(&self) -> &'a KdlNode {
+        self.node
+    }
+
+    /// Converts the next argument. With an `Option`, a missing argument is
+    /// `None`.
+    pub fn argument<T: FromKdlValue>(&mut self) -> Result<T, KdlDecodeError> {
+        self.args += 1;
+        Ok(self.node.arg_as(self.args - 1)?)
+    }
+
+    /// Converts the property called `name`. With an `Option`, a missing
+    /// property is `None`.
+    pub fn property<T: FromKdlValue>(&mut self, name: &'a str) -> Result<T, KdlDecodeError> {
+        self.props.push(name);
+        Ok(self.node.get_as(name)?)
+    }
+
+    /// Decodes the child called `name`, which must appear exactly once.
+    pub fn child<T: DecodeKdl>(&mut self, name: &'a str) -> Result<T, KdlDecodeError> {
+        self.optional_child(name)?
+            .ok_or_else(|| KdlDecodeError::MissingChild {
+                node: self.node.name().value().into(),
+                child: name.into(),
+                span: self.node.error_span(),
+            })
+    }
+
+    /// Decodes the child called `name`, or returns `None` if there isn't
+    /// one. It's still an error for it to appear more than once.
+    pub fn optional_child<T: DecodeKdl>(
+        &mut self,
+        name: &'a str,
+    ) -> Result<Option<T>, KdlDecodeError> {
+        self.children.push(name);
+        let mut found = self.node.children_named(name);
+        let child = match found.next() {
+            Some(child) => child,
+            None => return Ok(None),
+        };
+        if let Some(duplicate) = found.next() {
+            return Err(KdlDecodeError::DuplicateChild {
+                node: self.node.name().value().into(),
+                child: name.into(),
+                span: duplicate.error_span(),
+            });
+        }
+        T::decode_kdl(child).map(Some)
+    }
+
+    /// Decodes every child called `name`, in order.
+    pub fn children_named<T: DecodeKdl>(
+        &mut self,
+        name: &'a str,
+    ) -> Result<Vec<T>, KdlDecodeError> {
+        self.children.push(name);
+        self.node.children_named(name).map(T::decode_kdl).collect()
+    }
+
+    /// Decodes every child, in order, except for those with a name that's
+    /// already been asked for. Call this after the other methods so that
+    /// their children can be told apart from these.
+    ///
+    /// This is where enums come in handy, since each variant can decode a
+    /// differently-named child.
+    pub fn children<T: DecodeKdl>(&mut self) -> Result<Vec<T>, KdlDecodeError> {
+        self.all_children = true;
+        let children = self.node.children().map_or(&[][..], |doc| doc.nodes());
+        children
+            .iter()
+            .filter(|child| !self.children.contains(&child.name().value()))
+            .map(T::decode_kdl)
+            .collect()
+    }
+
+    /// Returns the error for a node whose name isn't one of `expected`, for
+    /// decoders that pick what to decode based on the node's name.
+    pub fn unknown_node(&self, expected: &'static [&'static str]) -> KdlDecodeError {
+        KdlDecodeError::UnknownNode {
+            node: self.node.name().value().into(),
+            expected,
+            span: self.node.error_span(),
+        }
+    }
+
+    /// Checks that everything in the node has been used, returning an error
+    /// for the first argument, property, or child that hasn't.
+    pub fn finish(self) -> Result<(), KdlDecodeError> {
+        let mut args = 0;
+        for entry in self.node.entries() {
+            let key = match entry.name() {
+                Some(name) if self.props.contains(&name.value()) => continue,
+                Some(name) => NodeKey::Key(name.clone()),
+                None => {
+                    args += 1;
+                    if args <= self.args {
+                        continue;
+                    }
+                    NodeKey::Index(args - 1)
+                }
+            };
+            return Err(KdlDecodeError::UnexpectedEntry {
+                node: self.node.name().value().into(),
+                key,
+                #[cfg(feature = "span")]
+                span: entry.span(),
+                #[cfg(not(feature = "span"))]
+                span: SourceSpan::from(0..0),
+            });
+        }
+        if self.all_children {
+            return Ok(());
+        }
+        let children = self.node.children().map_or(&[][..], |doc| doc.nodes());
+        match children
+            .iter()
+            .find(|child| !self.children.contains(&child.name().value()))
+        {
+            Some(child) => Err(KdlDecodeError::UnexpectedChild {
+                node: self.node.name().value().into(),
+                child: child.name().value().into(),
+                span: child.error_span(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Dep {
+        name: String,
+        features: Vec<Feature>,
+        source: Option<Feature>,
+    }
+
+    #[derive(Debug)]
+    struct Feature(String);
+
+    impl DecodeKdl for Feature {
+        fn decode_kdl(node: &KdlNode) -> Result<Self, KdlDecodeError> {
+            let mut decoder = KdlDecoder::new(node);
+            let name = decoder.argument()?;
+            decoder.finish()?;
+            Ok(Feature(name))
+        }
+    }
+
+    impl DecodeKdl for Dep {
+        fn decode_kdl(node: &KdlNode) -> Result<Self, KdlDecodeError> {
+            let mut decoder = KdlDecoder::new(node);
+            let dep = Dep {
+                name: decoder.property("name")?,
+                features: decoder.children_named("feature")?,
+                source: decoder.optional_child("source")?,
+            };
+            decoder.finish()?;
+            Ok(dep)
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn decode(input: &str) -> Result<Dep, KdlDecodeError> {
+        Dep::decode_kdl(&input.parse().unwrap())
+    }
+
+    #[test]
+    fn decoding() {
+        let dep = decode(r#"dep name="nom" { feature "std"; feature "alloc"; }"#).unwrap();
+        assert_eq!(dep.name, "nom");
+        assert_eq!(dep.features.len(), 2);
+        assert_eq!(dep.features[1].0, "alloc");
+        assert!(dep.source.is_none());
+
+        let dep = decode(r#"dep name="a" { source "git"; }"#).unwrap();
+        assert_eq!(dep.source.unwrap().0, "git");
+    }
+
+    #[test]
+    fn errors() {
+        let input = r#"dep name="a" extra=1 { feature "x"; }"#;
+        let err = decode(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unexpected property `extra` on node `dep`."
+        );
+        #[cfg(feature = "span")]
+        assert!(
+            matches!(err, KdlDecodeError::UnexpectedEntry { span, .. } if span == (13..20).into())
+        );
+
+        let err = decode(r#"dep name="a" 1"#).unwrap_err();
+        assert_eq!(err.to_string(), "Unexpected argument 0 on node `dep`.");
+
+        let err = decode(r#"dep name="a" { features "x"; }"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unexpected child `features` in node `dep`."
+        );
+
+        let err = decode(r#"dep name="a" { source "a"; source "b"; }"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Node `dep` has more than one `source` child."
+        );
+        #[cfg(feature = "span")]
+        assert!(
+            matches!(err, KdlDecodeError::DuplicateChild { span, .. } if span == (27..37).into())
+        );
+
+        // Errors from children come through as-is.
+        let input = r#"dep name="a" { feature 1; }"#;
+        let err = decode(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Expected argument 0 of node `feature` to be string, but found `1`."
+        );
+        #[cfg(feature = "span")]
+        assert!(matches!(err, KdlDecodeError::Conversion(err) if err.span == (23..24).into()));
+    }
+}
@@ -2,7 +2,12 @@
 use miette::SourceSpan;
 use std::{fmt::Display, str::FromStr};
 
-use crate::{parser, IntoKdlQuery, KdlError, KdlNode, KdlQueryIterator, KdlValue, NodeKey};
+use crate::{
+    nodes::KdlNodes, parser, IntoKdlQuery, KdlError, KdlNode, KdlQueryIterator, KdlValue,
+    KdlVersion, KdlWarning, NodeKey, ParseOptions,
+};
+#[cfg(feature = "span")]
+use crate::{KdlEntry, KdlTextEdit};
 
 /// Represents a KDL
 /// [`Document`](https://github.com/kdl-org/kdl/blob/main/SPEC.md#document).
@@ -21,8 +26,11 @@ use crate::{parser, IntoKdlQuery, KdlError, KdlNode, KdlQueryIterator, KdlValue,
 #[derive(Debug, Clone, Eq)]
 pub struct KdlDocument {
     pub(crate) leading: Option<String>,
-    pub(crate) nodes: Vec<KdlNode>,
+    pub(crate) nodes: KdlNodes,
+    /// Slashdashed nodes after the last node.
+    pub(crate) disabled: Vec<KdlNode>,
     pub(crate) trailing: Option<String>,
+    pub(crate) version: KdlVersion,
     #[cfg(feature = "span")]
     pub(crate) span: SourceSpan,
 }
@@ -31,7 +39,9 @@ impl PartialEq for KdlDocument {
     fn eq(&self, other: &Self) -> bool {
         self.leading == other.leading
             && self.nodes == other.nodes
+            && self.disabled == other.disabled
             && self.trailing == other.trailing
+        // Intentionally omitted: self.version == other.version
         // Intentionally omitted: self.span == other.span
     }
 }
@@ -40,7 +50,9 @@ impl std::hash::Hash for KdlDocument {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.leading.hash(state);
         self.nodes.hash(state);
+        self.disabled.hash(state);
         self.trailing.hash(state);
+        // Intentionally omitted: self.version.hash(state)
         // Intentionally omitted: self.span.hash(state)
     }
 }
@@ -50,7 +62,9 @@ impl Default for KdlDocument {
         Self {
             leading: Default::default(),
             nodes: Default::default(),
+            disabled: Default::default(),
             trailing: Default::default(),
+            version: KdlVersion::V1,
             #[cfg(feature = "span")]
             span: SourceSpan::from(0..0),
         }
@@ -65,9 +79,11 @@ impl KdlDocument {
 
     /// Gets this document's span.
     ///
-    /// This value will be properly initialized when created via [`KdlDocument::parse`]
-    /// but may become invalidated if the document is mutated. We do not currently
-    /// guarantee this to yield any particularly consistent results at that point.
+    /// For a node's children block, the span covers everything between (but
+    /// not including) the braces.
+    ///
+    /// This value will be properly initialized when parsed with [`str::parse`].
+    /// See [`KdlNode::span`] for how spans are kept up to date after edits.
     #[cfg(feature = "span")]
     pub fn span(&self) -> SourceSpan {
         self.span
@@ -79,16 +95,200 @@ impl KdlDocument {
         self.span = span.into();
     }
 
+    /// Gets the KDL version this document is written in.
+    ///
+    /// This is the version it was parsed as (see
+    /// [`KdlDocument::parse_auto`]), or the one it was converted to with
+    /// [`KdlDocument::to_v2`] or [`KdlDocument::to_v1`]. Documents built
+    /// with [`KdlDocument::new`] are [`KdlVersion::V1`].
+    pub fn version(&self) -> KdlVersion {
+        self.version
+    }
+
+    /// Recomputes the spans of this document and everything in it so they
+    /// point into its current string representation, as if
+    /// `doc.to_string()` had been parsed again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlDocument, KdlNode};
+    /// let mut doc: KdlDocument = "foo 1\n".parse().unwrap();
+    /// doc.nodes_mut().insert(0, KdlNode::new("bar"));
+    /// doc.recompute_spans();
+    ///
+    /// let output = doc.to_string();
+    /// let span = doc.get("foo").unwrap().span();
+    /// assert_eq!(&output[span.offset()..][..span.len()], "foo 1");
+    /// ```
+    #[cfg(feature = "span")]
+    pub fn recompute_spans(&mut self) {
+        self.recompute_spans_at(0, 0, self.version);
+    }
+
+    /// Applies `edit` to this document's text and updates the document to
+    /// match, re-parsing only the top-level nodes the edit touches (plus one
+    /// on either side) and splicing them in, instead of the whole document.
+    ///
+    /// The edit's span is relative to this document's current
+    /// [`Display`](std::fmt::Display) output, and this document's spans need
+    /// to line up with it: that's the case for freshly parsed documents,
+    /// after [`KdlDocument::recompute_spans`], and after a previous call to
+    /// this method. Unchanged nodes are kept as they are, with their spans
+    /// moved to account for the edit.
+    ///
+    /// When the edit's effects can't be contained to the nodes around it (for
+    /// example, when it opens a children block or a multi-line comment), this
+    /// falls back to re-parsing the whole document. If the edited text fails
+    /// to parse, the error is returned and the document is left unmodified.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlDocument, KdlTextEdit};
+    /// let mut doc: KdlDocument = "foo 1\nbar 2\nbaz 3\n".parse().unwrap();
+    /// doc.reparse(&KdlTextEdit {
+    ///     span: (10, 1).into(),
+    ///     replacement: "20".into(),
+    /// })
+    /// .unwrap();
+    /// assert_eq!(doc.to_string(), "foo 1\nbar 20\nbaz 3\n");
+    /// assert_eq!(doc.get_arg("bar"), Some(&20.into()));
+    /// assert_eq!(doc.get("baz").unwrap().span(), (13, 5).into());
+    /// ```
+    #[cfg(feature = "span")]
+    pub fn reparse(&mut self, edit: &KdlTextEdit) -> Result<(), KdlError> {
+        self.reparse_with_options(edit, &ParseOptions::default())
+    }
+
+    /// Applies `edit` like [`KdlDocument::reparse`], parsing the edited text
+    /// with the given [`ParseOptions`]. The text is always parsed as this
+    /// document's [`KdlDocument::version`], whatever `options.version` is.
+    ///
+    /// If the edit's span runs past the end of the document's text, or
+    /// starts or ends in the middle of a character, an
+    /// [`KdlErrorKind::InvalidEdit`](crate::KdlErrorKind::InvalidEdit) error
+    /// is returned.
+    #[cfg(feature = "span")]
+    pub fn reparse_with_options(
+        &mut self,
+        edit: &KdlTextEdit,
+        options: &ParseOptions,
+    ) -> Result<(), KdlError> {
+        let options = ParseOptions {
+            version: self.version,
+            ..options.clone()
+        };
+        let text = self.to_string();
+        edit.check(&text)?;
+        if !self.reparse_nodes(edit, &options) {
+            let text = KdlTextEdit::apply(&text, std::slice::from_ref(edit));
+            *self = KdlDocument::parse_with_options(&text, &options)?;
+        }
+        Ok(())
+    }
+
+    /// Gets the innermost node whose span covers the byte `offset`, if any.
+    ///
+    /// An offset covers a node if it falls anywhere from the start of its
+    /// type annotation or name up to (but not including) the end of its
+    /// last entry or closing brace. Leading and trailing whitespace and
+    /// comments don't belong to any node.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = "foo {\n    bar 1\n}".parse().unwrap();
+    /// assert_eq!(doc.node_at(1).unwrap().name().value(), "foo");
+    /// assert_eq!(doc.node_at(11).unwrap().name().value(), "bar");
+    /// assert_eq!(doc.node_at(5), doc.get("foo"));
+    /// ```
+    #[cfg(feature = "span")]
+    pub fn node_at(&self, offset: usize) -> Option<&KdlNode> {
+        let node = self
+            .nodes
+            .iter()
+            .find(|node| span_contains(node.span(), offset))?;
+        node.children()
+            .and_then(|children| children.node_at(offset))
+            .or(Some(node))
+    }
+
+    /// Gets the entry whose span covers the byte `offset`, if any. This
+    /// only looks at the entries of the innermost node found by
+    /// [`KdlDocument::node_at`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = "foo 1 bar=2".parse().unwrap();
+    /// assert_eq!(doc.entry_at(4).unwrap().value(), &1.into());
+    /// assert_eq!(doc.entry_at(8).unwrap().name().unwrap().value(), "bar");
+    /// assert_eq!(doc.entry_at(1), None);
+    /// ```
+    #[cfg(feature = "span")]
+    pub fn entry_at(&self, offset: usize) -> Option<&KdlEntry> {
+        self.node_at(offset)?
+            .entries()
+            .iter()
+            .find(|entry| span_contains(entry.span(), offset))
+    }
+
     /// Gets the first child node with a matching name.
     pub fn get(&self, name: &str) -> Option<&KdlNode> {
-        self.nodes.iter().find(move |n| n.name().value() == name)
+        let idx = self.nodes.position(name)?;
+        Some(&self.nodes[idx])
     }
 
     /// Gets a reference to the first child node with a matching name.
     pub fn get_mut(&mut self, name: &str) -> Option<&mut KdlNode> {
+        let idx = self.nodes.position(name)?;
+        Some(self.nodes.node_mut(idx))
+    }
+
+    /// Gets every child node with a matching name, in document order.
+    ///
+    /// Repeating a node is how KDL usually expresses a list, so prefer this
+    /// over [`KdlDocument::get`] whenever a name can appear more than once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = "server \"a\"\nport 80\nserver \"b\"".parse().unwrap();
+    /// let servers: Vec<_> = doc
+    ///     .get_all("server")
+    ///     .filter_map(|node| node.get(0)?.as_string())
+    ///     .collect();
+    /// assert_eq!(servers, vec!["a", "b"]);
+    /// ```
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a KdlNode> + 'a {
+        let (indexed, searched) = match self.nodes.positions(name) {
+            Some(positions) => (
+                Some(positions.into_iter().map(move |idx| &self.nodes[idx])),
+                None,
+            ),
+            None => (
+                None,
+                Some(self.nodes.iter().filter(move |n| n.name().value() == name)),
+            ),
+        };
+        indexed
+            .into_iter()
+            .flatten()
+            .chain(searched.into_iter().flatten())
+    }
+
+    /// Gets every child node with a matching name, mutably.
+    pub fn get_all_mut<'a>(
+        &'a mut self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a mut KdlNode> + 'a {
         self.nodes
             .iter_mut()
-            .find(move |n| n.name().value() == name)
+            .filter(move |n| n.name().value() == name)
     }
 
     /// Gets the first argument (value) of the first child node with a
@@ -173,13 +373,51 @@ impl KdlDocument {
     /// ```
     pub fn get_dash_args(&self, name: &str) -> Vec<&KdlValue> {
         self.get(name)
-            .and_then(|n| n.children())
-            .map(|doc| doc.nodes())
+            .map(|node| node.dash_values().collect())
             .unwrap_or_default()
-            .iter()
-            .filter(|e| e.name().value() == "-")
-            .filter_map(|e| e.get(0))
-            .collect()
+    }
+
+    /// Turns an index of this document's child nodes by name on or off.
+    /// It's off by default.
+    ///
+    /// Without the index, [`KdlDocument::get`], [`KdlDocument::get_all`],
+    /// and the methods built on them search the nodes one by one, which is
+    /// fastest for the usual document, but slow for ones with many
+    /// thousands of nodes. With it, they take constant time, after the
+    /// index is built on the first lookup.
+    ///
+    /// The index stays in sync with the nodes: it's rebuilt on the next
+    /// lookup after any change through [`KdlDocument::nodes_mut`] or
+    /// [`KdlDocument::iter_mut`]. Changing the node returned by
+    /// [`KdlDocument::get_mut`] only rebuilds it if the node was renamed.
+    /// The index only covers this document's own nodes, so turn it on for
+    /// children blocks separately.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlDocument, KdlNode};
+    /// let mut doc = KdlDocument::new();
+    /// doc.set_name_index(true);
+    /// for i in 0..10_000 {
+    ///     let mut node = KdlNode::new(format!("item-{}", i));
+    ///     node.push(i);
+    ///     doc.nodes_mut().push(node);
+    /// }
+    /// assert_eq!(doc.get_arg("item-9999"), Some(&9999.into()));
+    ///
+    /// doc.get_mut("item-0").unwrap().set_name("first");
+    /// assert_eq!(doc.get_arg("first"), Some(&0.into()));
+    /// assert!(doc.get("item-0").is_none());
+    /// ```
+    pub fn set_name_index(&mut self, enabled: bool) {
+        self.nodes.set_indexed(enabled);
+    }
+
+    /// Whether the index of child nodes by name is on. See
+    /// [`KdlDocument::set_name_index`].
+    pub fn has_name_index(&self) -> bool {
+        self.nodes.is_indexed()
     }
 
     /// Returns a reference to this document's child nodes.
@@ -192,6 +430,29 @@ impl KdlDocument {
         &mut self.nodes
     }
 
+    /// Returns an iterator over this document's child nodes. Shorthand for
+    /// `doc.nodes().iter()`, also used by `for node in &doc`.
+    pub fn iter(&self) -> std::slice::Iter<'_, KdlNode> {
+        self.nodes.iter()
+    }
+
+    /// Returns an iterator that allows modifying each of this document's
+    /// child nodes in place, also used by `for node in &mut doc`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let mut doc: KdlDocument = "a 1\nb 2".parse().unwrap();
+    /// for node in &mut doc {
+    ///     node.push(true);
+    /// }
+    /// assert_eq!(doc.to_string(), "a 1 true\nb 2 true");
+    /// ```
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, KdlNode> {
+        self.nodes.iter_mut()
+    }
+
     /// Gets leading text (whitespace, comments) for this KdlDocument.
     pub fn leading(&self) -> Option<&str> {
         self.leading.as_deref()
@@ -222,11 +483,13 @@ impl KdlDocument {
         self.len() == 0
     }
 
-    /// Clears leading and trailing text (whitespace, comments). `KdlNode`s in
-    /// this document will be unaffected.
+    /// Clears leading and trailing text (whitespace, comments, and
+    /// slashdashed nodes after the last node). `KdlNode`s in this document
+    /// will be unaffected.
     ///
     /// If you need to clear the `KdlNode`s, use [`Self::clear_fmt_recursive`].
     pub fn clear_fmt(&mut self) {
+        self.disabled.clear();
         self.leading = None;
         self.trailing = None;
     }
@@ -316,9 +579,57 @@ impl KdlDocument {
     }
 }
 
+impl KdlDocument {
+    /// Parses a document, returning it along with any non-fatal
+    /// [`KdlWarning`]s found in the input, such as a byte order mark,
+    /// mis-decoded text, mixed indentation, or extremely deep nesting.
+    ///
+    /// Warnings never cause parsing to fail: if the input is valid KDL, this
+    /// returns the same document [`str::parse`] would.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlDocument, KdlWarningKind};
+    /// let (doc, warnings) = KdlDocument::parse_with_warnings("\u{FEFF}foo 1").unwrap();
+    /// assert_eq!(doc.get_arg("foo"), Some(&1.into()));
+    /// assert_eq!(warnings[0].kind, KdlWarningKind::ByteOrderMark);
+    /// ```
+    pub fn parse_with_warnings(input: &str) -> Result<(Self, Vec<KdlWarning>), KdlError> {
+        let doc: KdlDocument = input.parse()?;
+        let warnings = crate::warning::check(input, &doc);
+        Ok((doc, warnings))
+    }
+}
+
+impl KdlDocument {
+    /// Parses a document using the given [`ParseOptions`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlDocument, ParseOptions};
+    /// let options = ParseOptions {
+    ///     source_name: Some("config/app.kdl".into()),
+    ///     ..ParseOptions::default()
+    /// };
+    /// let err = KdlDocument::parse_with_options("foo 1.", &options).unwrap_err();
+    /// assert_eq!(err.input.name.as_deref(), Some("config/app.kdl"));
+    /// ```
+    pub fn parse_with_options(input: &str, options: &ParseOptions) -> Result<Self, KdlError> {
+        let kdl_parser = parser::KdlParser::with_options(input, options);
+        kdl_parser
+            .parse(parser::document(&kdl_parser))
+            .map_err(|e| match &options.source_name {
+                Some(name) => e.with_source_name(name.clone()),
+                None => e,
+            })
+    }
+}
+
 impl Display for KdlDocument {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.stringify(f, 0)
+        self.stringify(f, 0, self.version)
     }
 }
 
@@ -327,8 +638,11 @@ impl KdlDocument {
         if let Some(s) = self.leading.as_mut() {
             crate::fmt::fmt_leading(s, indent, no_comments);
         }
+        if no_comments {
+            self.disabled.clear();
+        }
         let mut has_nodes = false;
-        for node in &mut self.nodes {
+        for node in self.nodes.iter_mut().chain(&mut self.disabled) {
             has_nodes = true;
             node.fmt_impl(indent, no_comments);
         }
@@ -340,16 +654,20 @@ impl KdlDocument {
         }
     }
 
+    /// Writes this document out as KDL of the given version, which is this
+    /// document's own [version](KdlDocument::version) at the top level, and
+    /// the top-level document's for children blocks.
     pub(crate) fn stringify(
         &self,
         f: &mut std::fmt::Formatter<'_>,
         indent: usize,
+        version: KdlVersion,
     ) -> std::fmt::Result {
         if let Some(leading) = &self.leading {
             write!(f, "{}", leading)?;
         }
-        for node in &self.nodes {
-            node.stringify(f, indent)?;
+        for node in self.nodes.iter().chain(&self.disabled) {
+            node.stringify(f, indent, version)?;
             if node.trailing.is_none() {
                 writeln!(f)?;
             }
@@ -361,12 +679,187 @@ impl KdlDocument {
     }
 }
 
+#[cfg(feature = "span")]
+pub(crate) fn span_contains(span: SourceSpan, offset: usize) -> bool {
+    span.offset() <= offset && offset < span.offset() + span.len()
+}
+
+#[cfg(feature = "span")]
+impl KdlDocument {
+    /// Mirrors [`KdlDocument::stringify`], assigning spans as if the output
+    /// started at `offset`. Returns the offset just past this document.
+    pub(crate) fn recompute_spans_at(
+        &mut self,
+        mut offset: usize,
+        indent: usize,
+        version: KdlVersion,
+    ) -> usize {
+        let start = offset;
+        if let Some(leading) = &self.leading {
+            offset += leading.len();
+        }
+        for node in self.nodes.iter_mut().chain(&mut self.disabled) {
+            offset = node.recompute_spans_at(offset, indent, version);
+            if node.trailing.is_none() {
+                offset += "\n".len();
+            }
+        }
+        self.span = SourceSpan::from(start..offset);
+        if let Some(trailing) = &self.trailing {
+            offset += trailing.len();
+        }
+        offset
+    }
+
+    /// Moves the spans of this document and everything in it by `delta`
+    /// bytes.
+    pub(crate) fn shift_spans(&mut self, delta: isize) {
+        self.span = crate::identifier::shifted(self.span, delta);
+        for node in self.nodes.iter_mut().chain(&mut self.disabled) {
+            node.shift_spans(delta);
+        }
+    }
+
+    /// The incremental part of [`KdlDocument::reparse`]. Returns `false`,
+    /// without modifying anything, if the whole document needs to be
+    /// re-parsed instead.
+    fn reparse_nodes(&mut self, edit: &KdlTextEdit, options: &ParseOptions) -> bool {
+        if self.nodes.is_empty() {
+            return false;
+        }
+        let (start, end) = (edit.span.offset(), edit.span.offset() + edit.span.len());
+        // Each node's full extent, including its leading and trailing text
+        // and the slashdashed nodes before it.
+        // These are contiguous, so every offset falls into one of them
+        // (or into the document's own leading or trailing text).
+        let extents = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let span = node.span();
+                let leading = crate::node::written_len(&node.disabled, self.version)
+                    + node.leading().map(str::len).unwrap_or(0);
+                let trailing = node.trailing().map(str::len).unwrap_or(1);
+                span.offset() - leading..span.offset() + span.len() + trailing
+            })
+            .collect::<Vec<_>>();
+        let last_node = self.nodes.len() - 1;
+        // An edit right at the boundary between two nodes could affect
+        // either of them. One more node after that makes sure the re-parsed
+        // text ends on a terminator that was there before the edit.
+        let last = extents
+            .iter()
+            .rposition(|extent| extent.start <= end)
+            .unwrap_or(0);
+        let last = (last + 1).min(last_node);
+        let first = extents
+            .iter()
+            .position(|extent| extent.end >= start)
+            .unwrap_or(last_node)
+            .min(last);
+        let doc_leading = self.leading.as_deref().unwrap_or("");
+        let mut doc_trailing = String::new();
+        for node in &self.disabled {
+            doc_trailing.push_str(&node.to_string_in(self.version));
+            if node.trailing.is_none() {
+                doc_trailing.push('\n');
+            }
+        }
+        doc_trailing.push_str(self.trailing.as_deref().unwrap_or(""));
+        let region_start = if first == 0 {
+            extents[0].start - doc_leading.len()
+        } else {
+            extents[first].start
+        };
+        let region_end = if last == last_node {
+            extents[last].end + doc_trailing.len()
+        } else {
+            extents[last].end
+        };
+        if start < region_start || end > region_end {
+            return false;
+        }
+
+        let mut old_text = String::new();
+        if first == 0 {
+            old_text.push_str(doc_leading);
+        }
+        for node in &self.nodes[first..=last] {
+            old_text.push_str(&node.to_string_in(self.version));
+            if node.trailing.is_none() {
+                old_text.push('\n');
+            }
+        }
+        if last == last_node {
+            old_text.push_str(&doc_trailing);
+        }
+        let new_text = format!(
+            "{}{}{}",
+            &old_text[..start - region_start],
+            edit.replacement,
+            &old_text[end - region_start..]
+        );
+        let mut region = match KdlDocument::parse_with_options(&new_text, options) {
+            Ok(region) => region,
+            Err(_) => return false,
+        };
+        // Unless the region runs to the end of the document, it has to end
+        // exactly where it did before, with the same terminator.
+        if last != last_node {
+            let same_end = region.disabled.is_empty()
+                && region.trailing().unwrap_or("").is_empty()
+                && region.nodes.last().and_then(|node| node.trailing())
+                    == self.nodes[last].trailing();
+            if !same_end {
+                return false;
+            }
+        }
+
+        let delta = new_text.len() as isize - old_text.len() as isize;
+        region.shift_spans(region_start as isize);
+        let doc_end = if last == last_node {
+            self.disabled = std::mem::take(&mut region.disabled);
+            self.trailing = region.trailing.take();
+            region.span.offset() + region.span.len()
+        } else {
+            ((self.span.offset() + self.span.len()) as isize + delta) as usize
+        };
+        if first == 0 {
+            self.leading = region.leading.take();
+        }
+        for node in &mut self.nodes[last + 1..] {
+            node.shift_spans(delta);
+        }
+        self.nodes.splice(first..=last, region.nodes.into_vec());
+        self.span = SourceSpan::from(self.span.offset()..doc_end);
+        true
+    }
+}
+
 impl IntoIterator for KdlDocument {
     type Item = KdlNode;
     type IntoIter = std::vec::IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.nodes.into_iter()
+        self.nodes.into_vec().into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a KdlDocument {
+    type Item = &'a KdlNode;
+    type IntoIter = std::slice::Iter<'a, KdlNode>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut KdlDocument {
+    type Item = &'a mut KdlNode;
+    type IntoIter = std::slice::IterMut<'a, KdlNode>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
     }
 }
 
@@ -387,6 +880,56 @@ mod test {
 
     use super::*;
 
+    #[test]
+    fn get_all() -> miette::Result<()> {
+        let mut doc: KdlDocument = "item 1\nother 2\nitem 3\nitem 4 { item 5; }".parse()?;
+        let items: Vec<_> = doc.get_all("item").filter_map(|n| n.get(0)).collect();
+        assert_eq!(items, vec![&1.into(), &3.into(), &4.into()]);
+        assert_eq!(doc.get_all("missing").count(), 0);
+        for item in doc.get_all_mut("item") {
+            item.push(true);
+        }
+        assert_eq!(
+            doc.to_string(),
+            "item 1 true\nother 2\nitem 3 true\nitem 4 true { item 5; }"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn iteration() -> miette::Result<()> {
+        let mut doc: KdlDocument = "a 1\nb\nc 2".parse()?;
+        let with_args: Vec<_> = doc
+            .iter()
+            .filter(|n| !n.entries().is_empty())
+            .map(|n| n.name().value())
+            .collect();
+        assert_eq!(with_args, vec!["a", "c"]);
+        for node in &mut doc {
+            node.set_name(node.name().value().to_uppercase());
+        }
+        let names: Vec<_> = (&doc).into_iter().map(|n| n.name().value()).collect();
+        assert_eq!(names, vec!["A", "B", "C"]);
+        assert_eq!(doc.into_iter().count(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn named_source() {
+        let options = ParseOptions {
+            source_name: Some("config/app.kdl".into()),
+            ..ParseOptions::default()
+        };
+        let err = KdlDocument::parse_with_options("foo {\n  bar 1.\n}", &options).unwrap_err();
+        assert_eq!(err.input.name.as_deref(), Some("config/app.kdl"));
+
+        let mut report = String::new();
+        miette::GraphicalReportHandler::new_themed(miette::GraphicalTheme::unicode_nocolor())
+            .render_report(&mut report, &err)
+            .unwrap();
+        assert!(report.contains("[config/app.kdl:1:1]"), "{}", report);
+    }
+
     #[test]
     fn canonical_clear_fmt() -> miette::Result<()> {
         let left_src = r#"
@@ -710,6 +1253,11 @@ inline { time; to; live "our" "dreams"; "y;all"; }
             &input,
         );
         check_span(r#"10.1"#, is_node.entry(1).unwrap().span(), &input);
+        check_span("is", is_node.name().span(), &input);
+        check_span("a", is_node.entry(0).unwrap().ty().unwrap().span(), &input);
+        let read = is_node.entry("read").unwrap();
+        check_span("read", read.name().unwrap().span(), &input);
+        check_span("int", read.ty().unwrap().span(), &input);
         check_span(r#"(u32)0x45"#, is_node.entry(2).unwrap().span(), &input);
 
         // Now let's look at some messed up parts of that "and" node
@@ -764,6 +1312,12 @@ inline { time; to; live "our" "dreams"; "y;all"; }
             it_node.entry(0).unwrap().span(),
             &input,
         );
+        check_span(r#""it""#, it_node.name().span(), &input);
+        check_span(
+            r#""has""#,
+            it_node.entry("has").unwrap().name().unwrap().span(),
+            &input,
+        );
 
         // Make sure inline nodes work ok
         let inline_node = doc.get("inline").unwrap();
@@ -789,6 +1343,234 @@ inline { time; to; live "our" "dreams"; "y;all"; }
         Ok(())
     }
 
+    #[cfg(feature = "span")]
+    fn collect_spans(doc: &KdlDocument, spans: &mut Vec<SourceSpan>) {
+        spans.push(doc.span());
+        for node in doc.nodes() {
+            spans.push(node.span());
+            spans.extend(node.ty().map(|ty| ty.span()));
+            spans.push(node.name().span());
+            for entry in node.entries() {
+                spans.push(entry.span());
+                spans.extend(entry.name().map(|name| name.span()));
+                spans.extend(entry.ty().map(|ty| ty.span()));
+            }
+            if let Some(children) = node.children() {
+                collect_spans(children, spans);
+            }
+        }
+    }
+
+    #[cfg(feature = "span")]
+    #[test]
+    fn recompute_spans_matches_parse() -> miette::Result<()> {
+        let input = r####"
+this {
+    is (a)"cool" document="to" read=(int)5 10.1 (u32)0x45
+    and x="" {
+        "it" /*shh*/ "has"="💯" r##"the"##
+        Best🎊est
+        "syntax ever"
+    }
+}
+inline { time; to; live "our" "dreams"; "y;all"; }
+"####;
+        let parsed: KdlDocument = input.parse()?;
+        let mut recomputed = parsed.clone();
+        recomputed.recompute_spans();
+
+        let mut expected = Vec::new();
+        collect_spans(&parsed, &mut expected);
+        let mut actual = Vec::new();
+        collect_spans(&recomputed, &mut actual);
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
+    #[cfg(feature = "span")]
+    #[test]
+    fn recompute_spans_after_mutation() -> miette::Result<()> {
+        let mut doc: KdlDocument = "foo 1 {\n    bar\n}\nbaz\n".parse()?;
+        let foo = doc.get_mut("foo").unwrap();
+        foo.set_name("a-much-longer-name");
+        foo.push(("key", "value"));
+        let mut child = KdlNode::new("new-child");
+        child.push(KdlEntry::new(KdlValue::Base10(42)));
+        foo.children_mut().as_mut().unwrap().nodes_mut().push(child);
+        doc.nodes_mut().insert(0, KdlNode::new("first"));
+
+        // Untouched spans still point at the originally parsed text.
+        assert_eq!(doc.get("baz").unwrap().span(), (18, 3).into());
+
+        doc.recompute_spans();
+        let output = doc.to_string();
+        check_spans_for_doc(&doc, &output);
+        check_span("baz", doc.get("baz").unwrap().span(), &output);
+        let foo = doc.get("a-much-longer-name").unwrap();
+        check_span(r#"key="value""#, foo.entry("key").unwrap().span(), &output);
+        check_span(
+            "new-child 42",
+            foo.children().unwrap().get("new-child").unwrap().span(),
+            &output,
+        );
+        check_span(&output, doc.span(), &output);
+
+        // Reparsing the output yields the same spans.
+        let mut expected = Vec::new();
+        collect_spans(&output.parse()?, &mut expected);
+        let mut actual = Vec::new();
+        collect_spans(&doc, &mut actual);
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
+    #[cfg(feature = "span")]
+    #[test]
+    fn reparse() -> miette::Result<()> {
+        let input =
+            "// header\nfoo 1 {\n    bar \"x\"\n}\n/-gone\nbaz; qux (t)2\nlast key=3 // end\n\n";
+        let edits = [
+            "", " ", "\n", ";", "1", "x ", "\"", "{", "}", "/*", "/-", "// c\n", "a=1 ",
+        ];
+        let mut checked = 0;
+        for start in 0..=input.len() {
+            for len in [0, 1, 3] {
+                let end = (start + len).min(input.len());
+                for replacement in edits {
+                    let edit = KdlTextEdit {
+                        span: (start..end).into(),
+                        replacement: replacement.into(),
+                    };
+                    let text = KdlTextEdit::apply(input, std::slice::from_ref(&edit));
+                    let mut doc: KdlDocument = input.parse()?;
+                    match text.parse::<KdlDocument>() {
+                        Ok(expected) => {
+                            doc.reparse(&edit)?;
+                            assert_eq!(doc, expected, "{:?}", text);
+                            assert_eq!(doc.to_string(), text);
+                            let (mut actual_spans, mut expected_spans) = (vec![], vec![]);
+                            collect_spans(&doc, &mut actual_spans);
+                            collect_spans(&expected, &mut expected_spans);
+                            assert_eq!(actual_spans, expected_spans, "{:?}", text);
+                            checked += 1;
+                        }
+                        Err(_) => {
+                            assert!(doc.reparse(&edit).is_err(), "{:?}", text);
+                            assert_eq!(doc.to_string(), input);
+                        }
+                    }
+                }
+            }
+        }
+        assert!(checked > 1000);
+
+        // Edits outside the text, or inside a character, are errors.
+        let mut doc: KdlDocument = "a \"é\"\n".parse()?;
+        for span in [(8, 0), (6, 3), (4, 0), (3, 1)] {
+            let err = doc
+                .reparse(&KdlTextEdit {
+                    span: span.into(),
+                    replacement: "x".into(),
+                })
+                .unwrap_err();
+            assert_eq!(err.kind, crate::KdlErrorKind::InvalidEdit);
+        }
+        assert_eq!(doc.to_string(), "a \"é\"\n");
+
+        // KDL 2.0 documents are re-parsed as KDL 2.0.
+        let mut doc = KdlDocument::parse_auto("/- kdl-version 2\nfoo #true\nbar baz\n")?;
+        for (offset, len, replacement) in [(21, 5, "#false"), (32, 3, "qux"), (0, 0, "x\n")] {
+            doc.reparse(&KdlTextEdit {
+                span: (offset, len).into(),
+                replacement: replacement.into(),
+            })?;
+        }
+        assert_eq!(doc.version(), KdlVersion::V2);
+        assert_eq!(
+            doc.to_string(),
+            "x\n/- kdl-version 2\nfoo #false\nbar qux\n"
+        );
+        assert_eq!(doc.get_arg("bar"), Some(&"qux".into()));
+
+        // Repeated edits keep working off the updated spans.
+        let mut doc: KdlDocument = "a 1\nb 2\nc 3\n".parse()?;
+        for (offset, replacement) in [(2, "10"), (8, "x="), (0, "z\n"), (18, "d\n")] {
+            doc.reparse(&KdlTextEdit {
+                span: (offset, 0).into(),
+                replacement: replacement.into(),
+            })?;
+        }
+        let output = doc.to_string();
+        assert_eq!(output, "z\na 101\nb x=2\nc 3\nd\n");
+        let (mut actual, mut expected) = (vec![], vec![]);
+        collect_spans(&doc, &mut actual);
+        collect_spans(&output.parse()?, &mut expected);
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
+    #[cfg(feature = "span")]
+    #[test]
+    fn node_and_entry_at() -> miette::Result<()> {
+        let input = "// comment\nfoo 1 {\n    bar key=(t)\"val\" {\n        baz\n    }\n}\nqux\n";
+        let doc: KdlDocument = input.parse()?;
+        let at = |needle: &str| input.find(needle).unwrap();
+        let name_at = |offset| doc.node_at(offset).map(|n| n.name().value());
+
+        assert_eq!(name_at(0), None);
+        assert_eq!(name_at(at("foo")), Some("foo"));
+        assert_eq!(name_at(at(" {")), Some("foo"));
+        assert_eq!(name_at(at("bar") + 2), Some("bar"));
+        assert_eq!(name_at(at("baz")), Some("baz"));
+        assert_eq!(name_at(at("}\nqux")), Some("foo"));
+        assert_eq!(name_at(at("qux")), Some("qux"));
+        assert_eq!(name_at(input.len()), None);
+
+        assert_eq!(doc.entry_at(at("1")).map(|e| e.value()), Some(&1.into()));
+        let prop = doc.entry_at(at("(t)")).unwrap();
+        assert_eq!(prop.name().map(|n| n.value()), Some("key"));
+        assert_eq!(doc.entry_at(at("key")), Some(prop));
+        assert_eq!(doc.entry_at(at("foo")), None);
+        assert_eq!(doc.entry_at(at("baz")), None);
+        Ok(())
+    }
+
+    #[test]
+    fn display_keeps_version() -> miette::Result<()> {
+        let mut doc = KdlDocument::parse_auto("node #true #null bare #\"raw\"# 1")?;
+        assert_eq!(doc.version(), KdlVersion::V2);
+        KdlDocument::fmt(&mut doc);
+        let out = doc.to_string();
+        assert_eq!(out, "node #true #null \"bare\" #\"raw\"# 1\n");
+        let reparsed = KdlDocument::parse_auto(&out)?;
+        assert_eq!(reparsed.version(), KdlVersion::V2);
+        assert_eq!(reparsed.to_string(), out);
+
+        doc.nodes_mut()[0].push(true);
+        doc.nodes_mut()[0].push(KdlEntry::new_prop("key", KdlValue::Null));
+        assert_eq!(
+            doc.to_string(),
+            "node #true #null \"bare\" #\"raw\"# 1 #true key=#null\n"
+        );
+
+        let mut doc: KdlDocument = "node true null".parse()?;
+        KdlDocument::fmt(&mut doc);
+        assert_eq!(doc.to_string(), "node true null\n");
+        Ok(())
+    }
+
+    #[cfg(feature = "span")]
+    #[test]
+    fn recompute_spans_v2() -> miette::Result<()> {
+        let mut doc = KdlDocument::parse_auto("node #true bare")?;
+        doc.nodes_mut()[0].push(KdlValue::Null);
+        doc.recompute_spans();
+        let output = doc.to_string();
+        check_spans_for_doc(&doc, &output);
+        check_span("#null", doc.nodes()[0].entries()[2].span(), &output);
+        Ok(())
+    }
+
     #[test]
     fn parse_examples() -> miette::Result<()> {
         include_str!("../examples/kdl-schema.kdl").parse::<KdlDocument>()?;
@@ -0,0 +1,233 @@
+use std::fmt::Write;
+
+use crate::{KdlDocument, KdlEntry, KdlNode, KdlValue};
+
+/// Dumps of a document's structure, for debugging.
+impl KdlDocument {
+    /// Draws this document as a tree, one node or entry per line, with each
+    /// node's entries and then its children beneath it. Entries show their
+    /// type annotations and values, along with the kind of value they are,
+    /// and everything shows its span (with the `span` feature), so it's easy
+    /// to see how a document was read, or what a merge or query did to it.
+    ///
+    /// Values are written out fresh, rather than how they were written in
+    /// the source, though integers keep their radix.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = "(svc)server 0x10 tls=true {\n    port 8080\n}".parse().unwrap();
+    /// # #[cfg(feature = "span")]
+    /// assert_eq!(
+    ///     doc.debug_tree(),
+    ///     "\
+    /// (svc)server @0..43
+    /// ├── [0] 0x10 (integer) @12..16
+    /// ├── tls=true (bool) @17..25
+    /// └── port @32..41
+    ///     └── [0] 8080 (integer) @37..41
+    /// "
+    /// );
+    /// ```
+    pub fn debug_tree(&self) -> String {
+        let mut out = String::new();
+        for node in self.nodes() {
+            tree_node(&mut out, node, "", "");
+        }
+        out
+    }
+
+    /// Writes this document out as a [Graphviz](https://graphviz.org) graph,
+    /// in the DOT language, with a box for each node listing its entries,
+    /// and an arrow from each node to each of its children. Render it with
+    /// something like `dot -Tsvg`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = "server port=8080 {\n    tls \"on\"\n}".parse().unwrap();
+    /// let dot = doc.to_dot();
+    /// assert!(dot.starts_with("digraph kdl {\n"));
+    /// assert!(dot.contains("n1 [label=\"tls\\n\\\"on\\\"\"]"));
+    /// assert!(dot.contains("n0 -> n1;"));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph kdl {\n    node [shape=box];\n    root [label=\"document\", shape=plaintext];\n");
+        let mut next = 0;
+        for node in self.nodes() {
+            dot_node(&mut out, node, "root", &mut next);
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn tree_node(out: &mut String, node: &KdlNode, prefix: &str, child_prefix: &str) {
+    out.push_str(prefix);
+    if let Some(ty) = node.ty() {
+        write!(out, "({})", ty).unwrap();
+    }
+    write!(out, "{}", node.name()).unwrap();
+    #[cfg(feature = "span")]
+    write_span(out, node.span());
+    out.push('\n');
+
+    let children = node.children().map_or(&[][..], KdlDocument::nodes);
+    let count = node.entries().len() + children.len();
+    let mut args = 0;
+    let mut items = 0;
+    for entry in node.entries() {
+        items += 1;
+        let (prefix, _) = branch(child_prefix, items == count);
+        out.push_str(&prefix);
+        match entry.name() {
+            Some(name) => write!(out, "{}=", name).unwrap(),
+            None => {
+                write!(out, "[{}] ", args).unwrap();
+                args += 1;
+            }
+        }
+        tree_entry(out, entry);
+    }
+    for child in children {
+        items += 1;
+        let (prefix, child_prefix) = branch(child_prefix, items == count);
+        tree_node(out, child, &prefix, &child_prefix);
+    }
+}
+
+/// The prefixes for an item in a tree, and for the items beneath it.
+fn branch(prefix: &str, last: bool) -> (String, String) {
+    let (branch, indent) = if last {
+        ("└── ", "    ")
+    } else {
+        ("├── ", "│   ")
+    };
+    (
+        format!("{}{}", prefix, branch),
+        format!("{}{}", prefix, indent),
+    )
+}
+
+fn tree_entry(out: &mut String, entry: &KdlEntry) {
+    if let Some(ty) = entry.ty() {
+        write!(out, "({})", ty).unwrap();
+    }
+    write!(out, "{} ({})", entry.value(), kind(entry.value())).unwrap();
+    #[cfg(feature = "span")]
+    write_span(out, entry.span());
+    out.push('\n');
+}
+
+#[cfg(feature = "span")]
+fn write_span(out: &mut String, span: miette::SourceSpan) {
+    write!(out, " @{}..{}", span.offset(), span.offset() + span.len()).unwrap();
+}
+
+fn kind(value: &KdlValue) -> &'static str {
+    match value {
+        KdlValue::String(_) | KdlValue::RawString(_) => "string",
+        KdlValue::Base2(_) | KdlValue::Base8(_) | KdlValue::Base10(_) | KdlValue::Base16(_) => {
+            "integer"
+        }
+        KdlValue::Base10Float(_) => "float",
+        #[cfg(feature = "bigint")]
+        KdlValue::BigInt(_) => "integer",
+        KdlValue::Bool(_) => "bool",
+        KdlValue::Null => "null",
+    }
+}
+
+fn dot_node(out: &mut String, node: &KdlNode, parent: &str, next: &mut usize) {
+    let id = format!("n{}", next);
+    *next += 1;
+    let mut label = String::new();
+    if let Some(ty) = node.ty() {
+        write!(label, "({})", ty).unwrap();
+    }
+    write!(label, "{}", node.name()).unwrap();
+    for entry in node.entries() {
+        label.push('\n');
+        if let Some(name) = entry.name() {
+            write!(label, "{}=", name).unwrap();
+        }
+        if let Some(ty) = entry.ty() {
+            write!(label, "({})", ty).unwrap();
+        }
+        write!(label, "{}", entry.value()).unwrap();
+    }
+    writeln!(out, "    {} [label=\"{}\"];", id, dot_escape(&label)).unwrap();
+    writeln!(out, "    {} -> {};", parent, id).unwrap();
+    for child in node.children().map_or(&[][..], KdlDocument::nodes) {
+        dot_node(out, child, &id, next);
+    }
+}
+
+fn dot_escape(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dumps() -> miette::Result<()> {
+        let doc: KdlDocument =
+            "a 1.5 \"s\\\\\" /-\"x\" {\n    b null {\n        c\n    }\n    (t)d k=(u8)0b1\n}\ne"
+                .parse()?;
+        let tree = doc.debug_tree();
+        #[cfg(feature = "span")]
+        let tree = tree
+            .lines()
+            .map(|line| line.split(" @").next().unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            tree.trim_end(),
+            "\
+a
+├── [0] 1.5 (float)
+├── [1] \"s\\\\\" (string)
+├── b
+│   ├── [0] null (null)
+│   └── c
+└── (t)d
+    └── k=(u8)0b1 (integer)
+e"
+        );
+
+        assert_eq!(
+            doc.to_dot(),
+            "\
+digraph kdl {
+    node [shape=box];
+    root [label=\"document\", shape=plaintext];
+    n0 [label=\"a\\n1.5\\n\\\"s\\\\\\\\\\\"\"];
+    root -> n0;
+    n1 [label=\"b\\nnull\"];
+    n0 -> n1;
+    n2 [label=\"c\"];
+    n1 -> n2;
+    n3 [label=\"(t)d\\nk=(u8)0b1\"];
+    n0 -> n3;
+    n4 [label=\"e\"];
+    root -> n4;
+}
+"
+        );
+        assert_eq!(KdlDocument::new().debug_tree(), "");
+        Ok(())
+    }
+}
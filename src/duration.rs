@@ -0,0 +1,202 @@
+use std::time::Duration;
+
+use crate::{KdlEntry, KdlValue};
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+impl KdlValue {
+    /// Creates a string value holding `duration` as an ISO 8601 duration,
+    /// like `PT1M30S`. Hours are the largest unit used, since days and up
+    /// don't always have a fixed length.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlValue;
+    /// use std::time::Duration;
+    ///
+    /// let value = KdlValue::from_duration(Duration::from_millis(90_500));
+    /// assert_eq!(value, KdlValue::String("PT1M30.5S".into()));
+    /// assert_eq!(value.as_duration(), Some(Duration::from_millis(90_500)));
+    /// ```
+    pub fn from_duration(duration: Duration) -> Self {
+        let secs = duration.as_secs();
+        let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+        let mut out = String::from("PT");
+        if hours > 0 {
+            out.push_str(&format!("{}H", hours));
+        }
+        if minutes > 0 {
+            out.push_str(&format!("{}M", minutes));
+        }
+        let nanos = duration.subsec_nanos();
+        if nanos > 0 {
+            let fraction = format!("{:09}", nanos);
+            out.push_str(&format!("{}.{}S", seconds, fraction.trim_end_matches('0')));
+        } else if seconds > 0 || out == "PT" {
+            out.push_str(&format!("{}S", seconds));
+        }
+        KdlValue::String(out)
+    }
+
+    /// Parses this value as an ISO 8601 duration (like `PT1M30S` or
+    /// `P1DT12H`), if it's a string. Days are taken to be 24 hours and weeks
+    /// 7 days. Durations using years or months, which don't have a fixed
+    /// length, return `None`, as do negative durations.
+    pub fn as_duration(&self) -> Option<Duration> {
+        parse_iso8601_duration(self.as_string()?)
+    }
+}
+
+impl KdlEntry {
+    /// Parses this entry's value as an ISO 8601 duration (see
+    /// [`KdlValue::as_duration`]), if it's annotated with `(duration)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// use std::time::Duration;
+    ///
+    /// let node: KdlNode = r#"server timeout=(duration)"PT30S""#.parse().unwrap();
+    /// let timeout = node.entry("timeout").unwrap();
+    /// assert_eq!(timeout.as_duration(), Some(Duration::from_secs(30)));
+    /// ```
+    pub fn as_duration(&self) -> Option<Duration> {
+        parse_iso8601_duration(self.annotated_string("duration")?)
+    }
+
+    /// Creates a new argument holding `duration`, formatted with
+    /// [`KdlValue::from_duration`] and annotated with `(duration)`.
+    pub fn new_duration(duration: Duration) -> Self {
+        let mut entry = KdlEntry::new(KdlValue::from_duration(duration));
+        entry.set_ty("duration");
+        entry
+    }
+}
+
+fn parse_iso8601_duration(input: &str) -> Option<Duration> {
+    let mut rest = input.strip_prefix('P')?;
+    if rest.is_empty() || rest == "T" {
+        return None;
+    }
+    let mut in_time = false;
+    let mut total: u128 = 0;
+    // Units must appear in order, each at most once.
+    let mut last_unit = 0;
+    while !rest.is_empty() {
+        if let Some(time) = rest.strip_prefix('T') {
+            if in_time {
+                return None;
+            }
+            in_time = true;
+            rest = time;
+            continue;
+        }
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+            .unwrap_or(rest.len());
+        let (number, after) = rest.split_at(number_len);
+        let designator = after.chars().next()?;
+        let (order, unit_secs) = match (in_time, designator) {
+            (false, 'W') => (1, 7 * 24 * 3600),
+            (false, 'D') => (2, 24 * 3600),
+            (true, 'H') => (3, 3600),
+            (true, 'M') => (4, 60),
+            (true, 'S') => (5, 1),
+            _ => return None,
+        };
+        if order <= last_unit {
+            return None;
+        }
+        last_unit = order;
+        total = total.checked_add(nanos_of(number, unit_secs)?)?;
+        rest = &after[designator.len_utf8()..];
+    }
+    if in_time && last_unit < 3 {
+        // A `T` with nothing after it.
+        return None;
+    }
+    let secs = u64::try_from(total / NANOS_PER_SECOND).ok()?;
+    Some(Duration::new(secs, (total % NANOS_PER_SECOND) as u32))
+}
+
+/// Converts a decimal number of `unit_secs`-long units into nanoseconds,
+/// rounding down to the nearest nanosecond.
+fn nanos_of(number: &str, unit_secs: u128) -> Option<u128> {
+    let (whole, fraction) = match number.find(['.', ',']) {
+        Some(i) => (&number[..i], &number[i + 1..]),
+        None => (number, ""),
+    };
+    if whole.is_empty() || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let unit_nanos = unit_secs * NANOS_PER_SECOND;
+    let mut nanos = whole.parse::<u128>().ok()?.checked_mul(unit_nanos)?;
+    let mut scale = unit_nanos;
+    for digit in fraction.chars() {
+        scale /= 10;
+        nanos = nanos.checked_add(u128::from(digit.to_digit(10)?) * scale)?;
+    }
+    Some(nanos)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(input: &str) -> Option<Duration> {
+        KdlValue::String(input.into()).as_duration()
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(parse("PT0S"), Some(Duration::ZERO));
+        assert_eq!(parse("PT1.5S"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse("PT0,25S"), Some(Duration::from_millis(250)));
+        assert_eq!(parse("PT2M"), Some(Duration::from_secs(120)));
+        assert_eq!(parse("PT1.5H"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse("P1DT12H"), Some(Duration::from_secs(36 * 3600)));
+        assert_eq!(parse("P2W"), Some(Duration::from_secs(14 * 24 * 3600)));
+        assert_eq!(parse("P1DT1H1M1.000000001S"), Some(Duration::new(90061, 1)));
+        for bad in [
+            "", "P", "PT", "P1D T1H", "PT1S2M", "PT1M1M", "P1M", "P1Y", "P1H", "PT1D", "1S",
+            "PT-1S", "PT.5S", "PT1.5.5S", "PT1", "P1DT", "PTT1S",
+        ] {
+            assert_eq!(parse(bad), None, "{:?}", bad);
+        }
+        assert_eq!(parse("PT99999999999999999999999999999999999999999H"), None);
+        assert_eq!(parse("PT340282366920938463463374607431.9999999999S"), None);
+        assert_eq!(KdlValue::Base10(30).as_duration(), None);
+    }
+
+    #[test]
+    fn formatting() {
+        for (duration, expected) in [
+            (Duration::ZERO, "PT0S"),
+            (Duration::from_secs(5), "PT5S"),
+            (Duration::from_secs(60), "PT1M"),
+            (Duration::from_secs(3600), "PT1H"),
+            (Duration::from_secs(36 * 3600 + 61), "PT36H1M1S"),
+            (Duration::from_nanos(1), "PT0.000000001S"),
+            (Duration::from_millis(3_600_250), "PT1H0.25S"),
+        ] {
+            let value = KdlValue::from_duration(duration);
+            assert_eq!(value.as_string(), Some(expected));
+            assert_eq!(value.as_duration(), Some(duration));
+        }
+    }
+
+    #[test]
+    fn entries() {
+        let entry = KdlEntry::new_duration(Duration::from_secs(90));
+        assert_eq!(entry.to_string(), r#"(duration)"PT1M30S""#);
+        let entry: KdlEntry = entry.to_string().parse().unwrap();
+        assert_eq!(entry.as_duration(), Some(Duration::from_secs(90)));
+
+        let entry: KdlEntry = r#""PT1M30S""#.parse().unwrap();
+        assert_eq!(entry.as_duration(), None);
+        let entry: KdlEntry = r#"(date)"PT1M30S""#.parse().unwrap();
+        assert_eq!(entry.as_duration(), None);
+    }
+}
@@ -0,0 +1,614 @@
+use std::ops::Range;
+#[cfg(feature = "span")]
+use std::sync::Arc;
+
+use miette::SourceSpan;
+
+use crate::KdlDocument;
+#[cfg(feature = "span")]
+use crate::{KdlError, KdlErrorKind};
+
+/// A single replacement of a range of text, as produced by
+/// [`KdlDocument::fmt_edits`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KdlTextEdit {
+    /// The byte range in the original text to replace.
+    pub span: SourceSpan,
+
+    /// The text to put in its place.
+    pub replacement: String,
+}
+
+impl KdlTextEdit {
+    /// Applies a set of edits to `source`. The edits must be sorted and
+    /// non-overlapping, which is always the case for edits returned by this
+    /// crate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an edit's span runs past the end of `source`, or starts or
+    /// ends in the middle of a character.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlDocument, KdlTextEdit};
+    /// let input = "foo   1\n";
+    /// let doc: KdlDocument = input.parse().unwrap();
+    /// let edits = doc.fmt_edits();
+    /// assert_eq!(KdlTextEdit::apply(input, &edits), "foo 1\n");
+    /// ```
+    pub fn apply(source: &str, edits: &[KdlTextEdit]) -> String {
+        let mut result = String::with_capacity(source.len());
+        let mut last = 0;
+        for edit in edits {
+            result.push_str(&source[last..edit.span.offset()]);
+            result.push_str(&edit.replacement);
+            last = edit.span.offset() + edit.span.len();
+        }
+        result.push_str(&source[last..]);
+        result
+    }
+
+    /// Checks that this edit's span lies within `source`, on character
+    /// boundaries.
+    #[cfg(feature = "span")]
+    pub(crate) fn check(&self, source: &str) -> Result<(), KdlError> {
+        let (start, end) = (self.span.offset(), self.span.offset() + self.span.len());
+        if end <= source.len() && source.is_char_boundary(start) && source.is_char_boundary(end) {
+            return Ok(());
+        }
+        let start = start.min(source.len());
+        Err(KdlError {
+            input: Arc::new(source.into()),
+            span: (start..end.min(source.len()).max(start)).into(),
+            label: Some("edited here"),
+            related_span: None,
+            related_label: None,
+            help: Some("Edit spans are byte offsets, and must start and end on character boundaries within the text."),
+            kind: KdlErrorKind::InvalidEdit,
+        })
+    }
+}
+
+/// A mapping between offsets in a piece of text and offsets in an edited
+/// version of it, as produced by [`KdlDocument::fmt_with_source_map`].
+///
+/// Text that the edits left alone maps exactly in both directions. Offsets
+/// inside replaced text map to the start or end of its replacement,
+/// depending on whether they start or end a span.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KdlSourceMap {
+    /// Pairs of (original range, edited range) for each edit, in order.
+    segments: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl KdlSourceMap {
+    /// Creates a source map for the result of applying `edits` (which must
+    /// be sorted and non-overlapping) with [`KdlTextEdit::apply`].
+    pub fn new(edits: &[KdlTextEdit]) -> Self {
+        let mut delta = 0isize;
+        let segments = edits
+            .iter()
+            .map(|edit| {
+                let old = edit.span.offset()..edit.span.offset() + edit.span.len();
+                let start = (old.start as isize + delta) as usize;
+                delta += edit.replacement.len() as isize - edit.span.len() as isize;
+                (old, start..start + edit.replacement.len())
+            })
+            .collect();
+        Self { segments }
+    }
+
+    /// Translates a span in the original text to the corresponding span in
+    /// the edited text.
+    pub fn to_edited(&self, span: SourceSpan) -> SourceSpan {
+        self.map_span(span, true)
+    }
+
+    /// Translates a span in the edited text back to the corresponding span
+    /// in the original text.
+    pub fn to_original(&self, span: SourceSpan) -> SourceSpan {
+        self.map_span(span, false)
+    }
+
+    fn map_span(&self, span: SourceSpan, forward: bool) -> SourceSpan {
+        let start = self.map_offset(span.offset(), forward, false);
+        if span.is_empty() {
+            return SourceSpan::from(start..start);
+        }
+        let end = self.map_offset(span.offset() + span.len(), forward, true);
+        SourceSpan::from(start..end.max(start))
+    }
+
+    fn map_offset(&self, offset: usize, forward: bool, is_end: bool) -> usize {
+        let mut delta = 0isize;
+        for (old, new) in &self.segments {
+            let (from, to) = if forward { (old, new) } else { (new, old) };
+            if offset < from.start {
+                break;
+            } else if offset == from.start {
+                // Text ending here comes before the replacement, and text
+                // starting here comes after anything inserted here.
+                return if !is_end && from.is_empty() {
+                    to.end
+                } else {
+                    to.start
+                };
+            } else if offset < from.end {
+                return if is_end { to.end } else { to.start };
+            }
+            delta = to.end as isize - from.end as isize;
+        }
+        (offset as isize + delta) as usize
+    }
+}
+
+impl KdlDocument {
+    /// Formats this document like [`KdlDocument::fmt`], and returns a
+    /// [`KdlSourceMap`] between its string representation from before and
+    /// after formatting.
+    ///
+    /// This lets diagnostics computed against the formatted text be pointed
+    /// back at the original input (and the other way around). Note that, as
+    /// with any other modification, this doesn't update the document's own
+    /// spans; use [`KdlDocument::recompute_spans`] for that.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let input = "foo    1   \"bar\"\n";
+    /// let mut doc: KdlDocument = input.parse().unwrap();
+    /// let map = doc.fmt_with_source_map();
+    /// let output = doc.to_string();
+    /// assert_eq!(output, "foo 1 \"bar\"\n");
+    ///
+    /// // `"bar"` is at 6..11 in the output, and 11..16 in the input.
+    /// assert_eq!(map.to_original((6, 5).into()), (11, 5).into());
+    /// assert_eq!(map.to_edited((11, 5).into()), (6, 5).into());
+    /// ```
+    pub fn fmt_with_source_map(&mut self) -> KdlSourceMap {
+        let original = self.to_string();
+        self.fmt();
+        KdlSourceMap::new(&diff(&original, &self.to_string()))
+    }
+
+    /// Works out what [`KdlDocument::fmt`] would change, without changing
+    /// anything, and returns it as a minimal set of edits against this
+    /// document's current string representation (which, for a freshly parsed
+    /// document, is the original source text).
+    ///
+    /// Editors can apply these edits directly instead of replacing the whole
+    /// buffer, which keeps cursor positions and undo history intact.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let input = "foo {\n    bar 1\n      baz    2\n}\n";
+    /// let doc: KdlDocument = input.parse().unwrap();
+    /// let edits = doc.fmt_edits();
+    /// assert_eq!(edits.len(), 2);
+    /// assert_eq!(edits[0].span, (20, 2).into());
+    /// assert_eq!(edits[0].replacement, "");
+    /// ```
+    pub fn fmt_edits(&self) -> Vec<KdlTextEdit> {
+        let mut formatted = self.clone();
+        formatted.fmt();
+        diff(&self.to_string(), &formatted.to_string())
+    }
+
+    /// Like [`KdlDocument::fmt_edits`], but for [`KdlDocument::fmt_no_comments`].
+    pub fn fmt_no_comments_edits(&self) -> Vec<KdlTextEdit> {
+        let mut formatted = self.clone();
+        formatted.fmt_no_comments();
+        diff(&self.to_string(), &formatted.to_string())
+    }
+}
+
+/// Computes the edits that turn `old` into `new`. They're diffed line by line
+/// first, and then each changed hunk is diffed again at the level of words and
+/// runs of whitespace, so that something like a reindent only touches the
+/// whitespace that actually changed.
+pub(crate) fn diff(old: &str, new: &str) -> Vec<KdlTextEdit> {
+    let old_lines = old.split_inclusive('\n').collect::<Vec<_>>();
+    let new_lines = new.split_inclusive('\n').collect::<Vec<_>>();
+    let old_starts = starts(&old_lines, 0);
+    let new_starts = starts(&new_lines, 0);
+
+    let mut edits = Vec::new();
+    for (a, b) in hunks(&old_lines, &new_lines) {
+        let (a_start, b_start) = (old_starts[a.start], new_starts[b.start]);
+        let old_words = words(&old[a_start..old_starts[a.end]]);
+        let new_words = words(&new[b_start..new_starts[b.end]]);
+        let old_word_starts = starts(&old_words, a_start);
+        let new_word_starts = starts(&new_words, b_start);
+        let mut edit = |a: Range<usize>, b: Range<usize>| {
+            push_edit(
+                &mut edits,
+                old,
+                new,
+                old_word_starts[a.start]..old_word_starts[a.end],
+                new_word_starts[b.start]..new_word_starts[b.end],
+            )
+        };
+        // Runs of whitespace all compare equal here, so they get lined up
+        // with each other (and edited in place) rather than with whatever
+        // whitespace happens to match exactly.
+        let (mut x, mut y) = (0, 0);
+        let word_hunks = hunks(&word_keys(&old_words), &word_keys(&new_words));
+        for (a, b) in word_hunks.into_iter().chain(std::iter::once((
+            old_words.len()..old_words.len(),
+            new_words.len()..new_words.len(),
+        ))) {
+            while x < a.start {
+                edit(x..x + 1, y..y + 1);
+                x += 1;
+                y += 1;
+            }
+            if !a.is_empty() || !b.is_empty() {
+                edit(a.clone(), b.clone());
+            }
+            x = a.end;
+            y = b.end;
+        }
+    }
+    edits
+}
+
+fn word_keys<'a>(words: &[&'a str]) -> Vec<Option<&'a str>> {
+    words
+        .iter()
+        .map(|w| Some(*w).filter(|w| *w == "\n" || !w.starts_with(char::is_whitespace)))
+        .collect()
+}
+
+/// The offset of each piece, plus one past the end, given that the first
+/// piece starts at `offset`.
+fn starts(pieces: &[&str], mut offset: usize) -> Vec<usize> {
+    let mut starts = Vec::with_capacity(pieces.len() + 1);
+    starts.push(offset);
+    for piece in pieces {
+        offset += piece.len();
+        starts.push(offset);
+    }
+    starts
+}
+
+/// Splits `text` into runs of non-whitespace, runs of whitespace, and
+/// newlines.
+fn words(text: &str) -> Vec<&str> {
+    let class = |c: char| match c {
+        '\n' => 0,
+        c if c.is_whitespace() => 1,
+        _ => 2,
+    };
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        let end = match chars.peek() {
+            Some(&(i, next)) if c == '\n' || class(next) != class(c) => i,
+            Some(_) => continue,
+            None => text.len(),
+        };
+        words.push(&text[start..end]);
+        start = end;
+    }
+    words
+}
+
+fn push_edit(
+    edits: &mut Vec<KdlTextEdit>,
+    old: &str,
+    new: &str,
+    old_range: Range<usize>,
+    new_range: Range<usize>,
+) {
+    let old_text = &old[old_range.clone()];
+    let new_text = &new[new_range];
+    let prefix = old_text
+        .char_indices()
+        .zip(new_text.chars())
+        .find(|((_, a), b)| a != b)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| old_text.len().min(new_text.len()));
+    let (old_rest, new_rest) = (&old_text[prefix..], &new_text[prefix..]);
+    let suffix = old_rest
+        .chars()
+        .rev()
+        .zip(new_rest.chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum::<usize>();
+    if old_rest.len() == suffix && new_rest.len() == suffix {
+        return;
+    }
+    let start = old_range.start + prefix;
+    let end = old_range.end - suffix;
+    edits.push(KdlTextEdit {
+        span: SourceSpan::from(start..end),
+        replacement: new_rest[..new_rest.len() - suffix].into(),
+    });
+}
+
+/// Myers' diff, in linear space. Returns the changed hunks as pairs of
+/// (replaced range of `a`, replacement range of `b`), in order.
+fn hunks<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(Range<usize>, Range<usize>)> {
+    let mut kept = Vec::new();
+    kept_pairs(a, b, (0, 0), &mut kept);
+
+    // Everything between two kept lines is a hunk.
+    let mut hunks = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    for (kx, ky) in kept.into_iter().chain(std::iter::once((a.len(), b.len()))) {
+        if kx > x || ky > y {
+            hunks.push((x..kx, y..ky));
+        }
+        x = kx + 1;
+        y = ky + 1;
+    }
+    hunks
+}
+
+/// Adds the indices of the items `a` and `b` have in common (as found by a
+/// shortest edit script) to `kept`, in order, offset by `at`. This splits the
+/// problem in two at the middle of a shortest path, and solves each half in
+/// turn, so it only needs memory proportional to the length of the input.
+fn kept_pairs<T: PartialEq>(a: &[T], b: &[T], at: (usize, usize), kept: &mut Vec<(usize, usize)>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    kept.extend((0..prefix).map(|i| (at.0 + i, at.1 + i)));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    let at = (at.0 + prefix, at.1 + prefix);
+    if !a.is_empty() && !b.is_empty() {
+        if let Some((x, y)) = middle(a, b) {
+            kept_pairs(&a[..x], &b[..y], at, kept);
+            kept_pairs(&a[x..], &b[y..], (at.0 + x, at.1 + y), kept);
+        }
+    }
+    kept.extend((0..suffix).map(|i| (at.0 + a.len() + i, at.1 + b.len() + i)));
+}
+
+/// Finds a point on a shortest edit path from the start of `a` and `b` to
+/// their ends, roughly halfway along it, by searching from both ends at once
+/// until the searches meet. Returns `None` if the only path is to replace
+/// everything. `a` and `b` must differ in their first and last items.
+fn middle<T: PartialEq>(a: &[T], b: &[T]) -> Option<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m + 1) / 2;
+    let idx = |k: isize| (k + max + 1) as usize;
+    // The furthest `x` reached on each diagonal `k = x - y`, going forwards
+    // from the start, and backwards from the end (as a distance from it).
+    let mut forward = vec![-1isize; 2 * max as usize + 3];
+    let mut backward = forward.clone();
+    forward[idx(1)] = 0;
+    backward[idx(1)] = 0;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    // Diagonals that ran off the edge of the grid don't need searching.
+    let (mut f_start, mut f_end, mut b_start, mut b_end) = (0, 0, 0, 0);
+    for d in 0..max {
+        for k in (-d + f_start..=d - f_end).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[idx(k - 1)] < forward[idx(k + 1)]) {
+                forward[idx(k + 1)]
+            } else {
+                forward[idx(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[idx(k)] = x;
+            if x > n {
+                f_end += 2;
+            } else if y > m {
+                f_start += 2;
+            } else if odd {
+                let k2 = delta - k;
+                if (-max..=max).contains(&k2)
+                    && backward[idx(k2)] != -1
+                    && x >= n - backward[idx(k2)]
+                {
+                    return split(a, b, x, y);
+                }
+            }
+        }
+        for k in (-d + b_start..=d - b_end).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[idx(k - 1)] < backward[idx(k + 1)]) {
+                backward[idx(k + 1)]
+            } else {
+                backward[idx(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[idx(k)] = x;
+            if x > n {
+                b_end += 2;
+            } else if y > m {
+                b_start += 2;
+            } else if !odd {
+                let k1 = delta - k;
+                if (-max..=max).contains(&k1) && forward[idx(k1)] != -1 {
+                    let x1 = forward[idx(k1)];
+                    if x1 >= n - x {
+                        return split(a, b, x1, x1 - k1);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Checks that splitting at `(x, y)` makes progress.
+fn split<T>(a: &[T], b: &[T], x: isize, y: isize) -> Option<(usize, usize)> {
+    let (x, y) = (x as usize, y as usize);
+    let trivial = (x == 0 && y == 0) || (x == a.len() && y == b.len());
+    Some((x, y)).filter(|_| !trivial)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[track_caller]
+    fn check(old: &str, new: &str) -> Vec<KdlTextEdit> {
+        let edits = diff(old, new);
+        assert_eq!(KdlTextEdit::apply(old, &edits), new);
+        for pair in edits.windows(2) {
+            assert!(pair[0].span.offset() + pair[0].span.len() <= pair[1].span.offset());
+        }
+        edits
+    }
+
+    #[test]
+    fn diffs() {
+        assert_eq!(check("", ""), vec![]);
+        assert_eq!(check("a\nb\n", "a\nb\n"), vec![]);
+        assert_eq!(check("", "a\n").len(), 1);
+        assert_eq!(check("a\n", "").len(), 1);
+        assert_eq!(
+            check("a\nb\nc\n", "a\nx\nc\n"),
+            vec![KdlTextEdit {
+                span: (2, 1).into(),
+                replacement: "x".into()
+            }]
+        );
+        check("a\nb\nc\nd\n", "b\nc\nx\ny\nd\ne");
+        check("🎊\nb\n", "🎉\nb\n");
+        check("same\nlines\n", "added\nsame\nmore\nlines\nend\n");
+    }
+
+    #[test]
+    fn large_diff() {
+        // Every line changes, which used to take memory quadratic in the
+        // number of lines.
+        let input = (0..3000)
+            .map(|i| format!("  node{}   {}\n", i, i))
+            .collect::<String>();
+        let doc: KdlDocument = input.parse().unwrap();
+        let edits = doc.fmt_edits();
+        assert_eq!(edits.len(), 6000);
+        let mut formatted = doc.clone();
+        formatted.fmt();
+        assert_eq!(KdlTextEdit::apply(&input, &edits), formatted.to_string());
+
+        let old = (0..5000).map(|i| format!("{}\n", i)).collect::<String>();
+        let new = (0..5000)
+            .map(|i| format!("{}\n", if i % 3 == 0 { i + 1 } else { i }))
+            .collect::<String>();
+        check(&old, &new);
+    }
+
+    #[test]
+    fn source_map() {
+        let map = KdlSourceMap::new(&[
+            KdlTextEdit {
+                span: (2, 3).into(),
+                replacement: "x".into(),
+            },
+            KdlTextEdit {
+                span: (8, 0).into(),
+                replacement: "inserted".into(),
+            },
+        ]);
+        for (from, to) in [
+            ((0, 2), (0, 2)),
+            ((0, 3), (0, 3)),
+            ((2, 3), (2, 1)),
+            ((3, 1), (2, 1)),
+            ((5, 3), (3, 3)),
+            ((5, 4), (3, 12)),
+            ((8, 1), (14, 1)),
+            ((8, 0), (14, 0)),
+            ((7, 1), (5, 1)),
+        ] {
+            assert_eq!(map.to_edited(from.into()), to.into(), "{:?}", from);
+        }
+        assert_eq!(map.to_original((2, 1).into()), (2, 3).into());
+        assert_eq!(map.to_original((6, 8).into()), (8, 0).into());
+        assert_eq!(map.to_original((3, 13).into()), (5, 5).into());
+        assert_eq!(
+            KdlSourceMap::default().to_edited((4, 2).into()),
+            (4, 2).into()
+        );
+    }
+
+    #[cfg(feature = "span")]
+    #[test]
+    fn fmt_with_source_map() {
+        let input = "// hi\n  foo    1 {\nbar  \"baz\"\n      }\nqux   (t)2\n";
+        let mut doc: KdlDocument = input.parse().unwrap();
+        let original = doc.clone();
+        let map = doc.fmt_with_source_map();
+        let output = doc.to_string();
+        let mut formatted = original.clone();
+        formatted.fmt();
+        assert_eq!(output, formatted.to_string());
+
+        // Every node and entry maps onto its formatted counterpart.
+        doc.recompute_spans();
+        let old_nodes = original
+            .nodes()
+            .iter()
+            .chain(original.get("foo").unwrap().children().unwrap().nodes());
+        let new_nodes = doc
+            .nodes()
+            .iter()
+            .chain(doc.get("foo").unwrap().children().unwrap().nodes());
+        for (old, new) in old_nodes.zip(new_nodes) {
+            assert_eq!(map.to_edited(old.name().span()), new.name().span());
+            assert_eq!(map.to_original(new.name().span()), old.name().span());
+            for (old, new) in old.entries().iter().zip(new.entries()) {
+                assert_eq!(map.to_edited(old.span()), new.span());
+                assert_eq!(map.to_original(new.span()), old.span());
+            }
+        }
+    }
+
+    #[test]
+    fn fmt_edits() {
+        let input = r#"
+// comment
+  foo    1 {
+bar    "baz"
+    /* keep */      qux
+  }
+"#;
+        let doc: KdlDocument = input.parse().unwrap();
+        let edits = doc.fmt_edits();
+        let mut formatted = doc.clone();
+        formatted.fmt();
+        assert_eq!(KdlTextEdit::apply(input, &edits), formatted.to_string());
+        // Nothing outside the changed whitespace is touched.
+        assert!(edits
+            .iter()
+            .all(|e| input[e.span.offset()..][..e.span.len()]
+                .chars()
+                .all(char::is_whitespace)));
+
+        let formatted_input = formatted.to_string();
+        let doc: KdlDocument = formatted_input.parse().unwrap();
+        assert_eq!(doc.fmt_edits(), vec![]);
+
+        let mut stripped = doc.clone();
+        stripped.fmt_no_comments();
+        let edits = doc.fmt_no_comments_edits();
+        assert_eq!(
+            KdlTextEdit::apply(&formatted_input, &edits),
+            stripped.to_string()
+        );
+    }
+}
@@ -0,0 +1,536 @@
+use std::sync::Arc;
+
+use crate::{
+    parser::{is_disallowed_v2, is_space_v2},
+    versions, EmitOptions, KdlDocument, KdlEntry, KdlError, KdlErrorKind, KdlNode, KdlValue,
+    KdlVersion, MultiLineStrings, NonFiniteFloats,
+};
+
+impl KdlDocument {
+    /// Writes this document out as a string, like its
+    /// [`Display`](std::fmt::Display) implementation but following
+    /// `options`. This only fails if `options` asks for it, such as when a
+    /// float is NaN under [`NonFiniteFloats::Error`].
+    ///
+    /// # Examples
+    ///
+    /// Non-finite floats:
+    ///
+    /// ```rust
+    /// # use kdl::{EmitOptions, KdlDocument, KdlEntry, KdlNode, NonFiniteFloats};
+    /// let mut node = KdlNode::new("ratio");
+    /// node.push(f64::INFINITY);
+    /// let mut doc = KdlDocument::new();
+    /// doc.nodes_mut().push(node);
+    ///
+    /// let err = doc.to_string_with_options(&EmitOptions::default()).unwrap_err();
+    /// assert_eq!(err.kind.code_str(), "kdl::non_finite_float");
+    ///
+    /// let options = EmitOptions {
+    ///     non_finite_floats: NonFiniteFloats::Annotation,
+    ///     ..EmitOptions::default()
+    /// };
+    /// let out = doc.to_string_with_options(&options).unwrap();
+    /// assert_eq!(out, "ratio (f64)\"inf\"\n");
+    /// let reparsed: KdlDocument = out.parse().unwrap();
+    /// assert_eq!(reparsed.nodes()[0].entries()[0].as_f64(), Some(f64::INFINITY));
+    /// ```
+    ///
+    /// Multi-line strings:
+    ///
+    /// ```rust
+    /// # use kdl::{EmitOptions, KdlDocument, KdlVersion, MultiLineStrings, ParseOptions};
+    /// let doc: KdlDocument = "job {\n    run \"cargo build\\ncargo test\"\n}\n".parse().unwrap();
+    /// let options = EmitOptions {
+    ///     multi_line_strings: MultiLineStrings::LongerThan(0),
+    ///     ..EmitOptions::default()
+    /// };
+    /// let out = doc.to_string_with_options(&options).unwrap();
+    /// assert_eq!(out, r#"job {
+    ///     run """
+    ///         cargo build
+    ///         cargo test
+    ///         """
+    /// }
+    /// "#);
+    ///
+    /// let options = ParseOptions {
+    ///     version: KdlVersion::V2,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let reparsed = KdlDocument::parse_with_options(&out, &options).unwrap();
+    /// assert_eq!(
+    ///     reparsed.get("job").unwrap().children().unwrap().get_arg("run"),
+    ///     Some(&"cargo build\ncargo test".into())
+    /// );
+    /// ```
+    pub fn to_string_with_options(&self, options: &EmitOptions) -> Result<String, KdlError> {
+        let mut doc = match options.version {
+            Some(version) => self.converted_for_emit(version),
+            None => self.clone(),
+        };
+        if let Some(redaction) = &options.redact {
+            doc.redact(&redaction.placeholder, |node, entry| {
+                redaction.matches(node, entry)
+            });
+        }
+        if let MultiLineStrings::LongerThan(len) = options.multi_line_strings {
+            multi_line_strings(&mut doc, 0, len);
+        }
+        let mut offending = false;
+        let non_finite_floats = match options.version {
+            // The conversion already wrote them as keywords.
+            Some(KdlVersion::V2) => None,
+            _ => Some(options.non_finite_floats),
+        };
+        non_finite_entries(&mut doc, &mut |entry, value| match non_finite_floats {
+            None => {}
+            Some(NonFiniteFloats::Error) => offending = true,
+            // KDL 2.0 keywords are the only reprs that have to go.
+            Some(NonFiniteFloats::Clamp)
+                if entry
+                    .value_repr
+                    .as_deref()
+                    .map_or(false, |r| r.starts_with('#')) =>
+            {
+                entry.value_repr = None
+            }
+            Some(NonFiniteFloats::Clamp) => {}
+            Some(NonFiniteFloats::Keyword) => {
+                entry.value_repr = Some(format!("#{}", non_finite_name(value)));
+            }
+            Some(NonFiniteFloats::Annotation) => {
+                entry.set_ty("f64");
+                let name = if value.is_nan() {
+                    "NaN"
+                } else {
+                    non_finite_name(value)
+                };
+                entry.value_repr = Some(format!("{:?}", name));
+            }
+        });
+        if !offending {
+            return Ok(version_marker(doc.to_string(), options, self.version));
+        }
+
+        #[cfg(feature = "span")]
+        doc.recompute_spans();
+        let mut span = None;
+        non_finite_entries(&mut doc, &mut |_entry, _| {
+            #[cfg(feature = "span")]
+            span.get_or_insert(_entry.span());
+            #[cfg(not(feature = "span"))]
+            span.get_or_insert((0..0).into());
+        });
+        Err(KdlError {
+            input: Arc::new(doc.to_string().as_str().into()),
+            span: span.unwrap(),
+            label: Some("non-finite float"),
+            related_span: None,
+            related_label: None,
+            help: Some("KDL 1.0 has no syntax for NaN or infinity. Replace the value, or pick a different NonFiniteFloats policy."),
+            kind: KdlErrorKind::NonFiniteFloat,
+        })
+    }
+
+    /// Writes this document out as KDL 2.0, starting with a
+    /// `/- kdl-version 2` marker. This is the same as
+    /// [`KdlDocument::to_string_with_options`] with `version` set to
+    /// [`KdlVersion::V2`] and `version_marker` set, so whatever the document
+    /// was parsed as, and however it was changed since, the output is valid
+    /// KDL 2.0. Errors from writing it are passed on.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlDocument, KdlEntry, KdlVersion};
+    /// let mut doc: KdlDocument = r#"server true name="web""#.parse().unwrap();
+    /// assert_eq!(doc.version(), KdlVersion::V1);
+    /// doc.nodes_mut()[0].push(KdlEntry::new_prop("debug", false));
+    /// // `Display` keeps writing the document as KDL 1.0...
+    /// assert_eq!(doc.to_string(), r#"server true name="web" debug=false"#);
+    /// // ...but `to_string_v2` writes all of it as KDL 2.0.
+    /// let out = doc.to_string_v2().unwrap();
+    /// assert_eq!(out, "/- kdl-version 2\nserver #true name=\"web\" debug=#false");
+    /// assert_eq!(KdlDocument::parse_auto(&out).unwrap().version(), KdlVersion::V2);
+    /// ```
+    pub fn to_string_v2(&self) -> Result<String, KdlError> {
+        let options = EmitOptions {
+            version: Some(KdlVersion::V2),
+            version_marker: true,
+            ..EmitOptions::default()
+        };
+        self.to_string_with_options(&options)
+    }
+}
+
+impl KdlEntry {
+    /// Returns this entry's value as an `f64`, if it's a float. NaN and
+    /// infinities written as `(f64)"NaN"`, `(f64)"inf"`, or `(f64)"-inf"`
+    /// (see [`NonFiniteFloats::Annotation`]) are understood too, as is the
+    /// `(f32)` annotation.
+    pub fn as_f64(&self) -> Option<f64> {
+        if let Some(value) = self.value().as_f64() {
+            return Some(value);
+        }
+        let ty = self.ty()?.value();
+        if ty != "f64" && ty != "f32" {
+            return None;
+        }
+        match self.value().as_string()? {
+            "NaN" => Some(f64::NAN),
+            "inf" => Some(f64::INFINITY),
+            "-inf" => Some(f64::NEG_INFINITY),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn non_finite_name(value: f64) -> &'static str {
+    if value.is_nan() {
+        "nan"
+    } else if value > 0.0 {
+        "inf"
+    } else {
+        "-inf"
+    }
+}
+
+/// Rewrites the strings in `doc` (at nesting level `depth`) that have
+/// newlines and are longer than `len` characters in multi-line form.
+fn multi_line_strings(doc: &mut KdlDocument, depth: usize, len: usize) {
+    for node in doc.nodes_mut() {
+        let indent = string_indent(node, depth);
+        for entry in node.entries_mut() {
+            let already_multi_line = entry
+                .value_repr
+                .as_ref()
+                .map_or(false, |repr| repr.contains(['\n', '\r']));
+            if already_multi_line {
+                continue;
+            }
+            let repr = match entry.value() {
+                KdlValue::String(text) if text.contains('\n') && text.chars().count() > len => {
+                    Some(multi_line_string(text, &indent))
+                }
+                KdlValue::RawString(text) if text.contains('\n') && text.chars().count() > len => {
+                    multi_line_raw_string(text, &indent)
+                }
+                _ => None,
+            };
+            if let Some(repr) = repr {
+                entry.value_repr = Some(repr);
+            }
+        }
+        if let Some(children) = node.children_mut() {
+            multi_line_strings(children, depth + 1, len);
+        }
+    }
+}
+
+/// The indentation for the lines of a multi-line string in one of `node`'s
+/// entries: one level past the node itself, which is at nesting level
+/// `depth`.
+pub(crate) fn string_indent(node: &KdlNode, depth: usize) -> String {
+    let mut indent = match node.leading.as_deref() {
+        Some(leading) => {
+            let line = leading.rsplit(['\n', '\r']).next().unwrap_or_default();
+            line.chars().take_while(|c| is_space_v2(*c)).collect()
+        }
+        None => " ".repeat(depth * 4),
+    };
+    indent.push_str("    ");
+    indent
+}
+
+fn multi_line_string(text: &str, indent: &str) -> String {
+    let mut body = String::new();
+    let mut quotes = 0;
+    for line in text.split('\n') {
+        body.push('\n');
+        if line.is_empty() {
+            continue;
+        }
+        body.push_str(indent);
+        let mut chars = line.chars();
+        // Whitespace-only lines would come out empty, so escape the first
+        // character of one to keep it.
+        if line.chars().all(is_space_v2) {
+            match chars.next() {
+                Some(' ') => body.push_str("\\s"),
+                Some('\t') => body.push_str("\\t"),
+                Some(c) => body.push_str(&format!("\\u{{{:x}}}", c as u32)),
+                None => {}
+            }
+        }
+        for c in chars {
+            match c {
+                // Only every third quote in a row needs escaping, so that
+                // they don't end the string.
+                '"' if quotes == 2 => {
+                    body.push_str("\\\"");
+                    quotes = 0;
+                    continue;
+                }
+                '"' => body.push('"'),
+                '\\' => body.push_str("\\\\"),
+                '\r' => body.push_str("\\r"),
+                '\u{08}' => body.push_str("\\b"),
+                '\u{0C}' => body.push_str("\\f"),
+                '\u{0B}' | '\u{85}' | '\u{2028}' | '\u{2029}' => {
+                    body.push_str(&format!("\\u{{{:x}}}", c as u32))
+                }
+                c if is_disallowed_v2(c) => body.push_str(&format!("\\u{{{:x}}}", c as u32)),
+                _ => body.push(c),
+            }
+            quotes = if c == '"' { quotes + 1 } else { 0 };
+        }
+        quotes = 0;
+    }
+    format!("\"\"\"{}\n{}\"\"\"", body, indent)
+}
+
+pub(crate) fn multi_line_raw_string(text: &str, indent: &str) -> Option<String> {
+    // Raw strings can't escape anything, so ones with other kinds of
+    // newlines, whitespace-only lines, or characters KDL 2.0 doesn't allow
+    // have to stay on one line.
+    let other_newline = |c| {
+        matches!(
+            c,
+            '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+        )
+    };
+    let blank = |line: &str| !line.is_empty() && line.chars().all(is_space_v2);
+    if text.contains(other_newline)
+        || text.contains(is_disallowed_v2)
+        || text.split('\n').any(blank)
+    {
+        return None;
+    }
+    let mut hashes = "#".to_string();
+    while text.contains(&format!("\"\"\"{}", hashes)) {
+        hashes.push('#');
+    }
+    let mut body = String::new();
+    for line in text.split('\n') {
+        body.push('\n');
+        if !line.is_empty() {
+            body.push_str(indent);
+            body.push_str(line);
+        }
+    }
+    Some(format!(
+        "{}\"\"\"{}\n{}\"\"\"{}",
+        hashes, body, indent, hashes
+    ))
+}
+
+/// Adds or updates the `/- kdl-version` marker at the start of `out`, as
+/// asked for by `options`.
+fn version_marker(mut out: String, options: &EmitOptions, version: KdlVersion) -> String {
+    let number = match options.version.unwrap_or(version) {
+        KdlVersion::V1 => "1",
+        KdlVersion::V2 => "2",
+    };
+    if let Some(range) = versions::version_marker_number(&out) {
+        if options.version.is_some() && out[range.clone()] != *number {
+            out.replace_range(range, number);
+        }
+    } else if options.version_marker {
+        let bom = if out.starts_with('\u{FEFF}') {
+            '\u{FEFF}'.len_utf8()
+        } else {
+            0
+        };
+        out.insert_str(bom, &format!("/- kdl-version {}\n", number));
+    }
+    out
+}
+
+fn non_finite_entries(doc: &mut KdlDocument, f: &mut impl FnMut(&mut KdlEntry, f64)) {
+    for node in doc.nodes_mut() {
+        for entry in node.entries_mut() {
+            if let KdlValue::Base10Float(value) = *entry.value() {
+                if !value.is_finite() {
+                    f(entry, value);
+                }
+            }
+        }
+        if let Some(children) = node.children_mut() {
+            non_finite_entries(children, f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn doc() -> KdlDocument {
+        let mut doc: KdlDocument = "a 1.5\nb {\n    c 0.0 1\n}\n".parse().unwrap();
+        let children = doc.nodes_mut()[1].children_mut().as_mut().unwrap();
+        let c = &mut children.nodes_mut()[0];
+        c.entries_mut()[0].set_value(f64::NAN);
+        c.push(f64::NEG_INFINITY);
+        let mut d = KdlNode::new("d");
+        d.push(f64::INFINITY);
+        doc.nodes_mut().push(d);
+        doc
+    }
+
+    fn emit(doc: &KdlDocument, policy: NonFiniteFloats) -> Result<String, KdlError> {
+        doc.to_string_with_options(&EmitOptions {
+            non_finite_floats: policy,
+            ..EmitOptions::default()
+        })
+    }
+
+    #[test]
+    fn policies() {
+        let doc = doc();
+        assert_eq!(emit(&doc, NonFiniteFloats::Clamp).unwrap(), doc.to_string());
+        assert_eq!(
+            emit(&doc, NonFiniteFloats::Keyword).unwrap(),
+            "a 1.5\nb {\n    c #nan 1 #-inf\n}\nd #inf\n"
+        );
+        let annotated = emit(&doc, NonFiniteFloats::Annotation).unwrap();
+        assert_eq!(
+            annotated,
+            "a 1.5\nb {\n    c (f64)\"NaN\" 1 (f64)\"-inf\"\n}\nd (f64)\"inf\"\n"
+        );
+        let reparsed: KdlDocument = annotated.parse().unwrap();
+        let c = &reparsed.nodes()[1].children().unwrap().nodes()[0];
+        assert!(c.entries()[0].as_f64().unwrap().is_nan());
+        assert_eq!(c.entries()[1].as_f64(), None);
+        assert_eq!(c.entries()[2].as_f64(), Some(f64::NEG_INFINITY));
+        assert_eq!(reparsed.nodes()[0].entries()[0].as_f64(), Some(1.5));
+    }
+
+    #[test]
+    fn error() {
+        let err = emit(&doc(), NonFiniteFloats::Error).unwrap_err();
+        assert_eq!(err.kind, KdlErrorKind::NonFiniteFloat);
+        #[cfg(feature = "span")]
+        assert_eq!(
+            &err.input.text[err.span.offset()..][..err.span.len()],
+            "0.0"
+        );
+        let fine: KdlDocument = "a 1.5".parse().unwrap();
+        assert_eq!(emit(&fine, NonFiniteFloats::Error).unwrap(), "a 1.5");
+    }
+
+    #[test]
+    fn versions() {
+        let options = |version, version_marker| EmitOptions {
+            version,
+            version_marker,
+            ..EmitOptions::default()
+        };
+        let mut doc: KdlDocument = "// settings\nnode true r\"raw\" null\n".parse().unwrap();
+        doc.nodes_mut()[0].push(f64::NAN);
+        assert_eq!(
+            doc.to_string_v2().unwrap(),
+            "/- kdl-version 2\n// settings\nnode #true #\"raw\"# #null #nan\n"
+        );
+        assert_eq!(
+            doc.to_string_with_options(&options(Some(KdlVersion::V2), false))
+                .unwrap(),
+            "// settings\nnode #true #\"raw\"# #null #nan\n"
+        );
+        // KDL 1.0 output still goes through `non_finite_floats`.
+        let err = doc
+            .to_string_with_options(&options(Some(KdlVersion::V1), true))
+            .unwrap_err();
+        assert_eq!(err.kind, KdlErrorKind::NonFiniteFloat);
+        doc.nodes_mut()[0].remove(3);
+        assert_eq!(
+            doc.to_string_with_options(&options(None, true)).unwrap(),
+            "/- kdl-version 1\n// settings\nnode true r\"raw\" null\n"
+        );
+
+        // Existing markers are kept, and updated if the version changes.
+        let v2 = KdlDocument::parse_auto("/- kdl-version 2\nnode #false #inf").unwrap();
+        assert_eq!(v2.to_string_v2().unwrap(), v2.to_string());
+        let mut options = options(Some(KdlVersion::V1), false);
+        options.non_finite_floats = NonFiniteFloats::Annotation;
+        assert_eq!(
+            v2.to_string_with_options(&options).unwrap(),
+            "/- kdl-version 1\nnode false (f64)\"inf\""
+        );
+        options.non_finite_floats = NonFiniteFloats::Clamp;
+        let clamped = v2.to_string_with_options(&options).unwrap();
+        assert!(clamped.parse::<KdlDocument>().is_ok(), "{}", clamped);
+    }
+
+    #[test]
+    fn from_finite() {
+        assert_eq!(KdlValue::from_finite(2.5), Some(KdlValue::Base10Float(2.5)));
+        assert_eq!(KdlValue::from_finite(f64::NAN), None);
+        assert_eq!(KdlValue::from_finite(-f64::INFINITY), None);
+    }
+
+    #[test]
+    fn multi_line_strings() {
+        let input = "a \"one\\ntwo\" short=\"x\\ny\"\nb {\n    // comment\n  c \"\\\"\\\"\\\"\\\"\\n  \\n\\\\\"\n}\n";
+        let mut doc: KdlDocument = input.parse().unwrap();
+        let c = &mut doc.nodes_mut()[1]
+            .children_mut()
+            .as_mut()
+            .unwrap()
+            .nodes_mut()[0];
+        c.push(KdlValue::RawString("raw\"\"\"#\n".into()));
+        let options = EmitOptions {
+            multi_line_strings: MultiLineStrings::LongerThan(3),
+            ..EmitOptions::default()
+        };
+        let out = doc.to_string_with_options(&options).unwrap();
+        assert_eq!(
+            out,
+            "a \"\"\"\n    one\n    two\n    \"\"\" short=\"x\\ny\"\nb {\n    // comment\n  c \"\"\"\n      \"\"\\\"\"\n      \\s \n      \\\\\n      \"\"\" ##\"\"\"\n      raw\"\"\"#\n\n      \"\"\"##\n}\n"
+        );
+        let options = crate::ParseOptions {
+            version: crate::KdlVersion::V2,
+            ..crate::ParseOptions::default()
+        };
+        let reparsed = KdlDocument::parse_with_options(&out, &options).unwrap();
+        let values = |doc: &KdlDocument| {
+            let c = &doc.nodes()[1].children().unwrap().nodes()[0];
+            vec![
+                doc.nodes()[0].entries()[0].value().clone(),
+                c.entries()[0].value().clone(),
+                c.entries()[1].value().clone(),
+            ]
+        };
+        assert_eq!(values(&reparsed), values(&doc));
+        assert_eq!(
+            doc.to_string_with_options(&EmitOptions::default()).unwrap(),
+            doc.to_string()
+        );
+
+        let mut doc = KdlDocument::new();
+        let mut node = KdlNode::new("a");
+        node.push("x\u{1}\ny\u{7F}");
+        node.push(KdlValue::RawString("x\u{1}\ny".into()));
+        doc.nodes_mut().push(node);
+        let options = EmitOptions {
+            version: Some(KdlVersion::V2),
+            multi_line_strings: MultiLineStrings::LongerThan(0),
+            ..EmitOptions::default()
+        };
+        let out = doc.to_string_with_options(&options).unwrap();
+        assert_eq!(
+            out,
+            "a \"\"\"\n    x\\u{1}\n    y\\u{7f}\n    \"\"\" \"\"\"\n    x\\u{1}\n    y\n    \"\"\"\n"
+        );
+        let options = crate::ParseOptions {
+            version: KdlVersion::V2,
+            ..crate::ParseOptions::default()
+        };
+        let reparsed = KdlDocument::parse_with_options(&out, &options).unwrap();
+        assert_eq!(reparsed.nodes()[0].get(0), Some(&"x\u{1}\ny\u{7F}".into()));
+        assert_eq!(reparsed.nodes()[0].get(1), Some(&"x\u{1}\ny".into()));
+
+        let v1: KdlDocument = "n r\"x\u{1}\ny\"\n".parse().unwrap();
+        assert_eq!(v1.to_v2().to_string(), "n \"x\\u{1}\\ny\"\n");
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt::Display;
+use std::io;
+use std::sync::Arc;
+
+use crate::{KdlDocument, KdlReadError, KdlSource, KdlWarning, KdlWarningKind};
+
+/// A text encoding [`KdlDocument::parse_bytes`] can read documents in.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum KdlEncoding {
+    /// UTF-8, with or without a byte order mark.
+    Utf8,
+
+    /// Little-endian UTF-16. Requires the `encoding` feature.
+    Utf16Le,
+
+    /// Big-endian UTF-16. Requires the `encoding` feature.
+    Utf16Be,
+
+    /// ISO 8859-1, where every byte is the character with the same code
+    /// point. Requires the `encoding` feature.
+    Latin1,
+}
+
+impl Display for KdlEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KdlEncoding::Utf8 => write!(f, "UTF-8"),
+            KdlEncoding::Utf16Le => write!(f, "UTF-16LE"),
+            KdlEncoding::Utf16Be => write!(f, "UTF-16BE"),
+            KdlEncoding::Latin1 => write!(f, "Latin-1"),
+        }
+    }
+}
+
+impl KdlDocument {
+    /// Parses a document from raw bytes, returning it along with any
+    /// [`KdlWarning`]s, like [`KdlDocument::parse_with_warnings`].
+    ///
+    /// The input is read as UTF-8, with or without a byte order mark. With
+    /// the `encoding` feature, UTF-16 input (detected by its byte order
+    /// mark, or by the zero bytes ASCII text has in it) and input that isn't
+    /// valid UTF-8, which is read as Latin-1, are decoded first, and a
+    /// [`KdlWarningKind::Transcoded`] warning says which encoding was used.
+    /// Without the feature, anything but UTF-8 is an
+    /// [`io::ErrorKind::InvalidData`] error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let (doc, warnings) = KdlDocument::parse_bytes(b"foo 1").unwrap();
+    /// assert_eq!(doc.get_arg("foo"), Some(&1.into()));
+    /// assert!(warnings.is_empty());
+    /// ```
+    pub fn parse_bytes(bytes: &[u8]) -> Result<(Self, Vec<KdlWarning>), KdlReadError> {
+        let (text, encoding) = decode(bytes)?;
+        let (doc, mut warnings) = KdlDocument::parse_with_warnings(&text)?;
+        if encoding != KdlEncoding::Utf8 {
+            warnings.insert(
+                0,
+                KdlWarning {
+                    input: Arc::new(KdlSource::from(text)),
+                    span: (0, 0).into(),
+                    label: Some("decoded from here"),
+                    help: Some("Save this file as UTF-8 so other KDL tools can read it."),
+                    kind: KdlWarningKind::Transcoded(encoding),
+                },
+            );
+        }
+        Ok((doc, warnings))
+    }
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(not(feature = "encoding"))]
+fn decode(bytes: &[u8]) -> Result<(String, KdlEncoding), io::Error> {
+    let text = std::str::from_utf8(bytes).map_err(invalid_data)?;
+    Ok((text.into(), KdlEncoding::Utf8))
+}
+
+#[cfg(feature = "encoding")]
+fn decode(bytes: &[u8]) -> Result<(String, KdlEncoding), io::Error> {
+    let utf16 = match bytes {
+        [0xFF, 0xFE, rest @ ..] => Some((rest, KdlEncoding::Utf16Le)),
+        [0xFE, 0xFF, rest @ ..] => Some((rest, KdlEncoding::Utf16Be)),
+        [a, 0, ..] if *a != 0 => Some((bytes, KdlEncoding::Utf16Le)),
+        [0, b, ..] if *b != 0 => Some((bytes, KdlEncoding::Utf16Be)),
+        _ => None,
+    };
+    if let Some((bytes, encoding)) = utf16 {
+        if bytes.len() % 2 != 0 {
+            return Err(invalid_data("UTF-16 input has an odd number of bytes"));
+        }
+        let units = bytes.chunks(2).map(|pair| match encoding {
+            KdlEncoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+            _ => u16::from_be_bytes([pair[0], pair[1]]),
+        });
+        let text = std::char::decode_utf16(units)
+            .collect::<Result<String, _>>()
+            .map_err(invalid_data)?;
+        return Ok((text, encoding));
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Ok((text.into(), KdlEncoding::Utf8)),
+        Err(_) => Ok((
+            bytes.iter().map(|&b| char::from(b)).collect(),
+            KdlEncoding::Latin1,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn utf8() {
+        let (doc, warnings) = KdlDocument::parse_bytes("\u{FEFF}foo \"é\"".as_bytes()).unwrap();
+        assert_eq!(doc.get_arg("foo"), Some(&"é".into()));
+        assert_eq!(doc.to_string(), "\u{FEFF}foo \"é\"");
+        let kinds: Vec<_> = warnings.into_iter().map(|w| w.kind).collect();
+        assert_eq!(kinds, vec![KdlWarningKind::ByteOrderMark]);
+    }
+
+    #[cfg(not(feature = "encoding"))]
+    #[test]
+    fn utf8_only() {
+        let err = KdlDocument::parse_bytes(b"foo \"\xe9\"").unwrap_err();
+        assert!(matches!(err, KdlReadError::Io(e) if e.kind() == io::ErrorKind::InvalidData));
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn transcoding() {
+        fn utf16(text: &str, bom: bool, le: bool) -> Vec<u8> {
+            let bom = if bom { Some(0xFEFF) } else { None };
+            bom.into_iter()
+                .chain(text.encode_utf16())
+                .flat_map(|u| if le { u.to_le_bytes() } else { u.to_be_bytes() })
+                .collect()
+        }
+        for (bytes, encoding) in [
+            (utf16("foo \"é\"", true, true), KdlEncoding::Utf16Le),
+            (utf16("foo \"é\"", false, true), KdlEncoding::Utf16Le),
+            (utf16("foo \"é\"", true, false), KdlEncoding::Utf16Be),
+            (utf16("foo \"é\"", false, false), KdlEncoding::Utf16Be),
+            (b"foo \"\xe9\"".to_vec(), KdlEncoding::Latin1),
+        ] {
+            let (doc, warnings) = KdlDocument::parse_bytes(&bytes).unwrap();
+            assert_eq!(doc.to_string(), "foo \"é\"", "{}", encoding);
+            let kinds: Vec<_> = warnings.into_iter().map(|w| w.kind).collect();
+            assert_eq!(kinds, vec![KdlWarningKind::Transcoded(encoding)]);
+        }
+
+        let err = KdlDocument::parse_bytes(&[0xFF, 0xFE, b'a']).unwrap_err();
+        assert!(matches!(err, KdlReadError::Io(e) if e.kind() == io::ErrorKind::InvalidData));
+        let err = KdlDocument::parse_bytes(&utf16("foo 1.", true, true)).unwrap_err();
+        assert!(matches!(err, KdlReadError::Parse(_)));
+    }
+}
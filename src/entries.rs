@@ -0,0 +1,235 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut},
+};
+
+use crate::{util::OnceBox, KdlEntry, KdlValue};
+
+/// Nodes with fewer entries than this just search them for properties,
+/// which is faster than hashing when there are only a few.
+const INDEX_THRESHOLD: usize = 16;
+
+/// A node's entries, along with an index of its properties by name, so that
+/// looking properties up in nodes with lots of them takes constant time.
+///
+/// The index is built the first time a property is looked up, without a
+/// lock, and thrown away whenever the entries are borrowed mutably, since
+/// they might change. The one exception is a single entry handed out
+/// through [`KdlEntries::entry_mut`], which is only checked the next time
+/// the entries are changed, and makes lookups search the entries until
+/// then if it was renamed.
+#[derive(Default)]
+pub(crate) struct KdlEntries {
+    entries: Vec<KdlEntry>,
+    /// Maps each property name to the position of the last entry with that
+    /// name, since that's the one that counts.
+    index: OnceBox<HashMap<String, usize>>,
+    /// The entry last handed out by [`KdlEntries::entry_mut`] while the
+    /// index was built, and the name it had then.
+    changed: Option<(usize, Option<String>)>,
+}
+
+impl KdlEntries {
+    /// Finds the position of the last property named `name`.
+    pub(crate) fn prop_position(&self, name: &str) -> Option<usize> {
+        if self.entries.len() < INDEX_THRESHOLD || self.renamed() {
+            return rposition(&self.entries, name);
+        }
+        self.index
+            .get_or_init(|| build_index(&self.entries))
+            .get(name)
+            .copied()
+    }
+
+    /// Gets a mutable reference to the entry at `idx`, keeping the index
+    /// unless the entry turns out to have been renamed.
+    pub(crate) fn entry_mut(&mut self, idx: usize) -> &mut KdlEntry {
+        self.settle();
+        if self.index.get().is_some() {
+            let name = self.entries[idx]
+                .name
+                .as_ref()
+                .map(|name| name.value().into());
+            self.changed = Some((idx, name));
+        }
+        &mut self.entries[idx]
+    }
+
+    /// Gets a mutable reference to the value of the entry at `idx`. Values
+    /// can't change which entries are properties, or what they're called,
+    /// so the index is kept.
+    pub(crate) fn value_mut(&mut self, idx: usize) -> &mut KdlValue {
+        &mut self.entries[idx].value
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<KdlEntry> {
+        self.entries
+    }
+
+    /// Whether the entry from the last [`KdlEntries::entry_mut`] isn't
+    /// called what the index thinks anymore.
+    fn renamed(&self) -> bool {
+        self.changed.as_ref().map_or(false, |(idx, name)| {
+            self.entries[*idx].name.as_ref().map(|name| name.value()) != name.as_deref()
+        })
+    }
+
+    /// Drops the index if it's out of date, since nothing can be changing
+    /// the entries anymore.
+    fn settle(&mut self) {
+        if self.renamed() {
+            self.index.clear();
+        }
+        self.changed = None;
+    }
+}
+
+fn rposition(entries: &[KdlEntry], name: &str) -> Option<usize> {
+    entries
+        .iter()
+        .rposition(|entry| entry.name.as_ref().map(|i| i.value()) == Some(name))
+}
+
+fn build_index(entries: &[KdlEntry]) -> HashMap<String, usize> {
+    let mut index = HashMap::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        if let Some(name) = &entry.name {
+            index.insert(name.value().to_string(), idx);
+        }
+    }
+    index
+}
+
+impl Deref for KdlEntries {
+    type Target = Vec<KdlEntry>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl DerefMut for KdlEntries {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.index.clear();
+        self.changed = None;
+        &mut self.entries
+    }
+}
+
+impl From<Vec<KdlEntry>> for KdlEntries {
+    fn from(entries: Vec<KdlEntry>) -> Self {
+        Self {
+            entries,
+            index: OnceBox::new(),
+            changed: None,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a KdlEntries {
+    type Item = &'a KdlEntry;
+    type IntoIter = std::slice::Iter<'a, KdlEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut KdlEntries {
+    type Item = &'a mut KdlEntry;
+    type IntoIter = std::slice::IterMut<'a, KdlEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl Clone for KdlEntries {
+    fn clone(&self) -> Self {
+        self.entries.clone().into()
+    }
+}
+
+impl PartialEq for KdlEntries {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl Eq for KdlEntries {}
+
+impl Hash for KdlEntries {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.entries.hash(state);
+    }
+}
+
+impl fmt::Debug for KdlEntries {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.entries.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entries(count: usize) -> KdlEntries {
+        let mut entries = Vec::new();
+        for i in 0..count {
+            entries.push(KdlEntry::new(i as i64));
+            entries.push(KdlEntry::new_prop(format!("p{}", i % 10), i as i64));
+        }
+        entries.into()
+    }
+
+    #[test]
+    fn lookups() {
+        for count in [1, 5, 50] {
+            let mut entries = entries(count);
+            for i in 0..count.min(10) {
+                let name = format!("p{}", i);
+                let last = (0..count).rev().find(|j| j % 10 == i).unwrap();
+                assert_eq!(entries.prop_position(&name), Some(last * 2 + 1));
+            }
+            assert_eq!(entries.prop_position("missing"), None);
+
+            // Changing the entries drops the index.
+            entries.push(KdlEntry::new_prop("p0", "new"));
+            entries[0].name = Some("first".into());
+            assert_eq!(entries.prop_position("p0"), Some(entries.len() - 1));
+            assert_eq!(entries.prop_position("first"), Some(0));
+            *entries.value_mut(0) = KdlValue::Null;
+            assert_eq!(entries.prop_position("first"), Some(0));
+
+            // So does renaming an entry, but changing it otherwise doesn't.
+            entries.entry_mut(0).set_value(1);
+            assert_eq!(entries.prop_position("first"), Some(0));
+            entries.entry_mut(0).name = Some("renamed".into());
+            assert_eq!(entries.prop_position("first"), None);
+            assert_eq!(entries.prop_position("renamed"), Some(0));
+            entries.entry_mut(1);
+            assert_eq!(entries.prop_position("renamed"), Some(0));
+            assert_eq!(entries.prop_position("first"), None);
+        }
+    }
+
+    #[test]
+    fn shared() {
+        fn check<T: Send + Sync>() {}
+        check::<KdlEntries>();
+
+        let entries = std::sync::Arc::new(entries(50));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let entries = entries.clone();
+                std::thread::spawn(move || entries.prop_position("p9"))
+            })
+            .collect();
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), Some(99));
+        }
+    }
+}
@@ -1,8 +1,10 @@
 #[cfg(feature = "span")]
 use miette::SourceSpan;
-use std::{fmt::Display, str::FromStr};
+use std::{borrow::Cow, fmt::Display, str::FromStr};
 
-use crate::{parser, KdlError, KdlIdentifier, KdlValue};
+use crate::{
+    parser, versions, IntegerOverflow, KdlError, KdlIdentifier, KdlValue, KdlVersion, ParseOptions,
+};
 
 /// KDL Entries are the "arguments" to KDL nodes: either a (positional)
 /// [`Argument`](https://github.com/kdl-org/kdl/blob/main/SPEC.md#argument) or
@@ -18,6 +20,10 @@ pub struct KdlEntry {
     pub(crate) trailing: Option<String>,
     #[cfg(feature = "span")]
     pub(crate) span: SourceSpan,
+    /// The span of the type annotation, parentheses included, or `0..0` if
+    /// it wasn't parsed.
+    #[cfg(feature = "span")]
+    pub(crate) ty_span: SourceSpan,
 }
 
 impl PartialEq for KdlEntry {
@@ -28,7 +34,8 @@ impl PartialEq for KdlEntry {
             && self.value_repr == other.value_repr
             && self.name == other.name
             && self.trailing == other.trailing
-        // intentionally omitted: self.span == other.span
+        // intentionally omitted: self.span == other.span, self.ty_span ==
+        // other.ty_span
     }
 }
 
@@ -40,7 +47,8 @@ impl std::hash::Hash for KdlEntry {
         self.value_repr.hash(state);
         self.name.hash(state);
         self.trailing.hash(state);
-        // intentionally omitted: self.span.hash(state)
+        // intentionally omitted: self.span.hash(state),
+        // self.ty_span.hash(state)
     }
 }
 
@@ -56,6 +64,8 @@ impl KdlEntry {
             trailing: None,
             #[cfg(feature = "span")]
             span: SourceSpan::from(0..0),
+            #[cfg(feature = "span")]
+            ty_span: SourceSpan::from(0..0),
         }
     }
 
@@ -81,9 +91,13 @@ impl KdlEntry {
 
     /// Gets this entry's span.
     ///
-    /// This value will be properly initialized when created via [`KdlDocument::parse`]
-    /// but may become invalidated if the document is mutated. We do not currently
-    /// guarantee this to yield any particularly consistent results at that point.
+    /// The span covers the entry from its property name (or type annotation,
+    /// for arguments) to the end of its value, but not any surrounding
+    /// whitespace.
+    ///
+    /// This value will be properly initialized when parsed with [`str::parse`].
+    /// See [`KdlNode::span`](crate::KdlNode::span) for how spans are kept up
+    /// to date after edits.
     #[cfg(feature = "span")]
     pub fn span(&self) -> SourceSpan {
         self.span
@@ -95,6 +109,61 @@ impl KdlEntry {
         self.span = span.into();
     }
 
+    /// Recomputes the spans of this entry and its identifiers so they point
+    /// into its current string representation, as if `entry.to_string()`
+    /// had been parsed again.
+    #[cfg(feature = "span")]
+    pub fn recompute_spans(&mut self) {
+        self.recompute_spans_at(0, KdlVersion::V1);
+    }
+
+    /// Mirrors [`KdlEntry::stringify`], assigning spans as if the output
+    /// started at `offset`. Returns the offset just past it.
+    #[cfg(feature = "span")]
+    pub(crate) fn recompute_spans_at(&mut self, mut offset: usize, version: KdlVersion) -> usize {
+        if let Some(leading) = &self.leading {
+            offset += leading.len();
+        }
+        let start = offset;
+        if let Some(name) = &mut self.name {
+            offset = name.recompute_span_at(offset, version) + "=".len();
+        }
+        if let Some(ty) = &mut self.ty {
+            let ty_start = offset;
+            offset = ty.recompute_span_at(offset + "(".len(), version) + ")".len();
+            self.ty_span = SourceSpan::from(ty_start..offset);
+        }
+        offset += self.value_repr_in(version).len();
+        self.span = SourceSpan::from(start..offset);
+        if let Some(trailing) = &self.trailing {
+            offset += trailing.len();
+        }
+        offset
+    }
+
+    /// Moves this entry's spans by `delta` bytes.
+    #[cfg(feature = "span")]
+    pub(crate) fn shift_spans(&mut self, delta: isize) {
+        self.span = crate::identifier::shifted(self.span, delta);
+        self.ty_span = crate::identifier::shifted(self.ty_span, delta);
+        if let Some(name) = &mut self.name {
+            name.shift_span(delta);
+        }
+        if let Some(ty) = &mut self.ty {
+            ty.shift_span(delta);
+        }
+    }
+
+    /// Gets this entry's value as a string, but only if it's annotated with
+    /// the type `ty`.
+    pub(crate) fn annotated_string(&self, ty: &str) -> Option<&str> {
+        if self.ty()?.value() == ty {
+            self.value().as_string()
+        } else {
+            None
+        }
+    }
+
     /// Gets the entry's type.
     pub fn ty(&self) -> Option<&KdlIdentifier> {
         self.ty.as_ref()
@@ -121,6 +190,8 @@ impl KdlEntry {
             trailing: None,
             #[cfg(feature = "span")]
             span: SourceSpan::from(0..0),
+            #[cfg(feature = "span")]
+            ty_span: SourceSpan::from(0..0),
         }
     }
 
@@ -159,15 +230,98 @@ impl KdlEntry {
     }
 
     /// Gets the custom string representation for this KdlEntry's [`KdlValue`].
+    ///
+    /// For parsed entries, this is exactly the text the value was parsed
+    /// from, spelled the way it was written: `_` separators, radix prefixes,
+    /// exponents, and string quoting style are all preserved. It's `None`
+    /// for entries created in code, and after [`KdlEntry::fmt`] or
+    /// [`KdlEntry::clear_fmt`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlEntry, KdlValue};
+    /// let entry: KdlEntry = "0xdead_beef".parse().unwrap();
+    /// assert_eq!(entry.value(), &KdlValue::Base16(0xdeadbeef));
+    /// assert_eq!(entry.value_repr(), Some("0xdead_beef"));
+    /// ```
     pub fn value_repr(&self) -> Option<&str> {
         self.value_repr.as_deref()
     }
 
     /// Sets a custom string representation for this KdlEntry's [`KdlValue`].
+    ///
+    /// The representation is written out as-is, even if it doesn't match
+    /// the value (or isn't valid KDL at all). Use
+    /// [`KdlEntry::try_set_value_repr`] to have it checked.
     pub fn set_value_repr(&mut self, repr: impl Into<String>) {
         self.value_repr = Some(repr.into());
     }
 
+    /// Parses `repr` as a KDL 1.0 value, then sets it as this entry's
+    /// representation and the parsed value as its value, so the two always
+    /// agree. The entry is left unchanged if `repr` isn't a single valid
+    /// value.
+    ///
+    /// Use [`KdlEntry::try_set_value_repr_with_options`] for entries in
+    /// KDL 2.0 documents.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlEntry, KdlValue};
+    /// let mut entry = KdlEntry::new_prop("mask", 255);
+    /// entry.try_set_value_repr("0b1111_1111").unwrap();
+    /// assert_eq!(entry.value(), &KdlValue::Base2(255));
+    /// assert_eq!(entry.to_string(), "mask=0b1111_1111");
+    ///
+    /// assert!(entry.try_set_value_repr("0b2").is_err());
+    /// assert_eq!(entry.value_repr(), Some("0b1111_1111"));
+    /// ```
+    pub fn try_set_value_repr(&mut self, repr: impl Into<String>) -> Result<(), KdlError> {
+        self.try_set_value_repr_with_options(repr, &ParseOptions::default())
+    }
+
+    /// Like [`KdlEntry::try_set_value_repr`], but parses `repr` with the
+    /// given [`ParseOptions`]: pass the `version` of the document the entry
+    /// is in, so that its representation is written the way that version
+    /// needs. Integers too large for an `i128` are always errors here,
+    /// whatever `options.integer_overflow` is.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlEntry, KdlValue, KdlVersion, ParseOptions};
+    /// let options = ParseOptions {
+    ///     version: KdlVersion::V2,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let mut entry = KdlEntry::new(false);
+    /// entry.try_set_value_repr_with_options("#true", &options).unwrap();
+    /// assert_eq!(entry.value(), &KdlValue::Bool(true));
+    /// assert!(entry.try_set_value_repr("#true").is_err());
+    /// ```
+    pub fn try_set_value_repr_with_options(
+        &mut self,
+        repr: impl Into<String>,
+        options: &ParseOptions,
+    ) -> Result<(), KdlError> {
+        let repr = repr.into();
+        let options = ParseOptions {
+            integer_overflow: IntegerOverflow::Error,
+            ..options.clone()
+        };
+        let kdl_parser = parser::KdlParser::with_options(&repr, &options);
+        let (_, value) = kdl_parser.parse(parser::lone_value(&kdl_parser))?;
+        self.value = value;
+        self.value_repr = Some(repr);
+        Ok(())
+    }
+
+    pub(crate) fn clear_value_repr(&mut self) {
+        self.value_repr = None;
+    }
+
     /// Length of this entry when rendered as a string.
     pub fn len(&self) -> usize {
         format!("{}", self).len()
@@ -179,10 +333,17 @@ impl KdlEntry {
     }
 
     /// Auto-formats this entry.
+    ///
+    /// Numbers annotated with `(decimal)` keep their original text, since
+    /// reformatting them through `f64` could change their value.
     pub fn fmt(&mut self) {
         self.leading = None;
         self.trailing = None;
-        self.value_repr = None;
+        let decimal = matches!(self.value, KdlValue::Base10Float(_))
+            && self.ty().map(|ty| ty.value()) == Some("decimal");
+        if !decimal {
+            self.value_repr = None;
+        }
         if let Some(name) = &mut self.name {
             name.fmt();
         }
@@ -191,25 +352,54 @@ impl KdlEntry {
 
 impl Display for KdlEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.stringify(f, KdlVersion::V1)
+    }
+}
+
+impl KdlEntry {
+    /// This entry as it's written in a document of the given version. See
+    /// [`KdlEntry::stringify`].
+    pub(crate) fn to_string_in(&self, version: KdlVersion) -> String {
+        struct InVersion<'a>(&'a KdlEntry, KdlVersion);
+        impl Display for InVersion<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.stringify(f, self.1)
+            }
+        }
+        InVersion(self, version).to_string()
+    }
+
+    /// Writes this entry out the way a document of the given version would.
+    /// Only the parts without a repr of their own depend on the version.
+    pub(crate) fn stringify(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        version: KdlVersion,
+    ) -> std::fmt::Result {
         if let Some(leading) = &self.leading {
             write!(f, "{}", leading)?;
         }
         if let Some(name) = &self.name {
-            write!(f, "{}=", name)?;
+            write!(f, "{}=", name.repr_in(version))?;
         }
         if let Some(ty) = &self.ty {
-            write!(f, "({})", ty)?;
-        }
-        if let Some(repr) = &self.value_repr {
-            write!(f, "{}", repr)?;
-        } else {
-            write!(f, "{}", self.value)?;
+            write!(f, "({})", ty.repr_in(version))?;
         }
+        write!(f, "{}", self.value_repr_in(version))?;
         if let Some(trailing) = &self.trailing {
             write!(f, "{}", trailing)?;
         }
         Ok(())
     }
+
+    /// This entry's value as it's written in a document of the given
+    /// version.
+    pub(crate) fn value_repr_in(&self, version: KdlVersion) -> Cow<'_, str> {
+        match &self.value_repr {
+            Some(repr) => Cow::Borrowed(repr),
+            None => versions::value_repr(&self.value, version),
+        }
+    }
 }
 
 impl<T> From<T> for KdlEntry
@@ -255,6 +445,53 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn value_repr() -> miette::Result<()> {
+        for raw in [
+            "1_000",
+            "+0o7_7",
+            "-0B101",
+            "1.5E+3",
+            "r##\"a\"#b\"##",
+            "\"\\u{41}\"",
+        ] {
+            let entry: KdlEntry = format!("name={}", raw).parse()?;
+            assert_eq!(entry.value_repr(), Some(raw));
+
+            let mut entry = KdlEntry::new(KdlValue::Null);
+            entry.try_set_value_repr(raw)?;
+            assert_eq!(entry.to_string(), raw);
+            assert_eq!(entry.value(), raw.parse::<KdlEntry>()?.value());
+        }
+
+        let mut entry = KdlEntry::new(1);
+        for bad in ["", "1 2", " 1", "foo", "\"unterminated", "1/*x*/"] {
+            assert!(entry.try_set_value_repr(bad).is_err(), "{:?}", bad);
+        }
+        assert_eq!(entry, KdlEntry::new(1));
+
+        let v2 = ParseOptions {
+            version: crate::KdlVersion::V2,
+            ..ParseOptions::default()
+        };
+        for (raw, value) in [
+            ("#null", KdlValue::Null),
+            ("bare", KdlValue::String("bare".into())),
+            ("#\"raw\"#", KdlValue::RawString("raw".into())),
+        ] {
+            let err = entry.try_set_value_repr(raw).unwrap_err();
+            assert_eq!(err.kind, crate::KdlErrorKind::Context("valid value"));
+            entry.try_set_value_repr_with_options(raw, &v2)?;
+            assert_eq!(entry.value(), &value);
+            assert_eq!(entry.to_string(), raw);
+        }
+        let err = entry
+            .try_set_value_repr_with_options("1 2", &v2)
+            .unwrap_err();
+        assert_eq!(err.kind, crate::KdlErrorKind::Context("a single value"));
+        Ok(())
+    }
+
     #[test]
     fn new() {
         let entry = KdlEntry::new(42);
@@ -269,6 +506,8 @@ mod test {
                 trailing: None,
                 #[cfg(feature = "span")]
                 span: SourceSpan::from(0..0),
+                #[cfg(feature = "span")]
+                ty_span: SourceSpan::from(0..0),
             }
         );
 
@@ -284,6 +523,8 @@ mod test {
                 trailing: None,
                 #[cfg(feature = "span")]
                 span: SourceSpan::from(0..0),
+                #[cfg(feature = "span")]
+                ty_span: SourceSpan::from(0..0),
             }
         );
     }
@@ -302,6 +543,8 @@ mod test {
                 trailing: Some("\t\\\n".into()),
                 #[cfg(feature = "span")]
                 span: SourceSpan::from(0..0),
+                #[cfg(feature = "span")]
+                ty_span: SourceSpan::from(0..0),
             }
         );
 
@@ -317,6 +560,8 @@ mod test {
                 trailing: Some("\t\\\n".into()),
                 #[cfg(feature = "span")]
                 span: SourceSpan::from(0..0),
+                #[cfg(feature = "span")]
+                ty_span: SourceSpan::from(0..0),
             }
         );
 
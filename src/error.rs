@@ -1,6 +1,9 @@
-use std::num::{ParseFloatError, ParseIntError};
+use std::{
+    num::{ParseFloatError, ParseIntError},
+    sync::Arc,
+};
 
-use miette::{Diagnostic, SourceSpan};
+use miette::{Diagnostic, MietteError, MietteSpanContents, SourceCode, SourceSpan, SpanContents};
 use nom::error::{ContextError, ErrorKind, FromExternalError, ParseError};
 use thiserror::Error;
 
@@ -25,8 +28,9 @@ use {
 ///
 /// This will display a message like:
 /// ```text
-/// Error:
-///   × Expected valid value.
+/// Error: kdl::invalid_number
+///
+///   × Invalid number.
 ///    ╭────
 ///  1 │ foo 1.
 ///    ·     ─┬
@@ -36,10 +40,11 @@ use {
 /// ```
 #[derive(Debug, Diagnostic, Clone, Eq, PartialEq, Error)]
 #[error("{kind}")]
+#[diagnostic(forward(kind))]
 pub struct KdlError {
-    /// Source string for the KDL document that failed to parse.
+    /// Source for the KDL document that failed to parse.
     #[source_code]
-    pub input: String,
+    pub input: Arc<KdlSource>,
 
     /// Offset in chars of the error.
     #[label("{}", label.unwrap_or("here"))]
@@ -48,6 +53,14 @@ pub struct KdlError {
     /// Label text for this span. Defaults to `"here"`.
     pub label: Option<&'static str>,
 
+    /// A secondary location related to the error, such as where the input
+    /// ran out while looking for the end of an unterminated string.
+    #[label("{}", related_label.unwrap_or("related"))]
+    pub related_span: Option<SourceSpan>,
+
+    /// Label text for [`KdlError::related_span`].
+    pub related_label: Option<&'static str>,
+
     /// Suggestion for fixing the parser error.
     #[help]
     pub help: Option<&'static str>,
@@ -56,7 +69,107 @@ pub struct KdlError {
     pub kind: KdlErrorKind,
 }
 
+impl KdlError {
+    /// Attaches a name (usually a file path) to the source of this error, so
+    /// that reports point at `name:line:column`.
+    ///
+    /// This is useful when the error came from somewhere that doesn't take a
+    /// [`ParseOptions`](crate::ParseOptions), such as [`str::parse`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let err = "foo 1.".parse::<KdlDocument>().unwrap_err();
+    /// let err = err.with_source_name("config/app.kdl");
+    /// assert_eq!(err.input.name.as_deref(), Some("config/app.kdl"));
+    /// ```
+    pub fn with_source_name(mut self, name: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.input).name = Some(name.into());
+        self
+    }
+}
+
+/// Source text for a diagnostic, along with an optional name for where it
+/// came from.
+///
+/// When a name is set, diagnostic reports will show it alongside the line and
+/// column of the snippet, for example `[config/app.kdl:12:1]`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct KdlSource {
+    /// Name of the source, usually a file path.
+    pub name: Option<String>,
+
+    /// The full source text.
+    pub text: String,
+}
+
+impl KdlSource {
+    /// Creates a new, unnamed source.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            name: None,
+            text: text.into(),
+        }
+    }
+
+    /// Creates a new source with the given name.
+    pub fn named(name: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            name: Some(name.into()),
+            text: text.into(),
+        }
+    }
+}
+
+impl From<&str> for KdlSource {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<String> for KdlSource {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl AsRef<str> for KdlSource {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl SourceCode for KdlSource {
+    fn read_span<'a>(
+        &'a self,
+        span: &SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> Result<Box<dyn SpanContents<'a> + 'a>, MietteError> {
+        let contents = self
+            .text
+            .read_span(span, context_lines_before, context_lines_after)?;
+        Ok(match &self.name {
+            Some(name) => Box::new(MietteSpanContents::new_named(
+                name.clone(),
+                contents.data(),
+                *contents.span(),
+                contents.line(),
+                contents.column(),
+                contents.line_count(),
+            )),
+            None => contents,
+        })
+    }
+}
+
 /// A type reprenting additional information specific to the type of error being returned.
+///
+/// Every variant carries a stable diagnostic code (e.g. `kdl::unterminated_string`)
+/// which is also available as a plain string through [`KdlErrorKind::code_str`].
+/// These codes will not change between releases, so they're safe to match on
+/// in tooling.
 #[derive(Debug, Diagnostic, Clone, Eq, PartialEq, Error)]
 pub enum KdlErrorKind {
     /// An error occurred while parsing an integer.
@@ -69,6 +182,106 @@ pub enum KdlErrorKind {
     #[diagnostic(code(kdl::parse_float))]
     ParseFloatError(ParseFloatError),
 
+    /// A quoted string was opened but never closed.
+    #[error("Unterminated string.")]
+    #[diagnostic(code(kdl::unterminated_string))]
+    UnterminatedString,
+
+    /// A string contained an escape sequence that isn't valid KDL.
+    #[error("Invalid escape sequence.")]
+    #[diagnostic(code(kdl::invalid_escape))]
+    InvalidEscape,
+
+    /// A KDL 2.0 multi-line string didn't start with a newline, didn't have
+    /// its closing quotes on their own line, or had a line that wasn't
+    /// indented as far as the closing quotes.
+    #[error("Invalid multi-line string.")]
+    #[diagnostic(code(kdl::invalid_multi_line_string))]
+    InvalidMultiLineString,
+
+    /// A multi-line (`/* */`) comment was opened but never closed.
+    #[error("Unterminated multi-line comment.")]
+    #[diagnostic(code(kdl::unterminated_comment))]
+    UnterminatedComment,
+
+    /// An identifier contained a character that isn't allowed in bare
+    /// identifiers.
+    #[error("Invalid identifier character.")]
+    #[diagnostic(code(kdl::invalid_identifier))]
+    InvalidIdentifierChar,
+
+    /// A reserved keyword (`true`, `false`, `null`) was used as a bare
+    /// identifier.
+    #[error("Reserved keyword used as an identifier.")]
+    #[diagnostic(code(kdl::reserved_keyword))]
+    ReservedKeyword,
+
+    /// A `#` keyword other than KDL 2.0's `#true`, `#false`, `#null`, `#inf`,
+    /// `#-inf`, and `#nan` was used as a value.
+    #[error("Unknown keyword.")]
+    #[diagnostic(code(kdl::unknown_keyword))]
+    UnknownKeyword,
+
+    /// A KDL 2.0 document contained a character that isn't allowed anywhere
+    /// in it, not even in strings or comments: most control characters,
+    /// `U+007F`, Unicode direction controls, or a byte order mark anywhere
+    /// but the very start.
+    #[error("Disallowed character.")]
+    #[diagnostic(code(kdl::disallowed_character))]
+    DisallowedCharacter,
+
+    /// A bare identifier was found where a value or new node was expected.
+    #[error("Bare identifier used as a value.")]
+    #[diagnostic(code(kdl::bare_identifier))]
+    BareIdentifier,
+
+    /// A number literal (decimal, float, hexadecimal, octal or binary) was
+    /// malformed.
+    #[error("Invalid number.")]
+    #[diagnostic(code(kdl::invalid_number))]
+    InvalidNumber,
+
+    /// A property had a missing or invalid value.
+    #[error("Invalid property value.")]
+    #[diagnostic(code(kdl::invalid_value))]
+    InvalidValue,
+
+    /// A type annotation was malformed or never closed.
+    #[error("Invalid type annotation.")]
+    #[diagnostic(code(kdl::invalid_annotation))]
+    InvalidAnnotation,
+
+    /// A line escape (`\`) was followed by something other than whitespace
+    /// and a newline.
+    #[error("Invalid line escape.")]
+    #[diagnostic(code(kdl::invalid_line_escape))]
+    InvalidLineEscape,
+
+    /// A node was followed by something other than `;`, a newline, or the
+    /// end of input.
+    #[error("Invalid node terminator.")]
+    #[diagnostic(code(kdl::invalid_node_terminator))]
+    InvalidNodeTerminator,
+
+    /// A children block was opened with `{` but never closed with a matching
+    /// `}`.
+    #[error("Unbalanced children block.")]
+    #[diagnostic(code(kdl::unbalanced_children))]
+    UnbalancedChildren,
+
+    /// A float was NaN or infinite while writing a document with
+    /// [`NonFiniteFloats::Error`](crate::NonFiniteFloats::Error).
+    #[error("Non-finite float can't be represented in KDL.")]
+    #[diagnostic(code(kdl::non_finite_float))]
+    NonFiniteFloat,
+
+    /// A [`KdlTextEdit`](crate::KdlTextEdit)'s span ran past the end of the
+    /// text it was applied to, or started or ended in the middle of a
+    /// character.
+    #[error("Invalid edit span.")]
+    #[diagnostic(code(kdl::invalid_edit))]
+    InvalidEdit,
+
     /// Generic parsing error. The given context string denotes the component
     /// that failed to parse.
     #[error("Expected {0}.")]
@@ -82,6 +295,101 @@ pub enum KdlErrorKind {
     Other,
 }
 
+impl KdlErrorKind {
+    /// Returns the stable diagnostic code for this error kind, as a plain
+    /// string (e.g. `"kdl::unterminated_string"`). This is the same code
+    /// reported through [`miette::Diagnostic::code`].
+    pub fn code_str(&self) -> &'static str {
+        use KdlErrorKind::*;
+        match self {
+            ParseIntError(_) => "kdl::parse_int",
+            ParseFloatError(_) => "kdl::parse_float",
+            UnterminatedString => "kdl::unterminated_string",
+            InvalidEscape => "kdl::invalid_escape",
+            InvalidMultiLineString => "kdl::invalid_multi_line_string",
+            UnterminatedComment => "kdl::unterminated_comment",
+            InvalidIdentifierChar => "kdl::invalid_identifier",
+            ReservedKeyword => "kdl::reserved_keyword",
+            UnknownKeyword => "kdl::unknown_keyword",
+            DisallowedCharacter => "kdl::disallowed_character",
+            BareIdentifier => "kdl::bare_identifier",
+            InvalidNumber => "kdl::invalid_number",
+            InvalidValue => "kdl::invalid_value",
+            InvalidAnnotation => "kdl::invalid_annotation",
+            InvalidLineEscape => "kdl::invalid_line_escape",
+            InvalidNodeTerminator => "kdl::invalid_node_terminator",
+            UnbalancedChildren => "kdl::unbalanced_children",
+            NonFiniteFloat => "kdl::non_finite_float",
+            InvalidEdit => "kdl::invalid_edit",
+            Context(_) => "kdl::parse_component",
+            Other => "kdl::other",
+        }
+    }
+
+    /// Returns `true` if this error is confined to a single token (a number,
+    /// an escape, a misused identifier, etc), meaning the surrounding
+    /// document structure is still intact and the rest of the input could
+    /// be understood if that token were fixed or skipped.
+    ///
+    /// Structural errors, such as unterminated strings or comments, or
+    /// unbalanced children blocks, are not recoverable since everything after
+    /// them is ambiguous.
+    pub fn is_recoverable(&self) -> bool {
+        use KdlErrorKind::*;
+        matches!(
+            self,
+            ParseIntError(_)
+                | ParseFloatError(_)
+                | InvalidEscape
+                | InvalidMultiLineString
+                | InvalidIdentifierChar
+                | ReservedKeyword
+                | UnknownKeyword
+                | DisallowedCharacter
+                | BareIdentifier
+                | InvalidNumber
+                | InvalidValue
+                | InvalidAnnotation
+        )
+    }
+
+    /// Returns `true` if this error is about a malformed number literal.
+    pub fn is_number_error(&self) -> bool {
+        use KdlErrorKind::*;
+        matches!(self, ParseIntError(_) | ParseFloatError(_) | InvalidNumber)
+    }
+
+    /// Returns `true` if this error is about a malformed string literal.
+    pub fn is_string_error(&self) -> bool {
+        matches!(
+            self,
+            KdlErrorKind::UnterminatedString
+                | KdlErrorKind::InvalidEscape
+                | KdlErrorKind::InvalidMultiLineString
+        )
+    }
+
+    /// Returns `true` if this error is about a misplaced or malformed
+    /// identifier.
+    pub fn is_identifier_error(&self) -> bool {
+        use KdlErrorKind::*;
+        matches!(
+            self,
+            InvalidIdentifierChar | ReservedKeyword | BareIdentifier
+        )
+    }
+
+    /// Returns `true` if this error is about the overall structure of the
+    /// document (node terminators, line escapes, children blocks, comments).
+    pub fn is_structural(&self) -> bool {
+        use KdlErrorKind::*;
+        matches!(
+            self,
+            UnterminatedComment | InvalidLineEscape | InvalidNodeTerminator | UnbalancedChildren
+        )
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub(crate) struct KdlParseError<I> {
     pub(crate) input: I,
@@ -90,6 +398,7 @@ pub(crate) struct KdlParseError<I> {
     pub(crate) label: Option<&'static str>,
     pub(crate) help: Option<&'static str>,
     pub(crate) kind: Option<KdlErrorKind>,
+    pub(crate) related: Option<Box<(I, &'static str)>>,
     pub(crate) touched: bool,
 }
 
@@ -102,6 +411,7 @@ impl<I> ParseError<I> for KdlParseError<I> {
             help: None,
             context: None,
             kind: None,
+            related: None,
             touched: false,
         }
     }
@@ -127,6 +437,7 @@ impl<'a> FromExternalError<&'a str, ParseIntError> for KdlParseError<&'a str> {
             help: None,
             context: None,
             kind: Some(KdlErrorKind::ParseIntError(e)),
+            related: None,
             touched: false,
         }
     }
@@ -141,6 +452,7 @@ impl<'a> FromExternalError<&'a str, ParseFloatError> for KdlParseError<&'a str>
             help: None,
             context: None,
             kind: Some(KdlErrorKind::ParseFloatError(e)),
+            related: None,
             touched: false,
         }
     }
@@ -7,7 +7,7 @@ pub(crate) fn fmt_leading(leading: &mut String, indent: usize, no_comments: bool
     let mut result = String::new();
     if !no_comments {
         let input = leading.trim();
-        let kdl_parser = crate::parser::KdlParser { full_input: input };
+        let kdl_parser = crate::parser::KdlParser::new(input);
         let comments = kdl_parser
             .parse(crate::parser::leading_comments(&kdl_parser))
             .expect("invalid leading text");
@@ -30,7 +30,7 @@ pub(crate) fn fmt_trailing(decor: &mut String, no_comments: bool) {
     let mut result = String::new();
     if !no_comments {
         let input = &*decor;
-        let kdl_parser = crate::parser::KdlParser { full_input: input };
+        let kdl_parser = crate::parser::KdlParser::new(input);
         let comments = kdl_parser
             .parse(crate::parser::trailing_comments(&kdl_parser))
             .expect("invalid trailing text");
@@ -0,0 +1,421 @@
+use crate::{KdlDocument, KdlEntry, KdlNode};
+
+/// A [`KdlDocument`] that records the edits made to it, so they can be
+/// undone and redone, for interactive editors.
+///
+/// Only the edits themselves are kept, along with what they replaced,
+/// rather than a copy of the document for each one. Nodes are addressed by
+/// their paths, as in [`KdlDocument::cursor_at`]: `[1, 0]` is the first
+/// child of the document's second node. Every edit returns `None` or `false`,
+/// and isn't recorded, if there's nothing at its path.
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::{KdlDocument, KdlEntry, KdlHistory, KdlNode};
+/// let doc: KdlDocument = "server { port 80; }\n".parse().unwrap();
+/// let mut history = KdlHistory::new(doc);
+/// let saved = history.savepoint();
+///
+/// history.set_entry(&[0, 0], 0, KdlEntry::new(8080));
+/// history.insert_node(&[1], KdlNode::new("client"));
+/// assert_eq!(history.document().to_string(), "server { port 8080; }\nclient\n");
+///
+/// history.undo();
+/// assert_eq!(history.document().to_string(), "server { port 8080; }\n");
+/// history.redo();
+/// assert_eq!(history.document().to_string(), "server { port 8080; }\nclient\n");
+///
+/// assert!(!history.is_at(&saved));
+/// history.restore(&saved);
+/// assert_eq!(history.document().to_string(), "server { port 80; }\n");
+/// ```
+#[derive(Debug, Clone)]
+pub struct KdlHistory {
+    document: KdlDocument,
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    /// Identifies the document's current version, for savepoints.
+    state: u64,
+    next_state: u64,
+}
+
+/// A version of the document in a [`KdlHistory`], returned by
+/// [`KdlHistory::savepoint`], that can be checked for or gone back to later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KdlSavepoint(u64);
+
+#[derive(Debug, Clone)]
+struct Step {
+    /// The edit that goes back to `state`.
+    edit: Edit,
+    state: u64,
+}
+
+#[derive(Debug, Clone)]
+enum Edit {
+    InsertNode {
+        path: Vec<usize>,
+        node: KdlNode,
+    },
+    RemoveNode {
+        path: Vec<usize>,
+        /// Whether to remove the parent's children block if this leaves it
+        /// empty, because inserting the node added it.
+        drop_children: bool,
+    },
+    ReplaceNode {
+        path: Vec<usize>,
+        node: KdlNode,
+    },
+    InsertEntry {
+        path: Vec<usize>,
+        index: usize,
+        entry: KdlEntry,
+    },
+    RemoveEntry {
+        path: Vec<usize>,
+        index: usize,
+    },
+    SetEntry {
+        path: Vec<usize>,
+        index: usize,
+        entry: KdlEntry,
+    },
+}
+
+impl KdlHistory {
+    /// Starts recording edits to `document`.
+    pub fn new(document: KdlDocument) -> Self {
+        Self {
+            document,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            state: 0,
+            next_state: 1,
+        }
+    }
+
+    /// Gets the document, as it is now.
+    pub fn document(&self) -> &KdlDocument {
+        &self.document
+    }
+
+    /// Stops recording, and returns the document.
+    pub fn into_document(self) -> KdlDocument {
+        self.document
+    }
+
+    /// Inserts `node` so that it ends up at `path`, moving the nodes after
+    /// it along. If its parent has no children block, one is added.
+    pub fn insert_node(&mut self, path: &[usize], node: KdlNode) -> bool {
+        self.record(Edit::InsertNode {
+            path: path.to_vec(),
+            node,
+        })
+        .is_some()
+    }
+
+    /// Removes the node at `path`, and returns it.
+    pub fn remove_node(&mut self, path: &[usize]) -> Option<KdlNode> {
+        match self.record(Edit::RemoveNode {
+            path: path.to_vec(),
+            drop_children: false,
+        })? {
+            Edit::InsertNode { node, .. } => Some(node.clone()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Replaces the node at `path` with `node`, and returns the old one.
+    pub fn replace_node(&mut self, path: &[usize], node: KdlNode) -> Option<KdlNode> {
+        match self.record(Edit::ReplaceNode {
+            path: path.to_vec(),
+            node,
+        })? {
+            Edit::ReplaceNode { node, .. } => Some(node.clone()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Changes the node at `path` with `f`, as a single edit that replaces
+    /// the whole node. This is the way to make changes the other methods
+    /// don't cover, like renaming a node or reformatting it.
+    pub fn update_node(&mut self, path: &[usize], f: impl FnOnce(&mut KdlNode)) -> bool {
+        let mut node = match node_mut(&mut self.document, path) {
+            Some(node) => node.clone(),
+            None => return false,
+        };
+        f(&mut node);
+        self.replace_node(path, node).is_some()
+    }
+
+    /// Inserts `entry` at `index` in the entries of the node at `path`.
+    pub fn insert_entry(&mut self, path: &[usize], index: usize, entry: KdlEntry) -> bool {
+        self.record(Edit::InsertEntry {
+            path: path.to_vec(),
+            index,
+            entry,
+        })
+        .is_some()
+    }
+
+    /// Removes the entry at `index` from the node at `path`, and returns it.
+    pub fn remove_entry(&mut self, path: &[usize], index: usize) -> Option<KdlEntry> {
+        match self.record(Edit::RemoveEntry {
+            path: path.to_vec(),
+            index,
+        })? {
+            Edit::InsertEntry { entry, .. } => Some(entry.clone()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Replaces the entry at `index` in the node at `path` with `entry`, and
+    /// returns the old one.
+    pub fn set_entry(&mut self, path: &[usize], index: usize, entry: KdlEntry) -> Option<KdlEntry> {
+        match self.record(Edit::SetEntry {
+            path: path.to_vec(),
+            index,
+            entry,
+        })? {
+            Edit::SetEntry { entry, .. } => Some(entry.clone()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Undoes the last edit that hasn't been undone. Returns `false` if
+    /// there's nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        step(
+            &mut self.document,
+            &mut self.state,
+            &mut self.undo,
+            &mut self.redo,
+        )
+    }
+
+    /// Redoes the last edit that was undone. Returns `false` if there's
+    /// nothing to redo. Making a new edit throws away everything that could
+    /// have been redone.
+    pub fn redo(&mut self) -> bool {
+        step(
+            &mut self.document,
+            &mut self.state,
+            &mut self.redo,
+            &mut self.undo,
+        )
+    }
+
+    /// Whether there's an edit to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Whether there's an edit to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Marks the document's current version, so that you can check whether
+    /// it's changed since, like whether it's been saved, or undo or redo
+    /// back to it with [`KdlHistory::restore`].
+    pub fn savepoint(&self) -> KdlSavepoint {
+        KdlSavepoint(self.state)
+    }
+
+    /// Whether the document is at `savepoint`'s version, having undone or
+    /// redone any edits made since.
+    pub fn is_at(&self, savepoint: &KdlSavepoint) -> bool {
+        self.state == savepoint.0
+    }
+
+    /// Undoes or redoes edits until the document is back at `savepoint`'s
+    /// version. Returns `false`, and changes nothing, if that version can't
+    /// be reached anymore, because it could only have been redone before a
+    /// new edit was made.
+    pub fn restore(&mut self, savepoint: &KdlSavepoint) -> bool {
+        if self.undo.iter().any(|step| step.state == savepoint.0) {
+            while !self.is_at(savepoint) {
+                self.undo();
+            }
+        } else if self.redo.iter().any(|step| step.state == savepoint.0) {
+            while !self.is_at(savepoint) {
+                self.redo();
+            }
+        }
+        self.is_at(savepoint)
+    }
+
+    /// Makes an edit, and returns the one that undoes it.
+    fn record(&mut self, edit: Edit) -> Option<&Edit> {
+        let undo = apply(&mut self.document, edit)?;
+        self.undo.push(Step {
+            edit: undo,
+            state: self.state,
+        });
+        self.redo.clear();
+        self.state = self.next_state;
+        self.next_state += 1;
+        self.undo.last().map(|step| &step.edit)
+    }
+}
+
+impl From<KdlDocument> for KdlHistory {
+    fn from(document: KdlDocument) -> Self {
+        Self::new(document)
+    }
+}
+
+/// Takes a step off of `from`, and puts the step that goes back onto `to`.
+fn step(doc: &mut KdlDocument, state: &mut u64, from: &mut Vec<Step>, to: &mut Vec<Step>) -> bool {
+    let step = match from.pop() {
+        Some(step) => step,
+        None => return false,
+    };
+    let edit = apply(doc, step.edit).expect("recorded edits always apply");
+    to.push(Step {
+        edit,
+        state: *state,
+    });
+    *state = step.state;
+    true
+}
+
+/// Applies `edit` to `doc`, and returns the edit that undoes it, or `None`
+/// if there was nothing at its path.
+fn apply(doc: &mut KdlDocument, edit: Edit) -> Option<Edit> {
+    match edit {
+        Edit::InsertNode { path, node } => {
+            let (&index, parent) = path.split_last()?;
+            let len = match parent {
+                [] => doc.nodes().len(),
+                _ => node_mut(doc, parent)?
+                    .children()
+                    .map_or(0, |children| children.nodes().len()),
+            };
+            if index > len {
+                return None;
+            }
+            let (children, created) = match parent {
+                [] => (doc, false),
+                _ => {
+                    let parent = node_mut(doc, parent)?;
+                    let created = parent.children().is_none();
+                    (parent.ensure_children(), created)
+                }
+            };
+            children.nodes_mut().insert(index, node);
+            Some(Edit::RemoveNode {
+                path,
+                drop_children: created,
+            })
+        }
+        Edit::RemoveNode {
+            path,
+            drop_children,
+        } => {
+            let (&index, parent) = path.split_last()?;
+            let node = match parent {
+                [] => remove(doc, index)?,
+                _ => {
+                    let parent = node_mut(doc, parent)?;
+                    let node = remove(parent.children_mut().as_mut()?, index)?;
+                    if drop_children && parent.children().map_or(false, |c| c.nodes().is_empty()) {
+                        *parent.children_mut() = None;
+                    }
+                    node
+                }
+            };
+            Some(Edit::InsertNode { path, node })
+        }
+        Edit::ReplaceNode { path, node } => {
+            let old = std::mem::replace(node_mut(doc, &path)?, node);
+            Some(Edit::ReplaceNode { path, node: old })
+        }
+        Edit::InsertEntry { path, index, entry } => {
+            let entries = node_mut(doc, &path)?.entries_mut();
+            if index > entries.len() {
+                return None;
+            }
+            entries.insert(index, entry);
+            Some(Edit::RemoveEntry { path, index })
+        }
+        Edit::RemoveEntry { path, index } => {
+            let entries = node_mut(doc, &path)?.entries_mut();
+            if index >= entries.len() {
+                return None;
+            }
+            let entry = entries.remove(index);
+            Some(Edit::InsertEntry { path, index, entry })
+        }
+        Edit::SetEntry { path, index, entry } => {
+            let old = std::mem::replace(node_mut(doc, &path)?.entries_mut().get_mut(index)?, entry);
+            Some(Edit::SetEntry {
+                path,
+                index,
+                entry: old,
+            })
+        }
+    }
+}
+
+fn node_mut<'a>(doc: &'a mut KdlDocument, path: &[usize]) -> Option<&'a mut KdlNode> {
+    let (&index, parents) = path.split_last()?;
+    let mut doc = doc;
+    for &i in parents {
+        doc = doc.nodes_mut().get_mut(i)?.children_mut().as_mut()?;
+    }
+    doc.nodes_mut().get_mut(index)
+}
+
+fn remove(doc: &mut KdlDocument, index: usize) -> Option<KdlNode> {
+    (index < doc.nodes().len()).then(|| doc.nodes_mut().remove(index))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::KdlValue;
+
+    #[test]
+    fn undo_redo() -> miette::Result<()> {
+        let input = "a 1 {\n    b\n}\nc";
+        let mut history = KdlHistory::new(input.parse()?);
+        let start = history.savepoint();
+        assert!(!history.can_undo());
+        assert!(!history.undo());
+
+        assert!(history.insert_node(&[1, 0], KdlNode::new("d")));
+        assert_eq!(history.remove_node(&[0, 0]).unwrap().name().value(), "b");
+        assert!(history.insert_entry(&[0], 1, KdlEntry::new_prop("x", 2)));
+        let removed = history.remove_entry(&[0], 0).unwrap();
+        assert_eq!(removed.value(), &KdlValue::from(1));
+        assert!(history.update_node(&[1], |node| node.set_name("e")));
+        let edited = history.savepoint();
+        let output = "a x=2 {}\ne {\n    d\n}";
+        assert_eq!(history.document().to_string(), output);
+
+        // Nothing at these paths, so nothing's recorded.
+        assert!(!history.insert_node(&[5, 0], KdlNode::new("f")));
+        assert!(!history.insert_node(&[1, 5], KdlNode::new("f")));
+        assert!(history.set_entry(&[0], 3, KdlEntry::new(0)).is_none());
+        assert!(history.remove_node(&[]).is_none());
+        assert!(history.is_at(&edited));
+
+        while history.undo() {}
+        assert!(history.is_at(&start));
+        assert_eq!(history.document().to_string(), input);
+        assert!(history.restore(&edited));
+        assert_eq!(history.document().to_string(), output);
+
+        assert!(history.undo());
+        assert!(history.can_redo());
+        assert!(history.set_entry(&[0], 0, KdlEntry::new(3)).is_some());
+        assert!(!history.can_redo());
+        assert!(!history.restore(&edited));
+        assert!(history.restore(&start));
+        assert_eq!(history.into_document().to_string(), input);
+        Ok(())
+    }
+}
@@ -1,8 +1,8 @@
 #[cfg(feature = "span")]
 use miette::SourceSpan;
-use std::{fmt::Display, str::FromStr};
+use std::{borrow::Cow, fmt::Display, str::FromStr};
 
-use crate::{parser, KdlError};
+use crate::{parser, versions, KdlError, KdlErrorKind, KdlVersion};
 
 /// Represents a KDL
 /// [Identifier](https://github.com/kdl-org/kdl/blob/main/SPEC.md#identifier).
@@ -42,9 +42,13 @@ impl KdlIdentifier {
 
     /// Gets this identifier's span.
     ///
-    /// This value will be properly initialized when created via [`KdlDocument::parse`]
-    /// but may become invalidated if the document is mutated. We do not currently
-    /// guarantee this to yield any particularly consistent results at that point.
+    /// The span covers the identifier as written, including any quotes for
+    /// string identifiers. For type annotations, this doesn't include the
+    /// surrounding parentheses.
+    ///
+    /// This value will be properly initialized when parsed with [`str::parse`].
+    /// See [`KdlNode::span`](crate::KdlNode::span) for how spans are kept up
+    /// to date after edits.
     #[cfg(feature = "span")]
     pub fn span(&self) -> SourceSpan {
         self.span
@@ -56,6 +60,21 @@ impl KdlIdentifier {
         self.span = span.into();
     }
 
+    /// Sets this identifier's span to cover its string representation,
+    /// starting at `offset`. Returns the offset just past it.
+    #[cfg(feature = "span")]
+    pub(crate) fn recompute_span_at(&mut self, offset: usize, version: KdlVersion) -> usize {
+        let len = self.repr_in(version).len();
+        self.span = SourceSpan::from(offset..offset + len);
+        offset + len
+    }
+
+    /// Moves this identifier's span by `delta` bytes.
+    #[cfg(feature = "span")]
+    pub(crate) fn shift_span(&mut self, delta: isize) {
+        self.span = shifted(self.span, delta);
+    }
+
     /// Gets the custom string representation for this identifier, if any.
     pub fn repr(&self) -> Option<&str> {
         self.repr.as_deref()
@@ -89,14 +108,25 @@ impl KdlIdentifier {
     }
 }
 
+/// Moves `span` by `delta` bytes.
+#[cfg(feature = "span")]
+pub(crate) fn shifted(span: SourceSpan, delta: isize) -> SourceSpan {
+    let offset = (span.offset() as isize + delta) as usize;
+    SourceSpan::from(offset..offset + span.len())
+}
+
 impl Display for KdlIdentifier {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Some(repr) = &self.repr {
-            write!(f, "{}", repr)
-        } else if self.plain_value() {
-            write!(f, "{}", self.value)
-        } else {
-            write!(f, "{:?}", self.value)
+        write!(f, "{}", self.repr_in(KdlVersion::V1))
+    }
+}
+
+impl KdlIdentifier {
+    /// This identifier as it's written in a document of the given version.
+    pub(crate) fn repr_in(&self, version: KdlVersion) -> Cow<'_, str> {
+        match &self.repr {
+            Some(repr) => Cow::Borrowed(repr),
+            None => versions::identifier_repr(&self.value, version),
         }
     }
 }
@@ -151,24 +181,91 @@ impl KdlIdentifier {
     }
 
     pub(crate) fn is_initial_char(c: char) -> bool {
-        !c.is_numeric() && Self::is_identifier_char(c)
+        !c.is_ascii_digit() && Self::is_identifier_char(c)
     }
 
-    fn plain_value(&self) -> bool {
-        let mut iter = self.value.chars();
-        if let Some(c) = iter.next() {
-            if !Self::is_initial_char(c) {
-                return false;
-            }
-        } else {
-            return false;
+    /// KDL 2.0's identifier characters: the same as 1.0's but with `<`, `>`
+    /// and `,` allowed, `#` and vertical tab disallowed, and none of the
+    /// characters KDL 2.0 disallows everywhere.
+    pub(crate) fn is_identifier_char_v2(c: char) -> bool {
+        match c {
+            '<' | '>' | ',' => true,
+            '#' | '\u{000B}' => false,
+            _ => !parser::is_disallowed_v2(c) && Self::is_identifier_char(c),
         }
-        for char in iter {
-            if !Self::is_identifier_char(char) {
-                return false;
-            }
+    }
+
+    pub(crate) fn is_initial_char_v2(c: char) -> bool {
+        !c.is_ascii_digit() && Self::is_identifier_char_v2(c)
+    }
+
+    /// Whether `value` can be written as a bare KDL 1.0 identifier: it
+    /// isn't empty, doesn't start with a digit or with a sign followed by a
+    /// digit, has no characters that aren't allowed in identifiers, and
+    /// isn't `true`, `false`, or `null`.
+    pub(crate) fn is_bare_v1(value: &str) -> bool {
+        let mut chars = value.chars();
+        match chars.next() {
+            Some(c) if Self::is_initial_char(c) => {}
+            _ => return false,
         }
-        true
+        if value.starts_with(['+', '-']) && chars.next().map_or(false, |c| c.is_ascii_digit()) {
+            return false;
+        }
+        value.chars().all(Self::is_identifier_char) && !matches!(value, "true" | "false" | "null")
+    }
+}
+
+impl KdlIdentifier {
+    /// Checks that `text` is a valid KDL identifier as written, either bare
+    /// or as a quoted string, returning the parse error if it isn't.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlErrorKind, KdlIdentifier};
+    /// assert!(KdlIdentifier::validate("foo-bar").is_ok());
+    /// assert!(KdlIdentifier::validate("\"foo bar\"").is_ok());
+    /// let err = KdlIdentifier::validate("foo bar").unwrap_err();
+    /// assert_eq!(err.kind, KdlErrorKind::InvalidIdentifierChar);
+    /// let err = KdlIdentifier::validate("true").unwrap_err();
+    /// assert_eq!(err.kind, KdlErrorKind::ReservedKeyword);
+    /// ```
+    pub fn validate(text: &str) -> Result<(), KdlError> {
+        text.parse::<KdlIdentifier>()
+            .map(|_| ())
+            .map_err(|mut err| {
+                if err.kind == KdlErrorKind::Other {
+                    // Something after an otherwise valid identifier.
+                    err.kind = KdlErrorKind::InvalidIdentifierChar;
+                    err.label = Some("invalid identifier character");
+                }
+                err
+            })
+    }
+
+    /// Whether an identifier with the string value `value` has to be quoted
+    /// to be written in KDL. Identifiers are written bare when this returns
+    /// `false`, and quoted otherwise.
+    ///
+    /// Besides identifiers with whitespace or punctuation like `=` or `{`,
+    /// this is also true for empty identifiers, ones that start with a digit
+    /// or with `+` or `-` followed by a digit (which would read as a
+    /// number), and the keywords `true`, `false`, and `null`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlIdentifier;
+    /// assert!(!KdlIdentifier::needs_quoting("foo-bar"));
+    /// assert!(!KdlIdentifier::needs_quoting("-foo"));
+    /// assert!(KdlIdentifier::needs_quoting("-1x"));
+    /// assert!(KdlIdentifier::needs_quoting("null"));
+    /// assert!(KdlIdentifier::needs_quoting("a=b"));
+    /// assert!(KdlIdentifier::needs_quoting(""));
+    /// ```
+    pub fn needs_quoting(value: &str) -> bool {
+        !Self::is_bare_v1(value)
     }
 }
 
@@ -260,5 +357,44 @@ mod test {
         let mut custom_repr = KdlIdentifier::from("foo");
         custom_repr.set_repr(r#""foo/bar""#.to_string());
         assert_eq!(format!("{}", custom_repr), r#""foo/bar""#);
+
+        // Everything that isn't a valid bare identifier comes out quoted,
+        // and parses back to the same value.
+        for value in [
+            "",
+            "true",
+            "null",
+            "-1x",
+            "+0",
+            "1a",
+            "a b",
+            "a\u{FEFF}b",
+            "\0x",
+            "a\u{2028}",
+        ] {
+            let ident = KdlIdentifier::from(value);
+            assert!(KdlIdentifier::needs_quoting(value), "{:?}", value);
+            let text = ident.to_string();
+            assert!(text.starts_with('"'), "{:?}", value);
+            assert_eq!(text.parse::<KdlIdentifier>().unwrap().value(), value);
+        }
+        for value in ["-", "-x", "+", ".5", "a1", "\u{0663}x", "日本"] {
+            assert!(!KdlIdentifier::needs_quoting(value), "{:?}", value);
+            assert_eq!(KdlIdentifier::from(value).to_string(), value);
+            assert!(KdlIdentifier::validate(value).is_ok(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn validation() {
+        assert!(KdlIdentifier::validate("\"a b\"").is_ok());
+        assert!(KdlIdentifier::validate("r#\"a\"b\"#").is_ok());
+        for text in ["", "-1x", "1a", "a=b", "a b", "(a)", "\"a"] {
+            assert!(KdlIdentifier::validate(text).is_err(), "{:?}", text);
+        }
+        assert_eq!(
+            KdlIdentifier::validate("-1x").unwrap_err().kind,
+            KdlErrorKind::InvalidIdentifierChar
+        );
     }
 }
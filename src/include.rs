@@ -0,0 +1,464 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::util::ErrorSpan;
+use crate::{KdlDocument, KdlError, KdlNode, KdlSource, KdlValue, KdlVersion};
+
+/// Loads the documents that [`KdlIncludes`] splices in.
+///
+/// Each source is looked up by the `path` written in the include directive,
+/// and `from`, the name of the source the directive is in (or `None` when
+/// loading the top-level document with [`KdlIncludes::load`], or if the
+/// source it's in doesn't have a name). The loader can treat paths however
+/// it likes: as files relative to `from`, as keys into an in-memory map, or
+/// as URLs.
+///
+/// The name of the returned [`KdlSource`] identifies it: it's used to
+/// detect include cycles, as `from` for its own includes, and in diagnostic
+/// reports. Sources without a name are named after their `path`.
+///
+/// Closures with the same signature as [`KdlLoader::load`] are loaders too.
+pub trait KdlLoader {
+    /// Loads the source that `path` refers to, from within the source named
+    /// `from`.
+    fn load(&mut self, path: &str, from: Option<&str>) -> io::Result<KdlSource>;
+}
+
+impl<F> KdlLoader for F
+where
+    F: FnMut(&str, Option<&str>) -> io::Result<KdlSource>,
+{
+    fn load(&mut self, path: &str, from: Option<&str>) -> io::Result<KdlSource> {
+        self(path, from)
+    }
+}
+
+/// A [`KdlLoader`] that reads files from disk.
+///
+/// Relative paths are resolved against the directory of the file that
+/// includes them, or the current directory for the top-level document.
+/// Files are named by their canonical path, so the same file reached by
+/// different relative paths is still recognized as the same file.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KdlFileLoader;
+
+impl KdlLoader for KdlFileLoader {
+    fn load(&mut self, path: &str, from: Option<&str>) -> io::Result<KdlSource> {
+        let dir = from.and_then(|from| Path::new(from).parent());
+        let path = match dir {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
+        };
+        let path = path.canonicalize()?;
+        let text = std::fs::read_to_string(&path)?;
+        Ok(KdlSource::named(path.display().to_string(), text))
+    }
+}
+
+/// Error returned by [`KdlIncludes`] when a document or one of its includes
+/// can't be loaded.
+///
+/// Each error is reported against the source it happened in: errors in an
+/// include directive point at the directive in the file that has it, and
+/// parse errors in an included file point into that file.
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum KdlIncludeError {
+    /// The top-level document couldn't be loaded.
+    #[error("Couldn't load `{path}`: {error}")]
+    #[diagnostic(code(kdl::include::io))]
+    Io {
+        /// The path it was loaded from.
+        path: String,
+        /// Why it couldn't be loaded.
+        error: io::Error,
+    },
+
+    /// An included document couldn't be loaded.
+    #[error("Couldn't include `{path}`: {error}")]
+    #[diagnostic(code(kdl::include::io))]
+    Load {
+        /// The path in the include directive.
+        path: String,
+        /// Why it couldn't be loaded.
+        error: io::Error,
+        /// The source the include directive is in.
+        #[source_code]
+        input: Arc<KdlSource>,
+        /// The span of the include directive. This is `0..0` without the
+        /// `span` feature.
+        #[label("included here")]
+        span: SourceSpan,
+    },
+
+    /// A document isn't valid KDL. Its error names the document it's in.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(KdlError),
+
+    /// A document includes itself, directly or through other documents.
+    #[error("Include cycle: {}.", chain.join(" -> "))]
+    #[diagnostic(code(kdl::include::cycle))]
+    Cycle {
+        /// The names of the documents in the cycle, starting and ending with
+        /// the one that includes itself.
+        chain: Vec<String>,
+        /// The source the include directive that closes the cycle is in.
+        #[source_code]
+        input: Arc<KdlSource>,
+        /// The span of that include directive. This is `0..0` without the
+        /// `span` feature.
+        #[label("included again here")]
+        span: SourceSpan,
+    },
+
+    /// An include directive wasn't written as `include "path"`.
+    #[error("Invalid include directive.")]
+    #[diagnostic(
+        code(kdl::include::invalid),
+        help("An include directive takes a single string argument, the path to include, and no properties or children.")
+    )]
+    Invalid {
+        /// The source the include directive is in.
+        #[source_code]
+        input: Arc<KdlSource>,
+        /// The span of the include directive. This is `0..0` without the
+        /// `span` feature.
+        #[label("here")]
+        span: SourceSpan,
+    },
+}
+
+/// Include directives, for splitting a document across several sources.
+/// They're one of the crate's [extensions](crate#extensions): an include
+/// directive is an ordinary node, named `include` unless you pick another
+/// name with [`KdlIncludes::with_directive`], that's only treated specially
+/// when a document is loaded through this resolver.
+///
+/// An include directive takes the path of the document to include as its
+/// only argument, and is replaced by that document's nodes, wherever it is
+/// in the document, including in children blocks. Included documents may
+/// include others in turn, and may be included more than once, but not in
+/// a cycle. They're parsed with [`KdlDocument::parse_auto`], so they can be
+/// written in either version of KDL, and are converted to the version of
+/// the document that includes them. Slashdashed directives are ignored like
+/// any other slashdashed node.
+///
+/// Included nodes keep the formatting they had in their own document, so
+/// [format](KdlDocument::fmt) the result if you're going to write it out.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::{collections::HashMap, io};
+/// # use kdl::{KdlIncludes, KdlSource};
+/// let files: HashMap<&str, &str> = vec![
+///     ("app.kdl", "name \"app\"\nserver {\n    include \"server.kdl\"\n}\n"),
+///     ("server.kdl", "host \"::1\"\nport 8080\n"),
+/// ]
+/// .into_iter()
+/// .collect();
+/// let mut includes = KdlIncludes::new(|path: &str, _from: Option<&str>| {
+///     let text = files.get(path).ok_or(io::ErrorKind::NotFound)?;
+///     Ok(KdlSource::named(path, *text))
+/// });
+/// let doc = includes.load("app.kdl").unwrap();
+/// let server = doc.get("server").unwrap().children().unwrap();
+/// assert_eq!(server.get_arg("port"), Some(&8080.into()));
+/// ```
+#[derive(Debug)]
+pub struct KdlIncludes<L> {
+    loader: L,
+    directive: String,
+}
+
+impl<L: KdlLoader> KdlIncludes<L> {
+    /// Creates a resolver that loads documents with `loader`, and treats
+    /// nodes named `include` as include directives.
+    pub fn new(loader: L) -> Self {
+        Self {
+            loader,
+            directive: "include".into(),
+        }
+    }
+
+    /// Treats nodes named `name` as include directives, instead of
+    /// `include`.
+    pub fn with_directive(mut self, name: impl Into<String>) -> Self {
+        self.directive = name.into();
+        self
+    }
+
+    /// Loads the document at `path`, along with everything it includes.
+    pub fn load(&mut self, path: &str) -> Result<KdlDocument, KdlIncludeError> {
+        let source = self
+            .loader
+            .load(path, None)
+            .map_err(|error| KdlIncludeError::Io {
+                path: path.into(),
+                error,
+            })?;
+        self.parse(source)
+    }
+
+    /// Parses `source`, and loads everything it includes. Give the source a
+    /// name if its includes are relative to where it came from.
+    pub fn parse(&mut self, source: impl Into<KdlSource>) -> Result<KdlDocument, KdlIncludeError> {
+        let source = source.into();
+        let mut stack = Vec::new();
+        stack.extend(source.name.clone());
+        self.parse_source(source, &mut stack)
+    }
+
+    fn parse_source(
+        &mut self,
+        source: KdlSource,
+        stack: &mut Vec<String>,
+    ) -> Result<KdlDocument, KdlIncludeError> {
+        let mut doc = KdlDocument::parse_auto(&source.text).map_err(|err| match &source.name {
+            Some(name) => KdlIncludeError::Parse(err.with_source_name(name.clone())),
+            None => KdlIncludeError::Parse(err),
+        })?;
+        self.resolve(&mut doc, &Arc::new(source), stack)?;
+        Ok(doc)
+    }
+
+    fn resolve(
+        &mut self,
+        doc: &mut KdlDocument,
+        input: &Arc<KdlSource>,
+        stack: &mut Vec<String>,
+    ) -> Result<(), KdlIncludeError> {
+        let mut i = 0;
+        while i < doc.nodes.len() {
+            let node = &mut doc.nodes[i];
+            if node.name().value() != self.directive {
+                if let Some(children) = node.children_mut().as_mut() {
+                    self.resolve(children, input, stack)?;
+                }
+                i += 1;
+                continue;
+            }
+            let span = node.error_span();
+            let path = directive_path(node).ok_or_else(|| KdlIncludeError::Invalid {
+                input: input.clone(),
+                span,
+            })?;
+            let source = self
+                .loader
+                .load(&path, input.name.as_deref())
+                .map_err(|error| KdlIncludeError::Load {
+                    path: path.clone(),
+                    error,
+                    input: input.clone(),
+                    span,
+                })?;
+            let name = source.name.clone().unwrap_or(path);
+            if stack.contains(&name) {
+                let mut chain = stack.clone();
+                chain.push(name);
+                return Err(KdlIncludeError::Cycle {
+                    chain,
+                    input: input.clone(),
+                    span,
+                });
+            }
+            stack.push(name.clone());
+            let source = KdlSource {
+                name: Some(name),
+                ..source
+            };
+            let mut included = self.parse_source(source, stack)?;
+            stack.pop();
+            drop_version_marker(&mut included);
+            if included.version != doc.version {
+                included = match doc.version {
+                    KdlVersion::V1 => included.to_v1(),
+                    KdlVersion::V2 => included.to_v2(),
+                };
+            }
+            let count = included.nodes.len();
+            doc.nodes.splice(i..=i, included.nodes.into_vec());
+            i += count;
+        }
+        Ok(())
+    }
+}
+
+/// Drops the `/- kdl-version` marker at the start of an included document,
+/// which would otherwise end up in the middle of the one including it.
+fn drop_version_marker(doc: &mut KdlDocument) {
+    let disabled = match doc.nodes.first_mut() {
+        Some(first) => &mut first.disabled,
+        None => return,
+    };
+    let is_marker = disabled.first().map_or(false, |node| {
+        node.name().value() == "kdl-version"
+            && node
+                .slashdash
+                .as_deref()
+                .map_or(true, |s| s.trim().is_empty())
+    });
+    if is_marker {
+        disabled.remove(0);
+    }
+}
+
+/// The path in an include directive, if it's a valid one.
+fn directive_path(node: &KdlNode) -> Option<String> {
+    match (node.entries(), node.children()) {
+        ([entry], None) if entry.name().is_none() => match entry.value() {
+            KdlValue::String(path) | KdlValue::RawString(path) => Some(path.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn loader(
+        files: &'static [(&'static str, &'static str)],
+    ) -> impl FnMut(&str, Option<&str>) -> io::Result<KdlSource> {
+        let files: HashMap<_, _> = files.iter().copied().collect();
+        move |path, _| {
+            let text = files.get(path).ok_or(io::ErrorKind::NotFound)?;
+            Ok(KdlSource::named(path, *text))
+        }
+    }
+
+    #[test]
+    fn splices() -> miette::Result<()> {
+        let mut includes = KdlIncludes::new(loader(&[
+            (
+                "root",
+                "a\ninclude \"b\"\nc {\n    include \"d\"\n}\ninclude \"d\"\n/-include \"x\"",
+            ),
+            ("b", "b1; b2"),
+            ("d", "/- kdl-version 2\nd #true\ninclude \"e\"\n"),
+            ("e", ""),
+        ]));
+        let doc = includes.load("root")?;
+        let names: Vec<_> = doc.nodes().iter().map(|n| n.name().value()).collect();
+        assert_eq!(names, vec!["a", "b1", "b2", "c", "d"]);
+        let c = doc.get("c").unwrap().children().unwrap();
+        assert_eq!(c.get_arg("d"), Some(&true.into()));
+
+        let mut includes = KdlIncludes::new(loader(&[("b", "b")])).with_directive("import");
+        let doc = includes.parse("import \"b\"\ninclude \"b\"")?;
+        let names: Vec<_> = doc.nodes().iter().map(|n| n.name().value()).collect();
+        assert_eq!(names, vec!["b", "include"]);
+        Ok(())
+    }
+
+    #[test]
+    fn versions() -> miette::Result<()> {
+        let mut includes = KdlIncludes::new(loader(&[
+            ("v1", "a true\ninclude \"v2\"\n"),
+            ("v2", "/- kdl-version 2\nd #true bare\n"),
+            (
+                "v2-root",
+                "/- kdl-version 2\nb #null\ninclude \"v1-only\"\n",
+            ),
+            ("v1-only", "c null r\"x\"\n"),
+        ]));
+        let doc = includes.load("v1")?;
+        assert_eq!(doc.version(), KdlVersion::V1);
+        assert_eq!(doc.to_string(), "a true\nd true \"bare\"\n");
+        let reparsed: KdlDocument = doc.to_string().parse()?;
+        assert_eq!(reparsed.get_arg("d"), Some(&true.into()));
+
+        let doc = includes.load("v2-root")?;
+        assert_eq!(doc.version(), KdlVersion::V2);
+        assert_eq!(
+            doc.to_string(),
+            "/- kdl-version 2\nb #null\nc #null #\"x\"#\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let mut includes = KdlIncludes::new(loader(&[
+            ("a", "x\ninclude \"b\""),
+            ("b", "include \"c\""),
+            ("c", "y {\n    include \"a\"\n}"),
+            ("bad", "ok\nnope 1."),
+            ("invalid", "include \"a\" \"b\""),
+        ]));
+
+        match includes.load("a").unwrap_err() {
+            KdlIncludeError::Cycle { chain, input, span } => {
+                assert_eq!(chain, vec!["a", "b", "c", "a"]);
+                assert_eq!(input.name.as_deref(), Some("c"));
+                #[cfg(feature = "span")]
+                assert_eq!(&input.text[span.offset()..][..span.len()], "include \"a\"");
+                let _ = span;
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+
+        match includes.parse("include \"bad\"").unwrap_err() {
+            KdlIncludeError::Parse(err) => {
+                assert_eq!(err.input.name.as_deref(), Some("bad"));
+                assert_eq!(err.input.text, "ok\nnope 1.");
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+
+        let input = "a\ninclude \"missing\"";
+        match includes.parse(KdlSource::named("root", input)).unwrap_err() {
+            KdlIncludeError::Load {
+                path, input: src, ..
+            } => {
+                assert_eq!(path, "missing");
+                assert_eq!(src.name.as_deref(), Some("root"));
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+
+        assert!(matches!(
+            includes.load("invalid").unwrap_err(),
+            KdlIncludeError::Invalid { .. }
+        ));
+        assert!(matches!(
+            includes.parse("include key=\"a\"").unwrap_err(),
+            KdlIncludeError::Invalid { .. }
+        ));
+        assert!(matches!(
+            includes.load("missing").unwrap_err(),
+            KdlIncludeError::Io { .. }
+        ));
+    }
+
+    #[test]
+    fn files() -> miette::Result<()> {
+        let dir = std::env::temp_dir().join(format!("kdl-include-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("main.kdl"), "include \"sub/a.kdl\"\n").unwrap();
+        std::fs::write(dir.join("sub/a.kdl"), "a\ninclude \"../sub/b.kdl\"\n").unwrap();
+        std::fs::write(dir.join("sub/b.kdl"), "b\n").unwrap();
+        let main = dir.join("main.kdl");
+        let doc = KdlIncludes::new(KdlFileLoader).load(main.to_str().unwrap())?;
+        assert_eq!(doc.to_string(), "a\nb\n");
+
+        // The same file through a different path is still a cycle.
+        std::fs::write(dir.join("sub/b.kdl"), "include \"./a.kdl\"\n").unwrap();
+        let err = KdlIncludes::new(KdlFileLoader)
+            .load(main.to_str().unwrap())
+            .unwrap_err();
+        assert!(matches!(err, KdlIncludeError::Cycle { .. }));
+        std::fs::remove_dir_all(&dir).unwrap();
+        Ok(())
+    }
+}
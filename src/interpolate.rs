@@ -0,0 +1,271 @@
+use std::ops::Range;
+
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::util::{edit_or_restore, ErrorSpan};
+use crate::{KdlDocument, KdlEntry, KdlNode, KdlValue};
+
+/// Error returned by [`KdlDocument::interpolate`] when a placeholder can't
+/// be expanded.
+///
+/// To render it, attach the document's source text, as described under
+/// [Error Reporting](crate#error-reporting).
+#[derive(Debug, Diagnostic, Clone, Eq, PartialEq, Error)]
+#[error("{kind}")]
+#[diagnostic(code(kdl::interpolation))]
+pub struct KdlInterpolationError {
+    /// What went wrong.
+    pub kind: KdlInterpolationErrorKind,
+
+    /// Span of the placeholder in the source text, or of its whole entry if
+    /// the placeholder couldn't be found there (because it was written with
+    /// escapes, or the entry was changed since it was parsed). This is
+    /// `0..0` without the `span` feature.
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
+/// The kind of [`KdlInterpolationError`].
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+#[non_exhaustive]
+pub enum KdlInterpolationErrorKind {
+    /// The resolver didn't have a value for this variable.
+    #[error("Undefined variable `{0}`.")]
+    Undefined(String),
+
+    /// A `${` wasn't closed with a `}`.
+    #[error("Unterminated `${{` placeholder.")]
+    Unterminated,
+
+    /// A placeholder had no variable name, as in `${}`.
+    #[error("Empty `${{}}` placeholder.")]
+    Empty,
+}
+
+impl KdlDocument {
+    /// Expands the placeholders in every string value in this document,
+    /// including in children, using `resolver` to look up each variable.
+    /// This is one of the crate's [extensions](crate#extensions): `${` has
+    /// no special meaning in KDL strings.
+    ///
+    /// A placeholder is `${NAME}` in a string value, where `NAME` is passed
+    /// to `resolver`, and the placeholder is replaced with whatever it
+    /// returns. Write `$${` for a literal `${`. Only string values,
+    /// arguments and properties alike, are expanded: node names, property
+    /// names, and type annotations are left alone, as are slashdashed nodes
+    /// and entries.
+    ///
+    /// Expanded strings lose their original formatting, so they're written
+    /// out as plain strings, keeping their type annotations.
+    ///
+    /// If a placeholder can't be expanded, the document is left unchanged
+    /// and the first such placeholder is returned as an error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let mut doc: KdlDocument = r#"
+    /// server host="${HOST}" url="http://${HOST}:${PORT}/" note="costs $${PRICE}"
+    /// "#.parse().unwrap();
+    /// doc.interpolate(|name| match name {
+    ///     "HOST" => Some("example.com".into()),
+    ///     "PORT" => Some("8080".into()),
+    ///     _ => None,
+    /// }).unwrap();
+    /// let server = doc.get("server").unwrap();
+    /// assert_eq!(server.get("url"), Some(&"http://example.com:8080/".into()));
+    /// assert_eq!(server.get("note"), Some(&"costs ${PRICE}".into()));
+    ///
+    /// let input = r#"server user="${USERNAME}""#;
+    /// let mut doc: KdlDocument = input.parse().unwrap();
+    /// let err = doc.interpolate(|_| None).unwrap_err();
+    /// assert_eq!(err.to_string(), "Undefined variable `USERNAME`.");
+    /// # #[cfg(feature = "span")]
+    /// assert_eq!(&input[err.span.offset()..][..err.span.len()], "${USERNAME}");
+    /// ```
+    pub fn interpolate(
+        &mut self,
+        mut resolver: impl FnMut(&str) -> Option<String>,
+    ) -> Result<(), KdlInterpolationError> {
+        edit_or_restore(self, |doc| doc.interpolate_impl(&mut resolver))
+    }
+
+    /// Expands placeholders like [`KdlDocument::interpolate`], looking up
+    /// each variable in the process's environment.
+    pub fn interpolate_env(&mut self) -> Result<(), KdlInterpolationError> {
+        self.interpolate(|name| std::env::var(name).ok())
+    }
+
+    fn interpolate_impl(
+        &mut self,
+        resolver: &mut dyn FnMut(&str) -> Option<String>,
+    ) -> Result<(), KdlInterpolationError> {
+        for node in self.nodes_mut() {
+            node.interpolate_impl(resolver)?;
+        }
+        Ok(())
+    }
+}
+
+impl KdlNode {
+    /// Expands the placeholders in this node's string values, and its
+    /// children's. See [`KdlDocument::interpolate`].
+    pub fn interpolate(
+        &mut self,
+        mut resolver: impl FnMut(&str) -> Option<String>,
+    ) -> Result<(), KdlInterpolationError> {
+        edit_or_restore(self, |node| node.interpolate_impl(&mut resolver))
+    }
+
+    fn interpolate_impl(
+        &mut self,
+        resolver: &mut dyn FnMut(&str) -> Option<String>,
+    ) -> Result<(), KdlInterpolationError> {
+        for entry in self.entries_mut() {
+            let expanded = match entry.value() {
+                KdlValue::String(s) | KdlValue::RawString(s) => {
+                    expand(s, resolver).map_err(|(kind, placeholder)| {
+                        let span = placeholder_span(entry, &s[placeholder]);
+                        KdlInterpolationError { kind, span }
+                    })?
+                }
+                _ => None,
+            };
+            if let Some(expanded) = expanded {
+                entry.set_value(KdlValue::String(expanded));
+                entry.value_repr = None;
+            }
+        }
+        if let Some(children) = self.children_mut().as_mut() {
+            children.interpolate_impl(resolver)?;
+        }
+        Ok(())
+    }
+}
+
+/// Expands the placeholders in `value`, returning `None` if there weren't
+/// any, or the error and where the placeholder is in `value`.
+fn expand(
+    value: &str,
+    resolver: &mut dyn FnMut(&str) -> Option<String>,
+) -> Result<Option<String>, (KdlInterpolationErrorKind, Range<usize>)> {
+    if !value.contains("${") {
+        return Ok(None);
+    }
+    let mut result = String::new();
+    let mut rest = 0;
+    while let Some(found) = value[rest..].find("${") {
+        let start = rest + found;
+        if value[..start].ends_with('$') {
+            // `$${`, an escaped `${`.
+            result.push_str(&value[rest..start - 1]);
+            result.push_str("${");
+            rest = start + 2;
+            continue;
+        }
+        result.push_str(&value[rest..start]);
+        let end = match value[start..].find('}') {
+            Some(end) => start + end,
+            None => {
+                return Err((KdlInterpolationErrorKind::Unterminated, start..start + 2));
+            }
+        };
+        let name = &value[start + 2..end];
+        if name.is_empty() {
+            return Err((KdlInterpolationErrorKind::Empty, start..end + 1));
+        }
+        match resolver(name) {
+            Some(replacement) => result.push_str(&replacement),
+            None => {
+                return Err((
+                    KdlInterpolationErrorKind::Undefined(name.into()),
+                    start..end + 1,
+                ))
+            }
+        }
+        rest = end + 1;
+    }
+    result.push_str(&value[rest..]);
+    Ok(Some(result))
+}
+
+/// Finds `placeholder` in the text `entry` was parsed from.
+fn placeholder_span(entry: &KdlEntry, placeholder: &str) -> SourceSpan {
+    let span = entry.error_span();
+    let repr = match entry.value_repr() {
+        Some(repr) if repr.len() <= span.len() => repr,
+        _ => return span,
+    };
+    match repr.find(placeholder) {
+        Some(found) => {
+            let offset = span.offset() + span.len() - repr.len() + found;
+            SourceSpan::from(offset..offset + placeholder.len())
+        }
+        None => span,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vars(name: &str) -> Option<String> {
+        match name {
+            "A" => Some("1".into()),
+            "B" => Some("${A}".into()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expands() -> miette::Result<()> {
+        let mut doc: KdlDocument =
+            "a \"${A}${B}\" (t)r\"$${A} $$ $\" x=\"-${A}-\" 1 {\n    b \"${A}\"\n}\n/-c \"${C}\""
+                .parse()?;
+        doc.interpolate(vars)?;
+        assert_eq!(
+            doc.to_string(),
+            "a \"1${A}\" (t)\"${A} $$ $\" x=\"-1-\" 1 {\n    b \"1\"\n}\n/-c \"${C}\""
+        );
+
+        let mut node: KdlNode = "a \"no placeholders\" r\"raw\"".parse()?;
+        node.interpolate(vars)?;
+        assert_eq!(node.to_string(), "a \"no placeholders\" r\"raw\"");
+        Ok(())
+    }
+
+    #[test]
+    fn errors() -> miette::Result<()> {
+        let input = "a \"${A}\"\nb x=\"${A} ${C}\" \"${\" \"${}\"";
+        let mut doc: KdlDocument = input.parse()?;
+        let err = doc.interpolate(vars).unwrap_err();
+        assert_eq!(err.kind, KdlInterpolationErrorKind::Undefined("C".into()));
+        #[cfg(feature = "span")]
+        assert_eq!(&input[err.span.offset()..][..err.span.len()], "${C}");
+        // Nothing was expanded.
+        assert_eq!(doc.to_string(), input);
+
+        let err = doc.interpolate(|_| Some("".into())).unwrap_err();
+        assert_eq!(err.kind, KdlInterpolationErrorKind::Unterminated);
+        assert_eq!(err.to_string(), "Unterminated `${` placeholder.");
+        #[cfg(feature = "span")]
+        assert_eq!(err.span, SourceSpan::from(26..28));
+
+        let mut node: KdlNode = "b \"${}\"".parse()?;
+        let err = node.interpolate(vars).unwrap_err();
+        assert_eq!(err.kind, KdlInterpolationErrorKind::Empty);
+        assert_eq!(err.to_string(), "Empty `${}` placeholder.");
+
+        // Escapes make the placeholder impossible to find, so the whole
+        // entry is pointed to.
+        let input = "b x=\"\\u{24}{C}\"";
+        let mut node: KdlNode = input.parse()?;
+        let err = node.interpolate(vars).unwrap_err();
+        assert_eq!(err.kind, KdlInterpolationErrorKind::Undefined("C".into()));
+        #[cfg(feature = "span")]
+        assert_eq!(err.span, SourceSpan::from(2..input.len()));
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::KdlEntry;
+
+/// Conversions for values using KDL's reserved `(ipv4)` and `(ipv6)` type
+/// annotations, plus `(cidr)` networks with the `ipnet` feature.
+impl KdlEntry {
+    /// Parses this entry's value as an IPv4 address, if it's a string
+    /// annotated with `(ipv4)`.
+    pub fn as_ipv4(&self) -> Option<Ipv4Addr> {
+        self.annotated_string("ipv4")?.parse().ok()
+    }
+
+    /// Parses this entry's value as an IPv6 address, if it's a string
+    /// annotated with `(ipv6)`.
+    pub fn as_ipv6(&self) -> Option<Ipv6Addr> {
+        self.annotated_string("ipv6")?.parse().ok()
+    }
+
+    /// Parses this entry's value as an IP address, if it's a string annotated
+    /// with either `(ipv4)` or `(ipv6)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// use std::net::{IpAddr, Ipv6Addr};
+    ///
+    /// let node: KdlNode = r#"listen (ipv6)"::1" port=8080"#.parse().unwrap();
+    /// let addr = node.entries()[0].as_ip_addr();
+    /// assert_eq!(addr, Some(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+    /// ```
+    pub fn as_ip_addr(&self) -> Option<IpAddr> {
+        self.as_ipv4()
+            .map(IpAddr::V4)
+            .or_else(|| self.as_ipv6().map(IpAddr::V6))
+    }
+
+    /// Parses this entry's value as an IP network in CIDR notation (like
+    /// `10.0.0.0/8` or `fd00::/8`), if it's a string annotated with `(cidr)`.
+    /// Requires the `ipnet` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let node: KdlNode = r#"allow (cidr)"10.0.0.0/8""#.parse().unwrap();
+    /// let net = node.entries()[0].as_ip_net().unwrap();
+    /// assert!(net.contains(&"10.1.2.3".parse::<std::net::IpAddr>().unwrap()));
+    /// ```
+    #[cfg(feature = "ipnet")]
+    pub fn as_ip_net(&self) -> Option<ipnet::IpNet> {
+        self.annotated_string("cidr")?.parse().ok()
+    }
+}
+
+/// Creates a new argument holding the address, annotated with `(ipv4)`.
+impl From<Ipv4Addr> for KdlEntry {
+    fn from(addr: Ipv4Addr) -> Self {
+        let mut entry = KdlEntry::new(addr.to_string());
+        entry.set_ty("ipv4");
+        entry
+    }
+}
+
+/// Creates a new argument holding the address, annotated with `(ipv6)`.
+impl From<Ipv6Addr> for KdlEntry {
+    fn from(addr: Ipv6Addr) -> Self {
+        let mut entry = KdlEntry::new(addr.to_string());
+        entry.set_ty("ipv6");
+        entry
+    }
+}
+
+/// Creates a new argument holding the address, annotated with `(ipv4)` or
+/// `(ipv6)` as appropriate.
+impl From<IpAddr> for KdlEntry {
+    fn from(addr: IpAddr) -> Self {
+        match addr {
+            IpAddr::V4(addr) => addr.into(),
+            IpAddr::V6(addr) => addr.into(),
+        }
+    }
+}
+
+/// Creates a new argument holding the network in CIDR notation, annotated
+/// with `(cidr)`. Requires the `ipnet` feature.
+#[cfg(feature = "ipnet")]
+impl From<ipnet::IpNet> for KdlEntry {
+    fn from(net: ipnet::IpNet) -> Self {
+        let mut entry = KdlEntry::new(net.to_string());
+        entry.set_ty("cidr");
+        entry
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(input: &str) -> KdlEntry {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn addresses() {
+        let localhost = Ipv4Addr::new(127, 0, 0, 1);
+        assert_eq!(entry(r#"(ipv4)"127.0.0.1""#).as_ipv4(), Some(localhost));
+        assert_eq!(entry(r#"(ipv4)"127.0.0.1""#).as_ipv6(), None);
+        assert_eq!(entry(r#"(ipv4)"::1""#).as_ipv4(), None);
+        assert_eq!(entry(r#"(ipv4)"256.0.0.1""#).as_ipv4(), None);
+        assert_eq!(entry(r#"(ipv6)"::1""#).as_ipv6(), Some(Ipv6Addr::LOCALHOST));
+        assert_eq!(entry(r#""127.0.0.1""#).as_ip_addr(), None);
+        assert_eq!(
+            entry(r#"(ipv4)"127.0.0.1""#).as_ip_addr(),
+            Some(IpAddr::V4(localhost))
+        );
+
+        for addr in [IpAddr::V4(localhost), IpAddr::V6(Ipv6Addr::LOCALHOST)] {
+            let reparsed = entry(&KdlEntry::from(addr).to_string());
+            assert_eq!(reparsed.as_ip_addr(), Some(addr));
+        }
+        assert_eq!(
+            KdlEntry::from(localhost).to_string(),
+            r#"(ipv4)"127.0.0.1""#
+        );
+    }
+
+    #[cfg(feature = "ipnet")]
+    #[test]
+    fn networks() {
+        let net: ipnet::IpNet = "fd00::/8".parse().unwrap();
+        assert_eq!(entry(r#"(cidr)"fd00::/8""#).as_ip_net(), Some(net));
+        assert_eq!(KdlEntry::from(net).to_string(), r#"(cidr)"fd00::/8""#);
+        assert_eq!(entry(r#"(cidr)"10.0.0.0""#).as_ip_net(), None);
+        assert_eq!(entry(r#"(ipv4)"10.0.0.0/8""#).as_ip_net(), None);
+    }
+}
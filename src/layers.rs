@@ -0,0 +1,707 @@
+use std::{io, path::Path, sync::Arc};
+
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::{
+    FromKdlValue, KdlDocument, KdlEntry, KdlError, KdlNode, KdlNodeMap, KdlReadError, KdlSource,
+    KdlValue, NodeKey,
+};
+
+/// How a node from a later layer is combined with a node of the same name
+/// from an earlier one, in [`KdlLayers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KdlMergeStrategy {
+    /// Merges the later node into the earlier one. Its type annotation, if
+    /// it has one, replaces the earlier node's. Its arguments, if it has any,
+    /// replace all of the earlier node's arguments. Each of its properties
+    /// replaces the earlier node's property of the same name, or is added if
+    /// there isn't one. Its children are merged into the earlier node's
+    /// children, one by one, with their own strategies.
+    Merge,
+
+    /// Replaces the earlier node, and everything in it, with the later one.
+    Replace,
+
+    /// Keeps both nodes, adding the later one after the earlier ones, for
+    /// nodes that can be given any number of times, like a list of plugins.
+    Append,
+}
+
+impl Default for KdlMergeStrategy {
+    fn default() -> Self {
+        Self::Merge
+    }
+}
+
+/// Layered configuration, where each layer can override what the ones
+/// before it set: built-in defaults first, then system-wide and per-user
+/// configuration files, then environment variables, for example.
+///
+/// Layers are merged node by node. For each node in a layer, the last node
+/// with the same name at the same place in the layers before it (at the top
+/// level, or among the children of the same node) is found, and the two are
+/// combined with the [`KdlMergeStrategy`] set for that node's path, which is
+/// [`KdlMergeStrategy::Merge`] unless you set another. Nodes with no such
+/// earlier node are added as they are.
+///
+/// The merged document is a [`KdlConfig`], which remembers which layer
+/// every node and entry came from, so errors about a value can point at the
+/// file and line it was set in. Merged nodes and entries keep the
+/// formatting they had in their own layers, so
+/// [format](KdlDocument::fmt) the document if you're going to write it out.
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::{KdlLayers, KdlMergeStrategy, KdlSource};
+/// let mut layers = KdlLayers::new();
+/// layers.set_strategy(&["plugin"], KdlMergeStrategy::Append);
+/// layers
+///     .add_source(KdlSource::named("defaults", "server port=80 host=\"::1\"\nplugin \"log\""))
+///     .unwrap();
+/// layers
+///     .add_source(KdlSource::named("/etc/app.kdl", "server port=8080\nplugin \"auth\""))
+///     .unwrap();
+/// let config = layers.resolve();
+///
+/// let server = config.document().get("server").unwrap();
+/// assert_eq!(server.get("port"), Some(&8080.into()));
+/// assert_eq!(server.get("host"), Some(&"::1".into()));
+/// let plugins: Vec<_> = config
+///     .document()
+///     .nodes()
+///     .iter()
+///     .filter(|node| node.name().value() == "plugin")
+///     .map(|node| node[0].clone())
+///     .collect();
+/// assert_eq!(plugins, vec!["log".into(), "auth".into()]);
+///
+/// let err = config.get_as::<u8>(server, "port").unwrap_err();
+/// assert_eq!(err.input.name.as_deref(), Some("/etc/app.kdl"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct KdlLayers {
+    layers: Vec<(KdlDocument, Arc<KdlSource>)>,
+    strategies: Vec<(Vec<String>, KdlMergeStrategy)>,
+    default: KdlMergeStrategy,
+}
+
+impl KdlLayers {
+    /// Creates an empty set of layers.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the merge strategy for nodes at `path`, the names of the node
+    /// and its parents, starting from the top level. For example,
+    /// `&["server", "listen"]` is for `listen` nodes in the children of a
+    /// top-level `server` node.
+    pub fn set_strategy(&mut self, path: &[&str], strategy: KdlMergeStrategy) -> &mut Self {
+        let path: Vec<String> = path.iter().map(|name| name.to_string()).collect();
+        self.strategies.retain(|(p, _)| *p != path);
+        self.strategies.push((path, strategy));
+        self
+    }
+
+    /// Sets the merge strategy for nodes that don't have one set with
+    /// [`KdlLayers::set_strategy`]. Defaults to [`KdlMergeStrategy::Merge`].
+    pub fn set_default_strategy(&mut self, strategy: KdlMergeStrategy) -> &mut Self {
+        self.default = strategy;
+        self
+    }
+
+    /// Parses `source` with [`KdlDocument::parse_auto`] and adds it as the
+    /// next layer. Give the source a name, so errors can say where values
+    /// came from.
+    pub fn add_source(&mut self, source: impl Into<KdlSource>) -> Result<(), KdlError> {
+        let source = source.into();
+        let doc = KdlDocument::parse_auto(&source.text).map_err(|err| match &source.name {
+            Some(name) => err.with_source_name(name.clone()),
+            None => err,
+        })?;
+        self.add_document(doc, source);
+        Ok(())
+    }
+
+    /// Adds an already-parsed document as the next layer. `source` must be
+    /// what it was parsed from, for its spans to point at the right text.
+    pub fn add_document(&mut self, doc: KdlDocument, source: KdlSource) {
+        self.layers.push((doc, Arc::new(source)));
+    }
+
+    /// Reads the file at `path` and adds it as the next layer, named after
+    /// its path.
+    pub fn add_file(&mut self, path: impl AsRef<Path>) -> Result<(), KdlReadError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        Ok(self.add_source(KdlSource::named(path.display().to_string(), text))?)
+    }
+
+    /// Like [`KdlLayers::add_file`], but skips the file if it doesn't
+    /// exist, for configuration files that are optional. Returns whether
+    /// the file was added.
+    pub fn add_optional_file(&mut self, path: impl AsRef<Path>) -> Result<bool, KdlReadError> {
+        match self.add_file(path) {
+            Ok(()) => Ok(true),
+            Err(KdlReadError::Io(err)) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Adds the process's environment variables that start with `prefix`
+    /// as the next layer. See [`KdlLayers::add_vars`].
+    ///
+    /// Variables whose name or value isn't valid UTF-8 are skipped.
+    pub fn add_env(&mut self, prefix: &str) {
+        let vars = std::env::vars_os().filter_map(|(name, value)| {
+            let name = name
+                .into_string()
+                .ok()
+                .filter(|name| name.starts_with(prefix))?;
+            Some((name, value.into_string().ok()?))
+        });
+        self.add_vars(prefix, vars)
+    }
+
+    /// Adds the variables in `vars` that start with `prefix` as the next
+    /// layer, each one setting the argument of a node.
+    ///
+    /// The rest of the variable's name is the path to the node, with `__`
+    /// between names, and is lowercased, with `_` turned into `-`, so that
+    /// with a prefix of `APP_`, `APP_SERVER__MAX_CONNECTIONS=10` is the same
+    /// as a layer of `server { max-connections 10; }`. Values are parsed as
+    /// KDL values where they can be, so `10` is a number, `true` a boolean,
+    /// and `"10"` a string, and are strings otherwise.
+    ///
+    /// The layer is named `environment`, and its text is the variables as
+    /// `NAME=value` lines, so errors point at the variable that set a value.
+    pub fn add_vars(&mut self, prefix: &str, vars: impl IntoIterator<Item = (String, String)>) {
+        let mut vars: Vec<(String, String)> = vars
+            .into_iter()
+            .filter(|(name, _)| name.len() > prefix.len() && name.starts_with(prefix))
+            .collect();
+        vars.sort();
+        let mut doc = KdlDocument::new();
+        let mut text = String::new();
+        for (name, value) in vars {
+            let start = text.len();
+            text.push_str(&name);
+            text.push('=');
+            let value_start = text.len();
+            text.push_str(&value);
+            let span = SourceSpan::from(start..text.len());
+            text.push('\n');
+
+            let mut nodes = &mut doc;
+            let path: Vec<String> = name[prefix.len()..]
+                .split("__")
+                .map(|name| name.to_lowercase().replace('_', "-"))
+                .collect();
+            let (last, parents) = path.split_last().expect("split always returns something");
+            for parent in parents {
+                let i = match nodes.nodes.iter().rposition(|n| n.name().value() == parent) {
+                    Some(i) => i,
+                    None => {
+                        let mut node = KdlNode::new(parent.as_str());
+                        node.set_span(span);
+                        nodes.nodes.push(node);
+                        nodes.nodes.len() - 1
+                    }
+                };
+                nodes = nodes.nodes[i].ensure_children();
+            }
+            let mut entry = KdlEntry::new(value.parse().unwrap_or(KdlValue::String(value)));
+            entry.set_span(value_start..span.offset() + span.len());
+            let mut node = KdlNode::new(last.as_str());
+            node.set_span(span);
+            node.push(entry);
+            nodes.nodes.push(node);
+        }
+        doc.fmt();
+        self.add_document(doc, KdlSource::named("environment", text));
+    }
+
+    /// Merges the layers, in the order they were added.
+    pub fn resolve(&self) -> KdlConfig {
+        let mut config = KdlConfig {
+            document: KdlDocument::new(),
+            sources: self
+                .layers
+                .iter()
+                .map(|(_, source)| source.clone())
+                .collect(),
+            origins: KdlNodeMap::new(),
+        };
+        for (layer, (doc, _)) in self.layers.iter().enumerate() {
+            let mut path = Vec::new();
+            let mut document = std::mem::take(&mut config.document);
+            self.merge_nodes(&mut document, doc, layer, &mut path, &mut config.origins);
+            config.document = document;
+        }
+        config
+    }
+
+    fn strategy(&self, path: &[&str]) -> KdlMergeStrategy {
+        self.strategies
+            .iter()
+            .find(|(p, _)| p.iter().map(String::as_str).eq(path.iter().copied()))
+            .map_or(self.default, |(_, strategy)| *strategy)
+    }
+
+    fn merge_nodes<'a>(
+        &self,
+        into: &mut KdlDocument,
+        from: &'a KdlDocument,
+        layer: usize,
+        path: &mut Vec<&'a str>,
+        origins: &mut KdlNodeMap<NodeOrigin>,
+    ) {
+        for node in from.nodes() {
+            path.push(node.name().value());
+            let existing = into
+                .nodes
+                .iter()
+                .rposition(|n| n.name().value() == node.name().value());
+            match (self.strategy(path), existing) {
+                (KdlMergeStrategy::Merge, Some(i)) => {
+                    self.merge_node(&mut into.nodes[i], node, layer, path, origins)
+                }
+                (KdlMergeStrategy::Replace, Some(i)) => {
+                    into.nodes[i] = added(node, layer, origins);
+                }
+                _ => into.nodes.push(added(node, layer, origins)),
+            }
+            path.pop();
+        }
+    }
+
+    fn merge_node<'a>(
+        &self,
+        into: &mut KdlNode,
+        from: &'a KdlNode,
+        layer: usize,
+        path: &mut Vec<&'a str>,
+        origins: &mut KdlNodeMap<NodeOrigin>,
+    ) {
+        let old_origin = origins.remove(into.id()).unwrap_or_else(|| NodeOrigin {
+            layer,
+            entries: Vec::new(),
+        });
+        let old: Vec<(KdlEntry, usize)> = std::mem::take(&mut into.entries)
+            .into_vec()
+            .into_iter()
+            .zip(
+                old_origin
+                    .entries
+                    .into_iter()
+                    .chain(std::iter::repeat(layer)),
+            )
+            .collect();
+        let new = from.entries();
+
+        let mut entries: Vec<(KdlEntry, usize)> = Vec::new();
+        if new.iter().any(|e| e.name().is_none()) {
+            let args = new.iter().filter(|e| e.name().is_none());
+            entries.extend(args.map(|e| (e.clone(), layer)));
+        } else {
+            let args = old.iter().filter(|(e, _)| e.name().is_none());
+            entries.extend(args.cloned());
+        }
+        // Properties stay where they were, with their latest values, and
+        // new ones go after them.
+        let mut props = old
+            .iter()
+            .filter_map(|(e, origin)| Some((e.name()?.value(), (e, *origin))))
+            .collect::<Vec<_>>();
+        props.extend(
+            new.iter()
+                .filter_map(|e| Some((e.name()?.value(), (e, layer)))),
+        );
+        for (i, (name, _)) in props.iter().enumerate() {
+            if props[..i].iter().any(|(seen, _)| seen == name) {
+                continue;
+            }
+            let overridden = new.iter().rev().find(|e| prop_named(e, name));
+            match overridden {
+                Some(entry) => entries.push((entry.clone(), layer)),
+                None => {
+                    // Only in the earlier node, duplicates and all.
+                    let dupes = props.iter().filter(|(n, _)| n == name);
+                    entries.extend(dupes.map(|(_, (e, origin))| ((*e).clone(), *origin)));
+                }
+            }
+        }
+
+        let (entries, entry_origins): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+        into.entries = entries.into();
+        if from.ty().is_some() {
+            into.ty = from.ty.clone();
+        }
+        let origin = NodeOrigin {
+            layer: old_origin.layer,
+            entries: entry_origins,
+        };
+        origins.insert(into.id(), origin);
+
+        if let Some(children) = from.children() {
+            match into.children_mut() {
+                Some(into) => self.merge_nodes(into, children, layer, path, origins),
+                none => {
+                    *none = Some(children.clone());
+                    for child in children.nodes() {
+                        record(child, layer, origins);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn prop_named(entry: &KdlEntry, name: &str) -> bool {
+    entry.name().map(|n| n.value()) == Some(name)
+}
+
+/// Which layers a node and each of its entries came from.
+#[derive(Debug, Clone)]
+struct NodeOrigin {
+    layer: usize,
+    entries: Vec<usize>,
+}
+
+/// Copies `node` into the merged document, recording that it and
+/// everything in it came from `layer`.
+fn added(node: &KdlNode, layer: usize, origins: &mut KdlNodeMap<NodeOrigin>) -> KdlNode {
+    record(node, layer, origins);
+    node.clone()
+}
+
+fn record(node: &KdlNode, layer: usize, origins: &mut KdlNodeMap<NodeOrigin>) {
+    let origin = NodeOrigin {
+        layer,
+        entries: vec![layer; node.entries().len()],
+    };
+    origins.insert(node.id(), origin);
+    if let Some(children) = node.children() {
+        for child in children.nodes() {
+            record(child, layer, origins);
+        }
+    }
+}
+
+/// The result of merging [`KdlLayers`]: a document, along with where each
+/// of its nodes and entries came from.
+///
+/// Provenance is tracked by [node ID](KdlNode::id) and entry position, so
+/// it's only accurate for nodes that haven't had entries added or removed
+/// since the layers were merged. Spans still point into the layer each node
+/// or entry came from, so don't [recompute](KdlDocument::recompute_spans)
+/// them if you want errors to point at the right place.
+#[derive(Debug, Clone)]
+pub struct KdlConfig {
+    document: KdlDocument,
+    sources: Vec<Arc<KdlSource>>,
+    origins: KdlNodeMap<NodeOrigin>,
+}
+
+/// Where a value in a [`KdlConfig`] came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KdlProvenance {
+    /// The layer it was set in. Its name is usually a file path.
+    pub source: Arc<KdlSource>,
+
+    /// Where it was set in [`KdlProvenance::source`].
+    pub span: SourceSpan,
+}
+
+/// An error about a value in a [`KdlConfig`], pointing at the layer the
+/// value was set in.
+#[derive(Debug, Diagnostic, Clone, Eq, PartialEq, Error)]
+#[error("{message}")]
+#[diagnostic(code(kdl::config))]
+pub struct KdlConfigError {
+    /// What's wrong.
+    pub message: String,
+
+    /// The layer the value was set in.
+    #[source_code]
+    pub input: Arc<KdlSource>,
+
+    /// Where the value was set in [`KdlConfigError::input`].
+    #[label("{}", label)]
+    pub span: SourceSpan,
+
+    /// Label text for the span.
+    pub label: String,
+}
+
+impl KdlConfig {
+    /// Gets the merged document.
+    pub fn document(&self) -> &KdlDocument {
+        &self.document
+    }
+
+    /// Gets a mutable reference to the merged document. See the
+    /// [type-level docs](KdlConfig) for how edits affect provenance.
+    pub fn document_mut(&mut self) -> &mut KdlDocument {
+        &mut self.document
+    }
+
+    /// Takes the merged document, dropping its provenance.
+    pub fn into_document(self) -> KdlDocument {
+        self.document
+    }
+
+    /// Gets the layers that were merged, in order.
+    pub fn sources(&self) -> &[Arc<KdlSource>] {
+        &self.sources
+    }
+
+    /// Finds where `node`, which must be in this config's document, was
+    /// first defined. Merged nodes keep the span of the earliest layer they
+    /// were in.
+    pub fn node_origin(&self, node: &KdlNode) -> Option<KdlProvenance> {
+        let origin = self.origins.get(node.id())?;
+        Some(KdlProvenance {
+            source: self.sources[origin.layer].clone(),
+            span: node.span(),
+        })
+    }
+
+    /// Finds where the entry of `node` at `key` (a property name or
+    /// argument index) was set, or `None` if there's no such entry.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlLayers, KdlSource};
+    /// let mut layers = KdlLayers::new();
+    /// layers.add_source(KdlSource::named("defaults", "log level=\"info\" color=true")).unwrap();
+    /// layers.add_vars("APP_", vec![("APP_LOG".into(), "debug".into())]);
+    /// let config = layers.resolve();
+    ///
+    /// let log = config.document().get("log").unwrap();
+    /// let origin = config.origin(log, "color").unwrap();
+    /// assert_eq!(origin.source.name.as_deref(), Some("defaults"));
+    /// let origin = config.origin(log, 0).unwrap();
+    /// assert_eq!(origin.source.name.as_deref(), Some("environment"));
+    /// assert_eq!(&origin.source.text[origin.span.offset()..][..origin.span.len()], "debug");
+    /// ```
+    pub fn origin(&self, node: &KdlNode, key: impl Into<NodeKey>) -> Option<KdlProvenance> {
+        let entry = node.entry(key)?;
+        let index = node.entries().iter().position(|e| std::ptr::eq(e, entry))?;
+        let origin = self.origins.get(node.id())?;
+        let layer = *origin.entries.get(index)?;
+        Some(KdlProvenance {
+            source: self.sources[layer].clone(),
+            span: entry.span(),
+        })
+    }
+
+    /// Makes an error about the entry of `node` at `key`, pointing at where
+    /// it was set, or at `node` if there's no such entry.
+    pub fn error(
+        &self,
+        node: &KdlNode,
+        key: impl Into<NodeKey>,
+        message: impl Into<String>,
+        label: impl Into<String>,
+    ) -> KdlConfigError {
+        let origin = self
+            .origin(node, key)
+            .or_else(|| self.node_origin(node))
+            .unwrap_or_else(|| KdlProvenance {
+                source: Arc::new(KdlSource::default()),
+                span: SourceSpan::from(0..0),
+            });
+        KdlConfigError {
+            message: message.into(),
+            input: origin.source,
+            span: origin.span,
+            label: label.into(),
+        }
+    }
+
+    /// Fetches an entry of `node` and converts it, like
+    /// [`KdlNode::get_as`], but with errors that point at the layer the
+    /// entry (or, if it's missing, the node) came from.
+    pub fn get_as<T: FromKdlValue>(
+        &self,
+        node: &KdlNode,
+        key: impl Into<NodeKey>,
+    ) -> Result<T, KdlConfigError> {
+        let key = key.into();
+        node.get_as(key.clone()).map_err(|err| {
+            let label = format!("expected {}", err.expected);
+            self.error(node, key, err.to_string(), label)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn layers(sources: &[(&str, &str)]) -> KdlLayers {
+        let mut layers = KdlLayers::new();
+        for (name, text) in sources {
+            layers.add_source(KdlSource::named(*name, *text)).unwrap();
+        }
+        layers
+    }
+
+    fn name(origin: Option<KdlProvenance>) -> Option<String> {
+        origin.and_then(|origin| origin.source.name.clone())
+    }
+
+    #[test]
+    fn merges() {
+        let config = layers(&[
+            ("a", "server 1 2 x=1 y=2 x=3 {\n    port 80\n}\nlog \"a\""),
+            ("b", "server y=20 z=30 {\n    host \"h\"\n    port 8080\n}"),
+            ("c", "(t)server 3\nlog \"c\"\nnew"),
+        ])
+        .resolve();
+        let doc = config.document();
+        let server = doc.get("server").unwrap();
+        let mut formatted = server.clone();
+        formatted.fmt();
+        assert_eq!(
+            formatted.to_string(),
+            "(t)server 3 x=1 x=3 y=20 z=30 {\n    port 8080\n    host \"h\"\n}\n"
+        );
+        assert_eq!(doc.get_arg("log"), Some(&"c".into()));
+        assert_eq!(doc.nodes().len(), 3);
+
+        assert_eq!(name(config.origin(server, 0)).as_deref(), Some("c"));
+        assert_eq!(name(config.origin(server, "x")).as_deref(), Some("a"));
+        assert_eq!(name(config.origin(server, "y")).as_deref(), Some("b"));
+        assert_eq!(name(config.origin(server, "z")).as_deref(), Some("b"));
+        assert_eq!(name(config.origin(server, "w")), None);
+        assert_eq!(name(config.node_origin(server)).as_deref(), Some("a"));
+        let port = server.children().unwrap().get("port").unwrap();
+        let origin = config.origin(port, 0).unwrap();
+        assert_eq!(
+            &origin.source.text[origin.span.offset()..][..origin.span.len()],
+            "8080"
+        );
+        let host = server.children().unwrap().get("host").unwrap();
+        assert_eq!(name(config.node_origin(host)).as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn strategies() {
+        let mut layers = layers(&[
+            (
+                "a",
+                "plugin \"x\"\nserver port=80 {\n    listen 1\n    tls\n}",
+            ),
+            ("b", "plugin \"y\"\nserver {\n    listen 2\n}"),
+        ]);
+        layers
+            .set_strategy(&["plugin"], KdlMergeStrategy::Append)
+            .set_strategy(&["server", "listen"], KdlMergeStrategy::Append);
+        let doc = layers.resolve().into_document();
+        assert_eq!(doc.get_args("plugin"), vec![&"x".into()]);
+        assert_eq!(
+            doc.nodes()
+                .iter()
+                .filter(|n| n.name().value() == "plugin")
+                .count(),
+            2
+        );
+        assert_eq!(
+            doc.get("server").unwrap().children().unwrap().nodes().len(),
+            3
+        );
+
+        layers.set_strategy(&["server"], KdlMergeStrategy::Replace);
+        let doc = layers.resolve().into_document();
+        assert_eq!(
+            doc.get("server").unwrap().to_string(),
+            "server {\n    listen 2\n}"
+        );
+
+        layers.set_default_strategy(KdlMergeStrategy::Append);
+        layers.set_strategy(&["plugin"], KdlMergeStrategy::Merge);
+        let doc = layers.resolve().into_document();
+        assert_eq!(doc.get_args("plugin"), vec![&"y".into()]);
+    }
+
+    #[test]
+    fn env() {
+        let mut layers = layers(&[("defaults", "server port=80 {\n    max-connections 10\n}")]);
+        layers.add_vars(
+            "APP_",
+            vec![
+                ("APP_SERVER__MAX_CONNECTIONS".to_string(), "20".to_string()),
+                ("APP_NAME".to_string(), "\"quoted\"".to_string()),
+                ("APP_MODE".to_string(), "plain text".to_string()),
+                ("OTHER".to_string(), "1".to_string()),
+                ("APP_".to_string(), "1".to_string()),
+            ],
+        );
+        let config = layers.resolve();
+        let doc = config.document();
+        assert_eq!(doc.get_arg("name"), Some(&"quoted".into()));
+        assert_eq!(doc.get_arg("mode"), Some(&"plain text".into()));
+        assert_eq!(doc.nodes().len(), 3);
+        let server = doc.get("server").unwrap();
+        let max = server.children().unwrap().get("max-connections").unwrap();
+        assert_eq!(max.get(0), Some(&20.into()));
+
+        let err = config.get_as::<bool>(max, 0).unwrap_err();
+        assert_eq!(err.input.name.as_deref(), Some("environment"));
+        assert_eq!(&err.input.text[err.span.offset()..][..err.span.len()], "20");
+        assert_eq!(err.label, "expected bool");
+
+        let err = config.get_as::<u16>(server, "host").unwrap_err();
+        assert_eq!(err.input.name.as_deref(), Some("defaults"));
+        assert_eq!(
+            err.to_string(),
+            "Node `server` is missing property `host`, expected u16."
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn env_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let prefix = format!("KDL_LAYERS_{}_", std::process::id());
+        let bad = std::ffi::OsStr::from_bytes(b"\xff");
+        std::env::set_var(format!("{}GOOD", prefix), "1");
+        std::env::set_var(format!("{}BAD", prefix), bad);
+        let mut layers = KdlLayers::new();
+        layers.add_env(&prefix);
+        std::env::remove_var(format!("{}GOOD", prefix));
+        std::env::remove_var(format!("{}BAD", prefix));
+
+        let doc = layers.resolve().into_document();
+        assert_eq!(doc.get_arg("good"), Some(&1.into()));
+        assert_eq!(doc.nodes().len(), 1);
+    }
+
+    #[test]
+    fn files() {
+        let dir = std::env::temp_dir().join(format!("kdl-layers-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.kdl");
+        std::fs::write(&path, "a 1\n").unwrap();
+        let mut layers = KdlLayers::new();
+        layers.add_file(&path).unwrap();
+        assert!(!layers.add_optional_file(dir.join("missing.kdl")).unwrap());
+        assert!(layers.add_file(dir.join("missing.kdl")).is_err());
+        std::fs::write(&path, "a 1.\n").unwrap();
+        match layers.add_optional_file(&path).unwrap_err() {
+            KdlReadError::Parse(err) => {
+                assert_eq!(err.input.name, Some(path.display().to_string()))
+            }
+            err => panic!("unexpected error: {:?}", err),
+        }
+        let config = layers.resolve();
+        assert_eq!(config.sources().len(), 1);
+        assert_eq!(config.document().to_string(), "a 1\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -43,6 +43,13 @@
 //! assert_eq!(doc.to_string(), doc_str);
 //! ```
 //!
+//! `KdlDocument::from_reader()` reads and parses a document from any
+//! `std::io::Read`, and `KdlDocument::write_to()` writes one to any
+//! `std::io::Write` a node at a time, without building the whole string first.
+//! `KdlDocument::parse_file()` reads and parses a file, naming it in any errors.
+//! `KdlDocument::parse_bytes()` parses raw bytes, along with any warnings, and
+//! with the `encoding` feature also reads UTF-16 and Latin-1.
+//!
 //! ## Controlling Formatting
 //!
 //! By default, everything is created with default formatting. You can parse
@@ -61,8 +68,31 @@
 //! assert_eq!(&doc.to_string(), node_str);
 //! ```
 //!
-//! [`KdlDocument`], [`KdlNode`], [`KdlEntry`], and [`KdlIdentifier`] can all
-//! be parsed and managed this way.
+//! [`KdlDocument`], [`KdlNode`], [`KdlEntry`], [`KdlIdentifier`], and
+//! [`KdlValue`] can all be parsed and managed this way, and written back out as
+//! KDL with [`ToString::to_string`].
+//!
+//! For tools that need to see every byte of the input, such as refactoring
+//! tools, [`KdlCstNode`] provides a lossless concrete syntax tree in which
+//! whitespace, comments, semicolons, and line continuations are all tokens
+//! with their own spans. Use [`KdlDocument::to_cst`] and
+//! [`KdlCstNode::to_document`] to convert between the two.
+//!
+//! The comments directly above a node can be read and replaced as plain text
+//! with [`KdlNode::comments`] and [`KdlNode::set_comment`], leaving the rest of
+//! its leading whitespace and comments alone. [`KdlNode::doc`] reads them as
+//! documentation, preferring `///` comments when there are any.
+//!
+//! Slashdashed (`/-`) nodes stay nodes, outside of the document's
+//! `nodes()`, and can be listed with `KdlDocument::disabled_nodes()`.
+//! Slashdashed entries are kept as comments, but can be listed with
+//! `KdlNode::disabled_entries()`. Both can be commented out or back in with
+//! `disable_node()`/`enable_node()` and `disable_entry()`/`enable_entry()`.
+//!
+//! Since `==` compares all of that formatting too, use
+//! [`KdlDocument::semantic_eq`] and [`KdlDocument::semantic_hash`] to compare
+//! documents by what they say instead, so that `a 0x10 "b"` and
+//! `a 16 r"b" // comment` count as the same.
 //!
 //! ### Query Engine
 //!
@@ -96,6 +126,113 @@
 //! assert_eq!(results, vec![&1.into(), &2.into(), &3.into()]);
 //! ```
 //!
+//! ### Reserved Type Annotations
+//!
+//! KDL reserves a number of type annotations for well-known kinds of values.
+//! `kdl` can convert the ones that map onto Rust types for you, such as
+//! `(duration)` values with [`KdlEntry::as_duration`] and
+//! [`KdlEntry::new_duration`]:
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! let node: kdl::KdlNode = r#"job timeout=(duration)"PT1M30S""#.parse().unwrap();
+//! let timeout = node.entry("timeout").unwrap().as_duration();
+//! assert_eq!(timeout, Some(Duration::from_secs(90)));
+//! ```
+//!
+//! Binary data can be stored as `(base64)` strings with `KdlEntry::from_bytes()`
+//! and read back with `KdlEntry::as_bytes()`, and `(ipv4)`/`(ipv6)` addresses
+//! convert to `std::net` types with `KdlEntry::as_ip_addr()` and friends. Other
+//! annotations are supported behind [optional features](#optional-features).
+//!
+//! `KdlEntry::typed_value()` interprets any of these annotations in one go,
+//! returning a `TypedValue` like `TypedValue::U8(200)` or
+//! `TypedValue::Duration(..)`. Values that don't match their reserved annotation
+//! are reported as `InvalidReservedValue` warnings by
+//! `KdlDocument::parse_with_warnings()`.
+//!
+//! ## KDL 2.0
+//!
+//! Documents are parsed as [KDL 1.0](https://github.com/kdl-org/kdl/blob/1.0.0/SPEC.md)
+//! by default. To parse [KDL 2.0](https://github.com/kdl-org/kdl/blob/2.0.0/SPEC.md)
+//! instead, with its `#true`/`#false`/`#null` and `#inf`/`#-inf`/`#nan`
+//! keywords, bare identifier strings, `#"raw"#` and dedented `"""` multi-line
+//! strings, and looser whitespace rules, set `ParseOptions::version` to
+//! `KdlVersion::V2` and use `KdlDocument::parse_with_options()`. When writing
+//! documents with `KdlDocument::to_string_with_options()`,
+//! `MultiLineStrings::LongerThan` in `EmitOptions` writes long strings in the
+//! multi-line form.
+//!
+//! `KdlDocument::to_v2()` and `KdlDocument::to_v1()` convert documents from
+//! one version to the other, keeping their comments and formatting.
+//! `KdlDocument::parse_auto()` parses either version, going by a leading
+//! `/- kdl-version 2` node when there is one, and `KdlDocument::version()`
+//! says which one a document is written in.
+//! `KdlDocument::to_string_v2()` writes any document as KDL 2.0, with a
+//! `/- kdl-version 2` marker, and `EmitOptions::version` picks the version
+//! `KdlDocument::to_string_with_options()` writes.
+//!
+//! `ParseOptions::compat` takes a `CompatMode` for reading documents from
+//! implementations that accept more than the spec does: byte order marks
+//! anywhere, bare identifiers that start with digits, CR/LF-only line breaks,
+//! and KDL 1.0 raw strings in KDL 2.0. `CompatMode::lenient()` allows all of
+//! them.
+//!
+//! ## Extensions
+//!
+//! A few features go beyond the KDL spec. Documents that use them are still
+//! ordinary KDL, and other implementations read them as plain strings,
+//! values, and nodes: they only mean something when you ask this crate to
+//! treat them specially.
+//!
+//! * `KdlDocument::interpolate()` expands `${NAME}` placeholders in strings.
+//! * `KdlDocument::substitute()` fills in `(var)"name"` template values.
+//! * `KdlIncludes` splices in the documents named by `include` nodes.
+//!
+//! ## Spans
+//!
+//! With the `span` feature (enabled by default), every [`KdlDocument`],
+//! [`KdlNode`], [`KdlEntry`], and [`KdlIdentifier`] produced by the parser
+//! records where it came from in the source text, available through their
+//! `span()` methods. This makes it possible to point diagnostics,
+//! linters, and editor tooling back at the user's original input:
+//!
+//! ```rust
+//! # #[cfg(feature = "span")]
+//! # {
+//! let input = "foo (u8)1 bar=2";
+//! let doc: kdl::KdlDocument = input.parse().unwrap();
+//! let node = doc.get("foo").unwrap();
+//! let bar = node.entry("bar").unwrap();
+//! assert_eq!(&input[bar.span().offset()..][..bar.span().len()], "bar=2");
+//! let ty = node.entry(0).unwrap().ty().unwrap();
+//! assert_eq!(&input[ty.span().offset()..][..ty.span().len()], "u8");
+//! # }
+//! ```
+//!
+//! Spans are byte offsets into the parsed string, and aren't updated when the
+//! tree is modified. After editing, call [`KdlDocument::recompute_spans`] (or
+//! its [`KdlNode`]/[`KdlEntry`] equivalents) to make them point into the
+//! current [`ToString::to_string`] output instead.
+//!
+//! Editor tooling can build on top of spans with [`KdlDocument::node_at`] and
+//! [`KdlDocument::entry_at`] (for hover and completion),
+//! [`KdlDocument::semantic_tokens`] (for syntax highlighting),
+//! [`KdlDocument::find_identifier`] (for references and renames), and
+//! [`KdlDocument::folding_ranges`] and [`KdlDocument::outline`] (for folding
+//! and document symbols).
+//!
+//! [`KdlDocument::fmt_edits`] works alongside these: it returns what
+//! [`KdlDocument::fmt`] would change as a list of [`KdlTextEdit`]s against the
+//! original text, so formatting doesn't have to replace the whole buffer.
+//! [`KdlDocument::fmt_with_source_map`] formats in place and returns a
+//! [`KdlSourceMap`] for translating spans between the original and formatted
+//! text.
+//!
+//! Going the other way, [`KdlDocument::reparse`] applies an edit made in the
+//! editor to an already-parsed document, re-parsing only the nodes around it.
+//!
 //! ## Error Reporting
 //!
 //! [`KdlError`] implements [`miette::Diagnostic`] and can be used to display
@@ -117,8 +254,9 @@
 //!
 //! This will display a message like:
 //! ```text
-//! Error:
-//!   × Expected valid value.
+//! Error: kdl::invalid_number
+//!
+//!   × Invalid number.
 //!    ╭────
 //!  1 │ foo 1.
 //!    ·     ─┬
@@ -127,6 +265,84 @@
 //!   help: Floating point numbers must be base 10, and have numbers after the decimal point.
 //! ```
 //!
+//! To have reports include the name of the file being parsed (as in
+//! `[config/app.kdl:1:1]`), pass it through [`ParseOptions::source_name`] to
+//! [`KdlDocument::parse_with_options`], or attach it to an existing error
+//! with [`KdlError::with_source_name`].
+//!
+//! Errors about documents that were already parsed, like
+//! [`KdlConversionError`], [`KdlInterpolationError`], [`KdlTemplateError`],
+//! and `KdlLint` warnings, point into the document but don't carry its source
+//! text. Attach it with [`miette::Report::with_source_code`] to render a
+//! snippet:
+//!
+//! ```rust
+//! let input = "server port=\"http\"";
+//! let doc: kdl::KdlDocument = input.parse().unwrap();
+//! let err = doc.nodes()[0].get_as::<u16>("port").unwrap_err();
+//! let report = miette::Report::new(err).with_source_code(input.to_string());
+//! # let _ = report;
+//! ```
+//!
+//! With the `serde` feature enabled, [`KdlError`] and [`KdlWarning`] also
+//! implement `serde::Serialize`, producing a flat object with `code`,
+//! `severity`, `message`, `label`, `help`, `source`, and `span` (`offset`,
+//! `length`, and 1-based `line`/`column`) fields, for shipping diagnostics to
+//! editors and web UIs without pre-rendering them.
+//!
+//! ## Optional Features
+//!
+//! * `span` (default): track source spans for every parsed item.
+//! * `serde`: implement `serde::Serialize` for diagnostics.
+//! * `bigint`: keep integers too large for an `i128` as exact
+//!   [`num_bigint::BigInt`](https://crates.io/crates/num-bigint) values instead of
+//!   failing, when parsing with `IntegerOverflow::BigInt` in `ParseOptions`.
+//! * `capi`: a C API for parsing, walking, and writing documents, declared in
+//!   `include/kdl.h`. Build it for linking with
+//!   `cargo rustc --release --features capi --crate-type cdylib` (or
+//!   `staticlib`).
+//! * `chrono`: convert entries annotated with `(date-time)`, `(date)`, and
+//!   `(time)` to and from [`chrono`](https://crates.io/crates/chrono) types,
+//!   with `KdlEntry::as_datetime()`, `KdlEntry::new_datetime()`, and friends.
+//! * `cli`: build the `kdl` command-line tool, with `kdl fmt`, `kdl query`,
+//!   `kdl validate --schema`, and `kdl convert --to json`. Install it with
+//!   `cargo install kdl --features cli`, and run `kdl --help` for details.
+//! * `encoding`: decode UTF-16 and Latin-1 input in `KdlDocument::parse_bytes()`,
+//!   reporting what it was decoded from as a `Transcoded` warning.
+//! * `ipnet`: convert entries annotated with `(cidr)` to and from
+//!   [`ipnet::IpNet`](https://crates.io/crates/ipnet), with `KdlEntry::as_ip_net()`
+//!   and `KdlEntry::from(net)`.
+//! * `macros`: build documents and nodes inline, with their syntax checked at
+//!   compile time, using the `kdl!` and `kdl_node!` macros, check KQL queries
+//!   and KDL files at compile time with `kdl_query!` and `include_kdl!`, and
+//!   map structs onto nodes with `#[derive(DecodeKdl, EncodeKdl, KdlBuilder)]`.
+//! * `mmap`: memory-map files with `KdlDocument::parse_file_mmap()`, using
+//!   [`memmap2`](https://crates.io/crates/memmap2), instead of reading them
+//!   into a `String`.
+//! * `rayon`: iterate over a document's nodes in parallel with
+//!   [`rayon`](https://crates.io/crates/rayon), using `KdlDocument::par_nodes()`,
+//!   `KdlDocument::par_nodes_mut()`, and `KdlDocument::par_descendants()`.
+//! * `rust_decimal`: read numbers exactly as
+//!   [`rust_decimal::Decimal`](https://crates.io/crates/rust_decimal) values with
+//!   `KdlEntry::as_decimal()` (for `(decimal)` numbers) and
+//!   `KdlEntry::to_decimal()` (for any number), and write them with
+//!   `KdlEntry::from(decimal)`.
+//! * `tokio`: read documents from a [`tokio`](https://crates.io/crates/tokio)
+//!   `AsyncRead` without blocking, buffered in memory, using
+//!   `KdlDocument::from_async_reader_buffered()`, and write them to an
+//!   `AsyncWrite` with `KdlDocument::write_to_async()`, or a node at a time
+//!   with `KdlAsyncWriter`.
+//! * `url`: convert entries annotated with `(url)` to and from
+//!   [`url::Url`](https://crates.io/crates/url), with `KdlEntry::as_url()` and
+//!   `KdlEntry::from(url)`.
+//! * `uuid`: convert entries annotated with `(uuid)` to and from
+//!   [`uuid::Uuid`](https://crates.io/crates/uuid), with `KdlEntry::as_uuid()`
+//!   and `KdlEntry::from(uuid)`.
+//! * `wasm`: JavaScript bindings made with
+//!   [`wasm-bindgen`](https://crates.io/crates/wasm-bindgen), to parse, format,
+//!   validate, and query documents from a browser or Node.js. Build them with
+//!   `wasm-pack build --release -- --features wasm`.
+//!
 //! ## Quirks
 //!
 //! ### Properties
@@ -142,12 +358,15 @@
 //! accepts just about anything valid, no matter how large and how small. This
 //! means a few things:
 //!
-//! * Numbers without a decimal point are interpreted as [`u64`].
+//! * Numbers without a decimal point are interpreted as [`i128`].
 //! * Numbers with a decimal point are interpreted as [`f64`].
 //! * Floating point numbers that evaluate to [`f64::INFINITY`] or
 //!   [`f64::NEG_INFINITY`] or NaN will be represented as such in the values,
 //!   instead of the original numbers.
-//! * A similar restriction applies to overflowed [`u64`] values.
+//! * Integers that don't fit in an [`i128`] fail to parse by default. Set
+//!   [`ParseOptions::integer_overflow`] to an [`IntegerOverflow`] policy to
+//!   clamp them, keep them as strings, or (with the `bigint` feature) keep
+//!   them exactly.
 //! * The original _representation_ of these numbers will be preserved, unless
 //!   you [`KdlDocument::fmt`] in which case the original representation will be
 //!   thrown away and the actual value will be used when serializing.
@@ -158,27 +377,124 @@
 //! License](LICENSE.md).
 
 #![deny(missing_debug_implementations, nonstandard_style)]
-#![warn(missing_docs, unreachable_pub, rust_2018_idioms, unreachable_pub)]
+#![warn(missing_docs, unreachable_pub, rust_2018_idioms)]
 #![cfg_attr(test, deny(warnings))]
 #![doc(html_favicon_url = "https://kdl.dev/favicon.ico")]
 #![doc(html_logo_url = "https://kdl.dev/logo.svg")]
 
+#[cfg(feature = "tokio")]
+pub use self::tokio::*;
+pub use convert::*;
+pub use cst::*;
+pub use cursor::*;
+pub use decode::*;
 pub use document::*;
+pub use edit::*;
+pub use encoding::*;
 pub use entry::*;
 pub use error::*;
+pub use history::*;
 pub use identifier::*;
+pub use include::*;
+pub use interpolate::*;
+#[cfg(feature = "macros")]
+pub use kdl_macros::{include_kdl, kdl, kdl_node, kdl_query, DecodeKdl, EncodeKdl, KdlBuilder};
+#[cfg(feature = "span")]
+pub use layers::*;
+#[cfg(feature = "span")]
+pub use lint::*;
 pub use node::*;
+pub use node_id::*;
+pub use node_map::*;
+pub use options::*;
+#[cfg(feature = "span")]
+pub use outline::*;
 pub use query::*;
+pub use roundtrip::*;
+#[cfg(feature = "span")]
+pub use semantic::*;
+pub use shared::*;
+pub use stats::*;
+pub use stream::*;
+pub use template::*;
+pub use traverse::*;
+pub use typed::*;
 pub use value::*;
+pub use visit::*;
+pub use warning::*;
 
+mod base64;
+#[cfg(feature = "bigint")]
+mod bigint;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod comment;
+mod convert;
+mod cst;
+mod cursor;
+#[cfg(feature = "chrono")]
+mod datetime;
+#[cfg(feature = "rust_decimal")]
+mod decimal;
+mod decode;
 mod document;
+mod dump;
+mod duration;
+mod edit;
+mod emit;
+mod encoding;
+mod entries;
 mod entry;
 mod error;
 mod fmt;
+mod history;
 mod identifier;
+mod include;
+mod interpolate;
+mod ip;
+#[cfg(feature = "span")]
+mod layers;
+#[cfg(feature = "span")]
+mod lint;
+mod lossless;
 mod node;
+mod node_id;
+mod node_map;
+mod nodes;
 mod nom_compat;
+mod options;
+#[cfg(feature = "span")]
+mod outline;
 mod parser;
 mod query;
 mod query_parser;
+#[cfg(feature = "rayon")]
+mod rayon;
+mod redact;
+mod roundtrip;
+#[cfg(feature = "span")]
+mod semantic;
+mod semantic_eq;
+#[cfg(feature = "serde")]
+mod ser;
+mod shared;
+mod slashdash;
+mod snapshot;
+mod stats;
+mod stream;
+mod template;
+#[cfg(feature = "tokio")]
+mod tokio;
+mod traverse;
+mod typed;
+#[cfg(feature = "url")]
+mod url;
+mod util;
+#[cfg(feature = "uuid")]
+mod uuid;
 mod value;
+mod versions;
+mod visit;
+mod warning;
+#[cfg(feature = "wasm")]
+pub mod wasm;
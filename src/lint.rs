@@ -0,0 +1,388 @@
+use std::{collections::HashMap, fmt};
+
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::{versions, KdlDocument, KdlEntry, KdlIdentifier, KdlNode, KdlTextEdit};
+
+/// A warning produced by a [`KdlLintPass`].
+///
+/// Rendering it needs the document's source text attached, as described
+/// under [Error Reporting](crate#error-reporting).
+#[derive(Debug, Diagnostic, Clone, Eq, PartialEq, Error)]
+#[error("{message}")]
+#[diagnostic(severity(Warning), code(kdl::lint))]
+pub struct KdlLint {
+    /// The annotation of the pass that produced this lint, like
+    /// `"deprecated"`.
+    pub annotation: String,
+
+    /// What's wrong.
+    pub message: String,
+
+    /// Span of the node or entry this lint is about.
+    #[label("here")]
+    pub span: SourceSpan,
+
+    /// How to fix it.
+    #[help]
+    pub help: Option<String>,
+
+    /// Edits to the source text that fix it, if it can be fixed
+    /// automatically. Apply them with [`KdlTextEdit::apply`].
+    pub suggestions: Vec<KdlTextEdit>,
+}
+
+impl KdlLint {
+    /// Creates a lint, with no help or suggestions.
+    pub fn new(
+        annotation: impl Into<String>,
+        message: impl Into<String>,
+        span: impl Into<SourceSpan>,
+    ) -> Self {
+        Self {
+            annotation: annotation.into(),
+            message: message.into(),
+            span: span.into(),
+            help: None,
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// Adds help text explaining how to fix this lint.
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Adds an edit that (along with any others) fixes this lint.
+    pub fn with_suggestion(
+        mut self,
+        span: impl Into<SourceSpan>,
+        replacement: impl Into<String>,
+    ) -> Self {
+        self.suggestions.push(KdlTextEdit {
+            span: span.into(),
+            replacement: replacement.into(),
+        });
+        self
+    }
+}
+
+/// Something a [`KdlLintPass`] checks: a node or an entry with the pass's
+/// type annotation.
+#[derive(Debug, Clone, Copy)]
+pub enum KdlLintTarget<'a> {
+    /// A node annotated like `(deprecated)node`.
+    Node(&'a KdlNode),
+
+    /// An entry annotated like `node (deprecated)1` or
+    /// `node key=(deprecated)1`.
+    Entry {
+        /// The node the entry is in.
+        node: &'a KdlNode,
+
+        /// The entry.
+        entry: &'a KdlEntry,
+    },
+}
+
+impl KdlLintTarget<'_> {
+    /// The span of the node or entry.
+    pub fn span(&self) -> SourceSpan {
+        match self {
+            KdlLintTarget::Node(node) => node.span(),
+            KdlLintTarget::Entry { entry, .. } => entry.span(),
+        }
+    }
+
+    /// The target's type annotation. The linter only checks annotated
+    /// targets, but one built by hand might not be.
+    pub fn ty(&self) -> Option<&KdlIdentifier> {
+        match self {
+            KdlLintTarget::Node(node) => node.ty(),
+            KdlLintTarget::Entry { entry, .. } => entry.ty(),
+        }
+    }
+
+    /// The span of the target's type annotation, parentheses included, or
+    /// `None` if it isn't known, like for a node that wasn't parsed.
+    pub fn ty_span(&self) -> Option<SourceSpan> {
+        let span = match self {
+            KdlLintTarget::Node(node) => node.ty_span,
+            KdlLintTarget::Entry { entry, .. } => entry.ty_span,
+        };
+        if self.ty().is_some() && !span.is_empty() {
+            Some(span)
+        } else {
+            None
+        }
+    }
+
+    /// The node's name, or the entry's property name. Arguments don't have
+    /// one.
+    pub fn name(&self) -> Option<&KdlIdentifier> {
+        match self {
+            KdlLintTarget::Node(node) => Some(node.name()),
+            KdlLintTarget::Entry { entry, .. } => entry.name(),
+        }
+    }
+}
+
+/// A check for nodes and entries with a particular type annotation,
+/// registered with a [`KdlLinter`].
+///
+/// Annotations are how documents opt into checks: the linter calls
+/// [`KdlLintPass::check`] with every node and entry (including in children,
+/// but not slashdashed ones) annotated like `(deprecated)`, for the pass
+/// whose [`KdlLintPass::annotation`] is `"deprecated"`.
+pub trait KdlLintPass {
+    /// The type annotation this pass checks, without parentheses.
+    fn annotation(&self) -> &str;
+
+    /// Checks `target`, adding any problems it finds to `lints`.
+    fn check(&mut self, target: KdlLintTarget<'_>, lints: &mut Vec<KdlLint>);
+}
+
+/// Runs [`KdlLintPass`]es over documents.
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::{KdlDocument, KdlLinter, KdlDeprecatedPass, KdlTextEdit};
+/// let input = "(deprecated)max-conn 10\nserver timeout=(deprecated)30\n";
+/// let doc: KdlDocument = input.parse().unwrap();
+/// let mut linter = KdlLinter::new();
+/// linter.register(KdlDeprecatedPass::new().rename("max-conn", "max-connections"));
+///
+/// let lints = linter.lint(&doc);
+/// assert_eq!(lints[0].message, "`max-conn` is deprecated.");
+/// assert_eq!(lints[0].help.as_deref(), Some("Use `max-connections` instead."));
+/// assert_eq!(lints[1].message, "`timeout` is deprecated.");
+///
+/// let fixed = KdlTextEdit::apply(input, &lints[0].suggestions);
+/// assert_eq!(fixed, "max-connections 10\nserver timeout=(deprecated)30\n");
+/// ```
+#[derive(Default)]
+pub struct KdlLinter {
+    passes: Vec<Box<dyn KdlLintPass>>,
+}
+
+impl KdlLinter {
+    /// Creates a linter with no passes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a pass. More than one pass can check the same annotation.
+    pub fn register(&mut self, pass: impl KdlLintPass + 'static) -> &mut Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// Runs every pass over `doc`, returning the lints they found, in
+    /// document order.
+    pub fn lint(&mut self, doc: &KdlDocument) -> Vec<KdlLint> {
+        let mut lints = Vec::new();
+        for node in doc.nodes() {
+            self.lint_node(node, &mut lints);
+        }
+        lints
+    }
+
+    fn lint_node(&mut self, node: &KdlNode, lints: &mut Vec<KdlLint>) {
+        if let Some(ty) = node.ty() {
+            self.run(ty, KdlLintTarget::Node(node), lints);
+        }
+        for entry in node.entries() {
+            if let Some(ty) = entry.ty() {
+                self.run(ty, KdlLintTarget::Entry { node, entry }, lints);
+            }
+        }
+        for child in node.children().map_or(&[][..], KdlDocument::nodes) {
+            self.lint_node(child, lints);
+        }
+    }
+
+    fn run(&mut self, ty: &KdlIdentifier, target: KdlLintTarget<'_>, lints: &mut Vec<KdlLint>) {
+        for pass in &mut self.passes {
+            if pass.annotation() == ty.value() {
+                pass.check(target, lints);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for KdlLinter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let annotations: Vec<&str> = self.passes.iter().map(|pass| pass.annotation()).collect();
+        f.debug_struct("KdlLinter")
+            .field("passes", &annotations)
+            .finish()
+    }
+}
+
+/// A [`KdlLintPass`] that warns about every node and property annotated
+/// `(deprecated)`, and suggests what to rename them to, if they've been
+/// renamed.
+#[derive(Debug, Clone, Default)]
+pub struct KdlDeprecatedPass {
+    renames: HashMap<String, String>,
+}
+
+impl KdlDeprecatedPass {
+    /// Creates a pass with no renames.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Suggests renaming deprecated nodes and properties named `old` to
+    /// `new`, dropping the `(deprecated)` annotation.
+    pub fn rename(mut self, old: impl Into<String>, new: impl Into<String>) -> Self {
+        self.renames.insert(old.into(), new.into());
+        self
+    }
+}
+
+impl KdlLintPass for KdlDeprecatedPass {
+    fn annotation(&self) -> &str {
+        "deprecated"
+    }
+
+    fn check(&mut self, target: KdlLintTarget<'_>, lints: &mut Vec<KdlLint>) {
+        let message = match target.name() {
+            Some(name) => format!("`{}` is deprecated.", name.value()),
+            None => "This value is deprecated.".to_string(),
+        };
+        let mut lint = KdlLint::new(self.annotation(), message, target.span());
+        let renamed = target
+            .name()
+            .and_then(|name| Some((name, self.renames.get(name.value())?)));
+        if let Some((name, new)) = renamed {
+            lint = lint.with_help(format!("Use `{}` instead.", new));
+            // Only suggest edits where we know what to replace.
+            if let (Some(ty), false) = (target.ty_span(), name.span().is_empty()) {
+                let version = match target {
+                    KdlLintTarget::Node(node) | KdlLintTarget::Entry { node, .. } => node.version,
+                };
+                let new_name = versions::identifier_repr(new, version).into_owned();
+                lint = match target {
+                    KdlLintTarget::Node(_) => {
+                        let end = name.span().offset() + name.span().len();
+                        lint.with_suggestion(ty.offset()..end, new_name)
+                    }
+                    KdlLintTarget::Entry { .. } => lint
+                        .with_suggestion(name.span(), new_name)
+                        .with_suggestion(ty, ""),
+                };
+            }
+        }
+        lints.push(lint);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Limit;
+
+    impl KdlLintPass for Limit {
+        fn annotation(&self) -> &str {
+            "limit"
+        }
+
+        fn check(&mut self, target: KdlLintTarget<'_>, lints: &mut Vec<KdlLint>) {
+            if let KdlLintTarget::Entry { entry, .. } = target {
+                if entry.value().as_i64().map_or(false, |v| v > 10) {
+                    lints.push(KdlLint::new("limit", "Too big.", target.span()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lints() -> miette::Result<()> {
+        let input = "a (limit)5 (limit)50 {\n    (deprecated)b old=(deprecated)1 (deprecated)2\n}\n/-(limit)c (limit)99\n";
+        let doc: KdlDocument = input.parse()?;
+        let mut linter = KdlLinter::new();
+        linter.register(Limit).register(
+            KdlDeprecatedPass::new()
+                .rename("old", "new")
+                .rename("b", "\"b 2\""),
+        );
+        assert_eq!(
+            format!("{:?}", linter),
+            "KdlLinter { passes: [\"limit\", \"deprecated\"] }"
+        );
+
+        let lints = linter.lint(&doc);
+        let found: Vec<_> = lints
+            .iter()
+            .map(|lint| {
+                (
+                    lint.message.as_str(),
+                    &input[lint.span.offset()..][..lint.span.len()],
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("Too big.", "(limit)50"),
+                (
+                    "`b` is deprecated.",
+                    "(deprecated)b old=(deprecated)1 (deprecated)2"
+                ),
+                ("`old` is deprecated.", "old=(deprecated)1"),
+                ("This value is deprecated.", "(deprecated)2"),
+            ]
+        );
+        assert!(lints[3].suggestions.is_empty());
+        let edits: Vec<_> = lints[1..3]
+            .iter()
+            .flat_map(|lint| lint.suggestions.clone())
+            .collect();
+        assert_eq!(
+            KdlTextEdit::apply(input, &edits),
+            "a (limit)5 (limit)50 {\n    \"\\\"b 2\\\"\" new=1 (deprecated)2\n}\n/-(limit)c (limit)99\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn deprecated_suggestions_use_parsed_parens() -> miette::Result<()> {
+        let input = "( deprecated )b 1 old=( deprecated ) 2\n";
+        let doc = KdlDocument::parse_auto(input)?;
+        let mut linter = KdlLinter::new();
+        linter.register(
+            KdlDeprecatedPass::new()
+                .rename("b", "c")
+                .rename("old", "a#b"),
+        );
+        let lints = linter.lint(&doc);
+        let edits: Vec<_> = lints
+            .iter()
+            .flat_map(|lint| lint.suggestions.clone())
+            .collect();
+        assert_eq!(KdlTextEdit::apply(input, &edits), "c 1 \"a#b\"= 2\n");
+        Ok(())
+    }
+
+    #[test]
+    fn deprecated_without_spans() {
+        let mut node = KdlNode::new("b");
+        node.set_ty("deprecated");
+        let target = KdlLintTarget::Node(&node);
+        assert_eq!(target.ty().map(|ty| ty.value()), Some("deprecated"));
+        assert_eq!(target.ty_span(), None);
+
+        let mut lints = Vec::new();
+        KdlDeprecatedPass::new()
+            .rename("b", "c")
+            .check(target, &mut lints);
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].help.as_deref(), Some("Use `c` instead."));
+        assert!(lints[0].suggestions.is_empty());
+    }
+}
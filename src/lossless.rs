@@ -0,0 +1,255 @@
+use std::sync::Arc;
+
+use miette::SourceSpan;
+
+use crate::{KdlDocument, KdlError, KdlSource, ParseOptions};
+
+impl KdlDocument {
+    /// Parses as much of `input` as possible, keeping the parts that don't
+    /// parse as raw text, so that writing the document back out reproduces
+    /// `input` byte for byte, errors and all. This is for tools that clean
+    /// up broken files a bit at a time, and need to leave what they don't
+    /// touch alone.
+    ///
+    /// Returns the document along with an error for each part that didn't
+    /// parse. Each error's [`related_span`](KdlError::related_span) covers
+    /// the raw text kept in its place, which is always whole lines: the
+    /// line with the error, back to the start of the node it's in, along
+    /// with any closing braces after it that no longer match. The raw text
+    /// is kept in the whitespace and comments around the nodes that did
+    /// parse, so it's left alone by edits to those nodes, but it's thrown
+    /// away by [`KdlDocument::fmt`] and anything else that rewrites the
+    /// formatting.
+    ///
+    /// Recovery is a heuristic: text after an error that happens to parse,
+    /// like the rest of an unterminated string, is read as nodes. Each
+    /// region that doesn't parse is retried from every line before it, so
+    /// this can be slow for very large files with errors in very large
+    /// nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let input = "server port=8080\nclient retries=3.\nlogging level=\"info\"\n";
+    /// let (doc, errors) = KdlDocument::parse_lossless(input);
+    /// assert_eq!(doc.to_string(), input);
+    /// assert!(doc.get("server").is_some());
+    /// assert!(doc.get("client").is_none());
+    /// assert!(doc.get("logging").is_some());
+    ///
+    /// assert_eq!(errors.len(), 1);
+    /// let raw = errors[0].related_span.unwrap();
+    /// assert_eq!(&input[raw.offset()..][..raw.len()], "client retries=3.\n");
+    /// ```
+    pub fn parse_lossless(input: &str) -> (Self, Vec<KdlError>) {
+        Self::parse_lossless_with_options(input, &ParseOptions::default())
+    }
+
+    /// Parses `input` like [`KdlDocument::parse_lossless`], using the given
+    /// [`ParseOptions`].
+    pub fn parse_lossless_with_options(
+        input: &str,
+        options: &ParseOptions,
+    ) -> (Self, Vec<KdlError>) {
+        let source = Arc::new(KdlSource {
+            name: options.source_name.clone(),
+            text: input.into(),
+        });
+        let region_options = ParseOptions {
+            source_name: None,
+            ..options.clone()
+        };
+        let parse = |text: &str| KdlDocument::parse_with_options(text, &region_options);
+
+        let mut doc = KdlDocument::new();
+        let mut errors: Vec<KdlError> = Vec::new();
+        let mut raw = String::new();
+        let mut raw_end = 0;
+        let mut pos = 0;
+        loop {
+            let rest = &input[pos..];
+            let err = match parse(rest) {
+                Ok(part) => {
+                    append(&mut doc, &mut raw, part, pos);
+                    break;
+                }
+                Err(err) => err,
+            };
+            let offset = char_boundary(rest, err.span.offset());
+            // Keep the longest run of whole lines before the error that
+            // parses. There's always one, since an empty document does.
+            let (cut, part) = line_starts(rest, offset)
+                .find_map(|cut| parse(&rest[..cut]).ok().map(|part| (cut, part)))
+                .unwrap_or((0, KdlDocument::new()));
+            append(&mut doc, &mut raw, part, pos);
+
+            let end = rest[offset..]
+                .find('\n')
+                .map_or(rest.len(), |i| offset + i + 1);
+            raw.push_str(&rest[cut..end]);
+            let region = (pos + cut..pos + end).into();
+            // A stray closing brace right where the last region stopped is
+            // most likely the end of the broken children block in it, and
+            // not an error of its own.
+            let fallout = raw_end == pos && cut == 0 && rest.trim_start().starts_with('}');
+            match errors.last_mut() {
+                Some(last) if fallout => {
+                    let start = last.related_span.map_or(pos, |span| span.offset());
+                    last.related_span = Some((start..pos + end).into());
+                }
+                _ => errors.push(relocate(err, &source, pos, region)),
+            }
+            raw_end = pos + end;
+            pos += end;
+        }
+        #[cfg(feature = "span")]
+        {
+            doc.span = (0..input.len()).into();
+        }
+        (doc, errors)
+    }
+}
+
+/// Adds the nodes from `part`, which starts at `offset` in the input, to
+/// the end of `doc`, keeping `raw` (which is then emptied), along with
+/// `doc`'s trailing text and `part`'s leading text, in between.
+fn append(doc: &mut KdlDocument, raw: &mut String, mut part: KdlDocument, offset: usize) {
+    #[cfg(feature = "span")]
+    part.shift_spans(offset as isize);
+    #[cfg(not(feature = "span"))]
+    let _ = offset;
+    doc.version = part.version;
+
+    // Slashdashed nodes at the end of `doc` stay in front of the glue.
+    let mut disabled = std::mem::take(&mut doc.disabled);
+    let mut glue = String::new();
+    let at_start = doc.nodes().is_empty() && disabled.is_empty();
+    if at_start {
+        glue.extend(doc.leading.take());
+    }
+    glue.extend(doc.trailing.take());
+    glue.push_str(raw);
+    raw.clear();
+    glue.extend(part.leading.take());
+    let has_nodes = !part.nodes.is_empty();
+    let (next_disabled, next_leading) = match part.nodes.first_mut() {
+        Some(first) => (&mut first.disabled, &mut first.leading),
+        None => (&mut part.disabled, &mut part.trailing),
+    };
+    if at_start && has_nodes {
+        doc.leading = Some(glue).filter(|glue| !glue.is_empty());
+    } else if let Some(first) = next_disabled.first_mut() {
+        first
+            .slashdash
+            .get_or_insert_with(String::new)
+            .insert_str(0, &glue);
+    } else if !has_nodes {
+        glue.extend(next_leading.take());
+        *next_leading = Some(glue).filter(|glue| !glue.is_empty());
+    } else if !glue.is_empty() {
+        glue.extend(next_leading.take());
+        *next_leading = Some(glue);
+    }
+    disabled.append(next_disabled);
+    *next_disabled = disabled;
+    doc.trailing = part.trailing.take();
+    doc.disabled = std::mem::take(&mut part.disabled);
+    doc.nodes_mut().extend(part.nodes.into_vec());
+}
+
+/// The starts of the lines in `text` up to the one containing `offset`,
+/// from last to first, leaving out the end of `text` itself.
+fn line_starts(text: &str, offset: usize) -> impl Iterator<Item = usize> + '_ {
+    let starts = text[..offset]
+        .match_indices('\n')
+        .map(|(i, _)| i + 1)
+        .collect::<Vec<_>>();
+    starts
+        .into_iter()
+        .rev()
+        .chain(std::iter::once(0))
+        .filter(move |&start| start < text.len())
+}
+
+fn char_boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+/// Points an error from parsing the part of the input at `offset` at the
+/// whole input instead.
+fn relocate(
+    mut err: KdlError,
+    source: &Arc<KdlSource>,
+    offset: usize,
+    region: SourceSpan,
+) -> KdlError {
+    err.input = source.clone();
+    err.span = (err.span.offset() + offset, err.span.len()).into();
+    err.related_span = Some(region);
+    err.related_label = Some("kept as is");
+    err
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keeps_everything() {
+        let input = "// top\na 1\nb 1.\nc {\n    d 0x\n}\ne \"ok\" /* x */\nf \"unterminated";
+        let (doc, errors) = KdlDocument::parse_lossless(input);
+        assert_eq!(doc.to_string(), input);
+        let names: Vec<_> = doc.nodes().iter().map(|n| n.name().value()).collect();
+        assert_eq!(names, vec!["a", "e"]);
+        #[cfg(feature = "span")]
+        assert_eq!(
+            doc.get("e").unwrap().span().offset(),
+            input.find("e ").unwrap()
+        );
+
+        let regions: Vec<_> = errors
+            .iter()
+            .map(|err| {
+                let region = err.related_span.unwrap();
+                assert!(region.offset() <= err.span.offset());
+                assert!(err.span.offset() <= region.offset() + region.len());
+                assert_eq!(err.input.text, input);
+                &input[region.offset()..][..region.len()]
+            })
+            .collect();
+        assert_eq!(
+            regions,
+            vec!["b 1.\n", "c {\n    d 0x\n}\n", "f \"unterminated"]
+        );
+    }
+
+    #[test]
+    fn edges() {
+        for input in [
+            "",
+            "a",
+            "\n\n",
+            "1.\n",
+            "a 1.\n\n",
+            "\n}\n// c\n",
+            "/-a\n1.\n/-b\nc\n/-d",
+            "a\n/-b\n1.\nc\n",
+        ] {
+            let (doc, errors) = KdlDocument::parse_lossless(input);
+            assert_eq!(doc.to_string(), input);
+            assert_eq!(errors.is_empty(), input.parse::<KdlDocument>().is_ok());
+        }
+
+        let options = ParseOptions {
+            source_name: Some("legacy.kdl".into()),
+            ..ParseOptions::default()
+        };
+        let (_, errors) = KdlDocument::parse_lossless_with_options("a 1.", &options);
+        assert_eq!(errors[0].input.name.as_deref(), Some("legacy.kdl"));
+    }
+}
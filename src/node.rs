@@ -8,8 +8,8 @@ use std::{
 use miette::SourceSpan;
 
 use crate::{
-    parser, IntoKdlQuery, KdlDocument, KdlEntry, KdlError, KdlIdentifier, KdlQueryIterator,
-    KdlValue,
+    entries::KdlEntries, parser, IntoKdlQuery, KdlDocument, KdlEntry, KdlError, KdlIdentifier,
+    KdlNodeId, KdlQueryIterator, KdlValue, KdlVersion,
 };
 
 /// Represents an individual KDL
@@ -17,33 +17,49 @@ use crate::{
 /// KDL Document.
 #[derive(Debug, Clone, Eq)]
 pub struct KdlNode {
+    /// Slashdashed nodes between the previous node and this one.
+    pub(crate) disabled: Vec<KdlNode>,
+    /// For a slashdashed node, the text before its `/-`.
+    pub(crate) slashdash: Option<String>,
     pub(crate) leading: Option<String>,
     pub(crate) ty: Option<KdlIdentifier>,
     pub(crate) name: KdlIdentifier,
-    // TODO: consider using `hashlink` for this instead, later.
-    pub(crate) entries: Vec<KdlEntry>,
+    pub(crate) entries: KdlEntries,
     pub(crate) before_children: Option<String>,
     pub(crate) children: Option<KdlDocument>,
     pub(crate) trailing: Option<String>,
     #[cfg(feature = "span")]
     pub(crate) span: SourceSpan,
+    /// The span of the type annotation, parentheses included, or `0..0` if
+    /// it wasn't parsed.
+    #[cfg(feature = "span")]
+    pub(crate) ty_span: SourceSpan,
+    pub(crate) id: KdlNodeId,
+    /// The KDL version this node's text, including its slashdashed
+    /// entries, is written in.
+    pub(crate) version: KdlVersion,
 }
 
 impl PartialEq for KdlNode {
     fn eq(&self, other: &Self) -> bool {
-        self.leading == other.leading
+        self.disabled == other.disabled
+            && self.slashdash == other.slashdash
+            && self.leading == other.leading
             && self.ty == other.ty
             && self.name == other.name
             && self.entries == other.entries
             && self.before_children == other.before_children
             && self.children == other.children
             && self.trailing == other.trailing
-        // intentionally omitted: self.span == other.span
+        // intentionally omitted: self.span == other.span, self.ty_span ==
+        // other.ty_span, self.id == other.id, self.version == other.version
     }
 }
 
 impl std::hash::Hash for KdlNode {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.disabled.hash(state);
+        self.slashdash.hash(state);
         self.leading.hash(state);
         self.ty.hash(state);
         self.name.hash(state);
@@ -51,7 +67,9 @@ impl std::hash::Hash for KdlNode {
         self.before_children.hash(state);
         self.children.hash(state);
         self.trailing.hash(state);
-        // Intentionally omitted: self.span.hash(state);
+        // Intentionally omitted: self.span.hash(state),
+        // self.ty_span.hash(state), self.id.hash(state),
+        // self.version.hash(state);
     }
 }
 
@@ -60,17 +78,34 @@ impl KdlNode {
     pub fn new(name: impl Into<KdlIdentifier>) -> Self {
         Self {
             name: name.into(),
+            disabled: Vec::new(),
+            slashdash: None,
             leading: None,
             ty: None,
-            entries: Vec::new(),
+            entries: KdlEntries::default(),
             before_children: None,
             children: None,
             trailing: None,
             #[cfg(feature = "span")]
             span: SourceSpan::from(0..0),
+            #[cfg(feature = "span")]
+            ty_span: SourceSpan::from(0..0),
+            id: KdlNodeId::next(),
+            version: KdlVersion::V1,
         }
     }
 
+    /// Returns true if this node is commented out with a slashdash (`/-`).
+    /// See [`KdlDocument::disabled_nodes`].
+    pub fn is_disabled(&self) -> bool {
+        self.slashdash.is_some()
+    }
+
+    /// Gets this node's ID. See [`KdlNodeId`].
+    pub fn id(&self) -> KdlNodeId {
+        self.id
+    }
+
     /// Gets this node's name.
     pub fn name(&self) -> &KdlIdentifier {
         &self.name
@@ -88,9 +123,16 @@ impl KdlNode {
 
     /// Gets this node's span.
     ///
-    /// This value will be properly initialized when created via [`KdlDocument::parse`]
-    /// but may become invalidated if the document is mutated. We do not currently
-    /// guarantee this to yield any particularly consistent results at that point.
+    /// The span covers the node from its type annotation (or name) up to and
+    /// including its children block or last entry, but not any leading or
+    /// trailing whitespace, comments, or terminator.
+    ///
+    /// This value will be properly initialized when parsed with [`str::parse`].
+    /// Spans are never updated automatically: after mutating, they still
+    /// point at the originally parsed text (or at `0..0`, for values that
+    /// weren't parsed at all). Use `recompute_spans()` on the document, node,
+    /// or entry you modified to make them match its current
+    /// [`Display`](std::fmt::Display) output.
     #[cfg(feature = "span")]
     pub fn span(&self) -> SourceSpan {
         self.span
@@ -102,6 +144,14 @@ impl KdlNode {
         self.span = span.into();
     }
 
+    /// Recomputes the spans of this node and everything in it so they point
+    /// into its current string representation, as if `node.to_string()` had
+    /// been parsed again.
+    #[cfg(feature = "span")]
+    pub fn recompute_spans(&mut self) {
+        self.recompute_spans_at(0, 0, KdlVersion::V1);
+    }
+
     /// Gets the node's type identifier, if any.
     pub fn ty(&self) -> Option<&KdlIdentifier> {
         self.ty.as_ref()
@@ -128,7 +178,88 @@ impl KdlNode {
         &mut self.entries
     }
 
+    /// Returns an iterator over this node's entries. Shorthand for
+    /// `node.entries().iter()`, also used by `for entry in &node`.
+    pub fn iter(&self) -> std::slice::Iter<'_, KdlEntry> {
+        self.entries.iter()
+    }
+
+    /// Returns an iterator that allows modifying each of this node's entries
+    /// in place, also used by `for entry in &mut node`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let mut node: KdlNode = "scale 1 2 factor=3".parse().unwrap();
+    /// for entry in &mut node {
+    ///     if entry.name().is_none() {
+    ///         entry.set_ty("u8");
+    ///     }
+    /// }
+    /// assert_eq!(node.to_string(), "scale (u8)1 (u8)2 factor=3");
+    /// ```
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, KdlEntry> {
+        self.entries.iter_mut()
+    }
+
+    /// Returns an iterator over the values of this node's arguments, skipping
+    /// its properties.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlNode, KdlValue};
+    /// let node: KdlNode = "file \"a.txt\" mode=0o644 \"b.txt\"".parse().unwrap();
+    /// let args: Vec<_> = node.args().filter_map(KdlValue::as_string).collect();
+    /// assert_eq!(args, vec!["a.txt", "b.txt"]);
+    ///
+    /// let props: Vec<_> = node.props().collect();
+    /// assert_eq!(props, vec![("mode", &KdlValue::Base8(0o644))]);
+    /// ```
+    pub fn args(&self) -> impl Iterator<Item = &KdlValue> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.name.is_none())
+            .map(|entry| &entry.value)
+    }
+
+    /// Returns an iterator over mutable references to the values of this
+    /// node's arguments.
+    ///
+    /// As with [`KdlEntry::value_mut`], changed values are still written out
+    /// using their original text until their entry's formatting is cleared.
+    pub fn args_mut(&mut self) -> impl Iterator<Item = &mut KdlValue> {
+        self.entries
+            .iter_mut()
+            .filter(|entry| entry.name.is_none())
+            .map(|entry| &mut entry.value)
+    }
+
+    /// Returns an iterator over this node's properties, as `(name, value)`
+    /// pairs in the order they were written. If a property is repeated, each
+    /// occurrence is included, even though only the last one counts (see
+    /// [`KdlNode::get`]).
+    pub fn props(&self) -> impl Iterator<Item = (&str, &KdlValue)> {
+        self.entries.iter().filter_map(|entry| {
+            let name = entry.name.as_ref()?;
+            Some((name.value(), &entry.value))
+        })
+    }
+
+    /// Returns an iterator over this node's properties, with mutable
+    /// references to their values. See [`KdlNode::args_mut`].
+    pub fn props_mut(&mut self) -> impl Iterator<Item = (&str, &mut KdlValue)> {
+        self.entries.iter_mut().filter_map(|entry| {
+            let name = entry.name.as_ref()?;
+            Some((name.value(), &mut entry.value))
+        })
+    }
+
     /// Gets leading text (whitespace, comments) for this node.
+    ///
+    /// Nodes commented out with a slashdash before this one aren't part of
+    /// it: see [`KdlDocument::disabled_nodes`].
     pub fn leading(&self) -> Option<&str> {
         self.leading.as_deref()
     }
@@ -168,13 +299,17 @@ impl KdlNode {
         self.len() == 0
     }
 
-    /// Clears leading and trailing text (whitespace, comments), as well as
-    /// the space before the children block, if any. Individual entries and
-    /// their formatting will be preserved.
+    /// Clears leading and trailing text (whitespace, comments, and
+    /// slashdashed nodes), as well as the space before the children block, if
+    /// any. Individual entries and their formatting will be preserved.
     ///
     /// If you want to clear formatting on all children and entries as well,
     /// use [`Self::clear_fmt_recursive`].
     pub fn clear_fmt(&mut self) {
+        self.disabled.clear();
+        if let Some(slashdash) = self.slashdash.as_mut() {
+            slashdash.clear();
+        }
         self.leading = None;
         self.trailing = None;
         self.before_children = None;
@@ -196,6 +331,11 @@ impl KdlNode {
 
     /// Gets a value by key. Number keys will look up arguments, strings will
     /// look up properties.
+    ///
+    /// Properties are looked up in constant time in nodes with lots of
+    /// entries, using an index that's built on the first lookup and rebuilt
+    /// after the entries change, so repeated lookups in a large node that
+    /// isn't being changed stay fast.
     pub fn get(&self, key: impl Into<NodeKey>) -> Option<&KdlValue> {
         self.entry_impl(key.into()).map(|e| &e.value)
     }
@@ -209,15 +349,8 @@ impl KdlNode {
     fn entry_impl(&self, key: NodeKey) -> Option<&KdlEntry> {
         match key {
             NodeKey::Key(key) => {
-                let mut current = None;
-                for entry in &self.entries {
-                    if entry.name.is_some()
-                        && entry.name.as_ref().map(|i| i.value()) == Some(key.value())
-                    {
-                        current = Some(entry);
-                    }
-                }
-                current
+                let idx = self.entries.prop_position(key.value())?;
+                Some(&self.entries[idx])
             }
             NodeKey::Index(idx) => {
                 let mut current_idx = 0;
@@ -237,7 +370,14 @@ impl KdlNode {
     /// Fetches a mutable referene to an value by key. Number keys will look
     /// up arguments, strings will look up properties.
     pub fn get_mut(&mut self, key: impl Into<NodeKey>) -> Option<&mut KdlValue> {
-        self.entry_mut_impl(key.into()).map(|e| &mut e.value)
+        match key.into() {
+            // Values can't rename the entry, so there's no need to check.
+            NodeKey::Key(key) => {
+                let idx = self.entries.prop_position(key.value())?;
+                Some(self.entries.value_mut(idx))
+            }
+            key => self.entry_mut_impl(key).map(|e| &mut e.value),
+        }
     }
 
     /// Fetches a mutable referene to an entry by key. Number keys will look
@@ -249,27 +389,18 @@ impl KdlNode {
     fn entry_mut_impl(&mut self, key: NodeKey) -> Option<&mut KdlEntry> {
         match key {
             NodeKey::Key(key) => {
-                let mut current = None;
-                for entry in &mut self.entries {
-                    if entry.name.is_some()
-                        && entry.name.as_ref().map(|i| i.value()) == Some(key.value())
-                    {
-                        current = Some(entry);
-                    }
-                }
-                current
+                let idx = self.entries.prop_position(key.value())?;
+                Some(self.entries.entry_mut(idx))
             }
             NodeKey::Index(idx) => {
-                let mut current_idx = 0;
-                for entry in &mut self.entries {
-                    if entry.name.is_none() {
-                        if current_idx == idx {
-                            return Some(entry);
-                        }
-                        current_idx += 1;
-                    }
-                }
-                None
+                let idx = self
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| entry.name.is_none())
+                    .nth(idx)?
+                    .0;
+                Some(self.entries.entry_mut(idx))
             }
         }
     }
@@ -384,6 +515,64 @@ impl KdlNode {
         self.children.as_ref()
     }
 
+    /// Gets the first argument of each of this node's `-` children, following
+    /// the KDL convention for lists of values. `-` children without any
+    /// arguments are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlNode, KdlValue};
+    /// let node: KdlNode = "ports {\n    - 80\n    - 443\n}".parse().unwrap();
+    /// let ports: Vec<_> = node.dash_values().filter_map(KdlValue::as_i64).collect();
+    /// assert_eq!(ports, vec![80, 443]);
+    /// ```
+    pub fn dash_values(&self) -> impl Iterator<Item = &KdlValue> {
+        self.children_named("-").filter_map(|node| node.get(0))
+    }
+
+    /// Creates a new node whose children are a `-` node for each of
+    /// `values`, the reverse of [`KdlNode::dash_values`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let mut node = KdlNode::from_dash_values("ports", [80, 443]);
+    /// node.fmt();
+    /// assert_eq!(node.to_string(), "ports {\n    - 80\n    - 443\n}");
+    /// ```
+    pub fn from_dash_values<V: Into<KdlValue>>(
+        name: impl Into<KdlIdentifier>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        let mut node = KdlNode::new(name);
+        let children = node.ensure_children();
+        for value in values {
+            let mut dash = KdlNode::new("-");
+            dash.push(value.into());
+            children.nodes_mut().push(dash);
+        }
+        node
+    }
+
+    /// Gets every child node with a matching name, in document order. See
+    /// [`KdlDocument::get_all`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let node: KdlNode = "deps { dep \"serde\"; dep \"nom\"; }".parse().unwrap();
+    /// assert_eq!(node.children_named("dep").count(), 2);
+    /// assert_eq!(node.children_named("nope").count(), 0);
+    /// ```
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a KdlNode> + 'a {
+        self.children
+            .iter()
+            .flat_map(move |children| children.get_all(name))
+    }
+
     /// Returns a mutable reference to this node's children, if any.
     pub fn children_mut(&mut self) -> &mut Option<KdlDocument> {
         &mut self.children
@@ -485,6 +674,12 @@ impl From<usize> for NodeKey {
     }
 }
 
+/// Gets the argument at an index.
+///
+/// # Panics
+///
+/// Panics if there's no argument at that index. Use [`KdlNode::get`], or
+/// [`KdlNode::arg_or`] for a fallback, when it might be missing.
 impl Index<usize> for KdlNode {
     type Output = KdlValue;
 
@@ -493,12 +688,24 @@ impl Index<usize> for KdlNode {
     }
 }
 
+/// Gets a mutable reference to the argument at an index.
+///
+/// # Panics
+///
+/// Panics if there's no argument at that index. Use [`KdlNode::get_mut`]
+/// when it might be missing.
 impl IndexMut<usize> for KdlNode {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         self.get_mut(index).expect("Argument out of range.")
     }
 }
 
+/// Gets the (last) property with a name.
+///
+/// # Panics
+///
+/// Panics if there's no property with that name. Use [`KdlNode::get`], or
+/// [`KdlNode::get_or`] for a fallback, when it might be missing.
 impl Index<&str> for KdlNode {
     type Output = KdlValue;
 
@@ -507,6 +714,8 @@ impl Index<&str> for KdlNode {
     }
 }
 
+/// Gets a mutable reference to the (last) property with a name, adding it
+/// with a `null` value first if there isn't one. This never panics.
 impl IndexMut<&str> for KdlNode {
     fn index_mut(&mut self, key: &str) -> &mut Self::Output {
         if self.get(key).is_none() {
@@ -516,6 +725,33 @@ impl IndexMut<&str> for KdlNode {
     }
 }
 
+impl IntoIterator for KdlNode {
+    type Item = KdlEntry;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_vec().into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a KdlNode {
+    type Item = &'a KdlEntry;
+    type IntoIter = std::slice::Iter<'a, KdlEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut KdlNode {
+    type Item = &'a mut KdlEntry;
+    type IntoIter = std::slice::IterMut<'a, KdlEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 impl FromStr for KdlNode {
     type Err = KdlError;
 
@@ -527,14 +763,34 @@ impl FromStr for KdlNode {
 
 impl Display for KdlNode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.stringify(f, 0)
+        self.stringify(f, 0, self.version)
     }
 }
 
 impl KdlNode {
     pub(crate) fn fmt_impl(&mut self, indent: usize, no_comments: bool) {
-        if let Some(s) = self.leading.as_mut() {
-            crate::fmt::fmt_leading(s, indent, no_comments);
+        if no_comments {
+            self.disabled.clear();
+        }
+        for node in &mut self.disabled {
+            node.fmt_impl(indent, no_comments);
+        }
+        if let Some(s) = self.slashdash.as_mut() {
+            // The indentation goes before the slashdash instead, and
+            // whatever's between the slashdash and the node stays as it is.
+            if s.is_empty() {
+                *s = format!("{:indent$}", "", indent = indent);
+            } else {
+                crate::fmt::fmt_leading(s, indent, no_comments);
+            }
+            self.leading.get_or_insert_with(String::new);
+        } else {
+            match self.leading.as_mut() {
+                // Nodes that followed a `;` on the same line still need indenting.
+                Some(s) if s.is_empty() => *s = format!("{:indent$}", "", indent = indent),
+                Some(s) => crate::fmt::fmt_leading(s, indent, no_comments),
+                None => {}
+            }
         }
         if let Some(s) = self.trailing.as_mut() {
             crate::fmt::fmt_trailing(s, no_comments);
@@ -567,26 +823,54 @@ impl KdlNode {
         }
     }
 
+    /// This node as it's written in a document of the given version. See
+    /// [`KdlNode::stringify`].
+    pub(crate) fn to_string_in(&self, version: KdlVersion) -> String {
+        struct InVersion<'a>(&'a KdlNode, KdlVersion);
+        impl Display for InVersion<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.stringify(f, 0, self.1)
+            }
+        }
+        InVersion(self, version).to_string()
+    }
+
+    /// Writes this node out the way a document of the given version would.
+    /// [`Display`] writes it in the version it was parsed as.
     pub(crate) fn stringify(
         &self,
         f: &mut std::fmt::Formatter<'_>,
         indent: usize,
+        version: KdlVersion,
     ) -> std::fmt::Result {
+        if let Some(slashdash) = &self.slashdash {
+            write!(f, "{}", slashdash)?;
+            if self.leading.is_none() {
+                write!(f, "{:indent$}", "", indent = indent)?;
+            }
+            write!(f, "/-")?;
+        }
+        for node in &self.disabled {
+            node.stringify(f, indent, version)?;
+            if node.trailing.is_none() {
+                writeln!(f)?;
+            }
+        }
         if let Some(leading) = &self.leading {
             write!(f, "{}", leading)?;
-        } else {
+        } else if self.slashdash.is_none() {
             write!(f, "{:indent$}", "", indent = indent)?;
         }
         if let Some(ty) = &self.ty {
-            write!(f, "({})", ty)?;
+            write!(f, "({})", ty.repr_in(version))?;
         }
-        write!(f, "{}", self.name)?;
+        write!(f, "{}", self.name.repr_in(version))?;
         let mut space_before_children = true;
         for entry in &self.entries {
             if entry.leading.is_none() {
                 write!(f, " ")?;
             }
-            write!(f, "{}", entry)?;
+            entry.stringify(f, version)?;
             space_before_children = entry.trailing.is_none();
         }
         if let Some(children) = &self.children {
@@ -599,7 +883,7 @@ impl KdlNode {
             if children.leading.is_none() {
                 writeln!(f)?;
             }
-            children.stringify(f, indent + 4)?;
+            children.stringify(f, indent + 4, version)?;
             if children.trailing.is_none() {
                 write!(f, "{:indent$}", "", indent = indent)?;
             }
@@ -612,10 +896,174 @@ impl KdlNode {
     }
 }
 
+/// The length of `nodes` when they're written out one after another, the
+/// way a document writes its nodes.
+#[cfg(feature = "span")]
+pub(crate) fn written_len(nodes: &[KdlNode], version: KdlVersion) -> usize {
+    nodes
+        .iter()
+        .map(|node| node.to_string_in(version).len() + node.trailing.as_ref().map_or(1, |_| 0))
+        .sum()
+}
+
+#[cfg(feature = "span")]
+impl KdlNode {
+    /// Mirrors [`KdlNode::stringify`], assigning spans as if the output
+    /// started at `offset`. Returns the offset just past this node.
+    pub(crate) fn recompute_spans_at(
+        &mut self,
+        mut offset: usize,
+        indent: usize,
+        version: KdlVersion,
+    ) -> usize {
+        if let Some(slashdash) = &self.slashdash {
+            offset += slashdash.len() + "/-".len();
+            if self.leading.is_none() {
+                offset += indent;
+            }
+        }
+        for node in &mut self.disabled {
+            offset = node.recompute_spans_at(offset, indent, version);
+            if node.trailing.is_none() {
+                offset += "\n".len();
+            }
+        }
+        offset += match &self.leading {
+            Some(leading) => leading.len(),
+            None if self.slashdash.is_some() => 0,
+            None => indent,
+        };
+        let start = offset;
+        if let Some(ty) = &mut self.ty {
+            offset = ty.recompute_span_at(offset + "(".len(), version) + ")".len();
+            self.ty_span = SourceSpan::from(start..offset);
+        }
+        offset = self.name.recompute_span_at(offset, version);
+        let mut space_before_children = true;
+        for entry in &mut self.entries {
+            if entry.leading.is_none() {
+                offset += " ".len();
+            }
+            offset = entry.recompute_spans_at(offset, version);
+            space_before_children = entry.trailing.is_none();
+        }
+        if let Some(children) = &mut self.children {
+            if let Some(before) = &self.before_children {
+                offset += before.len();
+            } else if space_before_children {
+                offset += " ".len();
+            }
+            offset += "{".len();
+            let children_start = offset;
+            if children.leading.is_none() {
+                offset += "\n".len();
+            }
+            offset = children.recompute_spans_at(offset, indent + 4, version);
+            // The children block's span covers everything inside the braces,
+            // including the newline we add when there's no leading text.
+            let end = children.span.offset() + children.span.len();
+            children.span = SourceSpan::from(children_start..end);
+            if children.trailing.is_none() {
+                offset += indent;
+            }
+            offset += "}".len();
+        }
+        self.span = SourceSpan::from(start..offset);
+        if let Some(trailing) = &self.trailing {
+            offset += trailing.len();
+        }
+        offset
+    }
+
+    /// Moves the spans of this node and everything in it by `delta` bytes.
+    pub(crate) fn shift_spans(&mut self, delta: isize) {
+        for node in &mut self.disabled {
+            node.shift_spans(delta);
+        }
+        self.span = crate::identifier::shifted(self.span, delta);
+        self.ty_span = crate::identifier::shifted(self.ty_span, delta);
+        if let Some(ty) = &mut self.ty {
+            ty.shift_span(delta);
+        }
+        self.name.shift_span(delta);
+        for entry in &mut self.entries {
+            entry.shift_spans(delta);
+        }
+        if let Some(children) = &mut self.children {
+            children.shift_spans(delta);
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn args_and_props() -> miette::Result<()> {
+        let mut node: KdlNode = "node 1 a=2 3 b=4 a=5".parse()?;
+        assert_eq!(
+            node.args().collect::<Vec<_>>(),
+            vec![&KdlValue::Base10(1), &KdlValue::Base10(3)]
+        );
+        let props: Vec<_> = node
+            .props()
+            .map(|(k, v)| (k, v.as_i64().unwrap()))
+            .collect();
+        assert_eq!(props, vec![("a", 2), ("b", 4), ("a", 5)]);
+
+        for value in node.args_mut() {
+            *value = KdlValue::Null;
+        }
+        for (name, value) in node.props_mut() {
+            if name == "b" {
+                *value = KdlValue::Bool(true);
+            }
+        }
+        node.clear_fmt_recursive();
+        assert_eq!(node.to_string(), "node null a=2 null b=true a=5");
+        assert_eq!(KdlNode::new("empty").props().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn dash_values() -> miette::Result<()> {
+        let node = KdlNode::from_dash_values("list", vec![KdlValue::from(1), "two".into()]);
+        let reparsed: KdlNode = node.to_string().parse()?;
+        let values: Vec<_> = reparsed.dash_values().cloned().collect();
+        assert_eq!(
+            values,
+            vec![KdlValue::Base10(1), KdlValue::String("two".into())]
+        );
+
+        let node: KdlNode = "list { - 1; -; other 2; - 3 4; }".parse()?;
+        assert_eq!(
+            node.dash_values().collect::<Vec<_>>(),
+            vec![&KdlValue::Base10(1), &KdlValue::Base10(3)]
+        );
+        assert_eq!(KdlNode::new("empty").dash_values().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn iteration() -> miette::Result<()> {
+        let mut node: KdlNode = "node 1 a=2 3".parse()?;
+        let args: Vec<_> = (&node)
+            .into_iter()
+            .filter(|e| e.name().is_none())
+            .map(|e| e.value().clone())
+            .collect();
+        assert_eq!(args, vec![KdlValue::Base10(1), KdlValue::Base10(3)]);
+        node.iter_mut().for_each(|e| e.set_leading("  "));
+        assert_eq!(node.to_string(), "node  1  a=2  3");
+        let names: Vec<_> = node
+            .into_iter()
+            .map(|e| e.name().map(|n| n.value().to_string()))
+            .collect();
+        assert_eq!(names, vec![None, Some("a".to_string()), None]);
+        Ok(())
+    }
+
     #[test]
     fn canonical_clear_fmt() -> miette::Result<()> {
         let mut left_node: KdlNode = r#"node /-"commented" param_name=103.000 {
@@ -668,6 +1116,29 @@ mod test {
         assert_eq!(&node["x"], &2.into())
     }
 
+    #[test]
+    fn many_properties() {
+        let mut node = KdlNode::new("generated");
+        for i in 0..100 {
+            node.push(KdlEntry::new_prop(format!("p{}", i), i));
+        }
+        node.push(KdlEntry::new_prop("p7", "again"));
+        assert_eq!(node.get("p42"), Some(&42.into()));
+        assert_eq!(node.get("p7"), Some(&"again".into()));
+        assert_eq!(node.get("p100"), None);
+
+        *node.get_mut("p42").unwrap() = 0.into();
+        assert_eq!(node.get("p42"), Some(&0.into()));
+        node.remove("p42");
+        assert_eq!(node.get("p42"), None);
+        assert_eq!(node.get("p43"), Some(&43.into()));
+        node.entries_mut()[0].name = Some("renamed".into());
+        assert_eq!(node.get("p0"), None);
+        assert_eq!(node.get("renamed"), Some(&0.into()));
+        node.insert("p0", 1);
+        assert_eq!(node.get("p0"), Some(&1.into()));
+    }
+
     #[test]
     fn insertion() {
         let mut node = KdlNode::new("foo");
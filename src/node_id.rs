@@ -0,0 +1,195 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{KdlDocument, KdlNode};
+
+/// An identifier for a [`KdlNode`], assigned when the node is created or
+/// parsed and returned by [`KdlNode::id`].
+///
+/// IDs are unique within the process, so no two nodes in a document share
+/// one unless a node was cloned: clones keep the original's ID, which means
+/// a cloned document's nodes can be matched up with the ones they came from.
+/// IDs belong to the node itself rather than its position, so they stay the
+/// same when nodes are moved around, renamed, or have their contents
+/// changed. They're not part of a node's equality or hash, and they aren't
+/// written out with it.
+///
+/// Use [`KdlDocument::get_by_id`] to look a node up by its ID, and
+/// [`KdlDocument::path_of`], [`KdlDocument::parent_of`], and
+/// [`KdlDocument::siblings_of`] to find out where it is in a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KdlNodeId(usize);
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+impl KdlNodeId {
+    pub(crate) fn next() -> Self {
+        KdlNodeId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl KdlDocument {
+    /// Gets the node with the given ID, wherever it is in this document
+    /// (including in children), or `None` if there's no such node. If
+    /// there's more than one, because of cloning, the first one in document
+    /// order is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let mut doc: KdlDocument = "a { b 1; }\nc".parse().unwrap();
+    /// let b = doc.nodes()[0].children().unwrap().nodes()[0].id();
+    ///
+    /// let mut children = doc.nodes_mut()[0].children_mut().take().unwrap();
+    /// doc.nodes_mut().push(children.nodes_mut().remove(0));
+    /// assert_eq!(doc.path_of(b), Some(vec![2]));
+    /// assert_eq!(doc.get_by_id(b).unwrap().get(0), Some(&1.into()));
+    /// ```
+    pub fn get_by_id(&self, id: KdlNodeId) -> Option<&KdlNode> {
+        self.descendants().find(|node| node.id() == id)
+    }
+
+    /// Gets a mutable reference to the node with the given ID. See
+    /// [`KdlDocument::get_by_id`].
+    pub fn get_by_id_mut(&mut self, id: KdlNodeId) -> Option<&mut KdlNode> {
+        let path = self.path_of(id)?;
+        let (last, parents) = path.split_last()?;
+        // Going through `node_mut` keeps the name index, which
+        // `nodes_mut` would throw away.
+        let mut doc = self;
+        for &idx in parents {
+            doc = doc.nodes.node_mut(idx).children_mut().as_mut()?;
+        }
+        Some(doc.nodes.node_mut(*last))
+    }
+
+    /// Finds the node with the given ID, returning its path: its index in
+    /// this document, followed by its index in each children block on the
+    /// way down. The path can be passed to [`KdlDocument::cursor_at`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let mut doc: KdlDocument = "a { b; c; }\nd".parse().unwrap();
+    /// let c = doc.nodes()[0].children().unwrap().nodes()[1].id();
+    /// assert_eq!(doc.path_of(c), Some(vec![0, 1]));
+    ///
+    /// doc.nodes_mut()[0].children_mut().as_mut().unwrap().nodes_mut().remove(0);
+    /// assert_eq!(doc.path_of(c), Some(vec![0, 0]));
+    /// assert_eq!(doc.parent_of(c).unwrap().name().value(), "a");
+    /// ```
+    pub fn path_of(&self, id: KdlNodeId) -> Option<Vec<usize>> {
+        let mut path = Vec::new();
+        find_path(self, id, &mut path).then(|| path)
+    }
+
+    /// Gets the parent of the node with the given ID. Returns `None` if
+    /// there's no such node, or if it's one of this document's top-level
+    /// nodes.
+    pub fn parent_of(&self, id: KdlNodeId) -> Option<&KdlNode> {
+        let path = self.path_of(id)?;
+        let cursor = self.cursor_at(&path[..path.len() - 1])?;
+        Some(cursor.node())
+    }
+
+    /// Gets the nodes in the same document as the node with the given ID,
+    /// including the node itself, or `None` if there's no such node.
+    pub fn siblings_of(&self, id: KdlNodeId) -> Option<&[KdlNode]> {
+        let path = self.path_of(id)?;
+        Some(self.cursor_at(&path)?.siblings())
+    }
+}
+
+fn find_path(doc: &KdlDocument, id: KdlNodeId, path: &mut Vec<usize>) -> bool {
+    for (i, node) in doc.nodes().iter().enumerate() {
+        path.push(i);
+        if node.id() == id {
+            return true;
+        }
+        if let Some(children) = node.children() {
+            if find_path(children, id, path) {
+                return true;
+            }
+        }
+        path.pop();
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ids() -> miette::Result<()> {
+        let mut doc: KdlDocument = "a { b; c { d; }; }\ne".parse()?;
+        let ids: Vec<_> = doc.descendants().map(|n| n.id()).collect();
+        let mut unique = ids.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), ids.len());
+        let (a, b, c, d, e) = (ids[0], ids[1], ids[2], ids[3], ids[4]);
+
+        assert_eq!(doc.path_of(d), Some(vec![0, 1, 0]));
+        assert_eq!(doc.parent_of(d).map(|n| n.id()), Some(c));
+        assert_eq!(doc.parent_of(a), None);
+        let siblings: Vec<_> = doc.siblings_of(c).unwrap().iter().map(|n| n.id()).collect();
+        assert_eq!(siblings, vec![b, c]);
+        assert_eq!(doc.siblings_of(e).unwrap().len(), 2);
+
+        // Moving `e` under `b` and renaming it keeps its ID.
+        let mut moved = doc.nodes_mut().remove(1);
+        moved.set_name("moved");
+        doc.nodes_mut()[0]
+            .children_mut()
+            .as_mut()
+            .unwrap()
+            .nodes_mut()[0]
+            .ensure_children()
+            .nodes_mut()
+            .push(moved);
+        assert_eq!(doc.path_of(e), Some(vec![0, 0, 0]));
+        assert_eq!(doc.parent_of(e).map(|n| n.id()), Some(b));
+
+        assert_eq!(doc.get_by_id(e).unwrap().name().value(), "moved");
+        doc.get_by_id_mut(d).unwrap().set_name("renamed");
+        assert_eq!(doc.get_by_id(d).unwrap().name().value(), "renamed");
+        assert_eq!(doc.path_of(d), Some(vec![0, 1, 0]));
+
+        let clone = doc.clone();
+        assert_eq!(clone.path_of(e), Some(vec![0, 0, 0]));
+        assert_eq!(clone.get_by_id(d), doc.get_by_id(d));
+        assert_eq!(KdlDocument::new().path_of(e), None);
+        assert_eq!(KdlDocument::new().siblings_of(e), None);
+        let fresh = KdlNode::new("fresh");
+        assert!(!ids.contains(&fresh.id()));
+        assert_eq!(doc.path_of(fresh.id()), None);
+        assert!(doc.get_by_id(fresh.id()).is_none());
+        assert!(doc.get_by_id_mut(fresh.id()).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn get_by_id_mut_keeps_name_index() -> miette::Result<()> {
+        let mut doc: KdlDocument = "a { b; }
+c
+d"
+        .parse()?;
+        doc.set_name_index(true);
+        let (b, c) = (
+            doc.nodes()[0].children().unwrap().nodes()[0].id(),
+            doc.nodes()[1].id(),
+        );
+        assert!(doc.get("c").is_some());
+        assert!(doc.nodes.has_built_index());
+
+        doc.get_by_id_mut(b).unwrap().push(1);
+        doc.get_by_id_mut(c).unwrap().set_name("e");
+        assert!(doc.nodes.has_built_index());
+        assert!(doc.get("c").is_none());
+        assert_eq!(doc.get("e").map(|n| n.id()), Some(c));
+        assert_eq!(doc.get_by_id(b).unwrap().get(0), Some(&1.into()));
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{KdlDocument, KdlNode, KdlNodeId};
+
+/// Data of your own attached to nodes, kept alongside a document rather
+/// than in it, such as the results of an analysis, or where each node came
+/// from.
+///
+/// Values are keyed by [`KdlNodeId`], so they stay attached to their nodes
+/// while the document is traversed and edited: moving, renaming, or
+/// changing a node doesn't change its ID. Since clones keep their
+/// original's IDs, values also carry over to a cloned document. Removing a
+/// node from the document doesn't remove its value, though, so use
+/// [`KdlNodeMap::retain_in`] to tidy up after removing nodes.
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::{KdlDocument, KdlNodeMap};
+/// let mut doc: KdlDocument = "a 1\nb 2\n".parse().unwrap();
+/// let mut checked = KdlNodeMap::new();
+/// for node in doc.nodes() {
+///     checked.insert(node.id(), node.name().value() == "a");
+/// }
+///
+/// let b = doc.nodes_mut().remove(1);
+/// doc.nodes_mut().insert(0, b);
+/// let found: Vec<_> = checked
+///     .nodes(&doc)
+///     .map(|(node, ok)| (node.name().value(), *ok))
+///     .collect();
+/// assert_eq!(found, vec![("b", false), ("a", true)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct KdlNodeMap<T> {
+    values: HashMap<KdlNodeId, T>,
+}
+
+impl<T> Default for KdlNodeMap<T> {
+    fn default() -> Self {
+        Self {
+            values: HashMap::new(),
+        }
+    }
+}
+
+impl<T> KdlNodeMap<T> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Attaches `value` to the node with the given ID, returning the value
+    /// it had before, if any.
+    pub fn insert(&mut self, id: KdlNodeId, value: T) -> Option<T> {
+        self.values.insert(id, value)
+    }
+
+    /// Gets the value attached to the node with the given ID.
+    pub fn get(&self, id: KdlNodeId) -> Option<&T> {
+        self.values.get(&id)
+    }
+
+    /// Gets a mutable reference to the value attached to the node with the
+    /// given ID.
+    pub fn get_mut(&mut self, id: KdlNodeId) -> Option<&mut T> {
+        self.values.get_mut(&id)
+    }
+
+    /// Gets the value attached to the node with the given ID, attaching one
+    /// made with `f` first if there isn't one yet.
+    pub fn get_or_insert_with(&mut self, id: KdlNodeId, f: impl FnOnce() -> T) -> &mut T {
+        self.values.entry(id).or_insert_with(f)
+    }
+
+    /// Removes the value attached to the node with the given ID, returning
+    /// it.
+    pub fn remove(&mut self, id: KdlNodeId) -> Option<T> {
+        self.values.remove(&id)
+    }
+
+    /// Whether there's a value attached to the node with the given ID.
+    pub fn contains(&self, id: KdlNodeId) -> bool {
+        self.values.contains_key(&id)
+    }
+
+    /// Gets the number of values in this map.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if this map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Removes all values from this map.
+    pub fn clear(&mut self) {
+        self.values.clear()
+    }
+
+    /// Iterates over the IDs and values in this map, in no particular
+    /// order. Use [`KdlNodeMap::nodes`] to go through them in document
+    /// order instead.
+    pub fn iter(&self) -> impl Iterator<Item = (KdlNodeId, &T)> {
+        self.values.iter().map(|(id, value)| (*id, value))
+    }
+
+    /// Iterates over the nodes in `doc` (including children) that have a
+    /// value in this map, in document order, along with their values.
+    pub fn nodes<'a>(
+        &'a self,
+        doc: &'a KdlDocument,
+    ) -> impl Iterator<Item = (&'a KdlNode, &'a T)> + 'a {
+        doc.descendants()
+            .filter_map(move |node| Some((node, self.values.get(&node.id())?)))
+    }
+
+    /// Removes the values for nodes that aren't in `doc` (or any of its
+    /// children) anymore.
+    pub fn retain_in(&mut self, doc: &KdlDocument) {
+        let ids: HashSet<KdlNodeId> = doc.descendants().map(KdlNode::id).collect();
+        self.values.retain(|id, _| ids.contains(id));
+    }
+}
+
+impl<T> FromIterator<(KdlNodeId, T)> for KdlNodeMap<T> {
+    fn from_iter<I: IntoIterator<Item = (KdlNodeId, T)>>(iter: I) -> Self {
+        Self {
+            values: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> Extend<(KdlNodeId, T)> for KdlNodeMap<T> {
+    fn extend<I: IntoIterator<Item = (KdlNodeId, T)>>(&mut self, iter: I) {
+        self.values.extend(iter)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn survives_edits() -> miette::Result<()> {
+        let mut doc: KdlDocument = "a { b; c; }\nd".parse()?;
+        let mut depths = KdlNodeMap::new();
+        for node in doc.descendants() {
+            let depth = doc.path_of(node.id()).unwrap().len();
+            depths.insert(node.id(), depth);
+        }
+        assert_eq!(depths.len(), 4);
+
+        // Move `c` to the top level and rename it.
+        let mut c = doc.nodes_mut()[0]
+            .children_mut()
+            .as_mut()
+            .unwrap()
+            .nodes_mut()
+            .remove(1);
+        c.set_name("moved");
+        let c_id = c.id();
+        doc.nodes_mut().push(c);
+        assert_eq!(depths.get(c_id), Some(&2));
+        *depths.get_or_insert_with(c_id, || 0) = 1;
+
+        let clone = doc.clone();
+        let found: Vec<_> = depths
+            .nodes(&clone)
+            .map(|(node, depth)| (node.name().value(), *depth))
+            .collect();
+        assert_eq!(found, vec![("a", 1), ("b", 2), ("d", 1), ("moved", 1)]);
+
+        doc.nodes_mut().remove(0);
+        depths.retain_in(&doc);
+        assert_eq!(depths.len(), 2);
+        assert!(depths.contains(c_id));
+        assert_eq!(depths.remove(c_id), Some(1));
+        assert!(!depths.contains(c_id));
+        Ok(())
+    }
+}
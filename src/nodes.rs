@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut},
+};
+
+use crate::{util::OnceBox, KdlNode};
+
+/// A document's nodes, along with an optional index of their positions by
+/// name, for documents with so many nodes that searching them for a name
+/// is too slow. See [`KdlDocument::set_name_index`](crate::KdlDocument::set_name_index).
+///
+/// Like the property index in `KdlEntries`, this one is built on the first
+/// lookup, without a lock, and thrown away whenever the nodes are borrowed
+/// mutably. The one exception is a single node handed out through
+/// [`KdlNodes::node_mut`], which is only checked the next time the nodes
+/// are changed, so that looking up and changing one node after another
+/// doesn't rebuild the index every time.
+#[derive(Default)]
+pub(crate) struct KdlNodes {
+    nodes: Vec<KdlNode>,
+    /// `None` unless the index is turned on.
+    index: Option<Box<NameIndex>>,
+}
+
+#[derive(Default)]
+struct NameIndex {
+    /// The positions of the nodes with each name, in order.
+    names: OnceBox<HashMap<String, Vec<usize>>>,
+    /// The node last handed out by [`KdlNodes::node_mut`] while the index
+    /// was built, which may have been renamed since.
+    changed: Option<usize>,
+}
+
+impl NameIndex {
+    /// The index, unless it's out of date.
+    fn names(&self, nodes: &[KdlNode]) -> Option<&HashMap<String, Vec<usize>>> {
+        if let (Some(names), Some(idx)) = (self.names.get(), self.changed) {
+            let indexed = names
+                .get(nodes[idx].name().value())
+                .map_or(false, |positions| positions.binary_search(&idx).is_ok());
+            if !indexed {
+                return None;
+            }
+        }
+        Some(self.names.get_or_init(|| {
+            let mut names: HashMap<String, Vec<usize>> = HashMap::new();
+            for (idx, node) in nodes.iter().enumerate() {
+                names
+                    .entry(node.name().value().to_string())
+                    .or_default()
+                    .push(idx);
+            }
+            names
+        }))
+    }
+
+    /// Drops the index if it's out of date, since nothing can be changing
+    /// the nodes anymore.
+    fn settle(&mut self, nodes: &[KdlNode]) {
+        if self.names(nodes).is_none() {
+            self.names.clear();
+        }
+        self.changed = None;
+    }
+}
+
+impl KdlNodes {
+    pub(crate) fn set_indexed(&mut self, indexed: bool) {
+        if indexed != self.is_indexed() {
+            self.index = if indexed { Some(Box::default()) } else { None };
+        }
+    }
+
+    pub(crate) fn is_indexed(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// Finds the positions of the nodes named `name`, in order, if the index
+    /// is turned on.
+    pub(crate) fn positions(&self, name: &str) -> Option<Vec<usize>> {
+        let index = self.index.as_ref()?;
+        let positions = match index.names(&self.nodes) {
+            Some(names) => names.get(name).cloned().unwrap_or_default(),
+            None => self
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| node.name().value() == name)
+                .map(|(idx, _)| idx)
+                .collect(),
+        };
+        Some(positions)
+    }
+
+    /// Finds the position of the first node named `name`.
+    pub(crate) fn position(&self, name: &str) -> Option<usize> {
+        match self
+            .index
+            .as_ref()
+            .and_then(|index| index.names(&self.nodes))
+        {
+            Some(names) => names.get(name)?.first().copied(),
+            None => self.nodes.iter().position(|n| n.name().value() == name),
+        }
+    }
+
+    /// Gets a mutable reference to the node at `idx`, keeping the index
+    /// unless the node turns out to have been renamed.
+    pub(crate) fn node_mut(&mut self, idx: usize) -> &mut KdlNode {
+        if let Some(index) = &mut self.index {
+            index.settle(&self.nodes);
+            if index.names.get().is_some() {
+                index.changed = Some(idx);
+            }
+        }
+        &mut self.nodes[idx]
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<KdlNode> {
+        self.nodes
+    }
+
+    #[cfg(test)]
+    pub(crate) fn has_built_index(&self) -> bool {
+        self.index
+            .as_ref()
+            .map_or(false, |index| index.names.get().is_some())
+    }
+}
+
+impl Deref for KdlNodes {
+    type Target = Vec<KdlNode>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.nodes
+    }
+}
+
+impl DerefMut for KdlNodes {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if let Some(index) = &mut self.index {
+            **index = NameIndex::default();
+        }
+        &mut self.nodes
+    }
+}
+
+impl From<Vec<KdlNode>> for KdlNodes {
+    fn from(nodes: Vec<KdlNode>) -> Self {
+        Self { nodes, index: None }
+    }
+}
+
+impl<'a> IntoIterator for &'a KdlNodes {
+    type Item = &'a KdlNode;
+    type IntoIter = std::slice::Iter<'a, KdlNode>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut KdlNodes {
+    type Item = &'a mut KdlNode;
+    type IntoIter = std::slice::IterMut<'a, KdlNode>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl Clone for KdlNodes {
+    fn clone(&self) -> Self {
+        let mut nodes: KdlNodes = self.nodes.clone().into();
+        nodes.set_indexed(self.is_indexed());
+        nodes
+    }
+}
+
+impl PartialEq for KdlNodes {
+    fn eq(&self, other: &Self) -> bool {
+        self.nodes == other.nodes
+    }
+}
+
+impl Eq for KdlNodes {}
+
+impl Hash for KdlNodes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.nodes.hash(state);
+    }
+}
+
+impl fmt::Debug for KdlNodes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.nodes.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn nodes(names: &[&str]) -> KdlNodes {
+        let mut nodes: KdlNodes = names
+            .iter()
+            .map(|name| KdlNode::new(*name))
+            .collect::<Vec<_>>()
+            .into();
+        nodes.set_indexed(true);
+        nodes
+    }
+
+    #[test]
+    fn lookups() {
+        let mut nodes = nodes(&["a", "b", "a", "c"]);
+        assert_eq!(nodes.position("a"), Some(0));
+        assert_eq!(nodes.positions("a"), Some(vec![0, 2]));
+        assert_eq!(nodes.position("d"), None);
+        assert_eq!(nodes.positions("d"), Some(vec![]));
+
+        // Changing a node without renaming it keeps the index.
+        nodes.node_mut(1).push(1);
+        assert_eq!(nodes.position("b"), Some(1));
+        nodes.node_mut(0).set_name("d");
+        assert_eq!(nodes.position("a"), Some(2));
+        assert_eq!(nodes.position("d"), Some(0));
+        assert_eq!(nodes.positions("a"), Some(vec![2]));
+        nodes.node_mut(3);
+        assert_eq!(nodes.positions("a"), Some(vec![2]));
+        assert_eq!(nodes.positions("d"), Some(vec![0]));
+
+        nodes.remove(0);
+        assert_eq!(nodes.positions("a"), Some(vec![1]));
+        assert_eq!(nodes.position("c"), Some(2));
+
+        nodes.set_indexed(false);
+        assert_eq!(nodes.positions("a"), None);
+        assert_eq!(nodes.position("a"), Some(1));
+    }
+}
@@ -0,0 +1,348 @@
+/// Options for controlling how a KDL document is parsed.
+///
+/// Use [`ParseOptions::default`] and override the fields you care about,
+/// then pass it to [`KdlDocument::parse_with_options`](crate::KdlDocument::parse_with_options).
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::{KdlDocument, ParseOptions};
+/// let options = ParseOptions {
+///     source_name: Some("config/app.kdl".into()),
+///     ..ParseOptions::default()
+/// };
+/// let doc = KdlDocument::parse_with_options("foo 1", &options).unwrap();
+/// assert_eq!(doc.get_arg("foo"), Some(&1.into()));
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ParseOptions {
+    /// Name of the input, usually a file path. Errors produced while parsing
+    /// will carry this name so reports can point at `name:line:column`.
+    pub source_name: Option<String>,
+
+    /// What to do with integer literals too large to fit in an `i128`.
+    /// Defaults to [`IntegerOverflow::Error`].
+    pub integer_overflow: IntegerOverflow,
+
+    /// Which version of the KDL spec to parse the input as. Defaults to
+    /// [`KdlVersion::V1`].
+    pub version: KdlVersion,
+
+    /// Which leniencies beyond the spec to allow. Defaults to none of them.
+    pub compat: CompatMode,
+}
+
+/// Leniencies beyond the KDL spec that [`ParseOptions`] can allow, for
+/// reading documents written for (or by) other KDL implementations that
+/// accept more than the spec does.
+///
+/// Every flag defaults to `false`, which follows the spec.
+/// [`CompatMode::lenient`] turns all of them on.
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::{CompatMode, KdlDocument, ParseOptions};
+/// let options = ParseOptions {
+///     compat: CompatMode {
+///         digit_identifiers: true,
+///         ..CompatMode::default()
+///     },
+///     ..ParseOptions::default()
+/// };
+/// let doc = KdlDocument::parse_with_options("1st-place \"gold\"", &options).unwrap();
+/// assert!(doc.get("1st-place").is_some());
+/// assert!("1st-place \"gold\"".parse::<KdlDocument>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct CompatMode {
+    /// Treat a byte order mark (`U+FEFF`) anywhere in a KDL 2.0 document as
+    /// whitespace, the way KDL 1.0 does, instead of only allowing one at
+    /// the very start.
+    pub byte_order_marks: bool,
+
+    /// Allow bare node names and property keys that start with a digit, or
+    /// look like a number in some other way, such as `1st` or `-2x`.
+    /// Arguments are always read as numbers.
+    pub digit_identifiers: bool,
+
+    /// Only treat `\r\n`, `\n`, and `\r` as line breaks between nodes.
+    /// The other Unicode line breaks (`U+0085`, `U+000C`, `U+2028`,
+    /// `U+2029`, and `U+000B` in KDL 2.0) separate entries like spaces do,
+    /// instead of ending the node.
+    pub ascii_newlines: bool,
+
+    /// Accept KDL 1.0's `r"raw"` and `r#"raw"#` strings in KDL 2.0
+    /// documents, alongside 2.0's `#"raw"#`.
+    pub legacy_raw_strings: bool,
+}
+
+impl CompatMode {
+    /// Allows every leniency.
+    pub fn lenient() -> Self {
+        CompatMode {
+            byte_order_marks: true,
+            digit_identifiers: true,
+            ascii_newlines: true,
+            legacy_raw_strings: true,
+        }
+    }
+}
+
+/// A version of the [KDL spec](https://github.com/kdl-org/kdl/blob/main/SPEC.md).
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::{KdlDocument, KdlVersion, ParseOptions};
+/// let options = ParseOptions {
+///     version: KdlVersion::V2,
+///     ..ParseOptions::default()
+/// };
+/// let doc = KdlDocument::parse_with_options("server enabled=#true port = 8080", &options).unwrap();
+/// assert_eq!(doc.get("server").unwrap().get("enabled"), Some(&true.into()));
+/// assert_eq!(doc.get("server").unwrap().get("port"), Some(&8080.into()));
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum KdlVersion {
+    /// [KDL 1.0](https://github.com/kdl-org/kdl/blob/1.0.0/SPEC.md), with
+    /// bare `true`, `false`, and `null` keywords and `r"raw"` strings.
+    V1,
+
+    /// [KDL 2.0](https://github.com/kdl-org/kdl/blob/2.0.0/SPEC.md). The
+    /// differences from 1.0 that this parser understands are:
+    ///
+    /// * Keywords start with `#`: `#true`, `#false`, `#null`, plus `#inf`,
+    ///   `#-inf`, and `#nan` for non-finite floats. The bare words (and
+    ///   `inf`, `-inf`, and `nan`) can't be used as identifiers.
+    /// * Bare identifiers can be used as string values, as in `color red`.
+    ///   Identifiers may contain `<`, `>`, and `,`, but not `#`, and can't
+    ///   start with something that looks like a number, such as `-1` or `.5`.
+    /// * Raw strings are written `#"like this"#`, without the `r`.
+    /// * Quoted strings drop the `\/` escape, and add `\s` for a space and
+    ///   `\` followed by whitespace, which skips that whitespace.
+    /// * Strings can only span lines in the `"""` multi-line form (or
+    ///   `#"""` for raw ones), which starts with a newline and ends with its
+    ///   closing quotes on their own line. The whitespace before the closing
+    ///   quotes is removed from every line.
+    /// * Whitespace is allowed around a property's `=`, inside type
+    ///   annotations, and between a type annotation and what it annotates.
+    ///   It's dropped when the document is printed back out.
+    /// * Vertical tab (`U+000B`) counts as a newline.
+    V2,
+}
+
+impl Default for KdlVersion {
+    fn default() -> Self {
+        KdlVersion::V1
+    }
+}
+
+/// How [`ParseOptions`] handles integer literals that don't fit in an `i128`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::{IntegerOverflow, KdlDocument, KdlValue, ParseOptions};
+/// let input = "limit 1_000_000_000_000_000_000_000_000_000_000_000_000_000";
+/// let parse = |integer_overflow| {
+///     let options = ParseOptions {
+///         integer_overflow,
+///         ..ParseOptions::default()
+///     };
+///     KdlDocument::parse_with_options(input, &options)
+/// };
+/// assert!(parse(IntegerOverflow::Error).is_err());
+///
+/// let doc = parse(IntegerOverflow::Saturate).unwrap();
+/// assert_eq!(doc.get_arg("limit"), Some(&KdlValue::Base10(i128::MAX)));
+///
+/// let doc = parse(IntegerOverflow::String).unwrap();
+/// assert_eq!(
+///     doc.to_string(),
+///     "limit (bigint)\"1000000000000000000000000000000000000000\""
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum IntegerOverflow {
+    /// Fail to parse, with a [`KdlErrorKind::ParseIntError`](crate::KdlErrorKind::ParseIntError).
+    Error,
+
+    /// Clamp the number to `i128::MAX` or `i128::MIN`. The entry keeps the
+    /// literal as it was written, so the document is still written out with
+    /// the original number.
+    Saturate,
+
+    /// Keep the literal as a [`KdlValue::String`](crate::KdlValue::String),
+    /// without `_` separators but with its sign and radix prefix, like
+    /// `"-0xffff"`. Entries without a type annotation are annotated
+    /// `(bigint)`, so they can still be told apart from strings, and they're
+    /// written out as the annotated strings they've become.
+    String,
+
+    /// Keep the exact number as a [`KdlValue::BigInt`](crate::KdlValue::BigInt).
+    /// Requires the `bigint` feature.
+    #[cfg(feature = "bigint")]
+    BigInt,
+}
+
+impl Default for IntegerOverflow {
+    fn default() -> Self {
+        IntegerOverflow::Error
+    }
+}
+
+/// Options for controlling how a KDL document is written out.
+///
+/// Pass it to [`KdlDocument::to_string_with_options`](crate::KdlDocument::to_string_with_options).
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct EmitOptions {
+    /// What to do with NaN and infinite floats, which KDL 1.0 has no syntax
+    /// for. Defaults to [`NonFiniteFloats::Error`].
+    pub non_finite_floats: NonFiniteFloats,
+
+    /// Whether to write strings that contain newlines in KDL 2.0's
+    /// multi-line form. Defaults to [`MultiLineStrings::Never`].
+    pub multi_line_strings: MultiLineStrings,
+
+    /// Which KDL version to write the document as. Keywords, strings, and
+    /// identifiers that aren't written the way that version needs them are
+    /// rewritten, the same as [`KdlDocument::to_v2`](crate::KdlDocument::to_v2)
+    /// and [`KdlDocument::to_v1`](crate::KdlDocument::to_v1) do, including
+    /// ones that were added or changed after parsing. Defaults to `None`,
+    /// which writes everything as it is.
+    ///
+    /// When writing KDL 2.0, NaN and infinite floats are written as `#nan`,
+    /// `#inf`, and `#-inf`, and `non_finite_floats` is ignored.
+    pub version: Option<KdlVersion>,
+
+    /// Whether to start the output with a `/- kdl-version 1` or
+    /// `/- kdl-version 2` node saying which version it's written in (see
+    /// [`KdlDocument::parse_auto`](crate::KdlDocument::parse_auto)), unless
+    /// the document already starts with one. Defaults to `false`.
+    ///
+    /// An existing marker is always updated when `version` changes the
+    /// document's version.
+    pub version_marker: bool,
+
+    /// Which values to hide, for writing out documents that may hold
+    /// secrets, like passwords in a configuration that's being logged.
+    /// Defaults to `None`, which writes every value.
+    pub redact: Option<Redaction>,
+}
+
+/// Which values [`EmitOptions`] hides. Use
+/// [`KdlDocument::redact`](crate::KdlDocument::redact) directly to pick them
+/// some other way.
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::{EmitOptions, KdlDocument, Redaction};
+/// let doc: KdlDocument = r#"
+/// database url="postgres://db" password="hunter2" {
+///     token (secret)"abc123"
+/// }
+/// "#.parse().unwrap();
+/// let options = EmitOptions {
+///     redact: Some(Redaction {
+///         names: vec!["password".into()],
+///         ..Redaction::default()
+///     }),
+///     ..EmitOptions::default()
+/// };
+/// assert_eq!(
+///     doc.to_string_with_options(&options).unwrap(),
+///     r#"
+/// database url="postgres://db" password="[redacted]" {
+///     token (secret)"[redacted]"
+/// }
+/// "#
+/// );
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Redaction {
+    /// Type annotations that mark a value as secret, as in `(secret)"abc"`,
+    /// or mark every value of a node as secret, as in
+    /// `(secret)token "abc"`. Defaults to `["secret"]`.
+    pub annotations: Vec<String>,
+
+    /// Names of properties whose values are secret, and of nodes whose
+    /// values are all secret, like `password`. Defaults to none.
+    pub names: Vec<String>,
+
+    /// The string that secret values are replaced with. Defaults to
+    /// `"[redacted]"`.
+    pub placeholder: String,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Redaction {
+            annotations: vec!["secret".into()],
+            names: Vec::new(),
+            placeholder: "[redacted]".into(),
+        }
+    }
+}
+
+/// How [`EmitOptions`] writes floats that are NaN or infinite.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum NonFiniteFloats {
+    /// Refuse to write the document, with a
+    /// [`KdlErrorKind::NonFiniteFloat`](crate::KdlErrorKind::NonFiniteFloat)
+    /// error pointing at the first offending value.
+    Error,
+
+    /// Write them the same way [`Display`](std::fmt::Display) does: NaN
+    /// becomes `0.0`, and infinities become the largest finite `f64` of the
+    /// same sign. The output is valid, but the values are lost.
+    Clamp,
+
+    /// Write them as KDL 2.0's `#nan`, `#inf`, and `#-inf` keywords. The
+    /// output can't be read by KDL 1.0 parsers, including this one.
+    Keyword,
+
+    /// Write them as `(f64)"NaN"`, `(f64)"inf"`, and `(f64)"-inf"`, which
+    /// are valid KDL 1.0 and are understood by
+    /// [`KdlEntry::as_f64`](crate::KdlEntry::as_f64).
+    Annotation,
+}
+
+impl Default for NonFiniteFloats {
+    fn default() -> Self {
+        NonFiniteFloats::Error
+    }
+}
+
+/// How [`EmitOptions`] writes strings that contain newlines.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum MultiLineStrings {
+    /// Write them the same way [`Display`](std::fmt::Display) does, on one
+    /// line with `\n` escapes.
+    Never,
+
+    /// Write the ones longer than this many characters as KDL 2.0
+    /// multi-line strings, indented one level past their node:
+    ///
+    /// ```kdl
+    /// script """
+    ///     echo "building"
+    ///     cargo build
+    ///     """
+    /// ```
+    ///
+    /// Strings that were already written across several lines are left as
+    /// they are. The output can only be read with
+    /// [`KdlVersion::V2`](crate::KdlVersion::V2), and only if the rest of
+    /// the document is valid KDL 2.0 too.
+    LongerThan(usize),
+}
+
+impl Default for MultiLineStrings {
+    fn default() -> Self {
+        MultiLineStrings::Never
+    }
+}
@@ -0,0 +1,269 @@
+use miette::SourceSpan;
+
+use crate::{KdlDocument, KdlNode, KdlSemanticTokenKind};
+
+/// A node in a document's outline, as produced by [`KdlDocument::outline`].
+///
+/// This lines up with an LSP `DocumentSymbol`: [`KdlSymbol::span`] is the
+/// symbol's full range and [`KdlSymbol::name_span`] is its selection range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KdlSymbol {
+    /// The node's name.
+    pub name: String,
+
+    /// The span of the whole node, including its children block.
+    pub span: SourceSpan,
+
+    /// The span of just the node's name.
+    pub name_span: SourceSpan,
+
+    /// Outline entries for the node's children, if it has any.
+    pub children: Vec<KdlSymbol>,
+}
+
+/// A foldable region of a document, as produced by
+/// [`KdlDocument::folding_ranges`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KdlFoldingRange {
+    /// What's being folded.
+    pub kind: KdlFoldingRangeKind,
+
+    /// The span of the foldable text.
+    pub span: SourceSpan,
+
+    /// The 0-based line the region starts on.
+    pub start_line: usize,
+
+    /// The 0-based line the region ends on.
+    pub end_line: usize,
+}
+
+/// The kind of region a [`KdlFoldingRange`] covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KdlFoldingRangeKind {
+    /// A node's children block, from its opening to its closing brace.
+    Children,
+    /// A string value that spans multiple lines.
+    String,
+    /// A run of comments with nothing but whitespace between them.
+    Comment,
+}
+
+impl KdlDocument {
+    /// Returns an outline of this document: the name and span of every node,
+    /// nested the same way the nodes are.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = "package {\n    name \"foo\"\n}\ndependencies".parse().unwrap();
+    /// let outline = doc.outline();
+    /// assert_eq!(outline[0].name, "package");
+    /// assert_eq!(outline[0].children[0].name, "name");
+    /// assert_eq!(outline[1].name, "dependencies");
+    /// ```
+    pub fn outline(&self) -> Vec<KdlSymbol> {
+        self.nodes().iter().map(symbol).collect()
+    }
+
+    /// Returns the regions of this document that an editor can fold: children
+    /// blocks, multi-line strings, and runs of comments. Only regions that
+    /// span more than one line are included.
+    ///
+    /// Like the other span-based helpers, this assumes the document's spans
+    /// line up with its [`Display`](std::fmt::Display) output, which is the
+    /// case for freshly parsed documents, or after
+    /// [`KdlDocument::recompute_spans`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlDocument, KdlFoldingRangeKind};
+    /// let doc: KdlDocument = "// one\n// two\nfoo {\n    bar\n}".parse().unwrap();
+    /// let ranges = doc
+    ///     .folding_ranges()
+    ///     .into_iter()
+    ///     .map(|r| (r.kind, r.start_line, r.end_line))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     ranges,
+    ///     vec![
+    ///         (KdlFoldingRangeKind::Comment, 0, 1),
+    ///         (KdlFoldingRangeKind::Children, 2, 4),
+    ///     ]
+    /// );
+    /// ```
+    pub fn folding_ranges(&self) -> Vec<KdlFoldingRange> {
+        let text = self.to_string();
+        let mut spans = Vec::new();
+        children_spans(self, &mut spans);
+
+        let mut comment_run: Option<SourceSpan> = None;
+        for token in self.semantic_tokens() {
+            match token.kind {
+                KdlSemanticTokenKind::String => {
+                    spans.push((KdlFoldingRangeKind::String, token.span));
+                }
+                KdlSemanticTokenKind::Comment => {
+                    comment_run = Some(match comment_run {
+                        Some(run) if is_blank(&text, end(run), token.span.offset()) => {
+                            SourceSpan::from(run.offset()..end(token.span))
+                        }
+                        Some(run) => {
+                            spans.push((KdlFoldingRangeKind::Comment, run));
+                            token.span
+                        }
+                        None => token.span,
+                    });
+                }
+                _ => {}
+            }
+        }
+        if let Some(run) = comment_run {
+            spans.push((KdlFoldingRangeKind::Comment, run));
+        }
+
+        let mut ranges = spans
+            .into_iter()
+            .filter_map(|(kind, span)| {
+                let start_line = line_of(&text, span.offset());
+                let end_line = end_line_of(&text, end(span));
+                (end_line > start_line).then(|| KdlFoldingRange {
+                    kind,
+                    span,
+                    start_line,
+                    end_line,
+                })
+            })
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|r| r.span.offset());
+        ranges
+    }
+}
+
+fn symbol(node: &KdlNode) -> KdlSymbol {
+    KdlSymbol {
+        name: node.name().value().into(),
+        span: node.span(),
+        name_span: node.name().span(),
+        children: node
+            .children()
+            .map(|children| children.outline())
+            .unwrap_or_default(),
+    }
+}
+
+fn children_spans(doc: &KdlDocument, spans: &mut Vec<(KdlFoldingRangeKind, SourceSpan)>) {
+    for node in doc.nodes() {
+        if let Some(children) = node.children() {
+            // Include the braces, which sit just outside the children
+            // document's own span and trailing whitespace.
+            let start = children.span().offset().saturating_sub(1);
+            let trailing = children.trailing().map(str::len).unwrap_or(0);
+            let span = SourceSpan::from(start..end(children.span()) + trailing + 1);
+            spans.push((KdlFoldingRangeKind::Children, span));
+            children_spans(children, spans);
+        }
+    }
+}
+
+fn end(span: SourceSpan) -> usize {
+    span.offset() + span.len()
+}
+
+fn is_blank(text: &str, start: usize, end: usize) -> bool {
+    text.get(start..end)
+        .map(|s| s.chars().all(char::is_whitespace))
+        .unwrap_or(false)
+}
+
+/// The 0-based line `offset` is on.
+fn line_of(text: &str, offset: usize) -> usize {
+    text.get(..offset)
+        .map(|before| before.matches('\n').count())
+        .unwrap_or(0)
+}
+
+/// The 0-based line of the last character before the exclusive `end` offset.
+fn end_line_of(text: &str, end: usize) -> usize {
+    text.get(..end)
+        .map(|before| before.trim_end_matches(['\r', '\n']).matches('\n').count())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use KdlFoldingRangeKind::*;
+
+    fn folds(input: &str) -> Vec<(KdlFoldingRangeKind, &str, usize, usize)> {
+        let doc: KdlDocument = input.parse().unwrap();
+        doc.folding_ranges()
+            .into_iter()
+            .map(|r| {
+                (
+                    r.kind,
+                    &input[r.span.offset()..][..r.span.len()],
+                    r.start_line,
+                    r.end_line,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn folding() {
+        let input = "/* a\n   block */\nfoo {\n    bar \"multi\nline\" {\n        baz\n    }\n    // one\n\n    // two\n    qux { quux; }\n}\n";
+        assert_eq!(
+            folds(input),
+            vec![
+                (Comment, "/* a\n   block */", 0, 1),
+                (
+                    Children,
+                    "{\n    bar \"multi\nline\" {\n        baz\n    }\n    // one\n\n    // two\n    qux { quux; }\n}",
+                    2,
+                    11
+                ),
+                (String, "\"multi\nline\"", 3, 4),
+                (Children, "{\n        baz\n    }", 4, 6),
+                (Comment, "// one\n\n    // two", 7, 9),
+            ]
+        );
+    }
+
+    #[test]
+    fn comment_runs_at_line_start() {
+        assert_eq!(
+            folds("foo\n// one\n// two\nbar\n"),
+            vec![(Comment, "// one\n// two", 1, 2)]
+        );
+    }
+
+    #[test]
+    fn single_line_regions_are_skipped() {
+        assert_eq!(folds("foo { bar; } // one\nbaz \"s\""), vec![]);
+    }
+
+    #[test]
+    fn outline() {
+        let input = "a {\n    b\n    c {\n        d\n    }\n}\ne\n";
+        let doc: KdlDocument = input.parse().unwrap();
+        let outline = doc.outline();
+        assert_eq!(outline.len(), 2);
+        let a = &outline[0];
+        assert_eq!(a.name, "a");
+        assert_eq!(
+            &input[a.span.offset()..][..a.span.len()],
+            &input[..input.find("\ne").unwrap()]
+        );
+        assert_eq!(a.name_span, (0, 1).into());
+        assert_eq!(
+            a.children.iter().map(|c| &c.name[..]).collect::<Vec<_>>(),
+            vec!["b", "c"]
+        );
+        assert_eq!(a.children[1].children[0].name, "d");
+        assert!(a.children[0].children.is_empty());
+        assert_eq!(outline[1].name, "e");
+    }
+}
@@ -4,6 +4,7 @@
 #![cfg_attr(not(feature = "span"), allow(dead_code, unused_variables))]
 
 use std::ops::RangeTo;
+use std::sync::Arc;
 
 use crate::nom_compat::{many0, many1, many_till};
 use miette::SourceSpan;
@@ -16,7 +17,8 @@ use nom::sequence::{delimited, preceded, terminated, tuple};
 use nom::{Finish, IResult, Offset, Parser, Slice};
 
 use crate::{
-    KdlDocument, KdlEntry, KdlError, KdlErrorKind, KdlIdentifier, KdlNode, KdlParseError, KdlValue,
+    CompatMode, IntegerOverflow, KdlDocument, KdlEntry, KdlError, KdlErrorKind, KdlIdentifier,
+    KdlNode, KdlParseError, KdlValue, KdlVersion, ParseOptions,
 };
 
 /// The parser for the entire input.
@@ -29,11 +31,23 @@ use crate::{
 /// take an input to configure the combinator and then return a function.
 pub(crate) struct KdlParser<'a> {
     pub(crate) full_input: &'a str,
+    pub(crate) integer_overflow: IntegerOverflow,
+    pub(crate) version: KdlVersion,
+    pub(crate) compat: CompatMode,
 }
 
 impl<'a> KdlParser<'a> {
     pub(crate) fn new(full_input: &'a str) -> Self {
-        Self { full_input }
+        Self::with_options(full_input, &ParseOptions::default())
+    }
+
+    pub(crate) fn with_options(full_input: &'a str, options: &ParseOptions) -> Self {
+        Self {
+            full_input,
+            integer_overflow: options.integer_overflow,
+            version: options.version,
+            compat: options.compat,
+        }
     }
 
     pub(crate) fn parse<T, P>(&self, parser: P) -> Result<T, KdlError>
@@ -46,10 +60,12 @@ impl<'a> KdlParser<'a> {
             .map_err(|e| {
                 let span_substr = &e.input[..e.len];
                 KdlError {
-                    input: self.full_input.into(),
+                    input: Arc::new(self.full_input.into()),
                     span: self.span_from_substr(span_substr),
                     help: e.help,
                     label: e.label,
+                    related_span: e.related.as_ref().map(|r| self.span_from_substr(r.0)),
+                    related_label: e.related.as_ref().map(|r| r.1),
                     kind: if let Some(kind) = e.kind {
                         kind
                     } else if let Some(ctx) = e.context {
@@ -109,14 +125,27 @@ fn set_details<'a>(
     help: Option<&'static str>,
 ) -> nom::Err<KdlParseError<&'a str>> {
     match &mut err {
-        nom::Err::Error(e) | nom::Err::Failure(e) => {
-            if !e.touched {
-                e.len = start.offset(e.input);
-                e.input = start;
-                e.label = label;
-                e.help = help;
-                e.touched = true;
-            }
+        nom::Err::Error(e) | nom::Err::Failure(e) if !e.touched => {
+            e.len = start.offset(e.input);
+            e.input = start;
+            e.label = label;
+            e.help = help;
+            e.touched = true;
+        }
+        _ => {}
+    }
+    err
+}
+
+/// Tags an error with a specific [`KdlErrorKind`], unless a more specific
+/// kind was already attached further down the parser stack.
+fn set_kind(
+    mut err: nom::Err<KdlParseError<&str>>,
+    kind: KdlErrorKind,
+) -> nom::Err<KdlParseError<&str>> {
+    match &mut err {
+        nom::Err::Error(e) | nom::Err::Failure(e) if e.kind.is_none() => {
+            e.kind = Some(kind);
         }
         _ => {}
     }
@@ -128,12 +157,19 @@ pub(crate) fn document<'a: 'b, 'b>(
 ) -> impl Fn(&'a str) -> IResult<&'a str, KdlDocument, KdlParseError<&'a str>> + 'b {
     move |input| {
         let start = input;
+        if kdl_parser.version == KdlVersion::V2 && start.len() == kdl_parser.full_input.len() {
+            disallowed_chars_v2(input, kdl_parser.compat.byte_order_marks)?;
+        }
         let (input, nodes) = many0(node(kdl_parser))(input)?;
+        let (input, disabled) = many0(disabled_node(kdl_parser))(input)?;
         let (input, trailing) = all_whitespace(kdl_parser)(input)?;
+        stray_terminator(kdl_parser, start, input)?;
         let mut doc = KdlDocument::new();
         doc.set_leading("");
         doc.set_trailing(trailing);
         *doc.nodes_mut() = nodes;
+        doc.disabled = disabled;
+        doc.version = kdl_parser.version;
         #[cfg(feature = "span")]
         doc.set_span(kdl_parser.span_from_before_and_after(start, trailing));
         Ok((input, doc))
@@ -144,12 +180,16 @@ pub(crate) fn node<'a: 'b, 'b>(
     kdl_parser: &'b KdlParser<'a>,
 ) -> impl Fn(&'a str) -> IResult<&'a str, KdlNode, KdlParseError<&'a str>> + 'b {
     |input| {
+        let (input, disabled) = many0(disabled_node(kdl_parser))(input)?;
         let (input, leading) = all_whitespace(kdl_parser)(input)?;
         let start = input;
         let (input, ty) = opt(context(
             "valid node type annotation",
             annotation(kdl_parser),
         ))(input)?;
+        #[cfg(feature = "span")]
+        let ty_span = kdl_parser.span_from_before_and_after(start, input);
+        let (input, _) = annotation_space(kdl_parser, ty.is_some())(input)?;
         let (input, name) = context("valid node name", identifier(kdl_parser))(input)?;
         let (input, entries) = many0(context("valid node entry", entry(kdl_parser)))(input)?;
         let (input, children) =
@@ -159,24 +199,37 @@ pub(crate) fn node<'a: 'b, 'b>(
             cut(recognize(preceded(
                 many0(node_space(kdl_parser)),
                 alt((
-                    terminated(recognize(tag(";")), opt(alt((linespace, eof)))),
-                    alt((newline, single_line_comment, eof)),
+                    terminated(
+                        recognize(tag(";")),
+                        opt(alt((linespace, line_break(kdl_parser), eof))),
+                    ),
+                    alt((line_break(kdl_parser), single_line_comment, eof)),
+                    last_child_end(kdl_parser),
                 )),
             ))),
         )(input)
         .map_err(|e| {
-            set_details(
-                e,
-                start,
-                Some("parsed node"),
-                Some("Nodes can only be terminated by `;` or a valid line ending."),
+            set_kind(
+                set_details(
+                    e,
+                    start,
+                    Some("parsed node"),
+                    Some("Nodes can only be terminated by `;` or a valid line ending."),
+                ),
+                KdlErrorKind::InvalidNodeTerminator,
             )
         })?;
         let mut node = KdlNode::new(name);
+        node.version = kdl_parser.version;
+        node.disabled = disabled;
         node.set_leading(leading);
         node.set_trailing(trailing);
         #[cfg(feature = "span")]
         node.set_span(kdl_parser.span_from_before_and_after(start, trailing));
+        #[cfg(feature = "span")]
+        if ty.is_some() {
+            node.ty_span = ty_span;
+        }
         node.ty = ty;
         let ents = node.entries_mut();
         *ents = entries;
@@ -189,6 +242,74 @@ pub(crate) fn node<'a: 'b, 'b>(
     }
 }
 
+/// A node commented out with a slashdash. The whitespace and comments
+/// before the slashdash are kept in the node's `slashdash` text.
+fn disabled_node<'a: 'b, 'b>(
+    kdl_parser: &'b KdlParser<'a>,
+) -> impl Fn(&'a str) -> IResult<&'a str, KdlNode, KdlParseError<&'a str>> + 'b {
+    move |input| {
+        let (input, before) = all_whitespace(kdl_parser)(input)?;
+        let start = input;
+        let (input, _) = tag("/-")(input)?;
+        let (input, mut node) = cut(node(kdl_parser))(input)
+            .map_err(|e| set_details(e, start, Some("slashdash"), None))?;
+        node.slashdash = Some(before.into());
+        Ok((input, node))
+    }
+}
+
+/// KDL 2.0 lets the last node in a children block leave out its
+/// terminator, as in `parent { child }`. This matches the `}` without
+/// consuming it, so [`children`] can still close the block.
+fn last_child_end<'a: 'b, 'b>(
+    kdl_parser: &'b KdlParser<'a>,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, KdlParseError<&'a str>> + 'b {
+    move |input| match kdl_parser.version {
+        KdlVersion::V1 => Err(nom::Err::Error(KdlParseError::from_error_kind(
+            input,
+            nom::error::ErrorKind::Tag,
+        ))),
+        KdlVersion::V2 => recognize(peek(tag("}")))(input),
+    }
+}
+
+/// Reports what's left over after the nodes of a document (or children
+/// block) when it's a `;` that doesn't terminate anything, like the second
+/// one in `node;;`, or, at the top level, a `}` that doesn't close
+/// anything.
+fn stray_terminator<'a>(
+    kdl_parser: &KdlParser<'a>,
+    start: &'a str,
+    input: &'a str,
+) -> Result<(), nom::Err<KdlParseError<&'a str>>> {
+    let top_level = start.len() == kdl_parser.full_input.len();
+    let (label, help, kind) = if input.starts_with(';') {
+        (
+            "extra `;`",
+            "Each node is terminated by a single `;`. Remove the extra one.",
+            KdlErrorKind::InvalidNodeTerminator,
+        )
+    } else if top_level && input.starts_with('}') {
+        (
+            "unmatched `}`",
+            "There's no children block for this `}` to close.",
+            KdlErrorKind::UnbalancedChildren,
+        )
+    } else {
+        return Ok(());
+    };
+    Err(nom::Err::Failure(KdlParseError {
+        input,
+        context: None,
+        len: 1,
+        label: Some(label),
+        help: Some(help),
+        kind: Some(kind),
+        related: None,
+        touched: true,
+    }))
+}
+
 pub(crate) fn identifier<'a: 'b, 'b>(
     kdl_parser: &'b KdlParser<'a>,
 ) -> impl Fn(&'a str) -> IResult<&'a str, KdlIdentifier, KdlParseError<&'a str>> + 'b {
@@ -247,7 +368,8 @@ fn errant_plain_identifier<'a: 'b, 'b>(
             len: name.len(),
             label: Some("plain identifiers can't be used here"),
             help: Some("If this was supposed to be a string, wrap it in quotes.\nIf this was supposed to be a new node, terminate the previous node with `;` or a newline."),
-            kind: None,
+            kind: Some(KdlErrorKind::BareIdentifier),
+            related: None,
             touched: false,
         }))
     }
@@ -258,19 +380,56 @@ fn plain_identifier<'a: 'b, 'b>(
 ) -> impl Fn(&'a str) -> IResult<&'a str, KdlIdentifier, KdlParseError<&'a str>> + 'b {
     move |input| {
         let start = input;
+        let v2 = kdl_parser.version == KdlVersion::V2;
+        let digits = kdl_parser.compat.digit_identifiers;
+        let initial = |c: char| {
+            if digits && c.is_ascii_digit() {
+                true
+            } else if v2 {
+                KdlIdentifier::is_initial_char_v2(c)
+            } else {
+                KdlIdentifier::is_initial_char(c)
+            }
+        };
+        let rest = |c| {
+            if v2 {
+                KdlIdentifier::is_identifier_char_v2(c)
+            } else {
+                KdlIdentifier::is_identifier_char(c)
+            }
+        };
         let (input, name) = recognize(preceded(
-            take_while_m_n(1, 1, KdlIdentifier::is_initial_char),
-            cut(take_while(KdlIdentifier::is_identifier_char)),
-        ))(input).map_err(|e| set_details(e, start, Some("invalid identifier character"), Some("See https://github.com/kdl-org/kdl/blob/main/SPEC.md#identifier for an explanation of valid KDL identifiers.")))?;
+            take_while_m_n(1, 1, initial),
+            cut(take_while(rest)),
+        ))(input).map_err(|e| set_kind(set_details(e, start, Some("invalid identifier character"), Some("See https://github.com/kdl-org/kdl/blob/main/SPEC.md#identifier for an explanation of valid KDL identifiers.")), KdlErrorKind::InvalidIdentifierChar))?;
+        if v2 {
+            check_identifier_v2(start, name, digits)?;
+        } else if !digits
+            && !KdlIdentifier::is_bare_v1(name)
+            && !matches!(name, "false" | "true" | "null")
+        {
+            // A sign followed by a digit, which starts a number instead.
+            return Err(nom::Err::Error(KdlParseError {
+                input: start,
+                context: Some("non-numeric identifier"),
+                len: name.len(),
+                label: Some("invalid identifier"),
+                help: Some("Identifiers can't start with `+` or `-` followed by a digit. If this was supposed to be a string, wrap it in quotes."),
+                kind: Some(KdlErrorKind::InvalidIdentifierChar),
+                related: None,
+                touched: false,
+            }));
+        }
         match name {
             "false" | "true" | "null" => {
                 return Err(nom::Err::Error(KdlParseError {
-                    input,
+                    input: start,
                     context: Some("non-keyword identifier"),
                     len: name.len(),
                     label: Some("reserved keyword"),
                     help: Some("Reserved keywords cannot be used as identifiers."),
-                    kind: None,
+                    kind: Some(KdlErrorKind::ReservedKeyword),
+                    related: None,
                     touched: false,
                 }))
             }
@@ -284,12 +443,60 @@ fn plain_identifier<'a: 'b, 'b>(
     }
 }
 
+/// KDL 2.0's extra rules for bare identifiers: they can't be a keyword
+/// without its `#`, and can't start like a number (unless `digits` allows
+/// it).
+fn check_identifier_v2<'a>(
+    start: &'a str,
+    name: &str,
+    digits: bool,
+) -> Result<(), nom::Err<KdlParseError<&'a str>>> {
+    let (label, help, kind) = match name {
+        "true" | "false" | "null" | "inf" | "-inf" | "nan" => (
+            "reserved keyword",
+            "KDL 2.0 keywords start with `#`, like `#true`. If this was supposed to be a string, wrap it in quotes.",
+            KdlErrorKind::ReservedKeyword,
+        ),
+        _ if !digits && looks_like_number(name) => (
+            "invalid identifier",
+            "Identifiers can't start with a digit, or with `.`, `+`, or `-` followed by a digit. If this was supposed to be a string, wrap it in quotes.",
+            KdlErrorKind::InvalidIdentifierChar,
+        ),
+        _ => return Ok(()),
+    };
+    Err(nom::Err::Failure(KdlParseError {
+        input: start,
+        context: Some("non-keyword identifier"),
+        len: name.len(),
+        label: Some(label),
+        help: Some(help),
+        kind: Some(kind),
+        related: None,
+        touched: true,
+    }))
+}
+
+fn looks_like_number(name: &str) -> bool {
+    let name = name.strip_prefix(['+', '-']).unwrap_or(name);
+    let name = name.strip_prefix('.').unwrap_or(name);
+    name.starts_with(|c: char| c.is_ascii_digit())
+}
+
 fn quoted_identifier<'a: 'b, 'b>(
     kdl_parser: &'b KdlParser<'a>,
-) -> impl Fn(&'a str) -> IResult<&str, KdlIdentifier, KdlParseError<&str>> + 'b {
+) -> impl Fn(&'a str) -> IResult<&'a str, KdlIdentifier, KdlParseError<&'a str>> + 'b {
     move |input| {
         let start = input;
-        let (input, (raw, val)) = alt((string, raw_string))(input)?;
+        let (input, (raw, val)) = match kdl_parser.version {
+            KdlVersion::V1 => alt((string, raw_string))(input),
+            KdlVersion::V2 => alt((
+                raw_multi_line_string_v2,
+                raw_string_v2,
+                multi_line_string_v2,
+                string_v2,
+                legacy_raw_string(kdl_parser),
+            ))(input),
+        }?;
         let mut ident = KdlIdentifier::from(val.as_string().unwrap());
         ident.set_repr(raw);
         #[cfg(feature = "span")]
@@ -333,7 +540,7 @@ fn entry<'a: 'b, 'b>(
     }
 }
 
-fn entry_maybe_space<'a: 'b, 'b>(
+pub(crate) fn entry_maybe_space<'a: 'b, 'b>(
     kdl_parser: &'b KdlParser<'a>,
 ) -> impl Fn(&'a str) -> IResult<&'a str, KdlEntry, KdlParseError<&'a str>> + 'b {
     move |input| {
@@ -353,14 +560,33 @@ fn property<'a: 'b, 'b>(
 ) -> impl Fn(&'a str) -> IResult<&'a str, KdlEntry, KdlParseError<&'a str>> + 'b {
     move |input| {
         let start = input;
+        if kdl_parser.version == KdlVersion::V2
+            && !kdl_parser.compat.digit_identifiers
+            && looks_like_number(input)
+        {
+            // Leave it for `argument`, rather than failing as an identifier.
+            return Err(nom::Err::Error(KdlParseError::from_error_kind(
+                input,
+                nom::error::ErrorKind::Tag,
+            )));
+        }
         let (input, name) = identifier(kdl_parser)(input)?;
-        let (input, _) = context("'=' after property name", tag("="))(input)?;
+        let (input, _) = equals(kdl_parser)(input)?;
+        let ty_start = input;
         let (input, ty) = opt(annotation(kdl_parser))(input)?;
-        let (input, (raw, value)) = context("property value", cut(value))(input).map_err(|e| set_details(e, input, Some("invalid value"), Some("Please refer to https://github.com/kdl-org/kdl/blob/main/SPEC.md#value for valid KDL value syntaxes.")))?;
+        #[cfg(feature = "span")]
+        let ty_span = kdl_parser.span_from_before_and_after(ty_start, input);
+        let (input, _) = annotation_space(kdl_parser, ty.is_some())(input)?;
+        let (input, (raw, value)) = context("property value", cut(parser_value(kdl_parser)))(input).map_err(|e| set_kind(set_details(e, input, Some("invalid value"), Some("Please refer to https://github.com/kdl-org/kdl/blob/main/SPEC.md#value for valid KDL value syntaxes.")), KdlErrorKind::InvalidValue))?;
         let mut entry = KdlEntry::new_prop(name, value);
+        #[cfg(feature = "span")]
+        if ty.is_some() {
+            entry.ty_span = ty_span;
+        }
         entry.ty = ty;
         entry.set_trailing("");
         entry.set_value_repr(raw);
+        annotate_overflow(kdl_parser, &mut entry);
         #[cfg(feature = "span")]
         entry.set_span(kdl_parser.span_from_before_and_after(start, input));
         Ok((input, entry))
@@ -373,15 +599,23 @@ fn argument<'a: 'b, 'b>(
     move |input| {
         let start = input;
         let (input, ty) = opt(annotation(kdl_parser))(input)?;
+        #[cfg(feature = "span")]
+        let ty_span = kdl_parser.span_from_before_and_after(start, input);
+        let (input, _) = annotation_space(kdl_parser, ty.is_some())(input)?;
         let (input, (raw, value)) = if ty.is_some() {
-            context("valid value", cut(value))(input)
+            context("valid value", cut(parser_value(kdl_parser)))(input)
         } else {
-            context("valid value", value)(input)
+            context("valid value", parser_value(kdl_parser))(input)
         }?;
         let mut entry = KdlEntry::new(value);
+        #[cfg(feature = "span")]
+        if ty.is_some() {
+            entry.ty_span = ty_span;
+        }
         entry.ty = ty;
         entry.set_trailing("");
         entry.set_value_repr(raw);
+        annotate_overflow(kdl_parser, &mut entry);
         #[cfg(feature = "span")]
         entry.set_span(kdl_parser.span_from_before_and_after(start, input));
         Ok((input, entry))
@@ -389,16 +623,72 @@ fn argument<'a: 'b, 'b>(
 }
 
 pub(crate) fn value(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
+    value_with_overflow(input, IntegerOverflow::default())
+}
+
+pub(crate) fn parser_value<'a: 'b, 'b>(
+    kdl_parser: &'b KdlParser<'a>,
+) -> impl Fn(&'a str) -> IResult<&'a str, (String, KdlValue), KdlParseError<&'a str>> + 'b {
+    move |input| match kdl_parser.version {
+        KdlVersion::V1 => value_with_overflow(input, kdl_parser.integer_overflow),
+        KdlVersion::V2 => value_v2(kdl_parser)(input),
+    }
+}
+
+/// A value on its own, as for [`KdlEntry::try_set_value_repr`], with
+/// nothing before or after it.
+pub(crate) fn lone_value<'a: 'b, 'b>(
+    kdl_parser: &'b KdlParser<'a>,
+) -> impl Fn(&'a str) -> IResult<&'a str, (String, KdlValue), KdlParseError<&'a str>> + 'b {
+    move |input| {
+        terminated(
+            context("valid value", parser_value(kdl_parser)),
+            context("a single value", eof),
+        )(input)
+    }
+}
+
+/// KDL 2.0 values: `#` keywords instead of bare ones, `#"raw"#` strings,
+/// and bare identifiers as strings.
+fn value_v2<'a: 'b, 'b>(
+    kdl_parser: &'b KdlParser<'a>,
+) -> impl Fn(&'a str) -> IResult<&'a str, (String, KdlValue), KdlParseError<&'a str>> + 'b {
+    move |input| {
+        let overflow = kdl_parser.integer_overflow;
+        alt((
+            keyword,
+            raw_multi_line_string_v2,
+            raw_string_v2,
+            multi_line_string_v2,
+            string_v2,
+            move |input| hexadecimal(input, overflow),
+            move |input| octal(input, overflow),
+            move |input| binary(input, overflow),
+            float,
+            move |input| integer(input, overflow),
+            legacy_raw_string(kdl_parser),
+            map(plain_identifier(kdl_parser), |ident| {
+                let raw = ident.repr().unwrap_or_default().to_string();
+                (raw, KdlValue::String(ident.value))
+            }),
+        ))(input)
+    }
+}
+
+fn value_with_overflow(
+    input: &str,
+    overflow: IntegerOverflow,
+) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
     alt((
         null,
         boolean,
         string,
         raw_string,
-        hexadecimal,
-        octal,
-        binary,
+        |input| hexadecimal(input, overflow),
+        |input| octal(input, overflow),
+        |input| binary(input, overflow),
         float,
-        integer,
+        |input| integer(input, overflow),
     ))(input)
 }
 
@@ -411,7 +701,12 @@ fn children<'a: 'b, 'b>(
         let (input, _) = tag("{")(input)?;
         let (input, children) = document(kdl_parser)(input)?;
         let (input, _) = cut(context("closing '}' in node children block", tag("}")))(input)
-            .map_err(|e| set_details(e, start, Some("children block body"), None))?;
+            .map_err(|e| {
+                set_kind(
+                    set_details(e, start, Some("children block body"), None),
+                    KdlErrorKind::UnbalancedChildren,
+                )
+            })?;
         Ok((input, (before, children)))
     }
 }
@@ -422,17 +717,118 @@ pub(crate) fn annotation<'a: 'b, 'b>(
     move |input| {
         let start = input;
         let (input, _) = tag("(")(input)?;
+        let (input, _) = annotation_space(kdl_parser, true)(input)?;
         let (input, ty) = cut(identifier(kdl_parser))(input)?;
+        let (input, _) = annotation_space(kdl_parser, true)(input)?;
         let (input, _) = context("closing ')' for type annotation", cut(tag(")")))(input)
-            .map_err(|e| set_details(e, start, Some("annotation"), Some("annotations can only be KDL identifiers (including string identifiers), and can't have any space inside the parentheses.")))?;
+            .map_err(|e| set_kind(set_details(e, start, Some("annotation"), Some("annotations can only be KDL identifiers (including string identifiers), and can't have any space inside the parentheses.")), KdlErrorKind::InvalidAnnotation))?;
         Ok((input, ty))
     }
 }
 
+/// The whitespace and comments around nodes, apart from slashdashed nodes,
+/// which are parsed as [`disabled_node`]s instead.
 fn all_whitespace<'a: 'b, 'b>(
     kdl_parser: &'b KdlParser<'a>,
 ) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, KdlParseError<&'a str>> + 'b {
-    move |input| recognize(many0(alt((comment(kdl_parser), unicode_space, newline))))(input)
+    move |input| {
+        recognize(many0(alt((
+            single_line_comment,
+            multi_line_comment,
+            unicode_space,
+            line_break(kdl_parser),
+            unicode_line_break(kdl_parser),
+        ))))(input)
+    }
+}
+
+/// A [`newline`], plus vertical tab in KDL 2.0. Only CR and LF with
+/// [`CompatMode::ascii_newlines`].
+fn line_break<'a: 'b, 'b>(
+    kdl_parser: &'b KdlParser<'a>,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, KdlParseError<&'a str>> + 'b {
+    move |input| match kdl_parser.version {
+        _ if kdl_parser.compat.ascii_newlines => alt((tag("\r\n"), tag("\r"), tag("\n")))(input),
+        KdlVersion::V1 => newline(input),
+        KdlVersion::V2 => alt((newline, tag("\u{000B}")))(input),
+    }
+}
+
+/// With [`CompatMode::ascii_newlines`], the line breaks that
+/// [`line_break`] leaves out, which count as plain space instead.
+fn unicode_line_break<'a: 'b, 'b>(
+    kdl_parser: &'b KdlParser<'a>,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, KdlParseError<&'a str>> + 'b {
+    move |input| {
+        if !kdl_parser.compat.ascii_newlines {
+            return Err(nom::Err::Error(KdlParseError::from_error_kind(
+                input,
+                nom::error::ErrorKind::Tag,
+            )));
+        }
+        match kdl_parser.version {
+            KdlVersion::V1 => recognize(many1(alt((
+                tag("\u{0085}"),
+                tag("\u{000C}"),
+                tag("\u{2028}"),
+                tag("\u{2029}"),
+            ))))(input),
+            KdlVersion::V2 => recognize(many1(alt((
+                tag("\u{0085}"),
+                tag("\u{000C}"),
+                tag("\u{2028}"),
+                tag("\u{2029}"),
+                tag("\u{000B}"),
+            ))))(input),
+        }
+    }
+}
+
+/// KDL 1.0's `r"raw"` strings, which [`CompatMode::legacy_raw_strings`]
+/// allows in KDL 2.0.
+fn legacy_raw_string<'a: 'b, 'b>(
+    kdl_parser: &'b KdlParser<'a>,
+) -> impl Fn(&'a str) -> IResult<&'a str, (String, KdlValue), KdlParseError<&'a str>> + 'b {
+    move |input| {
+        if !kdl_parser.compat.legacy_raw_strings {
+            return Err(nom::Err::Error(KdlParseError::from_error_kind(
+                input,
+                nom::error::ErrorKind::Tag,
+            )));
+        }
+        raw_string(input)
+    }
+}
+
+/// The space KDL 2.0 allows inside type annotations and after them, which
+/// KDL 1.0 doesn't allow at all. `allowed` is false when there's no
+/// annotation to put space after.
+fn annotation_space<'a: 'b, 'b>(
+    kdl_parser: &'b KdlParser<'a>,
+    allowed: bool,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, KdlParseError<&'a str>> + 'b {
+    move |input| match kdl_parser.version {
+        KdlVersion::V2 if allowed => recognize(many0(node_space(kdl_parser)))(input),
+        _ => Ok((input, "")),
+    }
+}
+
+/// The `=` between a property's name and value, which KDL 2.0 allows to
+/// have space around it.
+fn equals<'a: 'b, 'b>(
+    kdl_parser: &'b KdlParser<'a>,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, KdlParseError<&'a str>> + 'b {
+    move |input| {
+        let mut eq = context("'=' after property name", tag("="));
+        match kdl_parser.version {
+            KdlVersion::V1 => eq(input),
+            KdlVersion::V2 => recognize(tuple((
+                many0(node_space(kdl_parser)),
+                eq,
+                many0(node_space(kdl_parser)),
+            )))(input),
+        }
+    }
 }
 
 fn whitespace(input: &str) -> IResult<&str, &str, KdlParseError<&str>> {
@@ -452,6 +848,7 @@ fn node_space<'a: 'b, 'b>(
             recognize(alt((
                 delimited(many0(whitespace), escline, many0(whitespace)),
                 recognize(many1(whitespace)),
+                unicode_line_break(kdl_parser),
                 node_slashdash(kdl_parser),
             ))),
         )(input)
@@ -468,7 +865,7 @@ fn escline(input: &str) -> IResult<&str, &str, KdlParseError<&str>> {
                 alt((single_line_comment, newline)),
             )),
         ),
-    ))(input).map_err(|e| set_details(e, input, Some("line escape starts here"), Some("line escapes can only be followed by whitespace plus a newline (or single-line comment).")))
+    ))(input).map_err(|e| set_kind(set_details(e, input, Some("line escape starts here"), Some("line escapes can only be followed by whitespace plus a newline (or single-line comment).")), KdlErrorKind::InvalidLineEscape))
 }
 
 pub(crate) fn unicode_space(input: &str) -> IResult<&str, &str, KdlParseError<&str>> {
@@ -520,8 +917,50 @@ fn comment<'a: 'b, 'b>(
     }
 }
 
+/// Finds the comments (including slashdashed items) in a run of
+/// whitespace/comment text that was captured as a leading or trailing
+/// string. `in_node` selects whether slashdashes comment out entries and
+/// children blocks (inside a node) or whole nodes (between nodes).
+///
+/// Returned ranges are byte offsets into `text`, with the line ending that
+/// terminates single-line comments and slashdashed nodes left out.
+pub(crate) fn comment_ranges(
+    text: &str,
+    in_node: bool,
+    version: KdlVersion,
+) -> Vec<std::ops::Range<usize>> {
+    let options = ParseOptions {
+        version,
+        ..ParseOptions::default()
+    };
+    let kdl_parser = KdlParser::with_options(text, &options);
+    let mut ranges = Vec::new();
+    let mut input = text;
+    while let Some(c) = input.chars().next() {
+        let res = if in_node {
+            alt((
+                single_line_comment,
+                multi_line_comment,
+                node_slashdash(&kdl_parser),
+            ))(input)
+        } else {
+            comment(&kdl_parser)(input)
+        };
+        match res {
+            Ok((rest, comment)) if !comment.is_empty() => {
+                let start = text.offset(input);
+                let comment = comment.trim_end_matches(['\r', '\n']);
+                ranges.push(start..start + comment.len());
+                input = rest;
+            }
+            _ => input = &input[c.len_utf8()..],
+        }
+    }
+    ranges
+}
+
 /// `single-line-comment := '//' ('\r' [^\n] | [^\r\n])* (newline | eof)`
-fn single_line_comment(input: &str) -> IResult<&str, &str, KdlParseError<&str>> {
+pub(crate) fn single_line_comment(input: &str) -> IResult<&str, &str, KdlParseError<&str>> {
     recognize(preceded(
         tag("//"),
         cut(many_till(
@@ -533,12 +972,12 @@ fn single_line_comment(input: &str) -> IResult<&str, &str, KdlParseError<&str>>
 }
 
 /// `multi-line-comment := '/*' commented-block
-fn multi_line_comment(input: &str) -> IResult<&str, &str, KdlParseError<&str>> {
+pub(crate) fn multi_line_comment(input: &str) -> IResult<&str, &str, KdlParseError<&str>> {
     recognize(preceded(
         tag("/*"),
         context("comment block body", cut(commented_block)),
     ))(input)
-    .map_err(|e| set_details(e, input, Some("comment"), Some("multi-line comments must start with /* and be terminated with a matching */. They may be nested, but their */ must match.")))
+    .map_err(|e| set_kind(set_details(e, input, Some("comment"), Some("multi-line comments must start with /* and be terminated with a matching */. They may be nested, but their */ must match.")), KdlErrorKind::UnterminatedComment))
 }
 
 /// `commented-block := '*/' | (multi-line-comment | '*' | '/' | [^*/]+) commented-block`
@@ -565,16 +1004,35 @@ fn node_slashdash<'a: 'b, 'b>(
             tag("/-"),
             context(
                 "node following a slashdash",
-                cut(alt((
-                    recognize(entry_maybe_space(kdl_parser)),
-                    recognize(children(kdl_parser)),
-                ))),
+                cut(preceded(
+                    slashdash_space(kdl_parser),
+                    alt((
+                        recognize(entry_maybe_space(kdl_parser)),
+                        recognize(children(kdl_parser)),
+                    )),
+                )),
             ),
         ))(input)
         .map_err(|e| set_details(e, input, Some("slashdash"), None))
     }
 }
 
+/// KDL 2.0 allows line breaks and single-line comments between a
+/// slashdash and what it comments out, on top of the node space that
+/// entries and children blocks already allow before themselves.
+fn slashdash_space<'a: 'b, 'b>(
+    kdl_parser: &'b KdlParser<'a>,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, KdlParseError<&'a str>> + 'b {
+    move |input| match kdl_parser.version {
+        KdlVersion::V1 => Ok((input, "")),
+        KdlVersion::V2 => recognize(many0(alt((
+            node_space(kdl_parser),
+            line_break(kdl_parser),
+            single_line_comment,
+        ))))(input),
+    }
+}
+
 fn slashdash_comment<'a: 'b, 'b>(
     kdl_parser: &'b KdlParser<'a>,
 ) -> impl Fn(&'a str) -> IResult<&'a str, &'a str, KdlParseError<&'a str>> + 'b {
@@ -595,8 +1053,45 @@ fn null(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
     map(tag("null"), |s: &str| (s.into(), KdlValue::Null))(input)
 }
 
+/// `keyword := '#true' | '#false' | '#null' | '#inf' | '#-inf' | '#nan'`
+fn keyword(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
+    let (rest, name) = recognize(preceded(
+        char('#'),
+        take_while(KdlIdentifier::is_identifier_char_v2),
+    ))(input)?;
+    let value = match name {
+        "#true" => KdlValue::Bool(true),
+        "#false" => KdlValue::Bool(false),
+        "#null" => KdlValue::Null,
+        "#inf" => KdlValue::Base10Float(f64::INFINITY),
+        "#-inf" => KdlValue::Base10Float(f64::NEG_INFINITY),
+        "#nan" => KdlValue::Base10Float(f64::NAN),
+        // Leave `#"raw"#` strings alone.
+        "#" => {
+            return Err(nom::Err::Error(KdlParseError::from_error_kind(
+                input,
+                nom::error::ErrorKind::Tag,
+            )))
+        }
+        _ => {
+            return Err(nom::Err::Failure(KdlParseError {
+                input,
+                context: Some("keyword"),
+                len: name.len(),
+                label: Some("unknown keyword"),
+                help: Some("The only keywords are #true, #false, #null, #inf, #-inf, and #nan."),
+                kind: Some(KdlErrorKind::UnknownKeyword),
+                related: None,
+                touched: true,
+            }))
+        }
+    };
+    Ok((rest, (name.into(), value)))
+}
+
 /// `escaped-string := '"' character* '"'`
 fn string(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
+    let start = input;
     let (input, _) = tag("\"")(input)?;
     let mut original = String::new();
     let mut value = String::new();
@@ -607,14 +1102,356 @@ fn string(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>>
         value.push(processed);
     }
     let (input, _) =
-        cut(tag("\""))(input).map_err(|e| set_details(e, input, Some("string"), None))?;
+        cut(tag("\""))(input).map_err(|e| unterminated_string(e, start, &start[1..], 1))?;
     original.push('"');
     Ok((input, (original, KdlValue::String(value))))
 }
 
+/// The KDL 2.0 version of [`string`], with its escapes, and without
+/// literal newlines.
+fn string_v2(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
+    let start = input;
+    let (input, _) = tag("\"")(input)?;
+    let mut original = String::new();
+    let mut value = String::new();
+    original.push('"');
+    let (input, chars) = many0(alt((
+        map(whitespace_escape, |raw| (raw, None)),
+        map(character_v2, |(raw, c)| (raw, Some(c))),
+    )))(input)?;
+    for (raw, processed) in chars {
+        original.push_str(raw);
+        value.extend(processed);
+    }
+    let (input, _) =
+        cut(tag("\""))(input).map_err(|e| unterminated_string(e, start, &start[1..], 1))?;
+    original.push('"');
+    Ok((input, (original, KdlValue::String(value))))
+}
+
+/// `multi-line-string := '"""' newline (character | newline)* '"""'`,
+/// a KDL 2.0 string spanning several lines. The whitespace before the
+/// closing quotes is stripped from every line, and then escapes are
+/// processed.
+fn multi_line_string_v2(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
+    let start = input;
+    let (input, _) = tag("\"\"\"")(input)?;
+    let mut rest = input;
+    while !rest.starts_with("\"\"\"") {
+        match rest.chars().next() {
+            None => return Err(unterminated_multi_line_string(start, 3)),
+            Some(c) if c == '"' || is_newline_v2(c) => rest = &rest[c.len_utf8()..],
+            Some(_) => rest = alt((whitespace_escape, recognize(character_v2)))(rest)?.0,
+        }
+    }
+    let body = &input[..input.offset(rest)];
+    let (rest, _) = tag("\"\"\"")(rest)?;
+    let value = unescape_v2(&dedent(start, body)?);
+    let raw = &start[..start.offset(rest)];
+    Ok((rest, (raw.into(), KdlValue::String(value))))
+}
+
+/// `raw-string := '#' '"""' newline .* '"""' '#' | '#' raw-string '#'`,
+/// the raw version of [`multi_line_string_v2`].
+fn raw_multi_line_string_v2(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
+    let start = input;
+    let (input, hashes) = recognize(many1(char('#')))(input)?;
+    let (input, _) = tag("\"\"\"")(input)?;
+    let close = format!("\"\"\"{}", hashes);
+    let open_len = hashes.len() + 3;
+    let (rest, body) = take_until::<_, _, KdlParseError<&str>>(&close[..])(input)
+        .map_err(|_| unterminated_multi_line_string(start, open_len))?;
+    let (rest, _) = tag(&close[..])(rest)?;
+    let value = dedent(start, body)?;
+    let raw = &start[..start.offset(rest)];
+    Ok((rest, (raw.into(), KdlValue::RawString(value))))
+}
+
+fn unterminated_multi_line_string(start: &str, open_len: usize) -> nom::Err<KdlParseError<&str>> {
+    nom::Err::Failure(KdlParseError {
+        input: start,
+        context: Some("multi-line string"),
+        len: open_len,
+        label: Some("string starts here"),
+        help: Some("Add closing quotes on their own line to end the string."),
+        kind: Some(KdlErrorKind::UnterminatedString),
+        related: start
+            .char_indices()
+            .last()
+            .map(|(idx, _)| Box::new((&start[idx..], "input ends here"))),
+        touched: true,
+    })
+}
+
+/// Dedents the `body` of a multi-line string (everything between its
+/// quotes) that starts at `start`, as the spec describes: the first line
+/// must be empty, the last must be only whitespace, and that whitespace is
+/// removed from the start of every line in between. Lines that are only
+/// whitespace become empty, and newlines become `\n`.
+fn dedent<'a>(start: &'a str, body: &'a str) -> Result<String, nom::Err<KdlParseError<&'a str>>> {
+    let invalid = |at: &'a str, len, label, help| {
+        nom::Err::Failure(KdlParseError {
+            input: at,
+            context: Some("multi-line string"),
+            len,
+            label: Some(label),
+            help: Some(help),
+            kind: Some(KdlErrorKind::InvalidMultiLineString),
+            related: None,
+            touched: true,
+        })
+    };
+    let lines = lines_v2(body);
+    if lines.len() < 2 || !lines[0].is_empty() {
+        return Err(invalid(
+            start,
+            start.offset(body),
+            "no newline after the opening quotes",
+            "Multi-line strings have to start with a newline right after their opening quotes. For a single-line string, use a single `\"`.",
+        ));
+    }
+    let prefix = lines[lines.len() - 1];
+    if !prefix.chars().all(is_space_v2) {
+        return Err(invalid(
+            prefix,
+            prefix.len(),
+            "text before the closing quotes",
+            "The closing quotes of a multi-line string have to be on their own line, with only whitespace before them.",
+        ));
+    }
+    let mut dedented = Vec::with_capacity(lines.len() - 2);
+    for line in &lines[1..lines.len() - 1] {
+        if line.chars().all(is_space_v2) {
+            dedented.push("");
+        } else if let Some(line) = line.strip_prefix(prefix) {
+            dedented.push(line);
+        } else {
+            return Err(invalid(
+                line,
+                line.len(),
+                "line isn't indented like the closing quotes",
+                "Every line of a multi-line string has to start with the same whitespace as the line with its closing quotes.",
+            ));
+        }
+    }
+    Ok(dedented.join("\n"))
+}
+
+/// Splits `text` at KDL 2.0 newlines.
+fn lines_v2(text: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut rest = text;
+    while let Some(idx) = rest.find(is_newline_v2) {
+        lines.push(&rest[..idx]);
+        let newline_len = if rest[idx..].starts_with("\r\n") {
+            2
+        } else {
+            rest[idx..].chars().next().map_or(0, char::len_utf8)
+        };
+        rest = &rest[idx + newline_len..];
+    }
+    lines.push(rest);
+    lines
+}
+
+/// Processes the escapes in the dedented text of a multi-line string. The
+/// text was already checked while parsing, so there's nothing to fail on.
+fn unescape_v2(text: &str) -> String {
+    let mut value = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            if let Ok((after, _)) = whitespace_escape(rest) {
+                rest = after;
+                continue;
+            }
+            if let Ok((after, c)) = preceded(char('\\'), escape_v2)(rest) {
+                value.push(c);
+                rest = after;
+                continue;
+            }
+        }
+        value.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    value
+}
+
+/// Fails on the first character KDL 2.0 doesn't allow anywhere in a
+/// document. A byte order mark is only allowed as the first character,
+/// unless `byte_order_marks` allows them everywhere.
+fn disallowed_chars_v2(
+    input: &str,
+    byte_order_marks: bool,
+) -> Result<(), nom::Err<KdlParseError<&str>>> {
+    let found = input.char_indices().find(|&(i, c)| match c {
+        '\u{FEFF}' => i > 0 && !byte_order_marks,
+        _ => is_disallowed_v2(c),
+    });
+    match found {
+        Some((i, c)) => Err(nom::Err::Failure(KdlParseError {
+            input: &input[i..],
+            context: Some("allowed character"),
+            len: c.len_utf8(),
+            label: Some("disallowed character"),
+            help: Some("KDL 2.0 doesn't allow this character anywhere in a document. In strings, write it as a `\\u{...}` escape instead."),
+            kind: Some(KdlErrorKind::DisallowedCharacter),
+            related: None,
+            touched: true,
+        })),
+        None => Ok(()),
+    }
+}
+
+/// Characters KDL 2.0 doesn't allow to appear literally anywhere in a
+/// document: control characters other than whitespace and newlines,
+/// `U+007F`, Unicode direction controls, and the byte order mark (which
+/// callers allow at the start).
+pub(crate) fn is_disallowed_v2(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0000}'..='\u{0008}'
+            | '\u{000E}'..='\u{001F}'
+            | '\u{007F}'
+            | '\u{200E}'
+            | '\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{FEFF}'
+    )
+}
+
+/// `unicode-space` in KDL 2.0, which no longer includes the BOM.
+pub(crate) fn is_space_v2(c: char) -> bool {
+    matches!(
+        c,
+        ' ' | '\t' | '\u{00A0}' | '\u{1680}' | '\u{2000}'
+            ..='\u{200A}' | '\u{202F}' | '\u{205F}' | '\u{3000}'
+    )
+}
+
+/// `character := '\' escape | [^\\"] - newline`
+fn character_v2(input: &str) -> IResult<&str, (&str, char), KdlParseError<&str>> {
+    if input.starts_with(is_newline_v2) {
+        return Err(nom::Err::Error(KdlParseError::from_error_kind(
+            input,
+            nom::error::ErrorKind::NoneOf,
+        )));
+    }
+    with_raw(alt((preceded(char('\\'), cut(escape_v2)), none_of("\\\""))))(input).map_err(
+        |e| match e {
+            nom::Err::Failure(_) => {
+                let mut e = set_details(
+                    e,
+                    input,
+                    Some("invalid escape"),
+                    Some("Valid escapes are \\n, \\r, \\t, \\\\, \\\", \\b, \\f, \\s, \\u{XXXX}, and \\ followed by whitespace."),
+                );
+                if let nom::Err::Failure(e) = &mut e {
+                    e.len = 1 + bad_escape_len(&input[1..]);
+                }
+                e
+            }
+            e => e,
+        },
+    )
+}
+
+fn is_newline_v2(c: char) -> bool {
+    matches!(
+        c,
+        '\r' | '\n' | '\u{0085}' | '\u{000B}' | '\u{000C}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// `ws-escape := '\\' (unicode-space | newline)+`, which stands for nothing.
+fn whitespace_escape(input: &str) -> IResult<&str, &str, KdlParseError<&str>> {
+    recognize(preceded(
+        char('\\'),
+        many1(alt((unicode_space, newline, tag("\u{000B}")))),
+    ))(input)
+}
+
+/// `escape := ["\\bfnrts] | 'u{' hex-digit{1, 6} '}'`
+fn escape_v2(input: &str) -> IResult<&str, char, KdlParseError<&str>> {
+    match input.chars().next() {
+        Some('/') => Err(nom::Err::Failure(KdlParseError::from_error_kind(
+            input,
+            nom::error::ErrorKind::MapOpt,
+        )))
+        .map_err(|e| set_kind(e, KdlErrorKind::InvalidEscape)),
+        Some('s') => Ok((&input[1..], ' ')),
+        _ => escape(input),
+    }
+}
+
+/// Points an unterminated string error at its opening quote (`open_len`
+/// bytes long), with a secondary label at the first newline in its `body`,
+/// or at the end of the input if there is none.
+fn unterminated_string<'a>(
+    err: nom::Err<KdlParseError<&'a str>>,
+    start: &'a str,
+    body: &'a str,
+    open_len: usize,
+) -> nom::Err<KdlParseError<&'a str>> {
+    // Empty spans at the very end of the input don't get rendered, so the
+    // EOF label goes on the last character instead.
+    let related = match (body.find('\n'), body.char_indices().last()) {
+        (Some(idx), _) => Some((&body[idx..idx + 1], "string continues past this line")),
+        (None, Some((idx, _))) => Some((&body[idx..], "input ends here")),
+        (None, None) => None,
+    };
+    let mut err = set_kind(
+        set_details(
+            err,
+            start,
+            Some("string starts here"),
+            Some("Add a closing quote to end the string."),
+        ),
+        KdlErrorKind::UnterminatedString,
+    );
+    if let nom::Err::Error(e) | nom::Err::Failure(e) = &mut err {
+        if e.kind == Some(KdlErrorKind::UnterminatedString) && e.related.is_none() {
+            e.len = open_len;
+            e.related = related.map(Box::new);
+        }
+    }
+    err
+}
+
 /// `character := '\' escape | [^\"]`
 fn character(input: &str) -> IResult<&str, (&str, char), KdlParseError<&str>> {
-    with_raw(alt((preceded(char('\\'), cut(escape)), none_of("\\\""))))(input)
+    with_raw(alt((preceded(char('\\'), cut(escape)), none_of("\\\""))))(input).map_err(
+        |e| match e {
+            nom::Err::Failure(_) => {
+                let mut e = set_details(
+                e,
+                input,
+                Some("invalid escape"),
+                Some("Valid escapes are \\n, \\r, \\t, \\\\, \\/, \\\", \\b, \\f, and \\u{XXXX}."),
+            );
+                if let nom::Err::Failure(e) = &mut e {
+                    e.len = 1 + bad_escape_len(&input[1..]);
+                }
+                e
+            }
+            e => e,
+        },
+    )
+}
+
+/// Length of the malformed escape sequence at the start of `input` (just
+/// past the backslash), so errors cover exactly the offending characters.
+fn bad_escape_len(input: &str) -> usize {
+    if let Some(rest) = input.strip_prefix("u{") {
+        let end = rest
+            .find(|c: char| c == '}' || c == '"' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let close = usize::from(rest[end..].starts_with('}'));
+        "u{".len() + end + close
+    } else {
+        input.chars().next().map(char::len_utf8).unwrap_or(0)
+    }
 }
 
 /// This is like `recognize`, but _also_ returns the actual value.
@@ -652,6 +1489,7 @@ fn escape(input: &str) -> IResult<&str, char, KdlParseError<&str>> {
             _ => None,
         }),
     ))(input)
+    .map_err(|e| set_kind(e, KdlErrorKind::InvalidEscape))
 }
 
 fn unicode(input: &str) -> IResult<&str, char, KdlParseError<&str>> {
@@ -669,6 +1507,7 @@ fn unicode(input: &str) -> IResult<&str, char, KdlParseError<&str>> {
 /// `raw-string-hash := '#' raw-string-hash '#' | raw-string-quotes`
 /// `raw-string-quotes := '"' .* '"'`
 fn raw_string(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
+    let start = input;
     let mut raw = String::new();
     let (input, _) = char('r')(input)?;
     raw.push('r');
@@ -677,24 +1516,47 @@ fn raw_string(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&s
     let (input, _) = char('"')(input)?;
     raw.push('"');
     let close = format!("\"{}", hashes);
-    let (input, value) = take_until(&close[..])(input)?;
+    let (input, value) = cut(take_until(&close[..]))(input)
+        .map_err(|e| unterminated_string(e, start, input, raw.len()))?;
     raw.push_str(value);
     let (input, _) = cut(tag(&close[..]))(input)?;
     raw.push_str(&close);
     Ok((input, (raw, KdlValue::RawString(value.into()))))
 }
 
+/// `raw-string := '#' raw-string-quotes '#' | '#' raw-string '#'`, KDL
+/// 2.0's raw strings, which can't span lines.
+fn raw_string_v2(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
+    let start = input;
+    let (input, hashes) = recognize(many1(char('#')))(input)?;
+    let (input, _) = char('"')(input)?;
+    let close = format!("\"{}", hashes);
+    let open_len = hashes.len() + 1;
+    let (input, value) = cut(take_until(&close[..]))(input)
+        .map_err(|e| unterminated_string(e, start, input, open_len))?;
+    if value.contains(is_newline_v2) {
+        let err = nom::Err::Failure(KdlParseError::from_error_kind(
+            input,
+            nom::error::ErrorKind::TakeUntil,
+        ));
+        return Err(unterminated_string(err, start, value, open_len));
+    }
+    let (input, _) = tag(&close[..])(input)?;
+    let raw = format!("{}\"{}{}", hashes, value, close);
+    Ok((input, (raw, KdlValue::RawString(value.into()))))
+}
+
 fn float(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
     map_res(
         with_raw(alt((
             recognize(tuple((
-                integer,
-                opt(preceded(char('.'), cut(integer))),
+                integer_digits,
+                opt(preceded(char('.'), cut(integer_digits))),
                 one_of("eE"),
                 opt(one_of("+-")),
-                cut(integer),
+                cut(integer_digits),
             ))),
-            recognize(tuple((integer, char('.'), cut(integer)))),
+            recognize(tuple((integer_digits, char('.'), cut(integer_digits)))),
         ))),
         |(raw, x)| {
             str::replace(x, "_", "")
@@ -703,13 +1565,16 @@ fn float(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>>
         },
     )(input)
     .map_err(|e| {
-        set_details(
-            e,
-            input,
-            Some("invalid float"),
-            Some(
-                "Floating point numbers must be base 10, and have numbers after the decimal point.",
+        set_kind(
+            set_details(
+                e,
+                input,
+                Some("invalid float"),
+                Some(
+                    "Floating point numbers must be base 10, and have numbers after the decimal point.",
+                ),
             ),
+            KdlErrorKind::InvalidNumber,
         )
     })
 }
@@ -718,7 +1583,10 @@ fn float(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>>
 /// integer := sign? [1-9] [0-9_]*
 /// sign := '+' | '-'
 /// ```
-fn integer(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
+fn integer(
+    input: &str,
+    overflow: IntegerOverflow,
+) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
     let mut raw = String::new();
     let (input, (raw_sign, sign)) = with_raw(sign)(input)?;
     raw.push_str(raw_sign);
@@ -729,15 +1597,24 @@ fn integer(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>
         )))),
         move |(raw_int, out)| {
             raw.push_str(raw_int);
-            str::replace(out, "_", "")
-                .parse::<i64>()
-                .map(move |x| x * sign)
-                .map(|x| (raw.clone(), KdlValue::Base10(x)))
+            int_value(sign, out, 10, overflow, KdlValue::Base10).map(|x| (raw.clone(), x))
         },
     )(input)
 }
 
-fn hexadecimal(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
+/// Recognizes the same text as [`integer`], without converting it, so that
+/// floats aren't limited by the range of integers.
+fn integer_digits(input: &str) -> IResult<&str, &str, KdlParseError<&str>> {
+    recognize(tuple((
+        sign,
+        many1(terminated(one_of("0123456789"), many0(char('_')))),
+    )))(input)
+}
+
+fn hexadecimal(
+    input: &str,
+    overflow: IntegerOverflow,
+) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
     let mut raw = String::new();
     let (input, (raw_sign, sign)) = with_raw(sign)(input)?;
     raw.push_str(raw_sign);
@@ -754,17 +1631,17 @@ fn hexadecimal(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&
         )),
         move |(raw_body, hex): (&str, &str)| {
             raw.push_str(raw_body);
-            // TODO: Failure in case of int overflow!
-            i64::from_str_radix(&str::replace(hex, "_", ""), 16)
-                .map(|x| x * sign)
-                .map(|x| (raw.clone(), KdlValue::Base16(x)))
+            int_value(sign, hex, 16, overflow, KdlValue::Base16).map(|x| (raw.clone(), x))
         },
     )(input)
-    .map_err(|e| set_details(e, input, Some("invalid hexadecimal"), Some("Hexadecimal values can only include the characters 0-9 and a-f (case-insensitive), with optional `_` separators.")))
+    .map_err(|e| set_kind(set_details(e, input, Some("invalid hexadecimal"), Some("Hexadecimal values can only include the characters 0-9 and a-f (case-insensitive), with optional `_` separators.")), KdlErrorKind::InvalidNumber))
 }
 
 /// `octal := sign? '0o' [0-7] [0-7_]*`
-fn octal(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
+fn octal(
+    input: &str,
+    overflow: IntegerOverflow,
+) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
     let mut raw = String::new();
     let (input, (raw_sign, sign)) = with_raw(sign)(input)?;
     raw.push_str(raw_sign);
@@ -781,23 +1658,27 @@ fn octal(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>>
         )),
         move |(raw_body, oct): (&str, &str)| {
             raw.push_str(raw_body);
-            i64::from_str_radix(&str::replace(oct, "_", ""), 8)
-                .map(|x| x * sign)
-                .map(|x| (raw.clone(), KdlValue::Base8(x)))
+            int_value(sign, oct, 8, overflow, KdlValue::Base8).map(|x| (raw.clone(), x))
         },
     )(input)
     .map_err(|e| {
-        set_details(
-            e,
-            input,
-            Some("invalid octal"),
-            Some("octal values can only include the characters 0-7, with optional `_` separators."),
+        set_kind(
+            set_details(
+                e,
+                input,
+                Some("invalid octal"),
+                Some("octal values can only include the characters 0-7, with optional `_` separators."),
+            ),
+            KdlErrorKind::InvalidNumber,
         )
     })
 }
 
 /// `binary := sign? '0b' ('0' | '1') ('0' | '1' | '_')*`
-fn binary(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
+fn binary(
+    input: &str,
+    overflow: IntegerOverflow,
+) -> IResult<&str, (String, KdlValue), KdlParseError<&str>> {
     let mut raw = String::new();
     let (input, (raw_sign, sign)) = with_raw(sign)(input)?;
     raw.push_str(raw_sign);
@@ -811,15 +1692,76 @@ fn binary(input: &str) -> IResult<&str, (String, KdlValue), KdlParseError<&str>>
         )),
         move |(raw_body, binary): (&str, &str)| {
             raw.push_str(raw_body);
-            i64::from_str_radix(&str::replace(binary, "_", ""), 2)
-                .map(|x| x * sign)
-                .map(|x| (raw.clone(), KdlValue::Base2(x)))
+            int_value(sign, binary, 2, overflow, KdlValue::Base2).map(|x| (raw.clone(), x))
         },
     )(input)
-    .map_err(|e| set_details(e, input, Some("invalid binary"), Some("Hexadecimal values can only include the characters 0 and 1, with optional `_` separators.")))
+    .map_err(|e| set_kind(set_details(e, input, Some("invalid binary"), Some("Hexadecimal values can only include the characters 0 and 1, with optional `_` separators.")), KdlErrorKind::InvalidNumber))
 }
 
-fn sign(input: &str) -> IResult<&str, i64, KdlParseError<&str>> {
+/// Parses `digits` (which may contain `_` separators) in the given radix,
+/// negated if `sign` is negative, into the variant built by `make`. Negation
+/// happens before range checking, so `i128::MIN` parses fine. Numbers that
+/// don't fit in an `i128` are handled according to `overflow`.
+fn int_value(
+    sign: i128,
+    digits: &str,
+    radix: u32,
+    overflow: IntegerOverflow,
+    make: fn(i128) -> KdlValue,
+) -> Result<KdlValue, std::num::ParseIntError> {
+    let mut digits = str::replace(digits, "_", "");
+    if sign < 0 {
+        digits.insert(0, '-');
+    }
+    match i128::from_str_radix(&digits, radix) {
+        Ok(int) => Ok(make(int)),
+        Err(e)
+            if matches!(
+                e.kind(),
+                std::num::IntErrorKind::PosOverflow | std::num::IntErrorKind::NegOverflow
+            ) =>
+        {
+            match overflow {
+                IntegerOverflow::Error => Err(e),
+                IntegerOverflow::Saturate => Ok(make(if sign < 0 { i128::MIN } else { i128::MAX })),
+                IntegerOverflow::String => {
+                    let prefix = match radix {
+                        16 => "0x",
+                        8 => "0o",
+                        2 => "0b",
+                        _ => "",
+                    };
+                    let at = if sign < 0 { 1 } else { 0 };
+                    digits.insert_str(at, prefix);
+                    Ok(KdlValue::String(digits))
+                }
+                #[cfg(feature = "bigint")]
+                IntegerOverflow::BigInt => Ok(KdlValue::BigInt(
+                    num_bigint::BigInt::parse_bytes(digits.as_bytes(), radix)
+                        .expect("digits were already validated"),
+                )),
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Annotates an entry whose integer was too large and was kept as a string
+/// because of [`IntegerOverflow::String`]. It's written out as the string
+/// it is now, rather than the number it was.
+fn annotate_overflow(kdl_parser: &KdlParser<'_>, entry: &mut KdlEntry) {
+    let overflowed = kdl_parser.integer_overflow == IntegerOverflow::String
+        && matches!(entry.value(), KdlValue::String(_))
+        && entry.value_repr().map_or(false, looks_like_number);
+    if overflowed {
+        if entry.ty.is_none() {
+            entry.ty = Some("bigint".into());
+        }
+        entry.value_repr = None;
+    }
+}
+
+fn sign(input: &str) -> IResult<&str, i128, KdlParseError<&str>> {
     let (input, sign) = opt(alt((char('+'), char('-'))))(input)?;
     let mult = if let Some(sign) = sign {
         if sign == '+' {
@@ -945,6 +1887,109 @@ mod node_tests {
     }
 }
 
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    fn parse_err(input: &str) -> KdlError {
+        let kdl_parser = crate::parser::KdlParser::new(input);
+        kdl_parser
+            .parse(document(&kdl_parser))
+            .expect_err("parse should have failed")
+    }
+
+    #[test]
+    fn kinds() {
+        assert_eq!(parse_err("foo 1.").kind, KdlErrorKind::InvalidNumber);
+        assert_eq!(parse_err("foo 0xzz").kind, KdlErrorKind::InvalidNumber);
+        assert_eq!(
+            parse_err("foo \"bar").kind,
+            KdlErrorKind::UnterminatedString
+        );
+        assert_eq!(parse_err(r#"foo "\q""#).kind, KdlErrorKind::InvalidEscape);
+        assert_eq!(
+            parse_err("foo /* bar").kind,
+            KdlErrorKind::UnterminatedComment
+        );
+        assert_eq!(parse_err("foo bar").kind, KdlErrorKind::BareIdentifier);
+        assert_eq!(parse_err("foo x=").kind, KdlErrorKind::InvalidValue);
+        assert_eq!(parse_err("foo (x 1").kind, KdlErrorKind::InvalidAnnotation);
+        assert_eq!(parse_err("foo \\ x").kind, KdlErrorKind::InvalidLineEscape);
+        assert_eq!(parse_err("foo {").kind, KdlErrorKind::UnbalancedChildren);
+        assert_eq!(
+            parse_err("foo 1 }").kind,
+            KdlErrorKind::InvalidNodeTerminator
+        );
+    }
+
+    #[test]
+    fn stray_terminators() {
+        for (input, offset) in [("n;;", 2), ("n; ;", 3), ("a { b;; }", 6)] {
+            let err = parse_err(input);
+            assert_eq!(err.kind, KdlErrorKind::InvalidNodeTerminator, "{:?}", input);
+            assert_eq!(err.span, (offset..offset + 1).into(), "{:?}", input);
+            assert_eq!(err.label, Some("extra `;`"));
+        }
+        let err = parse_err("a\n}");
+        assert_eq!(err.kind, KdlErrorKind::UnbalancedChildren);
+        assert_eq!(err.span, (2..3).into());
+    }
+
+    #[test]
+    fn codes() {
+        use miette::Diagnostic;
+
+        let err = parse_err("foo {");
+        assert_eq!(err.kind.code_str(), "kdl::unbalanced_children");
+        assert_eq!(
+            err.code().map(|c| c.to_string()),
+            Some("kdl::unbalanced_children".into())
+        );
+        assert!(!err.kind.is_recoverable());
+        assert!(err.kind.is_structural());
+
+        let err = parse_err("foo 1.");
+        assert_eq!(err.kind.code_str(), "kdl::invalid_number");
+        assert!(err.kind.is_recoverable());
+        assert!(err.kind.is_number_error());
+    }
+
+    #[test]
+    fn unterminated_string_spans() {
+        let err = parse_err("foo \"bar");
+        assert_eq!(err.kind, KdlErrorKind::UnterminatedString);
+        assert_eq!(err.span, (4, 1).into());
+        assert_eq!(err.related_span, Some((7, 1).into()));
+        assert_eq!(err.related_label, Some("input ends here"));
+
+        let err = parse_err("foo \"bar\nbaz");
+        assert_eq!(err.span, (4, 1).into());
+        assert_eq!(err.related_span, Some((8, 1).into()));
+        assert_eq!(err.related_label, Some("string continues past this line"));
+
+        let err = parse_err("foo r##\"bar\"#");
+        assert_eq!(err.kind, KdlErrorKind::UnterminatedString);
+        assert_eq!(err.span, (4, 4).into());
+        assert_eq!(err.related_span, Some((12, 1).into()));
+    }
+
+    #[test]
+    fn invalid_escape_spans() {
+        let err = parse_err(r#"foo "a\qb""#);
+        assert_eq!(err.kind, KdlErrorKind::InvalidEscape);
+        assert_eq!(err.span, (6, 2).into());
+        assert_eq!(err.related_span, None);
+
+        let err = parse_err(r#"foo "a\u{zz}b""#);
+        assert_eq!(err.kind, KdlErrorKind::InvalidEscape);
+        assert_eq!(err.span, (6, 6).into());
+
+        let err = parse_err(r#"foo "a\u{41""#);
+        assert_eq!(err.kind, KdlErrorKind::InvalidEscape);
+        assert_eq!(err.span, (6, 5).into());
+    }
+}
+
 #[cfg(test)]
 mod whitespace_tests {
     #[test]
@@ -1102,6 +2147,27 @@ mod value_tests {
             value("+123"),
             Ok(("", ("+123".into(), KdlValue::Base10(123))))
         );
+        assert_eq!(
+            value("18446744073709551615"),
+            Ok((
+                "",
+                (
+                    "18446744073709551615".into(),
+                    KdlValue::Base10(u64::MAX as i128)
+                )
+            ))
+        );
+        assert_eq!(
+            value("-0x8000_0000_0000_0000_0000_0000_0000_0000"),
+            Ok((
+                "",
+                (
+                    "-0x8000_0000_0000_0000_0000_0000_0000_0000".into(),
+                    KdlValue::Base16(i128::MIN)
+                )
+            ))
+        );
+        assert!(value("170141183460469231731687303715884105728").is_err());
     }
 
     #[test]
@@ -1124,6 +2190,15 @@ mod value_tests {
             value("+123.456"),
             Ok(("", ("+123.456".into(), KdlValue::Base10Float(123.456))))
         );
+        // The integer part isn't limited to the range of integers.
+        let big = format!("{}0.5", u128::MAX);
+        assert_eq!(
+            value(&big),
+            Ok((
+                "",
+                (big.clone(), KdlValue::Base10Float(u128::MAX as f64 * 10.0))
+            ))
+        );
     }
 
     #[test]
@@ -1163,4 +2238,349 @@ mod value_tests {
             ))
         );
     }
+
+    #[test]
+    fn integer_overflow() {
+        let parse = |input: &str, integer_overflow, version| {
+            let options = ParseOptions {
+                integer_overflow,
+                version,
+                ..ParseOptions::default()
+            };
+            KdlDocument::parse_with_options(input, &options)
+        };
+        let huge = "n 1_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000 -0x1_0000_0000_0000_0000_0000_0000_0000_0000 x=(u128)0b1";
+        let huge = format!("{}{}", huge, "0".repeat(128));
+        for version in [KdlVersion::V1, KdlVersion::V2] {
+            assert!(parse(&huge, IntegerOverflow::Error, version).is_err());
+
+            let doc = parse(&huge, IntegerOverflow::Saturate, version).unwrap();
+            let values: Vec<_> = doc.nodes()[0]
+                .entries()
+                .iter()
+                .map(|e| e.value().clone())
+                .collect();
+            assert_eq!(
+                values,
+                vec![
+                    KdlValue::Base10(i128::MAX),
+                    KdlValue::Base16(i128::MIN),
+                    KdlValue::Base2(i128::MAX)
+                ]
+            );
+            assert_eq!(doc.to_string(), huge);
+
+            let doc = parse(&huge, IntegerOverflow::String, version).unwrap();
+            assert_eq!(
+                doc.to_string(),
+                format!(
+                    "n (bigint)\"100000000000000000000000000000000000000000000\" (bigint)\"-0x100000000000000000000000000000000\" x=(u128)\"0b1{}\"",
+                    "0".repeat(128)
+                )
+            );
+            // Numbers that fit are left alone.
+            let doc = parse("n 1 \"2\"", IntegerOverflow::String, version).unwrap();
+            assert_eq!(doc.to_string(), "n 1 \"2\"");
+        }
+    }
+}
+
+#[cfg(test)]
+mod v2_tests {
+    use super::*;
+
+    fn parse_v2(input: &str) -> Result<KdlDocument, KdlError> {
+        let options = ParseOptions {
+            version: KdlVersion::V2,
+            ..ParseOptions::default()
+        };
+        let kdl_parser = KdlParser::with_options(input, &options);
+        kdl_parser.parse(document(&kdl_parser))
+    }
+
+    fn args(doc: &KdlDocument) -> Vec<KdlValue> {
+        doc.nodes()[0]
+            .entries()
+            .iter()
+            .map(|e| e.value().clone())
+            .collect()
+    }
+
+    #[test]
+    fn keywords() {
+        let doc = parse_v2("node #true #false #null #inf #-inf").unwrap();
+        assert_eq!(
+            args(&doc),
+            vec![
+                KdlValue::Bool(true),
+                KdlValue::Bool(false),
+                KdlValue::Null,
+                KdlValue::Base10Float(f64::INFINITY),
+                KdlValue::Base10Float(f64::NEG_INFINITY),
+            ]
+        );
+        let doc = parse_v2("node #nan").unwrap();
+        assert!(matches!(args(&doc)[0], KdlValue::Base10Float(x) if x.is_nan()));
+        // Printing keeps the keywords as written.
+        assert_eq!(doc.to_string(), "node #nan");
+
+        assert_eq!(
+            parse_v2("node true").unwrap_err().kind,
+            KdlErrorKind::ReservedKeyword
+        );
+        assert_eq!(
+            parse_v2("null").unwrap_err().kind,
+            KdlErrorKind::ReservedKeyword
+        );
+        let err = parse_v2("node #yes").unwrap_err();
+        assert_eq!(err.kind, KdlErrorKind::UnknownKeyword);
+        assert_eq!(err.span, (5..9).into());
+
+        // And v1 doesn't know about any of this.
+        let kdl_parser = KdlParser::new("node #true");
+        assert!(kdl_parser.parse(document(&kdl_parser)).is_err());
+    }
+
+    #[test]
+    fn identifiers() {
+        let doc = parse_v2("node foo a<b>,c -x").unwrap();
+        assert_eq!(
+            args(&doc),
+            vec![
+                KdlValue::String("foo".into()),
+                KdlValue::String("a<b>,c".into()),
+                KdlValue::String("-x".into()),
+            ]
+        );
+        assert_eq!(doc.to_string(), "node foo a<b>,c -x");
+
+        assert!(parse_v2("node a#b").is_err());
+        for input in ["-1x", ".5", "+.5x"] {
+            let err = parse_v2(input).unwrap_err();
+            assert_eq!(err.kind, KdlErrorKind::InvalidIdentifierChar, "{}", input);
+        }
+        // Numbers still parse as numbers.
+        let doc = parse_v2("node -1 1.5 0x10").unwrap();
+        assert_eq!(
+            args(&doc),
+            vec![
+                KdlValue::Base10(-1),
+                KdlValue::Base10Float(1.5),
+                KdlValue::Base16(16),
+            ]
+        );
+    }
+
+    #[test]
+    fn disallowed_characters() {
+        assert!(parse_v2("\u{FEFF}node \"\u{2067}\"").is_err());
+        for (input, offset) in [
+            ("node \"a\u{7F}b\"", 7),
+            ("node // \u{202E}", 8),
+            ("node\u{FEFF}1", 4),
+            ("node \u{1}", 5),
+        ] {
+            let err = parse_v2(input).unwrap_err();
+            assert_eq!(err.kind, KdlErrorKind::DisallowedCharacter, "{:?}", input);
+            assert_eq!(err.span.offset(), offset, "{:?}", input);
+        }
+        let doc = parse_v2("\u{FEFF}node \"\\u{202E}\"").unwrap();
+        assert_eq!(args(&doc), vec![KdlValue::String("\u{202E}".into())]);
+        // KDL 1.0 has no such rule.
+        assert!("node \"\u{202E}\"".parse::<KdlDocument>().is_ok());
+    }
+
+    fn parse_compat(input: &str, version: KdlVersion) -> Result<KdlDocument, KdlError> {
+        let options = ParseOptions {
+            version,
+            compat: CompatMode::lenient(),
+            ..ParseOptions::default()
+        };
+        let kdl_parser = KdlParser::with_options(input, &options);
+        kdl_parser.parse(document(&kdl_parser))
+    }
+
+    #[test]
+    fn compat_mode() {
+        use KdlVersion::{V1, V2};
+
+        let doc = parse_compat("node\u{FEFF}1", V2).unwrap();
+        assert_eq!(args(&doc), vec![KdlValue::Base10(1)]);
+
+        for version in [V1, V2] {
+            let doc = parse_compat("1st -2x=3 4", version).unwrap();
+            assert_eq!(doc.nodes()[0].name().value(), "1st");
+            assert_eq!(doc.nodes()[0].get("-2x"), Some(&3.into()));
+            assert_eq!(doc.nodes()[0].get(0), Some(&4.into()));
+            assert_eq!(doc.to_string(), "1st -2x=3 4");
+
+            let doc = parse_compat("a 1\u{2028}2\r\nb\u{85}3", version).unwrap();
+            assert_eq!(doc.nodes().len(), 2);
+            assert_eq!(args(&doc), vec![KdlValue::Base10(1), KdlValue::Base10(2)]);
+            assert_eq!(doc.to_string(), "a 1\u{2028}2\r\nb\u{85}3");
+        }
+        assert_eq!(parse_v2("a 1\u{2028}b 2").unwrap().nodes().len(), 2);
+
+        let doc = parse_compat(r##"r"name" r#"raw "value""# #"new"#"##, V2).unwrap();
+        assert_eq!(doc.nodes()[0].name().value(), "name");
+        assert_eq!(
+            args(&doc),
+            vec![
+                KdlValue::RawString(r#"raw "value""#.into()),
+                KdlValue::RawString("new".into()),
+            ]
+        );
+        assert!(parse_v2(r#"node r"raw""#).is_err());
+        assert_eq!(
+            parse_compat("node red", V2).unwrap().nodes()[0].get(0),
+            Some(&"red".into())
+        );
+    }
+
+    #[test]
+    fn strings() {
+        let doc = parse_v2(r###"node #"raw \n"# "a\sb\   c" ##"x"#y"##"###).unwrap();
+        assert_eq!(
+            args(&doc),
+            vec![
+                KdlValue::RawString(r"raw \n".into()),
+                KdlValue::String("a bc".into()),
+                KdlValue::RawString(r##"x"#y"##.into()),
+            ]
+        );
+        let doc = parse_v2("node \"a\\\n    b\"").unwrap();
+        assert_eq!(args(&doc), vec![KdlValue::String("ab".into())]);
+
+        assert_eq!(
+            parse_v2(r#"node "a\/b""#).unwrap_err().kind,
+            KdlErrorKind::InvalidEscape
+        );
+        assert_eq!(
+            parse_v2("node \"a\nb\"").unwrap_err().kind,
+            KdlErrorKind::UnterminatedString
+        );
+        assert_eq!(
+            parse_v2("node #\"a\nb\"#").unwrap_err().kind,
+            KdlErrorKind::UnterminatedString
+        );
+        assert!(parse_v2(r#"node r"raw""#).is_err());
+    }
+
+    #[test]
+    fn whitespace() {
+        let doc = parse_v2("node key = 1 ( u8 ) 2 other= (i32)3\u{000B}( t ) next").unwrap();
+        let node = &doc.nodes()[0];
+        assert_eq!(node.get("key"), Some(&KdlValue::Base10(1)));
+        assert_eq!(node.get("other"), Some(&KdlValue::Base10(3)));
+        assert_eq!(node.get(0), Some(&KdlValue::Base10(2)));
+        assert_eq!(node.entries()[1].ty().map(|t| t.value()), Some("u8"));
+        let next = &doc.nodes()[1];
+        assert_eq!(next.name().value(), "next");
+        assert_eq!(next.ty().map(|t| t.value()), Some("t"));
+        assert_eq!(
+            doc.to_string(),
+            "node key=1 (u8)2 other=(i32)3\u{000B}(t)next"
+        );
+
+        // Space before `=` can even include a slashdashed entry.
+        let doc = parse_v2("node key /-val =1").unwrap();
+        assert_eq!(args(&doc), vec![KdlValue::String("key".into())]);
+        let doc = parse_v2("node key /-val = 1").unwrap();
+        assert_eq!(args(&doc), vec![KdlValue::String("key".into())]);
+    }
+
+    #[test]
+    fn children_terminators() {
+        for input in [
+            "parent { child }",
+            "parent { a; b }",
+            "a { b { c } }",
+            "parent { child 1 key=2 }",
+            "parent {\n    child }",
+        ] {
+            let doc = parse_v2(input).unwrap();
+            assert_eq!(doc.to_string(), input);
+        }
+        let doc = parse_v2("a { b { c } }").unwrap();
+        let b = &doc.nodes()[0].children().unwrap().nodes()[0];
+        assert_eq!(b.children().unwrap().nodes()[0].name().value(), "c");
+
+        // Top-level nodes and nodes followed by anything but `}` still need
+        // terminators.
+        assert_eq!(
+            parse_v2("a { b } c").unwrap_err().kind,
+            KdlErrorKind::InvalidNodeTerminator
+        );
+        assert_eq!(
+            parse_v2("a }").unwrap_err().kind,
+            KdlErrorKind::UnbalancedChildren
+        );
+        assert_eq!(
+            parse_v2("n;;").unwrap_err().kind,
+            KdlErrorKind::InvalidNodeTerminator
+        );
+    }
+
+    #[test]
+    fn slashdash_space() {
+        for (input, children) in [
+            ("node /- { a }", false),
+            ("node {a} /-{b}", true),
+            ("node /-\n  {a}", false),
+            ("node /- // why\n  {a}", false),
+        ] {
+            let doc = parse_v2(input).unwrap();
+            assert_eq!(doc.nodes().len(), 1, "{:?}", input);
+            assert_eq!(doc.nodes()[0].children().is_some(), children, "{:?}", input);
+            assert_eq!(doc.to_string(), input);
+        }
+        let doc = parse_v2("node 1 /-\n  2 3").unwrap();
+        assert_eq!(args(&doc), vec![KdlValue::Base10(1), KdlValue::Base10(3)]);
+        // KDL 1.0 only allows that before nodes.
+        assert!("node 1 /-\n  2 3".parse::<KdlDocument>().is_err());
+    }
+
+    #[test]
+    fn multi_line_strings() {
+        let input = "node \"\"\"\n    echo \"hi\"\n\n      \\tindented \\\n      joined\n    \"\"\" #\"\"\"\n  raw \\n\n  \"\"\"#";
+        let doc = parse_v2(input).unwrap();
+        assert_eq!(
+            args(&doc),
+            vec![
+                KdlValue::String("echo \"hi\"\n\n  \tindented joined".into()),
+                KdlValue::RawString(r"raw \n".into()),
+            ]
+        );
+        assert_eq!(doc.to_string(), input);
+
+        // CRLF becomes `\n`, and whitespace-only lines become empty.
+        let doc = parse_v2("node \"\"\"\r\n  a\r\n \r\n  b\r\n  \"\"\"").unwrap();
+        assert_eq!(args(&doc), vec![KdlValue::String("a\n\nb".into())]);
+        let doc = parse_v2("node \"\"\"\n\"\"\"").unwrap();
+        assert_eq!(args(&doc), vec![KdlValue::String("".into())]);
+        let doc = parse_v2("\"\"\"\n  multi-line name\n  \"\"\"").unwrap();
+        assert_eq!(doc.nodes()[0].name().value(), "multi-line name");
+
+        let err = |input| parse_v2(input).unwrap_err();
+        let e = err("node \"\"\"a\n\"\"\"");
+        assert_eq!(e.kind, KdlErrorKind::InvalidMultiLineString);
+        assert_eq!(e.label, Some("no newline after the opening quotes"));
+        let e = err("node \"\"\"\n  a\n  b \"\"\"");
+        assert_eq!(e.kind, KdlErrorKind::InvalidMultiLineString);
+        assert_eq!(e.span, (13..17).into());
+        let e = err("node \"\"\"\n    a\n  b\n    \"\"\"");
+        assert_eq!(e.label, Some("line isn't indented like the closing quotes"));
+        assert_eq!(e.span, (15..18).into());
+        let e = err("node \"\"\"\n  a\n");
+        assert_eq!(e.kind, KdlErrorKind::UnterminatedString);
+        assert_eq!(e.span, (5..8).into());
+        assert_eq!(
+            err("node #\"\"\"\n  a\n  \"\"\"").kind,
+            KdlErrorKind::UnterminatedString
+        );
+        assert_eq!(
+            err("node \"\"\"\n  \\/\n  \"\"\"").kind,
+            KdlErrorKind::InvalidEscape
+        );
+    }
 }
@@ -21,8 +21,8 @@ pub trait IntoKdlQuery: IntoQuerySealed {}
 
 impl IntoKdlQuery for KdlQuery {}
 impl IntoKdlQuery for String {}
-impl<'a> IntoKdlQuery for &'a str {}
-impl<'a> IntoKdlQuery for &'a String {}
+impl IntoKdlQuery for &str {}
+impl IntoKdlQuery for &String {}
 
 #[doc(hidden)]
 pub trait IntoQuerySealed {
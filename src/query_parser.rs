@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use crate::nom_compat::many0;
 use crate::parser::{value, KdlParser};
 use crate::query::{
@@ -30,7 +32,7 @@ impl<'a> KdlQueryParser<'a> {
             .map_err(|e| {
                 let span_substr = &e.input[..e.len];
                 KdlError {
-                    input: self.0.full_input.into(),
+                    input: Arc::new(self.0.full_input.into()),
                     span: self.0.span_from_substr(span_substr),
                     help: if let Some(help) = e.help {
                         Some(help)
@@ -40,6 +42,8 @@ impl<'a> KdlQueryParser<'a> {
                         None
                     },
                     label: e.label,
+                    related_span: e.related.as_ref().map(|r| self.0.span_from_substr(r.0)),
+                    related_label: e.related.as_ref().map(|r| r.1),
                     kind: if let Some(kind) = e.kind {
                         kind
                     } else if let Some(ctx) = e.context {
@@ -59,14 +63,12 @@ fn set_details<'a>(
     help: Option<&'static str>,
 ) -> nom::Err<KdlParseError<&'a str>> {
     match &mut err {
-        nom::Err::Error(e) | nom::Err::Failure(e) => {
-            if !e.touched {
-                e.len = start.offset(e.input);
-                e.input = start;
-                e.label = label;
-                e.help = help;
-                e.touched = true;
-            }
+        nom::Err::Error(e) | nom::Err::Failure(e) if !e.touched => {
+            e.len = start.offset(e.input);
+            e.input = start;
+            e.label = label;
+            e.help = help;
+            e.touched = true;
         }
         _ => {}
     }
@@ -152,6 +154,7 @@ fn node_matchers<'a: 'b, 'b>(
                     kind: None,
                     label: Some("scope()"),
                     help: Some("Make sure scope() precedes any other items within a (comma-separated) selector."),
+                    related: None,
                     touched: false,
                     context: Some("scope() to be the first item in this selector"),
                 }));
@@ -170,6 +173,7 @@ fn node_matchers<'a: 'b, 'b>(
                     kind: None,
                     label: Some("type annotation"),
                     help: Some("The syntax for node selectors is (type)node[attribute=value]."),
+                    related: None,
                     touched: false,
                     context: Some("only one type annotation per selector"),
                 }));
@@ -194,6 +198,7 @@ fn node_matchers<'a: 'b, 'b>(
                 kind: None,
                 label: Some("type annotation"),
                 help: Some("The syntax for node selectors is (type)node[attribute=value]."),
+                related: None,
                 touched: false,
                 context: Some("type annotation to not be used after a node name"),
             }));
@@ -210,6 +215,7 @@ fn node_matchers<'a: 'b, 'b>(
                 kind: None,
                 label: Some("node matcher"),
                 help: Some("node matcher must not be empty"),
+                related: None,
                 touched: false,
                 context: Some("a valid node matcher"),
             }))
@@ -224,6 +230,7 @@ fn node_matchers<'a: 'b, 'b>(
                     kind: None,
                     label: Some("type annotation"),
                     help: Some("The syntax for node selectors is (type)node[attribute=value]."),
+                    related: None,
                     touched: false,
                     context: Some("type annotation to come before attribute matcher(s)"),
                 }));
@@ -238,6 +245,7 @@ fn node_matchers<'a: 'b, 'b>(
                     kind: None,
                     label: Some("node name"),
                     help: Some("The syntax for node selectors is (type)node[attribute=value]."),
+                    related: None,
                     touched: false,
                     context: Some("node name to come before attribute matcher(s)"),
                 }));
@@ -301,6 +309,7 @@ fn attribute_matcher_inner<'a: 'b, 'b>(
                                 kind: None,
                                 label: Some("non-string operator value"),
                                 help: Some("Only strings can be used as arguments for string-related operators (*=, ^=, $=)."),
+                                related: None,
                                 touched: false,
                                 context: Some("a string as an operator value"),
                             }))
@@ -322,6 +331,7 @@ fn attribute_matcher_inner<'a: 'b, 'b>(
                         kind: None,
                         label: Some("operator value"),
                         help: Some("Only valid KDL values can be used on the right hand side of attribute matcher operators."),
+                        related: None,
                         touched: false,
                         context: Some("a valid operator argument"),
                     }))
@@ -450,6 +460,7 @@ fn arg_accessor(input: &str) -> IResult<&str, KdlQueryMatcherAccessor, KdlParseE
                 kind: None,
                 label: Some("arg accessor"),
                 help: Some("arg accessor must be an integer"),
+                related: None,
                 touched: false,
                 context: Some("a valid arg accessor"),
             }))
@@ -473,6 +484,7 @@ fn prop_name_accessor<'a: 'b, 'b>(
                 kind: None,
                 label: Some("accessor"),
                 help: Some("accessor must be one of: type(), arg(), prop(), propname"),
+                related: None,
                 touched: false,
                 context: Some("a valid accessor"),
             }))
@@ -539,6 +551,7 @@ fn bad_accessor<'a: 'b, 'b>(
                 kind: None,
                 label: Some("incorrect scope() accessor"),
                 help: Some("Accessors must be one of: type(), arg(), prop(), propname"),
+                related: None,
                 touched: false,
                 context: Some(
                     "'scope()' to be the first item only at the top level of the query selector",
@@ -564,6 +577,7 @@ fn bad_accessor<'a: 'b, 'b>(
                         kind: None,
                         label: Some("invalid attribute accessor"),
                         help: Some("Accessors must be one of: type(), arg(), prop(), propname"),
+                        related: None,
                         touched: false,
                         context: Some("a valid attribute accessor"),
                     }));
@@ -577,6 +591,7 @@ fn bad_accessor<'a: 'b, 'b>(
             kind: None,
             label: Some("accessor"),
             help: Some("accessor must be one of: type(), arg(), prop(), propname"),
+            related: None,
             touched: false,
             context: Some("a valid accessor"),
         }))
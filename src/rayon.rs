@@ -0,0 +1,87 @@
+use rayon::prelude::*;
+
+use crate::{KdlDocument, KdlNode};
+
+/// Parallel iteration with [`rayon`]. Requires the `rayon` feature.
+impl KdlDocument {
+    /// Returns a parallel iterator over this document's own nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// use rayon::prelude::*;
+    ///
+    /// let doc: KdlDocument = "a 1\nb 2\nc 3".parse().unwrap();
+    /// let sum: i64 = doc.par_nodes().filter_map(|n| n.get(0)?.as_i64()).sum();
+    /// assert_eq!(sum, 6);
+    /// ```
+    pub fn par_nodes(&self) -> rayon::slice::Iter<'_, KdlNode> {
+        self.nodes().par_iter()
+    }
+
+    /// Returns a parallel iterator that allows modifying each of this
+    /// document's own nodes in place.
+    pub fn par_nodes_mut(&mut self) -> rayon::slice::IterMut<'_, KdlNode> {
+        self.nodes_mut().par_iter_mut()
+    }
+
+    /// Returns a parallel iterator over every node in this document, at any
+    /// depth. Collecting it gives the same order as
+    /// [`KdlDocument::descendants`].
+    ///
+    /// Gathering the nodes up front is a quick serial walk; it's the work
+    /// done on each node afterwards that gets spread across threads.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// use rayon::prelude::*;
+    ///
+    /// let doc: KdlDocument = "a { b; c { d; }; }\ne".parse().unwrap();
+    /// let names: Vec<_> = doc.par_descendants().map(|n| n.name().value()).collect();
+    /// assert_eq!(names, vec!["a", "b", "c", "d", "e"]);
+    /// ```
+    pub fn par_descendants(&self) -> rayon::vec::IntoIter<&KdlNode> {
+        self.descendants().collect::<Vec<_>>().into_par_iter()
+    }
+}
+
+/// Parallel iteration over a node's children with [`rayon`]. Requires the
+/// `rayon` feature.
+impl KdlNode {
+    /// Returns a parallel iterator over every node below this one. See
+    /// [`KdlDocument::par_descendants`].
+    pub fn par_descendants(&self) -> rayon::vec::IntoIter<&KdlNode> {
+        self.descendants().collect::<Vec<_>>().into_par_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parallel() -> miette::Result<()> {
+        let input: String = (0..1000)
+            .map(|i| format!("node{} {} {{ child {}; }}\n", i, i, i * 2))
+            .collect();
+        let mut doc: KdlDocument = input.parse()?;
+        let sum: i64 = doc
+            .par_descendants()
+            .filter_map(|n| n.get(0)?.as_i64())
+            .sum();
+        assert_eq!(sum, (0..1000).map(|i| i * 3).sum::<i64>());
+        assert_eq!(
+            doc.par_descendants().map(|n| n.id()).collect::<Vec<_>>(),
+            doc.descendants().map(|n| n.id()).collect::<Vec<_>>()
+        );
+        assert_eq!(doc.nodes()[0].par_descendants().count(), 1);
+
+        doc.par_nodes_mut().for_each(|n| n.clear_children());
+        assert_eq!(doc.par_descendants().count(), 1000);
+        assert_eq!(doc.par_nodes().count(), 1000);
+        Ok(())
+    }
+}
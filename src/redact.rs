@@ -0,0 +1,163 @@
+use crate::{versions, KdlDocument, KdlEntry, KdlIdentifier, KdlNode, KdlValue, Redaction};
+
+impl KdlDocument {
+    /// Replaces the values of the entries that `predicate` picks, in this
+    /// document and its children, with `placeholder`, so that the document
+    /// can be shown without giving secrets away. `predicate` is called with
+    /// each entry and the node it's in.
+    ///
+    /// Everything but the values stays the same: entries keep their names,
+    /// type annotations, and positions, so the document has the same shape.
+    /// Slashdashed nodes and entries are redacted the same way as the rest.
+    /// Only values are redacted, though, so anything secret in comments is
+    /// still written out; remove those with
+    /// [`KdlDocument::fmt_no_comments`] first if they might have any.
+    ///
+    /// [`EmitOptions::redact`](crate::EmitOptions::redact) redacts values by
+    /// name or type annotation while writing a document out.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let mut doc: KdlDocument = "user \"alice\" api-key=\"k-123\" pin=1234".parse().unwrap();
+    /// // Hide every property, but not the arguments.
+    /// doc.redact("***", |_node, entry| entry.name().is_some());
+    /// assert_eq!(doc.to_string(), "user \"alice\" api-key=\"***\" pin=\"***\"");
+    /// ```
+    pub fn redact(
+        &mut self,
+        placeholder: &str,
+        mut predicate: impl FnMut(&KdlNode, &KdlEntry) -> bool,
+    ) {
+        self.redact_impl(placeholder, &mut predicate)
+    }
+
+    fn redact_impl(
+        &mut self,
+        placeholder: &str,
+        predicate: &mut dyn FnMut(&KdlNode, &KdlEntry) -> bool,
+    ) {
+        for node in self.nodes.iter_mut().chain(&mut self.disabled) {
+            redact_node(node, placeholder, predicate);
+        }
+    }
+}
+
+fn redact_node(
+    node: &mut KdlNode,
+    placeholder: &str,
+    predicate: &mut dyn FnMut(&KdlNode, &KdlEntry) -> bool,
+) {
+    for disabled in &mut node.disabled {
+        redact_node(disabled, placeholder, predicate);
+    }
+    let secret: Vec<bool> = node
+        .entries()
+        .iter()
+        .map(|entry| predicate(node, entry))
+        .collect();
+    for (entry, secret) in node.entries_mut().iter_mut().zip(secret) {
+        if secret {
+            entry.set_value(KdlValue::String(placeholder.into()));
+            entry.value_repr = None;
+        }
+    }
+    node.rewrite_disabled_entries(|node, entry| {
+        if !predicate(node, entry) {
+            return false;
+        }
+        let value = KdlValue::String(placeholder.into());
+        entry.value_repr = Some(versions::value_repr(&value, node.version).into_owned());
+        entry.set_value(value);
+        true
+    });
+    if let Some(children) = node.children_mut().as_mut() {
+        children.redact_impl(placeholder, predicate);
+    }
+}
+
+impl Redaction {
+    /// Whether `entry`, in `node`, is secret.
+    pub(crate) fn matches(&self, node: &KdlNode, entry: &KdlEntry) -> bool {
+        let annotated = |ty: Option<&KdlIdentifier>| {
+            ty.map_or(false, |ty| self.annotations.iter().any(|a| a == ty.value()))
+        };
+        let named = |name: &str| self.names.iter().any(|n| n == name);
+        annotated(entry.ty())
+            || annotated(node.ty())
+            || named(node.name().value())
+            || entry.name().map_or(false, |name| named(name.value()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{EmitOptions, KdlDocument, Redaction};
+
+    #[test]
+    fn redacts() {
+        let doc: KdlDocument = "a (secret)1 2 x=(secret)r\"s\" {\n    password \"p\" (u8)3\n    (secret)b 4 y=5\n    c key=\"k\" (secret)6\n}\n/-d (secret)7\n"
+            .parse()
+            .unwrap();
+        let options = EmitOptions {
+            redact: Some(Redaction {
+                names: vec!["password".into(), "key".into()],
+                placeholder: "-".into(),
+                ..Redaction::default()
+            }),
+            ..EmitOptions::default()
+        };
+        assert_eq!(
+            doc.to_string_with_options(&options).unwrap(),
+            "a (secret)\"-\" 2 x=(secret)\"-\" {\n    password \"-\" (u8)\"-\"\n    (secret)b \"-\" y=\"-\"\n    c key=\"-\" (secret)\"-\"\n}\n/-d (secret)\"-\"\n"
+        );
+        // The document itself is untouched.
+        assert!(doc.to_string().contains("password \"p\""));
+
+        let options = EmitOptions {
+            redact: Some(Redaction {
+                annotations: Vec::new(),
+                ..Redaction::default()
+            }),
+            ..EmitOptions::default()
+        };
+        assert_eq!(
+            doc.to_string_with_options(&options).unwrap(),
+            doc.to_string()
+        );
+    }
+
+    #[test]
+    fn slashdashed() {
+        let mut doc: KdlDocument = "a /-(secret)\"pw\" x=1 /-key=\"k\" /-(secret)r\"s\" {\n    /-b key=2\n}\n/-c (secret)3\n".parse().unwrap();
+        doc.redact("-", |_node, entry| {
+            entry.ty().map_or(false, |ty| ty.value() == "secret")
+                || entry.name().map_or(false, |name| name.value() == "key")
+        });
+        assert_eq!(
+            doc.to_string(),
+            "a /-(secret)\"-\" x=1 /-key=\"-\" /-(secret)\"-\" {\n    /-b key=\"-\"\n}\n/-c (secret)\"-\"\n"
+        );
+
+        let mut doc = KdlDocument::parse_auto("a /- key=#true 1 /-2").unwrap();
+        doc.redact("-", |_node, entry| entry.name().is_some());
+        assert_eq!(doc.to_string(), "a /- key=\"-\" 1 /-2");
+        assert_eq!(doc.nodes()[0].disabled_entries()[0].value(), &"-".into());
+
+        let doc =
+            KdlDocument::parse_auto("db user=admin /-password=hunter2 /-password=\"hunter3\"\n")
+                .unwrap();
+        let options = EmitOptions {
+            redact: Some(Redaction {
+                names: vec!["password".into()],
+                ..Redaction::default()
+            }),
+            ..EmitOptions::default()
+        };
+        assert_eq!(
+            doc.to_string_with_options(&options).unwrap(),
+            "db user=admin /-password=\"[redacted]\" /-password=\"[redacted]\"\n"
+        );
+    }
+}
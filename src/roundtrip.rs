@@ -0,0 +1,225 @@
+use std::sync::Arc;
+
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::util::ErrorSpan;
+use crate::{KdlDocument, KdlError, KdlNode, KdlSource, ParseOptions};
+
+/// Error returned by [`verify_roundtrip`].
+#[derive(Debug, Diagnostic, Clone, Eq, PartialEq, Error)]
+#[non_exhaustive]
+pub enum KdlRoundtripError {
+    /// The input didn't parse.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(KdlError),
+
+    /// The input parsed, but writing it back out produced a document that
+    /// doesn't. The error's source is the output, named `"output"`.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Reparse(KdlError),
+
+    /// Writing the input back out changed it.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Diverged(KdlRoundtripReport),
+}
+
+/// Where a document's output stopped matching its input, as found by
+/// [`verify_roundtrip`].
+#[derive(Debug, Diagnostic, Clone, Eq, PartialEq, Error)]
+#[error("Document didn't survive a round trip.")]
+#[diagnostic(code(kdl::roundtrip))]
+pub struct KdlRoundtripReport {
+    /// The input.
+    #[source_code]
+    pub input: Arc<KdlSource>,
+
+    /// What the input was written back out as.
+    pub output: String,
+
+    /// The part of the input that was written differently. Everything
+    /// before and after it matches the output.
+    #[label("written differently")]
+    pub span: SourceSpan,
+
+    /// What [`KdlRoundtripReport::span`] was written as, in the output.
+    pub output_span: SourceSpan,
+
+    /// Whether the output means something different from the input, and
+    /// not just being written differently. See [`KdlDocument::semantic_eq`].
+    pub meaning_changed: bool,
+
+    /// Span of the first node in the input whose meaning changed, if it
+    /// can be pinned to one, rather than, say, the output having a node
+    /// the input didn't. This is `0..0` without the `span` feature.
+    #[label("meaning changed")]
+    pub node_span: Option<SourceSpan>,
+
+    /// Explains whether the meaning changed.
+    #[help]
+    pub help: &'static str,
+}
+
+/// Checks that `input` survives being parsed and written back out: that the
+/// output is exactly the same text, and that it parses again into a
+/// document that means the same thing.
+///
+/// The input can be KDL 1.0 or 2.0, as picked by
+/// [`KdlDocument::parse_auto`], and the output is parsed again as the same
+/// version.
+///
+/// This is meant for tests, run over a corpus of documents, to catch any
+/// changes to how faithfully documents are written out.
+///
+/// # Examples
+///
+/// ```rust
+/// kdl::verify_roundtrip("// config\nserver port=8080 {\n    tls true\n}\n").unwrap();
+/// kdl::verify_roundtrip("/- kdl-version 2\nserver tls=#true").unwrap();
+///
+/// let err = kdl::verify_roundtrip("node 1.").unwrap_err();
+/// assert!(matches!(err, kdl::KdlRoundtripError::Parse(_)));
+/// ```
+pub fn verify_roundtrip(input: &str) -> Result<(), KdlRoundtripError> {
+    let doc = KdlDocument::parse_auto(input).map_err(KdlRoundtripError::Parse)?;
+    check(input, &doc, doc.to_string())
+}
+
+fn check(input: &str, doc: &KdlDocument, output: String) -> Result<(), KdlRoundtripError> {
+    let options = ParseOptions {
+        version: doc.version(),
+        ..ParseOptions::default()
+    };
+    let reparsed = KdlDocument::parse_with_options(&output, &options)
+        .map_err(|err| KdlRoundtripError::Reparse(err.with_source_name("output")))?;
+    if input == output {
+        return Ok(());
+    }
+    let meaning_changed = !doc.semantic_eq(&reparsed);
+    let node_span = if meaning_changed {
+        first_changed(doc.nodes(), reparsed.nodes()).map(KdlNode::error_span)
+    } else {
+        None
+    };
+    let (span, output_span) = text_spans(input, &output);
+    Err(KdlRoundtripError::Diverged(KdlRoundtripReport {
+        input: Arc::new(KdlSource::new(input)),
+        output,
+        span,
+        output_span,
+        meaning_changed,
+        node_span,
+        help: if meaning_changed {
+            "The output means something different from the input."
+        } else {
+            "The output means the same thing as the input, but is written differently."
+        },
+    }))
+}
+
+/// The spans of the parts of `input` and `output` between their common
+/// prefix and suffix.
+fn text_spans(input: &str, output: &str) -> (SourceSpan, SourceSpan) {
+    let (a, b) = (input.as_bytes(), output.as_bytes());
+    let mut prefix = a.iter().zip(b).take_while(|(l, r)| l == r).count();
+    while !input.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+    let max_suffix = a.len().min(b.len()) - prefix;
+    let mut suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take(max_suffix)
+        .take_while(|(l, r)| l == r)
+        .count();
+    while !input.is_char_boundary(a.len() - suffix) {
+        suffix -= 1;
+    }
+    (
+        (prefix..a.len() - suffix).into(),
+        (prefix..b.len() - suffix).into(),
+    )
+}
+
+/// The first node in `left` that doesn't mean the same as its counterpart
+/// in `right`, going into children if the nodes themselves are otherwise
+/// the same.
+fn first_changed<'a>(left: &'a [KdlNode], right: &[KdlNode]) -> Option<&'a KdlNode> {
+    for (l, r) in left.iter().zip(right) {
+        if l.semantic_eq(r) {
+            continue;
+        }
+        let (mut l_shell, mut r_shell) = (l.clone(), r.clone());
+        *l_shell.children_mut() = None;
+        *r_shell.children_mut() = None;
+        if l_shell.semantic_eq(&r_shell) {
+            return first_changed(children(l), children(r)).or(Some(l));
+        }
+        return Some(l);
+    }
+    left.get(right.len())
+}
+
+fn children(node: &KdlNode) -> &[KdlNode] {
+    node.children().map_or(&[], KdlDocument::nodes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn diverged(input: &str, output: &str) -> KdlRoundtripReport {
+        let doc: KdlDocument = input.parse().unwrap();
+        match check(input, &doc, output.into()) {
+            Err(KdlRoundtripError::Diverged(report)) => report,
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn roundtrips() {
+        let corpus = [
+            "",
+            "a",
+            "/* c */ a 1 /-2 x=r#\"y\"# { b; c { d \"é\"; }; }\n// end",
+            "(t)a \\\n    0x10 1.5e3\n\n",
+            "/- kdl-version 2\nnode #true",
+            "a #null #\"raw\"# bare {\n    b #inf\n}\n",
+        ];
+        for input in corpus {
+            assert_eq!(verify_roundtrip(input), Ok(()));
+        }
+    }
+
+    #[test]
+    fn divergences() {
+        let report = diverged("a 0x10 {\n    b 1\n}\n", "a 16 {\n    b 1\n}\n");
+        assert!(!report.meaning_changed);
+        assert_eq!(report.span, (2..6).into());
+        assert_eq!(report.output_span, (2..4).into());
+        assert_eq!(report.node_span, None);
+
+        let input = "a 1 {\n    b 1\n    c \"é\"\n}\n";
+        let report = diverged(input, "a 1 {\n    b 1\n    c \"è\"\n}\n");
+        assert!(report.meaning_changed);
+        assert_eq!(&input[report.span.offset()..][..report.span.len()], "é");
+        #[cfg(feature = "span")]
+        assert_eq!(report.node_span, Some((18..24).into()));
+
+        let report = diverged("a 1", "a 1\nb");
+        assert!(report.meaning_changed);
+        assert_eq!(report.node_span, None);
+
+        let err = check("a", &"a".parse().unwrap(), "a 1.".into()).unwrap_err();
+        match err {
+            KdlRoundtripError::Reparse(err) => {
+                assert_eq!(err.input.name.as_deref(), Some("output"))
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use miette::SourceSpan;
+
+use crate::{parser, KdlDocument, KdlEntry, KdlIdentifier, KdlNode, KdlValue, KdlVersion};
+
+/// A classified span of source text, as produced by
+/// [`KdlDocument::semantic_tokens`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KdlSemanticToken {
+    /// What kind of syntax this token is.
+    pub kind: KdlSemanticTokenKind,
+
+    /// Where the token is in the source text.
+    pub span: SourceSpan,
+}
+
+/// The classification of a [`KdlSemanticToken`].
+///
+/// These map directly onto LSP semantic token types (or TextMate scopes) for
+/// syntax highlighting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KdlSemanticTokenKind {
+    /// A node's name.
+    NodeName,
+    /// A property's key.
+    PropertyKey,
+    /// The identifier inside a type annotation, like `u8` in `(u8)1`.
+    TypeAnnotation,
+    /// A string or raw string value.
+    String,
+    /// A numeric value, in any base.
+    Number,
+    /// A `true`, `false`, or `null` value.
+    Keyword,
+    /// A single-line, multi-line, or slashdash (`/-`) comment.
+    Comment,
+}
+
+impl KdlDocument {
+    /// Walks this document and returns classified spans for every node name,
+    /// property key, type annotation, value, and comment in it, in source
+    /// order. Because the classification comes from the parsed tree, it's
+    /// always consistent with what the parser actually accepted.
+    ///
+    /// Spans are only meaningful for documents that were parsed, or that had
+    /// [`KdlDocument::recompute_spans`] called after being modified.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlDocument, KdlSemanticTokenKind};
+    /// let input = "node (u8)1 key=\"value\" // comment";
+    /// let doc: KdlDocument = input.parse().unwrap();
+    /// let tokens = doc
+    ///     .semantic_tokens()
+    ///     .into_iter()
+    ///     .map(|t| (t.kind, &input[t.span.offset()..][..t.span.len()]))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     tokens,
+    ///     vec![
+    ///         (KdlSemanticTokenKind::NodeName, "node"),
+    ///         (KdlSemanticTokenKind::TypeAnnotation, "u8"),
+    ///         (KdlSemanticTokenKind::Number, "1"),
+    ///         (KdlSemanticTokenKind::PropertyKey, "key"),
+    ///         (KdlSemanticTokenKind::String, "\"value\""),
+    ///         (KdlSemanticTokenKind::Comment, "// comment"),
+    ///     ]
+    /// );
+    /// ```
+    pub fn semantic_tokens(&self) -> Vec<KdlSemanticToken> {
+        let mut tokens = Tokens(Vec::new(), self.version);
+        tokens.document(self);
+        tokens.0.sort_by_key(|t| t.span.offset());
+        tokens.0
+    }
+
+    /// Finds every node name, property key, and type annotation in this
+    /// document (including in children) whose value is `name`, in source
+    /// order. The returned tokens' kinds are always
+    /// [`KdlSemanticTokenKind::NodeName`],
+    /// [`KdlSemanticTokenKind::PropertyKey`], or
+    /// [`KdlSemanticTokenKind::TypeAnnotation`].
+    ///
+    /// Identifiers are compared by value, so `foo` and `"foo"` both match
+    /// `"foo"`, while the spans cover their text as written, including any
+    /// quotes. Together with [`KdlTextEdit`](crate::KdlTextEdit), this is
+    /// enough to implement a rename.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlDocument, KdlSemanticTokenKind};
+    /// let input = "foo foo=(foo)1 {\n    \"foo\" bar=1\n}";
+    /// let doc: KdlDocument = input.parse().unwrap();
+    /// let found = doc
+    ///     .find_identifier("foo")
+    ///     .into_iter()
+    ///     .map(|t| (t.kind, &input[t.span.offset()..][..t.span.len()]))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     found,
+    ///     vec![
+    ///         (KdlSemanticTokenKind::NodeName, "foo"),
+    ///         (KdlSemanticTokenKind::PropertyKey, "foo"),
+    ///         (KdlSemanticTokenKind::TypeAnnotation, "foo"),
+    ///         (KdlSemanticTokenKind::NodeName, "\"foo\""),
+    ///     ]
+    /// );
+    /// ```
+    pub fn find_identifier(&self, name: &str) -> Vec<KdlSemanticToken> {
+        let mut tokens = Tokens(Vec::new(), self.version);
+        tokens.identifiers(self, name);
+        tokens.0.sort_by_key(|t| t.span.offset());
+        tokens.0
+    }
+}
+
+/// The tokens found so far, and the version of the document they're in.
+struct Tokens(Vec<KdlSemanticToken>, KdlVersion);
+
+impl Tokens {
+    fn push(&mut self, kind: KdlSemanticTokenKind, span: SourceSpan) {
+        if !span.is_empty() {
+            self.0.push(KdlSemanticToken { kind, span });
+        }
+    }
+
+    fn ident(&mut self, kind: KdlSemanticTokenKind, ident: &KdlIdentifier) {
+        self.push(kind, ident.span());
+    }
+
+    fn identifiers(&mut self, doc: &KdlDocument, name: &str) {
+        use KdlSemanticTokenKind::*;
+        for node in doc.nodes() {
+            self.matching(TypeAnnotation, node.ty(), name);
+            self.matching(NodeName, Some(node.name()), name);
+            for entry in node.entries() {
+                self.matching(PropertyKey, entry.name(), name);
+                self.matching(TypeAnnotation, entry.ty(), name);
+            }
+            if let Some(children) = node.children() {
+                self.identifiers(children, name);
+            }
+        }
+    }
+
+    fn matching(&mut self, kind: KdlSemanticTokenKind, ident: Option<&KdlIdentifier>, name: &str) {
+        if let Some(ident) = ident.filter(|ident| ident.value() == name) {
+            self.ident(kind, ident);
+        }
+    }
+
+    /// Adds the comments found in `text`, which starts at `offset`.
+    fn trivia(&mut self, text: Option<&str>, offset: usize, in_node: bool) {
+        if let Some(text) = text {
+            for range in parser::comment_ranges(text, in_node, self.1) {
+                self.push(
+                    KdlSemanticTokenKind::Comment,
+                    SourceSpan::from(offset + range.start..offset + range.end),
+                );
+            }
+        }
+    }
+
+    /// Adds the slashdashed `nodes`, which start at `offset`, as comments.
+    fn disabled(&mut self, nodes: &[KdlNode], mut offset: usize) {
+        for node in nodes {
+            let mut text = node.to_string_in(self.1);
+            if node.trailing().is_none() {
+                text.push('\n');
+            }
+            self.trivia(node.slashdash.as_deref(), offset, false);
+            let start = offset + node.slashdash.as_ref().map_or(0, String::len);
+            let end = offset + text.trim_end_matches(['\r', '\n']).len();
+            self.push(KdlSemanticTokenKind::Comment, SourceSpan::from(start..end));
+            offset += text.len();
+        }
+    }
+
+    fn document(&mut self, doc: &KdlDocument) {
+        let start = doc.span().offset();
+        self.trivia(doc.leading(), start, false);
+        for node in doc.nodes() {
+            self.node(node);
+        }
+        let end = start + doc.span().len();
+        let disabled_start = end.saturating_sub(crate::node::written_len(&doc.disabled, self.1));
+        self.disabled(&doc.disabled, disabled_start);
+        self.trivia(doc.trailing(), end, false);
+    }
+
+    fn node(&mut self, node: &KdlNode) {
+        let start = node.span().offset();
+        let end = start + node.span().len();
+        let leading = node.leading().unwrap_or_default();
+        let leading_start = start.saturating_sub(leading.len());
+        self.disabled(
+            &node.disabled,
+            leading_start.saturating_sub(crate::node::written_len(&node.disabled, self.1)),
+        );
+        if let Some(leading) = node.leading() {
+            self.trivia(Some(leading), leading_start, false);
+        }
+        if let Some(ty) = node.ty() {
+            self.ident(KdlSemanticTokenKind::TypeAnnotation, ty);
+        }
+        self.ident(KdlSemanticTokenKind::NodeName, node.name());
+        for entry in node.entries() {
+            self.entry(entry);
+        }
+        if let Some(children) = node.children() {
+            // The children block's span starts just past its opening brace.
+            let brace = children.span().offset().saturating_sub(1);
+            if let Some(before) = node.before_children() {
+                self.trivia(Some(before), brace.saturating_sub(before.len()), true);
+            }
+            self.document(children);
+        }
+        self.trivia(node.trailing(), end, true);
+    }
+
+    fn entry(&mut self, entry: &KdlEntry) {
+        let start = entry.span().offset();
+        let end = start + entry.span().len();
+        if let Some(leading) = entry.leading() {
+            self.trivia(Some(leading), start.saturating_sub(leading.len()), true);
+        }
+        if let Some(name) = entry.name() {
+            self.ident(KdlSemanticTokenKind::PropertyKey, name);
+        }
+        if let Some(ty) = entry.ty() {
+            self.ident(KdlSemanticTokenKind::TypeAnnotation, ty);
+        }
+        let value_len = entry.value_repr_in(self.1).len();
+        let kind = match entry.value() {
+            KdlValue::String(_) | KdlValue::RawString(_) => KdlSemanticTokenKind::String,
+            KdlValue::Bool(_) | KdlValue::Null => KdlSemanticTokenKind::Keyword,
+            _ => KdlSemanticTokenKind::Number,
+        };
+        self.push(kind, SourceSpan::from(end.saturating_sub(value_len)..end));
+        self.trivia(entry.trailing(), end, true);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use KdlSemanticTokenKind::*;
+
+    fn tokens(input: &str) -> Vec<(KdlSemanticTokenKind, &str)> {
+        let doc: KdlDocument = input.parse().unwrap();
+        doc.semantic_tokens()
+            .into_iter()
+            .map(|t| (t.kind, &input[t.span.offset()..][..t.span.len()]))
+            .collect()
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(
+            tokens(r##"n "s" r#"raw"# 0x1F 1.5e3 true null (t)false"##),
+            vec![
+                (NodeName, "n"),
+                (String, r#""s""#),
+                (String, r##"r#"raw"#"##),
+                (Number, "0x1F"),
+                (Number, "1.5e3"),
+                (Keyword, "true"),
+                (Keyword, "null"),
+                (TypeAnnotation, "t"),
+                (Keyword, "false"),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments() {
+        let input = r#"// leading
+/* block /* nested */ */ (ty)"node" /-1 key=2 /* inline */ {
+    /-child 1 { grandchild; }
+    child // trailing
+} /-{ gone; }
+/-
+gone
+last;"#;
+        assert_eq!(
+            tokens(input),
+            vec![
+                (Comment, "// leading"),
+                (Comment, "/* block /* nested */ */"),
+                (TypeAnnotation, "ty"),
+                (NodeName, r#""node""#),
+                (Comment, "/-1"),
+                (PropertyKey, "key"),
+                (Number, "2"),
+                (Comment, "/* inline */"),
+                (Comment, "/-child 1 { grandchild; }"),
+                (NodeName, "child"),
+                (Comment, "// trailing"),
+                (Comment, "/-{ gone; }"),
+                (Comment, "/-\ngone"),
+                (NodeName, "last"),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_identifier() {
+        let input = r#"(foo)node foo="foo" {
+    /-foo
+    foo (foo)1 bar="foo"
+}
+"foo"; foobar"#;
+        let doc: KdlDocument = input.parse().unwrap();
+        let found = doc
+            .find_identifier("foo")
+            .into_iter()
+            .map(|t| {
+                (
+                    t.kind,
+                    t.span.offset(),
+                    &input[t.span.offset()..][..t.span.len()],
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                (TypeAnnotation, 1, "foo"),
+                (PropertyKey, 10, "foo"),
+                (NodeName, 36, "foo"),
+                (TypeAnnotation, 41, "foo"),
+                (NodeName, 59, r#""foo""#),
+            ]
+        );
+        assert_eq!(doc.find_identifier("nope"), vec![]);
+    }
+
+    #[test]
+    fn after_recompute() {
+        let mut doc: KdlDocument = "foo 1".parse().unwrap();
+        doc.nodes_mut().insert(0, KdlNode::new("bar"));
+        doc.recompute_spans();
+        let output = doc.to_string();
+        let kinds = doc
+            .semantic_tokens()
+            .into_iter()
+            .map(|t| (t.kind, &output[t.span.offset()..][..t.span.len()]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![(NodeName, "bar"), (NodeName, "foo"), (Number, "1")]
+        );
+    }
+}
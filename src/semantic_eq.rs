@@ -0,0 +1,216 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::{KdlDocument, KdlEntry, KdlIdentifier, KdlNode};
+
+/// Semantic comparison, for when two documents should count as the same if
+/// they say the same thing, however they're written.
+impl KdlDocument {
+    /// Whether this document says the same thing as `other`.
+    ///
+    /// [`PartialEq`] compares documents exactly, including whitespace,
+    /// comments, and how each value was written, so that two documents are only
+    /// equal if they'd print the same. Semantic equality only compares what's
+    /// left once a document's been read:
+    ///
+    /// * Nodes are compared in order, by name, type annotation, arguments,
+    ///   properties, and children.
+    /// * Arguments are compared in order, each with its type annotation and
+    ///   value.
+    /// * Properties are compared by name, each with its type annotation and
+    ///   value, in any order. When a node has the same property more than once,
+    ///   only the last one counts, as it does in KDL.
+    /// * An empty children block (`{}`) is the same as none at all.
+    /// * Identifiers are compared by value, so `foo`, `"foo"`, and `#"foo"#` are
+    ///   the same.
+    /// * Values are compared by value: `"\u{41}"`, `"A"`, and `#"A"#` are the same
+    ///   string, and `16`, `0x10`, `0o20`, `0b10000`, `16.0`, and `1.6e1` are
+    ///   the same number.
+    ///
+    /// Everything else is ignored: whitespace, comments, slashdashed nodes and
+    /// entries, line continuations, semicolons, the order of properties relative
+    /// to arguments, spans, node IDs, and which version of KDL the document was
+    /// written in.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let left: KdlDocument = "// config\nserver port=0x1f90 host=\"local\"".parse().unwrap();
+    /// let right: KdlDocument = "server host=r\"local\" port=8080 {}".parse().unwrap();
+    /// assert_ne!(left, right);
+    /// assert!(left.semantic_eq(&right));
+    /// assert_eq!(left.semantic_hash(), right.semantic_hash());
+    /// ```
+    pub fn semantic_eq(&self, other: &Self) -> bool {
+        nodes_eq(self.nodes(), other.nodes())
+    }
+
+    /// Hashes this document's meaning, so that documents that are
+    /// [`KdlDocument::semantic_eq`] always have the same hash. Hashes are
+    /// only stable within one build of `kdl`, so they shouldn't be stored.
+    pub fn semantic_hash(&self) -> u64 {
+        let mut state = DefaultHasher::new();
+        hash_nodes(self.nodes(), &mut state);
+        state.finish()
+    }
+}
+
+impl KdlNode {
+    /// Whether this node (and its children) say the same thing as `other`.
+    /// See [`KdlDocument::semantic_eq`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let left: KdlNode = "(u8)node 1 key=2 key=3".parse().unwrap();
+    /// let right: KdlNode = "(\"u8\")\"node\" key=0x3 /-\"skipped\" 1.0".parse().unwrap();
+    /// assert!(left.semantic_eq(&right));
+    /// assert_eq!(left.semantic_hash(), right.semantic_hash());
+    /// ```
+    pub fn semantic_eq(&self, other: &Self) -> bool {
+        ident_eq(self.ty(), other.ty())
+            && self.name().value() == other.name().value()
+            && args(self).count() == args(other).count()
+            && args(self).zip(args(other)).all(|(l, r)| entry_eq(l, r))
+            && {
+                let (left, right) = (props(self), props(other));
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(&right)
+                        .all(|((lk, l), (rk, r))| lk == rk && entry_eq(l, r))
+            }
+            && nodes_eq(children(self), children(other))
+    }
+
+    /// Hashes this node's meaning, so that nodes that are
+    /// [`KdlNode::semantic_eq`] always have the same hash. See
+    /// [`KdlDocument::semantic_hash`].
+    pub fn semantic_hash(&self) -> u64 {
+        let mut state = DefaultHasher::new();
+        hash_node(self, &mut state);
+        state.finish()
+    }
+}
+
+fn nodes_eq(left: &[KdlNode], right: &[KdlNode]) -> bool {
+    left.len() == right.len() && left.iter().zip(right).all(|(l, r)| l.semantic_eq(r))
+}
+
+fn ident_eq(left: Option<&KdlIdentifier>, right: Option<&KdlIdentifier>) -> bool {
+    left.map(KdlIdentifier::value) == right.map(KdlIdentifier::value)
+}
+
+fn entry_eq(left: &KdlEntry, right: &KdlEntry) -> bool {
+    ident_eq(left.ty(), right.ty()) && left.value().same_value(right.value())
+}
+
+pub(crate) fn args(node: &KdlNode) -> impl Iterator<Item = &KdlEntry> {
+    node.entries().iter().filter(|e| e.name().is_none())
+}
+
+/// The properties that count, sorted by name.
+pub(crate) fn props(node: &KdlNode) -> Vec<(&str, &KdlEntry)> {
+    let mut props: Vec<(&str, &KdlEntry)> = Vec::new();
+    // Going backwards, so the first of each name seen is the last one set.
+    for entry in node.entries().iter().rev() {
+        if let Some(name) = entry.name() {
+            if !props.iter().any(|(key, _)| *key == name.value()) {
+                props.push((name.value(), entry));
+            }
+        }
+    }
+    props.sort_by_key(|(key, _)| *key);
+    props
+}
+
+fn children(node: &KdlNode) -> &[KdlNode] {
+    node.children().map_or(&[], KdlDocument::nodes)
+}
+
+fn hash_nodes(nodes: &[KdlNode], state: &mut impl Hasher) {
+    nodes.len().hash(state);
+    for node in nodes {
+        hash_node(node, state);
+    }
+}
+
+fn hash_node(node: &KdlNode, state: &mut impl Hasher) {
+    node.ty().map(KdlIdentifier::value).hash(state);
+    node.name().value().hash(state);
+    args(node).count().hash(state);
+    for arg in args(node) {
+        hash_entry(arg, state);
+    }
+    let props = props(node);
+    props.len().hash(state);
+    for (key, prop) in props {
+        key.hash(state);
+        hash_entry(prop, state);
+    }
+    hash_nodes(children(node), state);
+}
+
+fn hash_entry(entry: &KdlEntry, state: &mut impl Hasher) {
+    entry.ty().map(KdlIdentifier::value).hash(state);
+    entry.value().hash_value(state);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn same(left: &str, right: &str) -> bool {
+        let left: KdlDocument = left.parse().unwrap();
+        let right: KdlDocument = right.parse().unwrap();
+        let eq = left.semantic_eq(&right);
+        assert_eq!(eq, right.semantic_eq(&left));
+        if eq {
+            assert_eq!(left.semantic_hash(), right.semantic_hash());
+        }
+        eq
+    }
+
+    #[test]
+    fn ignores_trivia() {
+        assert!(same("a 1 2;b", "/* c */ a \\\n  1 /-\"x\" 2 // d\n\nb {}"));
+        assert!(same("a {\n    b\n}", "a { b; }"));
+        assert!(same("/-a\nb", "b"));
+        assert!(!same("a {}", "a {b;}"));
+    }
+
+    #[test]
+    fn representations() {
+        assert!(same("\"a\" \"b\"", "a r\"b\""));
+        assert!(same(
+            "a 16 0x10 0o20 0b10000 16.0 1.6e1",
+            "a 16 16 16 16 16 16"
+        ));
+        assert!(same("a \"\\u{41}\"", "a \"A\""));
+        assert!(same("(u8)a (i32)1", "(\"u8\")a (i32)0x1"));
+        assert!(same("a 0.5 -0.0", "a 5e-1 0"));
+        assert!(!same("a 1", "a \"1\""));
+        assert!(!same("a (u8)1", "a 1"));
+        assert!(!same("a 1 2", "a 2 1"));
+        assert!(!same("a null", "a false"));
+    }
+
+    #[test]
+    fn properties() {
+        assert!(same("a x=1 2 y=3", "a 2 y=3 x=1"));
+        assert!(same("a x=1 x=2", "a x=2"));
+        assert!(!same("a x=1 x=2", "a x=1"));
+        assert!(!same("a x=1", "a x=1 y=1"));
+        assert!(!same("a x=1", "a 1"));
+    }
+
+    #[test]
+    fn ignores_version() {
+        let v1: KdlDocument = "a true null r\"x\" b=\"c\"".parse().unwrap();
+        let v2 = KdlDocument::parse_auto("/- kdl-version 2\na #true #null #\"x\"# b=c").unwrap();
+        assert!(v1.semantic_eq(&v2));
+        assert_eq!(v1.semantic_hash(), v2.semantic_hash());
+    }
+}
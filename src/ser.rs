@@ -0,0 +1,228 @@
+//! [`serde::Serialize`] support for diagnostics, enabled by the `serde`
+//! feature.
+//!
+//! Diagnostics serialize to a stable, flat shape that doesn't require the
+//! receiving end to know anything about miette:
+//!
+//! ```json
+//! {
+//!   "code": "kdl::invalid_number",
+//!   "severity": "error",
+//!   "message": "Invalid number.",
+//!   "label": "invalid float",
+//!   "help": "Floating point numbers must be base 10, and have numbers after the decimal point.",
+//!   "source": "config/app.kdl",
+//!   "span": { "offset": 4, "length": 2, "line": 1, "column": 5 },
+//!   "related": null
+//! }
+//! ```
+//!
+//! `source` is the name given through [`crate::ParseOptions::source_name`] or
+//! `with_source_name`, or `null` if there isn't one. `line` and `column` are
+//! 1-based, with `column` counted in characters. `related` holds an optional
+//! secondary `label` and `span`, such as where the input ran out while
+//! reading an unterminated string.
+//! `offset` and `length` are in bytes. The source text itself is not
+//! included.
+
+use miette::SourceSpan;
+use serde::ser::{Error, Serialize, SerializeStruct, Serializer};
+
+use crate::{KdlError, KdlErrorKind, KdlWarning, KdlWarningKind};
+
+struct Span<'a> {
+    input: &'a str,
+    span: SourceSpan,
+}
+
+impl Serialize for Span<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let offset = self.span.offset().min(self.input.len());
+        let before = self.input.get(..offset).ok_or_else(|| {
+            S::Error::custom(format!(
+                "span offset {} is not on a character boundary",
+                offset
+            ))
+        })?;
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .map(|l| l.chars().count())
+            .unwrap_or(0)
+            + 1;
+        let mut state = serializer.serialize_struct("Span", 4)?;
+        state.serialize_field("offset", &self.span.offset())?;
+        state.serialize_field("length", &self.span.len())?;
+        state.serialize_field("line", &line)?;
+        state.serialize_field("column", &column)?;
+        state.end()
+    }
+}
+
+struct Related<'a> {
+    label: Option<&'static str>,
+    span: Span<'a>,
+}
+
+impl Serialize for Related<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Related", 2)?;
+        state.serialize_field("label", &self.label)?;
+        state.serialize_field("span", &self.span)?;
+        state.end()
+    }
+}
+
+struct Shape<'a> {
+    name: &'static str,
+    source: Option<&'a str>,
+    related: Option<Related<'a>>,
+    code: &'static str,
+    severity: &'static str,
+    message: String,
+    label: Option<&'static str>,
+    help: Option<&'static str>,
+    span: Span<'a>,
+}
+
+impl Serialize for Shape<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct(self.name, 8)?;
+        state.serialize_field("code", self.code)?;
+        state.serialize_field("severity", self.severity)?;
+        state.serialize_field("message", &self.message)?;
+        state.serialize_field("label", &self.label)?;
+        state.serialize_field("help", &self.help)?;
+        state.serialize_field("source", &self.source)?;
+        state.serialize_field("span", &self.span)?;
+        state.serialize_field("related", &self.related)?;
+        state.end()
+    }
+}
+
+impl Serialize for KdlError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Shape {
+            name: "KdlError",
+            source: self.input.name.as_deref(),
+            related: self.related_span.map(|span| Related {
+                label: self.related_label,
+                span: Span {
+                    input: &self.input.text,
+                    span,
+                },
+            }),
+            code: self.kind.code_str(),
+            severity: "error",
+            message: self.kind.to_string(),
+            label: self.label,
+            help: self.help,
+            span: Span {
+                input: &self.input.text,
+                span: self.span,
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+impl Serialize for KdlErrorKind {
+    /// Error kinds serialize as their stable code (e.g.
+    /// `"kdl::invalid_number"`).
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code_str())
+    }
+}
+
+impl Serialize for KdlWarning {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Shape {
+            name: "KdlWarning",
+            source: self.input.name.as_deref(),
+            related: None,
+            code: self.kind.code_str(),
+            severity: "warning",
+            message: self.kind.to_string(),
+            label: self.label,
+            help: self.help,
+            span: Span {
+                input: &self.input.text,
+                span: self.span,
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+impl Serialize for KdlWarningKind {
+    /// Warning kinds serialize as their stable code (e.g.
+    /// `"kdl::warning::bom"`).
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::KdlDocument;
+
+    #[test]
+    fn error_shape() {
+        let err = "foo {\n  bar 1.\n}".parse::<KdlDocument>().unwrap_err();
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "code": "kdl::invalid_number",
+                "severity": "error",
+                "message": "Invalid number.",
+                "label": "invalid float",
+                "help": "Floating point numbers must be base 10, and have numbers after the decimal point.",
+                "source": null,
+                "span": { "offset": 12, "length": 2, "line": 2, "column": 7 },
+                "related": null,
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(&err.kind).unwrap(),
+            serde_json::json!("kdl::invalid_number")
+        );
+    }
+
+    #[test]
+    fn warning_shape() {
+        let (_, warnings) = KdlDocument::parse_with_warnings("\u{FEFF}foo").unwrap();
+        let json = serde_json::to_value(&warnings).unwrap();
+        assert_eq!(json[0]["code"], "kdl::warning::bom");
+        assert_eq!(json[0]["severity"], "warning");
+        assert_eq!(json[0]["span"]["offset"], 0);
+    }
+
+    #[test]
+    fn related_span() {
+        let err = "foo \"bar".parse::<KdlDocument>().unwrap_err();
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(
+            json["related"],
+            serde_json::json!({
+                "label": "input ends here",
+                "span": { "offset": 7, "length": 1, "line": 1, "column": 8 },
+            })
+        );
+    }
+
+    #[test]
+    fn mid_character_span() {
+        let mut err = "é 1.".parse::<KdlDocument>().unwrap_err();
+        err.span = (1, 1).into();
+        assert!(serde_json::to_value(&err).is_err());
+    }
+
+    #[test]
+    fn named_source() {
+        let err = "foo 1.".parse::<KdlDocument>().unwrap_err();
+        let json = serde_json::to_value(err.with_source_name("app.kdl")).unwrap();
+        assert_eq!(json["source"], "app.kdl");
+    }
+}
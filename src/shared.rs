@@ -0,0 +1,309 @@
+use std::{fmt, sync::Arc};
+
+use crate::{KdlDocument, KdlEntry, KdlIdentifier, KdlNode, NodeKey};
+
+/// A persistent, immutable version of a [`KdlDocument`], made with
+/// [`KdlDocument::freeze`], for keeping lots of versions of a document
+/// around without copying all of it for each one.
+///
+/// Cloning a shared document is `O(1)`: clones point at the same nodes.
+/// Editing one copies just the path from the document down to what's
+/// changed, so the new version shares every untouched subtree with the
+/// versions it came from, which are left as they were. Turn it back into an
+/// ordinary document with [`KdlSharedDocument::thaw`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::{KdlDocument, KdlEntry};
+/// let doc: KdlDocument = "server { port 80; }\nclient { retries 3; }".parse().unwrap();
+/// let v1 = doc.freeze();
+///
+/// let mut v2 = v1.clone();
+/// let server = v2.get_mut("server").unwrap();
+/// let port = server.children_mut().as_mut().unwrap().get_mut("port").unwrap();
+/// port.entries_mut()[0] = KdlEntry::new(8080);
+///
+/// assert_eq!(v1.to_string(), "server { port 80; }\nclient { retries 3; }");
+/// assert_eq!(v2.to_string(), "server { port 8080; }\nclient { retries 3; }");
+/// // `client` wasn't touched, so both versions share it.
+/// assert!(v1.get("client").unwrap().ptr_eq(v2.get("client").unwrap()));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct KdlSharedDocument(Arc<SharedDocument>);
+
+#[derive(Debug, Clone, Default)]
+struct SharedDocument {
+    /// The document, without its nodes.
+    document: KdlDocument,
+    nodes: Vec<KdlSharedNode>,
+}
+
+/// A node in a [`KdlSharedDocument`]. Like the document, cloning one is
+/// `O(1)`, and editing one copies it, but not its children.
+#[derive(Debug, Clone)]
+pub struct KdlSharedNode(Arc<SharedNode>);
+
+#[derive(Debug, Clone)]
+struct SharedNode {
+    /// The node, without its children.
+    node: KdlNode,
+    children: Option<KdlSharedDocument>,
+}
+
+impl KdlDocument {
+    /// Turns this document into a [`KdlSharedDocument`], which is cheap to
+    /// clone and edit without losing earlier versions.
+    pub fn freeze(mut self) -> KdlSharedDocument {
+        let nodes = std::mem::take(&mut self.nodes)
+            .into_vec()
+            .into_iter()
+            .map(KdlSharedNode::from)
+            .collect();
+        KdlSharedDocument(Arc::new(SharedDocument {
+            document: self,
+            nodes,
+        }))
+    }
+}
+
+impl KdlSharedDocument {
+    /// Creates a new, empty shared document.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes an ordinary [`KdlDocument`] out of this version. This copies
+    /// the whole document.
+    pub fn thaw(&self) -> KdlDocument {
+        let mut document = self.0.document.clone();
+        document.nodes = self
+            .0
+            .nodes
+            .iter()
+            .map(KdlSharedNode::thaw)
+            .collect::<Vec<_>>()
+            .into();
+        document
+    }
+
+    /// Gets this document's nodes.
+    pub fn nodes(&self) -> &[KdlSharedNode] {
+        &self.0.nodes
+    }
+
+    /// Gets a mutable reference to this document's nodes. If this version
+    /// shares them with any others, the list is copied first, but not the
+    /// nodes in it.
+    pub fn nodes_mut(&mut self) -> &mut Vec<KdlSharedNode> {
+        &mut Arc::make_mut(&mut self.0).nodes
+    }
+
+    /// Gets the first node named `name`.
+    pub fn get(&self, name: &str) -> Option<&KdlSharedNode> {
+        self.0
+            .nodes
+            .iter()
+            .find(|n| n.node().name().value() == name)
+    }
+
+    /// Gets a mutable reference to the first node named `name`. See
+    /// [`KdlSharedDocument::nodes_mut`].
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut KdlSharedNode> {
+        let idx = self
+            .0
+            .nodes
+            .iter()
+            .position(|n| n.node().name().value() == name)?;
+        Some(&mut self.nodes_mut()[idx])
+    }
+
+    /// Whether this and `other` are the same version, and not just equal.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl KdlSharedNode {
+    /// Makes an ordinary [`KdlNode`] out of this one, including its
+    /// children. This copies the whole node.
+    pub fn thaw(&self) -> KdlNode {
+        let mut node = self.0.node.clone();
+        node.children = self.0.children.as_ref().map(KdlSharedDocument::thaw);
+        node
+    }
+
+    /// Gets this node, without its children. Its [`KdlNode::children`] is
+    /// always `None`, even if it has a children block: the children are only
+    /// reachable through [`KdlSharedNode::children`].
+    pub fn node(&self) -> &KdlNode {
+        &self.0.node
+    }
+
+    /// Sets this node's name, copying the node first if it's shared. See
+    /// [`KdlNode::set_name`].
+    pub fn set_name(&mut self, name: impl Into<KdlIdentifier>) {
+        self.node_mut().set_name(name)
+    }
+
+    /// Sets this node's type annotation, copying the node first if it's
+    /// shared. See [`KdlNode::set_ty`].
+    pub fn set_ty(&mut self, ty: impl Into<KdlIdentifier>) {
+        self.node_mut().set_ty(ty)
+    }
+
+    /// Gets a mutable reference to this node's type annotation, copying the
+    /// node first if it's shared.
+    pub fn ty_mut(&mut self) -> &mut Option<KdlIdentifier> {
+        self.node_mut().ty_mut()
+    }
+
+    /// Gets a mutable reference to this node's entries, copying the node
+    /// first if it's shared, but not its children.
+    pub fn entries_mut(&mut self) -> &mut Vec<KdlEntry> {
+        self.node_mut().entries_mut()
+    }
+
+    /// Fetches a mutable reference to an entry by key, copying the node
+    /// first if it's shared. See [`KdlNode::entry_mut`].
+    pub fn entry_mut(&mut self, key: impl Into<NodeKey>) -> Option<&mut KdlEntry> {
+        self.node_mut().entry_mut(key)
+    }
+
+    /// Adds an entry to the end of this node, copying the node first if it's
+    /// shared. See [`KdlNode::push`].
+    pub fn push(&mut self, entry: impl Into<KdlEntry>) {
+        self.node_mut().push(entry)
+    }
+
+    /// Removes an entry by key, copying the node first if it's shared. See
+    /// [`KdlNode::remove`].
+    pub fn remove(&mut self, key: impl Into<NodeKey>) -> Option<KdlEntry> {
+        self.node_mut().remove(key)
+    }
+
+    /// Gets this node's children block, if it has one.
+    pub fn children(&self) -> Option<&KdlSharedDocument> {
+        self.0.children.as_ref()
+    }
+
+    /// Gets a mutable reference to this node's children block, copying the
+    /// node first if it's shared, but not the children.
+    pub fn children_mut(&mut self) -> &mut Option<KdlSharedDocument> {
+        &mut Arc::make_mut(&mut self.0).children
+    }
+
+    /// Whether this and `other` are the same version of a node, and not just
+    /// equal.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// The node without its children, copied first if it's shared. This
+    /// isn't public, since anything it set as the node's children would be
+    /// lost: those live in `children`.
+    fn node_mut(&mut self) -> &mut KdlNode {
+        &mut Arc::make_mut(&mut self.0).node
+    }
+}
+
+impl From<KdlDocument> for KdlSharedDocument {
+    fn from(document: KdlDocument) -> Self {
+        document.freeze()
+    }
+}
+
+impl From<KdlNode> for KdlSharedNode {
+    fn from(mut node: KdlNode) -> Self {
+        let children = node.children.take().map(KdlDocument::freeze);
+        KdlSharedNode(Arc::new(SharedNode { node, children }))
+    }
+}
+
+impl PartialEq for KdlSharedDocument {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || (self.0.document == other.0.document && self.0.nodes == other.0.nodes)
+    }
+}
+
+impl Eq for KdlSharedDocument {}
+
+impl PartialEq for KdlSharedNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || (self.0.node == other.0.node && self.0.children == other.0.children)
+    }
+}
+
+impl Eq for KdlSharedNode {}
+
+impl fmt::Display for KdlSharedDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.thaw())
+    }
+}
+
+impl fmt::Display for KdlSharedNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.thaw())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sharing() -> miette::Result<()> {
+        let input = "// top\na 1 {\n    b 2 {\n        c 3\n    }\n    d 4\n}\ne 5 { f; }\n";
+        let doc: KdlDocument = input.parse()?;
+        let v1 = doc.clone().freeze();
+        assert_eq!(v1.thaw(), doc);
+        assert_eq!(v1.to_string(), input);
+
+        let v2 = v1.clone();
+        assert!(v1.ptr_eq(&v2));
+
+        let mut v3 = v2.clone();
+        let a = v3.get_mut("a").unwrap();
+        let b = a.children_mut().as_mut().unwrap().get_mut("b").unwrap();
+        b.push(("x", true));
+        assert_eq!(v1, v2);
+        assert_ne!(v1, v3);
+        assert_eq!(v1.to_string(), input);
+        assert_eq!(
+            v3.to_string(),
+            "// top\na 1 {\n    b 2 x=true {\n        c 3\n    }\n    d 4\n}\ne 5 { f; }\n"
+        );
+
+        // Only the path down to `b` was copied.
+        let (a1, a3) = (v1.get("a").unwrap(), v3.get("a").unwrap());
+        assert!(!a1.ptr_eq(a3));
+        assert!(v1.get("e").unwrap().ptr_eq(v3.get("e").unwrap()));
+        let (c1, c3) = (a1.children().unwrap(), a3.children().unwrap());
+        assert!(c1.get("d").unwrap().ptr_eq(c3.get("d").unwrap()));
+        let (b1, b3) = (c1.get("b").unwrap(), c3.get("b").unwrap());
+        assert!(!b1.ptr_eq(b3));
+        assert!(b1.children().unwrap().ptr_eq(b3.children().unwrap()));
+
+        assert!(b3.node().children().is_none());
+        let mut v4 = v3.clone();
+        let e = v4.get_mut("e").unwrap();
+        e.set_name("g");
+        e.set_ty("t");
+        *e.entry_mut(0).unwrap() = KdlEntry::new(6);
+        e.push(7);
+        assert_eq!(e.remove(1), Some(KdlEntry::new(7)));
+        assert!(e.children().is_some());
+        assert_eq!(e.to_string(), "(t)g 6 { f; }\n");
+        assert!(e
+            .children()
+            .unwrap()
+            .ptr_eq(v3.get("e").unwrap().children().unwrap()));
+        assert_eq!(v3.get("e").unwrap().to_string(), "e 5 { f; }\n");
+
+        v3.nodes_mut().remove(0);
+        assert_eq!(v3.thaw().to_string(), "e 5 { f; }\n");
+        assert_eq!(KdlSharedDocument::new().to_string(), "");
+        Ok(())
+    }
+}
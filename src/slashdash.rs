@@ -0,0 +1,540 @@
+use std::ops::Range;
+
+use crate::{
+    parser::{self, KdlParser},
+    KdlDocument, KdlEntry, KdlNode, KdlVersion, NodeKey, ParseOptions,
+};
+
+impl KdlDocument {
+    /// Gets the nodes in this document that are commented out with a
+    /// slashdash (`/-`), in the order they appear.
+    ///
+    /// These aren't part of [`KdlDocument::nodes`]: use
+    /// [`KdlDocument::enable_node`] to turn one back into a real node.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = "foo 1\n/-bar 2\n".parse().unwrap();
+    /// assert_eq!(doc.nodes().len(), 1);
+    /// let disabled = doc.disabled_nodes();
+    /// assert_eq!(disabled[0].name().value(), "bar");
+    /// assert!(disabled[0].is_disabled());
+    /// ```
+    pub fn disabled_nodes(&self) -> Vec<&KdlNode> {
+        self.nodes
+            .iter()
+            .flat_map(|node| &node.disabled)
+            .chain(&self.disabled)
+            .collect()
+    }
+
+    /// Comments out the node at `index` in [`KdlDocument::nodes`] by
+    /// putting a slashdash in front of it, keeping its text as it was.
+    /// Returns `false` if there's no such node.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let mut doc: KdlDocument = "foo 1\nbar 2\n".parse().unwrap();
+    /// assert!(doc.disable_node(0));
+    /// assert_eq!(doc.to_string(), "/-foo 1\nbar 2\n");
+    /// assert!(doc.get("foo").is_none());
+    /// ```
+    pub fn disable_node(&mut self, index: usize) -> bool {
+        if index >= self.nodes.len() {
+            return false;
+        }
+        let mut node = self.nodes.remove(index);
+        let mut disabled = std::mem::take(&mut node.disabled);
+        match node.leading.take() {
+            Some(leading) => {
+                node.slashdash = Some(leading);
+                node.leading = Some(String::new());
+            }
+            None => node.slashdash = Some(String::new()),
+        }
+        disabled.push(node);
+        let after = match self.nodes.get_mut(index) {
+            Some(next) => &mut next.disabled,
+            None => &mut self.disabled,
+        };
+        disabled.append(after);
+        *after = disabled;
+        true
+    }
+
+    /// Uncomments the node at `index` in [`KdlDocument::disabled_nodes`],
+    /// turning it back into a real node in the same place. Returns `false`
+    /// if there's no such node.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let mut doc: KdlDocument = "foo 1\n/- bar 2 // two\nbaz 3\n".parse().unwrap();
+    /// assert!(doc.enable_node(0));
+    /// assert_eq!(doc.to_string(), "foo 1\nbar 2 // two\nbaz 3\n");
+    /// assert_eq!(doc.get_arg("bar"), Some(&2.into()));
+    /// ```
+    pub fn enable_node(&mut self, mut index: usize) -> bool {
+        let mut slot = 0;
+        loop {
+            let disabled = match self.nodes.get(slot) {
+                Some(node) => node.disabled.len(),
+                None if slot == self.nodes.len() => self.disabled.len(),
+                None => return false,
+            };
+            if index < disabled {
+                break;
+            }
+            index -= disabled;
+            slot += 1;
+        }
+        let disabled = match self.nodes.get_mut(slot) {
+            Some(node) => &mut node.disabled,
+            None => &mut self.disabled,
+        };
+        let mut before = disabled.drain(..=index).collect::<Vec<_>>();
+        let mut node = before.pop().expect("there's a node at this index");
+        let slashdash = node.slashdash.take().unwrap_or_default();
+        let had_leading = node.leading.is_some();
+        // Whatever space there was between the slashdash and the node goes
+        // away along with it.
+        let leading = node.leading.take().filter(|l| !l.trim().is_empty());
+        if let Some(first) = node.disabled.first_mut() {
+            let text = first.slashdash.get_or_insert_with(String::new);
+            text.insert_str(0, &slashdash);
+            node.leading = leading;
+        } else if had_leading || !slashdash.is_empty() {
+            node.leading = Some(slashdash + leading.as_deref().unwrap_or_default());
+        }
+        before.append(&mut node.disabled);
+        node.disabled = before;
+        self.nodes.insert(slot, node);
+        true
+    }
+}
+
+/// Where a node keeps the text around its entries.
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    Leading(usize),
+    Trailing(usize),
+    BeforeChildren,
+    NodeTrailing,
+}
+
+impl KdlNode {
+    /// Gets the arguments and properties of this node that are commented
+    /// out with a slashdash (`/-`), in the order they appear.
+    ///
+    /// These aren't part of [`KdlNode::entries`]: use
+    /// [`KdlNode::enable_entry`] to turn one back into a real entry.
+    ///
+    /// The slashdashed text is read as the KDL version the node was parsed
+    /// as, or KDL 1.0 for nodes made with [`KdlNode::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let node: KdlNode = "foo 1 /-2 /-key=3".parse().unwrap();
+    /// let disabled = node.disabled_entries();
+    /// assert_eq!(disabled[0].value(), &2.into());
+    /// assert_eq!(disabled[1].name().unwrap().value(), "key");
+    /// ```
+    pub fn disabled_entries(&self) -> Vec<KdlEntry> {
+        self.slashdashed_entries()
+            .into_iter()
+            .map(|(_, _, entry)| entry)
+            .collect()
+    }
+
+    /// Comments out an entry by putting a slashdash in front of it, keeping
+    /// its text as it was. Number keys look up arguments and string keys
+    /// look up properties, the same as [`KdlNode::entry`]. Returns `false`
+    /// if there's no such entry.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let mut node: KdlNode = "foo 1 debug=true 2".parse().unwrap();
+    /// assert!(node.disable_entry("debug"));
+    /// assert!(node.disable_entry(1));
+    /// assert_eq!(node.to_string(), "foo 1 /-debug=true /-2");
+    /// ```
+    pub fn disable_entry(&mut self, key: impl Into<NodeKey>) -> bool {
+        let idx = match self.entry_index(&key.into()) {
+            Some(idx) => idx,
+            None => return false,
+        };
+        let mut entry = self.entries.remove(idx);
+        let mut text = entry.leading.take().unwrap_or_else(|| " ".into());
+        text.push_str("/-");
+        text.push_str(&entry.to_string_in(self.version));
+        if let Some(next) = self.entries.get_mut(idx) {
+            text.push_str(next.leading.as_deref().unwrap_or(" "));
+            next.leading = Some(text);
+        } else if let Some(prev) = idx.checked_sub(1).and_then(|i| self.entries.get_mut(i)) {
+            let had_trailing = prev.trailing.is_some();
+            prev.trailing = Some(prev.trailing.take().unwrap_or_default() + &text);
+            if !had_trailing && self.children.is_some() && self.before_children.is_none() {
+                self.before_children = Some(" ".into());
+            }
+        } else if self.children.is_some() {
+            text.push_str(self.before_children.as_deref().unwrap_or(" "));
+            self.before_children = Some(text);
+        } else {
+            // Nodes without trailing text get a line break when they're
+            // written out, so this one needs its own now.
+            text.push_str(self.trailing.as_deref().unwrap_or("\n"));
+            self.trailing = Some(text);
+        }
+        true
+    }
+
+    /// Uncomments a slashdashed entry, turning it back into a real entry in
+    /// the same place. Number keys look up slashdashed arguments (`0` is the
+    /// first slashdashed argument) and string keys look up slashdashed
+    /// properties. Returns `false` if there's no such entry.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let mut node: KdlNode = "foo 1 /-2 3 /-debug=true".parse().unwrap();
+    /// assert!(node.enable_entry(0));
+    /// assert!(node.enable_entry("debug"));
+    /// assert_eq!(node.to_string(), "foo 1 2 3 debug=true");
+    /// assert_eq!(node.get(1), Some(&2.into()));
+    /// ```
+    pub fn enable_entry(&mut self, key: impl Into<NodeKey>) -> bool {
+        let key = key.into();
+        let mut args = 0;
+        let found = self
+            .slashdashed_entries()
+            .into_iter()
+            .find(|(_, _, entry)| match (&key, &entry.name) {
+                (NodeKey::Key(key), Some(name)) => key.value() == name.value(),
+                (NodeKey::Index(idx), None) => {
+                    args += 1;
+                    args - 1 == *idx
+                }
+                _ => false,
+            });
+        let (slot, range, mut entry) = match found {
+            Some(found) => found,
+            None => return false,
+        };
+        let text = self.slot_mut(slot).take().unwrap_or_default();
+        let mut leading = text[..range.start].to_string();
+        leading.push_str(entry.leading.as_deref().unwrap_or_default());
+        if leading.is_empty() {
+            leading.push(' ');
+        }
+        entry.leading = Some(leading);
+        let after = text[range.end..].to_string();
+        match slot {
+            Slot::Leading(i) => {
+                let after = if after.is_empty() { " ".into() } else { after };
+                self.entries[i].leading = Some(after);
+                self.entries.insert(i, entry);
+            }
+            Slot::Trailing(i) => {
+                self.entries[i].trailing = Some(String::new());
+                entry.trailing = Some(after);
+                self.entries.insert(i + 1, entry);
+            }
+            Slot::BeforeChildren => {
+                self.before_children = Some(after);
+                self.entries.push(entry);
+            }
+            Slot::NodeTrailing => {
+                // Just the line break `disable_entry` added, which the node
+                // gets anyway without trailing text.
+                self.trailing = Some(after).filter(|after| after != "\n");
+                self.entries.push(entry);
+            }
+        }
+        true
+    }
+
+    fn entry_index(&self, key: &NodeKey) -> Option<usize> {
+        match key {
+            NodeKey::Key(key) => self.entries.iter().rposition(|entry| {
+                entry.name.as_ref().map(|name| name.value()) == Some(key.value())
+            }),
+            NodeKey::Index(idx) => self
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| entry.name.is_none())
+                .nth(*idx)
+                .map(|(i, _)| i),
+        }
+    }
+
+    /// Calls `rewrite` with each of this node's slashdashed entries, and
+    /// writes the ones it returns `true` for back in place of their old
+    /// text, still slashdashed.
+    pub(crate) fn rewrite_disabled_entries(
+        &mut self,
+        mut rewrite: impl FnMut(&KdlNode, &mut KdlEntry) -> bool,
+    ) {
+        let mut changed = Vec::new();
+        for (slot, range, mut entry) in self.slashdashed_entries() {
+            if rewrite(self, &mut entry) {
+                changed.push((slot, range, entry));
+            }
+        }
+        // Later ranges first, so the earlier ones in the same slot still
+        // line up.
+        for (slot, range, entry) in changed.into_iter().rev() {
+            let entry = entry.to_string_in(self.version);
+            if let Some(text) = self.slot_mut(slot) {
+                text.replace_range(range, &format!("/-{}", entry));
+            }
+        }
+    }
+
+    fn slashdashed_entries(&self) -> Vec<(Slot, Range<usize>, KdlEntry)> {
+        let mut slots = Vec::new();
+        for i in 0..self.entries.len() {
+            slots.push(Slot::Leading(i));
+            slots.push(Slot::Trailing(i));
+        }
+        slots.push(Slot::BeforeChildren);
+        slots.push(Slot::NodeTrailing);
+
+        let mut found = Vec::new();
+        for slot in slots {
+            let text = match self.slot(slot) {
+                Some(text) if text.contains("/-") => text,
+                _ => continue,
+            };
+            for range in parser::comment_ranges(text, true, self.version) {
+                let entry = text[range.clone()]
+                    .strip_prefix("/-")
+                    .and_then(|item| parse_entry(item, self.version));
+                if let Some(entry) = entry {
+                    found.push((slot, range, entry));
+                }
+            }
+        }
+        found
+    }
+
+    fn slot(&self, slot: Slot) -> Option<&str> {
+        match slot {
+            Slot::Leading(i) => self.entries[i].leading.as_deref(),
+            Slot::Trailing(i) => self.entries[i].trailing.as_deref(),
+            Slot::BeforeChildren => self.before_children.as_deref(),
+            Slot::NodeTrailing => self.trailing.as_deref(),
+        }
+    }
+
+    fn slot_mut(&mut self, slot: Slot) -> &mut Option<String> {
+        match slot {
+            Slot::Leading(i) => &mut self.entries[i].leading,
+            Slot::Trailing(i) => &mut self.entries[i].trailing,
+            Slot::BeforeChildren => &mut self.before_children,
+            Slot::NodeTrailing => &mut self.trailing,
+        }
+    }
+}
+
+fn options(version: KdlVersion) -> ParseOptions {
+    ParseOptions {
+        version,
+        ..ParseOptions::default()
+    }
+}
+
+/// Parses the text after an entry's slashdash, or returns `None` if it's a
+/// slashdashed children block.
+fn parse_entry(item: &str, version: KdlVersion) -> Option<KdlEntry> {
+    let kdl_parser = KdlParser::with_options(item, &options(version));
+    kdl_parser
+        .parse(parser::entry_maybe_space(&kdl_parser))
+        .ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::KdlValue;
+
+    #[test]
+    fn nodes() {
+        let input = "foo 1\n/-bar 2; /-baz\nparent {\n    /-child\n    other\n}\n/-last 4\n";
+        let mut doc: KdlDocument = input.parse().unwrap();
+        let names = |doc: &KdlDocument| -> Vec<String> {
+            doc.disabled_nodes()
+                .iter()
+                .map(|node| node.name().value().to_string())
+                .collect()
+        };
+        assert_eq!(names(&doc), ["bar", "baz", "last"]);
+        assert_eq!(names(doc.nodes()[1].children().unwrap()), ["child"]);
+        assert!(doc.disabled_nodes().iter().all(|node| node.is_disabled()));
+        assert!(!doc.nodes()[0].is_disabled());
+        assert_eq!(doc.to_string(), input);
+
+        assert!(doc.enable_node(2));
+        assert_eq!(doc.get_arg("last"), Some(&4.into()));
+        assert!(doc.enable_node(0));
+        assert_eq!(
+            doc.to_string(),
+            "foo 1\nbar 2; /-baz\nparent {\n    /-child\n    other\n}\nlast 4\n"
+        );
+        assert_eq!(names(&doc), ["baz"]);
+        assert!(!doc.enable_node(1));
+
+        let children = doc.nodes_mut()[2].children_mut().as_mut().unwrap();
+        assert!(children.enable_node(0));
+        assert!(children.disable_node(1));
+        assert!(!children.disable_node(1));
+        assert_eq!(
+            doc.to_string(),
+            "foo 1\nbar 2; /-baz\nparent {\n    child\n    /-other\n}\nlast 4\n"
+        );
+
+        assert!(doc.disable_node(3));
+        assert!(doc.disable_node(0));
+        assert_eq!(
+            doc.to_string(),
+            "/-foo 1\nbar 2; /-baz\nparent {\n    child\n    /-other\n}\n/-last 4\n"
+        );
+        let reparsed: KdlDocument = doc.to_string().parse().unwrap();
+        assert_eq!(names(&reparsed), ["foo", "baz", "last"]);
+        assert_eq!(reparsed, doc);
+    }
+
+    #[test]
+    fn same_names() {
+        let mut doc: KdlDocument = "a 1\n/-a 2\na 3\n/-a 4\n".parse().unwrap();
+        assert!(doc.enable_node(1));
+        assert_eq!(doc.to_string(), "a 1\n/-a 2\na 3\na 4\n");
+        assert!(doc.disable_node(1));
+        assert_eq!(doc.to_string(), "a 1\n/-a 2\n/-a 3\na 4\n");
+        let args = doc
+            .disabled_nodes()
+            .iter()
+            .map(|node| node.get(0).cloned())
+            .collect::<Vec<_>>();
+        assert_eq!(args, [Some(2.into()), Some(3.into())]);
+    }
+
+    #[test]
+    fn programmatic_nodes() {
+        let mut doc = KdlDocument::new();
+        doc.nodes_mut().push(KdlNode::new("a"));
+        doc.nodes_mut().push(KdlNode::new("b"));
+        assert!(doc.disable_node(0));
+        assert_eq!(doc.to_string(), "/-a\nb\n");
+        assert!(doc.disable_node(0));
+        assert_eq!(doc.to_string(), "/-a\n/-b\n");
+        assert!(!doc.disable_node(0));
+        assert!(doc.enable_node(0));
+        assert!(doc.enable_node(0));
+        assert_eq!(doc.to_string(), "a\nb\n");
+
+        let mut parent = KdlNode::new("parent");
+        parent
+            .ensure_children()
+            .nodes_mut()
+            .push(KdlNode::new("child"));
+        assert!(parent.ensure_children().disable_node(0));
+        assert_eq!(parent.to_string(), "parent {\n    /-child\n}");
+    }
+
+    #[test]
+    fn entries() {
+        let mut node: KdlNode = "foo /-0 1 /-key=\"x\" 2 /-(u8)3 {\n    child\n}"
+            .parse()
+            .unwrap();
+        let disabled = node.disabled_entries();
+        assert_eq!(disabled.len(), 3);
+        assert_eq!(disabled[2].ty().unwrap().value(), "u8");
+
+        assert!(node.enable_entry(1));
+        assert!(node.enable_entry("key"));
+        assert!(!node.enable_entry("key"));
+        assert_eq!(
+            node.to_string(),
+            "foo /-0 1 key=\"x\" 2 (u8)3 {\n    child\n}"
+        );
+        assert_eq!(node.get(2), Some(&3.into()));
+
+        assert!(node.disable_entry(0));
+        assert!(node.disable_entry(1));
+        assert!(!node.disable_entry(5));
+        assert_eq!(
+            node.to_string(),
+            "foo /-0 /-1 key=\"x\" 2 /-(u8)3 {\n    child\n}"
+        );
+        let reparsed: KdlNode = node.to_string().parse().unwrap();
+        assert_eq!(reparsed.entries().len(), 2);
+        assert_eq!(reparsed.disabled_entries().len(), 3);
+    }
+
+    #[test]
+    fn programmatic_entries() {
+        let mut node = KdlNode::new("foo");
+        node.push(1);
+        assert!(node.disable_entry(0));
+        assert!(node.disabled_entries().len() == 1);
+        assert!(node.enable_entry(0));
+        assert_eq!(node.to_string(), "foo 1");
+
+        let mut node = KdlNode::new("foo");
+        node.push(1);
+        node.push(2);
+        node.ensure_children();
+        assert!(node.disable_entry(1));
+        assert_eq!(node.to_string(), "foo 1 /-2 {\n}");
+    }
+
+    #[test]
+    fn v2_entries() {
+        let doc =
+            KdlDocument::parse_with_options("foo /-#true bar", &options(KdlVersion::V2)).unwrap();
+        let mut node = doc.nodes()[0].clone();
+        assert!(node.enable_entry(0));
+        assert_eq!(node.get(0), Some(&true.into()));
+        assert_eq!(node.to_string(), "foo #true bar");
+    }
+
+    #[test]
+    fn v2_only_entries() {
+        let input = "n /-bare 1 /-\"a\\sb\" /-x=bare\n";
+        let mut doc = KdlDocument::parse_with_options(input, &options(KdlVersion::V2)).unwrap();
+        let node = &mut doc.nodes_mut()[0];
+        let disabled = node.disabled_entries();
+        assert_eq!(disabled.len(), 3);
+        assert_eq!(disabled[0].value(), &"bare".into());
+        assert_eq!(disabled[1].value(), &"a b".into());
+        assert_eq!(disabled[2].name().unwrap().value(), "x");
+        assert!(node.enable_entry(0));
+        assert!(node.enable_entry("x"));
+        assert_eq!(doc.to_string(), "n bare 1 /-\"a\\sb\" x=bare\n");
+    }
+
+    #[test]
+    fn disable_in_version() {
+        let mut doc = KdlDocument::parse_with_options("n 1\n", &options(KdlVersion::V2)).unwrap();
+        let node = &mut doc.nodes_mut()[0];
+        node.push(true);
+        node.push(("k", KdlValue::Null));
+        assert!(node.disable_entry(1));
+        assert!(node.disable_entry("k"));
+        assert_eq!(doc.to_string(), "n 1 /-#true /-k=#null\n");
+        let reparsed =
+            KdlDocument::parse_with_options(&doc.to_string(), &options(KdlVersion::V2)).unwrap();
+        assert_eq!(reparsed.nodes()[0].disabled_entries().len(), 2);
+    }
+}
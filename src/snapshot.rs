@@ -0,0 +1,118 @@
+use crate::{
+    semantic_eq::{args, props},
+    KdlDocument, KdlEntry, KdlIdentifier, KdlNode,
+};
+
+/// Snapshots, for golden tests (with something like `insta`) that should
+/// only change when a document's meaning does.
+impl KdlDocument {
+    /// Writes this document out in a normalized form that only depends on
+    /// what it says, and not how it was written: documents have the same
+    /// snapshot exactly when they're [`KdlDocument::semantic_eq`].
+    ///
+    /// In a snapshot:
+    ///
+    /// * Comments, slashdashed nodes and entries, line continuations, and
+    ///   semicolons are gone, and nodes are indented by four spaces, one per
+    ///   line.
+    /// * Each node's arguments come first, in order, then its properties,
+    ///   sorted by name, with only the last of any that are repeated.
+    /// * Empty children blocks are gone.
+    /// * Identifiers are only quoted if they have to be.
+    /// * Integral numbers are written as base 10 integers, so `0x10` and
+    ///   `16.0` are both `16`, and raw strings as plain strings.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = r#"
+    /// // The server.
+    /// "server" port=0x1f90 r"local" /-debug=true { }
+    /// "#.parse().unwrap();
+    /// assert_eq!(doc.to_snapshot_string(), "server \"local\" port=8080\n");
+    /// ```
+    pub fn to_snapshot_string(&self) -> String {
+        snapshot_nodes(self.nodes()).to_string()
+    }
+}
+
+impl KdlNode {
+    /// Writes this node out in a normalized form. See
+    /// [`KdlDocument::to_snapshot_string`].
+    pub fn to_snapshot_string(&self) -> String {
+        snapshot_node(self).to_string()
+    }
+}
+
+fn snapshot_nodes(nodes: &[KdlNode]) -> KdlDocument {
+    let mut doc = KdlDocument::new();
+    doc.nodes_mut().extend(nodes.iter().map(snapshot_node));
+    doc
+}
+
+fn snapshot_node(node: &KdlNode) -> KdlNode {
+    let mut snapshot = KdlNode::new(node.name().value());
+    if let Some(ty) = node.ty() {
+        snapshot.set_ty(ty.value());
+    }
+    for arg in args(node) {
+        snapshot.push(snapshot_entry(None, arg));
+    }
+    for (name, prop) in props(node) {
+        snapshot.push(snapshot_entry(Some(name), prop));
+    }
+    if let Some(children) = node.children() {
+        if !children.nodes().is_empty() {
+            *snapshot.children_mut() = Some(snapshot_nodes(children.nodes()));
+        }
+    }
+    snapshot
+}
+
+fn snapshot_entry(name: Option<&str>, entry: &KdlEntry) -> KdlEntry {
+    let value = entry.value().canonical();
+    let mut snapshot = match name {
+        Some(name) => KdlEntry::new_prop(name, value),
+        None => KdlEntry::new(value),
+    };
+    if let Some(ty) = entry.ty().map(KdlIdentifier::value) {
+        snapshot.set_ty(ty);
+    }
+    snapshot
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn snapshots() {
+        let docs = [
+            "a 1 2;b",
+            "/* c */ a \\\n  1 /-\"x\" 2 // d\n\nb {}",
+            "(u8)a 0x10 16.0 y=r\"z\" x=1 x=0.5 { (t)c -0.0; }",
+            "(\"u8\")\"a\" 16 0b10000 x=5e-1 y=\"z\" {\n  (t)c 0\n}",
+        ];
+        for left in docs {
+            for right in docs {
+                let left: KdlDocument = left.parse().unwrap();
+                let right: KdlDocument = right.parse().unwrap();
+                assert_eq!(
+                    left.semantic_eq(&right),
+                    left.to_snapshot_string() == right.to_snapshot_string()
+                );
+            }
+        }
+        let doc: KdlDocument = docs[2].parse().unwrap();
+        assert_eq!(
+            doc.to_snapshot_string(),
+            "(u8)a 16 16 x=0.5 y=\"z\" {\n    (t)c 0\n}\n"
+        );
+        assert_eq!(
+            doc.nodes()[0].children().unwrap().nodes()[0].to_snapshot_string(),
+            "(t)c 0"
+        );
+        assert_eq!(KdlDocument::new().to_snapshot_string(), "");
+    }
+}
@@ -0,0 +1,151 @@
+use std::mem::size_of;
+
+use crate::{KdlDocument, KdlEntry, KdlIdentifier, KdlNode, KdlValue};
+
+/// Sizes of a document, as returned by [`KdlDocument::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct KdlDocumentStats {
+    /// The number of nodes, including children, but not slashdashed nodes.
+    pub nodes: usize,
+
+    /// The number of arguments and properties in all of those nodes.
+    pub entries: usize,
+
+    /// How deeply nodes are nested: `0` for an empty document, `1` if it
+    /// only has top-level nodes, `2` if some of those have children, and so
+    /// on.
+    pub max_depth: usize,
+
+    /// The total length in bytes of every string value, node name, property
+    /// name, and type annotation, as values rather than as they're written,
+    /// so without quotes or escapes.
+    pub string_bytes: usize,
+
+    /// An estimate of how much heap memory the document uses, in bytes.
+    ///
+    /// This counts the memory allocated for every string (including
+    /// formatting and comments) and list in the document, but not the
+    /// allocator's own overhead, or the memory for the [`KdlDocument`]
+    /// value itself, which is `std::mem::size_of::<KdlDocument>()`.
+    pub heap_bytes: usize,
+}
+
+impl KdlDocument {
+    /// Measures this document, without writing it out.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = "package name=\"kdl\" {\n    (semver)version \"5.0.0\"\n}".parse().unwrap();
+    /// let stats = doc.stats();
+    /// assert_eq!(stats.nodes, 2);
+    /// assert_eq!(stats.entries, 2);
+    /// assert_eq!(stats.max_depth, 2);
+    /// assert_eq!(stats.string_bytes, "packagenamekdlsemverversion5.0.0".len());
+    /// assert!(stats.heap_bytes > stats.string_bytes);
+    /// ```
+    pub fn stats(&self) -> KdlDocumentStats {
+        let mut stats = KdlDocumentStats::default();
+        stats.document(self, 1);
+        stats
+    }
+}
+
+impl KdlDocumentStats {
+    fn document(&mut self, doc: &KdlDocument, depth: usize) {
+        self.heap_bytes += string(&doc.leading) + string(&doc.trailing);
+        self.heap_bytes += doc.nodes.capacity() * size_of::<KdlNode>();
+        self.heap_bytes += disabled_bytes(&doc.disabled);
+        if !doc.nodes.is_empty() {
+            self.max_depth = self.max_depth.max(depth);
+        }
+        for node in &doc.nodes {
+            self.node(node, depth);
+        }
+    }
+
+    fn node(&mut self, node: &KdlNode, depth: usize) {
+        self.nodes += 1;
+        self.heap_bytes += disabled_bytes(&node.disabled)
+            + string(&node.slashdash)
+            + string(&node.leading)
+            + string(&node.before_children)
+            + string(&node.trailing)
+            + node.entries.capacity() * size_of::<KdlEntry>();
+        self.identifier(Some(&node.name));
+        self.identifier(node.ty.as_ref());
+        for entry in &node.entries {
+            self.entry(entry);
+        }
+        if let Some(children) = &node.children {
+            self.document(children, depth + 1);
+        }
+    }
+
+    fn entry(&mut self, entry: &KdlEntry) {
+        self.entries += 1;
+        self.heap_bytes +=
+            string(&entry.leading) + string(&entry.value_repr) + string(&entry.trailing);
+        self.identifier(entry.name.as_ref());
+        self.identifier(entry.ty.as_ref());
+        match &entry.value {
+            KdlValue::String(s) | KdlValue::RawString(s) => {
+                self.string_bytes += s.len();
+                self.heap_bytes += s.capacity();
+            }
+            #[cfg(feature = "bigint")]
+            KdlValue::BigInt(i) => {
+                self.heap_bytes += (i.bits() as usize + 7) / 8;
+            }
+            _ => {}
+        }
+    }
+
+    fn identifier(&mut self, ident: Option<&KdlIdentifier>) {
+        if let Some(ident) = ident {
+            self.string_bytes += ident.value.len();
+            self.heap_bytes += ident.value.capacity() + string(&ident.repr);
+        }
+    }
+}
+
+fn string(s: &Option<String>) -> usize {
+    s.as_ref().map_or(0, String::capacity)
+}
+
+/// The heap memory used by slashdashed nodes, which count towards
+/// [`KdlDocumentStats::heap_bytes`] and nothing else.
+fn disabled_bytes(nodes: &Vec<KdlNode>) -> usize {
+    let mut stats = KdlDocumentStats::default();
+    for node in nodes {
+        stats.node(node, 0);
+    }
+    nodes.capacity() * size_of::<KdlNode>() + stats.heap_bytes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stats() -> miette::Result<()> {
+        assert_eq!(KdlDocument::new().stats(), KdlDocumentStats::default());
+
+        let doc: KdlDocument =
+            "a 1 /-b=2 {\n    b {\n        c \"xy\" r\"z\"\n    }\n}\n/-d {e;}\n(t)f {}".parse()?;
+        let stats = doc.stats();
+        assert_eq!(stats.nodes, 4);
+        assert_eq!(stats.entries, 3);
+        assert_eq!(stats.max_depth, 3);
+        assert_eq!(stats.string_bytes, "abcxyzft".len());
+        let text = doc.to_string().len();
+        assert!(stats.heap_bytes > text, "{} <= {}", stats.heap_bytes, text);
+
+        // An empty children block doesn't add a level.
+        let doc: KdlDocument = "a {}".parse()?;
+        assert_eq!(doc.stats().max_depth, 1);
+        Ok(())
+    }
+}
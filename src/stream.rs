@@ -0,0 +1,198 @@
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::{KdlDocument, KdlError, ParseOptions};
+
+/// Error returned by [`KdlDocument::from_reader`],
+/// [`KdlDocument::parse_file`], and [`KdlDocument::parse_bytes`], and by
+/// `KdlDocument::from_async_reader_buffered` with the `tokio` feature.
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum KdlReadError {
+    /// Reading failed, or the input wasn't valid UTF-8.
+    #[error(transparent)]
+    #[diagnostic(code(kdl::io))]
+    Io(#[from] io::Error),
+
+    /// The input was read, but isn't a valid KDL document.
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Parse(#[from] KdlError),
+}
+
+impl KdlDocument {
+    /// Reads a whole document from `reader`, such as a file or socket, and
+    /// parses it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc = KdlDocument::from_reader(&b"foo 1\nbar 2\n"[..]).unwrap();
+    /// assert_eq!(doc.get_arg("bar"), Some(&2.into()));
+    /// ```
+    pub fn from_reader(mut reader: impl Read) -> Result<Self, KdlReadError> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+        Ok(input.parse()?)
+    }
+
+    /// Reads and parses the file at `path`. Parse errors carry the path as
+    /// their [`ParseOptions::source_name`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kdl::KdlDocument;
+    /// let doc = KdlDocument::parse_file("config/app.kdl").unwrap();
+    /// ```
+    pub fn parse_file(path: impl AsRef<Path>) -> Result<Self, KdlReadError> {
+        let path = path.as_ref();
+        let input = std::fs::read_to_string(path)?;
+        Ok(Self::parse_with_options(&input, &file_options(path))?)
+    }
+
+    /// Like [`KdlDocument::parse_file`], but memory-maps the file with
+    /// [`memmap2`](https://crates.io/crates/memmap2) and parses it in place,
+    /// instead of copying it into a `String` first. Requires the `mmap`
+    /// feature.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this process or any
+    /// other, until this function returns. Changing a mapped file while it's
+    /// being read is undefined behavior: the parser may see text that isn't
+    /// valid UTF-8, or the process may be killed by `SIGBUS`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use kdl::KdlDocument;
+    /// // SAFETY: nothing else writes to this file while it's parsed.
+    /// let doc = unsafe { KdlDocument::parse_file_mmap("config/app.kdl") }.unwrap();
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub unsafe fn parse_file_mmap(path: impl AsRef<Path>) -> Result<Self, KdlReadError> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the caller promises the file isn't changed while mapped.
+        let map = memmap2::Mmap::map(&file)?;
+        let input =
+            std::str::from_utf8(&map).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Self::parse_with_options(input, &file_options(path))?)
+    }
+
+    /// Writes the document to `writer`, exactly as
+    /// [`to_string`](ToString::to_string) would, but one node at a time
+    /// instead of building the whole text in memory first.
+    ///
+    /// The document is written in many small pieces, so wrap unbuffered
+    /// writers like files in a [`std::io::BufWriter`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = "foo 1 // one\nbar 2".parse().unwrap();
+    /// let mut out = Vec::new();
+    /// doc.write_to(&mut out).unwrap();
+    /// assert_eq!(out, b"foo 1 // one\nbar 2");
+    /// ```
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        if let Some(leading) = self.leading() {
+            writer.write_all(leading.as_bytes())?;
+        }
+        for node in self.nodes().iter().chain(&self.disabled) {
+            write!(writer, "{}", node.to_string_in(self.version))?;
+            if node.trailing().is_none() {
+                writeln!(writer)?;
+            }
+        }
+        if let Some(trailing) = self.trailing() {
+            writer.write_all(trailing.as_bytes())?;
+        }
+        writer.flush()
+    }
+}
+
+fn file_options(path: &Path) -> ParseOptions {
+    ParseOptions {
+        source_name: Some(path.display().to_string()),
+        ..ParseOptions::default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let input = "/* hi */ foo 1 {\n    bar \"baz\"\n}\n\nqux\n";
+        let doc = KdlDocument::from_reader(input.as_bytes()).unwrap();
+        let mut out = Vec::new();
+        doc.write_to(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), input);
+
+        let mut doc = KdlDocument::new();
+        doc.nodes_mut().push(crate::KdlNode::new("a"));
+        doc.nodes_mut().push(crate::KdlNode::new("b"));
+        let mut out = Vec::new();
+        doc.write_to(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), doc.to_string());
+    }
+
+    #[test]
+    fn errors() {
+        let err = KdlDocument::from_reader(&b"foo \xff"[..]).unwrap_err();
+        assert!(matches!(err, KdlReadError::Io(e) if e.kind() == io::ErrorKind::InvalidData));
+        let err = KdlDocument::from_reader(&b"foo 1."[..]).unwrap_err();
+        assert!(matches!(err, KdlReadError::Parse(_)));
+    }
+
+    #[test]
+    fn parse_file() {
+        let dir = std::env::temp_dir().join(format!("kdl-parse-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("good.kdl");
+        std::fs::write(&path, "foo 1 {\n    bar \"baz\"\n}\n").unwrap();
+        let doc = KdlDocument::parse_file(&path).unwrap();
+        assert_eq!(doc.to_string(), "foo 1 {\n    bar \"baz\"\n}\n");
+
+        let empty = dir.join("empty.kdl");
+        std::fs::write(&empty, "").unwrap();
+        assert!(KdlDocument::parse_file(&empty).unwrap().is_empty());
+
+        let bad = dir.join("bad.kdl");
+        std::fs::write(&bad, "foo 1.").unwrap();
+        match KdlDocument::parse_file(&bad).unwrap_err() {
+            KdlReadError::Parse(e) => {
+                assert_eq!(e.input.name, Some(bad.display().to_string()))
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let err = KdlDocument::parse_file(dir.join("missing.kdl")).unwrap_err();
+        assert!(matches!(err, KdlReadError::Io(e) if e.kind() == io::ErrorKind::NotFound));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn parse_file_mmap() {
+        let dir = std::env::temp_dir().join(format!("kdl-parse-mmap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("good.kdl");
+        std::fs::write(&path, "foo 1 {\n    bar \"baz\"\n}\n").unwrap();
+        let doc = unsafe { KdlDocument::parse_file_mmap(&path) }.unwrap();
+        assert_eq!(doc, KdlDocument::parse_file(&path).unwrap());
+
+        let bad = dir.join("bad.kdl");
+        std::fs::write(&bad, b"foo \xff").unwrap();
+        let err = unsafe { KdlDocument::parse_file_mmap(&bad) }.unwrap_err();
+        assert!(matches!(err, KdlReadError::Io(e) if e.kind() == io::ErrorKind::InvalidData));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
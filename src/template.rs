@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::util::{edit_or_restore, ErrorSpan};
+use crate::{KdlDocument, KdlEntry, KdlNode, KdlValue};
+
+/// Error returned by [`KdlDocument::substitute`] when a placeholder can't
+/// be filled in.
+///
+/// Its span points into the template's source text, which has to be
+/// attached to render it: see [Error Reporting](crate#error-reporting).
+#[derive(Debug, Diagnostic, Clone, Eq, PartialEq, Error)]
+#[error("{kind}")]
+#[diagnostic(code(kdl::template))]
+pub struct KdlTemplateError {
+    /// What went wrong.
+    pub kind: KdlTemplateErrorKind,
+
+    /// Span of the placeholder in the source text. This is `0..0` without
+    /// the `span` feature.
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
+/// The kind of [`KdlTemplateError`].
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+#[non_exhaustive]
+pub enum KdlTemplateErrorKind {
+    /// There was no value for this variable.
+    #[error("Undefined variable `{0}`.")]
+    Undefined(String),
+
+    /// A placeholder's value wasn't a string naming its variable.
+    #[error("Invalid placeholder `{0}`. Placeholders are written as `(var)\"name\"`.")]
+    Invalid(String),
+}
+
+impl KdlDocument {
+    /// Replaces every placeholder in this document, including in children,
+    /// with its variable's value from `vars`. Templates are one of the
+    /// crate's [extensions](crate#extensions): a template is an ordinary
+    /// document, where some values are placeholders.
+    ///
+    /// A placeholder is a value with a `var` type annotation, like
+    /// `(var)"port"`, and the string is the name of the variable it's
+    /// replaced with. Unlike [interpolation](KdlDocument::interpolate),
+    /// which works inside strings, placeholders stand for whole values, of
+    /// any type: `port=(var)"port"` can become `port=8080`, and
+    /// `tls=(var)"tls"` can become `tls=true`. Arguments and properties can
+    /// both be placeholders, in nodes at any depth, but slashdashed nodes
+    /// and entries are left alone.
+    ///
+    /// Substituted values lose the placeholder's type annotation and are
+    /// written out plainly, so the result is an ordinary document.
+    ///
+    /// If a placeholder can't be filled in, the document is left unchanged
+    /// and the first such placeholder is returned as an error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use std::collections::HashMap;
+    /// # use kdl::{KdlDocument, KdlValue};
+    /// let template: KdlDocument = r#"
+    /// tenant (var)"name" {
+    ///     server port=(var)"port" tls=(var)"tls"
+    /// }
+    /// "#.parse().unwrap();
+    ///
+    /// let mut vars = HashMap::new();
+    /// vars.insert("name".to_string(), KdlValue::from("acme"));
+    /// vars.insert("port".to_string(), KdlValue::from(8443));
+    /// vars.insert("tls".to_string(), KdlValue::from(true));
+    /// let mut doc = template.clone();
+    /// doc.substitute(&vars).unwrap();
+    /// assert_eq!(
+    ///     doc.to_string(),
+    ///     "\ntenant \"acme\" {\n    server port=8443 tls=true\n}\n"
+    /// );
+    ///
+    /// vars.remove("tls");
+    /// let mut doc = template.clone();
+    /// let err = doc.substitute(&vars).unwrap_err();
+    /// assert_eq!(err.to_string(), "Undefined variable `tls`.");
+    /// assert_eq!(doc, template);
+    /// ```
+    pub fn substitute(&mut self, vars: &HashMap<String, KdlValue>) -> Result<(), KdlTemplateError> {
+        self.substitute_with(|name| vars.get(name).cloned())
+    }
+
+    /// Replaces every placeholder like [`KdlDocument::substitute`], using
+    /// `resolver` to look up each variable's value.
+    pub fn substitute_with(
+        &mut self,
+        mut resolver: impl FnMut(&str) -> Option<KdlValue>,
+    ) -> Result<(), KdlTemplateError> {
+        edit_or_restore(self, |doc| doc.substitute_impl(&mut resolver))
+    }
+
+    /// Gets the names of the variables used in this document's
+    /// placeholders, in the order they're first used, so you can check you
+    /// have all of them before substituting.
+    pub fn template_vars(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for node in self.descendants() {
+            for entry in node.entries() {
+                if let Some(KdlValue::String(name) | KdlValue::RawString(name)) = placeholder(entry)
+                {
+                    if !names.contains(&name.as_str()) {
+                        names.push(name);
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    fn substitute_impl(
+        &mut self,
+        resolver: &mut dyn FnMut(&str) -> Option<KdlValue>,
+    ) -> Result<(), KdlTemplateError> {
+        for node in self.nodes_mut() {
+            node.substitute_impl(resolver)?;
+        }
+        Ok(())
+    }
+}
+
+impl KdlNode {
+    /// Replaces the placeholders in this node, and its children, with their
+    /// variables' values from `vars`. See [`KdlDocument::substitute`].
+    pub fn substitute(&mut self, vars: &HashMap<String, KdlValue>) -> Result<(), KdlTemplateError> {
+        edit_or_restore(self, |node| {
+            node.substitute_impl(&mut |name| vars.get(name).cloned())
+        })
+    }
+
+    fn substitute_impl(
+        &mut self,
+        resolver: &mut dyn FnMut(&str) -> Option<KdlValue>,
+    ) -> Result<(), KdlTemplateError> {
+        for entry in self.entries_mut() {
+            let value = match placeholder(entry) {
+                Some(KdlValue::String(name) | KdlValue::RawString(name)) => {
+                    resolver(name).ok_or_else(|| KdlTemplateErrorKind::Undefined(name.clone()))
+                }
+                Some(other) => Err(KdlTemplateErrorKind::Invalid(other.to_string())),
+                None => continue,
+            };
+            let value = value.map_err(|kind| KdlTemplateError {
+                kind,
+                span: entry.error_span(),
+            })?;
+            entry.set_value(value);
+            entry.ty = None;
+            entry.value_repr = None;
+        }
+        if let Some(children) = self.children_mut().as_mut() {
+            children.substitute_impl(resolver)?;
+        }
+        Ok(())
+    }
+}
+
+/// The value of `entry`, if it's a placeholder.
+fn placeholder(entry: &KdlEntry) -> Option<&KdlValue> {
+    match entry.ty() {
+        Some(ty) if ty.value() == "var" => Some(entry.value()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vars() -> HashMap<String, KdlValue> {
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), KdlValue::from(1));
+        vars.insert("b".to_string(), KdlValue::from("two"));
+        vars.insert("c".to_string(), KdlValue::Null);
+        vars
+    }
+
+    #[test]
+    fn substitutes() -> miette::Result<()> {
+        let mut doc: KdlDocument =
+            "n (var)\"a\" x=(var)r\"b\" (u8)1 {\n    m (var)\"c\" (var)\"a\"\n}\n/-o (var)\"z\" /-(var)\"z\""
+                .parse()?;
+        assert_eq!(doc.template_vars(), vec!["a", "b", "c"]);
+        doc.substitute(&vars())?;
+        assert_eq!(
+            doc.to_string(),
+            "n 1 x=\"two\" (u8)1 {\n    m null 1\n}\n/-o (var)\"z\" /-(var)\"z\""
+        );
+        assert!(doc.template_vars().is_empty());
+
+        // Substituted values aren't placeholders themselves.
+        let mut node: KdlNode = "n (var)\"a\"".parse()?;
+        node.substitute(&vars())?;
+        let mut count = 0;
+        let mut doc = KdlDocument::new();
+        doc.nodes_mut().push(node);
+        doc.substitute_with(|_| {
+            count += 1;
+            None
+        })?;
+        assert_eq!(count, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn errors() -> miette::Result<()> {
+        let input = "n (var)\"a\"\nm {\n    o x=(var)\"missing\"\n}";
+        let mut doc: KdlDocument = input.parse()?;
+        let err = doc.substitute(&vars()).unwrap_err();
+        assert_eq!(err.kind, KdlTemplateErrorKind::Undefined("missing".into()));
+        #[cfg(feature = "span")]
+        assert_eq!(
+            &input[err.span.offset()..][..err.span.len()],
+            "x=(var)\"missing\""
+        );
+        assert_eq!(doc.to_string(), input);
+
+        let mut node: KdlNode = "n (var)1".parse()?;
+        let err = node.substitute(&vars()).unwrap_err();
+        assert_eq!(err.kind, KdlTemplateErrorKind::Invalid("1".into()));
+        assert_eq!(
+            err.to_string(),
+            "Invalid placeholder `1`. Placeholders are written as `(var)\"name\"`."
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{KdlDocument, KdlNode, KdlReadError};
+
+/// Asynchronous reading and writing with [`tokio`]. Requires the `tokio`
+/// feature.
+impl KdlDocument {
+    /// Reads all of `reader` into memory, then parses it. This is a
+    /// buffered convenience wrapper, not an incremental parser: waiting on
+    /// the input doesn't block the thread the way
+    /// [`KdlDocument::from_reader`] would, but the whole document is held in
+    /// memory and parsed in one go on the current task once `reader` is
+    /// done. For large inputs, consider running the parse itself with
+    /// `tokio::task::spawn_blocking`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let doc = KdlDocument::from_async_reader_buffered(&b"foo 1\nbar 2\n"[..]).await.unwrap();
+    /// assert_eq!(doc.get_arg("bar"), Some(&2.into()));
+    /// # });
+    /// ```
+    pub async fn from_async_reader_buffered(
+        mut reader: impl AsyncRead + Unpin,
+    ) -> Result<Self, KdlReadError> {
+        let mut input = Vec::new();
+        reader.read_to_end(&mut input).await?;
+        let input =
+            String::from_utf8(input).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(input.parse()?)
+    }
+
+    /// Writes the document to `writer`, exactly as
+    /// [`to_string`](ToString::to_string) would, one node at a time. Each
+    /// node waits for `writer` to take the previous one, so a slow reader on
+    /// the other end holds back the writing instead of letting it pile up in
+    /// memory.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let doc: KdlDocument = "foo 1 // one\nbar 2".parse().unwrap();
+    /// let mut out = Vec::new();
+    /// doc.write_to_async(&mut out).await.unwrap();
+    /// assert_eq!(out, b"foo 1 // one\nbar 2");
+    /// # });
+    /// ```
+    pub async fn write_to_async(&self, mut writer: impl AsyncWrite + Unpin) -> io::Result<()> {
+        if let Some(leading) = self.leading() {
+            writer.write_all(leading.as_bytes()).await?;
+        }
+        for node in self.nodes().iter().chain(&self.disabled) {
+            let mut text = node.to_string_in(self.version);
+            if node.trailing().is_none() {
+                text.push('\n');
+            }
+            writer.write_all(text.as_bytes()).await?;
+        }
+        if let Some(trailing) = self.trailing() {
+            writer.write_all(trailing.as_bytes()).await?;
+        }
+        writer.flush().await
+    }
+}
+
+/// Writes a stream of nodes into a [`tokio`] [`AsyncWrite`] as they come,
+/// such as records appended to a log. Requires the `tokio` feature.
+///
+/// Every node is written as soon as it's given, in full, followed by a
+/// newline unless its trailing text already ends with one. Writing waits
+/// until `writer` is ready for more.
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::{KdlAsyncWriter, KdlNode};
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut writer = KdlAsyncWriter::new(Vec::new());
+/// for i in 1..=2 {
+///     let mut node = KdlNode::new("event");
+///     node.push(i);
+///     writer.write_node(&node).await.unwrap();
+/// }
+/// assert_eq!(writer.into_inner(), b"event 1\nevent 2\n");
+/// # });
+/// ```
+#[derive(Debug)]
+pub struct KdlAsyncWriter<W> {
+    writer: W,
+}
+
+impl<W: AsyncWrite + Unpin> KdlAsyncWriter<W> {
+    /// Creates a writer that writes nodes into `writer`.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Writes one node, with its children.
+    pub async fn write_node(&mut self, node: &KdlNode) -> io::Result<()> {
+        let mut text = node.to_string();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        self.writer.write_all(text.as_bytes()).await
+    }
+
+    /// Writes every node from `nodes`, in order.
+    pub async fn write_nodes<'a>(
+        &mut self,
+        nodes: impl IntoIterator<Item = &'a KdlNode>,
+    ) -> io::Result<()> {
+        for node in nodes {
+            self.write_node(node).await?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the underlying writer.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush().await
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn from_async_reader_buffered() {
+        let input = "foo 1 {\n    bar \"baz\"\n}\n";
+        let doc = block_on(KdlDocument::from_async_reader_buffered(input.as_bytes())).unwrap();
+        assert_eq!(doc.to_string(), input);
+
+        let err = block_on(KdlDocument::from_async_reader_buffered(&b"foo \xff"[..])).unwrap_err();
+        assert!(matches!(err, KdlReadError::Io(e) if e.kind() == io::ErrorKind::InvalidData));
+        let err = block_on(KdlDocument::from_async_reader_buffered(&b"foo 1."[..])).unwrap_err();
+        assert!(matches!(err, KdlReadError::Parse(_)));
+    }
+
+    #[test]
+    fn async_writer() {
+        let input = "/* hi */ foo 1 {\n    bar \"baz\"\n}\n\nqux // end";
+        let doc: KdlDocument = input.parse().unwrap();
+        let mut out = Vec::new();
+        block_on(doc.write_to_async(&mut out)).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), input);
+
+        let mut writer = KdlAsyncWriter::new(Vec::new());
+        block_on(writer.write_nodes(doc.nodes())).unwrap();
+        block_on(writer.write_node(&KdlNode::new("more"))).unwrap();
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "/* hi */ foo 1 {\n    bar \"baz\"\n}\n\nqux // end\nmore\n"
+        );
+    }
+}
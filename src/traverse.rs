@@ -0,0 +1,460 @@
+use std::collections::VecDeque;
+
+use crate::{KdlDocument, KdlNode};
+
+/// Depth-first iterator over every node in a document, in document order.
+/// Created by [`KdlDocument::descendants`] and [`KdlNode::descendants`].
+#[derive(Debug, Clone)]
+pub struct Descendants<'a> {
+    stack: Vec<std::slice::Iter<'a, KdlNode>>,
+}
+
+impl<'a> Descendants<'a> {
+    fn new(nodes: &'a [KdlNode]) -> Self {
+        Self {
+            stack: vec![nodes.iter()],
+        }
+    }
+
+    /// Turns this into an iterator that also yields each node's depth: `0`
+    /// for the nodes the iteration started from, `1` for their children, and
+    /// so on.
+    pub fn with_depth(self) -> DescendantsWithDepth<'a> {
+        DescendantsWithDepth(self)
+    }
+
+    fn next_with_depth(&mut self) -> Option<(usize, &'a KdlNode)> {
+        while let Some(nodes) = self.stack.last_mut() {
+            if let Some(node) = nodes.next() {
+                let depth = self.stack.len() - 1;
+                if let Some(children) = node.children() {
+                    self.stack.push(children.nodes().iter());
+                }
+                return Some((depth, node));
+            }
+            self.stack.pop();
+        }
+        None
+    }
+}
+
+impl<'a> Iterator for Descendants<'a> {
+    type Item = &'a KdlNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_depth().map(|(_, node)| node)
+    }
+}
+
+/// Like [`Descendants`], but yields `(depth, node)` pairs. Created by
+/// [`Descendants::with_depth`].
+#[derive(Debug, Clone)]
+pub struct DescendantsWithDepth<'a>(Descendants<'a>);
+
+impl<'a> Iterator for DescendantsWithDepth<'a> {
+    type Item = (usize, &'a KdlNode);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_with_depth()
+    }
+}
+
+/// Breadth-first iterator over every node in a document, one level at a
+/// time. Created by [`KdlDocument::breadth_first`] and
+/// [`KdlNode::breadth_first`].
+#[derive(Debug, Clone)]
+pub struct BreadthFirst<'a> {
+    queue: VecDeque<(usize, &'a KdlNode)>,
+}
+
+impl<'a> BreadthFirst<'a> {
+    fn new(nodes: &'a [KdlNode]) -> Self {
+        Self {
+            queue: nodes.iter().map(|node| (0, node)).collect(),
+        }
+    }
+
+    /// Turns this into an iterator that also yields each node's depth. See
+    /// [`Descendants::with_depth`].
+    pub fn with_depth(self) -> BreadthFirstWithDepth<'a> {
+        BreadthFirstWithDepth(self)
+    }
+
+    fn next_with_depth(&mut self) -> Option<(usize, &'a KdlNode)> {
+        let (depth, node) = self.queue.pop_front()?;
+        if let Some(children) = node.children() {
+            self.queue
+                .extend(children.nodes().iter().map(|child| (depth + 1, child)));
+        }
+        Some((depth, node))
+    }
+}
+
+impl<'a> Iterator for BreadthFirst<'a> {
+    type Item = &'a KdlNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_depth().map(|(_, node)| node)
+    }
+}
+
+/// Like [`BreadthFirst`], but yields `(depth, node)` pairs. Created by
+/// [`BreadthFirst::with_depth`].
+#[derive(Debug, Clone)]
+pub struct BreadthFirstWithDepth<'a>(BreadthFirst<'a>);
+
+impl<'a> Iterator for BreadthFirstWithDepth<'a> {
+    type Item = (usize, &'a KdlNode);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_with_depth()
+    }
+}
+
+impl KdlDocument {
+    /// Returns an iterator over every node in this document, depth-first and
+    /// in document order: each node is followed by its children (and their
+    /// children) before its next sibling.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = "a { b { c; }; d; }\ne".parse().unwrap();
+    /// let names: Vec<_> = doc.descendants().map(|n| n.name().value()).collect();
+    /// assert_eq!(names, vec!["a", "b", "c", "d", "e"]);
+    ///
+    /// let depths: Vec<_> = doc.descendants().with_depth().map(|(d, _)| d).collect();
+    /// assert_eq!(depths, vec![0, 1, 2, 1, 0]);
+    /// ```
+    pub fn descendants(&self) -> Descendants<'_> {
+        Descendants::new(self.nodes())
+    }
+
+    /// Calls `f` with the depth of, and a mutable reference to, every node in
+    /// this document, in the same order as [`KdlDocument::descendants`].
+    ///
+    /// Each node is visited before its children, so `f` can add, remove, or
+    /// change a node's children and the walk will follow the result.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let mut doc: KdlDocument = "a { b; }\nc".parse().unwrap();
+    /// doc.for_each_descendant_mut(|depth, node| node.push(depth as i64));
+    /// assert_eq!(doc.to_string(), "a 0 { b 1; }\nc 0");
+    /// ```
+    pub fn for_each_descendant_mut(&mut self, mut f: impl FnMut(usize, &mut KdlNode)) {
+        for_each_mut(self, 0, &mut f);
+    }
+
+    /// Returns an iterator over every node in this document, breadth-first:
+    /// all the top-level nodes, then all of their children, and so on. Nodes
+    /// on the same level come in document order.
+    ///
+    /// Since nodes come out level by level, stopping at a given depth only
+    /// visits the nodes above it, however big the document is below.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = "a { b { c; }; d; }\ne".parse().unwrap();
+    /// let names: Vec<_> = doc.breadth_first().map(|n| n.name().value()).collect();
+    /// assert_eq!(names, vec!["a", "e", "b", "d", "c"]);
+    ///
+    /// let shallow = doc
+    ///     .breadth_first()
+    ///     .with_depth()
+    ///     .take_while(|(depth, _)| *depth < 2)
+    ///     .count();
+    /// assert_eq!(shallow, 4);
+    /// ```
+    pub fn breadth_first(&self) -> BreadthFirst<'_> {
+        BreadthFirst::new(self.nodes())
+    }
+}
+
+impl KdlNode {
+    /// Returns an iterator over every node below this one, depth-first and in
+    /// document order. The node itself isn't included, and its children are
+    /// at depth `0`. See [`KdlDocument::descendants`].
+    pub fn descendants(&self) -> Descendants<'_> {
+        Descendants::new(self.children().map(|doc| doc.nodes()).unwrap_or_default())
+    }
+
+    /// Returns a breadth-first iterator over every node below this one. The
+    /// node itself isn't included, and its children are at depth `0`. See
+    /// [`KdlDocument::breadth_first`].
+    pub fn breadth_first(&self) -> BreadthFirst<'_> {
+        BreadthFirst::new(self.children().map(|doc| doc.nodes()).unwrap_or_default())
+    }
+
+    /// Calls `f` with every node below this one. See
+    /// [`KdlDocument::for_each_descendant_mut`].
+    pub fn for_each_descendant_mut(&mut self, mut f: impl FnMut(usize, &mut KdlNode)) {
+        if let Some(children) = self.children_mut() {
+            for_each_mut(children, 0, &mut f);
+        }
+    }
+}
+
+/// Searching by predicate. The plain methods only look at a document's own
+/// nodes and return the index of what they find; the `_recursive` ones look
+/// at every node depth-first, like [`KdlDocument::descendants`], and return
+/// its path, which can be passed to [`KdlDocument::cursor_at`].
+impl KdlDocument {
+    /// Finds the first of this document's nodes that matches `predicate`,
+    /// along with its index.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlDocument;
+    /// let doc: KdlDocument = "a 1\nb 2\nc 3 { d 4; }".parse().unwrap();
+    /// let (index, node) = doc.find(|n| n[0] == 2.into()).unwrap();
+    /// assert_eq!((index, node.name().value()), (1, "b"));
+    ///
+    /// let (path, node) = doc.find_recursive(|n| n[0] == 4.into()).unwrap();
+    /// assert_eq!((path, node.name().value()), (vec![2, 0], "d"));
+    /// ```
+    pub fn find(&self, mut predicate: impl FnMut(&KdlNode) -> bool) -> Option<(usize, &KdlNode)> {
+        self.find_map(|node| predicate(node).then(|| node))
+    }
+
+    /// Calls `f` on each of this document's nodes until it returns `Some`,
+    /// returning that along with the node's index.
+    pub fn find_map<'a, T>(
+        &'a self,
+        mut f: impl FnMut(&'a KdlNode) -> Option<T>,
+    ) -> Option<(usize, T)> {
+        self.nodes()
+            .iter()
+            .enumerate()
+            .find_map(|(i, node)| Some((i, f(node)?)))
+    }
+
+    /// Gets the index of the first of this document's nodes that matches
+    /// `predicate`.
+    pub fn position(&self, predicate: impl FnMut(&KdlNode) -> bool) -> Option<usize> {
+        self.nodes().iter().position(predicate)
+    }
+
+    /// Finds the first node anywhere in this document that matches
+    /// `predicate`, along with its path.
+    pub fn find_recursive(
+        &self,
+        mut predicate: impl FnMut(&KdlNode) -> bool,
+    ) -> Option<(Vec<usize>, &KdlNode)> {
+        self.find_map_recursive(|node| predicate(node).then(|| node))
+    }
+
+    /// Calls `f` on every node in this document until it returns `Some`,
+    /// returning that along with the node's path.
+    pub fn find_map_recursive<'a, T>(
+        &'a self,
+        mut f: impl FnMut(&'a KdlNode) -> Option<T>,
+    ) -> Option<(Vec<usize>, T)> {
+        let mut path = Vec::new();
+        let found = search(self, &mut path, &mut f)?;
+        Some((path, found))
+    }
+
+    /// Gets the path of the first node anywhere in this document that
+    /// matches `predicate`.
+    pub fn position_recursive(
+        &self,
+        mut predicate: impl FnMut(&KdlNode) -> bool,
+    ) -> Option<Vec<usize>> {
+        self.find_map_recursive(|node| predicate(node).then(|| ()))
+            .map(|(path, _)| path)
+    }
+}
+
+/// Searching this node's children. These work like the
+/// [`KdlDocument`] methods of the same name, with indices and paths relative
+/// to the children block.
+impl KdlNode {
+    /// See [`KdlDocument::find`].
+    pub fn find(&self, predicate: impl FnMut(&KdlNode) -> bool) -> Option<(usize, &KdlNode)> {
+        self.children()?.find(predicate)
+    }
+
+    /// See [`KdlDocument::find_map`].
+    pub fn find_map<'a, T>(
+        &'a self,
+        f: impl FnMut(&'a KdlNode) -> Option<T>,
+    ) -> Option<(usize, T)> {
+        self.children()?.find_map(f)
+    }
+
+    /// See [`KdlDocument::position`].
+    pub fn position(&self, predicate: impl FnMut(&KdlNode) -> bool) -> Option<usize> {
+        self.children()?.position(predicate)
+    }
+
+    /// See [`KdlDocument::find_recursive`].
+    pub fn find_recursive(
+        &self,
+        predicate: impl FnMut(&KdlNode) -> bool,
+    ) -> Option<(Vec<usize>, &KdlNode)> {
+        self.children()?.find_recursive(predicate)
+    }
+
+    /// See [`KdlDocument::find_map_recursive`].
+    pub fn find_map_recursive<'a, T>(
+        &'a self,
+        f: impl FnMut(&'a KdlNode) -> Option<T>,
+    ) -> Option<(Vec<usize>, T)> {
+        self.children()?.find_map_recursive(f)
+    }
+
+    /// See [`KdlDocument::position_recursive`].
+    pub fn position_recursive(
+        &self,
+        predicate: impl FnMut(&KdlNode) -> bool,
+    ) -> Option<Vec<usize>> {
+        self.children()?.position_recursive(predicate)
+    }
+}
+
+/// Depth-first search, leaving `path` pointing at whatever was found.
+fn search<'a, T>(
+    doc: &'a KdlDocument,
+    path: &mut Vec<usize>,
+    f: &mut dyn FnMut(&'a KdlNode) -> Option<T>,
+) -> Option<T> {
+    for (i, node) in doc.nodes().iter().enumerate() {
+        path.push(i);
+        if let Some(found) = f(node) {
+            return Some(found);
+        }
+        if let Some(found) = node
+            .children()
+            .and_then(|children| search(children, path, f))
+        {
+            return Some(found);
+        }
+        path.pop();
+    }
+    None
+}
+
+fn for_each_mut(doc: &mut KdlDocument, depth: usize, f: &mut dyn FnMut(usize, &mut KdlNode)) {
+    for node in doc.nodes_mut() {
+        f(depth, node);
+        if let Some(children) = node.children_mut() {
+            for_each_mut(children, depth + 1, f);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn names<'a>(nodes: impl Iterator<Item = &'a KdlNode>) -> Vec<&'a str> {
+        nodes.map(|n| n.name().value()).collect()
+    }
+
+    #[test]
+    fn descendants() -> miette::Result<()> {
+        let doc: KdlDocument = "a {\n  b {\n    c\n  }\n  d {}\n}\ne { f; }".parse()?;
+        assert_eq!(names(doc.descendants()), vec!["a", "b", "c", "d", "e", "f"]);
+        assert_eq!(
+            doc.descendants()
+                .with_depth()
+                .map(|(depth, n)| (depth, n.name().value()))
+                .collect::<Vec<_>>(),
+            vec![(0, "a"), (1, "b"), (2, "c"), (1, "d"), (0, "e"), (1, "f")]
+        );
+        assert_eq!(names(doc.nodes()[0].descendants()), vec!["b", "c", "d"]);
+        assert_eq!(KdlDocument::new().descendants().count(), 0);
+        let leaf: KdlNode = "leaf 1".parse()?;
+        assert_eq!(leaf.descendants().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn breadth_first() -> miette::Result<()> {
+        let doc: KdlDocument = "a { b { c; }; d { e; }; }\nf { g; }".parse()?;
+        assert_eq!(
+            names(doc.breadth_first()),
+            vec!["a", "f", "b", "d", "g", "c", "e"]
+        );
+        assert_eq!(
+            doc.breadth_first()
+                .with_depth()
+                .map(|(depth, _)| depth)
+                .collect::<Vec<_>>(),
+            vec![0, 0, 1, 1, 1, 2, 2]
+        );
+        assert_eq!(
+            names(doc.nodes()[0].breadth_first()),
+            vec!["b", "d", "c", "e"]
+        );
+        assert_eq!(KdlDocument::new().breadth_first().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn find() -> miette::Result<()> {
+        let doc: KdlDocument = "a { x 1; }\nb { c { x 2; }; x 3; }\nx 4".parse()?;
+        let is_x = |n: &KdlNode| n.name().value() == "x";
+        assert_eq!(
+            doc.find(is_x).map(|(i, n)| (i, n[0].clone())),
+            Some((2, 4.into()))
+        );
+        assert_eq!(doc.position(is_x), Some(2));
+        assert_eq!(doc.position(|n| n.name().value() == "c"), None);
+        assert_eq!(doc.position_recursive(is_x), Some(vec![0, 0]));
+        let (path, n) = doc
+            .find_map_recursive(|n| n.get(0)?.as_i64().filter(|n| *n > 1))
+            .unwrap();
+        assert_eq!((path, n), (vec![1, 0, 0], 2));
+        assert_eq!(
+            doc.find_map(|n| n.children().filter(|c| c.nodes().len() == 2)),
+            Some((1, doc.nodes()[1].children().unwrap()))
+        );
+
+        let b = &doc.nodes()[1];
+        assert_eq!(b.position(is_x), Some(1));
+        assert_eq!(b.find(is_x).map(|(_, n)| &n[0]), Some(&3.into()));
+        assert_eq!(b.position_recursive(is_x), Some(vec![0, 0]));
+        assert_eq!(b.find_recursive(|_| false), None);
+        assert_eq!(doc.nodes()[2].find(|_| true), None);
+        Ok(())
+    }
+
+    #[test]
+    fn for_each_descendant_mut() -> miette::Result<()> {
+        let mut doc: KdlDocument = "a { b; }\nc { d { e; }; }".parse()?;
+        let mut seen = Vec::new();
+        doc.for_each_descendant_mut(|depth, node| {
+            seen.push((depth, node.name().value().to_string()));
+            if node.name().value() == "b" {
+                node.ensure_children()
+                    .nodes_mut()
+                    .push(KdlNode::new("added"));
+            }
+        });
+        let seen: Vec<_> = seen.iter().map(|(d, n)| (*d, n.as_str())).collect();
+        assert_eq!(
+            seen,
+            vec![
+                (0, "a"),
+                (1, "b"),
+                (2, "added"),
+                (0, "c"),
+                (1, "d"),
+                (2, "e")
+            ]
+        );
+
+        let mut count = 0;
+        doc.nodes_mut()[1].for_each_descendant_mut(|_, _| count += 1);
+        assert_eq!(count, 2);
+        Ok(())
+    }
+}
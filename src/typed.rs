@@ -0,0 +1,201 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+use crate::{KdlEntry, KdlValue};
+
+/// A [`KdlEntry`]'s value, interpreted according to its type annotation.
+/// Returned by [`KdlEntry::typed_value`].
+///
+/// Each variant corresponds to one of KDL's [reserved type
+/// annotations](https://github.com/kdl-org/kdl/blob/main/SPEC.md#reserved-type-annotations-for-numbers-without-decimals)
+/// that this crate understands. Some of them require an optional feature,
+/// like `chrono` for dates and times. Values with no annotation, or with an
+/// annotation this crate doesn't interpret, are left as [`TypedValue::Value`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum TypedValue {
+    /// A value without a type annotation that this crate interprets.
+    Value(KdlValue),
+    /// An `(i8)` number.
+    I8(i8),
+    /// An `(i16)` number.
+    I16(i16),
+    /// An `(i32)` number.
+    I32(i32),
+    /// An `(i64)` number.
+    I64(i64),
+    /// An `(isize)` number.
+    Isize(isize),
+    /// A `(u8)` number.
+    U8(u8),
+    /// A `(u16)` number.
+    U16(u16),
+    /// A `(u32)` number.
+    U32(u32),
+    /// A `(u64)` number.
+    U64(u64),
+    /// A `(usize)` number.
+    Usize(usize),
+    /// An `(f32)` number. Finite numbers too large for a finite `f32` don't
+    /// fit, but NaN and infinities do.
+    F32(f32),
+    /// An `(f64)` number, including NaN and infinities written with
+    /// [`NonFiniteFloats::Annotation`](crate::NonFiniteFloats::Annotation).
+    F64(f64),
+    /// A `(duration)` string, as parsed by [`KdlEntry::as_duration`].
+    Duration(Duration),
+    /// A `(base64)` string, as decoded by [`KdlEntry::as_bytes`].
+    Bytes(Vec<u8>),
+    /// An `(ipv4)` address string.
+    Ipv4(Ipv4Addr),
+    /// An `(ipv6)` address string.
+    Ipv6(Ipv6Addr),
+    /// A `(date-time)` string. Requires the `chrono` feature.
+    #[cfg(feature = "chrono")]
+    DateTime(chrono::DateTime<chrono::FixedOffset>),
+    /// A `(date)` string. Requires the `chrono` feature.
+    #[cfg(feature = "chrono")]
+    Date(chrono::NaiveDate),
+    /// A `(time)` string. Requires the `chrono` feature.
+    #[cfg(feature = "chrono")]
+    Time(chrono::NaiveTime),
+    /// A `(decimal)` number. Requires the `rust_decimal` feature.
+    #[cfg(feature = "rust_decimal")]
+    Decimal(rust_decimal::Decimal),
+    /// A `(cidr)` network string. Requires the `ipnet` feature.
+    #[cfg(feature = "ipnet")]
+    Cidr(ipnet::IpNet),
+    /// A `(url)` string. Requires the `url` feature.
+    #[cfg(feature = "url")]
+    Url(url::Url),
+    /// A `(uuid)` string. Requires the `uuid` feature.
+    #[cfg(feature = "uuid")]
+    Uuid(uuid::Uuid),
+}
+
+impl KdlEntry {
+    /// Interprets this entry's value according to its type annotation.
+    ///
+    /// Returns `None` if the entry has an annotation this crate understands
+    /// but the value doesn't fit it, like `(u8)300` or `(uuid)"nope"`.
+    /// Unannotated values, and ones with unknown annotations, come back
+    /// as-is in [`TypedValue::Value`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlNode, KdlValue, TypedValue};
+    /// let node: KdlNode = r#"limits (u8)200 (duration)"PT5S" (u8)300 "plain""#
+    ///     .parse()
+    ///     .unwrap();
+    /// let typed: Vec<_> = node.entries().iter().map(|e| e.typed_value()).collect();
+    /// assert_eq!(typed[0], Some(TypedValue::U8(200)));
+    /// assert_eq!(
+    ///     typed[1],
+    ///     Some(TypedValue::Duration(std::time::Duration::from_secs(5)))
+    /// );
+    /// assert_eq!(typed[2], None);
+    /// assert_eq!(typed[3], Some(TypedValue::Value(KdlValue::from("plain"))));
+    /// ```
+    pub fn typed_value(&self) -> Option<TypedValue> {
+        let value = self.value();
+        let ty = match self.ty() {
+            Some(ty) => ty.value(),
+            None => return Some(TypedValue::Value(value.clone())),
+        };
+        Some(match ty {
+            "i8" => TypedValue::I8(value.as_i8()?),
+            "i16" => TypedValue::I16(value.as_i16()?),
+            "i32" => TypedValue::I32(value.as_i32()?),
+            "i64" => TypedValue::I64(value.as_i64()?),
+            "isize" => TypedValue::Isize(value.as_isize()?),
+            "u8" => TypedValue::U8(value.as_u8()?),
+            "u16" => TypedValue::U16(value.as_u16()?),
+            "u32" => TypedValue::U32(value.as_u32()?),
+            "u64" => TypedValue::U64(value.as_u64()?),
+            "usize" => TypedValue::Usize(value.as_usize()?),
+            "f32" => TypedValue::F32(narrow_f32(self.as_f64()?)?),
+            "f64" => TypedValue::F64(self.as_f64()?),
+            "duration" => TypedValue::Duration(self.as_duration()?),
+            "base64" => TypedValue::Bytes(self.as_bytes()?),
+            "ipv4" => TypedValue::Ipv4(self.as_ipv4()?),
+            "ipv6" => TypedValue::Ipv6(self.as_ipv6()?),
+            #[cfg(feature = "chrono")]
+            "date-time" => TypedValue::DateTime(self.as_datetime()?),
+            #[cfg(feature = "chrono")]
+            "date" => TypedValue::Date(self.as_date()?),
+            #[cfg(feature = "chrono")]
+            "time" => TypedValue::Time(self.as_time()?),
+            #[cfg(feature = "rust_decimal")]
+            "decimal" => TypedValue::Decimal(self.as_decimal()?),
+            #[cfg(feature = "ipnet")]
+            "cidr" => TypedValue::Cidr(self.as_ip_net()?),
+            #[cfg(feature = "url")]
+            "url" => TypedValue::Url(self.as_url()?),
+            #[cfg(feature = "uuid")]
+            "uuid" => TypedValue::Uuid(self.as_uuid()?),
+            _ => TypedValue::Value(value.clone()),
+        })
+    }
+}
+
+/// Narrows `float` to an `f32`, unless it's finite but too large to be a
+/// finite `f32`. Precision is lost as usual.
+fn narrow_f32(float: f64) -> Option<f32> {
+    let narrow = float as f32;
+    if float.is_finite() && narrow.is_infinite() {
+        None
+    } else {
+        Some(narrow)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn typed(input: &str) -> Option<TypedValue> {
+        input.parse::<KdlEntry>().unwrap().typed_value()
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(typed("(i8)-128"), Some(TypedValue::I8(-128)));
+        assert_eq!(typed("(i8)128"), None);
+        assert_eq!(
+            typed("(u64)0xffff_ffff_ffff_ffff"),
+            Some(TypedValue::U64(u64::MAX))
+        );
+        assert_eq!(typed("(u32)-1"), None);
+        assert_eq!(typed("(i32)1.0"), None);
+        assert_eq!(typed("(usize)\"1\""), None);
+        assert_eq!(typed("(f64)1.5"), Some(TypedValue::F64(1.5)));
+        assert_eq!(typed("(f32)0.25"), Some(TypedValue::F32(0.25)));
+        assert_eq!(typed("(f32)1e300"), None);
+        assert_eq!(typed("(f32)-1e39"), None);
+        assert_eq!(
+            typed("(f32)\"-inf\""),
+            Some(TypedValue::F32(f32::NEG_INFINITY))
+        );
+        assert_eq!(typed("(f64)\"inf\""), Some(TypedValue::F64(f64::INFINITY)));
+        assert_eq!(typed("(f64)\"x\""), None);
+    }
+
+    #[test]
+    fn others() {
+        assert_eq!(
+            typed("(ipv4)\"10.0.0.1\""),
+            Some(TypedValue::Ipv4(Ipv4Addr::new(10, 0, 0, 1)))
+        );
+        assert_eq!(
+            typed("(base64)\"AAE=\""),
+            Some(TypedValue::Bytes(vec![0, 1]))
+        );
+        assert_eq!(typed("(duration)\"forever\""), None);
+        assert_eq!(typed("42"), Some(TypedValue::Value(KdlValue::Base10(42))));
+        assert_eq!(
+            typed("(unheard-of)42"),
+            Some(TypedValue::Value(KdlValue::Base10(42)))
+        );
+    }
+}
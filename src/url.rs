@@ -0,0 +1,97 @@
+use url::Url;
+
+use crate::{KdlEntry, KdlValue};
+
+impl KdlValue {
+    /// Parses this value as an absolute [`Url`], if it's a string.
+    pub fn as_url(&self) -> Option<Url> {
+        Url::parse(self.as_string()?).ok()
+    }
+}
+
+/// Integration with [`url`], for values using KDL's reserved `(url)` type
+/// annotation. Requires the `url` feature.
+impl KdlEntry {
+    /// Parses this entry's value as an absolute [`Url`], if it's a string
+    /// annotated with `(url)`.
+    ///
+    /// Invalid URLs are also reported by
+    /// [`KdlDocument::parse_with_warnings`](crate::KdlDocument::parse_with_warnings),
+    /// pointing at the offending value.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let node: KdlNode = r#"service endpoint=(url)"https://example.com/api""#
+    ///     .parse()
+    ///     .unwrap();
+    /// let endpoint = node.entry("endpoint").unwrap().as_url().unwrap();
+    /// assert_eq!(endpoint.host_str(), Some("example.com"));
+    /// ```
+    pub fn as_url(&self) -> Option<Url> {
+        Url::parse(self.annotated_string("url")?).ok()
+    }
+}
+
+/// Creates a new argument holding the URL, annotated with `(url)`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::KdlEntry;
+/// use url::Url;
+///
+/// let entry = KdlEntry::from(Url::parse("https://kdl.dev").unwrap());
+/// assert_eq!(entry.to_string(), r#"(url)"https://kdl.dev/""#);
+/// ```
+impl From<Url> for KdlEntry {
+    fn from(url: Url) -> Self {
+        let mut entry = KdlEntry::new(String::from(url));
+        entry.set_ty("url");
+        entry
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{KdlDocument, KdlWarningKind};
+
+    #[test]
+    fn values() {
+        let url = KdlValue::from("https://example.com:8080/a?b=c").as_url();
+        assert_eq!(url.as_ref().and_then(|url| url.port()), Some(8080));
+        assert_eq!(KdlValue::from("/relative/path").as_url(), None);
+        assert_eq!(KdlValue::Bool(true).as_url(), None);
+    }
+
+    #[test]
+    fn entries() {
+        let url = Url::parse("postgres://db.internal/app").unwrap();
+        let entry = KdlEntry::from(url.clone());
+        assert_eq!(entry.to_string(), r#"(url)"postgres://db.internal/app""#);
+        let reparsed: KdlEntry = entry.to_string().parse().unwrap();
+        assert_eq!(reparsed.as_url(), Some(url));
+
+        let entry: KdlEntry = r#""https://kdl.dev""#.parse().unwrap();
+        assert_eq!(entry.as_url(), None);
+    }
+
+    #[test]
+    fn validation() {
+        let input =
+            "service {\n    endpoint (url)\"https://ok.example\" backup=(url)\"http://[::1\"\n}";
+        let (_, warnings) = KdlDocument::parse_with_warnings(input).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].kind,
+            KdlWarningKind::InvalidReservedValue("url".into())
+        );
+        #[cfg(feature = "span")]
+        assert_eq!(
+            &input[warnings[0].span.offset()..][..warnings[0].span.len()],
+            "\"http://[::1\""
+        );
+    }
+}
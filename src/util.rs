@@ -0,0 +1,123 @@
+use std::{
+    marker::PhantomData,
+    ptr,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Mutex,
+    },
+};
+
+use miette::SourceSpan;
+
+use crate::{KdlEntry, KdlNode};
+
+/// Items that errors point at, whether or not the `span` feature is on.
+pub(crate) trait ErrorSpan {
+    /// The item's span, or `0..0` without the `span` feature.
+    fn error_span(&self) -> SourceSpan;
+}
+
+impl ErrorSpan for KdlNode {
+    #[cfg(feature = "span")]
+    fn error_span(&self) -> SourceSpan {
+        self.span()
+    }
+
+    #[cfg(not(feature = "span"))]
+    fn error_span(&self) -> SourceSpan {
+        SourceSpan::from(0..0)
+    }
+}
+
+impl ErrorSpan for KdlEntry {
+    #[cfg(feature = "span")]
+    fn error_span(&self) -> SourceSpan {
+        self.span()
+    }
+
+    #[cfg(not(feature = "span"))]
+    fn error_span(&self) -> SourceSpan {
+        SourceSpan::from(0..0)
+    }
+}
+
+/// Runs `edit` on a copy of `value`, and only keeps the result if it
+/// succeeds, so a failed edit leaves `value` untouched.
+pub(crate) fn edit_or_restore<T: Clone, E>(
+    value: &mut T,
+    edit: impl FnOnce(&mut T) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut edited = value.clone();
+    edit(&mut edited)?;
+    *value = edited;
+    Ok(())
+}
+
+/// A value that's built the first time it's asked for, through a shared
+/// reference, without taking a lock. If two threads build it at the same
+/// time, one of them keeps theirs and the other's is dropped.
+pub(crate) struct OnceBox<T> {
+    ptr: AtomicPtr<T>,
+    // Owns a `T`, and is only `Sync` when sharing it lets any thread build
+    // one, like `OnceLock`.
+    _owns: PhantomData<(Box<T>, Mutex<T>)>,
+}
+
+impl<T> OnceBox<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            ptr: AtomicPtr::new(ptr::null_mut()),
+            _owns: PhantomData,
+        }
+    }
+
+    pub(crate) fn get(&self) -> Option<&T> {
+        // SAFETY: a non-null pointer came from `Box::into_raw` in
+        // `get_or_init`, and is only freed through `&mut self`.
+        unsafe { self.ptr.load(Ordering::Acquire).as_ref() }
+    }
+
+    pub(crate) fn get_or_init(&self, init: impl FnOnce() -> T) -> &T {
+        if let Some(value) = self.get() {
+            return value;
+        }
+        let new = Box::into_raw(Box::new(init()));
+        match self
+            .ptr
+            .compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire)
+        {
+            // SAFETY: `new` is now owned by `self`, as above.
+            Ok(_) => unsafe { &*new },
+            Err(existing) => {
+                // SAFETY: `new` was never shared, and `existing` is owned
+                // by `self`, as above.
+                unsafe {
+                    drop(Box::from_raw(new));
+                    &*existing
+                }
+            }
+        }
+    }
+
+    /// Drops the value, so that it's built again next time.
+    pub(crate) fn clear(&mut self) {
+        let old = std::mem::replace(self.ptr.get_mut(), ptr::null_mut());
+        if !old.is_null() {
+            // SAFETY: `old` came from `Box::into_raw`, and nothing else can
+            // be using it while `self` is borrowed mutably.
+            drop(unsafe { Box::from_raw(old) });
+        }
+    }
+}
+
+impl<T> Default for OnceBox<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for OnceBox<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
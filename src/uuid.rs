@@ -0,0 +1,92 @@
+use uuid::Uuid;
+
+use crate::{KdlEntry, KdlValue};
+
+impl KdlValue {
+    /// Parses this value as a [`Uuid`], if it's a string. Any format accepted
+    /// by [`Uuid::parse_str`] works, including the usual hyphenated one.
+    pub fn as_uuid(&self) -> Option<Uuid> {
+        Uuid::parse_str(self.as_string()?).ok()
+    }
+}
+
+/// Integration with [`uuid`], for values using KDL's reserved `(uuid)` type
+/// annotation. Requires the `uuid` feature.
+impl KdlEntry {
+    /// Parses this entry's value as a [`Uuid`], if it's a string annotated
+    /// with `(uuid)`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::KdlNode;
+    /// let node: KdlNode = r#"user id=(uuid)"67e55044-10b1-426f-9247-bb680e5fe0c8""#
+    ///     .parse()
+    ///     .unwrap();
+    /// let id = node.entry("id").unwrap().as_uuid().unwrap();
+    /// assert_eq!(id.as_u128(), 0x67e55044_10b1_426f_9247_bb680e5fe0c8);
+    /// ```
+    pub fn as_uuid(&self) -> Option<Uuid> {
+        Uuid::parse_str(self.annotated_string("uuid")?).ok()
+    }
+}
+
+/// Creates a new argument holding the UUID in its lowercase, hyphenated
+/// form, annotated with `(uuid)`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use kdl::KdlEntry;
+/// use uuid::Uuid;
+///
+/// let entry = KdlEntry::from(Uuid::from_u128(0x67e55044_10b1_426f_9247_bb680e5fe0c8));
+/// assert_eq!(entry.to_string(), r#"(uuid)"67e55044-10b1-426f-9247-bb680e5fe0c8""#);
+/// ```
+impl From<Uuid> for KdlEntry {
+    fn from(uuid: Uuid) -> Self {
+        let mut entry = KdlEntry::new(uuid.hyphenated().to_string());
+        entry.set_ty("uuid");
+        entry
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{KdlDocument, KdlWarningKind};
+
+    const ID: &str = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+
+    #[test]
+    fn values() {
+        let id = Uuid::parse_str(ID).unwrap();
+        assert_eq!(KdlValue::from(ID).as_uuid(), Some(id));
+        assert_eq!(KdlValue::from(ID.to_uppercase()).as_uuid(), Some(id));
+        assert_eq!(KdlValue::from("not-a-uuid").as_uuid(), None);
+        assert_eq!(KdlValue::Base10(1).as_uuid(), None);
+    }
+
+    #[test]
+    fn entries() {
+        let id = Uuid::parse_str(ID).unwrap();
+        let entry = KdlEntry::from(id);
+        assert_eq!(entry.ty().map(|ty| ty.value()), Some("uuid"));
+        let reparsed: KdlEntry = entry.to_string().parse().unwrap();
+        assert_eq!(reparsed.as_uuid(), Some(id));
+
+        let entry: KdlEntry = format!("{:?}", ID).parse().unwrap();
+        assert_eq!(entry.as_uuid(), None);
+    }
+
+    #[test]
+    fn validation() {
+        let input = format!("user (uuid){:?} (uuid)\"67e55044\"", ID);
+        let (_, warnings) = KdlDocument::parse_with_warnings(&input).unwrap();
+        let kinds: Vec<_> = warnings.into_iter().map(|w| w.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![KdlWarningKind::InvalidReservedValue("uuid".into())]
+        );
+    }
+}
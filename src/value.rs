@@ -1,7 +1,18 @@
-use std::fmt::Display;
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use crate::{parser, KdlError};
 
 /// A specific [KDL Value](https://github.com/kdl-org/kdl/blob/main/SPEC.md#value).
-#[derive(Debug, Clone, PartialOrd)]
+///
+/// Integers are stored as `i128`, which covers the full range of both `i64`
+/// and `u64`. Use the `as_*` accessors like [`KdlValue::as_u32`] to get one
+/// back as a narrower type.
+///
+/// This enum is non-exhaustive, since optional features can add variants
+/// (like `BigInt`, with the `bigint` feature), and enabling a feature
+/// anywhere in a dependency graph mustn't break anyone else's `match`es.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum KdlValue {
     /// A [KDL Raw String](https://github.com/kdl-org/kdl/blob/main/SPEC.md#raw-string).
     RawString(String),
@@ -12,17 +23,17 @@ pub enum KdlValue {
     /// A [KDL
     /// Number](https://github.com/kdl-org/kdl/blob/main/SPEC.md#number) in
     /// binary form (e.g. `0b010101`).
-    Base2(i64),
+    Base2(i128),
 
     /// A [KDL
     /// Number](https://github.com/kdl-org/kdl/blob/main/SPEC.md#number) in
     /// octal form (e.g. `0o12345670`).
-    Base8(i64),
+    Base8(i128),
 
     /// A [KDL
     /// Number](https://github.com/kdl-org/kdl/blob/main/SPEC.md#number) in
     /// decimal form (e.g. `1234567890`).
-    Base10(i64),
+    Base10(i128),
 
     /// A [KDL
     /// Number](https://github.com/kdl-org/kdl/blob/main/SPEC.md#number) in
@@ -32,7 +43,15 @@ pub enum KdlValue {
     /// A [KDL
     /// Number](https://github.com/kdl-org/kdl/blob/main/SPEC.md#number) in
     /// hexadecimal form (e.g. `1234567890abcdef`).
-    Base16(i64),
+    Base16(i128),
+
+    /// A [KDL
+    /// Number](https://github.com/kdl-org/kdl/blob/main/SPEC.md#number)
+    /// integer too large for an `i128`. The parser only produces these with
+    /// [`IntegerOverflow::BigInt`](crate::IntegerOverflow::BigInt). Requires
+    /// the `bigint` feature.
+    #[cfg(feature = "bigint")]
+    BigInt(num_bigint::BigInt),
 
     /// A [KDL Boolean](https://github.com/kdl-org/kdl/blob/main/SPEC.md#boolean).
     Bool(bool),
@@ -45,39 +64,33 @@ impl Eq for KdlValue {}
 
 impl PartialEq for KdlValue {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::RawString(l0), Self::RawString(r0)) => l0 == r0,
-            (Self::String(l0), Self::String(r0)) => l0 == r0,
-            (Self::Base2(l0), Self::Base2(r0)) => l0 == r0,
-            (Self::Base8(l0), Self::Base8(r0)) => l0 == r0,
-            (Self::Base10(l0), Self::Base10(r0)) => l0 == r0,
-            (Self::Base10Float(l0), Self::Base10Float(r0)) => {
-                let l0 = if l0 == &f64::INFINITY {
-                    f64::MAX
-                } else if l0 == &f64::NEG_INFINITY {
-                    -f64::MAX
-                } else if l0.is_nan() {
-                    // We collapse NaN to 0.0 because we're evil like that.
-                    0.0
-                } else {
-                    *l0
-                };
-                let r0 = if r0 == &f64::INFINITY {
-                    f64::MAX
-                } else if r0 == &f64::NEG_INFINITY {
-                    -f64::MAX
-                } else if r0.is_nan() {
-                    // We collapse NaN to 0.0 because we're evil like that.
-                    0.0
-                } else {
-                    *r0
-                };
-                l0 == r0
-            }
-            (Self::Base16(l0), Self::Base16(r0)) => l0 == r0,
-            (Self::Bool(l0), Self::Bool(r0)) => l0 == r0,
-            _ => core::mem::discriminant(self) == core::mem::discriminant(other),
-        }
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl PartialOrd for KdlValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Values are ordered first by kind: [`KdlValue::Null`], then booleans
+/// (`false < true`), then numbers, then strings.
+///
+/// Numbers of any variant are compared by their numeric value, so
+/// `Base10(2) < Base10Float(2.5) < Base16(3)`. Strings are compared by
+/// their contents. As with equality, floats are compared with NaN treated
+/// as `0.0` and infinities as the largest finite `f64` of the same sign,
+/// which makes the order total.
+///
+/// Values that compare as the same number or string but are different
+/// variants, like `Base10(1)` and `Base16(1)`, or `String("a")` and
+/// `RawString("a")`, aren't equal. They're ordered by variant, in the order
+/// the variants are declared.
+impl Ord for KdlValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_by_value(other)
+            .then_with(|| self.variant_index().cmp(&other.variant_index()))
     }
 }
 
@@ -91,28 +104,220 @@ impl std::hash::Hash for KdlValue {
             KdlValue::Base2(val) => val.hash(state),
             KdlValue::Base8(val) => val.hash(state),
             KdlValue::Base10(val) => val.hash(state),
-            KdlValue::Base10Float(val) => {
-                let val = if val == &f64::INFINITY {
-                    f64::MAX
-                } else if val == &f64::NEG_INFINITY {
-                    -f64::MAX
-                } else if val.is_nan() {
-                    // We collapse NaN to 0.0 because we're evil like that.
-                    0.0
-                } else {
-                    *val
-                };
-                // Good enough to be close-ish for our purposes.
-                (val.trunc() as i64).hash(state);
-                (val.fract() as i64).hash(state);
-            }
+            KdlValue::Base10Float(val) => normalize_float(*val).to_bits().hash(state),
             KdlValue::Base16(val) => val.hash(state),
+            #[cfg(feature = "bigint")]
+            KdlValue::BigInt(val) => val.hash(state),
             KdlValue::Bool(val) => val.hash(state),
             KdlValue::Null => core::mem::discriminant(self).hash(state),
         }
     }
 }
 
+/// Collapses floats so that every value has a single representation: NaN
+/// becomes `0.0` (because we're evil like that), infinities become the
+/// largest finite `f64` of the same sign, and `-0.0` becomes `0.0`.
+fn normalize_float(value: f64) -> f64 {
+    if value == f64::INFINITY {
+        f64::MAX
+    } else if value == f64::NEG_INFINITY {
+        -f64::MAX
+    } else if value.is_nan() || value == 0.0 {
+        0.0
+    } else {
+        value
+    }
+}
+
+/// A numeric [`KdlValue`], for comparing across variants.
+enum Number {
+    Int(i128),
+    Float(f64),
+    #[cfg(feature = "bigint")]
+    Big(num_bigint::BigInt),
+}
+
+impl Number {
+    fn cmp(&self, other: &Self) -> Ordering {
+        use Number::*;
+        match (self, other) {
+            (Int(l), Int(r)) => l.cmp(r),
+            (Float(l), Float(r)) => cmp_floats(*l, *r),
+            (Int(i), Float(f)) => cmp_int_float(*i, *f),
+            (Float(f), Int(i)) => cmp_int_float(*i, *f).reverse(),
+            #[cfg(feature = "bigint")]
+            (Big(l), Big(r)) => l.cmp(r),
+            #[cfg(feature = "bigint")]
+            (Big(b), Int(i)) => b.cmp(&num_bigint::BigInt::from(*i)),
+            #[cfg(feature = "bigint")]
+            (Int(i), Big(b)) => num_bigint::BigInt::from(*i).cmp(b),
+            #[cfg(feature = "bigint")]
+            (Big(b), Float(f)) => cmp_big_float(b, *f),
+            #[cfg(feature = "bigint")]
+            (Float(f), Big(b)) => cmp_big_float(b, *f).reverse(),
+        }
+    }
+}
+
+/// Compares two normalized floats (see [`normalize_float`]). Since they're
+/// never NaN, this is a total order.
+fn cmp_floats(l: f64, r: f64) -> Ordering {
+    l.partial_cmp(&r).expect("normalized floats are never NaN")
+}
+
+/// `2^127`, the magnitude just past the range of `i128`.
+const I128_LIMIT: f64 = 170_141_183_460_469_231_731_687_303_715_884_105_728.0;
+
+/// Compares an integer and a (finite) float exactly.
+fn cmp_int_float(int: i128, float: f64) -> Ordering {
+    if float >= I128_LIMIT {
+        return Ordering::Less;
+    } else if float < -I128_LIMIT {
+        return Ordering::Greater;
+    }
+    let trunc = float.trunc();
+    int.cmp(&(trunc as i128))
+        .then_with(|| cmp_floats(0.0, float - trunc))
+}
+
+/// Compares a big integer outside the range of `i128` (see
+/// [`KdlValue::as_number`]) and a (finite) float exactly.
+#[cfg(feature = "bigint")]
+fn cmp_big_float(big: &num_bigint::BigInt, float: f64) -> Ordering {
+    if float.abs() < I128_LIMIT {
+        return match big.sign() {
+            num_bigint::Sign::Minus => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+    }
+    // Floats this large are always integers.
+    let float: num_bigint::BigInt = format!("{:.0}", float).parse().unwrap();
+    big.cmp(&float)
+}
+
+impl KdlValue {
+    /// Compares values the way [`Ord`] does, but without telling apart
+    /// variants that hold the same number or string.
+    fn cmp_by_value(&self, other: &Self) -> Ordering {
+        use KdlValue::*;
+        match (self, other) {
+            (Null, Null) => Ordering::Equal,
+            (Bool(l), Bool(r)) => l.cmp(r),
+            (String(l) | RawString(l), String(r) | RawString(r)) => l.cmp(r),
+            _ => match (self.as_number(), other.as_number()) {
+                (Some(l), Some(r)) => l.cmp(&r),
+                _ => self.kind_rank().cmp(&other.kind_rank()),
+            },
+        }
+    }
+
+    /// Whether two values are the same number, string, boolean, or null,
+    /// whatever their variants.
+    pub(crate) fn same_value(&self, other: &Self) -> bool {
+        self.cmp_by_value(other) == Ordering::Equal
+    }
+
+    /// The simplest way of writing this value, which is the same for every
+    /// value it's [`KdlValue::same_value`] as: integral numbers become base 10
+    /// integers, and raw strings become plain ones.
+    pub(crate) fn canonical(&self) -> KdlValue {
+        match self.as_number() {
+            Some(Number::Int(i)) => KdlValue::Base10(i),
+            Some(Number::Float(f)) if f.fract() == 0.0 && f.abs() < I128_LIMIT => {
+                KdlValue::Base10(f as i128)
+            }
+            Some(Number::Float(f)) => KdlValue::Base10Float(f),
+            #[cfg(feature = "bigint")]
+            Some(Number::Big(b)) => KdlValue::BigInt(b),
+            None => match self {
+                KdlValue::RawString(s) => KdlValue::String(s.clone()),
+                other => other.clone(),
+            },
+        }
+    }
+
+    /// Hashes this value consistently with [`KdlValue::same_value`].
+    pub(crate) fn hash_value<H: std::hash::Hasher>(&self, state: &mut H) {
+        use std::hash::Hash;
+        self.kind_rank().hash(state);
+        match self.as_number() {
+            Some(Number::Int(i)) => i.hash(state),
+            // Integral floats can be equal to integers, so they're hashed
+            // as one. Larger ones (and big integers) only hash their kind.
+            Some(Number::Float(f)) if f.fract() == 0.0 && f.abs() < I128_LIMIT => {
+                (f as i128).hash(state)
+            }
+            Some(Number::Float(f)) if f.fract() != 0.0 => f.to_bits().hash(state),
+            Some(_) => {}
+            None => match self {
+                KdlValue::String(s) | KdlValue::RawString(s) => s.hash(state),
+                KdlValue::Bool(b) => b.hash(state),
+                _ => {}
+            },
+        }
+    }
+
+    fn as_number(&self) -> Option<Number> {
+        use KdlValue::*;
+        match self {
+            Base2(i) | Base8(i) | Base10(i) | Base16(i) => Some(Number::Int(*i)),
+            Base10Float(f) => Some(Number::Float(normalize_float(*f))),
+            // Big integers can be built small, so those are compared (and
+            // hashed) like any other integer, and `Number::Big` is only ever
+            // outside the range of `i128`.
+            #[cfg(feature = "bigint")]
+            BigInt(b) => Some(match i128::try_from(b) {
+                Ok(i) => Number::Int(i),
+                Err(_) => Number::Big(b.clone()),
+            }),
+            _ => None,
+        }
+    }
+
+    fn kind_rank(&self) -> u8 {
+        use KdlValue::*;
+        match self {
+            Null => 0,
+            Bool(_) => 1,
+            String(_) | RawString(_) => 3,
+            _ => 2,
+        }
+    }
+
+    fn variant_index(&self) -> u8 {
+        use KdlValue::*;
+        match self {
+            RawString(_) => 0,
+            String(_) => 1,
+            Base2(_) => 2,
+            Base8(_) => 3,
+            Base10(_) => 4,
+            Base10Float(_) => 5,
+            Base16(_) => 6,
+            #[cfg(feature = "bigint")]
+            BigInt(_) => 7,
+            Bool(_) => 8,
+            Null => 9,
+        }
+    }
+}
+
+macro_rules! int_accessors {
+    ($($name:ident: $ty:ty),* $(,)?) => {
+        $(
+            #[doc = concat!(
+                "Returns `Some(", stringify!($ty), ")` if the `KdlValue` is an integer ",
+                "([`KdlValue::Base2`], [`KdlValue::Base8`], [`KdlValue::Base10`], or ",
+                "[`KdlValue::Base16`]) that fits in an `", stringify!($ty), "`, ",
+                "otherwise returns `None`."
+            )]
+            pub fn $name(&self) -> Option<$ty> {
+                <$ty>::try_from(self.as_i128()?).ok()
+            }
+        )*
+    };
+}
+
 impl KdlValue {
     /// Returns `true` if the value is a [`KdlValue::RawString`].
     pub fn is_raw_string(&self) -> bool {
@@ -188,10 +393,10 @@ impl KdlValue {
         }
     }
 
-    /// Returns `Some(i64)` if the `KdlValue` is a [`KdlValue::Base2`],
+    /// Returns `Some(i128)` if the `KdlValue` is a [`KdlValue::Base2`],
     /// [`KdlValue::Base8`], [`KdlValue::Base10`], or [`KdlValue::Base16`],
     /// otherwise returns `None`.
-    pub fn as_i64(&self) -> Option<i64> {
+    pub fn as_i128(&self) -> Option<i128> {
         use KdlValue::*;
         match self {
             Base2(i) | Base8(i) | Base10(i) | Base16(i) => Some(*i),
@@ -199,6 +404,20 @@ impl KdlValue {
         }
     }
 
+    int_accessors! {
+        as_i8: i8,
+        as_i16: i16,
+        as_i32: i32,
+        as_i64: i64,
+        as_isize: isize,
+        as_u8: u8,
+        as_u16: u16,
+        as_u32: u32,
+        as_u64: u64,
+        as_u128: u128,
+        as_usize: usize,
+    }
+
     /// Returns `Some(f64)` if the `KdlValue` is a [`KdlValue::Base10Float`],
     /// otherwise returns `None`.
     pub fn as_f64(&self) -> Option<f64> {
@@ -209,6 +428,78 @@ impl KdlValue {
         }
     }
 
+    /// Creates a [`KdlValue::Base10Float`], or returns `None` if `value` is
+    /// NaN or infinite, since KDL 1.0 can't represent those.
+    ///
+    /// `KdlValue::from(f64)` accepts any float, but non-finite ones can't be
+    /// written out faithfully; see [`NonFiniteFloats`](crate::NonFiniteFloats).
+    pub fn from_finite(value: f64) -> Option<Self> {
+        if value.is_finite() {
+            Some(KdlValue::Base10Float(value))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value as an `i64` if that can be done without losing
+    /// anything: integers that fit in an `i64`, and floats with no
+    /// fractional part that do too (so `3.0` is `Some(3)`, but `3.5` is
+    /// `None`). Returns `None` for everything else.
+    pub fn as_i64_lossless(&self) -> Option<i64> {
+        i64::try_from(self.as_i128_lossless()?).ok()
+    }
+
+    /// Like [`KdlValue::as_i64_lossless`], but for `u64`.
+    pub fn as_u64_lossless(&self) -> Option<u64> {
+        u64::try_from(self.as_i128_lossless()?).ok()
+    }
+
+    /// Like [`KdlValue::as_i64_lossless`], but for `i128`.
+    pub fn as_i128_lossless(&self) -> Option<i128> {
+        match self {
+            Self::Base10Float(value) => {
+                if value.fract() == 0.0 && (-I128_LIMIT..I128_LIMIT).contains(value) {
+                    Some(*value as i128)
+                } else {
+                    None
+                }
+            }
+            _ => self.as_i128(),
+        }
+    }
+
+    /// Returns the value as an `f64` if that can be done without losing
+    /// anything: floats, and integers that an `f64` can represent exactly
+    /// (every integer up to 2<sup>53</sup> in magnitude, and some beyond).
+    /// Returns `None` for everything else.
+    ///
+    /// NaN and infinite floats are returned as they are.
+    pub fn as_f64_lossless(&self) -> Option<f64> {
+        match self {
+            Self::Base10Float(value) => Some(*value),
+            _ => {
+                let int = self.as_i128()?;
+                let float = int as f64;
+                if float < I128_LIMIT && float as i128 == int {
+                    Some(float)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Returns any number as an `f64`, rounding to the nearest `f64` if it
+    /// can't be represented exactly. Returns `None` for non-numbers.
+    pub fn as_f64_lossy(&self) -> Option<f64> {
+        match self {
+            Self::Base10Float(value) => Some(*value),
+            #[cfg(feature = "bigint")]
+            Self::BigInt(value) => value.to_string().parse().ok(),
+            _ => self.as_i128().map(|int| int as f64),
+        }
+    }
+
     /// Returns `Some(bool)` if the `KdlValue` is a [`KdlValue::Bool`], otherwise returns `None`.
     pub fn as_bool(&self) -> Option<bool> {
         if let Self::Bool(v) = self {
@@ -224,8 +515,8 @@ impl Display for KdlValue {
         match self {
             Self::RawString(_) => self.write_raw_string(f),
             Self::String(_) => self.write_string(f),
-            Self::Base2(value) => write!(f, "0b{:b}", value),
-            Self::Base8(value) => write!(f, "0o{:o}", value),
+            Self::Base2(value) => write!(f, "{}0b{:b}", sign(*value), value.unsigned_abs()),
+            Self::Base8(value) => write!(f, "{}0o{:o}", sign(*value), value.unsigned_abs()),
             Self::Base10(value) => write!(f, "{:?}", value),
             Self::Base10Float(value) => write!(
                 f,
@@ -240,13 +531,37 @@ impl Display for KdlValue {
                     *value
                 }
             ),
-            Self::Base16(value) => write!(f, "0x{:x}", value),
+            Self::Base16(value) => write!(f, "{}0x{:x}", sign(*value), value.unsigned_abs()),
+            #[cfg(feature = "bigint")]
+            Self::BigInt(value) => write!(f, "{}", value),
             Self::Bool(value) => write!(f, "{}", value),
             Self::Null => write!(f, "null"),
         }
     }
 }
 
+/// The sign to write in front of a non-decimal integer, since formatting
+/// them as binary, octal, or hex would write negative ones as two's
+/// complement.
+fn sign(value: i128) -> &'static str {
+    if value < 0 {
+        "-"
+    } else {
+        ""
+    }
+}
+
+impl FromStr for KdlValue {
+    type Err = KdlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kdl_parser = parser::KdlParser::new(s);
+        kdl_parser
+            .parse(parser::parser_value(&kdl_parser))
+            .map(|(_, value)| value)
+    }
+}
+
 impl KdlValue {
     fn write_string(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let string = self.as_string().unwrap();
@@ -267,15 +582,18 @@ impl KdlValue {
     }
     fn write_raw_string(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let raw = self.as_string().unwrap();
-        let mut consecutive = 0usize;
+        // The string can't contain a `"` followed by as many `#`s as the
+        // delimiters have, or it'd end early.
+        let mut after_quote = None;
         let mut maxhash = 0usize;
         for char in raw.chars() {
-            if char == '#' {
-                consecutive += 1;
-            } else if char == '"' {
-                maxhash = maxhash.max(consecutive + 1);
-            } else {
-                consecutive = 0;
+            after_quote = match (char, after_quote) {
+                ('"', _) => Some(0),
+                ('#', Some(hashes)) => Some(hashes + 1),
+                _ => None,
+            };
+            if let Some(hashes) = after_quote {
+                maxhash = maxhash.max(hashes + 1);
             }
         }
         write!(f, "r")?;
@@ -286,18 +604,32 @@ impl KdlValue {
     }
 }
 
-impl From<i64> for KdlValue {
-    fn from(value: i64) -> Self {
-        KdlValue::Base10(value)
-    }
+macro_rules! from_ints {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for KdlValue {
+                fn from(value: $ty) -> Self {
+                    KdlValue::Base10(value as i128)
+                }
+            }
+        )*
+    };
 }
 
+from_ints!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, usize);
+
 impl From<f64> for KdlValue {
     fn from(value: f64) -> Self {
         KdlValue::Base10Float(value)
     }
 }
 
+impl From<f32> for KdlValue {
+    fn from(value: f32) -> Self {
+        KdlValue::Base10Float(value.into())
+    }
+}
+
 impl From<&str> for KdlValue {
     fn from(value: &str) -> Self {
         KdlValue::String(value.to_string())
@@ -364,4 +696,169 @@ mod test {
         let null = KdlValue::Null;
         assert_eq!(format!("{}", null), "null");
     }
+
+    #[test]
+    fn round_trip() -> miette::Result<()> {
+        use KdlValue::*;
+        for value in [
+            RawString("a\"#b".into()),
+            RawString("\"##\"#".into()),
+            String("\"\\\n\u{7}".into()),
+            Base2(-5),
+            Base8(-0o17),
+            Base10(i128::MIN),
+            Base10Float(-0.25),
+            Base10Float(1e300),
+            Base10Float(1.0),
+            Base16(-0xff),
+            Base16(i128::MIN),
+            Bool(false),
+            Null,
+        ] {
+            let text = value.to_string();
+            let parsed: KdlValue = text.parse()?;
+            assert_eq!(parsed, value, "{}", text);
+        }
+        assert_eq!("0x10".parse::<KdlValue>()?, Base16(16));
+        assert!("bare".parse::<KdlValue>().is_err());
+        assert!("(u8)1".parse::<KdlValue>().is_err());
+        assert!(" 1".parse::<KdlValue>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn ordering() {
+        use KdlValue::*;
+        let sorted = vec![
+            Null,
+            Bool(false),
+            Bool(true),
+            Base10Float(f64::NEG_INFINITY),
+            Base10(-5),
+            Base10Float(-4.5),
+            Base10(0),
+            Base10Float(0.5),
+            Base2(1),
+            Base10(1),
+            Base10Float(1.0),
+            Base16(1),
+            Base8(2),
+            Base10(i128::MAX),
+            Base10Float(1e300),
+            RawString("a".into()),
+            String("a".into()),
+            String("b".into()),
+        ];
+        let mut shuffled = sorted.clone();
+        shuffled.reverse();
+        shuffled.sort();
+        assert_eq!(shuffled, sorted);
+        for (i, l) in sorted.iter().enumerate() {
+            for (j, r) in sorted.iter().enumerate() {
+                assert_eq!(l.cmp(r), i.cmp(&j), "{:?} vs {:?}", l, r);
+            }
+        }
+
+        // Floats follow the same collapsing rules as equality.
+        assert_eq!(
+            Base10Float(f64::NAN).cmp(&Base10Float(0.0)),
+            Ordering::Equal
+        );
+        assert_eq!(Base10Float(-0.0).cmp(&Base10Float(0.0)), Ordering::Equal);
+        assert_eq!(
+            Base10Float(f64::INFINITY).cmp(&Base10Float(f64::MAX)),
+            Ordering::Equal
+        );
+        assert!(Base10Float(f64::MAX) > Base10(i128::MAX));
+        assert!(
+            Base10Float(9007199254740993.0) < Base10(9007199254740993),
+            "compared exactly, not through f64"
+        );
+    }
+
+    #[test]
+    fn hashing() {
+        use std::collections::{BTreeMap, HashSet};
+        use KdlValue::*;
+
+        let set: HashSet<_> = [
+            Base10Float(0.0),
+            Base10Float(-0.0),
+            Base10Float(f64::NAN),
+            Base10Float(0.5),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(set.len(), 2);
+
+        let mut map = BTreeMap::new();
+        map.insert(String("b".into()), 1);
+        map.insert(Base10(3), 2);
+        map.insert(Null, 3);
+        assert_eq!(
+            map.keys().collect::<Vec<_>>(),
+            [&Null, &Base10(3), &String("b".into())]
+        );
+    }
+
+    #[test]
+    fn coercions() {
+        use KdlValue::*;
+
+        assert_eq!(Base10Float(3.0).as_i64_lossless(), Some(3));
+        assert_eq!(Base10Float(-3.0).as_i64_lossless(), Some(-3));
+        assert_eq!(Base10Float(-3.0).as_u64_lossless(), None);
+        assert_eq!(Base10Float(3.5).as_i64_lossless(), None);
+        assert_eq!(Base10Float(f64::NAN).as_i64_lossless(), None);
+        assert_eq!(Base10Float(f64::INFINITY).as_i128_lossless(), None);
+        assert_eq!(Base10Float(1e19).as_i64_lossless(), None);
+        assert_eq!(
+            Base10Float(1e19).as_u64_lossless(),
+            Some(10_000_000_000_000_000_000)
+        );
+        assert_eq!(Base10Float(2f64.powi(127)).as_i128_lossless(), None);
+        assert_eq!(Base16(7).as_i64_lossless(), Some(7));
+        assert_eq!(Base10(u64::MAX.into()).as_i64_lossless(), None);
+        assert_eq!(String("3".into()).as_i64_lossless(), None);
+
+        assert_eq!(Base10(1 << 53).as_f64_lossless(), Some(2f64.powi(53)));
+        assert_eq!(Base10((1 << 53) + 1).as_f64_lossless(), None);
+        assert_eq!(Base10(1 << 100).as_f64_lossless(), Some(2f64.powi(100)));
+        assert_eq!(Base10(i128::MAX).as_f64_lossless(), None);
+        assert_eq!(Base10(i128::MIN).as_f64_lossless(), Some(-(2f64.powi(127))));
+        assert_eq!(
+            Base10Float(-(2f64.powi(127))).as_i128_lossless(),
+            Some(i128::MIN)
+        );
+        assert_eq!(Base10Float(0.1).as_f64_lossless(), Some(0.1));
+
+        assert_eq!(Base10((1 << 53) + 1).as_f64_lossy(), Some(2f64.powi(53)));
+        assert_eq!(Base10(i128::MAX).as_f64_lossy(), Some(2f64.powi(127)));
+        assert_eq!(Base10Float(0.1).as_f64_lossy(), Some(0.1));
+        assert_eq!(Bool(true).as_f64_lossy(), None);
+    }
+
+    #[test]
+    fn integer_widths() {
+        let big = KdlValue::from(u64::MAX);
+        assert_eq!(big.as_u64(), Some(u64::MAX));
+        assert_eq!(big.as_i64(), None);
+        assert_eq!(big.as_u128(), Some(u64::MAX as u128));
+        assert_eq!(big.as_i128(), Some(u64::MAX as i128));
+
+        let small = KdlValue::Base16(-1);
+        assert_eq!(small.as_i8(), Some(-1));
+        assert_eq!(small.as_u8(), None);
+        assert_eq!(small.as_u128(), None);
+
+        let byte = KdlValue::Base2(255);
+        assert_eq!(byte.as_u8(), Some(255));
+        assert_eq!(byte.as_i8(), None);
+        assert_eq!(byte.as_i16(), Some(255));
+        assert_eq!(byte.as_usize(), Some(255));
+
+        assert_eq!(KdlValue::from(i128::MIN).as_i128(), Some(i128::MIN));
+        assert_eq!(KdlValue::Base10Float(1.0).as_u32(), None);
+        assert_eq!(KdlValue::from("1").as_u32(), None);
+    }
 }
@@ -0,0 +1,616 @@
+use std::{borrow::Cow, ops::Range};
+
+use crate::{
+    emit::{multi_line_raw_string, non_finite_name, string_indent},
+    parser::{self, KdlParser},
+    KdlDocument, KdlEntry, KdlError, KdlIdentifier, KdlNode, KdlValue, KdlVersion, ParseOptions,
+};
+
+impl KdlDocument {
+    /// Rewrites a KDL 1.0 document as KDL 2.0, so that it can be parsed
+    /// with [`KdlVersion::V2`].
+    ///
+    /// Only the parts of the document that mean something different (or
+    /// nothing at all) in KDL 2.0 are changed, so comments, whitespace, and
+    /// the formatting of everything else are kept as they are:
+    ///
+    /// * `true`, `false`, and `null` become `#true`, `#false`, and `#null`,
+    ///   and NaN and infinite floats become `#nan`, `#inf`, and `#-inf`.
+    /// * Identifiers that aren't valid bare identifiers any more, such as
+    ///   `a#b` or `inf`, are quoted.
+    /// * `r"raw"` strings become `#"raw"#`, and ones that span lines become
+    ///   multi-line strings. Quoted strings that span lines or use the `\/`
+    ///   escape are re-escaped.
+    /// * Slashdashed nodes and entries are converted too, since KDL 2.0
+    ///   parsers still have to parse them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlDocument, KdlVersion, ParseOptions};
+    /// let v1: KdlDocument = r#"
+    /// // Our settings
+    /// server "a#b" enabled=true {
+    ///     path r"C:\srv"  /-backup=null
+    /// }
+    /// "#.parse().unwrap();
+    ///
+    /// let v2 = v1.to_v2();
+    /// assert_eq!(v2.to_string(), r##"
+    /// // Our settings
+    /// server "a#b" enabled=#true {
+    ///     path #"C:\srv"#  /-backup=#null
+    /// }
+    /// "##);
+    ///
+    /// let options = ParseOptions {
+    ///     version: KdlVersion::V2,
+    ///     ..ParseOptions::default()
+    /// };
+    /// let reparsed = KdlDocument::parse_with_options(&v2.to_string(), &options).unwrap();
+    /// assert_eq!(reparsed.to_v1().to_string(), v1.to_string());
+    /// ```
+    pub fn to_v2(&self) -> KdlDocument {
+        self.converted(KdlVersion::V1, KdlVersion::V2)
+    }
+
+    /// Rewrites a KDL 2.0 document (one parsed with [`KdlVersion::V2`]) as
+    /// KDL 1.0, the reverse of [`KdlDocument::to_v2`].
+    ///
+    /// `#` keywords lose their `#`, bare identifier strings and identifiers
+    /// that aren't valid in KDL 1.0 are quoted, `#"raw"#` strings get their
+    /// `r` back, and multi-line strings are written on one line.
+    ///
+    /// KDL 1.0 has no syntax for NaN or infinity, so `#nan`, `#inf`, and
+    /// `#-inf` are written as `(f64)"NaN"`, `(f64)"inf"`, and `(f64)"-inf"`,
+    /// the same as [`NonFiniteFloats::Annotation`](crate::NonFiniteFloats::Annotation)
+    /// does. This is the only change to the document's values:
+    /// [`KdlEntry::as_f64`] still reads them.
+    pub fn to_v1(&self) -> KdlDocument {
+        self.converted(KdlVersion::V2, KdlVersion::V1)
+    }
+
+    /// Parses a document that may be written in either KDL 1.0 or KDL 2.0,
+    /// picking the right grammar for it. [`KdlDocument::version`] says which
+    /// one it was.
+    ///
+    /// Documents that start with a `/- kdl-version 1` or `/- kdl-version 2`
+    /// node, as the KDL 2.0 spec suggests, are always parsed as that
+    /// version. Otherwise, documents using `#true`, `#null`, `#"raw"#`
+    /// strings, or other syntax only KDL 2.0 has are tried as KDL 2.0
+    /// first, and everything else as KDL 1.0 first. If that fails, the
+    /// other version is tried, and if both fail, the error from the first
+    /// attempt is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use kdl::{KdlDocument, KdlVersion};
+    /// let doc = KdlDocument::parse_auto("node true").unwrap();
+    /// assert_eq!(doc.version(), KdlVersion::V1);
+    ///
+    /// let doc = KdlDocument::parse_auto("node bare-string #true").unwrap();
+    /// assert_eq!(doc.version(), KdlVersion::V2);
+    ///
+    /// let doc = KdlDocument::parse_auto("/- kdl-version 2\nnode 1").unwrap();
+    /// assert_eq!(doc.version(), KdlVersion::V2);
+    /// ```
+    pub fn parse_auto(input: &str) -> Result<KdlDocument, KdlError> {
+        let parse = |version| KdlDocument::parse_with_options(input, &Converter::options(version));
+        if let Some(version) = version_marker(input) {
+            return parse(version);
+        }
+        let (first, second) = if looks_like_v2(input) {
+            (KdlVersion::V2, KdlVersion::V1)
+        } else {
+            (KdlVersion::V1, KdlVersion::V2)
+        };
+        parse(first).or_else(|err| parse(second).map_err(|_| err))
+    }
+
+    fn converted(&self, from: KdlVersion, to: KdlVersion) -> KdlDocument {
+        let mut doc = self.clone();
+        Converter {
+            from,
+            to,
+            keep_non_finite: false,
+        }
+        .document(&mut doc, 0);
+        doc
+    }
+
+    /// Rewrites this document as `to` for
+    /// [`KdlDocument::to_string_with_options`]. NaN and infinite floats are
+    /// left alone when writing KDL 1.0, for `NonFiniteFloats` to deal with.
+    pub(crate) fn converted_for_emit(&self, to: KdlVersion) -> KdlDocument {
+        let mut doc = self.clone();
+        Converter {
+            from: self.version,
+            to,
+            keep_non_finite: to == KdlVersion::V1,
+        }
+        .document(&mut doc, 0);
+        doc
+    }
+}
+
+struct Converter {
+    from: KdlVersion,
+    to: KdlVersion,
+    keep_non_finite: bool,
+}
+
+impl Converter {
+    fn options(version: KdlVersion) -> ParseOptions {
+        ParseOptions {
+            version,
+            ..ParseOptions::default()
+        }
+    }
+
+    fn document(&self, doc: &mut KdlDocument, depth: usize) {
+        doc.version = self.to;
+        self.trivia(&mut doc.leading);
+        for node in doc.nodes.iter_mut().chain(&mut doc.disabled) {
+            self.node(node, depth);
+        }
+        self.trivia(&mut doc.trailing);
+    }
+
+    fn node(&self, node: &mut KdlNode, depth: usize) {
+        node.version = self.to;
+        for node in &mut node.disabled {
+            self.node(node, depth);
+        }
+        self.trivia(&mut node.slashdash);
+        self.trivia(&mut node.leading);
+        if let Some(ty) = node.ty.as_mut() {
+            self.identifier(ty);
+        }
+        self.identifier(&mut node.name);
+        let indent = string_indent(node, depth);
+        for entry in node.entries_mut() {
+            self.entry(entry, &indent);
+        }
+        self.trivia(&mut node.before_children);
+        if let Some(children) = node.children_mut() {
+            self.document(children, depth + 1);
+        }
+        self.trivia(&mut node.trailing);
+    }
+
+    fn entry(&self, entry: &mut KdlEntry, indent: &str) {
+        self.trivia(&mut entry.leading);
+        if let Some(name) = entry.name.as_mut() {
+            self.identifier(name);
+        }
+        if let Some(ty) = entry.ty.as_mut() {
+            self.identifier(ty);
+        }
+        self.value(entry, indent);
+        self.trivia(&mut entry.trailing);
+    }
+
+    fn identifier(&self, ident: &mut KdlIdentifier) {
+        let text = ident.to_string();
+        if self.parse_identifier(&text, self.to).as_deref() == Some(ident.value()) {
+            return;
+        }
+        let bare = ident.value();
+        let repr = if self.parse_identifier(bare, self.to).as_deref() == Some(bare) {
+            bare.to_string()
+        } else if self.to == KdlVersion::V1 {
+            KdlValue::String(bare.into()).to_string()
+        } else {
+            quote_v2(bare)
+        };
+        ident.set_repr(repr);
+    }
+
+    fn value(&self, entry: &mut KdlEntry, indent: &str) {
+        let non_finite = matches!(entry.value, KdlValue::Base10Float(x) if !x.is_finite());
+        if non_finite && self.keep_non_finite {
+            return;
+        }
+        let text = match &entry.value_repr {
+            Some(repr) => repr.clone(),
+            None => entry.value.to_string(),
+        };
+        if !non_finite && self.parse_value(&text).as_ref() == Some(&entry.value) {
+            return;
+        }
+        let value = entry.value.clone();
+        let repr = match (self.to, &value) {
+            (KdlVersion::V1, KdlValue::Base10Float(x)) if non_finite => {
+                entry.set_ty("f64");
+                let name = if x.is_nan() {
+                    "NaN"
+                } else {
+                    non_finite_name(*x)
+                };
+                format!("{:?}", name)
+            }
+            (KdlVersion::V1, value) => value.to_string(),
+            (_, KdlValue::RawString(s)) if s.contains(is_newline) => {
+                match multi_line_raw_string(s, indent) {
+                    Some(repr) => repr,
+                    None => {
+                        // Raw strings can't escape anything, so this one
+                        // has to become a regular string.
+                        let repr = quote_v2(s);
+                        entry.value = KdlValue::String(s.clone());
+                        repr
+                    }
+                }
+            }
+            (_, KdlValue::RawString(s)) if s.contains(parser::is_disallowed_v2) => {
+                let repr = quote_v2(s);
+                entry.value = KdlValue::String(s.clone());
+                repr
+            }
+            (_, value) => value_repr(value, KdlVersion::V2).into_owned(),
+        };
+        entry.value_repr = Some(repr);
+    }
+
+    /// Converts the slashdashed entries and children blocks in a stretch of
+    /// whitespace and comments, along with line breaks that only KDL 2.0
+    /// has. The rest of it is the same in both versions.
+    fn trivia(&self, text: &mut Option<String>) {
+        let original = match text {
+            Some(text) if text.contains("/-") || self.space(text) != **text => text.clone(),
+            _ => return,
+        };
+        let mut converted = String::new();
+        let mut last = 0;
+        for range in parser::comment_ranges(&original, true, self.from) {
+            let comment = &original[range.clone()];
+            if let Some(item) = comment.strip_prefix("/-") {
+                if let Some(item) = self.slashdashed(item) {
+                    converted.push_str(&self.space(&original[last..range.start]));
+                    converted.push_str("/-");
+                    converted.push_str(&item);
+                    last = range.end;
+                }
+            }
+        }
+        converted.push_str(&self.space(&original[last..]));
+        *text = Some(converted);
+    }
+
+    /// Rewrites vertical tabs, which are line breaks in KDL 2.0 but not
+    /// allowed at all outside of strings in KDL 1.0, as newlines.
+    fn space<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        if self.to == KdlVersion::V1 && text.contains('\u{0B}') {
+            Cow::Owned(text.replace('\u{0B}', "\n"))
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// Converts a slashdashed entry or children block, or returns `None` if
+    /// it can't be parsed on its own. It's parsed as part of a placeholder
+    /// node.
+    fn slashdashed(&self, item: &str) -> Option<String> {
+        let prefix = "-";
+        let separator = if item.starts_with(|c: char| c.is_whitespace() || c == '{') {
+            ""
+        } else {
+            " "
+        };
+        let input = format!("{}{}{}", prefix, separator, item);
+        let doc = KdlDocument::parse_with_options(&input, &Self::options(self.from)).ok()?;
+        let converted = doc.converted(self.from, self.to).to_string();
+        converted
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_prefix(separator))
+            .map(String::from)
+    }
+
+    fn parse_identifier(&self, text: &str, version: KdlVersion) -> Option<String> {
+        if version == KdlVersion::V2 && text.contains(parser::is_disallowed_v2) {
+            return None;
+        }
+        let kdl_parser = KdlParser::with_options(text, &Self::options(version));
+        let ident = kdl_parser.parse(parser::identifier(&kdl_parser)).ok()?;
+        Some(ident.value)
+    }
+
+    fn parse_value(&self, text: &str) -> Option<KdlValue> {
+        // Parsing a single value doesn't check the characters that KDL 2.0
+        // doesn't allow anywhere in a document, so check them here.
+        if self.to == KdlVersion::V2 && text.contains(parser::is_disallowed_v2) {
+            return None;
+        }
+        let kdl_parser = KdlParser::with_options(text, &Self::options(self.to));
+        let (_, value) = kdl_parser.parse(parser::parser_value(&kdl_parser)).ok()?;
+        Some(value)
+    }
+}
+
+/// Reads the version from a `/- kdl-version <n>` node at the start of
+/// `input`, if there is one.
+fn version_marker(input: &str) -> Option<KdlVersion> {
+    let number = version_marker_number(input)?;
+    match &input[number] {
+        "1" => Some(KdlVersion::V1),
+        "2" => Some(KdlVersion::V2),
+        _ => None,
+    }
+}
+
+/// Finds the version number in a `/- kdl-version <n>` node at the start of
+/// `input`, returning its byte range.
+pub(crate) fn version_marker_number(input: &str) -> Option<Range<usize>> {
+    let is_space = |c: char| parser::is_space_v2(c) || c == '\u{FEFF}';
+    let rest = input.trim_start_matches(|c: char| is_space(c) || is_newline(c));
+    let rest = rest.strip_prefix("/-")?.trim_start_matches(is_space);
+    let rest = rest.strip_prefix("kdl-version")?;
+    let number = rest.trim_start_matches(is_space);
+    if number.len() == rest.len() {
+        return None;
+    }
+    let end = number
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(number.len());
+    let after = number[end..].chars().next();
+    if !matches!(after, None | Some(';') | Some('/'))
+        && !after.map_or(false, |c| is_space(c) || is_newline(c))
+    {
+        return None;
+    }
+    let start = input.len() - number.len();
+    Some(start..start + end)
+}
+
+/// How `value` is written in `version` by an entry without a
+/// [repr](KdlEntry::value_repr) of its own.
+pub(crate) fn value_repr(value: &KdlValue, version: KdlVersion) -> Cow<'static, str> {
+    let repr = match (version, value) {
+        (KdlVersion::V1, value) => value.to_string(),
+        (_, KdlValue::Base10Float(x)) if !x.is_finite() => format!("#{}", non_finite_name(*x)),
+        (_, KdlValue::Bool(b)) => format!("#{}", b),
+        (_, KdlValue::Null) => return Cow::Borrowed("#null"),
+        (_, KdlValue::String(s)) => quote_v2(s),
+        // Raw strings can't escape anything, and the single-line kind
+        // can't have newlines.
+        (_, KdlValue::RawString(s))
+            if s.contains(is_newline) || s.contains(parser::is_disallowed_v2) =>
+        {
+            quote_v2(s)
+        }
+        (_, KdlValue::RawString(s)) => raw_v2(s),
+        (_, value) => value.to_string(),
+    };
+    Cow::Owned(repr)
+}
+
+/// How an identifier with the given value is written in `version` when it
+/// doesn't have a [repr](KdlIdentifier::repr) of its own: bare if it can
+/// be, and quoted otherwise.
+pub(crate) fn identifier_repr(value: &str, version: KdlVersion) -> Cow<'_, str> {
+    let bare = match version {
+        KdlVersion::V1 => KdlIdentifier::is_bare_v1(value),
+        KdlVersion::V2 => {
+            let kdl_parser = KdlParser::with_options(value, &Converter::options(version));
+            let ident = kdl_parser.parse(parser::identifier(&kdl_parser));
+            ident.map_or(false, |ident| ident.value == value)
+        }
+    };
+    match version {
+        _ if bare => Cow::Borrowed(value),
+        KdlVersion::V1 => Cow::Owned(KdlValue::String(value.into()).to_string()),
+        KdlVersion::V2 => Cow::Owned(quote_v2(value)),
+    }
+}
+
+/// Whether `input` uses syntax that only means something in KDL 2.0. This
+/// doesn't try to skip comments or string contents: it only decides which
+/// version to try first.
+fn looks_like_v2(input: &str) -> bool {
+    const KEYWORDS: &[&str] = &["#true", "#false", "#null", "#inf", "#-inf", "#nan"];
+    KEYWORDS.iter().any(|keyword| input.contains(keyword))
+        || input.contains("\"\"\"")
+        || input
+            .match_indices("#\"")
+            .any(|(i, _)| !input[..i].trim_end_matches('#').ends_with('r'))
+}
+
+fn is_newline(c: char) -> bool {
+    matches!(
+        c,
+        '\r' | '\n' | '\u{0085}' | '\u{000B}' | '\u{000C}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// Writes `s` as a KDL 2.0 quoted string, escaping everything that would
+/// either end it or not be allowed in it.
+fn quote_v2(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '\\' | '"' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\u{08}' => quoted.push_str("\\b"),
+            '\u{0C}' => quoted.push_str("\\f"),
+            '\u{00}'..='\u{1F}'
+            | '\u{7F}'
+            | '\u{85}'
+            | '\u{200E}'
+            | '\u{200F}'
+            | '\u{2028}'..='\u{202E}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{FEFF}' => quoted.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes `s`, which has no newlines, as a KDL 2.0 raw string.
+fn raw_v2(s: &str) -> String {
+    let mut hashes = "#".to_string();
+    while s.contains(&format!("\"{}", hashes)) {
+        hashes.push('#');
+    }
+    format!("{}\"{}\"{}", hashes, s, hashes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_v2(input: &str) -> KdlDocument {
+        KdlDocument::parse_with_options(input, &Converter::options(KdlVersion::V2)).unwrap()
+    }
+
+    #[test]
+    fn keywords() {
+        let v1: KdlDocument = "node true false null 1 0x10 1.5 \"str\"".parse().unwrap();
+        let v2 = v1.to_v2();
+        assert_eq!(v2.to_string(), "node #true #false #null 1 0x10 1.5 \"str\"");
+        assert_eq!(parse_v2(&v2.to_string()), v2);
+        assert_eq!(v2.to_v1().to_string(), v1.to_string());
+
+        let mut v1 = KdlDocument::new();
+        let mut node = KdlNode::new("floats");
+        node.push(f64::INFINITY);
+        node.push(f64::NAN);
+        node.push(true);
+        v1.nodes_mut().push(node);
+        let v2 = v1.to_v2();
+        assert_eq!(v2.to_string(), "floats #inf #nan #true\n");
+        assert_eq!(
+            v2.to_v1().to_string(),
+            "floats (f64)\"inf\" (f64)\"NaN\" true\n"
+        );
+    }
+
+    #[test]
+    fn identifiers() {
+        let v1: KdlDocument = "a#b inf=1 (nan)2 \"quoted\"=3 .5x=4".parse().unwrap();
+        let v2 = v1.to_v2();
+        assert_eq!(
+            v2.to_string(),
+            "\"a#b\" \"inf\"=1 (\"nan\")2 \"quoted\"=3 \".5x\"=4"
+        );
+        let reparsed = parse_v2(&v2.to_string());
+        assert_eq!(reparsed.nodes()[0].name().value(), "a#b");
+        assert_eq!(reparsed.nodes()[0].get("inf"), Some(&1.into()));
+
+        let v2 = parse_v2("a<b> c,d #false key=val");
+        assert_eq!(v2.to_v1().to_string(), "\"a<b>\" \"c,d\" false key=\"val\"");
+    }
+
+    #[test]
+    fn strings() {
+        let v1: KdlDocument = "node {\n    s \"a\\/b\" \"line\nbreak\" r\"raw\" r##\"a\"#b\"##\n    m r\"one\ntwo\"\n}"
+            .parse()
+            .unwrap();
+        let v2 = v1.to_v2();
+        assert_eq!(
+            v2.to_string(),
+            "node {\n    s \"a/b\" \"line\\nbreak\" #\"raw\"# ##\"a\"#b\"##\n    m #\"\"\"\n        one\n        two\n        \"\"\"#\n}"
+        );
+        let reparsed = parse_v2(&v2.to_string());
+        let values = |doc: &KdlDocument| -> Vec<KdlValue> {
+            doc.nodes()[0]
+                .children()
+                .unwrap()
+                .nodes()
+                .iter()
+                .flat_map(|n| n.entries().iter().map(|e| e.value().clone()))
+                .collect()
+        };
+        assert_eq!(values(&reparsed), values(&v1));
+
+        let v2 = parse_v2("s \"\"\"\n  multi\n  line\n  \"\"\" \"a\\sb\" #\"raw\"#");
+        assert_eq!(
+            v2.to_v1().to_string(),
+            "s \"multi\\nline\" \"a b\" r\"raw\""
+        );
+
+        // Raw strings that can't be written in multi-line form.
+        let mut v1 = KdlDocument::new();
+        let mut node = KdlNode::new("s");
+        node.push(KdlValue::RawString("a\n  \nb".into()));
+        v1.nodes_mut().push(node);
+        let v2 = v1.to_v2();
+        assert_eq!(v2.to_string(), "s \"a\\n  \\nb\"\n");
+        assert_eq!(
+            v2.nodes()[0].entries()[0].value(),
+            &KdlValue::String("a\n  \nb".into())
+        );
+    }
+
+    #[test]
+    fn parse_auto() {
+        let version = |input: &str| KdlDocument::parse_auto(input).unwrap().version();
+        assert_eq!(version("node 1 \"a\" r#\"raw\"#"), KdlVersion::V1);
+        assert_eq!(version("node null"), KdlVersion::V1);
+        assert_eq!(version("node bare"), KdlVersion::V2);
+        assert_eq!(version("node #null #\"raw\"#"), KdlVersion::V2);
+        assert_eq!(version("\u{FEFF}\n/-kdl-version 2; node 1"), KdlVersion::V2);
+        assert_eq!(version("/- kdl-version 1\nnode 1"), KdlVersion::V1);
+        assert_eq!(version("/- kdl-version 20\nnode 1"), KdlVersion::V1);
+
+        // The marker wins, even when the other version would have worked.
+        let err = KdlDocument::parse_auto("/- kdl-version 1\nnode bare").unwrap_err();
+        assert_eq!(err.span.offset(), 22);
+        // Errors come from the version that looked more likely.
+        let input = "node #true true";
+        let err = KdlDocument::parse_auto(input).unwrap_err();
+        let v2_err = KdlDocument::parse_with_options(input, &Converter::options(KdlVersion::V2))
+            .unwrap_err();
+        assert_eq!(err.span, v2_err.span);
+
+        let v2 = KdlDocument::parse_auto("node #true").unwrap();
+        assert_eq!(v2.to_v1().version(), KdlVersion::V1);
+        assert_eq!(v2.to_v1().to_v2().version(), KdlVersion::V2);
+        assert_eq!(KdlDocument::new().version(), KdlVersion::V1);
+    }
+
+    #[test]
+    fn comments() {
+        let input = "// true\n/-node true /* null */\nnode /-true r\"x\" /-{\n    child null\n} // r\"x\"\n";
+        let v1: KdlDocument = input.parse().unwrap();
+        let v2 = v1.to_v2();
+        assert_eq!(
+            v2.to_string(),
+            "// true\n/-node #true /* null */\nnode /-#true #\"x\"# /-{\n    child #null\n} // r\"x\"\n"
+        );
+        let reparsed = parse_v2(&v2.to_string());
+        assert_eq!(reparsed.to_v1().to_string(), input);
+    }
+
+    #[test]
+    fn v2_only_newlines() {
+        let v2 = parse_v2("a\u{0B}b 1 /-#true\u{0B}c { d\u{0B} }\u{0B}/-e\u{0B}");
+        let v1 = v2.to_v1();
+        assert_eq!(v1.to_string(), "a\nb 1 /-true\nc { d\n }\n/-e\n");
+        let reparsed: KdlDocument = v1.to_string().parse().unwrap();
+        assert_eq!(reparsed.nodes().len(), 3);
+    }
+
+    #[test]
+    fn disallowed_characters() {
+        let v1: KdlDocument = "n \"a\u{1}b\" r\"c\u{7F}\" \"k\u{200E}\"=1\n"
+            .parse()
+            .unwrap();
+        let v2 = v1.to_v2();
+        assert_eq!(
+            v2.to_string(),
+            "n \"a\\u{1}b\" \"c\\u{7f}\" \"k\\u{200e}\"=1\n"
+        );
+        let reparsed = parse_v2(&v2.to_string());
+        assert_eq!(reparsed.nodes()[0].get(0), Some(&"a\u{1}b".into()));
+        assert_eq!(reparsed.nodes()[0].get(1), Some(&"c\u{7F}".into()));
+        assert_eq!(reparsed.nodes()[0].get("k\u{200E}"), Some(&1.into()));
+    }
+}
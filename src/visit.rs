@@ -0,0 +1,410 @@
+use crate::{KdlDocument, KdlEntry, KdlNode, KdlValue};
+
+/// A read-only pass over a [`KdlDocument`], driven by [`walk`].
+///
+/// Each method is called when the walk reaches that kind of element, and by
+/// default just carries on into the element's contents by calling the
+/// matching `walk_*` function. Override the methods you care about; if an
+/// override still wants to see what's inside, it should call the `walk_*`
+/// function itself, and if it doesn't, that part of the tree is skipped.
+///
+/// The `'a` lifetime lets visitors hold on to references into the document,
+/// like a list of matching nodes.
+///
+/// # Examples
+///
+/// ```rust
+/// use kdl::{walk, walk_node, KdlDocument, KdlNode, KdlValue, KdlVisitor};
+///
+/// /// Collects every string value, except inside `secret` nodes.
+/// #[derive(Default)]
+/// struct Strings<'a>(Vec<&'a str>);
+///
+/// impl<'a> KdlVisitor<'a> for Strings<'a> {
+///     fn visit_node(&mut self, node: &'a KdlNode) {
+///         if node.name().value() != "secret" {
+///             walk_node(self, node);
+///         }
+///     }
+///
+///     fn visit_value(&mut self, value: &'a KdlValue) {
+///         if let Some(s) = value.as_string() {
+///             self.0.push(s);
+///         }
+///     }
+/// }
+///
+/// let doc: KdlDocument = r#"
+///     user "alice" role="admin" { secret "hunter2"; }
+///     user "bob"
+/// "#
+/// .parse()
+/// .unwrap();
+/// let mut strings = Strings::default();
+/// walk(&mut strings, &doc);
+/// assert_eq!(strings.0, vec!["alice", "admin", "bob"]);
+/// ```
+pub trait KdlVisitor<'a> {
+    /// Called for the document passed to [`walk`], and for each node's
+    /// children block.
+    fn visit_document(&mut self, doc: &'a KdlDocument) {
+        walk_document(self, doc);
+    }
+
+    /// Called for each node, before its entries and children.
+    fn visit_node(&mut self, node: &'a KdlNode) {
+        walk_node(self, node);
+    }
+
+    /// Called for each of a node's entries, in order.
+    fn visit_entry(&mut self, entry: &'a KdlEntry) {
+        walk_entry(self, entry);
+    }
+
+    /// Called for each entry's value.
+    fn visit_value(&mut self, value: &'a KdlValue) {
+        let _ = value;
+    }
+}
+
+impl<'a, V: KdlVisitor<'a> + ?Sized> KdlVisitor<'a> for &mut V {
+    fn visit_document(&mut self, doc: &'a KdlDocument) {
+        (**self).visit_document(doc);
+    }
+
+    fn visit_node(&mut self, node: &'a KdlNode) {
+        (**self).visit_node(node);
+    }
+
+    fn visit_entry(&mut self, entry: &'a KdlEntry) {
+        (**self).visit_entry(entry);
+    }
+
+    fn visit_value(&mut self, value: &'a KdlValue) {
+        (**self).visit_value(value);
+    }
+}
+
+/// Runs `visitor` over `doc`, starting with [`KdlVisitor::visit_document`].
+pub fn walk<'a, V: KdlVisitor<'a> + ?Sized>(visitor: &mut V, doc: &'a KdlDocument) {
+    visitor.visit_document(doc);
+}
+
+/// Visits each of `doc`'s nodes. This is what
+/// [`KdlVisitor::visit_document`] does by default.
+pub fn walk_document<'a, V: KdlVisitor<'a> + ?Sized>(visitor: &mut V, doc: &'a KdlDocument) {
+    for node in doc.nodes() {
+        visitor.visit_node(node);
+    }
+}
+
+/// Visits each of `node`'s entries, then its children, if it has any. This is
+/// what [`KdlVisitor::visit_node`] does by default.
+pub fn walk_node<'a, V: KdlVisitor<'a> + ?Sized>(visitor: &mut V, node: &'a KdlNode) {
+    for entry in node.entries() {
+        visitor.visit_entry(entry);
+    }
+    if let Some(children) = node.children() {
+        visitor.visit_document(children);
+    }
+}
+
+/// Visits `entry`'s value. This is what [`KdlVisitor::visit_entry`] does by
+/// default.
+pub fn walk_entry<'a, V: KdlVisitor<'a> + ?Sized>(visitor: &mut V, entry: &'a KdlEntry) {
+    visitor.visit_value(entry.value());
+}
+
+/// A pass that can modify a [`KdlDocument`] in place, driven by
+/// [`walk_mut`]. This is the mutable counterpart to [`KdlVisitor`], and works
+/// the same way: override the methods you care about, and call the matching
+/// `walk_*_mut` function from an override to carry on into its contents.
+///
+/// On top of changing elements, a visitor can delete them by returning
+/// `false` from [`KdlVisitorMut::retain_node`] or
+/// [`KdlVisitorMut::retain_entry`]. Those are asked about each node or entry
+/// right before it would be visited, and deleted nodes' children are never
+/// visited.
+///
+/// Values changed through [`KdlVisitorMut::visit_value_mut`] lose their
+/// original representation, so the new value is what gets written out.
+///
+/// # Examples
+///
+/// ```rust
+/// use kdl::{walk_mut, walk_node_mut, KdlDocument, KdlNode, KdlValue, KdlVisitorMut};
+///
+/// struct Cleanup;
+///
+/// impl KdlVisitorMut for Cleanup {
+///     fn retain_node(&mut self, node: &KdlNode) -> bool {
+///         node.name().value() != "legacy"
+///     }
+///
+///     fn visit_node_mut(&mut self, node: &mut KdlNode) {
+///         if node.name().value() == "colour" {
+///             node.set_name("color");
+///         }
+///         walk_node_mut(self, node);
+///     }
+///
+///     fn visit_value_mut(&mut self, value: &mut KdlValue) {
+///         if let KdlValue::String(s) = value {
+///             *s = s.to_lowercase();
+///         }
+///     }
+/// }
+///
+/// let mut doc: KdlDocument = "theme { colour \"RED\"; legacy { x; }; }".parse().unwrap();
+/// walk_mut(&mut Cleanup, &mut doc);
+/// assert_eq!(doc.to_string(), "theme { color \"red\"; }");
+/// ```
+pub trait KdlVisitorMut {
+    /// Called for the document passed to [`walk_mut`], and for each node's
+    /// children block.
+    fn visit_document_mut(&mut self, doc: &mut KdlDocument) {
+        walk_document_mut(self, doc);
+    }
+
+    /// Whether to keep `node`. Nodes this returns `false` for are removed
+    /// from their document without being visited.
+    fn retain_node(&mut self, node: &KdlNode) -> bool {
+        let _ = node;
+        true
+    }
+
+    /// Called for each node that's kept, before its entries and children.
+    fn visit_node_mut(&mut self, node: &mut KdlNode) {
+        walk_node_mut(self, node);
+    }
+
+    /// Whether to keep `entry`. Entries this returns `false` for are removed
+    /// from their node without being visited.
+    fn retain_entry(&mut self, entry: &KdlEntry) -> bool {
+        let _ = entry;
+        true
+    }
+
+    /// Called for each of a node's entries that's kept, in order.
+    fn visit_entry_mut(&mut self, entry: &mut KdlEntry) {
+        walk_entry_mut(self, entry);
+    }
+
+    /// Called for each entry's value.
+    fn visit_value_mut(&mut self, value: &mut KdlValue) {
+        let _ = value;
+    }
+}
+
+impl<V: KdlVisitorMut + ?Sized> KdlVisitorMut for &mut V {
+    fn visit_document_mut(&mut self, doc: &mut KdlDocument) {
+        (**self).visit_document_mut(doc);
+    }
+
+    fn retain_node(&mut self, node: &KdlNode) -> bool {
+        (**self).retain_node(node)
+    }
+
+    fn visit_node_mut(&mut self, node: &mut KdlNode) {
+        (**self).visit_node_mut(node);
+    }
+
+    fn retain_entry(&mut self, entry: &KdlEntry) -> bool {
+        (**self).retain_entry(entry)
+    }
+
+    fn visit_entry_mut(&mut self, entry: &mut KdlEntry) {
+        (**self).visit_entry_mut(entry);
+    }
+
+    fn visit_value_mut(&mut self, value: &mut KdlValue) {
+        (**self).visit_value_mut(value);
+    }
+}
+
+/// Runs `visitor` over `doc`, starting with
+/// [`KdlVisitorMut::visit_document_mut`].
+pub fn walk_mut<V: KdlVisitorMut + ?Sized>(visitor: &mut V, doc: &mut KdlDocument) {
+    visitor.visit_document_mut(doc);
+}
+
+/// Visits each of `doc`'s nodes, removing the ones the visitor doesn't
+/// retain. This is what [`KdlVisitorMut::visit_document_mut`] does by
+/// default.
+pub fn walk_document_mut<V: KdlVisitorMut + ?Sized>(visitor: &mut V, doc: &mut KdlDocument) {
+    let nodes = std::mem::take(doc.nodes_mut());
+    let mut removed_leading = None;
+    for mut node in nodes {
+        if visitor.retain_node(&node) {
+            if let Some(leading) = removed_leading.take() {
+                if is_blank(node.leading()) {
+                    node.set_leading(leading);
+                }
+            }
+            visitor.visit_node_mut(&mut node);
+            doc.nodes_mut().push(node);
+        } else if removed_leading.is_none() {
+            removed_leading = node.leading().map(whitespace_prefix);
+        }
+    }
+}
+
+/// Visits each of `node`'s entries, removing the ones the visitor doesn't
+/// retain, then its children, if it has any. This is what
+/// [`KdlVisitorMut::visit_node_mut`] does by default.
+pub fn walk_node_mut<V: KdlVisitorMut + ?Sized>(visitor: &mut V, node: &mut KdlNode) {
+    let entries = std::mem::take(node.entries_mut());
+    let mut removed_leading = None;
+    for mut entry in entries {
+        if visitor.retain_entry(&entry) {
+            if let Some(leading) = removed_leading.take() {
+                if is_blank(entry.leading()) {
+                    entry.set_leading(leading);
+                }
+            }
+            visitor.visit_entry_mut(&mut entry);
+            node.entries_mut().push(entry);
+        } else if removed_leading.is_none() {
+            removed_leading = entry.leading().map(whitespace_prefix);
+        }
+    }
+    if let Some(children) = node.children_mut() {
+        visitor.visit_document_mut(children);
+    }
+}
+
+/// Visits `entry`'s value, dropping its original representation if the
+/// visitor changed it. This is what [`KdlVisitorMut::visit_entry_mut`] does
+/// by default.
+pub fn walk_entry_mut<V: KdlVisitorMut + ?Sized>(visitor: &mut V, entry: &mut KdlEntry) {
+    let before = entry.value().clone();
+    visitor.visit_value_mut(entry.value_mut());
+    if *entry.value() != before {
+        entry.clear_value_repr();
+    }
+}
+
+/// The whitespace at the start of a removed element's leading text, which is
+/// what separated it from whatever came before. Comments after that belonged
+/// to the removed element, so they go with it.
+fn whitespace_prefix(leading: &str) -> String {
+    let len = leading.len() - leading.trim_start().len();
+    leading[..len].to_string()
+}
+
+/// Whether the element after one or more removed ones should take over the
+/// removed elements' separator, so that removing the first node of a block,
+/// say, doesn't pull the next one up onto the line with the `{`. Leading text
+/// with comments in it is left alone.
+fn is_blank(leading: Option<&str>) -> bool {
+    matches!(leading, Some(leading) if leading.trim().is_empty())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl<'a> KdlVisitor<'a> for Log {
+        fn visit_document(&mut self, doc: &'a KdlDocument) {
+            self.0.push("doc".into());
+            walk_document(self, doc);
+        }
+
+        fn visit_node(&mut self, node: &'a KdlNode) {
+            self.0.push(format!("node {}", node.name().value()));
+            walk_node(self, node);
+        }
+
+        fn visit_entry(&mut self, entry: &'a KdlEntry) {
+            if let Some(name) = entry.name() {
+                self.0.push(format!("prop {}", name.value()));
+            }
+            walk_entry(self, entry);
+        }
+
+        fn visit_value(&mut self, value: &'a KdlValue) {
+            self.0.push(format!("value {}", value));
+        }
+    }
+
+    #[test]
+    fn order() -> miette::Result<()> {
+        let doc: KdlDocument = "a 1 x=2 { b; }\nc".parse()?;
+        let mut log = Log::default();
+        walk(&mut log, &doc);
+        assert_eq!(
+            log.0,
+            vec!["doc", "node a", "value 1", "prop x", "value 2", "doc", "node b", "node c"]
+        );
+        Ok(())
+    }
+
+    /// Counts nodes, another pass run over the same document.
+    #[derive(Default)]
+    struct Count(usize);
+
+    impl<'a> KdlVisitor<'a> for Count {
+        fn visit_node(&mut self, node: &'a KdlNode) {
+            self.0 += 1;
+            walk_node(self, node);
+        }
+    }
+
+    #[test]
+    fn defaults_and_references() -> miette::Result<()> {
+        let doc: KdlDocument = "a { b { c; }; }\nd".parse()?;
+        let mut count = Count::default();
+        let mut log = Log::default();
+        walk(&mut &mut count, &doc);
+        walk(&mut log, &doc);
+        let passes: &mut [&mut dyn KdlVisitor<'_>] = &mut [&mut count, &mut log];
+        for pass in passes.iter_mut() {
+            walk(pass, &doc);
+        }
+        assert_eq!(count.0, 8);
+        assert_eq!(log.0.iter().filter(|l| l.starts_with("node")).count(), 8);
+        Ok(())
+    }
+
+    struct Rewrite;
+
+    impl KdlVisitorMut for Rewrite {
+        fn retain_node(&mut self, node: &KdlNode) -> bool {
+            node.name().value() != "drop"
+        }
+
+        fn visit_node_mut(&mut self, node: &mut KdlNode) {
+            let upper = node.name().value().to_uppercase();
+            node.set_name(upper);
+            walk_node_mut(self, node);
+        }
+
+        fn retain_entry(&mut self, entry: &KdlEntry) -> bool {
+            !entry.value().is_null()
+        }
+
+        fn visit_value_mut(&mut self, value: &mut KdlValue) {
+            if let Some(n) = value.as_i64() {
+                if n > 100 {
+                    *value = KdlValue::Base10(100);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rewrite() -> miette::Result<()> {
+        let mut doc: KdlDocument =
+            "a 0x10 null 500 {\n    drop { b; }\n    c k=null 1_000\n}\ndrop\n".parse()?;
+        walk_mut(&mut Rewrite, &mut doc);
+        assert_eq!(doc.to_string(), "A 0x10 100 {\n    C 100\n}\n");
+
+        let mut log = Log::default();
+        walk(&mut log, &doc);
+        assert_eq!(log.0.iter().filter(|l| l.starts_with("node")).count(), 2);
+        Ok(())
+    }
+}
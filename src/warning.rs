@@ -0,0 +1,301 @@
+use std::sync::Arc;
+
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::util::ErrorSpan;
+use crate::{KdlDocument, KdlEncoding, KdlEntry, KdlNode, KdlSource};
+
+/// Nesting depth past which [`KdlDocument::parse_with_warnings`] reports a
+/// [`KdlWarningKind::DeepNesting`] warning.
+pub const DEEP_NESTING_THRESHOLD: usize = 64;
+
+/// A non-fatal diagnostic produced while parsing a KDL document.
+///
+/// Warnings never cause parsing to fail. They point out things that are
+/// technically valid KDL but are likely to cause trouble, such as a leading
+/// byte order mark or text that looks like it was decoded with the wrong
+/// encoding. They're returned by [`KdlDocument::parse_with_warnings`].
+///
+/// Like [`KdlError`](crate::KdlError), this implements
+/// [`miette::Diagnostic`], with a severity of
+/// [`miette::Severity::Warning`].
+#[derive(Debug, Diagnostic, Clone, Eq, PartialEq, Error)]
+#[error("{kind}")]
+#[diagnostic(severity(Warning), forward(kind))]
+pub struct KdlWarning {
+    /// Source for the KDL document this warning applies to.
+    #[source_code]
+    pub input: Arc<KdlSource>,
+
+    /// Offset in chars of the warning.
+    #[label("{}", label.unwrap_or("here"))]
+    pub span: SourceSpan,
+
+    /// Label text for this span. Defaults to `"here"`.
+    pub label: Option<&'static str>,
+
+    /// Suggestion for addressing the warning.
+    #[help]
+    pub help: Option<&'static str>,
+
+    /// Specific kind of warning.
+    pub kind: KdlWarningKind,
+}
+
+/// The specific category of a [`KdlWarning`].
+#[derive(Debug, Diagnostic, Clone, Eq, PartialEq, Error)]
+pub enum KdlWarningKind {
+    /// The document starts with a UTF-8 byte order mark.
+    #[error("Document starts with a byte order mark.")]
+    #[diagnostic(code(kdl::warning::bom))]
+    ByteOrderMark,
+
+    /// The document contains C1 control characters, which usually means it
+    /// was Windows-1252 (or Latin-1) text decoded as if it were UTF-8, or
+    /// the other way around.
+    #[error("Document contains characters that look like mis-decoded Windows-1252 text.")]
+    #[diagnostic(code(kdl::warning::suspicious_encoding))]
+    SuspiciousEncoding,
+
+    /// Lines in the document are indented with a mix of tabs and spaces.
+    #[error("Mixed tab and space indentation.")]
+    #[diagnostic(code(kdl::warning::mixed_indentation))]
+    MixedIndentation,
+
+    /// Nodes are nested deeper than [`DEEP_NESTING_THRESHOLD`].
+    #[error("Nodes are nested more than {} levels deep.", DEEP_NESTING_THRESHOLD)]
+    #[diagnostic(code(kdl::warning::deep_nesting))]
+    DeepNesting,
+
+    /// An entry's value doesn't match the format of its reserved type
+    /// annotation, like a `(duration)` that isn't an ISO 8601 duration or a
+    /// `(u8)` over 255. Only annotations this crate knows how to parse (given
+    /// the enabled features) are checked.
+    #[error("Value isn't a valid `({0})`.")]
+    #[diagnostic(code(kdl::warning::invalid_reserved_value))]
+    InvalidReservedValue(String),
+
+    /// The document wasn't UTF-8, and was decoded from another encoding by
+    /// [`KdlDocument::parse_bytes`].
+    #[error("Document was decoded from {0}.")]
+    #[diagnostic(code(kdl::warning::transcoded))]
+    Transcoded(KdlEncoding),
+}
+
+impl KdlWarning {
+    /// Attaches a name (usually a file path) to the source of this warning,
+    /// so that reports point at `name:line:column`.
+    pub fn with_source_name(mut self, name: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.input).name = Some(name.into());
+        self
+    }
+}
+
+impl KdlWarningKind {
+    /// Returns the stable diagnostic code for this warning kind, as a plain
+    /// string (e.g. `"kdl::warning::bom"`).
+    pub fn code_str(&self) -> &'static str {
+        match self {
+            KdlWarningKind::ByteOrderMark => "kdl::warning::bom",
+            KdlWarningKind::SuspiciousEncoding => "kdl::warning::suspicious_encoding",
+            KdlWarningKind::MixedIndentation => "kdl::warning::mixed_indentation",
+            KdlWarningKind::DeepNesting => "kdl::warning::deep_nesting",
+            KdlWarningKind::InvalidReservedValue(_) => "kdl::warning::invalid_reserved_value",
+            KdlWarningKind::Transcoded(_) => "kdl::warning::transcoded",
+        }
+    }
+}
+
+pub(crate) fn check(input: &str, doc: &KdlDocument) -> Vec<KdlWarning> {
+    let source = Arc::new(KdlSource::from(input));
+    let mut warnings = Vec::new();
+    let mut warn = |span: SourceSpan,
+                    label: Option<&'static str>,
+                    help: Option<&'static str>,
+                    kind: KdlWarningKind| {
+        warnings.push(KdlWarning {
+            input: source.clone(),
+            span,
+            label,
+            help,
+            kind,
+        })
+    };
+
+    if input.starts_with('\u{FEFF}') {
+        warn(
+            (0, '\u{FEFF}'.len_utf8()).into(),
+            Some("byte order mark"),
+            Some("Byte order marks are unnecessary in UTF-8 and some tools will choke on them."),
+            KdlWarningKind::ByteOrderMark,
+        );
+    }
+
+    if let Some((offset, c)) = input
+        .char_indices()
+        .find(|(_, c)| ('\u{80}'..='\u{9F}').contains(c) || *c == '\u{FFFD}')
+    {
+        warn(
+            (offset, c.len_utf8()).into(),
+            Some("suspicious character"),
+            Some("Make sure this file is saved as UTF-8."),
+            KdlWarningKind::SuspiciousEncoding,
+        );
+    }
+
+    let mut line_start = 0;
+    let mut indent_style = None;
+    for line in input.split_inclusive('\n') {
+        let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        let style = (indent.contains(' '), indent.contains('\t'));
+        let mixed = match style {
+            (true, true) => true,
+            (false, false) => false,
+            _ => *indent_style.get_or_insert(style) != style,
+        };
+        if mixed {
+            warn(
+                (line_start, indent.len()).into(),
+                Some("inconsistent indentation"),
+                Some("Pick either tabs or spaces for indentation and use it throughout."),
+                KdlWarningKind::MixedIndentation,
+            );
+            break;
+        }
+        line_start += line.len();
+    }
+
+    if let Some(node) = too_deep(doc, 0) {
+        warn(
+            node.error_span(),
+            Some("deeply nested node"),
+            Some("Consider flattening this structure; some KDL consumers limit recursion depth."),
+            KdlWarningKind::DeepNesting,
+        );
+    }
+
+    let mut invalid = Vec::new();
+    invalid_reserved_values(doc, &mut invalid);
+    for entry in invalid {
+        warn(
+            value_span(entry),
+            Some("invalid value"),
+            Some("Values with a reserved type annotation must use that type's standard format."),
+            KdlWarningKind::InvalidReservedValue(entry.ty().unwrap().value().into()),
+        );
+    }
+
+    warnings
+}
+
+fn invalid_reserved_values<'a>(doc: &'a KdlDocument, invalid: &mut Vec<&'a KdlEntry>) {
+    for node in doc.nodes() {
+        invalid.extend(
+            node.entries()
+                .iter()
+                .filter(|entry| invalid_for_annotation(entry)),
+        );
+        if let Some(children) = node.children() {
+            invalid_reserved_values(children, invalid);
+        }
+    }
+}
+
+/// Whether `entry` has a type annotation we understand that its value doesn't
+/// fit, going by [`KdlEntry::typed_value`].
+fn invalid_for_annotation(entry: &KdlEntry) -> bool {
+    entry.ty().is_some() && entry.typed_value().is_none()
+}
+
+fn too_deep(doc: &KdlDocument, depth: usize) -> Option<&KdlNode> {
+    for node in doc.nodes() {
+        if depth >= DEEP_NESTING_THRESHOLD {
+            return Some(node);
+        }
+        if let Some(node) = node.children().and_then(|c| too_deep(c, depth + 1)) {
+            return Some(node);
+        }
+    }
+    None
+}
+
+/// The span of just the value in `entry`, without its name or annotation.
+fn value_span(entry: &KdlEntry) -> SourceSpan {
+    let len = match entry.value_repr() {
+        Some(repr) => repr.len(),
+        None => entry.value().to_string().len(),
+    };
+    let span = entry.error_span();
+    let end = span.offset() + span.len();
+    SourceSpan::from(end.saturating_sub(len)..end)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<KdlWarningKind> {
+        let (_, warnings) = KdlDocument::parse_with_warnings(input).unwrap();
+        warnings.into_iter().map(|w| w.kind).collect()
+    }
+
+    #[test]
+    fn clean() {
+        assert_eq!(kinds("foo 1\nbar {\n    baz\n}\n"), vec![]);
+    }
+
+    #[test]
+    fn bom() {
+        assert_eq!(kinds("\u{FEFF}foo 1"), vec![KdlWarningKind::ByteOrderMark]);
+    }
+
+    #[test]
+    fn suspicious_encoding() {
+        assert_eq!(
+            kinds("foo \"caf\u{0082}\""),
+            vec![KdlWarningKind::SuspiciousEncoding]
+        );
+    }
+
+    #[test]
+    fn mixed_indentation() {
+        assert_eq!(
+            kinds("foo {\n    bar\n\tbaz\n}"),
+            vec![KdlWarningKind::MixedIndentation]
+        );
+        assert_eq!(
+            kinds("foo {\n \tbar\n}"),
+            vec![KdlWarningKind::MixedIndentation]
+        );
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let depth = DEEP_NESTING_THRESHOLD + 1;
+        let input = format!("{}{}", "a {\n".repeat(depth), "}\n".repeat(depth));
+        assert_eq!(kinds(&input), vec![KdlWarningKind::DeepNesting]);
+    }
+
+    #[test]
+    fn invalid_reserved_value() {
+        assert_eq!(
+            kinds("foo (base64)\"aGk=\" (base64)\"a\""),
+            vec![KdlWarningKind::InvalidReservedValue("base64".into())]
+        );
+        assert_eq!(
+            kinds("foo (duration)\"PT1S\" (made-up)\"x\" {\n    bar t=(duration)\"1S\"\n}"),
+            vec![KdlWarningKind::InvalidReservedValue("duration".into())]
+        );
+        assert_eq!(
+            kinds("foo (u8)255 (u8)256"),
+            vec![KdlWarningKind::InvalidReservedValue("u8".into())]
+        );
+        #[cfg(feature = "span")]
+        {
+            let input = "foo t=(duration)\"soon\"";
+            let (_, warnings) = KdlDocument::parse_with_warnings(input).unwrap();
+            assert_eq!(&input[warnings[0].span.offset()..], "\"soon\"");
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! JavaScript bindings, enabled by the `wasm` feature, made with
+//! [`wasm-bindgen`](https://crates.io/crates/wasm-bindgen).
+//!
+//! Every function takes KDL text in either version, going by a
+//! `/- kdl-version` marker when there is one. Documents are handed to
+//! JavaScript as arrays of plain node objects, each with its `name`, type
+//! annotation as `type` (or `null`), `arguments`, `properties`, and
+//! `children`, the same shape as `kdl convert --to json`. When a property
+//! appears more than once, the last one wins. Integers too large to be exact
+//! as a `number` are `BigInt`s, and type annotations on values aren't kept.
+//!
+//! Invalid input is thrown as an `Error` whose message is the parser's, with
+//! the same fields that [`validate`] returns.
+//!
+//! To build the bindings, use
+//! `wasm-pack build --release -- --features wasm`, or
+//! `cargo rustc --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib`
+//! and then `wasm-bindgen`.
+
+use js_sys::{Array, Error, Object, Reflect};
+use miette::Diagnostic;
+use wasm_bindgen::prelude::*;
+
+use crate::{EmitOptions, KdlDocument, KdlError, KdlNode, KdlValue};
+
+/// Parses `input` into an array of node objects.
+///
+/// Throws an `Error` if `input` isn't valid KDL.
+#[wasm_bindgen]
+pub fn parse(input: &str) -> Result<JsValue, JsValue> {
+    let doc = parse_doc(input)?;
+    Ok(nodes(&doc))
+}
+
+/// Formats `input` with [`KdlDocument::fmt`], and writes it back out in the
+/// version it was written in.
+///
+/// Throws an `Error` if `input` isn't valid KDL.
+#[wasm_bindgen]
+pub fn format(input: &str) -> Result<String, JsValue> {
+    let mut doc = parse_doc(input)?;
+    doc.fmt();
+    let options = EmitOptions {
+        version: Some(doc.version()),
+        ..EmitOptions::default()
+    };
+    doc.to_string_with_options(&options)
+        .map_err(|err| Error::new(&err.to_string()).into())
+}
+
+/// Checks whether `input` is valid KDL. Returns `null` if it is, and an
+/// object describing the first problem if it isn't, with its `message`,
+/// `label`, `help` (or `null`), and where it is: `offset` and `length` in
+/// UTF-16 code units, as JavaScript strings count them, and 1-based `line`
+/// and `column`.
+#[wasm_bindgen]
+pub fn validate(input: &str) -> JsValue {
+    match KdlDocument::parse_auto(input) {
+        Ok(_) => JsValue::NULL,
+        Err(err) => diagnostic(Object::new(), input, &err).into(),
+    }
+}
+
+/// Finds the nodes in `input` that match a KQL `query`, anywhere in the
+/// document, and returns them as an array of node objects.
+///
+/// Throws an `Error` if `input` isn't valid KDL, or `query` isn't a valid
+/// query, in which case its location is within `query`.
+#[wasm_bindgen]
+pub fn query(input: &str, query: &str) -> Result<JsValue, JsValue> {
+    let doc = parse_doc(input)?;
+    let found = doc.query_all(query).map_err(|err| {
+        JsValue::from(diagnostic(Error::new(&err.to_string()).into(), query, &err))
+    })?;
+    Ok(found.map(node).collect::<Array>().into())
+}
+
+fn parse_doc(input: &str) -> Result<KdlDocument, JsValue> {
+    KdlDocument::parse_auto(input).map_err(|err| {
+        let error = Error::new(&err.to_string());
+        diagnostic(error.into(), input, &err).into()
+    })
+}
+
+/// Sets the fields describing `err` on `object`.
+fn diagnostic(object: Object, input: &str, err: &KdlError) -> Object {
+    let location = Location::new(input, err.span.offset(), err.span.len());
+    let help = Diagnostic::help(err).map(|help| help.to_string());
+    set(&object, "message", err.to_string().into());
+    set(&object, "label", err.label.unwrap_or("here").into());
+    set(&object, "help", help.map_or(JsValue::NULL, JsValue::from));
+    set(&object, "offset", location.offset.into());
+    set(&object, "length", location.length.into());
+    set(&object, "line", location.line.into());
+    set(&object, "column", location.column.into());
+    object
+}
+
+fn set(object: &Object, key: &str, value: JsValue) {
+    Reflect::set(object, &key.into(), &value).unwrap_throw();
+}
+
+fn nodes(doc: &KdlDocument) -> JsValue {
+    doc.nodes().iter().map(node).collect::<Array>().into()
+}
+
+fn node(node: &KdlNode) -> JsValue {
+    let object = Object::new();
+    set(&object, "name", node.name().value().into());
+    set(
+        &object,
+        "type",
+        node.ty().map_or(JsValue::NULL, |ty| ty.value().into()),
+    );
+    set(
+        &object,
+        "arguments",
+        node.args().map(value).collect::<Array>().into(),
+    );
+    let props = Object::new();
+    for (key, val) in node.props() {
+        set(&props, key, value(val));
+    }
+    set(&object, "properties", props.into());
+    set(
+        &object,
+        "children",
+        node.children().map_or_else(|| Array::new().into(), nodes),
+    );
+    object.into()
+}
+
+/// Integers are kept exact with a `BigInt` when a `number` can't hold them.
+const MAX_SAFE_INTEGER: i128 = (1 << 53) - 1;
+
+fn value(value: &KdlValue) -> JsValue {
+    match value {
+        KdlValue::String(s) | KdlValue::RawString(s) => s.into(),
+        KdlValue::Base2(i) | KdlValue::Base8(i) | KdlValue::Base10(i) | KdlValue::Base16(i) => {
+            if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(i) {
+                (*i as f64).into()
+            } else {
+                (*i).into()
+            }
+        }
+        #[cfg(feature = "bigint")]
+        KdlValue::BigInt(i) => JsValue::bigint_from_str(&i.to_string()),
+        KdlValue::Base10Float(f) => (*f).into(),
+        KdlValue::Bool(b) => (*b).into(),
+        KdlValue::Null => JsValue::NULL,
+    }
+}
+
+/// Where a span of `input` is, the way JavaScript sees it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Location {
+    offset: usize,
+    length: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Location {
+    /// Locates the span starting at byte `offset` and `length` bytes long.
+    fn new(input: &str, offset: usize, length: usize) -> Self {
+        let offset = offset.min(input.len());
+        let end = (offset + length).min(input.len());
+        let before = input.get(..offset).unwrap_or(input);
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let utf16_len = |s: &str| s.encode_utf16().count();
+        Location {
+            offset: utf16_len(before),
+            length: input.get(offset..end).map_or(0, utf16_len),
+            line: before.matches('\n').count() + 1,
+            column: utf16_len(&before[line_start..]) + 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn location() {
+        let input = "a 1\nb \"ü😀\" 1.";
+        let offset = input.find("1.").unwrap();
+        assert_eq!(
+            Location::new(input, offset, 2),
+            Location {
+                offset: 12,
+                length: 2,
+                line: 2,
+                column: 9,
+            }
+        );
+        assert_eq!(
+            Location::new(input, 0, 1),
+            Location {
+                offset: 0,
+                length: 1,
+                line: 1,
+                column: 1,
+            }
+        );
+        let end = Location::new(input, input.len(), 0);
+        assert_eq!((end.offset, end.line), (input.encode_utf16().count(), 2));
+    }
+}
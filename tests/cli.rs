@@ -0,0 +1,97 @@
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn kdl(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_kdl"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    assert!(output.status.success(), "{:?}", output);
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn fmt() {
+    let output = kdl(&["fmt"], "a   1 {b;c;}");
+    assert_eq!(stdout(&output), "a 1 {\n    b\n    c\n}\n");
+    let output = kdl(&["fmt"], "/- kdl-version 2\na   #true   bare");
+    assert_eq!(stdout(&output), "/- kdl-version 2\na #true \"bare\"\n");
+
+    assert!(kdl(&["fmt", "--check"], "a 1\n").status.success());
+    assert_eq!(kdl(&["fmt", "--check"], "a   1\n").status.code(), Some(1));
+}
+
+#[test]
+fn query() {
+    let output = kdl(&["query", "dependencies > []", "examples/Cargo.kdl"], "");
+    assert_eq!(stdout(&output), "nom \"6.0.1\"\nthiserror \"1.0.22\"\n");
+    assert_eq!(kdl(&["query", "a >>>"], "a").status.code(), Some(2));
+}
+
+#[test]
+fn validate() {
+    assert!(kdl(&["validate"], "a 1").status.success());
+    let output = kdl(&["validate"], "a 1.");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid number"));
+
+    let schema = "examples/kdl-schema.kdl";
+    let output = kdl(&["validate", "--schema", schema, schema], "");
+    assert!(output.status.success(), "{:?}", output);
+
+    let output = kdl(
+        &["validate", "--schema", schema],
+        "document bogus=1 {\n    node \"a\" {\n        min \"one\"\n    }\n}\nextra",
+    );
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unexpected property `bogus`"), "{}", stderr);
+    assert!(
+        stderr.contains("expected a value of type number"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("unexpected node `extra`"), "{}", stderr);
+}
+
+#[test]
+fn convert() {
+    let output = kdl(
+        &["convert", "--to", "json"],
+        "a 1 x=\"y\" {\n    b true 2.5\n}",
+    );
+    assert_eq!(
+        stdout(&output),
+        concat!(
+            r#"[{"name":"a","type":null,"arguments":[1],"properties":{"x":"y"},"#,
+            r#""children":[{"name":"b","type":null,"arguments":[true,2.5],"#,
+            r#""properties":{},"children":[]}]}]"#,
+            "\n"
+        )
+    );
+    let output = kdl(&["convert", "--to", "v2"], "a true r\"raw\"");
+    assert_eq!(stdout(&output), "a #true #\"raw\"#");
+    let output = kdl(&["convert", "--to", "v1"], "/- kdl-version 2\na #null");
+    assert_eq!(stdout(&output), "/- kdl-version 1\na null");
+
+    assert_eq!(kdl(&["convert"], "a").status.code(), Some(2));
+    assert_eq!(
+        kdl(&["convert", "--to", "yaml"], "a").status.code(),
+        Some(2)
+    );
+}
@@ -4,15 +4,30 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use kdl::{KdlDocument, KdlError, KdlIdentifier, KdlValue};
+use kdl::{KdlDocument, KdlError, KdlIdentifier, KdlValue, KdlVersion, ParseOptions};
 use miette::IntoDiagnostic;
 
 #[test]
 fn spec_compliance() -> miette::Result<()> {
+    run_suite("test_cases", KdlVersion::V1)
+}
+
+/// Cases for the KDL 2.0 grammar, which `test_cases` (from the KDL 1.0
+/// spec) doesn't cover.
+#[test]
+fn spec_compliance_v2() -> miette::Result<()> {
+    run_suite("test_cases_v2", KdlVersion::V2)
+}
+
+fn run_suite(suite: &str, version: KdlVersion) -> miette::Result<()> {
     let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("tests")
-        .join("test_cases")
+        .join(suite)
         .join("input");
+    let options = ParseOptions {
+        version,
+        ..ParseOptions::default()
+    };
     for test_name in fs::read_dir(&input).into_diagnostic()? {
         let test_path = test_name.into_diagnostic()?.path();
         println!(
@@ -21,7 +36,7 @@ fn spec_compliance() -> miette::Result<()> {
         );
         let src = normalize_line_endings(fs::read_to_string(&test_path).into_diagnostic()?);
         println!("src: {}", src);
-        let res: Result<KdlDocument, KdlError> = src.parse();
+        let res = KdlDocument::parse_with_options(&src, &options);
         validate_res(res, &test_path)?;
     }
     Ok(())
@@ -72,7 +87,7 @@ fn stringify_to_expected(mut doc: KdlDocument) -> String {
 fn normalize_numbers(doc: &mut KdlDocument) {
     for node in doc.nodes_mut() {
         for entry in node.entries_mut() {
-            if let Some(value) = entry.value().as_i64() {
+            if let Some(value) = entry.value().as_i128() {
                 *entry.value_mut() = KdlValue::Base10(value);
             }
         }
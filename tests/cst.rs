@@ -0,0 +1,48 @@
+use std::{fs, path::PathBuf};
+
+use kdl::{KdlCstElement, KdlCstNode, KdlDocument};
+use miette::IntoDiagnostic;
+
+#[test]
+fn cst_roundtrips_spec_inputs() -> miette::Result<()> {
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("test_cases")
+        .join("input");
+    for test_path in fs::read_dir(&input).into_diagnostic()? {
+        let test_path = test_path.into_diagnostic()?.path();
+        let src = fs::read_to_string(&test_path).into_diagnostic()?;
+        if src.parse::<KdlDocument>().is_err() {
+            assert!(src.parse::<KdlCstNode>().is_err());
+            continue;
+        }
+        println!("{}", test_path.display());
+        let cst: KdlCstNode = src.parse()?;
+        assert_eq!(cst.to_string(), src);
+        assert_eq!(cst.to_document()?, src.parse::<KdlDocument>()?);
+        check_spans(&cst, &src);
+    }
+    Ok(())
+}
+
+/// Every node's span covers exactly its children, and every token's span
+/// covers exactly its text.
+fn check_spans(node: &KdlCstNode, src: &str) {
+    let mut offset = node.span.offset();
+    for child in &node.children {
+        let span = match child {
+            KdlCstElement::Node(child) => {
+                check_spans(child, src);
+                child.span
+            }
+            KdlCstElement::Token(token) => {
+                assert!(!token.text.is_empty());
+                assert_eq!(&src[token.span.offset()..][..token.span.len()], token.text);
+                token.span
+            }
+        };
+        assert_eq!(span.offset(), offset);
+        offset += span.len();
+    }
+    assert_eq!(offset, node.span.offset() + node.span.len());
+}
@@ -0,0 +1,322 @@
+#![cfg(feature = "macros")]
+
+use kdl::{DecodeKdl, EncodeKdl, KdlDecodeError, KdlDocument, KdlNode};
+
+#[derive(DecodeKdl, EncodeKdl, Debug, Clone, PartialEq)]
+struct Package {
+    #[kdl(argument)]
+    name: String,
+    #[kdl(argument)]
+    version: Option<String>,
+    #[kdl(property)]
+    edition: u16,
+    #[kdl(property, name = "publish")]
+    publishable: Option<bool>,
+    #[kdl(child)]
+    license: License,
+    #[kdl(child)]
+    repository: Option<Repository>,
+    #[kdl(children, name = "dep")]
+    dependencies: Vec<Dependency>,
+}
+
+#[derive(DecodeKdl, EncodeKdl, Debug, Clone, PartialEq)]
+struct License {
+    #[kdl(argument)]
+    spdx: String,
+}
+
+#[derive(DecodeKdl, EncodeKdl, Debug, Clone, PartialEq)]
+struct Repository {
+    #[kdl(property)]
+    url: String,
+}
+
+#[derive(DecodeKdl, EncodeKdl, Debug, Clone, PartialEq)]
+struct Dependency {
+    #[kdl(argument)]
+    name: String,
+    #[kdl(property)]
+    default_features: Option<bool>,
+    #[kdl(children)]
+    features: Vec<Feature>,
+}
+
+#[derive(DecodeKdl, EncodeKdl, Debug, Clone, PartialEq)]
+#[kdl(name = "feature")]
+struct Feature {
+    #[kdl(argument)]
+    name: String,
+}
+
+const INPUT: &str = r#"
+package "kdl" "5.0.0" edition=2021 {
+    license "Apache-2.0"
+    dep "miette" default-features=false {
+        feature "fancy"
+    }
+    dep "nom"
+}
+"#;
+
+fn package_node(input: &str) -> KdlNode {
+    let doc: KdlDocument = input.parse().unwrap();
+    doc.get("package").unwrap().clone()
+}
+
+#[test]
+fn decoding() {
+    let package = Package::decode_kdl(&package_node(INPUT)).unwrap();
+    assert_eq!(
+        package,
+        Package {
+            name: "kdl".into(),
+            version: Some("5.0.0".into()),
+            edition: 2021,
+            publishable: None,
+            license: License {
+                spdx: "Apache-2.0".into()
+            },
+            repository: None,
+            dependencies: vec![
+                Dependency {
+                    name: "miette".into(),
+                    default_features: Some(false),
+                    features: vec![Feature {
+                        name: "fancy".into()
+                    }],
+                },
+                Dependency {
+                    name: "nom".into(),
+                    default_features: None,
+                    features: vec![],
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn roundtrip() {
+    let package = Package::decode_kdl(&package_node(INPUT)).unwrap();
+    let node = package.encode_kdl();
+    assert_eq!(
+        node.to_string(),
+        r#"package "kdl" "5.0.0" edition=2021 {
+    license "Apache-2.0"
+    dep "miette" default-features=false {
+        feature "fancy"
+    }
+    dep "nom"
+}"#
+    );
+    assert_eq!(Package::decode_kdl(&node).unwrap(), package);
+
+    let mut package = package;
+    package.version = None;
+    package.publishable = Some(true);
+    package.repository = Some(Repository {
+        url: "https://github.com/kdl-org/kdl-rs".into(),
+    });
+    package.dependencies.clear();
+    let node = package.encode_kdl();
+    assert_eq!(
+        node.to_string(),
+        r#"package "kdl" null edition=2021 publish=true {
+    license "Apache-2.0"
+    repository url="https://github.com/kdl-org/kdl-rs"
+}"#
+    );
+    assert_eq!(Package::decode_kdl(&node).unwrap(), package);
+}
+
+#[test]
+fn errors() {
+    let input = "package \"kdl\" edition=2021";
+    let err = Package::decode_kdl(&package_node(input)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Node `package` is missing child `license`."
+    );
+
+    let input = "package \"kdl\" edition=2021 {\n    license \"MIT\"\n    dependency \"nom\"\n}";
+    let err = Package::decode_kdl(&package_node(input)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unexpected child `dependency` in node `package`."
+    );
+    #[cfg(feature = "span")]
+    {
+        let offset = input.find("dependency").unwrap();
+        assert_eq!(
+            err,
+            KdlDecodeError::UnexpectedChild {
+                node: "package".into(),
+                child: "dependency".into(),
+                span: (offset..offset + 16).into(),
+            }
+        );
+    }
+
+    let input = "package \"kdl\" edition=\"2021\" { license \"MIT\"; }";
+    let err = Package::decode_kdl(&package_node(input)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected property `edition` of node `package` to be u16, but found `\"2021\"`."
+    );
+    #[cfg(feature = "span")]
+    assert!(matches!(
+        err,
+        KdlDecodeError::Conversion(err) if err.span == (14..28).into()
+    ));
+}
+
+#[derive(DecodeKdl, EncodeKdl, Debug, Clone, PartialEq)]
+struct Job {
+    #[kdl(argument)]
+    name: String,
+    #[kdl(argument)]
+    priority: Option<Priority>,
+    #[kdl(property)]
+    on_failure: Policy,
+    #[kdl(child)]
+    schedule: Option<Schedule>,
+    #[kdl(children)]
+    steps: Vec<Step>,
+}
+
+#[derive(DecodeKdl, EncodeKdl, Debug, Clone, Copy, PartialEq)]
+#[kdl(rename_all = "SCREAMING_SNAKE_CASE")]
+enum Policy {
+    Retry,
+    GiveUp,
+}
+
+#[derive(DecodeKdl, EncodeKdl, Debug, Clone, Copy, PartialEq)]
+enum Priority {
+    #[kdl(name = "!")]
+    Urgent,
+    Low,
+}
+
+#[derive(DecodeKdl, EncodeKdl, Debug, Clone, PartialEq)]
+struct Schedule {
+    #[kdl(argument)]
+    cron: String,
+}
+
+#[derive(DecodeKdl, EncodeKdl, Debug, Clone, PartialEq)]
+enum Step {
+    Checkout,
+    RunScript {
+        #[kdl(argument)]
+        command: String,
+        #[kdl(property)]
+        working_dir: Option<String>,
+    },
+    Cached(Schedule),
+}
+
+#[test]
+fn enums() {
+    let input = r#"job "build" "!" on-failure="GIVE_UP" {
+    schedule "0 * * * *"
+    checkout
+    run-script "cargo build" working-dir="crates"
+    cached "daily"
+}"#;
+    let node: KdlNode = input.parse().unwrap();
+    let job = Job::decode_kdl(&node).unwrap();
+    assert_eq!(
+        job,
+        Job {
+            name: "build".into(),
+            on_failure: Policy::GiveUp,
+            priority: Some(Priority::Urgent),
+            schedule: Some(Schedule {
+                cron: "0 * * * *".into()
+            }),
+            steps: vec![
+                Step::Checkout,
+                Step::RunScript {
+                    command: "cargo build".into(),
+                    working_dir: Some("crates".into()),
+                },
+                Step::Cached(Schedule {
+                    cron: "daily".into()
+                }),
+            ],
+        }
+    );
+    assert_eq!(job.encode_kdl().to_string(), input);
+    assert_eq!(Policy::Retry.encode_kdl().to_string(), "RETRY");
+
+    let input = r#"job "x" "low" on-failure="RETRY" { deploy; }"#;
+    let node: KdlNode = input.parse().unwrap();
+    let err = Job::decode_kdl(&node).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unknown node `deploy`, expected one of `checkout`, `run-script`, `cached`."
+    );
+    #[cfg(feature = "span")]
+    assert!(matches!(err, KdlDecodeError::UnknownNode { span, .. }
+        if span.offset() == input.find("deploy").unwrap() && span.len() == 6));
+
+    let node: KdlNode = r#"job "x" on-failure="retry""#.parse().unwrap();
+    assert_eq!(
+        Job::decode_kdl(&node).unwrap_err().to_string(),
+        r#"Expected property `on-failure` of node `job` to be one of "RETRY", "GIVE_UP", but found `"retry"`."#
+    );
+
+    let node: KdlNode = "job \"x\" on-failure=\"RETRY\" { checkout 1; }"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        Job::decode_kdl(&node).unwrap_err().to_string(),
+        "Unexpected argument 0 on node `checkout`."
+    );
+}
+
+// Only ever written through its builder.
+#[allow(dead_code)]
+#[derive(kdl::KdlBuilder)]
+struct Service {
+    #[kdl(argument)]
+    name: String,
+    #[kdl(argument)]
+    image: Option<String>,
+    #[kdl(argument)]
+    tag: Option<String>,
+    #[kdl(property)]
+    replicas: u8,
+    #[kdl(child)]
+    license: Option<License>,
+    #[kdl(children)]
+    steps: Vec<Step>,
+}
+
+#[test]
+fn builder() {
+    let node = Service::builder()
+        .steps(vec![Step::Checkout])
+        .replicas(3)
+        .name("web".into())
+        .license(License { spdx: "MIT".into() })
+        .steps(Some(Step::RunScript {
+            command: "make".into(),
+            working_dir: None,
+        }))
+        .build();
+    assert_eq!(
+        node.to_string(),
+        r#"service "web" replicas=3 {
+    license "MIT"
+    checkout
+    run-script "make"
+}"#
+    );
+
+    let node = Service::builder().tag("latest".into()).build();
+    assert_eq!(node.to_string(), r#"service null null "latest""#);
+    assert_eq!(Service::builder().build().to_string(), "service");
+}
@@ -25,3 +25,17 @@ fn build_and_format() {
 "#
     );
 }
+
+#[test]
+fn format_semicolon_separated() {
+    let mut doc: KdlDocument = "a 1 {b;c;}".parse().unwrap();
+    doc.fmt();
+    assert_eq!(
+        doc.to_string(),
+        r#"a 1 {
+    b
+    c
+}
+"#
+    );
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "macros")]
+
+use kdl::{include_kdl, kdl, kdl_node, kdl_query, KdlDocument, KdlNode, KdlQuery, KdlValue};
+use miette::Result;
+
+#[test]
+fn matches_parsed_document() -> Result<()> {
+    let mut built = kdl! {
+        package name="kdl" version="5.0.0" {
+            (author)"Kat Marchán" email=null;
+            "build-deps" 0x10 0o17 0b101 -42 1.5 -2.5e3 true false;
+            - r"raw\string"
+        }
+        empty;;
+        other
+    };
+    let mut parsed: KdlDocument = r#"
+        package name="kdl" version="5.0.0" {
+            (author)"Kat Marchán" email=null
+            build-deps 0x10 0o17 0b101 -42 1.5 -2.5e3 true false
+            - r"raw\string"
+        }
+        empty
+        other
+    "#
+    .parse()?;
+    built.clear_fmt_recursive();
+    parsed.clear_fmt_recursive();
+    assert_eq!(built.to_string(), parsed.to_string());
+
+    let deps = &built.get("package").unwrap().children().unwrap().nodes()[1];
+    assert_eq!(deps[0], KdlValue::Base16(16));
+    assert_eq!(deps[1], KdlValue::Base8(15));
+    assert_eq!(deps[2], KdlValue::Base2(5));
+    assert_eq!(deps[3], KdlValue::Base10(-42));
+    Ok(())
+}
+
+#[test]
+fn interpolation() {
+    let name = "web";
+    let replicas = 3u8;
+    let node: KdlNode = kdl_node! {
+        (svc)service #name replicas=#replicas ratio=#(f64::from(replicas) / 2.0) {
+            port (u16)#(8000 + u16::from(replicas))
+        }
+    };
+    assert_eq!(
+        node.to_string(),
+        "(svc)service \"web\" replicas=3 ratio=1.5 {\n    port (u16)8003\n}"
+    );
+}
+
+#[test]
+fn keywords_as_names() {
+    let doc = kdl!(type r#type="x"; fn 1);
+    assert_eq!(doc.to_string(), "type type=\"x\"\nfn 1\n");
+    assert_eq!(kdl!().nodes().len(), 0);
+    assert_eq!(
+        kdl_node!(min - 170141183460469231731687303715884105728)[0],
+        KdlValue::Base10(i128::MIN)
+    );
+}
+
+#[test]
+fn queries() -> Result<()> {
+    let doc = kdl! {
+        package { name "kdl"; version "5.0.0" }
+        dependencies { miette "5.7.0"; nom "7.1.1" }
+    };
+    let query: KdlQuery = kdl_query!("dependencies > []");
+    assert_eq!(query, "dependencies > []".parse()?);
+    let names: Vec<_> = doc
+        .query_all(query)?
+        .map(|node| node.name().value())
+        .collect();
+    assert_eq!(names, vec!["miette", "nom"]);
+    let version = doc.query(kdl_query!(r#"package > [arg() = "5.0.0"]"#))?;
+    assert_eq!(version.map(|node| node.name().value()), Some("version"));
+    Ok(())
+}
+
+#[test]
+fn included() -> Result<()> {
+    let doc: KdlDocument = include_kdl!("tests/test_cases/input/all_node_fields.kdl");
+    let expected = std::fs::read_to_string("tests/test_cases/input/all_node_fields.kdl")
+        .expect("couldn't read test case");
+    assert_eq!(doc.to_string(), expected);
+    assert_eq!(doc, expected.parse()?);
+    Ok(())
+}
@@ -1,3 +1,6 @@
+// These tests deliberately pass `&String` to exercise that `IntoKdlQuery` impl.
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 use kdl::{KdlDocument, KdlQuery};
 use miette::Result;
 